@@ -1,7 +1,7 @@
 use InputFile::*;
 use iai_callgrind::{library_benchmark, library_benchmark_group, main};
 use std::{hint::black_box, ops::Range};
-use zeronbt::{FsmResult, NbtFsm};
+use zeronbt::{CompleteFsm, FsmResult, NbtFsm, workload::Workload};
 
 include!("common.rs");
 
@@ -19,6 +19,11 @@ pub fn chunk_io(input: InputFile, chunk_size: usize) -> ChunkedIoSource {
     }
 }
 
+/// A generated document of about 180KiB
+pub fn generated() -> InputFile {
+    Literal(Workload::new(1).to_bytes().leak())
+}
+
 impl ChunkedIoSource {
     #[inline]
     pub fn current_view(&self) -> &'static [u8] {
@@ -48,6 +53,9 @@ impl ChunkedIoSource {
 #[bench::chunk_small_io(chunk_io(Chunk, 16))]
 #[bench::chunk_large_io(chunk_io(Chunk, 1024))]
 #[bench::chunk_complete(chunk_io(Chunk, 1024 * 1024))]
+#[bench::generated_small_io(chunk_io(generated(), 16))]
+#[bench::generated_large_io(chunk_io(generated(), 1024))]
+#[bench::generated_complete(chunk_io(generated(), 1024 * 1024))]
 fn parse_zeronbt(io_source: ChunkedIoSource) -> u64 {
     black_box(parse_from_source(black_box(io_source)))
 }
@@ -76,9 +84,26 @@ fn parse_from_source(mut source: ChunkedIoSource) -> u64 {
     count
 }
 
+// The same inputs as the `_complete` cases above, parsed without any refill bookkeeping
+#[library_benchmark]
+#[bench::bigtest_complete(BigTest)]
+#[bench::chunk_complete(Chunk)]
+#[bench::generated_complete(generated())]
+fn parse_complete(input: InputFile) -> u64 {
+    let mut count = 0;
+    for fragment in CompleteFsm::new(black_box(input.data())) {
+        match fragment {
+            Err(error) => panic!("Failed to parse NBT: {error}"),
+            Ok(fragment) => sink(black_box(fragment)),
+        }
+        count += 1;
+    }
+    black_box(count)
+}
+
 library_benchmark_group!(
     name = parse_streaming;
     compare_by_id = true;
-    benchmarks = parse_zeronbt
+    benchmarks = parse_zeronbt, parse_complete
 );
 main!(library_benchmark_groups = parse_streaming);
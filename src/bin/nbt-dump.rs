@@ -0,0 +1,253 @@
+//! Dumps NBT files as SNBT or JSON
+//!
+//! ```text
+//! nbt-dump [--json] [--compact] <file>
+//! nbt-dump --explain <file>
+//! ```
+//!
+//! `--explain` traces the parser over a file instead, to find out where and why it fails to
+//! parse, followed by the number of each tag found.
+//!
+//! The compression is detected from the data. Roots without a name, as sent over the network, and
+//! little-endian Bedrock Edition files are detected by falling back to them when the data does
+//! not parse otherwise, skipping the 8 byte header of a Bedrock `level.dat`. Region files
+//! (`.mca`, `.mcr`) are dumped chunk by chunk.
+use std::{
+    error::Error,
+    fmt,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+    process::ExitCode,
+};
+
+use zeronbt::{
+    Bedrock, Dialect, FsmResult, NbtFragment, NbtFsm, compression::Decompress,
+    error::NbtParseError, explain::explain, io::NbtReader, json::JsonWriter, region::Region,
+    snbt::SnbtWriter,
+};
+
+const USAGE: &str = "usage: nbt-dump [--json] [--compact] <file>\n       nbt-dump --explain <file>";
+
+/// Adapts an [io::Write] to the [fmt::Write] the writers produce text through, keeping the last
+/// error around as [fmt::Error] carries none
+struct Output<W> {
+    out: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> fmt::Write for Output<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.out.write_all(s.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
+}
+
+enum Writer<W> {
+    Snbt(SnbtWriter<Output<W>>),
+    Json(JsonWriter<Output<W>>),
+}
+
+impl<W: Write> Writer<W> {
+    fn push(&mut self, fragment: NbtFragment<'_>) -> io::Result<bool> {
+        let result = match self {
+            Writer::Snbt(writer) => writer.push(fragment),
+            Writer::Json(writer) => writer.push(fragment),
+        };
+        result.map_err(|_| self.take_error())
+    }
+
+    fn output(&mut self) -> &mut Output<W> {
+        match self {
+            Writer::Snbt(writer) => writer.get_mut(),
+            Writer::Json(writer) => writer.get_mut(),
+        }
+    }
+
+    /// Writes text around the documents
+    fn write(&mut self, text: fmt::Arguments<'_>) -> io::Result<()> {
+        fmt::Write::write_fmt(self.output(), text).map_err(|_| self.take_error())
+    }
+
+    fn take_error(&mut self) -> io::Error {
+        self.output()
+            .error
+            .take()
+            .unwrap_or_else(|| io::Error::other("formatting failed"))
+    }
+}
+
+fn main() -> ExitCode {
+    let mut json = false;
+    let mut pretty = true;
+    let mut explain = false;
+    let mut path = None;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--compact" => pretty = false,
+            "--explain" => explain = true,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            _ if path.is_none() && !arg.starts_with("--") => path = Some(arg),
+            _ => {
+                eprintln!("{USAGE}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    let Some(path) = path else {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    };
+    let mut output = Output {
+        out: BufWriter::new(io::stdout().lock()),
+        error: None,
+    };
+    if explain {
+        let path = Path::new(&path);
+        return match explain_file(path, &mut output) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(err) => {
+                eprintln!("nbt-dump: {}: {err}", path.display());
+                ExitCode::FAILURE
+            }
+        };
+    }
+    let mut writer = match json {
+        true => Writer::Json(JsonWriter::new(output).pretty(pretty)),
+        false => Writer::Snbt(SnbtWriter::new(output).pretty(pretty)),
+    };
+    let path = Path::new(&path);
+    let region = matches!(
+        path.extension().and_then(|extension| extension.to_str()),
+        Some("mca" | "mcr")
+    );
+    let result = match region {
+        true => dump_region(path, json, &mut writer),
+        false => dump_file(path, &mut writer),
+    };
+    let result = result.and_then(|()| Ok(writer.output().out.flush()?));
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("nbt-dump: {}: {err}", path.display());
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Dumps every root tag in a file, one per line
+fn dump_file(path: &Path, writer: &mut Writer<impl Write>) -> Result<(), Box<dyn Error>> {
+    let mut data = Vec::new();
+    Decompress::new(BufReader::new(File::open(path)?))?.read_to_end(&mut data)?;
+    let print = |fragment: NbtFragment<'_>| {
+        if writer.push(fragment)? {
+            writer.write(format_args!("\n"))?;
+        }
+        Ok(())
+    };
+    // Checking the whole input first keeps a failed attempt from producing output
+    let Err(err) = parse(NbtFsm::new(), &data, |_| Ok(())) else {
+        return parse(NbtFsm::new(), &data, print);
+    };
+    if parse(NbtFsm::new_nameless(), &data, |_| Ok(())).is_ok() {
+        return parse(NbtFsm::new_nameless(), &data, print);
+    }
+    let bedrock = skip_bedrock_header(&data);
+    if parse(NbtFsm::with_dialect(Bedrock), bedrock, |_| Ok(())).is_ok() {
+        return parse(NbtFsm::with_dialect(Bedrock), bedrock, print);
+    }
+    Err(err)
+}
+
+/// Skips the header of a Bedrock `level.dat`, the version of the file followed by the length of
+/// the rest, both little-endian
+fn skip_bedrock_header(data: &[u8]) -> &[u8] {
+    match data.split_at_checked(8) {
+        Some((header, rest)) if header[4..] == (rest.len() as u32).to_le_bytes() => rest,
+        _ => data,
+    }
+}
+
+/// Traces the parser over a file, failing if it does not parse
+fn explain_file(path: &Path, output: &mut Output<impl Write>) -> Result<(), Box<dyn Error>> {
+    let mut data = Vec::new();
+    Decompress::new(BufReader::new(File::open(path)?))?.read_to_end(&mut data)?;
+    let trace = explain(NbtFsm::new(), &data, output);
+    let trace = trace.and_then(|trace| {
+        fmt::Write::write_fmt(output, format_args!("\n{}", trace.histogram))?;
+        Ok(trace)
+    });
+    let trace = trace.map_err(|_| {
+        output
+            .error
+            .take()
+            .unwrap_or_else(|| io::Error::other("formatting failed"))
+    })?;
+    output.out.flush()?;
+    match trace.error {
+        Some((offset, err)) => Err(format!("{err} (at byte {offset})").into()),
+        None => Ok(()),
+    }
+}
+
+/// Parses all of `data`, passing every fragment to `push`
+fn parse<D: Dialect>(
+    fsm: NbtFsm<'_, D>,
+    data: &[u8],
+    mut push: impl FnMut(NbtFragment<'_>) -> Result<(), Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    let mut fsm = fsm.with_data(data);
+    loop {
+        match fsm.next_fragment()? {
+            FsmResult::Found(fragment) => push(fragment)?,
+            FsmResult::Needs(_) if fsm.is_idle() && fsm.consumed() == data.len() => {
+                return Ok(());
+            }
+            FsmResult::Needs(_) => return Err(NbtParseError::UnexpectedEnd.into()),
+        }
+    }
+}
+
+/// Dumps the chunks of a region, streaming each one from its decompressor
+fn dump_region(
+    path: &Path,
+    json: bool,
+    writer: &mut Writer<impl Write>,
+) -> Result<(), Box<dyn Error>> {
+    let mut region = Region::open_file(path)?;
+    if json {
+        writer.write(format_args!("["))?;
+    }
+    for (index, chunk) in region.chunks().enumerate() {
+        let chunk = chunk?;
+        let (x, z) = (chunk.pos.x, chunk.pos.z);
+        match json {
+            true if index == 0 => writer.write(format_args!("{{\"x\":{x},\"z\":{z},\"data\":"))?,
+            true => writer.write(format_args!(",\n{{\"x\":{x},\"z\":{z},\"data\":"))?,
+            false => writer.write(format_args!("// chunk {x} {z}\n"))?,
+        }
+        let data = Decompress::with_compression(chunk.raw.compression, chunk.raw.data.as_slice())?;
+        let mut reader = NbtReader::new(data);
+        let mut done = false;
+        while !done && let Some(fragment) = reader.next_fragment()? {
+            done = writer.push(fragment)?;
+        }
+        if !done {
+            return Err(NbtParseError::UnexpectedEnd.into());
+        }
+        match json {
+            true => writer.write(format_args!("}}"))?,
+            false => writer.write(format_args!("\n"))?,
+        }
+    }
+    if json {
+        writer.write(format_args!("]\n"))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,146 @@
+//! Queries and compares NBT files
+//!
+//! ```text
+//! nbt-query get <file> <path>
+//! nbt-query diff <left> <right>
+//! ```
+//!
+//! `get` prints every value selected by an [NbtPath] as SNBT, one per line, and `diff` lists the
+//! structural differences between two files, exiting with status 1 if there are any. Files are
+//! loaded like `nbt-dump` does, region files (`.mca`, `.mcr`) are handled chunk by chunk.
+use std::{
+    error::Error,
+    fs::File,
+    io::{self, BufReader, Read, Write},
+    path::Path,
+    process::ExitCode,
+};
+
+use zeronbt::{
+    FsmResult, NbtFsm,
+    compression::Decompress,
+    diff::diff,
+    error::NbtParseError,
+    path::NbtPath,
+    region::{ChunkPos, Region},
+    snbt::SnbtWriter,
+    value::{NbtValue, NbtValueBuilder},
+};
+
+const USAGE: &str = "usage: nbt-query get <file> <path>\n       nbt-query diff <left> <right>";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = match args.as_slice() {
+        ["get", file, path] => get(Path::new(file), path),
+        ["diff", left, right] => compare(Path::new(left), Path::new(right)),
+        ["-h" | "--help"] => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("nbt-query: {err}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Prints the values selected by `path`, returning whether there were any
+fn get(file: &Path, path: &str) -> Result<bool, Box<dyn Error>> {
+    let path: NbtPath = path.parse()?;
+    let mut out = io::stdout().lock();
+    let mut found = false;
+    for (chunk, value) in load(file)? {
+        for selected in path.select(&value) {
+            found = true;
+            let snbt = to_snbt(&selected)?;
+            match chunk {
+                Some(ChunkPos { x, z }) => writeln!(out, "{x} {z}: {snbt}")?,
+                None => writeln!(out, "{snbt}")?,
+            }
+        }
+    }
+    Ok(found)
+}
+
+/// Prints the differences between two files, returning whether they are equal
+fn compare(left: &Path, right: &Path) -> Result<bool, Box<dyn Error>> {
+    let (left, right) = (load(left)?, load(right)?);
+    let mut out = io::stdout().lock();
+    let mut equal = true;
+    for index in 0..left.len().max(right.len()) {
+        match (left.get(index), right.get(index)) {
+            (Some((pos, left)), Some((_, right))) => {
+                for difference in diff(left, right) {
+                    equal = false;
+                    match pos {
+                        Some(ChunkPos { x, z }) => writeln!(out, "{x} {z}: {difference}")?,
+                        None => writeln!(out, "{difference}")?,
+                    }
+                }
+            }
+            _ => {
+                equal = false;
+                writeln!(out, "the files hold a different number of chunks")?;
+                break;
+            }
+        }
+    }
+    Ok(equal)
+}
+
+/// Writes a value as SNBT by encoding it and streaming the fragments through an [SnbtWriter]
+fn to_snbt(value: &NbtValue) -> Result<String, Box<dyn Error>> {
+    let data = value.to_bytes("")?;
+    let mut fsm = NbtFsm::new().with_data(&data);
+    let mut writer = SnbtWriter::new(String::new());
+    while let FsmResult::Found(fragment) = fsm.next_fragment()? {
+        writer.push(fragment)?;
+    }
+    Ok(writer.into_inner())
+}
+
+/// The root of a file, or every chunk of a region along with its position
+type Roots = Vec<(Option<ChunkPos>, NbtValue)>;
+
+fn load(path: &Path) -> Result<Roots, Box<dyn Error>> {
+    let region = matches!(
+        path.extension().and_then(|extension| extension.to_str()),
+        Some("mca" | "mcr")
+    );
+    if region {
+        let mut region = Region::open_file(path)?;
+        return region
+            .values()
+            .map(|chunk| Ok(chunk.map(|(pos, _, value)| (Some(pos), value))?))
+            .collect();
+    }
+    let mut data = Vec::new();
+    Decompress::new(BufReader::new(File::open(path)?))?.read_to_end(&mut data)?;
+    let value = read(NbtFsm::new(), &data).or_else(|_| read(NbtFsm::new_nameless(), &data))?;
+    Ok(vec![(None, value)])
+}
+
+fn read(fsm: NbtFsm<'_>, data: &[u8]) -> Result<NbtValue, NbtParseError> {
+    let mut fsm = fsm.with_data(data);
+    let mut builder = NbtValueBuilder::new();
+    loop {
+        match fsm.next_fragment()? {
+            FsmResult::Found(fragment) => {
+                if let Some((_, value)) = builder.push(fragment)? {
+                    return Ok(value);
+                }
+            }
+            FsmResult::Needs(_) => return Err(NbtParseError::UnexpectedEnd),
+        }
+    }
+}
@@ -0,0 +1,201 @@
+//! Converting between NBT and CBOR, for storage and RPC systems that speak CBOR
+//!
+//! Compounds become maps with text keys and lists become arrays. Integers are always encoded with
+//! the width of their type, so bytes, shorts, ints and longs can be told apart when reading them
+//! back with [CborReader]. Byte, int and long arrays become byte strings holding the big-endian
+//! elements, tagged as typed arrays (RFC 8746).
+//!
+//! ```
+//! # use zeronbt::{CompleteFsm, cbor::{CborReader, CborWriter}};
+//! let data = include_bytes!("../assets/bigtest.nbt");
+//! let mut writer = CborWriter::new();
+//! for fragment in CompleteFsm::new(data) {
+//!     writer.push(fragment.unwrap());
+//! }
+//! let cbor = writer.into_inner();
+//! let mut reader = CborReader::new(&cbor);
+//! let mut fragments = 0;
+//! while let Some(_) = reader.next_fragment().unwrap() {
+//!     fragments += 1;
+//! }
+//! // Everything but the root name survives
+//! assert_eq!(fragments + 1, CompleteFsm::new(data).count());
+//! ```
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{
+    NbtFragment,
+    text::{Emit, Number, Seq, Structure},
+};
+
+mod read;
+pub use read::CborReader;
+
+const MAP: u8 = 5;
+const ARRAY: u8 = 4;
+const BYTES: u8 = 2;
+const TEXT: u8 = 3;
+const TAG: u8 = 6;
+const BREAK: u8 = 0xFF;
+
+/// The typed array tags of RFC 8746 for big-endian signed integers
+const SINT8: u64 = 72;
+const SINT32_BE: u64 = 74;
+const SINT64_BE: u64 = 75;
+
+/// Writes the fragments of documents pushed to it as CBOR
+///
+/// Root names are skipped. Invalid strings are written lossily. As the number of entries of a
+/// compound is not known until it ends, compounds and lists are written with indefinite lengths.
+#[derive(Debug, Clone, Default)]
+pub struct CborWriter {
+    structure: Structure,
+    format: Format,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Format {
+    out: Vec<u8>,
+    /// Whether a byte, int or long array is being written, whose elements are collected in `array`
+    in_array: bool,
+    array: Vec<u8>,
+}
+
+impl CborWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes the next fragment, returning true once the root tag is complete
+    ///
+    /// Fragments of the next document may be pushed after that, they are written right after the
+    /// previous one.
+    pub fn push(&mut self, fragment: NbtFragment<'_>) -> bool {
+        // Writing to a Vec can not fail
+        matches!(self.structure.push(fragment, &mut self.format), Ok(true))
+    }
+
+    pub fn get_ref(&self) -> &Vec<u8> {
+        &self.format.out
+    }
+
+    /// The output written so far, which may be drained to pass it on while writing
+    pub fn get_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.format.out
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.format.out
+    }
+}
+
+/// Writes the initial byte of an item along with its argument, in the shortest form
+fn head(out: &mut Vec<u8>, major: u8, arg: u64) {
+    let major = major << 5;
+    match arg {
+        0..24 => out.push(major | arg as u8),
+        24..=0xFF => out.extend_from_slice(&[major | 24, arg as u8]),
+        0x100..=0xFFFF => {
+            out.push(major | 25);
+            out.extend_from_slice(&(arg as u16).to_be_bytes());
+        }
+        0x1_0000..=0xFFFF_FFFF => {
+            out.push(major | 26);
+            out.extend_from_slice(&(arg as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(major | 27);
+            out.extend_from_slice(&arg.to_be_bytes());
+        }
+    }
+}
+
+/// Writes an integer with an argument of `width` bytes, even where a shorter one would do
+fn int(out: &mut Vec<u8>, value: i64, width: usize) {
+    let (major, arg) = match value {
+        0.. => (0, value as u64),
+        _ => (1 << 5, !value as u64),
+    };
+    let info = match width {
+        1 => 24,
+        2 => 25,
+        4 => 26,
+        _ => 27,
+    };
+    out.push(major | info);
+    out.extend_from_slice(&arg.to_be_bytes()[8 - width..]);
+}
+
+impl Emit for Format {
+    fn begin_compound(&mut self) -> fmt::Result {
+        self.out.push(MAP << 5 | 31);
+        Ok(())
+    }
+
+    fn key(&mut self, key: &str) -> fmt::Result {
+        self.string(key)
+    }
+
+    fn end_compound(&mut self) -> fmt::Result {
+        self.out.push(BREAK);
+        Ok(())
+    }
+
+    fn begin_seq(&mut self, seq: Seq) -> fmt::Result {
+        let tag = match seq {
+            Seq::List(_) => {
+                self.out.push(ARRAY << 5 | 31);
+                return Ok(());
+            }
+            Seq::ByteArray => SINT8,
+            Seq::IntArray => SINT32_BE,
+            Seq::LongArray => SINT64_BE,
+        };
+        head(&mut self.out, TAG, tag);
+        self.in_array = true;
+        self.array.clear();
+        Ok(())
+    }
+
+    fn end_seq(&mut self) -> fmt::Result {
+        // Arrays only hold numbers, so the sequence that ends is the array if one is open
+        match self.in_array {
+            true => {
+                head(&mut self.out, BYTES, self.array.len() as u64);
+                self.out.extend_from_slice(&self.array);
+                self.in_array = false;
+            }
+            false => self.out.push(BREAK),
+        }
+        Ok(())
+    }
+
+    fn number(&mut self, number: Number) -> fmt::Result {
+        if self.in_array {
+            number.write_be(&mut self.array);
+            return Ok(());
+        }
+        match number {
+            Number::Byte(value) => int(&mut self.out, value.into(), 1),
+            Number::Short(value) => int(&mut self.out, value.into(), 2),
+            Number::Int(value) => int(&mut self.out, value.into(), 4),
+            Number::Long(value) => int(&mut self.out, value, 8),
+            Number::Float(value) => {
+                self.out.push(0xFA);
+                self.out.extend_from_slice(&value.to_be_bytes());
+            }
+            Number::Double(value) => {
+                self.out.push(0xFB);
+                self.out.extend_from_slice(&value.to_be_bytes());
+            }
+        }
+        Ok(())
+    }
+
+    fn string(&mut self, string: &str) -> fmt::Result {
+        head(&mut self.out, TEXT, string.len() as u64);
+        self.out.extend_from_slice(string.as_bytes());
+        Ok(())
+    }
+}
@@ -0,0 +1,716 @@
+use alloc::{vec, vec::Vec};
+
+use super::{ARRAY, BREAK, BYTES, MAP, SINT32_BE, SINT64_BE, TAG, TEXT};
+use crate::{
+    NbtFragment, NbtTag,
+    error::CborError,
+    mutf8,
+    text::{Number, list_frame},
+};
+
+/// Parses CBOR into fragments, the inverse of [CborWriter](super::CborWriter)
+///
+/// Maps become compounds and must have text keys, arrays become lists and byte strings become
+/// byte arrays, unless tagged as typed arrays of 32 or 64-bit big-endian signed integers. Other
+/// tags are ignored. Integers are read as the type matching the width of their encoding, or the
+/// next wider one they fit in, with values stored in the initial byte read as ints. Half and single
+/// precision floats become floats. Booleans become the bytes 1 and 0, and `null`, `undefined` and
+/// other simple values can not be read.
+///
+/// The elements of a list must all be integers, all be floats or all have the same type
+/// otherwise. Numeric lists are read as the widest type among their elements.
+///
+/// Like [SnbtReader](crate::snbt::SnbtReader), the root is given an empty name, frames are never
+/// split and several root values may follow each other.
+#[derive(Debug, Clone)]
+pub struct CborReader<'d> {
+    data: &'d [u8],
+    pos: usize,
+    stack: Vec<Container>,
+    state: State,
+    /// The name, string or numbers that are produced as the next frame
+    buf: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Container {
+    /// The entries still to come, or None for maps of indefinite length
+    Map(Option<u64>),
+    Array {
+        tag: NbtTag,
+        remaining: u64,
+        indefinite: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    /// Between two root values
+    Root,
+    /// The name in the buffer is produced next, before the value or the entries of a compound
+    Name {
+        compound: bool,
+    },
+    NameEnd {
+        compound: bool,
+    },
+    Value,
+    /// Inside a map, expecting the next key or the end of the map
+    Entry,
+    /// Inside a list of containers or strings, expecting the next element or the end of the list
+    Element,
+    StringEnd,
+    ByteArrayEnd,
+    /// The elements of a numeric list or array in the buffer are produced as one frame
+    Frame(NbtTag),
+}
+
+/// The initial byte of an item and its argument
+#[derive(Debug, Clone, Copy)]
+struct Head {
+    major: u8,
+    info: u8,
+    arg: u64,
+    /// The position after the head
+    end: usize,
+}
+
+impl Head {
+    fn indefinite(&self) -> bool {
+        self.info == 31
+    }
+}
+
+impl<'d> CborReader<'d> {
+    pub fn new(data: &'d [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            stack: Vec::new(),
+            state: State::Root,
+            buf: Vec::new(),
+        }
+    }
+
+    /// The number of bytes parsed so far
+    pub fn consumed(&self) -> usize {
+        self.pos
+    }
+
+    /// Whether the reader is between two root values
+    pub fn is_idle(&self) -> bool {
+        self.state == State::Root
+    }
+
+    /// Reads the next fragment, returning None once the input ends between two root values
+    pub fn next_fragment(&mut self) -> Result<Option<NbtFragment<'_>>, CborError> {
+        loop {
+            match self.state {
+                State::Root => {
+                    if self.pos == self.data.len() {
+                        return Ok(None);
+                    }
+                    self.buf.clear();
+                    let head = self.untagged(self.pos)?;
+                    if head.major == MAP {
+                        return Ok(Some(self.open_map(head, true)));
+                    }
+                    self.state = State::Name { compound: false };
+                }
+                State::Name { compound } => {
+                    self.state = State::NameEnd { compound };
+                    if !self.buf.is_empty() {
+                        return Ok(Some(NbtFragment::NameFrame(&self.buf)));
+                    }
+                }
+                State::NameEnd { compound } => {
+                    self.state = match compound {
+                        true => State::Entry,
+                        false => State::Value,
+                    };
+                    return Ok(Some(NbtFragment::NameFrame(&[])));
+                }
+                State::Value => return self.value().map(Some),
+                State::Entry => {
+                    let Some(Container::Map(remaining)) = self.stack.last_mut() else {
+                        unreachable!("entries are only read inside maps")
+                    };
+                    let end = match remaining {
+                        Some(0) => true,
+                        Some(remaining) => {
+                            *remaining -= 1;
+                            false
+                        }
+                        None => self.data.get(self.pos) == Some(&BREAK),
+                    };
+                    if end {
+                        if remaining.is_none() {
+                            self.pos += 1;
+                        }
+                        self.stack.pop();
+                        self.finish_value();
+                        return Ok(Some(NbtFragment::End));
+                    }
+                    let key = self.pos;
+                    let head = self.untagged(key)?;
+                    if head.major != TEXT {
+                        return Err(CborError::InvalidKey(key));
+                    }
+                    self.pos = self.text(head)?;
+                    let head = self.untagged(self.pos)?;
+                    if head.major == MAP {
+                        return Ok(Some(self.open_map(head, true)));
+                    }
+                    self.state = State::Name { compound: false };
+                }
+                State::Element => {
+                    let Some(Container::Array {
+                        tag,
+                        remaining,
+                        indefinite,
+                    }) = self.stack.last_mut()
+                    else {
+                        unreachable!("elements are only read inside arrays")
+                    };
+                    let tag = *tag;
+                    if *remaining == 0 {
+                        if *indefinite {
+                            self.pos += 1;
+                        }
+                        self.stack.pop();
+                        self.finish_value();
+                        continue;
+                    }
+                    *remaining -= 1;
+                    let found = self.peek_tag(self.pos)?;
+                    if found != tag {
+                        return Err(CborError::MixedList {
+                            pos: self.pos,
+                            expected: tag,
+                            found,
+                        });
+                    }
+                    self.state = State::Value;
+                }
+                State::StringEnd => {
+                    self.finish_value();
+                    return Ok(Some(NbtFragment::StringFrame(&[])));
+                }
+                State::ByteArrayEnd => {
+                    self.finish_value();
+                    return Ok(Some(NbtFragment::ByteArrayFrame(&[])));
+                }
+                State::Frame(tag) => {
+                    self.finish_value();
+                    return Ok(Some(list_frame(tag, &self.buf)));
+                }
+            }
+        }
+    }
+
+    /// Reads a value that is not preceded by a name, or whose name was produced already
+    fn value(&mut self) -> Result<NbtFragment<'_>, CborError> {
+        let typed = self.skip_tags()?;
+        let start = self.pos;
+        let head = self.head(start)?;
+        match head.major {
+            MAP => Ok(self.open_map(head, false)),
+            ARRAY => self.list(head),
+            BYTES => {
+                self.pos = self.bytes(head)?;
+                let tag = match typed {
+                    Some(SINT32_BE) => NbtTag::IntArray,
+                    Some(SINT64_BE) => NbtTag::LongArray,
+                    _ => NbtTag::ByteArray,
+                };
+                self.array(tag, start)
+            }
+            TEXT => {
+                self.pos = self.text(head)?;
+                if self.buf.is_empty() {
+                    self.finish_value();
+                    return Ok(NbtFragment::StringFrame(&[]));
+                }
+                self.state = State::StringEnd;
+                Ok(NbtFragment::StringFrame(&self.buf))
+            }
+            _ => {
+                let number = self.number(head, start)?;
+                self.pos = head.end;
+                self.finish_value();
+                Ok(number.into_fragment())
+            }
+        }
+    }
+
+    fn open_map(&mut self, head: Head, named: bool) -> NbtFragment<'static> {
+        self.pos = head.end;
+        let len = (!head.indefinite()).then_some(head.arg);
+        self.stack.push(Container::Map(len));
+        self.state = match named {
+            true => State::Name { compound: true },
+            false => State::Entry,
+        };
+        NbtFragment::CompoundTag
+    }
+
+    /// Produces the byte string in the buffer as an array
+    fn array(&mut self, tag: NbtTag, pos: usize) -> Result<NbtFragment<'_>, CborError> {
+        let width = match tag {
+            NbtTag::IntArray => 4,
+            NbtTag::LongArray => 8,
+            _ if self.buf.is_empty() => {
+                self.finish_value();
+                return Ok(NbtFragment::ByteArrayFrame(&[]));
+            }
+            _ => {
+                self.state = State::ByteArrayEnd;
+                return Ok(NbtFragment::ByteArrayFrame(&self.buf));
+            }
+        };
+        if !self.buf.len().is_multiple_of(width) {
+            return Err(CborError::InvalidArray(pos));
+        }
+        let len = self.buf.len() / width;
+        match len {
+            0 => self.finish_value(),
+            _ => self.state = State::Frame(Number::element_tag(tag)),
+        }
+        Ok(match tag {
+            NbtTag::IntArray => NbtFragment::IntArrayTag(len),
+            _ => NbtFragment::LongArrayTag(len),
+        })
+    }
+
+    /// Reads an array, producing its elements right away if they are numbers
+    fn list(&mut self, head: Head) -> Result<NbtFragment<'_>, CborError> {
+        self.pos = head.end;
+        let len = match head.indefinite() {
+            true => self.count_items()?,
+            false => head.arg,
+        };
+        if len == 0 {
+            self.pos += usize::from(head.indefinite());
+            self.finish_value();
+            return Ok(NbtFragment::ListTag(NbtTag::End, 0));
+        }
+        let tag = self.peek_tag(self.pos)?;
+        let len = usize::try_from(len).map_err(|_| CborError::InvalidItem(self.pos))?;
+        if tag.is_numeric() {
+            let tag = self.numbers(len, tag)?;
+            self.pos += usize::from(head.indefinite());
+            self.state = State::Frame(tag);
+            return Ok(NbtFragment::ListTag(tag, len));
+        }
+        self.stack.push(Container::Array {
+            tag,
+            remaining: len as u64,
+            indefinite: head.indefinite(),
+        });
+        self.state = State::Element;
+        Ok(NbtFragment::ListTag(tag, len))
+    }
+
+    /// Reads `len` numbers into the buffer as the widest of their types, returning that type
+    fn numbers(&mut self, len: usize, first: NbtTag) -> Result<NbtTag, CborError> {
+        let floats = matches!(first, NbtTag::Float | NbtTag::Double);
+        let mut widest = first;
+        let mut pos = self.pos;
+        for _ in 0..len {
+            let found = self.peek_tag(pos)?;
+            if !found.is_numeric() || floats != matches!(found, NbtTag::Float | NbtTag::Double) {
+                return Err(CborError::MixedList {
+                    pos,
+                    expected: first,
+                    found,
+                });
+            }
+            widest = widest.max(found);
+            pos = skip_item(self.data, pos)?;
+        }
+        self.buf.clear();
+        for _ in 0..len {
+            let head = self.untagged(self.pos)?;
+            let number = self.number(head, self.pos)?;
+            // Every element fits the widest type
+            let number = number.widen(widest).unwrap_or(number);
+            number.write_be(&mut self.buf);
+            self.pos = head.end;
+        }
+        Ok(widest)
+    }
+
+    /// Called once a value has been read completely, returns to the enclosing container
+    fn finish_value(&mut self) {
+        self.state = match self.stack.last() {
+            Some(Container::Map(_)) => State::Entry,
+            Some(Container::Array { .. }) => State::Element,
+            None => State::Root,
+        };
+    }
+
+    /// Skips the semantic tags in front of the next item, returning the innermost one
+    fn skip_tags(&mut self) -> Result<Option<u64>, CborError> {
+        let mut tag = None;
+        loop {
+            let head = self.head(self.pos)?;
+            if head.major != TAG {
+                return Ok(tag);
+            }
+            tag = Some(head.arg);
+            self.pos = head.end;
+        }
+    }
+
+    fn head(&self, pos: usize) -> Result<Head, CborError> {
+        read_head(self.data, pos)
+    }
+
+    /// The head of the item at `pos`, after any semantic tags in front of it
+    fn untagged(&self, mut pos: usize) -> Result<Head, CborError> {
+        loop {
+            let head = self.head(pos)?;
+            if head.major != TAG {
+                return Ok(head);
+            }
+            pos = head.end;
+        }
+    }
+
+    /// Reads a text string into the buffer as Modified UTF-8, returning the position after it
+    fn text(&mut self, head: Head) -> Result<usize, CborError> {
+        let end = self.bytes(head)?;
+        let text =
+            core::str::from_utf8(&self.buf).map_err(|_| CborError::InvalidString(self.pos))?;
+        if let alloc::borrow::Cow::Owned(encoded) = mutf8::encode(text) {
+            self.buf = encoded;
+        }
+        Ok(end)
+    }
+
+    /// Reads a byte or text string into the buffer, joining the chunks of indefinite length ones,
+    /// and returns the position after it
+    fn bytes(&mut self, head: Head) -> Result<usize, CborError> {
+        self.buf.clear();
+        if !head.indefinite() {
+            let content = self.content(head)?;
+            self.buf.extend_from_slice(content);
+            return Ok(head.end + content.len());
+        }
+        let mut pos = head.end;
+        loop {
+            if self.data.get(pos) == Some(&BREAK) {
+                return Ok(pos + 1);
+            }
+            let chunk = self.head(pos)?;
+            if chunk.major != head.major || chunk.indefinite() {
+                return Err(CborError::InvalidItem(pos));
+            }
+            let content = self.content(chunk)?;
+            self.buf.extend_from_slice(content);
+            pos = chunk.end + content.len();
+        }
+    }
+
+    /// The content of a string of definite length
+    fn content(&self, head: Head) -> Result<&'d [u8], CborError> {
+        usize::try_from(head.arg)
+            .ok()
+            .and_then(|len| self.data.get(head.end..head.end.checked_add(len)?))
+            .ok_or(CborError::UnexpectedEnd)
+    }
+
+    /// Counts the items of an indefinite length array, which start at the current position
+    fn count_items(&self) -> Result<u64, CborError> {
+        let (mut pos, mut count) = (self.pos, 0);
+        while self.data.get(pos) != Some(&BREAK) {
+            pos = skip_item(self.data, pos)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// The tag of the item at `pos`
+    fn peek_tag(&self, mut pos: usize) -> Result<NbtTag, CborError> {
+        let mut typed = None;
+        let mut head = self.head(pos)?;
+        while head.major == TAG {
+            typed = Some(head.arg);
+            pos = head.end;
+            head = self.head(pos)?;
+        }
+        Ok(match head.major {
+            MAP => NbtTag::Compound,
+            ARRAY => NbtTag::List,
+            BYTES => match typed {
+                Some(SINT32_BE) => NbtTag::IntArray,
+                Some(SINT64_BE) => NbtTag::LongArray,
+                _ => NbtTag::ByteArray,
+            },
+            TEXT => NbtTag::String,
+            _ => self.number(head, pos)?.tag(),
+        })
+    }
+
+    /// Reads an integer, float or boolean
+    fn number(&self, head: Head, pos: usize) -> Result<Number, CborError> {
+        let invalid = CborError::InvalidItem(pos);
+        let value = match head.major {
+            0 => i128::from(head.arg),
+            1 => -1 - i128::from(head.arg),
+            7 => {
+                return match head.info {
+                    20 | 21 => Ok(Number::Byte(i8::from(head.info == 21))),
+                    25 => Ok(Number::Float(half(head.arg as u16))),
+                    26 => Ok(Number::Float(f32::from_bits(head.arg as u32))),
+                    27 => Ok(Number::Double(f64::from_bits(head.arg))),
+                    _ => Err(invalid),
+                };
+            }
+            _ => return Err(invalid),
+        };
+        let number = match head.info {
+            24 => i8::try_from(value).map(Number::Byte).ok(),
+            25 => i16::try_from(value).map(Number::Short).ok(),
+            27 => i64::try_from(value).map(Number::Long).ok(),
+            _ => i32::try_from(value).map(Number::Int).ok(),
+        };
+        // Values that do not fit the type of their width are read as the next wider one
+        number
+            .or_else(|| i16::try_from(value).map(Number::Short).ok())
+            .or_else(|| i32::try_from(value).map(Number::Int).ok())
+            .or_else(|| i64::try_from(value).map(Number::Long).ok())
+            .ok_or(invalid)
+    }
+}
+
+impl Number {
+    /// The tag of the elements of a numeric array
+    fn element_tag(array: NbtTag) -> NbtTag {
+        match array {
+            NbtTag::IntArray => NbtTag::Int,
+            NbtTag::LongArray => NbtTag::Long,
+            _ => NbtTag::Byte,
+        }
+    }
+
+    /// Converts an integer to a wider integer type, or a float to a double
+    fn widen(self, tag: NbtTag) -> Option<Number> {
+        let value = match self {
+            Number::Byte(value) => i64::from(value),
+            Number::Short(value) => i64::from(value),
+            Number::Int(value) => i64::from(value),
+            Number::Long(value) => value,
+            Number::Float(value) if tag == NbtTag::Double => {
+                return Some(Number::Double(value.into()));
+            }
+            Number::Float(_) | Number::Double(_) => return None,
+        };
+        match tag {
+            NbtTag::Short => value.try_into().ok().map(Number::Short),
+            NbtTag::Int => value.try_into().ok().map(Number::Int),
+            NbtTag::Long => Some(Number::Long(value)),
+            _ => None,
+        }
+    }
+}
+
+fn read_head(data: &[u8], pos: usize) -> Result<Head, CborError> {
+    let &initial = data.get(pos).ok_or(CborError::UnexpectedEnd)?;
+    let (major, info) = (initial >> 5, initial & 0x1F);
+    let width = match info {
+        0..24 => 0,
+        24 => 1,
+        25 => 2,
+        26 => 4,
+        27 => 8,
+        31 if matches!(major, BYTES | TEXT | ARRAY | MAP) => 0,
+        _ => return Err(CborError::InvalidItem(pos)),
+    };
+    let start = pos + 1;
+    let bytes = data
+        .get(start..start + width)
+        .ok_or(CborError::UnexpectedEnd)?;
+    let arg = match width {
+        0 => u64::from(info),
+        _ => bytes
+            .iter()
+            .fold(0, |arg, &byte| arg << 8 | u64::from(byte)),
+    };
+    Ok(Head {
+        major,
+        info,
+        arg,
+        end: start + width,
+    })
+}
+
+/// Returns the position after the item at `pos`, including everything nested in it
+fn skip_item(data: &[u8], mut pos: usize) -> Result<usize, CborError> {
+    // The items still to skip at each open level, None for levels of indefinite length
+    let mut levels = vec![Some(1u64)];
+    while let Some(level) = levels.last_mut() {
+        match level {
+            Some(0) => {
+                levels.pop();
+                continue;
+            }
+            Some(remaining) => *remaining -= 1,
+            None if data.get(pos) == Some(&BREAK) => {
+                pos += 1;
+                levels.pop();
+                continue;
+            }
+            None => {}
+        }
+        let head = read_head(data, pos)?;
+        pos = head.end;
+        match head.major {
+            _ if head.indefinite() => levels.push(None),
+            BYTES | TEXT => {
+                pos = usize::try_from(head.arg)
+                    .ok()
+                    .and_then(|len| pos.checked_add(len))
+                    .filter(|&end| end <= data.len())
+                    .ok_or(CborError::UnexpectedEnd)?;
+            }
+            ARRAY => levels.push(Some(head.arg)),
+            MAP => levels.push(Some(head.arg.saturating_mul(2))),
+            TAG => levels.push(Some(1)),
+            _ => {}
+        }
+    }
+    Ok(pos)
+}
+
+/// Decodes a half precision float
+fn half(bits: u16) -> f32 {
+    let sign = u32::from(bits & 0x8000) << 16;
+    let exponent = u32::from(bits >> 10 & 0x1F);
+    let mantissa = u32::from(bits & 0x3FF);
+    let magnitude = match exponent {
+        // Subnormal halves are normal floats, the mantissa is scaled by 2^-24
+        0 => mantissa as f32 * f32::from_bits(0x3380_0000),
+        0x1F => f32::from_bits(0x7F80_0000 | mantissa << 13),
+        _ => f32::from_bits((exponent + 127 - 15) << 23 | mantissa << 13),
+    };
+    f32::from_bits(sign | magnitude.to_bits())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        CompleteFsm,
+        cbor::CborWriter,
+        value::{NbtCompound, NbtList, NbtValue, NbtValueBuilder},
+    };
+    use alloc::vec;
+
+    fn parse(cbor: &[u8]) -> Result<NbtValue, CborError> {
+        let mut reader = CborReader::new(cbor);
+        let mut builder = NbtValueBuilder::new();
+        loop {
+            let fragment = reader.next_fragment()?.ok_or(CborError::UnexpectedEnd)?;
+            if let Some((_, value)) = builder.push(fragment).unwrap() {
+                assert!(reader.is_idle());
+                return Ok(value);
+            }
+        }
+    }
+
+    #[test]
+    fn matches_binary() {
+        let inputs: [&[u8]; 2] = [
+            include_bytes!("../../assets/bigtest.nbt"),
+            include_bytes!("../../assets/chunk_0-0.nbt"),
+        ];
+        for data in inputs {
+            let mut writer = CborWriter::new();
+            let mut binary: Vec<_> = CompleteFsm::new(data).map(Result::unwrap).collect();
+            for fragment in &binary {
+                writer.push(fragment.clone());
+            }
+            // The root name is lost in CBOR
+            if binary[1] != NbtFragment::NameFrame(&[]) {
+                binary.remove(1);
+            }
+
+            let cbor = writer.into_inner();
+            let mut reader = CborReader::new(&cbor);
+            let mut read = Vec::new();
+            while let Some(fragment) = reader.next_fragment().unwrap() {
+                read.push(fragment.into_owned());
+            }
+            let binary: Vec<_> = binary.into_iter().map(NbtFragment::into_owned).collect();
+            assert_eq!(read, binary);
+            assert_eq!(reader.consumed(), cbor.len());
+        }
+    }
+
+    #[test]
+    fn root_end() {
+        // An empty compound followed by the End tag of an empty document
+        let data = [0x0A, 0, 0, 0, 0];
+        let mut writer = CborWriter::new();
+        for fragment in CompleteFsm::new(&data) {
+            writer.push(fragment.unwrap());
+        }
+        let cbor = writer.into_inner();
+        assert_eq!(cbor, [0xBF, 0xFF]);
+        assert_eq!(
+            parse(&cbor).unwrap(),
+            NbtValue::Compound(NbtCompound::new())
+        );
+    }
+
+    #[test]
+    fn foreign() {
+        // {"a": 1, "b": [_ 1.5 (half), 2.0 (single)], "c": (_ "x", "y"), "d": true, "e": 300}
+        let cbor = [
+            0xBF, 0x61, b'a', 0x01, 0x61, b'b', 0x9F, 0xF9, 0x3E, 0x00, 0xFA, 0x40, 0x00, 0x00,
+            0x00, 0xFF, 0x61, b'c', 0x7F, 0x61, b'x', 0x61, b'y', 0xFF, 0x61, b'd', 0xF5, 0x61,
+            b'e', 0x19, 0x01, 0x2C, 0xFF,
+        ];
+        let list = |values: Vec<NbtValue>| NbtValue::List(NbtList::try_from(values).unwrap());
+        let compound: NbtCompound = [
+            ("a", NbtValue::Int(1)),
+            ("b", list(vec![NbtValue::Float(1.5), NbtValue::Float(2.0)])),
+            ("c", NbtValue::String("xy".into())),
+            ("d", NbtValue::Byte(1)),
+            ("e", NbtValue::Short(300)),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(parse(&cbor), Ok(NbtValue::Compound(compound)));
+
+        // [-1 (byte), 70000 (int)] widens to ints, and 200 in one byte to a short
+        let ints = [0x82, 0x38, 0x00, 0x1A, 0x00, 0x01, 0x11, 0x70];
+        assert_eq!(
+            parse(&ints),
+            Ok(list(vec![NbtValue::Int(-1), NbtValue::Int(70000)]))
+        );
+        assert_eq!(parse(&[0x18, 0xC8]), Ok(NbtValue::Short(200)));
+        assert_eq!(half(0x0001), f32::from_bits(0x3380_0000));
+        assert_eq!(half(0xFC00), f32::NEG_INFINITY);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse(&[0xA1, 0x01, 0x01]), Err(CborError::InvalidKey(1)));
+        assert_eq!(parse(&[0xF6]), Err(CborError::InvalidItem(0)));
+        assert_eq!(parse(&[0x62, b'a']), Err(CborError::UnexpectedEnd));
+        assert_eq!(
+            parse(&[0xD8, 0x4A, 0x43, 0, 0, 0]),
+            Err(CborError::InvalidArray(2))
+        );
+        assert_eq!(
+            parse(&[0x82, 0x01, 0xF9, 0x3C, 0x00]),
+            Err(CborError::MixedList {
+                pos: 2,
+                expected: NbtTag::Int,
+                found: NbtTag::Float
+            })
+        );
+    }
+}
@@ -0,0 +1,213 @@
+//! Checksums of encoded NBT, computed while it is read or written
+//!
+//! [Checksummed] passes bytes through to or from another source or sink, feeding them to a
+//! [Checksum] on the way. Wrapping a file checksums the compressed payload, wrapping the decoder
+//! or encoder checksums the plain NBT, and both can be done at once without a second pass.
+//!
+//! ```
+//! # use zeronbt::{checksum::{Checksummed, Crc32}, io::Driver};
+//! let data = include_bytes!("../assets/bigtest.nbt");
+//! let mut driver = Driver::new(Checksummed::new(&data[..], Crc32::new()));
+//! while driver.next_fragment().unwrap().is_some() {}
+//! assert_eq!(driver.get_ref().checksum().value(), Crc32::checksum(data));
+//! ```
+#[cfg(feature = "xxhash")]
+use core::fmt;
+
+use crate::io::Refill;
+
+/// A checksum or hash that bytes are fed to incrementally
+pub trait Checksum {
+    fn update(&mut self, bytes: &[u8]);
+    /// The checksum of every byte fed so far, widened to 64 bits
+    fn finish(&self) -> u64;
+}
+
+/// The CRC-32 used by gzip, zip and PNG
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Crc32 {
+    state: u32,
+}
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xEDB8_8320,
+                _ => crc >> 1,
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+impl Crc32 {
+    pub const fn new() -> Self {
+        Self { state: 0 }
+    }
+
+    /// The checksum of `bytes`
+    pub fn checksum(bytes: &[u8]) -> u32 {
+        let mut crc = Self::new();
+        crc.update(bytes);
+        crc.value()
+    }
+
+    pub const fn value(&self) -> u32 {
+        self.state
+    }
+}
+
+impl Checksum for Crc32 {
+    fn update(&mut self, bytes: &[u8]) {
+        let mut crc = !self.state;
+        for &byte in bytes {
+            crc = CRC32_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+        }
+        self.state = !crc;
+    }
+
+    fn finish(&self) -> u64 {
+        self.value().into()
+    }
+}
+
+/// The 32-bit xxHash, as used by LZ4
+#[cfg(feature = "xxhash")]
+#[derive(Clone)]
+pub struct XxHash32(xxhash_rust::xxh32::Xxh32);
+
+#[cfg(feature = "xxhash")]
+impl XxHash32 {
+    pub const fn new(seed: u32) -> Self {
+        Self(xxhash_rust::xxh32::Xxh32::new(seed))
+    }
+
+    /// The hash of `bytes`
+    pub fn checksum(bytes: &[u8], seed: u32) -> u32 {
+        xxhash_rust::xxh32::xxh32(bytes, seed)
+    }
+
+    pub fn value(&self) -> u32 {
+        self.0.digest()
+    }
+}
+
+#[cfg(feature = "xxhash")]
+impl Default for XxHash32 {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+#[cfg(feature = "xxhash")]
+impl fmt::Debug for XxHash32 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("XxHash32").field(&self.value()).finish()
+    }
+}
+
+#[cfg(feature = "xxhash")]
+impl Checksum for XxHash32 {
+    fn update(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        self.value().into()
+    }
+}
+
+/// A source or sink that checksums every byte passing through it
+///
+/// Sources are std [Read](std::io::Read) or [Refill] implementations, sinks are std
+/// [Write](std::io::Write) implementations. Sources may be read ahead of the end of a document.
+#[derive(Debug, Clone, Default)]
+pub struct Checksummed<T, C> {
+    inner: T,
+    checksum: C,
+}
+
+impl<T, C: Checksum> Checksummed<T, C> {
+    pub fn new(inner: T, checksum: C) -> Self {
+        Self { inner, checksum }
+    }
+
+    pub fn checksum(&self) -> &C {
+        &self.checksum
+    }
+
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Reading from or writing to the inner value directly bypasses the checksum
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    pub fn into_parts(self) -> (T, C) {
+        (self.inner, self.checksum)
+    }
+}
+
+impl<T: Refill, C: Checksum> Refill for Checksummed<T, C> {
+    type Error = T::Error;
+
+    fn refill(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let len = self.inner.refill(buf)?;
+        self.checksum.update(&buf[..len]);
+        Ok(len)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: std::io::Read, C: Checksum> std::io::Read for Checksummed<T, C> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.checksum.update(&buf[..len]);
+        Ok(len)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: std::io::Write, C: Checksum> std::io::Write for Checksummed<T, C> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let len = self.inner.write(buf)?;
+        self.checksum.update(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_values() {
+        assert_eq!(Crc32::checksum(b""), 0);
+        assert_eq!(Crc32::checksum(b"123456789"), 0xCBF4_3926);
+        let mut crc = Crc32::new();
+        crc.update(b"1234");
+        crc.update(b"56789");
+        assert_eq!(crc.value(), 0xCBF4_3926);
+        #[cfg(feature = "xxhash")]
+        {
+            let mut hash = XxHash32::new(0);
+            hash.update(b"1234");
+            hash.update(b"56789");
+            assert_eq!(hash.value(), XxHash32::checksum(b"123456789", 0));
+        }
+    }
+}
@@ -0,0 +1,821 @@
+//! Helpers for decoding the contents of Anvil chunks
+//!
+//! Block states are stored per 16x16x16 section as a palette and a packed array of palette
+//! indices, using the layout introduced in 1.16, where indices never span two longs. Biomes are
+//! stored the same way for each 4x4x4 cell of a section, and heightmaps use the layout with 9 bits
+//! per column.
+//!
+//! Worlds from before 1.13 identify blocks by numeric ids instead, stored as plain byte arrays
+//! with a data value in a nibble array alongside, see [LegacySection] and [McRegionBlocks].
+//!
+//! [block_entities] streams the block entities of a chunk straight from its encoded form.
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{
+    NbtTag,
+    convert::NbtArray,
+    error::{ChunkError, NbtConvertError, NbtParseError, NbtResult},
+    extract::{CompoundReader, FromFragments, ListIter, ListReader, ValueReader, read_value},
+    split::entry_spans,
+    value::NbtCompound,
+    view::BeSlice,
+};
+
+/// The number of blocks in a section
+pub const SECTION_VOLUME: usize = 16 * 16 * 16;
+
+/// The number of 4x4x4 biome cells in a section
+pub const BIOME_CELLS: usize = 4 * 4 * 4;
+
+/// A view of unsigned values packed into longs with a fixed number of bits each, starting at the
+/// least significant bits, where no value spans two longs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PackedBits<'d> {
+    data: BeSlice<'d, i64>,
+    bits: u32,
+    len: usize,
+}
+
+impl<'d> PackedBits<'d> {
+    /// Views `len` values of `bits` bits each, checking that `data` has exactly the right length
+    ///
+    /// # Panics
+    /// Panics if `bits` is not in `1..=64`
+    pub fn new(data: BeSlice<'d, i64>, bits: u32, len: usize) -> Result<Self, ChunkError> {
+        assert!((1..=64).contains(&bits), "invalid bit count {bits}");
+        let expected = len.div_ceil((64 / bits) as usize);
+        if data.len() != expected {
+            return Err(ChunkError::InvalidDataLen {
+                expected,
+                found: data.len(),
+            });
+        }
+        Ok(Self { data, bits, len })
+    }
+
+    pub const fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<u64> {
+        if index >= self.len {
+            return None;
+        }
+        let per_long = (64 / self.bits) as usize;
+        let long = self.data.get(index / per_long)? as u64;
+        let shift = (index % per_long) as u32 * self.bits;
+        Some((long >> shift) & (u64::MAX >> (64 - self.bits)))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        (0..self.len).filter_map(|index| self.get(index))
+    }
+}
+
+/// A palette along with packed indices into it, which are omitted for single entry palettes
+#[derive(Debug, Clone, PartialEq)]
+struct Paletted<'d, T> {
+    palette: Vec<T>,
+    data: Option<PackedBits<'d>>,
+    len: usize,
+}
+
+impl<'d, T> Paletted<'d, T> {
+    fn new(
+        palette: impl IntoIterator<Item = T>,
+        data: BeSlice<'d, i64>,
+        len: usize,
+        min_bits: u32,
+    ) -> Result<Self, ChunkError> {
+        let palette: Vec<T> = palette.into_iter().collect();
+        let data = match palette.len() {
+            0 => return Err(ChunkError::EmptyPalette),
+            1 => None,
+            entries => {
+                let bits = (usize::BITS - (entries - 1).leading_zeros()).max(min_bits);
+                Some(PackedBits::new(data, bits, len)?)
+            }
+        };
+        Ok(Self { palette, data, len })
+    }
+
+    fn bits(&self) -> u32 {
+        self.data.map_or(0, |data| data.bits())
+    }
+
+    fn index(&self, index: usize) -> Option<usize> {
+        match &self.data {
+            Some(data) => data.get(index).map(|index| index as usize),
+            None => (index < self.len).then_some(0),
+        }
+    }
+
+    fn get(&self, index: usize) -> Option<&T> {
+        self.palette.get(self.index(index)?)
+    }
+}
+
+/// The block states of a chunk section, resolved through its palette
+///
+/// Entries can be any type, e.g. the `&NbtValue`s of the `palette` list or block names extracted
+/// from it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockStates<'d, T> {
+    inner: Paletted<'d, T>,
+}
+
+impl<'d, T> BlockStates<'d, T> {
+    /// Creates the block states from the `palette` and `data` entries of a section's
+    /// `block_states`
+    ///
+    /// `data` is only required if the palette has more than one entry.
+    pub fn from_section(
+        palette: impl IntoIterator<Item = T>,
+        data: BeSlice<'d, i64>,
+    ) -> Result<Self, ChunkError> {
+        Ok(Self {
+            inner: Paletted::new(palette, data, SECTION_VOLUME, 4)?,
+        })
+    }
+
+    pub fn palette(&self) -> &[T] {
+        &self.inner.palette
+    }
+
+    /// The number of bits used by each packed index, 0 for single entry palettes
+    pub fn bits_per_entry(&self) -> u32 {
+        self.inner.bits()
+    }
+
+    /// The palette index of the block at `x`, `y`, `z`, with all coordinates in `0..16`
+    pub fn palette_index(&self, x: usize, y: usize, z: usize) -> Option<usize> {
+        self.inner.index(section_index(x, y, z)?)
+    }
+
+    /// The palette entry of the block at `x`, `y`, `z`, with all coordinates in `0..16`
+    ///
+    /// Returns [None] for coordinates outside the section and indices outside the palette.
+    pub fn get(&self, x: usize, y: usize, z: usize) -> Option<&T> {
+        self.inner.get(section_index(x, y, z)?)
+    }
+
+    /// Iterates over the palette entries of all blocks, ordered by y, then z, then x
+    pub fn iter(&self) -> impl Iterator<Item = Option<&T>> {
+        (0..SECTION_VOLUME).map(|index| self.inner.get(index))
+    }
+}
+
+/// The biomes of a chunk section, resolved through its palette
+///
+/// Biomes are stored for cells of 4x4x4 blocks. Entries are usually the biome names of the
+/// `palette` list, e.g. `minecraft:plains`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Biomes<'d, T> {
+    inner: Paletted<'d, T>,
+}
+
+impl<'d, T> Biomes<'d, T> {
+    /// Creates the biomes from the `palette` and `data` entries of a section's `biomes`
+    ///
+    /// `data` is only required if the palette has more than one entry.
+    pub fn from_section(
+        palette: impl IntoIterator<Item = T>,
+        data: BeSlice<'d, i64>,
+    ) -> Result<Self, ChunkError> {
+        Ok(Self {
+            inner: Paletted::new(palette, data, BIOME_CELLS, 1)?,
+        })
+    }
+
+    pub fn palette(&self) -> &[T] {
+        &self.inner.palette
+    }
+
+    /// The number of bits used by each packed index, 0 for single entry palettes
+    pub fn bits_per_entry(&self) -> u32 {
+        self.inner.bits()
+    }
+
+    /// The palette index of the cell at `x`, `y`, `z`, with all coordinates in `0..4`
+    pub fn palette_index(&self, x: usize, y: usize, z: usize) -> Option<usize> {
+        self.inner.index(cell_index(x, y, z)?)
+    }
+
+    /// The palette entry of the cell at `x`, `y`, `z`, with all coordinates in `0..4`
+    ///
+    /// Returns [None] for coordinates outside the section and indices outside the palette.
+    pub fn get(&self, x: usize, y: usize, z: usize) -> Option<&T> {
+        self.inner.get(cell_index(x, y, z)?)
+    }
+
+    /// The palette entry of the cell holding the block at `x`, `y`, `z`, with all coordinates in
+    /// `0..16`
+    pub fn at_block(&self, x: usize, y: usize, z: usize) -> Option<&T> {
+        section_index(x, y, z)?;
+        self.get(x / 4, y / 4, z / 4)
+    }
+
+    /// Iterates over the palette entries of all cells, ordered by y, then z, then x
+    pub fn iter(&self) -> impl Iterator<Item = Option<&T>> {
+        (0..BIOME_CELLS).map(|index| self.inner.get(index))
+    }
+}
+
+fn cell_index(x: usize, y: usize, z: usize) -> Option<usize> {
+    (x < 4 && y < 4 && z < 4).then_some(y * 16 + z * 4 + x)
+}
+
+fn section_index(x: usize, y: usize, z: usize) -> Option<usize> {
+    (x < 16 && y < 16 && z < 16).then_some(y * 256 + z * 16 + x)
+}
+
+/// The number of bits used by each height in a heightmap
+pub const HEIGHTMAP_BITS: u32 = 9;
+
+/// The heights of a heightmap indexed by `[z][x]`, counted in blocks above the bottom of the world
+pub type HeightGrid = [[u16; 16]; 16];
+
+/// The kinds of heightmaps stored in the `Heightmaps` compound of a chunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HeightmapKind {
+    WorldSurfaceWg,
+    WorldSurface,
+    OceanFloorWg,
+    OceanFloor,
+    MotionBlocking,
+    MotionBlockingNoLeaves,
+}
+
+impl HeightmapKind {
+    pub const ALL: [Self; 6] = [
+        Self::WorldSurfaceWg,
+        Self::WorldSurface,
+        Self::OceanFloorWg,
+        Self::OceanFloor,
+        Self::MotionBlocking,
+        Self::MotionBlockingNoLeaves,
+    ];
+
+    /// The key of this heightmap in the `Heightmaps` compound
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::WorldSurfaceWg => "WORLD_SURFACE_WG",
+            Self::WorldSurface => "WORLD_SURFACE",
+            Self::OceanFloorWg => "OCEAN_FLOOR_WG",
+            Self::OceanFloor => "OCEAN_FLOOR",
+            Self::MotionBlocking => "MOTION_BLOCKING",
+            Self::MotionBlockingNoLeaves => "MOTION_BLOCKING_NO_LEAVES",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+/// Decodes the packed long array of a single heightmap
+pub fn decode_heightmap(data: BeSlice<'_, i64>) -> Result<HeightGrid, ChunkError> {
+    let packed = PackedBits::new(data, HEIGHTMAP_BITS, 256)?;
+    let mut grid = [[0; 16]; 16];
+    for (index, height) in packed.iter().enumerate() {
+        grid[index / 16][index % 16] = height as u16;
+    }
+    Ok(grid)
+}
+
+/// The decoded heightmaps of a chunk
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Heightmaps {
+    maps: Vec<(HeightmapKind, HeightGrid)>,
+}
+
+impl Heightmaps {
+    /// Decodes all known heightmaps of a chunk's `Heightmaps` compound, ignoring unknown keys
+    pub fn from_compound(compound: &NbtCompound) -> Result<Self, ChunkError> {
+        let mut maps = Vec::new();
+        for (name, value) in compound.iter() {
+            let Some(kind) = HeightmapKind::from_name(name) else {
+                continue;
+            };
+            let longs =
+                i64::from_nbt_array(value).map_err(|err| NbtConvertError::in_field(name, err))?;
+            let bytes: Vec<u8> = longs.iter().flat_map(|long| long.to_be_bytes()).collect();
+            let data = BeSlice::new(&bytes).expect("the length is a multiple of 8");
+            maps.push((kind, decode_heightmap(data)?));
+        }
+        Ok(Self { maps })
+    }
+
+    pub fn get(&self, kind: HeightmapKind) -> Option<&HeightGrid> {
+        self.maps
+            .iter()
+            .find(|(other, _)| *other == kind)
+            .map(|(_, grid)| grid)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (HeightmapKind, &HeightGrid)> {
+        self.maps.iter().map(|(kind, grid)| (*kind, grid))
+    }
+}
+
+/// A block of a world from before 1.13, identified by its numeric id
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct LegacyBlock {
+    pub id: u16,
+    /// The data value of the block, e.g. the color of wool
+    pub data: u8,
+}
+
+/// The blocks of an Anvil chunk section from before 1.13, ordered by y, then z, then x
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LegacySection<'d> {
+    blocks: &'d [u8],
+    add: Option<&'d [u8]>,
+    data: &'d [u8],
+}
+
+impl<'d> LegacySection<'d> {
+    /// Creates the section from its `Blocks`, `Data` and optional `Add` arrays, the last holding
+    /// the high bits of ids above 255
+    pub fn from_section(
+        blocks: &'d [u8],
+        data: &'d [u8],
+        add: Option<&'d [u8]>,
+    ) -> Result<Self, ChunkError> {
+        check_len("Blocks", blocks, SECTION_VOLUME)?;
+        check_len("Data", data, SECTION_VOLUME / 2)?;
+        if let Some(add) = add {
+            check_len("Add", add, SECTION_VOLUME / 2)?;
+        }
+        Ok(Self { blocks, add, data })
+    }
+
+    /// The block at `x`, `y`, `z`, with all coordinates in `0..16`
+    pub fn get(&self, x: usize, y: usize, z: usize) -> Option<LegacyBlock> {
+        Some(self.block(section_index(x, y, z)?))
+    }
+
+    /// Iterates over all blocks, ordered by y, then z, then x
+    pub fn iter(&self) -> impl Iterator<Item = LegacyBlock> + '_ {
+        (0..SECTION_VOLUME).map(|index| self.block(index))
+    }
+
+    fn block(&self, index: usize) -> LegacyBlock {
+        let add = self.add.map_or(0, |add| nibble(add, index));
+        LegacyBlock {
+            id: self.blocks[index] as u16 | (add as u16) << 8,
+            data: nibble(self.data, index),
+        }
+    }
+}
+
+/// The height of McRegion chunks
+pub const MCREGION_HEIGHT: usize = 128;
+
+/// The blocks of a chunk in the McRegion format used before 1.2, stored in the `Level` compound
+/// as a single column of 16x128x16 blocks, ordered by x, then z, then y
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct McRegionBlocks<'d> {
+    blocks: &'d [u8],
+    data: &'d [u8],
+}
+
+impl<'d> McRegionBlocks<'d> {
+    /// Creates the blocks from the `Blocks` and `Data` arrays of a chunk
+    pub fn from_level(blocks: &'d [u8], data: &'d [u8]) -> Result<Self, ChunkError> {
+        check_len("Blocks", blocks, 16 * 16 * MCREGION_HEIGHT)?;
+        check_len("Data", data, 16 * 16 * MCREGION_HEIGHT / 2)?;
+        Ok(Self { blocks, data })
+    }
+
+    /// The block at `x`, `y`, `z`, with `x` and `z` in `0..16` and `y` in `0..128`
+    pub fn get(&self, x: usize, y: usize, z: usize) -> Option<LegacyBlock> {
+        if x >= 16 || y >= MCREGION_HEIGHT || z >= 16 {
+            return None;
+        }
+        let index = (x * 16 + z) * MCREGION_HEIGHT + y;
+        Some(LegacyBlock {
+            id: self.blocks[index].into(),
+            data: nibble(self.data, index),
+        })
+    }
+
+    /// Splits the column into the 8 sections an Anvil chunk would hold, from the bottom up
+    pub fn sections(&self) -> impl Iterator<Item = [LegacyBlock; SECTION_VOLUME]> + '_ {
+        (0..MCREGION_HEIGHT / 16).map(|section| {
+            let mut blocks = [LegacyBlock::default(); SECTION_VOLUME];
+            for (index, block) in blocks.iter_mut().enumerate() {
+                let (y, z, x) = (index / 256, index / 16 % 16, index % 16);
+                *block = self
+                    .get(x, section * 16 + y, z)
+                    .expect("the coordinates are in the chunk");
+            }
+            blocks
+        })
+    }
+}
+
+fn check_len(name: &'static str, array: &[u8], expected: usize) -> Result<(), ChunkError> {
+    match array.len() == expected {
+        true => Ok(()),
+        false => Err(ChunkError::InvalidArrayLen {
+            name,
+            expected,
+            found: array.len(),
+        }),
+    }
+}
+
+/// The 4 bits at `index` of a nibble array, starting with the low bits of each byte
+fn nibble(array: &[u8], index: usize) -> u8 {
+    (array[index / 2] >> (index % 2 * 4)) & 0xF
+}
+
+/// A block entity of a chunk, found by [block_entities]
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockEntity<'d> {
+    value: ValueReader<'d>,
+    span: Range<usize>,
+    payload: &'d [u8],
+}
+
+impl<'d> BlockEntity<'d> {
+    /// The byte range of the entries of the block entity in the chunk, including the End tag
+    /// closing them
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// The encoded entries of the block entity, see [span](Self::span)
+    pub fn payload(&self) -> &'d [u8] {
+        self.payload
+    }
+
+    /// Reads the block entity as any type that can be read from a compound
+    pub fn read<T: FromFragments<'d>>(&self) -> NbtResult<T> {
+        T::from_fragments(self.value.clone())
+    }
+
+    pub fn compound<T>(
+        &self,
+        read: impl FnOnce(&CompoundReader<'d>) -> NbtResult<T>,
+    ) -> NbtResult<T> {
+        self.value.clone().compound(read)
+    }
+
+    /// The id of the block entity, e.g. `minecraft:chest`
+    pub fn id(&self) -> NbtResult<&'d str> {
+        self.compound(|entity| entity.field("id"))
+    }
+
+    /// The world coordinates of the block holding the block entity
+    pub fn position(&self) -> NbtResult<[i32; 3]> {
+        self.compound(|entity| Ok([entity.field("x")?, entity.field("y")?, entity.field("z")?]))
+    }
+}
+
+/// The block entities of a chunk, see [block_entities]
+#[derive(Debug)]
+pub struct BlockEntities<'d> {
+    chunk: &'d [u8],
+    elements: Option<ListIter<'d, ValueReader<'d>>>,
+    /// The end of the list in the chunk
+    end: usize,
+    /// The element following the one being read, which tells where that one ends
+    pending: Option<NbtResult<ValueReader<'d>>>,
+}
+
+impl<'d> BlockEntities<'d> {
+    fn fail(&mut self, err: NbtParseError) -> Option<NbtResult<BlockEntity<'d>>> {
+        self.elements = None;
+        Some(Err(err))
+    }
+}
+
+impl<'d> Iterator for BlockEntities<'d> {
+    type Item = NbtResult<BlockEntity<'d>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let elements = self.elements.as_mut()?;
+        let value = match self.pending.take().or_else(|| elements.next())? {
+            Ok(value) if value.tag() == NbtTag::Compound => value,
+            Ok(_) => return self.fail(NbtParseError::UnexpectedType),
+            Err(err) => return self.fail(err),
+        };
+        // Elements run until the end of the list, so where one starts follows from the input
+        // left after it, and it ends where the next one starts
+        let end = match elements.next() {
+            Some(Ok(next)) => {
+                let end = self.end - next.rest().len();
+                self.pending = Some(Ok(next));
+                end
+            }
+            Some(Err(err)) => return self.fail(err),
+            None => self.end,
+        };
+        let span = self.end - value.rest().len()..end;
+        Some(Ok(BlockEntity {
+            value,
+            payload: &self.chunk[span.clone()],
+            span,
+        }))
+    }
+}
+
+/// Finds the block entities of an encoded chunk, stored in `block_entities` since 1.18 and in
+/// `Level.TileEntities` before
+///
+/// Chunks without either list have no block entities.
+///
+/// ```
+/// # use zeronbt::chunk::block_entities;
+/// let chunk = include_bytes!("../assets/chunk_0-0.nbt");
+/// for entity in block_entities(chunk).unwrap() {
+///     let entity = entity.unwrap();
+///     println!("{} at {:?}", entity.id().unwrap(), entity.position().unwrap());
+/// }
+/// ```
+pub fn block_entities(chunk: &[u8]) -> NbtResult<BlockEntities<'_>> {
+    let span = match entry_span(chunk, "block_entities")? {
+        Some(span) => Some(span),
+        None => match entry_span(chunk, "Level")? {
+            Some(level) => entry_span(&chunk[level.clone()], "TileEntities")?
+                .map(|span| span.start + level.start..span.end + level.start),
+            None => None,
+        },
+    };
+    let Some(span) = span else {
+        return Ok(BlockEntities {
+            chunk,
+            elements: None,
+            end: 0,
+            pending: None,
+        });
+    };
+    let list: ListReader = read_value(&chunk[span.clone()])?;
+    if !list.is_empty() && list.tag() != NbtTag::Compound {
+        return Err(NbtParseError::UnexpectedType);
+    }
+    Ok(BlockEntities {
+        chunk,
+        elements: Some(list.iter()),
+        end: span.end,
+        pending: None,
+    })
+}
+
+fn entry_span(data: &[u8], key: &str) -> NbtResult<Option<Range<usize>>> {
+    let spans = entry_spans(data)?;
+    Ok(spans
+        .into_iter()
+        .find(|(name, _)| name == key)
+        .map(|(_, span)| span))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::{NbtList, NbtValue};
+    use alloc::vec;
+
+    fn longs(values: &[i64]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_be_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn single_entry() {
+        let states = BlockStates::from_section(["air"], BeSlice::new(&[]).unwrap()).unwrap();
+        assert_eq!(states.bits_per_entry(), 0);
+        assert_eq!(states.get(15, 15, 15), Some(&"air"));
+        assert_eq!(states.get(16, 0, 0), None);
+        assert!(matches!(
+            BlockStates::<&str>::from_section([], BeSlice::new(&[]).unwrap()),
+            Err(ChunkError::EmptyPalette)
+        ));
+    }
+
+    #[test]
+    fn packed() {
+        // 5 entries need 4 bits, so 16 indices fit in each long
+        let mut data = vec![0; 256];
+        data[0] = 0x4321;
+        data[255] = 1 << 60;
+        let bytes = longs(&data);
+        let states =
+            BlockStates::from_section(["a", "b", "c", "d", "e"], BeSlice::new(&bytes).unwrap())
+                .unwrap();
+        assert_eq!(states.bits_per_entry(), 4);
+        assert_eq!(states.get(0, 0, 0), Some(&"b"));
+        assert_eq!(states.get(3, 0, 0), Some(&"e"));
+        assert_eq!(states.get(4, 0, 0), Some(&"a"));
+        assert_eq!(states.get(15, 15, 15), Some(&"b"));
+
+        let bytes = longs(&data[..10]);
+        assert_eq!(
+            BlockStates::from_section(["a", "b"], BeSlice::new(&bytes).unwrap()),
+            Err(ChunkError::InvalidDataLen {
+                expected: 256,
+                found: 10
+            })
+        );
+    }
+
+    #[test]
+    fn read_chunk() {
+        let (_, chunk) = NbtValue::read(include_bytes!("../assets/chunk_0-0.nbt")).unwrap();
+        let Some(NbtValue::List(sections)) = chunk.get("sections") else {
+            panic!("missing sections");
+        };
+        let bottom = sections
+            .iter()
+            .find(|section| section.get("Y") == Some(&NbtValue::Byte(-4)))
+            .unwrap();
+        let block_states = bottom.get("block_states").unwrap();
+        let Some(NbtValue::List(palette)) = block_states.get("palette") else {
+            panic!("missing palette");
+        };
+        let data = match block_states.get("data") {
+            Some(NbtValue::LongArray(data)) => longs(data),
+            _ => Vec::new(),
+        };
+        let states = BlockStates::from_section(
+            palette.iter().map(|entry| entry.get("Name").unwrap()),
+            BeSlice::new(&data).unwrap(),
+        )
+        .unwrap();
+        let bedrock = NbtValue::String("minecraft:bedrock".into());
+        for x in 0..16 {
+            for z in 0..16 {
+                assert_eq!(states.get(x, 0, z), Some(&&bedrock));
+            }
+        }
+        assert!(states.iter().all(|entry| entry.is_some()));
+    }
+
+    #[test]
+    fn biomes() {
+        // 3 entries need 2 bits, so 32 indices fit in each long
+        let mut data = vec![0; 2];
+        data[0] = 0b10_01;
+        data[1] = 2 << 62;
+        let bytes = longs(&data);
+        let biomes = Biomes::from_section(
+            ["plains", "forest", "desert"],
+            BeSlice::new(&bytes).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(biomes.bits_per_entry(), 2);
+        assert_eq!(biomes.get(0, 0, 0), Some(&"forest"));
+        assert_eq!(biomes.get(1, 0, 0), Some(&"desert"));
+        assert_eq!(biomes.get(3, 3, 3), Some(&"desert"));
+        assert_eq!(biomes.at_block(15, 15, 15), Some(&"desert"));
+        assert_eq!(biomes.at_block(9, 0, 0), Some(&"plains"));
+        assert_eq!(biomes.get(4, 0, 0), None);
+
+        // Even two entries take a bit each
+        let biomes = Biomes::from_section(["a", "b"], BeSlice::new(&bytes[..8]).unwrap()).unwrap();
+        assert_eq!(biomes.bits_per_entry(), 1);
+        assert_eq!(
+            biomes.iter().filter(|&biome| biome == Some(&"b")).count(),
+            2
+        );
+
+        let (_, chunk) = NbtValue::read(include_bytes!("../assets/chunk_0-0.nbt")).unwrap();
+        let Some(NbtValue::List(sections)) = chunk.get("sections") else {
+            panic!("missing sections");
+        };
+        // The sections padding the world above and below only hold light
+        for biomes in sections.iter().filter_map(|section| section.get("biomes")) {
+            let Some(NbtValue::List(palette)) = biomes.get("palette") else {
+                panic!("missing palette");
+            };
+            let data = match biomes.get("data") {
+                Some(NbtValue::LongArray(data)) => longs(data),
+                _ => Vec::new(),
+            };
+            let biomes =
+                Biomes::from_section(palette.iter(), BeSlice::new(&data).unwrap()).unwrap();
+            assert!(
+                biomes
+                    .iter()
+                    .all(|biome| biome.is_some_and(|biome| biome.as_str().is_some()))
+            );
+        }
+    }
+
+    #[test]
+    fn heightmaps() {
+        let (_, chunk) = NbtValue::read(include_bytes!("../assets/chunk_0-0.nbt")).unwrap();
+        let compound = chunk.get("Heightmaps").unwrap().as_compound().unwrap();
+        let heightmaps = Heightmaps::from_compound(compound).unwrap();
+        assert_eq!(heightmaps.iter().count(), compound.len());
+        let surface = heightmaps.get(HeightmapKind::WorldSurface).unwrap();
+        let floor = heightmaps.get(HeightmapKind::OceanFloor).unwrap();
+        for (surface, floor) in surface.iter().flatten().zip(floor.iter().flatten()) {
+            assert!((1..=384).contains(surface));
+            assert!(surface >= floor);
+        }
+
+        let mut packed = vec![0; 37];
+        packed[0] = 5 | 300 << 9;
+        let bytes = longs(&packed);
+        let grid = decode_heightmap(BeSlice::new(&bytes).unwrap()).unwrap();
+        assert_eq!(grid[0][..3], [5, 300, 0]);
+    }
+
+    #[test]
+    fn legacy_blocks() {
+        let mut blocks = vec![0; SECTION_VOLUME];
+        let mut data = vec![0; SECTION_VOLUME / 2];
+        let mut add = vec![0; SECTION_VOLUME / 2];
+        // Red wool at 1, 0, 0 and a block with id 257 at 0, 1, 0
+        blocks[1] = 35;
+        data[0] = 0xE0;
+        blocks[256] = 1;
+        add[128] = 0x01;
+        let section = LegacySection::from_section(&blocks, &data, Some(&add)).unwrap();
+        assert_eq!(section.get(1, 0, 0), Some(LegacyBlock { id: 35, data: 14 }));
+        assert_eq!(section.get(0, 1, 0), Some(LegacyBlock { id: 257, data: 0 }));
+        assert_eq!(section.get(0, 16, 0), None);
+        assert_eq!(section.iter().filter(|block| block.id != 0).count(), 2);
+        assert_eq!(
+            LegacySection::from_section(&blocks, &data[1..], None),
+            Err(ChunkError::InvalidArrayLen {
+                name: "Data",
+                expected: 2048,
+                found: 2047
+            })
+        );
+
+        let mut blocks = vec![0; 16 * 16 * MCREGION_HEIGHT];
+        let data = vec![0x21; 16 * 16 * MCREGION_HEIGHT / 2];
+        // Bedrock at the bottom of the column at 2, 3 and stone above it
+        blocks[(2 * 16 + 3) * MCREGION_HEIGHT] = 7;
+        blocks[(2 * 16 + 3) * MCREGION_HEIGHT + 17] = 1;
+        let column = McRegionBlocks::from_level(&blocks, &data).unwrap();
+        assert_eq!(column.get(2, 0, 3), Some(LegacyBlock { id: 7, data: 1 }));
+        assert_eq!(column.get(2, 17, 3), Some(LegacyBlock { id: 1, data: 2 }));
+        assert_eq!(column.get(2, 128, 3), None);
+        let sections: Vec<_> = column.sections().collect();
+        assert_eq!(sections.len(), 8);
+        assert_eq!(sections[1][256 + 3 * 16 + 2].id, 1);
+    }
+
+    #[test]
+    fn block_entities() {
+        let entity = |id: &str, x: i32| -> NbtValue {
+            let mut entity: NbtCompound = [("id", NbtValue::from(id))].into_iter().collect();
+            for (key, value) in [("x", x), ("y", 64), ("z", -3)] {
+                entity.insert(key, value);
+            }
+            entity.insert("Items", NbtList::with_tag(NbtTag::Compound));
+            entity.into()
+        };
+        let list = NbtList::try_from(vec![
+            entity("minecraft:chest", 1),
+            entity("minecraft:sign", 2),
+        ])
+        .unwrap();
+        let modern: NbtCompound = [
+            ("DataVersion", NbtValue::Int(3465)),
+            ("block_entities", list.clone().into()),
+        ]
+        .into_iter()
+        .collect();
+        let level: NbtCompound = [("TileEntities", list.clone())].into_iter().collect();
+        let legacy: NbtCompound = [("Level", level)].into_iter().collect();
+
+        for chunk in [modern, legacy] {
+            let data = NbtValue::Compound(chunk).to_bytes("").unwrap();
+            let entities: Vec<_> = super::block_entities(&data)
+                .unwrap()
+                .map(Result::unwrap)
+                .collect();
+            assert_eq!(entities.len(), 2);
+            for (entity, expected) in entities.iter().zip(list.iter()) {
+                assert_eq!(
+                    entity.position().unwrap()[0],
+                    expected.get("x").unwrap().as_int().unwrap()
+                );
+                let document = [&[NbtTag::Compound as u8, 0, 0], entity.payload()].concat();
+                assert_eq!(&NbtValue::read(&document).unwrap().1, expected);
+            }
+            assert_eq!(entities[1].id().unwrap(), "minecraft:sign");
+        }
+
+        let chunk = include_bytes!("../assets/chunk_0-0.nbt");
+        assert_eq!(super::block_entities(chunk).unwrap().count(), 0);
+    }
+}
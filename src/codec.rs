@@ -0,0 +1,236 @@
+//! A [tokio_util::codec] implementation for sending NBT over framed transports
+use alloc::{string::String, vec::Vec};
+use bytes::{Buf, BufMut, BytesMut};
+use core::mem;
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::{
+    FsmResult, NbtFsm,
+    error::{NbtIoError, NbtParseError, NbtWriteError},
+    value::{NbtValue, NbtValueBuilder},
+};
+
+/// How the payloads are separated on the wire
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Framing {
+    /// Payloads are written back to back, and the end of a payload is found by parsing it
+    #[default]
+    Structure,
+    /// Every payload is prefixed with its length as a big-endian u32
+    LengthPrefixed,
+}
+
+/// Decodes and encodes root tags, optionally in the nameless network format
+///
+/// Decoding is incremental: data is parsed as it arrives, and only the partially built value is
+/// kept between calls, so large payloads do not have to be buffered in full.
+#[derive(Debug, Clone, Default)]
+pub struct NbtCodec {
+    framing: Framing,
+    nameless: bool,
+    max_len: Option<usize>,
+    fsm: NbtFsm<'static>,
+    builder: NbtValueBuilder,
+    /// The length of the payload being decoded with [Framing::LengthPrefixed]
+    remaining: Option<usize>,
+}
+
+impl NbtCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Uses the network format, where root tags are not named
+    pub fn nameless(mut self) -> Self {
+        self.nameless = true;
+        self.fsm = NbtFsm::new_nameless();
+        self
+    }
+
+    pub fn framing(mut self, framing: Framing) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    /// Rejects length-prefixed payloads longer than `max_len`
+    pub fn max_len(mut self, max_len: usize) -> Self {
+        self.max_len = Some(max_len);
+        self
+    }
+
+    fn reset(&mut self) {
+        self.fsm = if self.nameless {
+            NbtFsm::new_nameless()
+        } else {
+            NbtFsm::new()
+        };
+        self.builder = NbtValueBuilder::new();
+    }
+}
+
+impl Decoder for NbtCodec {
+    type Item = (String, NbtValue);
+    type Error = NbtIoError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if self.framing == Framing::LengthPrefixed && self.remaining.is_none() {
+            if src.len() < 4 {
+                return Ok(None);
+            }
+            let len = src.get_u32() as usize;
+            if self.max_len.is_some_and(|max| len > max) {
+                return Err(NbtParseError::InvalidLen(len as i32).into());
+            }
+            self.remaining = Some(len);
+        }
+        let available = self.remaining.map_or(src.len(), |len| len.min(src.len()));
+        let mut fsm = mem::take(&mut self.fsm).with_data(&src[..available]);
+        let mut root = None;
+        let result = loop {
+            match fsm.next_fragment() {
+                Ok(FsmResult::Found(fragment)) => match self.builder.push(fragment) {
+                    Ok(Some(value)) => {
+                        root = Some(value);
+                        break Ok(());
+                    }
+                    Ok(None) => (),
+                    Err(err) => break Err(err),
+                },
+                Ok(FsmResult::Needs(_)) => break Ok(()),
+                Err(err) => break Err(err),
+            }
+        };
+        let consumed = fsm.consumed();
+        self.fsm = fsm.with_data(&[]);
+        src.advance(consumed);
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= consumed;
+        }
+        if let Err(err) = result {
+            self.reset();
+            return Err(err.into());
+        }
+        match (root, self.remaining) {
+            (Some(root), None | Some(0)) => {
+                self.remaining = None;
+                self.reset();
+                Ok(Some(root))
+            }
+            (Some(_), Some(_)) => {
+                self.remaining = None;
+                Err(NbtParseError::UnexpectedFragment.into())
+            }
+            (None, Some(0)) => {
+                self.remaining = None;
+                self.reset();
+                Err(NbtParseError::UnexpectedEnd.into())
+            }
+            (None, _) => {
+                src.reserve(self.remaining.unwrap_or(1).saturating_sub(src.len()));
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl NbtCodec {
+    fn encode_with(
+        &mut self,
+        write: impl FnOnce(&mut Vec<u8>) -> Result<(), NbtWriteError>,
+        dst: &mut BytesMut,
+    ) -> Result<(), NbtIoError> {
+        let mut out = Vec::new();
+        write(&mut out)?;
+        if self.framing == Framing::LengthPrefixed {
+            let len =
+                u32::try_from(out.len()).map_err(|_| NbtWriteError::TooManyElements(out.len()))?;
+            dst.put_u32(len);
+        }
+        dst.extend_from_slice(&out);
+        Ok(())
+    }
+}
+
+impl Encoder<&NbtValue> for NbtCodec {
+    type Error = NbtIoError;
+
+    /// Encodes the value as a root tag with an empty name, or no name in the nameless format
+    fn encode(&mut self, value: &NbtValue, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        if self.nameless {
+            self.encode_with(|out| value.write_nameless(out), dst)
+        } else {
+            self.encode_with(|out| value.write("", out), dst)
+        }
+    }
+}
+
+impl Encoder<(&str, &NbtValue)> for NbtCodec {
+    type Error = NbtIoError;
+
+    /// Encodes a named root tag, the name is dropped in the nameless format
+    fn encode(
+        &mut self,
+        (name, value): (&str, &NbtValue),
+        dst: &mut BytesMut,
+    ) -> Result<(), Self::Error> {
+        if self.nameless {
+            self.encode_with(|out| value.write_nameless(out), dst)
+        } else {
+            self.encode_with(|out| value.write(name, out), dst)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bigtest() -> (String, NbtValue) {
+        NbtValue::read(include_bytes!("../assets/bigtest.nbt")).unwrap()
+    }
+
+    fn round_trip(mut codec: NbtCodec, expected_name: &str) {
+        let (name, value) = bigtest();
+        let mut encoded = BytesMut::new();
+        codec.encode((name.as_str(), &value), &mut encoded).unwrap();
+        codec.encode(&value, &mut encoded).unwrap();
+
+        // Feed the data in small pieces, as it would arrive from a socket
+        let mut src = BytesMut::new();
+        let mut decoded = Vec::new();
+        for chunk in encoded.chunks(5) {
+            src.extend_from_slice(chunk);
+            while let Some(item) = codec.decode(&mut src).unwrap() {
+                decoded.push(item);
+            }
+        }
+        assert!(src.is_empty());
+        assert_eq!(
+            decoded,
+            [
+                (expected_name.into(), value.clone()),
+                (String::new(), value)
+            ]
+        );
+    }
+
+    #[test]
+    fn structure_delimited() {
+        round_trip(NbtCodec::new(), "Level");
+    }
+
+    #[test]
+    fn nameless_length_prefixed() {
+        round_trip(
+            NbtCodec::new().nameless().framing(Framing::LengthPrefixed),
+            "",
+        );
+    }
+
+    #[test]
+    fn max_len() {
+        let mut codec = NbtCodec::new().framing(Framing::LengthPrefixed).max_len(16);
+        let mut src = BytesMut::from(&[0, 0, 1, 0][..]);
+        assert!(codec.decode(&mut src).is_err());
+    }
+}
@@ -0,0 +1,453 @@
+//! Decompression of NBT files and chunk payloads
+//!
+//! Files on disk are almost always gzip compressed, while chunks in region files are usually
+//! zlib compressed. [Decompress] detects which one it is handed, so both can be parsed with
+//! [NbtReader::decompress]. Servers may also recompress their data with zstd, which is supported
+//! with the `zstd` feature, and Minecraft 1.20.5 added LZ4 as a region compression type, which is
+//! supported with the `lz4` feature.
+//!
+//! [Compress] applies any of the formats when writing. Other algorithms can be plugged in by
+//! implementing [CustomCompression].
+use alloc::{sync::Arc, vec::Vec};
+use core::fmt;
+use std::io::{self, Chain, Cursor, ErrorKind, Read, Write};
+
+use crate::io::NbtReader;
+
+#[cfg(feature = "lz4")]
+mod lz4;
+#[cfg(feature = "lz4")]
+pub use lz4::{Lz4Decoder, Lz4Encoder};
+
+/// The compression formats NBT is commonly stored in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zlib,
+    Zstd,
+    /// The block stream format of lz4-java
+    Lz4,
+    /// An algorithm implemented outside of this crate through [CustomCompression]
+    Custom,
+}
+
+/// A compression algorithm provided by the user, e.g. for the named compression type 127 of region
+/// files
+///
+/// Custom codecs work on complete payloads rather than streams, which matches how chunks are
+/// stored in region files.
+pub trait CustomCompression: Send + Sync {
+    /// The name identifying the algorithm, region files use a namespaced id like `mod:algorithm`
+    fn name(&self) -> &str;
+    /// Reads all of `input`, appending the decompressed data to `out`
+    fn decompress(&self, input: &mut dyn Read, out: &mut Vec<u8>) -> io::Result<()>;
+    /// Compresses `data`, writing the result to `out`
+    fn compress(&self, data: &[u8], out: &mut dyn Write) -> io::Result<()>;
+}
+
+impl Compression {
+    /// Guesses the compression from the first bytes of the data, falling back to [Compression::None]
+    pub fn detect(header: &[u8]) -> Self {
+        match *header {
+            [0x1F, 0x8B, ..] => Compression::Gzip,
+            [0x28, 0xB5, 0x2F, 0xFD, ..] => Compression::Zstd,
+            [b'L', b'Z', b'4', b'B', ..] => Compression::Lz4,
+            // The compression method is deflate, and the header checksum matches. 0x08 is excluded
+            // as it is also the tag of a root string
+            [cmf, flg, ..] if cmf & 0x0F == 8 && cmf > 0x08 && cmf <= 0x78 => {
+                if u16::from_be_bytes([cmf, flg]) % 31 == 0 {
+                    Compression::Zlib
+                } else {
+                    Compression::None
+                }
+            }
+            _ => Compression::None,
+        }
+    }
+
+    /// Maps the compression type stored in region file headers to a [Compression]
+    pub const fn from_region_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(Compression::Gzip),
+            2 => Some(Compression::Zlib),
+            3 => Some(Compression::None),
+            4 => Some(Compression::Lz4),
+            127 => Some(Compression::Custom),
+            _ => None,
+        }
+    }
+
+    /// The compression type stored in region file headers, zstd has no assigned id
+    pub const fn region_id(self) -> Option<u8> {
+        match self {
+            Compression::Gzip => Some(1),
+            Compression::Zlib => Some(2),
+            Compression::None => Some(3),
+            Compression::Lz4 => Some(4),
+            Compression::Custom => Some(127),
+            Compression::Zstd => None,
+        }
+    }
+}
+
+type Peeked<R> = Chain<Cursor<Vec<u8>>, R>;
+
+/// A reader that decompresses the data read from `R`
+pub enum Decompress<R> {
+    None(Peeked<R>),
+    #[cfg(feature = "flate2")]
+    Gzip(flate2::read::MultiGzDecoder<Peeked<R>>),
+    #[cfg(feature = "flate2")]
+    Zlib(flate2::read::ZlibDecoder<Peeked<R>>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::stream::read::Decoder<'static, io::BufReader<Peeked<R>>>),
+    #[cfg(feature = "lz4")]
+    Lz4(Lz4Decoder<Peeked<R>>),
+    /// The payload decompressed by a [CustomCompression]
+    Custom(Cursor<Vec<u8>>),
+}
+
+impl<R: Read> Decompress<R> {
+    /// Detects the compression from the first bytes read from `reader`
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut header = Vec::with_capacity(4);
+        (&mut reader).take(4).read_to_end(&mut header)?;
+        let compression = Compression::detect(&header);
+        Self::from_peeked(compression, Cursor::new(header).chain(reader))
+    }
+
+    /// Decompresses the data with a known compression, returning an error if support for it was
+    /// not compiled in
+    pub fn with_compression(compression: Compression, reader: R) -> io::Result<Self> {
+        Self::from_peeked(compression, Cursor::new(Vec::new()).chain(reader))
+    }
+
+    /// Decompresses all of `reader` with a custom codec
+    pub fn custom(codec: &dyn CustomCompression, mut reader: R) -> io::Result<Self> {
+        let mut data = Vec::new();
+        codec.decompress(&mut reader, &mut data)?;
+        Ok(Decompress::Custom(Cursor::new(data)))
+    }
+
+    fn from_peeked(compression: Compression, reader: Peeked<R>) -> io::Result<Self> {
+        match compression {
+            Compression::None => Ok(Decompress::None(reader)),
+            #[cfg(feature = "flate2")]
+            Compression::Gzip => Ok(Decompress::Gzip(flate2::read::MultiGzDecoder::new(reader))),
+            #[cfg(feature = "flate2")]
+            Compression::Zlib => Ok(Decompress::Zlib(flate2::read::ZlibDecoder::new(reader))),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Ok(Decompress::Zstd(zstd::stream::read::Decoder::new(reader)?)),
+            #[cfg(feature = "lz4")]
+            Compression::Lz4 => Ok(Decompress::Lz4(Lz4Decoder::new(reader))),
+            #[allow(unreachable_patterns)]
+            compression => Err(unsupported(compression)),
+        }
+    }
+}
+
+impl<R> Decompress<R> {
+    pub fn compression(&self) -> Compression {
+        match self {
+            Decompress::None(_) => Compression::None,
+            #[cfg(feature = "flate2")]
+            Decompress::Gzip(_) => Compression::Gzip,
+            #[cfg(feature = "flate2")]
+            Decompress::Zlib(_) => Compression::Zlib,
+            #[cfg(feature = "zstd")]
+            Decompress::Zstd(_) => Compression::Zstd,
+            #[cfg(feature = "lz4")]
+            Decompress::Lz4(_) => Compression::Lz4,
+            Decompress::Custom(_) => Compression::Custom,
+        }
+    }
+}
+
+impl<R: Read> Read for Decompress<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Decompress::None(reader) => reader.read(buf),
+            #[cfg(feature = "flate2")]
+            Decompress::Gzip(reader) => reader.read(buf),
+            #[cfg(feature = "flate2")]
+            Decompress::Zlib(reader) => reader.read(buf),
+            #[cfg(feature = "zstd")]
+            Decompress::Zstd(reader) => reader.read(buf),
+            #[cfg(feature = "lz4")]
+            Decompress::Lz4(reader) => reader.read(buf),
+            Decompress::Custom(reader) => reader.read(buf),
+        }
+    }
+}
+
+impl fmt::Debug for dyn CustomCompression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CustomCompression")
+            .field(&self.name())
+            .finish()
+    }
+}
+
+/// A writer that compresses the data before writing it to `W`
+///
+/// [Compress::finish] must be called once all data was written, to write out any trailers.
+pub enum Compress<W: Write> {
+    None(W),
+    #[cfg(feature = "flate2")]
+    Gzip(flate2::write::GzEncoder<W>),
+    #[cfg(feature = "flate2")]
+    Zlib(flate2::write::ZlibEncoder<W>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::stream::write::Encoder<'static, W>),
+    #[cfg(feature = "lz4")]
+    Lz4(Lz4Encoder<W>),
+    /// Buffers the payload until [Compress::finish], as [CustomCompression] works on complete
+    /// payloads
+    Custom {
+        codec: Arc<dyn CustomCompression>,
+        data: Vec<u8>,
+        writer: W,
+    },
+}
+
+impl<W: Write> Compress<W> {
+    /// Compresses with the default level of the format, returning an error if support for it was
+    /// not compiled in
+    pub fn new(compression: Compression, writer: W) -> io::Result<Self> {
+        match compression {
+            Compression::None => Ok(Compress::None(writer)),
+            #[cfg(feature = "flate2")]
+            Compression::Gzip => Ok(Compress::Gzip(flate2::write::GzEncoder::new(
+                writer,
+                flate2::Compression::default(),
+            ))),
+            #[cfg(feature = "flate2")]
+            Compression::Zlib => Ok(Compress::Zlib(flate2::write::ZlibEncoder::new(
+                writer,
+                flate2::Compression::default(),
+            ))),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Ok(Compress::Zstd(zstd::stream::write::Encoder::new(
+                writer,
+                zstd::DEFAULT_COMPRESSION_LEVEL,
+            )?)),
+            #[cfg(feature = "lz4")]
+            Compression::Lz4 => Ok(Compress::Lz4(Lz4Encoder::new(writer))),
+            #[allow(unreachable_patterns)]
+            compression => Err(unsupported(compression)),
+        }
+    }
+
+    /// Compresses with a custom codec
+    pub fn custom(codec: Arc<dyn CustomCompression>, writer: W) -> Self {
+        Compress::Custom {
+            codec,
+            data: Vec::new(),
+            writer,
+        }
+    }
+
+    /// Finishes the compressed stream and returns the underlying writer
+    pub fn finish(self) -> io::Result<W> {
+        match self {
+            Compress::None(writer) => Ok(writer),
+            #[cfg(feature = "flate2")]
+            Compress::Gzip(writer) => writer.finish(),
+            #[cfg(feature = "flate2")]
+            Compress::Zlib(writer) => writer.finish(),
+            #[cfg(feature = "zstd")]
+            Compress::Zstd(writer) => writer.finish(),
+            #[cfg(feature = "lz4")]
+            Compress::Lz4(writer) => writer.finish(),
+            Compress::Custom {
+                codec,
+                data,
+                mut writer,
+            } => {
+                codec.compress(&data, &mut writer)?;
+                Ok(writer)
+            }
+        }
+    }
+}
+
+impl<W: Write> Compress<W> {
+    pub fn compression(&self) -> Compression {
+        match self {
+            Compress::None(_) => Compression::None,
+            #[cfg(feature = "flate2")]
+            Compress::Gzip(_) => Compression::Gzip,
+            #[cfg(feature = "flate2")]
+            Compress::Zlib(_) => Compression::Zlib,
+            #[cfg(feature = "zstd")]
+            Compress::Zstd(_) => Compression::Zstd,
+            #[cfg(feature = "lz4")]
+            Compress::Lz4(_) => Compression::Lz4,
+            Compress::Custom { .. } => Compression::Custom,
+        }
+    }
+}
+
+impl<R> fmt::Debug for Decompress<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Decompress")
+            .field(&self.compression())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<W: Write> fmt::Debug for Compress<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Compress")
+            .field(&self.compression())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<W: Write> Write for Compress<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Compress::None(writer) => writer.write(buf),
+            #[cfg(feature = "flate2")]
+            Compress::Gzip(writer) => writer.write(buf),
+            #[cfg(feature = "flate2")]
+            Compress::Zlib(writer) => writer.write(buf),
+            #[cfg(feature = "zstd")]
+            Compress::Zstd(writer) => writer.write(buf),
+            #[cfg(feature = "lz4")]
+            Compress::Lz4(writer) => writer.write(buf),
+            Compress::Custom { data, .. } => data.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Compress::None(writer) => writer.flush(),
+            #[cfg(feature = "flate2")]
+            Compress::Gzip(writer) => writer.flush(),
+            #[cfg(feature = "flate2")]
+            Compress::Zlib(writer) => writer.flush(),
+            #[cfg(feature = "zstd")]
+            Compress::Zstd(writer) => writer.flush(),
+            #[cfg(feature = "lz4")]
+            Compress::Lz4(writer) => writer.flush(),
+            Compress::Custom { .. } => Ok(()),
+        }
+    }
+}
+
+/// Compresses `data` in memory
+pub fn compress(compression: Compression, data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut writer = Compress::new(compression, Vec::new())?;
+    writer.write_all(data)?;
+    writer.finish()
+}
+
+fn unsupported(compression: Compression) -> io::Error {
+    if compression == Compression::Custom {
+        return io::Error::new(
+            ErrorKind::InvalidInput,
+            "custom compression requires a CustomCompression codec",
+        );
+    }
+    io::Error::new(
+        ErrorKind::Unsupported,
+        alloc::format!("support for {compression:?} compression is not enabled"),
+    )
+}
+
+impl<R: Read> NbtReader<Decompress<R>> {
+    /// Parses NBT that may be gzip or zlib compressed, detecting the compression from the first
+    /// bytes of `reader`
+    pub fn decompress(reader: R) -> io::Result<Self> {
+        Ok(NbtReader::new(Decompress::new(reader)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::NbtValue;
+
+    const BIGTEST: &[u8] = include_bytes!("../assets/bigtest.nbt");
+
+    #[test]
+    fn detect() {
+        assert_eq!(Compression::detect(BIGTEST), Compression::None);
+        assert_eq!(Compression::detect(&[0x1F, 0x8B, 8]), Compression::Gzip);
+        assert_eq!(Compression::detect(&[0x78, 0x9C]), Compression::Zlib);
+        assert_eq!(
+            Compression::detect(&[0x28, 0xB5, 0x2F, 0xFD]),
+            Compression::Zstd
+        );
+        assert_eq!(Compression::detect(b"LZ4Block"), Compression::Lz4);
+        assert_eq!(Compression::detect(&[0x78, 0x9D]), Compression::None);
+        assert_eq!(Compression::detect(&[]), Compression::None);
+    }
+
+    #[test]
+    fn region_ids() {
+        for id in 0..=u8::MAX {
+            if let Some(compression) = Compression::from_region_id(id) {
+                assert_eq!(compression.region_id(), Some(id));
+            }
+        }
+        assert_eq!(Compression::from_region_id(2), Some(Compression::Zlib));
+        assert_eq!(Compression::Zstd.region_id(), None);
+    }
+
+    /// Stores the payload reversed
+    struct Reverse;
+
+    impl CustomCompression for Reverse {
+        fn name(&self) -> &str {
+            "test:reverse"
+        }
+        fn decompress(&self, input: &mut dyn Read, out: &mut Vec<u8>) -> io::Result<()> {
+            input.read_to_end(out)?;
+            out.reverse();
+            Ok(())
+        }
+        fn compress(&self, data: &[u8], out: &mut dyn Write) -> io::Result<()> {
+            out.write_all(&data.iter().rev().copied().collect::<Vec<_>>())
+        }
+    }
+
+    #[test]
+    fn custom_codec() {
+        let mut writer = Compress::custom(Arc::new(Reverse), Vec::new());
+        writer.write_all(BIGTEST).unwrap();
+        let data = writer.finish().unwrap();
+        assert_ne!(data, BIGTEST);
+        assert_eq!(
+            Decompress::with_compression(Compression::Custom, data.as_slice())
+                .unwrap_err()
+                .kind(),
+            ErrorKind::InvalidInput
+        );
+        let decompress = Decompress::custom(&Reverse, data.as_slice()).unwrap();
+        assert_eq!(
+            NbtReader::new(decompress).read_value().unwrap(),
+            Some(NbtValue::read(BIGTEST).unwrap())
+        );
+    }
+
+    #[cfg(all(feature = "flate2", feature = "zstd", feature = "lz4"))]
+    #[test]
+    fn read_compressed() {
+        let expected = NbtValue::read(BIGTEST).unwrap();
+        for compression in [
+            Compression::None,
+            Compression::Gzip,
+            Compression::Zlib,
+            Compression::Zstd,
+            Compression::Lz4,
+        ] {
+            let data = compress(compression, BIGTEST).unwrap();
+            let mut reader = NbtReader::decompress(data.as_slice()).unwrap();
+            assert_eq!(reader.get_ref().compression(), compression);
+            assert_eq!(reader.read_value().unwrap(), Some(expected.clone()));
+        }
+    }
+}
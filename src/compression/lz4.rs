@@ -0,0 +1,214 @@
+//! The block stream format written by lz4-java's `LZ4BlockOutputStream`, which Minecraft uses for
+//! region compression type 4
+use alloc::vec::Vec;
+use std::io::{self, ErrorKind, Read, Write};
+use xxhash_rust::xxh32::xxh32;
+
+const MAGIC: &[u8; 8] = b"LZ4Block";
+const HEADER_LEN: usize = MAGIC.len() + 13;
+const METHOD_RAW: u8 = 0x10;
+const METHOD_LZ4: u8 = 0x20;
+const CHECKSUM_SEED: u32 = 0x9747B28C;
+const BLOCK_SIZE: usize = 64 * 1024;
+/// The largest block lz4-java will produce, with a compression level of 15
+const MAX_BLOCK_SIZE: usize = 1 << 25;
+
+fn checksum(data: &[u8]) -> u32 {
+    xxh32(data, CHECKSUM_SEED) & 0x0FFF_FFFF
+}
+
+fn invalid(msg: &'static str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, msg)
+}
+
+/// Decompresses an LZ4 block stream
+#[derive(Debug)]
+pub struct Lz4Decoder<R> {
+    reader: R,
+    compressed: Vec<u8>,
+    block: Vec<u8>,
+    pos: usize,
+    finished: bool,
+}
+
+impl<R: Read> Lz4Decoder<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            compressed: Vec::new(),
+            block: Vec::new(),
+            pos: 0,
+            finished: false,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads the next block into `self.block`, returning false once the end marker is reached
+    fn next_block(&mut self) -> io::Result<bool> {
+        let mut header = [0; HEADER_LEN];
+        self.reader.read_exact(&mut header)?;
+        if !header.starts_with(MAGIC) {
+            return Err(invalid("LZ4 block is missing its magic"));
+        }
+        let token = header[MAGIC.len()];
+        let field = |idx: usize| {
+            let start = MAGIC.len() + 1 + idx * 4;
+            u32::from_le_bytes(header[start..start + 4].try_into().unwrap())
+        };
+        let (compressed_len, len, expected) = (field(0) as usize, field(1) as usize, field(2));
+        if len > MAX_BLOCK_SIZE || compressed_len > MAX_BLOCK_SIZE {
+            return Err(invalid("LZ4 block is too large"));
+        }
+        self.pos = 0;
+        self.block.resize(len, 0);
+        match token & 0xF0 {
+            METHOD_RAW if len == 0 && compressed_len == 0 => return Ok(false),
+            METHOD_RAW if len == compressed_len => self.reader.read_exact(&mut self.block)?,
+            METHOD_LZ4 => {
+                self.compressed.resize(compressed_len, 0);
+                self.reader.read_exact(&mut self.compressed)?;
+                let written = lz4_flex::block::decompress_into(&self.compressed, &mut self.block)
+                    .map_err(|_| invalid("LZ4 block is corrupt"))?;
+                if written != len {
+                    return Err(invalid("LZ4 block has the wrong length"));
+                }
+            }
+            _ => return Err(invalid("LZ4 block uses an unknown compression method")),
+        }
+        if checksum(&self.block) != expected {
+            return Err(invalid("LZ4 block checksum mismatch"));
+        }
+        Ok(true)
+    }
+}
+
+impl<R: Read> Read for Lz4Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.block.len() {
+            if self.finished || !self.next_block()? {
+                self.finished = true;
+                return Ok(0);
+            }
+        }
+        let len = buf.len().min(self.block.len() - self.pos);
+        buf[..len].copy_from_slice(&self.block[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+/// Compresses data into an LZ4 block stream that lz4-java can read
+#[derive(Debug)]
+pub struct Lz4Encoder<W: Write> {
+    writer: W,
+    block: Vec<u8>,
+}
+
+impl<W: Write> Lz4Encoder<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            block: Vec::with_capacity(BLOCK_SIZE),
+        }
+    }
+
+    fn write_block(
+        &mut self,
+        method: u8,
+        data: &[u8],
+        len: usize,
+        checksum: u32,
+    ) -> io::Result<()> {
+        // The level is log2 of the block size, relative to 1KiB
+        let level = (BLOCK_SIZE.trailing_zeros() - 10) as u8;
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(MAGIC);
+        header.push(method | level);
+        header.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        header.extend_from_slice(&(len as u32).to_le_bytes());
+        header.extend_from_slice(&checksum.to_le_bytes());
+        self.writer.write_all(&header)?;
+        self.writer.write_all(data)
+    }
+
+    fn flush_block(&mut self) -> io::Result<()> {
+        if self.block.is_empty() {
+            return Ok(());
+        }
+        let block = core::mem::take(&mut self.block);
+        let compressed = lz4_flex::block::compress(&block);
+        let checksum = checksum(&block);
+        if compressed.len() < block.len() {
+            self.write_block(METHOD_LZ4, &compressed, block.len(), checksum)?;
+        } else {
+            self.write_block(METHOD_RAW, &block, block.len(), checksum)?;
+        }
+        self.block = block;
+        self.block.clear();
+        Ok(())
+    }
+
+    /// Writes out the buffered block and the end marker, returning the underlying writer
+    pub fn finish(mut self) -> io::Result<W> {
+        self.flush_block()?;
+        self.write_block(METHOD_RAW, &[], 0, 0)?;
+        Ok(self.writer)
+    }
+}
+
+impl<W: Write> Write for Lz4Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(BLOCK_SIZE - self.block.len());
+        self.block.extend_from_slice(&buf[..len]);
+        if self.block.len() == BLOCK_SIZE {
+            self.flush_block()?;
+        }
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush_block()?;
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_blocks() {
+        // Spans several blocks, with both compressible and incompressible ones
+        let mut data: Vec<u8> = (0..BLOCK_SIZE * 2).map(|idx| (idx % 7) as u8).collect();
+        let mut state = 1u32;
+        data.extend((0..BLOCK_SIZE).map(|_| {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            (state >> 16) as u8
+        }));
+        let mut encoder = Lz4Encoder::new(Vec::new());
+        encoder.write_all(&data).unwrap();
+        let encoded = encoder.finish().unwrap();
+        assert!(encoded.starts_with(MAGIC));
+
+        let mut decoded = Vec::new();
+        Lz4Decoder::new(encoded.as_slice())
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn checksum_mismatch() {
+        let mut encoder = Lz4Encoder::new(Vec::new());
+        encoder.write_all(b"some data").unwrap();
+        let mut encoded = encoder.finish().unwrap();
+        encoded[HEADER_LEN] ^= 1;
+        let err = Lz4Decoder::new(encoded.as_slice())
+            .read_to_end(&mut Vec::new())
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
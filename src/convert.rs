@@ -0,0 +1,368 @@
+//! Conversions between Rust types and [NbtValue]s that do not require serde
+//!
+//! With the `derive` feature enabled, [FromNbt] and [ToNbt] can be derived for structs, see
+//! [zeronbt_derive](https://docs.rs/zeronbt-derive) for the supported attributes.
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+
+use crate::{
+    NbtTag,
+    error::NbtConvertError,
+    value::{NbtCompound, NbtList, NbtValue},
+};
+
+#[cfg(feature = "derive")]
+pub use zeronbt_derive::{FromNbt, ToNbt};
+
+/// Converts a value into its NBT representation
+///
+/// Implementations must always produce a value with the same tag, as lists of the type could not
+/// be represented otherwise.
+pub trait ToNbt {
+    fn to_nbt(&self) -> NbtValue;
+}
+
+/// Reads a value from its NBT representation
+pub trait FromNbt: Sized {
+    fn from_nbt(value: &NbtValue) -> Result<Self, NbtConvertError>;
+}
+
+/// Element types which can be stored in one of the NBT array types instead of a list
+pub trait NbtArray: Sized {
+    fn to_nbt_array(values: &[Self]) -> NbtValue;
+    /// Reads the elements from either an array or a list of matching elements
+    fn from_nbt_array(value: &NbtValue) -> Result<Vec<Self>, NbtConvertError>;
+}
+
+#[inline]
+fn wrong_tag(expected: NbtTag, value: &NbtValue) -> NbtConvertError {
+    NbtConvertError::WrongTag {
+        expected,
+        found: value.tag(),
+    }
+}
+
+macro_rules! impl_scalar {
+    ($($t:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl ToNbt for $t {
+                #[inline]
+                fn to_nbt(&self) -> NbtValue {
+                    NbtValue::$variant(*self)
+                }
+            }
+            impl FromNbt for $t {
+                #[inline]
+                fn from_nbt(value: &NbtValue) -> Result<Self, NbtConvertError> {
+                    match value {
+                        NbtValue::$variant(val) => Ok(*val),
+                        _ => Err(wrong_tag(NbtTag::$variant, value)),
+                    }
+                }
+            }
+        )*
+    };
+}
+impl_scalar!(
+    i8 => Byte,
+    i16 => Short,
+    i32 => Int,
+    i64 => Long,
+    f32 => Float,
+    f64 => Double,
+);
+
+impl ToNbt for bool {
+    fn to_nbt(&self) -> NbtValue {
+        NbtValue::Byte(*self as i8)
+    }
+}
+
+impl FromNbt for bool {
+    fn from_nbt(value: &NbtValue) -> Result<Self, NbtConvertError> {
+        i8::from_nbt(value).map(|byte| byte != 0)
+    }
+}
+
+impl ToNbt for str {
+    fn to_nbt(&self) -> NbtValue {
+        NbtValue::String(self.into())
+    }
+}
+
+impl ToNbt for String {
+    fn to_nbt(&self) -> NbtValue {
+        NbtValue::String(self.clone())
+    }
+}
+
+impl FromNbt for String {
+    fn from_nbt(value: &NbtValue) -> Result<Self, NbtConvertError> {
+        match value {
+            NbtValue::String(string) => Ok(string.clone()),
+            _ => Err(wrong_tag(NbtTag::String, value)),
+        }
+    }
+}
+
+impl ToNbt for NbtValue {
+    fn to_nbt(&self) -> NbtValue {
+        self.clone()
+    }
+}
+
+impl FromNbt for NbtValue {
+    fn from_nbt(value: &NbtValue) -> Result<Self, NbtConvertError> {
+        Ok(value.clone())
+    }
+}
+
+impl ToNbt for NbtCompound {
+    fn to_nbt(&self) -> NbtValue {
+        NbtValue::Compound(self.clone())
+    }
+}
+
+impl FromNbt for NbtCompound {
+    fn from_nbt(value: &NbtValue) -> Result<Self, NbtConvertError> {
+        value
+            .as_compound()
+            .cloned()
+            .ok_or_else(|| wrong_tag(NbtTag::Compound, value))
+    }
+}
+
+impl ToNbt for NbtList {
+    fn to_nbt(&self) -> NbtValue {
+        NbtValue::List(self.clone())
+    }
+}
+
+impl FromNbt for NbtList {
+    fn from_nbt(value: &NbtValue) -> Result<Self, NbtConvertError> {
+        value
+            .as_list()
+            .cloned()
+            .ok_or_else(|| wrong_tag(NbtTag::List, value))
+    }
+}
+
+impl<T: ToNbt> ToNbt for [T] {
+    /// # Panics
+    /// Panics if the elements convert to values with different tags, which breaks the contract of
+    /// [ToNbt]
+    fn to_nbt(&self) -> NbtValue {
+        let mut list = NbtList::new();
+        for value in self {
+            if let Err(value) = list.push(value.to_nbt()) {
+                panic!(
+                    "Found a {:?} while converting a list of {:?} to NBT",
+                    value.tag(),
+                    list.tag()
+                );
+            }
+        }
+        NbtValue::List(list)
+    }
+}
+
+impl<T: ToNbt> ToNbt for Vec<T> {
+    fn to_nbt(&self) -> NbtValue {
+        self.as_slice().to_nbt()
+    }
+}
+
+impl<T: FromNbt> FromNbt for Vec<T> {
+    fn from_nbt(value: &NbtValue) -> Result<Self, NbtConvertError> {
+        let list = value
+            .as_list()
+            .ok_or_else(|| wrong_tag(NbtTag::List, value))?;
+        list.iter().map(T::from_nbt).collect()
+    }
+}
+
+impl<T: ToNbt, const N: usize> ToNbt for [T; N] {
+    fn to_nbt(&self) -> NbtValue {
+        self.as_slice().to_nbt()
+    }
+}
+
+impl<T: FromNbt, const N: usize> FromNbt for [T; N] {
+    fn from_nbt(value: &NbtValue) -> Result<Self, NbtConvertError> {
+        let values = Vec::<T>::from_nbt(value)?;
+        let found = values.len();
+        values
+            .try_into()
+            .map_err(|_| NbtConvertError::WrongLength { expected: N, found })
+    }
+}
+
+impl<T: ToNbt> ToNbt for BTreeMap<String, T> {
+    fn to_nbt(&self) -> NbtValue {
+        NbtValue::Compound(
+            self.iter()
+                .map(|(key, value)| (key.as_str(), value.to_nbt()))
+                .collect(),
+        )
+    }
+}
+
+impl<T: FromNbt> FromNbt for BTreeMap<String, T> {
+    fn from_nbt(value: &NbtValue) -> Result<Self, NbtConvertError> {
+        let compound = value
+            .as_compound()
+            .ok_or_else(|| wrong_tag(NbtTag::Compound, value))?;
+        compound
+            .iter()
+            .map(|(key, value)| {
+                T::from_nbt(value)
+                    .map(|value| (key.into(), value))
+                    .map_err(|err| NbtConvertError::in_field(key, err))
+            })
+            .collect()
+    }
+}
+
+impl<T: ToNbt + ?Sized> ToNbt for &T {
+    fn to_nbt(&self) -> NbtValue {
+        (**self).to_nbt()
+    }
+}
+
+/// Converts the value stored under `key`, failing if it is missing
+pub(crate) fn field<T: FromNbt>(compound: &NbtCompound, key: &str) -> Result<T, NbtConvertError> {
+    let value = compound
+        .get(key)
+        .ok_or_else(|| NbtConvertError::MissingKey(key.into()))?;
+    T::from_nbt(value).map_err(|err| NbtConvertError::in_field(key, err))
+}
+
+/// Converts the value stored under `key`, returning `default` if it is missing
+pub(crate) fn field_or<T: FromNbt>(
+    compound: &NbtCompound,
+    key: &str,
+    default: T,
+) -> Result<T, NbtConvertError> {
+    match compound.get(key) {
+        Some(_) => field(compound, key),
+        None => Ok(default),
+    }
+}
+
+macro_rules! impl_array {
+    ($($t:ty => $variant:ident, $element:ident);* $(;)?) => {
+        $(impl NbtArray for $t {
+            fn to_nbt_array(values: &[Self]) -> NbtValue {
+                NbtValue::$variant(values.to_vec())
+            }
+            fn from_nbt_array(value: &NbtValue) -> Result<Vec<Self>, NbtConvertError> {
+                match value {
+                    NbtValue::$variant(values) => Ok(values.clone()),
+                    NbtValue::List(list) if list.is_empty() || list.tag() == NbtTag::$element => {
+                        list.iter().map(<$t>::from_nbt).collect()
+                    }
+                    _ => Err(wrong_tag(NbtTag::$variant, value)),
+                }
+            }
+        })*
+    };
+}
+impl_array!(
+    i8 => ByteArray, Byte;
+    i32 => IntArray, Int;
+    i64 => LongArray, Long;
+);
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use super::*;
+    use alloc::{string::ToString, vec};
+
+    #[derive(Debug, PartialEq, ToNbt, FromNbt)]
+    struct Chunk {
+        #[nbt(rename = "xPos")]
+        x: i32,
+        #[nbt(rename = "zPos")]
+        z: i32,
+        #[nbt(rename = "Status", default = "default_status")]
+        status: String,
+        #[nbt(array)]
+        heights: Vec<i64>,
+        sections: Vec<Section>,
+        #[nbt(default)]
+        light_populated: bool,
+        inhabited: Option<i64>,
+    }
+
+    #[derive(Debug, PartialEq, ToNbt, FromNbt)]
+    struct Section {
+        #[nbt(rename = "Y")]
+        y: i8,
+        #[nbt(array)]
+        blocks: Option<Vec<i8>>,
+    }
+
+    fn default_status() -> String {
+        "empty".to_string()
+    }
+
+    #[test]
+    fn derive_round_trip() {
+        let chunk = Chunk {
+            x: 1,
+            z: -2,
+            status: "full".into(),
+            heights: vec![1, 2, 3],
+            sections: vec![
+                Section {
+                    y: 0,
+                    blocks: Some(vec![1, 2]),
+                },
+                Section { y: 1, blocks: None },
+            ],
+            light_populated: true,
+            inhabited: None,
+        };
+        let value = chunk.to_nbt();
+        assert_eq!(value.get("xPos"), Some(&NbtValue::Int(1)));
+        assert_eq!(
+            value.get("heights"),
+            Some(&NbtValue::LongArray(vec![1, 2, 3]))
+        );
+        assert_eq!(value.get("inhabited"), None);
+        let sections = value.get("sections").and_then(NbtValue::as_list).unwrap();
+        assert_eq!(
+            sections[0].get("blocks"),
+            Some(&NbtValue::ByteArray(vec![1, 2]))
+        );
+        assert_eq!(sections[1].get("blocks"), None);
+        assert_eq!(Chunk::from_nbt(&value), Ok(chunk));
+    }
+
+    #[test]
+    fn derive_defaults_and_errors() {
+        let mut compound: NbtCompound = [("xPos", 3), ("zPos", 4)].into_iter().collect();
+        compound.insert("heights", NbtList::new());
+        compound.insert("sections", NbtList::new());
+        let chunk = Chunk::from_nbt(&compound.clone().into()).unwrap();
+        assert_eq!(chunk.status, "empty");
+        assert!(!chunk.light_populated);
+        assert_eq!(chunk.inhabited, None);
+
+        compound.insert("zPos", 4i64);
+        assert_eq!(
+            Chunk::from_nbt(&compound.clone().into()),
+            Err(NbtConvertError::in_field(
+                "zPos",
+                NbtConvertError::WrongTag {
+                    expected: NbtTag::Int,
+                    found: NbtTag::Long
+                }
+            ))
+        );
+        compound.remove("zPos");
+        assert_eq!(
+            Chunk::from_nbt(&compound.into()),
+            Err(NbtConvertError::MissingKey("zPos".into()))
+        );
+    }
+}
@@ -0,0 +1,360 @@
+//! Exporting compounds found while parsing as CSV rows
+//!
+//! A [CsvExporter] is given the path of the values that make up the rows, usually the elements of
+//! a list of compounds like `Level.TileEntities[]`, and a path for each column relative to a row.
+//! Rows are written as soon as their value is complete, so only one row is held in memory at a
+//! time.
+//!
+//! Numbers are written as they are, strings are quoted where CSV requires it, and lists, arrays and
+//! compounds are written as SNBT. Columns whose path selects nothing are left empty.
+//!
+//! ```
+//! # use zeronbt::{CompleteFsm, csv::CsvExporter};
+//! let data = include_bytes!("../assets/bigtest.nbt");
+//! let mut exporter = CsvExporter::new(String::new(), "\"listTest (compound)\"[]".parse().unwrap())
+//!     .column("name", "name".parse().unwrap())
+//!     .column("created", "created-on".parse().unwrap());
+//! for fragment in CompleteFsm::new(data) {
+//!     exporter.push(fragment.unwrap()).unwrap();
+//! }
+//! assert_eq!(
+//!     exporter.into_inner(),
+//!     "name,created\nCompound tag #0,1264099775885\nCompound tag #1,1264099775885\n"
+//! );
+//! ```
+use alloc::{string::String, vec::Vec};
+use core::fmt::{self, Write};
+
+use crate::{
+    FsmResult, NbtFragment, NbtFsm,
+    path::{NbtPath, PathSegment},
+    snbt::SnbtWriter,
+    text::{Emit, Number, Seq, Structure},
+    value::{NbtCompound, NbtList, NbtValue},
+};
+
+/// Writes the rows selected by a path as CSV, see the [module docs](self)
+///
+/// The row path may contain keys, indices and `[]`, but no negative indices, as the length of a
+/// list is not known while its elements are being parsed.
+#[derive(Debug, Clone)]
+pub struct CsvExporter<W> {
+    structure: Structure,
+    rows: Rows<W>,
+}
+
+#[derive(Debug, Clone)]
+struct Rows<W> {
+    out: W,
+    selector: NbtPath,
+    columns: Vec<(String, NbtPath)>,
+    header_written: bool,
+    /// Whether each open value is a compound, or a list or array with the index of its next
+    /// element
+    stack: Vec<Option<usize>>,
+    path: Vec<PathSegment>,
+    /// The row being assembled
+    row: Option<Assembler>,
+}
+
+impl<W: Write> CsvExporter<W> {
+    pub fn new(out: W, rows: NbtPath) -> Self {
+        Self {
+            structure: Structure::default(),
+            rows: Rows {
+                out,
+                selector: rows,
+                columns: Vec::new(),
+                header_written: false,
+                stack: Vec::new(),
+                path: Vec::new(),
+                row: None,
+            },
+        }
+    }
+
+    /// Adds a column filled from `path`, relative to each row
+    pub fn column(mut self, name: impl Into<String>, path: NbtPath) -> Self {
+        self.rows.columns.push((name.into(), path));
+        self
+    }
+
+    /// Parses the next fragment, returning true once the root tag is complete
+    ///
+    /// The header is written with the first fragment, and rows as soon as they are complete.
+    pub fn push(&mut self, fragment: NbtFragment<'_>) -> Result<bool, fmt::Error> {
+        self.rows.header()?;
+        self.structure.push(fragment, &mut self.rows)
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.rows.out
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.rows.out
+    }
+
+    pub fn into_inner(self) -> W {
+        self.rows.out
+    }
+}
+
+impl<W: Write> Rows<W> {
+    fn header(&mut self) -> fmt::Result {
+        if self.header_written {
+            return Ok(());
+        }
+        self.header_written = true;
+        for (index, (name, _)) in self.columns.iter().enumerate() {
+            if index > 0 {
+                self.out.write_char(',')?;
+            }
+            write_field(&mut self.out, name)?;
+        }
+        self.out.write_char('\n')
+    }
+
+    /// Starts a value, which begins a row if the path selects it
+    fn begin(&mut self) {
+        if let Some(Some(next)) = self.stack.last_mut() {
+            self.path.push(PathSegment::Index(*next as i32));
+            *next += 1;
+        }
+        if self.row.is_none() && self.selected() {
+            self.row = Some(Assembler::default());
+        }
+    }
+
+    /// Ends a value, leaving its path
+    fn end(&mut self) {
+        if !self.stack.is_empty() {
+            self.path.pop();
+        }
+    }
+
+    fn selected(&self) -> bool {
+        let selector = self.selector.segments();
+        selector.len() == self.path.len()
+            && selector.iter().zip(&self.path).all(|(selector, segment)| {
+                match (selector, segment) {
+                    (PathSegment::All, PathSegment::Index(_)) => true,
+                    _ => selector == segment,
+                }
+            })
+    }
+
+    /// Passes an event to the row being assembled, writing it once it is complete
+    fn assemble(&mut self, event: impl FnOnce(&mut Assembler) -> Option<NbtValue>) -> fmt::Result {
+        let Some(row) = &mut self.row else {
+            return Ok(());
+        };
+        let Some(value) = event(row) else {
+            return Ok(());
+        };
+        self.row = None;
+        for (index, (_, path)) in self.columns.iter().enumerate() {
+            if index > 0 {
+                self.out.write_char(',')?;
+            }
+            if let Some(cell) = path.select(&value).first() {
+                write_cell(&mut self.out, cell)?;
+            }
+        }
+        self.out.write_char('\n')
+    }
+}
+
+impl<W: Write> Emit for Rows<W> {
+    fn begin_compound(&mut self) -> fmt::Result {
+        self.begin();
+        self.stack.push(None);
+        self.assemble(Assembler::begin_compound)
+    }
+
+    fn key(&mut self, key: &str) -> fmt::Result {
+        self.path.push(PathSegment::Key(key.into()));
+        self.assemble(|row| row.key(key))
+    }
+
+    fn end_compound(&mut self) -> fmt::Result {
+        self.stack.pop();
+        self.end();
+        self.assemble(Assembler::end)
+    }
+
+    fn begin_seq(&mut self, seq: Seq) -> fmt::Result {
+        self.begin();
+        self.stack.push(Some(0));
+        self.assemble(|row| row.begin_seq(seq))
+    }
+
+    fn end_seq(&mut self) -> fmt::Result {
+        self.stack.pop();
+        self.end();
+        self.assemble(Assembler::end)
+    }
+
+    fn number(&mut self, number: Number) -> fmt::Result {
+        self.begin();
+        self.end();
+        self.assemble(|row| row.number(number))
+    }
+
+    fn string(&mut self, string: &str) -> fmt::Result {
+        self.begin();
+        self.end();
+        self.assemble(|row| row.value(NbtValue::String(string.into())))
+    }
+}
+
+/// Builds a value from the events of its structure
+#[derive(Debug, Clone, Default)]
+struct Assembler {
+    stack: Vec<Partial>,
+}
+
+#[derive(Debug, Clone)]
+enum Partial {
+    /// A compound, along with the key of the entry being assembled
+    Compound(NbtCompound, Option<String>),
+    List(Vec<NbtValue>),
+    ByteArray(Vec<i8>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Assembler {
+    fn begin_compound(&mut self) -> Option<NbtValue> {
+        self.stack.push(Partial::Compound(NbtCompound::new(), None));
+        None
+    }
+
+    fn key(&mut self, key: &str) -> Option<NbtValue> {
+        if let Some(Partial::Compound(_, next)) = self.stack.last_mut() {
+            *next = Some(key.into());
+        }
+        None
+    }
+
+    fn begin_seq(&mut self, seq: Seq) -> Option<NbtValue> {
+        self.stack.push(match seq {
+            Seq::List(_) => Partial::List(Vec::new()),
+            Seq::ByteArray => Partial::ByteArray(Vec::new()),
+            Seq::IntArray => Partial::IntArray(Vec::new()),
+            Seq::LongArray => Partial::LongArray(Vec::new()),
+        });
+        None
+    }
+
+    fn end(&mut self) -> Option<NbtValue> {
+        let value = match self.stack.pop()? {
+            Partial::Compound(compound, _) => NbtValue::Compound(compound),
+            // The elements of a parsed list always share a tag
+            Partial::List(values) => NbtValue::List(NbtList::try_from(values).unwrap_or_default()),
+            Partial::ByteArray(values) => NbtValue::ByteArray(values),
+            Partial::IntArray(values) => NbtValue::IntArray(values),
+            Partial::LongArray(values) => NbtValue::LongArray(values),
+        };
+        self.value(value)
+    }
+
+    fn number(&mut self, number: Number) -> Option<NbtValue> {
+        match (self.stack.last_mut(), number) {
+            (Some(Partial::ByteArray(values)), Number::Byte(value)) => values.push(value),
+            (Some(Partial::IntArray(values)), Number::Int(value)) => values.push(value),
+            (Some(Partial::LongArray(values)), Number::Long(value)) => values.push(value),
+            (_, number) => {
+                return self.value(match number {
+                    Number::Byte(value) => NbtValue::Byte(value),
+                    Number::Short(value) => NbtValue::Short(value),
+                    Number::Int(value) => NbtValue::Int(value),
+                    Number::Long(value) => NbtValue::Long(value),
+                    Number::Float(value) => NbtValue::Float(value),
+                    Number::Double(value) => NbtValue::Double(value),
+                });
+            }
+        }
+        None
+    }
+
+    /// Adds a complete value to the innermost container, returning it if it is the whole value
+    fn value(&mut self, value: NbtValue) -> Option<NbtValue> {
+        match self.stack.last_mut() {
+            None => return Some(value),
+            Some(Partial::Compound(compound, key)) => {
+                compound.insert(key.take().unwrap_or_default(), value);
+            }
+            Some(Partial::List(values)) => values.push(value),
+            // Arrays only hold numbers, which are handled by Assembler::number
+            Some(_) => {}
+        }
+        None
+    }
+}
+
+fn write_cell(out: &mut impl Write, value: &NbtValue) -> fmt::Result {
+    match value {
+        NbtValue::Byte(value) => write!(out, "{value}"),
+        NbtValue::Short(value) => write!(out, "{value}"),
+        NbtValue::Int(value) => write!(out, "{value}"),
+        NbtValue::Long(value) => write!(out, "{value}"),
+        NbtValue::Float(value) => write!(out, "{value}"),
+        NbtValue::Double(value) => write!(out, "{value}"),
+        NbtValue::String(string) => write_field(out, string),
+        value => {
+            let data = value.to_bytes("").map_err(|_| fmt::Error)?;
+            let mut snbt = SnbtWriter::new(String::new());
+            let mut fsm = NbtFsm::new().with_data(&data);
+            while let Ok(FsmResult::Found(fragment)) = fsm.next_fragment() {
+                snbt.push(fragment)?;
+            }
+            write_field(out, &snbt.into_inner())
+        }
+    }
+}
+
+/// Writes a field, quoting it if it contains a separator, quote or line break
+fn write_field(out: &mut impl Write, field: &str) -> fmt::Result {
+    if !field.contains([',', '"', '\n', '\r']) {
+        return out.write_str(field);
+    }
+    out.write_char('"')?;
+    for char in field.chars() {
+        if char == '"' {
+            out.write_char('"')?;
+        }
+        out.write_char(char)?;
+    }
+    out.write_char('"')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CompleteFsm;
+
+    #[test]
+    fn export() {
+        let data = include_bytes!("../assets/bigtest.nbt");
+        let mut exporter = CsvExporter::new(String::new(), NbtPath::root())
+            .column("short", "shortTest".parse().unwrap())
+            .column("egg", "\"nested compound test\".egg".parse().unwrap())
+            .column("longs", "\"listTest (long)\"".parse().unwrap())
+            .column(
+                "first name",
+                "\"listTest (compound)\"[0].name".parse().unwrap(),
+            )
+            .column("missing", "missing".parse().unwrap());
+        let mut done = false;
+        for fragment in CompleteFsm::new(data) {
+            done = exporter.push(fragment.unwrap()).unwrap();
+        }
+        assert!(done);
+        assert_eq!(
+            exporter.into_inner(),
+            "short,egg,longs,first name,missing\n\
+             32767,\"{name:\"\"Eggbert\"\",value:0.5f}\",\"[11L,12L,13L,14L,15L]\",Compound tag #0,\n"
+        );
+    }
+}
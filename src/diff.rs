@@ -0,0 +1,167 @@
+//! Structural comparison of values
+//!
+//! Compounds are matched by key, ignoring the order of their entries, and list elements by index.
+//! Floats are compared by their bits, so NaNs equal themselves while `0.0` and `-0.0` differ.
+//! Paths are written in the syntax of [NbtPath](crate::path::NbtPath).
+use alloc::{format, string::String, vec::Vec};
+use core::fmt;
+
+use crate::{
+    path::push_key,
+    value::{NbtCompound, NbtValue},
+};
+
+/// A place where two values differ
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference {
+    /// The location of the difference, e.g. `Level.Sections[3].Palette`, empty for the root
+    pub path: String,
+    pub kind: DiffKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffKind {
+    /// A compound entry or list element only present in the left value
+    OnlyLeft(NbtValue),
+    /// A compound entry or list element only present in the right value
+    OnlyRight(NbtValue),
+    /// Values of different tags, or scalars and arrays with different contents
+    Changed { left: NbtValue, right: NbtValue },
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "<root>"
+        } else {
+            &self.path
+        };
+        match &self.kind {
+            DiffKind::OnlyLeft(value) => write!(f, "- {path}: {value:?}"),
+            DiffKind::OnlyRight(value) => write!(f, "+ {path}: {value:?}"),
+            DiffKind::Changed { left, right } => write!(f, "~ {path}: {left:?} != {right:?}"),
+        }
+    }
+}
+
+/// Lists every place where `left` and `right` differ, returning nothing if they are equal
+pub fn diff(left: &NbtValue, right: &NbtValue) -> Vec<Difference> {
+    let mut out = Vec::new();
+    diff_at(&mut String::new(), left, right, &mut out);
+    out
+}
+
+fn diff_at(path: &mut String, left: &NbtValue, right: &NbtValue, out: &mut Vec<Difference>) {
+    let changed = match (left, right) {
+        (NbtValue::Compound(left), NbtValue::Compound(right)) => {
+            return diff_compounds(path, left, right, out);
+        }
+        (NbtValue::List(left), NbtValue::List(right)) if left.tag() == right.tag() => {
+            for index in 0..left.len().max(right.len()) {
+                let len = path.len();
+                path.push_str(&format!("[{index}]"));
+                match (left.get(index), right.get(index)) {
+                    (Some(left), Some(right)) => diff_at(path, left, right, out),
+                    (Some(left), None) => push(path, DiffKind::OnlyLeft(left.clone()), out),
+                    (None, Some(right)) => push(path, DiffKind::OnlyRight(right.clone()), out),
+                    (None, None) => unreachable!(),
+                }
+                path.truncate(len);
+            }
+            return;
+        }
+        (NbtValue::Float(left), NbtValue::Float(right)) => left.to_bits() != right.to_bits(),
+        (NbtValue::Double(left), NbtValue::Double(right)) => left.to_bits() != right.to_bits(),
+        (left, right) => left != right,
+    };
+    if changed {
+        let kind = DiffKind::Changed {
+            left: left.clone(),
+            right: right.clone(),
+        };
+        push(path, kind, out);
+    }
+}
+
+fn diff_compounds(
+    path: &mut String,
+    left: &NbtCompound,
+    right: &NbtCompound,
+    out: &mut Vec<Difference>,
+) {
+    let len = path.len();
+    for (key, value) in left.iter() {
+        push_key(path, key);
+        match right.get(key) {
+            Some(other) => diff_at(path, value, other, out),
+            None => push(path, DiffKind::OnlyLeft(value.clone()), out),
+        }
+        path.truncate(len);
+    }
+    for (key, value) in right.iter() {
+        if !left.contains_key(key) {
+            push_key(path, key);
+            push(path, DiffKind::OnlyRight(value.clone()), out);
+            path.truncate(len);
+        }
+    }
+}
+
+fn push(path: &str, kind: DiffKind, out: &mut Vec<Difference>) {
+    out.push(Difference {
+        path: path.into(),
+        kind,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::NbtList;
+    use alloc::{string::ToString, vec};
+
+    #[test]
+    fn differences() {
+        let data = include_bytes!("../assets/bigtest.nbt");
+        let (_, value) = NbtValue::read(data).unwrap();
+        assert!(diff(&value, &value).is_empty());
+
+        let reversed = NbtValue::Compound(
+            value
+                .as_compound()
+                .unwrap()
+                .iter()
+                .rev()
+                .map(|(key, value)| (key, value.clone()))
+                .collect(),
+        );
+        assert!(diff(&value, &reversed).is_empty());
+
+        let mut changed = value.as_compound().unwrap().clone();
+        changed.remove("intTest");
+        changed.insert("new key", 1i8);
+        changed.insert("shortTest", 1i32);
+        let list = NbtList::try_from(vec![NbtValue::Long(11)]).unwrap();
+        changed.insert("listTest (long)", list);
+        let lines: Vec<_> = diff(&value, &NbtValue::Compound(changed))
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "~ shortTest: Short(32767) != Int(1)",
+                "- intTest: Int(2147483647)",
+                "- \"listTest (long)\"[1]: Long(12)",
+                "- \"listTest (long)\"[2]: Long(13)",
+                "- \"listTest (long)\"[3]: Long(14)",
+                "- \"listTest (long)\"[4]: Long(15)",
+                "+ \"new key\": Byte(1)",
+            ]
+        );
+
+        let nan = NbtValue::Float(f32::NAN);
+        assert!(diff(&nan, &nan).is_empty());
+        assert_eq!(diff(&NbtValue::Float(0.0), &NbtValue::Float(-0.0)).len(), 1);
+    }
+}
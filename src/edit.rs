@@ -0,0 +1,549 @@
+//! One-pass structural editing of documents too big to load as a whole
+//!
+//! An [NbtEditor] passes the fragments pushed to it on to an [NbtWriter], except for the values
+//! selected by the paths of its callbacks. Those are assembled into [NbtValue]s and handed to the
+//! callback, which decides whether to keep, replace or remove them. Entries can be inserted into
+//! compounds as well.
+//!
+//! ```
+//! # use zeronbt::{CompleteFsm, edit::{Edit, NbtEditor}, value::NbtValue};
+//! let data = include_bytes!("../assets/bigtest.nbt");
+//! let mut editor = NbtEditor::new()
+//!     .on("intTest".parse().unwrap(), |_, _| Edit::Replace(NbtValue::Int(1)))
+//!     .on("\"listTest (compound)\"[0]".parse().unwrap(), |_, _| Edit::Remove)
+//!     .insert("".parse().unwrap(), "added", NbtValue::from("new"));
+//! for fragment in CompleteFsm::new(data) {
+//!     editor.push(fragment.unwrap()).unwrap();
+//! }
+//! let (_, edited) = NbtValue::read(&editor.into_inner()).unwrap();
+//! assert_eq!(edited.get("intTest"), Some(&NbtValue::Int(1)));
+//! assert_eq!(edited.get("added").and_then(NbtValue::as_str), Some("new"));
+//! ```
+use alloc::{boxed::Box, string::String, vec::Vec};
+
+use crate::{
+    NbtFragment, NbtWriter,
+    error::{EditError, NbtParseError},
+    mutf8,
+    path::{NbtPath, PathSegment, resolve},
+    value::{NbtValue, NbtValueBuilder},
+};
+
+/// What a callback of an [NbtEditor] does with the value it is given
+#[derive(Debug, Clone, PartialEq)]
+pub enum Edit {
+    /// Writes the value unchanged
+    Keep,
+    /// Writes another value in its place, which has to match the type of the list for list
+    /// elements
+    Replace(NbtValue),
+    /// Leaves the value out. Removing the root leaves out the whole document.
+    Remove,
+}
+
+type Callback<'e> = Box<dyn FnMut(&NbtPath, &NbtValue) -> Edit + 'e>;
+
+/// Rewrites documents while they are being parsed, see the [module docs](self)
+///
+/// Callbacks are run for the values their path selects, the first matching callback in the order
+/// they were added takes the value. Callbacks are not run for values inside a value that another
+/// callback took, and only compound entries and the elements of lists of strings, lists and
+/// compounds can be selected, not the elements of numeric lists or arrays.
+pub struct NbtEditor<'e> {
+    callbacks: Vec<(NbtPath, Callback<'e>)>,
+    inserts: Vec<(NbtPath, String, NbtValue)>,
+    writer: NbtWriter,
+    /// The path of the innermost open compound or list, with the length of the list for each
+    /// index
+    path: Vec<(PathSegment, usize)>,
+    stack: Vec<Level>,
+    /// The frames of the name of the next entry
+    name: Vec<u8>,
+    /// Compounds are announced before their name, which decides where the compound is sent
+    compound_pending: bool,
+    /// The path segment of the value whose name has been passed on, None for the root
+    value_next: Option<Option<(PathSegment, usize)>>,
+    capture: Option<Capture>,
+    done: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Level {
+    /// A compound or a list of strings, lists or compounds, which are part of the path unless they
+    /// are the root
+    Compound {
+        root: bool,
+    },
+    List {
+        len: usize,
+        next: usize,
+        root: bool,
+    },
+    /// A numeric list or array, along with the number of elements still to come
+    Frames(usize),
+    /// A string or byte array, which ends with an empty frame
+    Text,
+}
+
+/// A value selected by a callback, or an entry replaced by an inserted one, that is assembled
+/// before deciding what to write
+struct Capture {
+    builder: NbtValueBuilder,
+    /// The callback that takes the value, None if it is dropped
+    callback: Option<usize>,
+    path: NbtPath,
+    element: bool,
+}
+
+impl<'e> NbtEditor<'e> {
+    pub fn new() -> Self {
+        Self {
+            callbacks: Vec::new(),
+            inserts: Vec::new(),
+            writer: NbtWriter::new(),
+            path: Vec::new(),
+            stack: Vec::new(),
+            name: Vec::new(),
+            compound_pending: false,
+            value_next: None,
+            capture: None,
+            done: false,
+        }
+    }
+
+    /// Runs `callback` for every value `path` selects, with the path of the value and the value
+    pub fn on(
+        mut self,
+        path: NbtPath,
+        callback: impl FnMut(&NbtPath, &NbtValue) -> Edit + 'e,
+    ) -> Self {
+        self.callbacks.push((path, Box::new(callback)));
+        self
+    }
+
+    /// Adds an entry to every compound `path` selects, after its other entries
+    ///
+    /// Entries with the same key are left out of those compounds.
+    pub fn insert(mut self, path: NbtPath, key: impl Into<String>, value: NbtValue) -> Self {
+        self.inserts.push((path, key.into(), value));
+        self
+    }
+
+    /// Edits the next fragment, returning true once the root tag is complete
+    ///
+    /// Fragments of the next document may be pushed after that, it is written right after the
+    /// previous one.
+    pub fn push(&mut self, fragment: NbtFragment<'_>) -> Result<bool, EditError> {
+        if self.done {
+            self.done = false;
+            self.name.clear();
+        }
+        if let Some(capture) = &mut self.capture {
+            if let Some((name, value)) = capture.builder.push(fragment)? {
+                self.apply(&name, value)?;
+            }
+            return Ok(self.done);
+        }
+        if let Some(next) = self.value_next.take() {
+            self.start_value(next, fragment)?;
+            return Ok(self.done);
+        }
+        match (self.stack.last().copied(), fragment) {
+            (Some(Level::List { len, next, .. }), fragment) => {
+                let segment = (PathSegment::Index(next as i32), len);
+                if let Some(callback) = self.callback(Some(&segment)) {
+                    let mut builder = NbtValueBuilder::new();
+                    if fragment == NbtFragment::CompoundTag {
+                        builder.push(NbtFragment::CompoundTag)?;
+                    }
+                    builder.push(NbtFragment::NameFrame(&[]))?;
+                    let path = self.full_path(Some(&segment));
+                    self.capture = Some(Capture {
+                        builder,
+                        callback: Some(callback),
+                        path,
+                        element: true,
+                    });
+                    if fragment != NbtFragment::CompoundTag {
+                        return self.push(fragment);
+                    }
+                    return Ok(self.done);
+                }
+                self.start_value(Some(segment), fragment)?;
+            }
+            (Some(Level::Frames(remaining)), fragment) => {
+                let len = match &fragment {
+                    NbtFragment::ByteListFrame(values) => values.len(),
+                    NbtFragment::ShortListFrame(values) => values.len(),
+                    NbtFragment::IntListFrame(values) => values.len(),
+                    NbtFragment::LongListFrame(values) => values.len(),
+                    NbtFragment::FloatListFrame(values) => values.len(),
+                    NbtFragment::DoubleListFrame(values) => values.len(),
+                    _ => return Err(NbtParseError::UnexpectedFragment.into()),
+                };
+                self.writer.push(fragment)?;
+                let remaining = remaining.saturating_sub(len);
+                match remaining {
+                    0 => self.end_level(),
+                    _ => *self.stack.last_mut().unwrap() = Level::Frames(remaining),
+                }
+            }
+            (Some(Level::Text), fragment) => {
+                let end = matches!(
+                    fragment,
+                    NbtFragment::StringFrame([]) | NbtFragment::ByteArrayFrame([])
+                );
+                self.writer.push(fragment)?;
+                if end {
+                    self.end_level();
+                }
+            }
+            (_, NbtFragment::CompoundTag) => self.compound_pending = true,
+            (_, NbtFragment::NameFrame(data)) if !data.is_empty() => {
+                self.name.extend_from_slice(data)
+            }
+            (_, NbtFragment::NameFrame(_)) => self.named()?,
+            (Some(Level::Compound { .. }), NbtFragment::End) => {
+                for (path, key, value) in &self.inserts {
+                    if matches(path.segments(), &self.path) {
+                        self.writer.push_value(key, value)?;
+                    }
+                }
+                self.writer.push(NbtFragment::End)?;
+                self.end_level();
+            }
+            _ => return Err(NbtParseError::UnexpectedFragment.into()),
+        }
+        Ok(self.done)
+    }
+
+    pub fn get_ref(&self) -> &Vec<u8> {
+        self.writer.get_ref()
+    }
+
+    /// The output written so far, see [NbtWriter::get_mut]
+    pub fn get_mut(&mut self) -> &mut Vec<u8> {
+        self.writer.get_mut()
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.writer.into_inner()
+    }
+
+    /// Decides where the entry or root whose name is complete goes
+    fn named(&mut self) -> Result<(), EditError> {
+        let segment = match self.stack.is_empty() {
+            true => None,
+            false => {
+                let name = mutf8::decode(&self.name).ok_or(NbtParseError::InvalidString)?;
+                Some((PathSegment::Key(name.into_owned()), 0))
+            }
+        };
+        let callback = self.callback(segment.as_ref());
+        let replaced = match &segment {
+            Some((PathSegment::Key(key), _)) => self
+                .inserts
+                .iter()
+                .any(|(path, inserted, _)| inserted == key && matches(path.segments(), &self.path)),
+            _ => false,
+        };
+        let compound = core::mem::take(&mut self.compound_pending);
+        if callback.is_some() || replaced {
+            let mut builder = NbtValueBuilder::new();
+            if compound {
+                builder.push(NbtFragment::CompoundTag)?;
+            }
+            builder.push(NbtFragment::NameFrame(&self.name))?;
+            builder.push(NbtFragment::NameFrame(&[]))?;
+            self.capture = Some(Capture {
+                builder,
+                // Inserted entries take the place of existing ones
+                callback: callback.filter(|_| !replaced),
+                path: self.full_path(segment.as_ref()),
+                element: false,
+            });
+            self.name.clear();
+            return Ok(());
+        }
+        if compound {
+            self.writer.push(NbtFragment::CompoundTag)?;
+        }
+        if !self.name.is_empty() {
+            self.writer.push(NbtFragment::NameFrame(&self.name))?;
+            self.name.clear();
+        }
+        self.writer.push(NbtFragment::NameFrame(&[]))?;
+        match compound {
+            true => self.enter(segment, Level::Compound { root: false }),
+            false => self.value_next = Some(segment),
+        }
+        Ok(())
+    }
+
+    /// Passes on the first fragment of a value that no callback takes
+    fn start_value(
+        &mut self,
+        segment: Option<(PathSegment, usize)>,
+        fragment: NbtFragment<'_>,
+    ) -> Result<(), EditError> {
+        let level = match fragment {
+            NbtFragment::CompoundTag => Some(Level::Compound { root: false }),
+            NbtFragment::ListTag(tag, len) if len > 0 && tag.is_numeric() => {
+                Some(Level::Frames(len))
+            }
+            NbtFragment::ListTag(_, len) if len > 0 => Some(Level::List {
+                len,
+                next: 0,
+                root: false,
+            }),
+            NbtFragment::IntArrayTag(len) | NbtFragment::LongArrayTag(len) if len > 0 => {
+                Some(Level::Frames(len))
+            }
+            NbtFragment::StringFrame(data) | NbtFragment::ByteArrayFrame(data)
+                if !data.is_empty() =>
+            {
+                Some(Level::Text)
+            }
+            NbtFragment::End | NbtFragment::NameFrame(_) => {
+                return Err(NbtParseError::UnexpectedFragment.into());
+            }
+            _ => None,
+        };
+        self.writer.push(fragment)?;
+        match level {
+            Some(level) => self.enter(segment, level),
+            None => self.complete(),
+        }
+        Ok(())
+    }
+
+    /// Opens a level, adding compounds and lists to the path
+    fn enter(&mut self, segment: Option<(PathSegment, usize)>, level: Level) {
+        let level = match (level, &segment) {
+            (Level::Compound { .. }, None) => Level::Compound { root: true },
+            (Level::List { len, next, .. }, None) => Level::List {
+                len,
+                next,
+                root: true,
+            },
+            _ => level,
+        };
+        if let (Level::Compound { .. } | Level::List { .. }, Some(segment)) = (level, segment) {
+            self.path.push(segment);
+        }
+        self.stack.push(level);
+    }
+
+    fn end_level(&mut self) {
+        if let Some(Level::Compound { root: false } | Level::List { root: false, .. }) =
+            self.stack.pop()
+        {
+            self.path.pop();
+        }
+        self.complete();
+    }
+
+    /// Called once a value has been passed on or written completely, counting it towards the
+    /// enclosing list
+    fn complete(&mut self) {
+        match self.stack.last_mut() {
+            Some(Level::List { len, next, .. }) => {
+                *next += 1;
+                if next == len {
+                    self.end_level();
+                }
+            }
+            Some(_) => {}
+            None => self.done = true,
+        }
+    }
+
+    /// Writes what the callback makes of a captured value
+    fn apply(&mut self, name: &str, value: NbtValue) -> Result<(), EditError> {
+        let capture = self.capture.take().expect("a value was being captured");
+        let edit = match capture.callback {
+            Some(callback) => (self.callbacks[callback].1)(&capture.path, &value),
+            None => Edit::Remove,
+        };
+        match edit {
+            Edit::Keep => {
+                self.writer.push_value(name, &value)?;
+            }
+            Edit::Replace(value) => {
+                self.writer.push_value(name, &value)?;
+            }
+            Edit::Remove if capture.element => {
+                self.writer.remove_element();
+            }
+            Edit::Remove => {}
+        }
+        self.complete();
+        Ok(())
+    }
+
+    /// The first callback selecting the value at `segment` in the innermost open level, or the
+    /// root if there is none
+    fn callback(&self, segment: Option<&(PathSegment, usize)>) -> Option<usize> {
+        self.callbacks.iter().position(|(path, _)| {
+            let segments = path.segments();
+            match segment {
+                Some(segment) => {
+                    segments.len() == self.path.len() + 1
+                        && matches(&segments[..self.path.len()], &self.path)
+                        && matches(&segments[self.path.len()..], core::slice::from_ref(segment))
+                }
+                None => segments.is_empty(),
+            }
+        })
+    }
+
+    fn full_path(&self, segment: Option<&(PathSegment, usize)>) -> NbtPath {
+        let mut path = NbtPath::root();
+        for (segment, _) in self.path.iter().chain(segment) {
+            path.push(segment.clone());
+        }
+        path
+    }
+}
+
+impl Default for NbtEditor<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether the segments of a path select the concrete path of a value, whose indices are
+/// stored with the length of their list
+fn matches(selector: &[PathSegment], path: &[(PathSegment, usize)]) -> bool {
+    selector.len() == path.len()
+        && selector
+            .iter()
+            .zip(path)
+            .all(|(selector, (segment, len))| match (selector, segment) {
+                (PathSegment::All, PathSegment::Index(_)) => true,
+                (&PathSegment::Index(selector), &PathSegment::Index(index)) => {
+                    resolve(selector, *len) == Some(index as usize)
+                }
+                _ => selector == segment,
+            })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        CompleteFsm, NbtTag,
+        error::NbtWriteError,
+        value::{NbtCompound, NbtList},
+    };
+    use alloc::vec;
+
+    fn edit(editor: &mut NbtEditor<'_>, data: &[u8]) -> NbtValue {
+        for fragment in CompleteFsm::new(data) {
+            editor.push(fragment.unwrap()).unwrap();
+        }
+        let out = core::mem::take(editor.get_mut());
+        NbtValue::read(&out).unwrap().1
+    }
+
+    #[test]
+    fn unchanged() {
+        let data = include_bytes!("../assets/bigtest.nbt");
+        let mut seen = 0;
+        let mut editor =
+            NbtEditor::new().on("\"nested compound test\".egg".parse().unwrap(), |_, _| {
+                seen += 1;
+                Edit::Keep
+            });
+        for fragment in CompleteFsm::new(data) {
+            editor.push(fragment.unwrap()).unwrap();
+        }
+        assert_eq!(editor.into_inner(), data);
+        assert_eq!(seen, 1);
+    }
+
+    #[test]
+    fn edits() {
+        let data = include_bytes!("../assets/bigtest.nbt");
+        let (_, mut expected) = NbtValue::read(data).unwrap();
+        let mut paths = Vec::new();
+        let mut editor = NbtEditor::new()
+            .on("\"listTest (compound)\"[-1]".parse().unwrap(), |path, _| {
+                paths.push(path.clone());
+                Edit::Remove
+            })
+            .on(
+                "\"listTest (compound)\"[].name".parse().unwrap(),
+                |_, value| {
+                    let name = value.as_str().unwrap();
+                    Edit::Replace(NbtValue::from(name.to_uppercase()))
+                },
+            )
+            .on("byteTest".parse().unwrap(), |_, _| Edit::Remove)
+            .insert(
+                "\"nested compound test\".ham".parse().unwrap(),
+                "value",
+                NbtValue::Int(7),
+            );
+        let edited = edit(&mut editor, data);
+        drop(editor);
+        assert_eq!(paths, ["\"listTest (compound)\"[1]".parse().unwrap()]);
+
+        let NbtValue::Compound(root) = &mut expected else {
+            unreachable!()
+        };
+        root.remove("byteTest");
+        let NbtValue::List(list) = root.get_mut("listTest (compound)").unwrap() else {
+            unreachable!()
+        };
+        let mut first = list.iter().next().unwrap().clone();
+        if let NbtValue::Compound(first) = &mut first {
+            first.insert("name", "COMPOUND TAG #0");
+        }
+        *list = NbtList::try_from(vec![first]).unwrap();
+        let Some(NbtValue::Compound(ham)) =
+            root.get_mut("nested compound test")
+                .and_then(|nested| match nested {
+                    NbtValue::Compound(nested) => nested.get_mut("ham"),
+                    _ => None,
+                })
+        else {
+            unreachable!()
+        };
+        ham.remove("value");
+        ham.insert("value", NbtValue::Int(7));
+        assert_eq!(edited, expected);
+    }
+
+    #[test]
+    fn root() {
+        let data = include_bytes!("../assets/bigtest.nbt");
+        let mut editor = NbtEditor::new().on(NbtPath::root(), |_, _| {
+            Edit::Replace(NbtCompound::new().into())
+        });
+        assert_eq!(edit(&mut editor, data), NbtCompound::new().into());
+
+        let mut editor = NbtEditor::new().on(NbtPath::root(), |_, _| Edit::Remove);
+        for fragment in CompleteFsm::new(data) {
+            editor.push(fragment.unwrap()).unwrap();
+        }
+        assert!(editor.get_ref().is_empty());
+    }
+
+    #[test]
+    fn wrong_element() {
+        let data = include_bytes!("../assets/bigtest.nbt");
+        let path = "\"listTest (compound)\"[0]".parse().unwrap();
+        let mut editor = NbtEditor::new().on(path, |_, _| Edit::Replace(NbtValue::Int(1)));
+        let error = CompleteFsm::new(data)
+            .map(|fragment| editor.push(fragment.unwrap()))
+            .find_map(Result::err);
+        assert_eq!(
+            error,
+            Some(EditError::Write(NbtWriteError::WrongElementTag {
+                expected: NbtTag::Compound,
+                found: NbtTag::Int
+            }))
+        );
+    }
+}
@@ -1,5 +1,8 @@
+use alloc::{boxed::Box, string::String};
 use thiserror::Error;
 
+use crate::{NbtTag, path::NbtPath};
+
 pub type NbtResult<T> = Result<T, NbtParseError>;
 
 #[derive(Debug, Clone, Error, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -8,4 +11,292 @@ pub enum NbtParseError {
     InvalidTag(u8),
     #[error("Found invalid length {0} while parsing NBT.")]
     InvalidLen(i32),
+    #[error("Found a list of End tags with length {0} while parsing NBT.")]
+    EndListLength(usize),
+    #[error("Found compounds and lists nested more than {0} deep while parsing NBT.")]
+    TooDeep(usize),
+    #[error("Found a VarInt that is too long while parsing NBT.")]
+    InvalidVarInt,
+    #[error("Found a string that is not valid MUTF-8 while parsing NBT.")]
+    InvalidString,
+    #[error("Found a fragment that does not fit the structure of the NBT document.")]
+    UnexpectedFragment,
+    #[error("Reached the end of the input while parsing NBT.")]
+    UnexpectedEnd,
+    #[error("Found a value of a different type than expected while reading NBT.")]
+    UnexpectedType,
+    #[error("A required field was missing while reading NBT.")]
+    MissingField,
+    #[error("Found the key {0:?} more than once in a compound while reading NBT.")]
+    DuplicateKey(String),
+}
+
+/// Errors produced when converting an [NbtValue](crate::value::NbtValue) into a Rust type
+#[derive(Debug, Clone, Error, PartialEq, Eq, Hash)]
+pub enum NbtConvertError {
+    #[error("Expected a {expected} but found a {found}.")]
+    WrongTag { expected: NbtTag, found: NbtTag },
+    #[error("Missing required key {0:?}.")]
+    MissingKey(String),
+    #[error("Expected a list of {expected} elements but found {found}.")]
+    WrongLength { expected: usize, found: usize },
+    #[error("Invalid value for key {key:?}: {source}")]
+    InField {
+        key: String,
+        source: Box<NbtConvertError>,
+    },
+}
+
+impl NbtConvertError {
+    /// Wraps an error that occurred while converting the value stored under `key`
+    pub fn in_field(key: &str, source: NbtConvertError) -> Self {
+        NbtConvertError::InField {
+            key: key.into(),
+            source: Box::new(source),
+        }
+    }
+}
+
+/// Errors produced when encoding NBT
+#[derive(Debug, Clone, Error, PartialEq, Eq, Hash)]
+pub enum NbtWriteError {
+    #[error("Can not encode a string of {0} bytes, the limit is 65535.")]
+    StringTooLong(usize),
+    #[error("Can not encode a list or array of {0} elements, the limit is 2147483647.")]
+    TooManyElements(usize),
+    #[error("Found a fragment that does not fit the structure of the NBT document.")]
+    UnexpectedFragment,
+    #[error("Can not add a {found} to a list of {expected}.")]
+    WrongElementTag { expected: NbtTag, found: NbtTag },
+    #[error("The document exceeds its budget of {budget} bytes at `{path}`.")]
+    OverBudget { budget: usize, path: NbtPath },
+}
+
+/// Errors produced while editing a document with an [NbtEditor](crate::edit::NbtEditor)
+#[derive(Debug, Clone, Error, PartialEq, Eq, Hash)]
+pub enum EditError {
+    #[error(transparent)]
+    Parse(#[from] NbtParseError),
+    #[error(transparent)]
+    Write(#[from] NbtWriteError),
+}
+
+/// Errors produced when [joining](crate::segment::join) segments
+#[derive(Debug, Clone, Error, PartialEq, Eq, Hash)]
+pub enum SegmentError {
+    #[error(transparent)]
+    Parse(#[from] NbtParseError),
+    #[error("Segment {0} is missing.")]
+    Missing(i32),
+    #[error("Found segment {0} more than once.")]
+    Duplicate(i32),
+    #[error("Found segment {0} after the last segment.")]
+    AfterLast(i32),
+}
+
+/// Errors produced while converting between dialects with a
+/// [Transcoder](crate::transcode::Transcoder)
+#[derive(Debug, Clone, Error, PartialEq, Eq, Hash)]
+pub enum TranscodeError {
+    #[error(transparent)]
+    Parse(#[from] NbtParseError),
+    #[error(transparent)]
+    Write(#[from] NbtWriteError),
+}
+
+/// Errors produced when parsing an [NbtPath](crate::path::NbtPath)
+#[derive(Debug, Clone, Error, PartialEq, Eq, Hash)]
+pub enum PathError {
+    #[error("Found unexpected character {found:?} at position {pos} of the path.")]
+    UnexpectedChar { pos: usize, found: char },
+    #[error("The path ends inside a quoted key or index.")]
+    UnexpectedEnd,
+    #[error("Found invalid index {0:?} in the path.")]
+    InvalidIndex(String),
+}
+
+/// Errors produced while parsing [SNBT](crate::snbt)
+///
+/// Positions are byte offsets into the text.
+#[derive(Debug, Clone, Error, PartialEq, Eq, Hash)]
+pub enum SnbtError {
+    #[error("Found unexpected character {found:?} at position {pos} of the SNBT.")]
+    UnexpectedChar { pos: usize, found: char },
+    #[error("Found unexpected {found:?} at position {pos} of the SNBT.")]
+    UnexpectedToken { pos: usize, found: String },
+    #[error("The SNBT ends in the middle of a value.")]
+    UnexpectedEnd,
+    #[error("Found an invalid escape sequence in the string at position {0} of the SNBT.")]
+    InvalidString(usize),
+    #[error("Found a {found} at position {pos} of the SNBT, in a list or array of {expected}.")]
+    MixedList {
+        pos: usize,
+        expected: NbtTag,
+        found: NbtTag,
+    },
+}
+
+/// Errors produced while reading JSON as NBT
+///
+/// Positions are byte offsets into the text.
+#[derive(Debug, Clone, Error, PartialEq, Eq, Hash)]
+pub enum JsonError {
+    #[error("Found unexpected character {found:?} at position {pos} of the JSON.")]
+    UnexpectedChar { pos: usize, found: char },
+    #[error("Found unexpected {found:?} at position {pos} of the JSON.")]
+    UnexpectedToken { pos: usize, found: String },
+    #[error("The JSON ends in the middle of a value.")]
+    UnexpectedEnd,
+    #[error("Found an invalid escape sequence in the string at position {0} of the JSON.")]
+    InvalidString(usize),
+    #[error("The number at position {0} of the JSON does not fit its type.")]
+    InvalidNumber(usize),
+    #[error("Found a {found:?} at position {pos} of the JSON, in a list or array of {expected:?}.")]
+    MixedList {
+        pos: usize,
+        expected: NbtTag,
+        found: NbtTag,
+    },
+    #[error("The value at position {pos} of the JSON can not be read as a {hint:?}.")]
+    InvalidHint { pos: usize, hint: NbtTag },
+}
+
+/// Errors produced while reading CBOR as NBT
+///
+/// Positions are byte offsets into the input.
+#[cfg(feature = "cbor")]
+#[derive(Debug, Clone, Error, PartialEq, Eq, Hash)]
+pub enum CborError {
+    #[error("The CBOR ends in the middle of an item.")]
+    UnexpectedEnd,
+    #[error("The item at position {0} of the CBOR is malformed or has no NBT equivalent.")]
+    InvalidItem(usize),
+    #[error("The map key at position {0} of the CBOR is not a text string.")]
+    InvalidKey(usize),
+    #[error("The text string at position {0} of the CBOR is not valid UTF-8.")]
+    InvalidString(usize),
+    #[error("The typed array at position {0} of the CBOR does not hold whole elements.")]
+    InvalidArray(usize),
+    #[error("Found a {found:?} at position {pos} of the CBOR, in a list of {expected:?}.")]
+    MixedList {
+        pos: usize,
+        expected: NbtTag,
+        found: NbtTag,
+    },
+}
+
+/// Errors produced while decoding chunk data
+#[derive(Debug, Clone, Error, PartialEq, Eq, Hash)]
+pub enum ChunkError {
+    #[error("The palette has no entries.")]
+    EmptyPalette,
+    #[error("Expected {expected} longs of packed data but found {found}.")]
+    InvalidDataLen { expected: usize, found: usize },
+    #[error("Expected {expected} bytes of {name} but found {found}.")]
+    InvalidArrayLen {
+        name: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    #[error(transparent)]
+    Convert(#[from] NbtConvertError),
+}
+
+/// Errors produced while reading or writing schematics
+#[derive(Debug, Clone, Error, PartialEq, Eq, Hash)]
+pub enum SchemError {
+    #[error(transparent)]
+    Parse(#[from] NbtParseError),
+    #[error(transparent)]
+    Convert(#[from] NbtConvertError),
+    #[error(transparent)]
+    Write(#[from] NbtWriteError),
+    #[error("Schematic version {0} is not supported.")]
+    UnsupportedVersion(i32),
+    #[error("Found an invalid varint in the block data.")]
+    InvalidVarint,
+    #[error("The palette has missing indices or is used with indices outside of it.")]
+    InvalidPalette,
+    #[error("Expected data for {expected} blocks but found {found}.")]
+    InvalidBlockData { expected: usize, found: usize },
+    #[error("Expected a position with 3 coordinates but found {0}.")]
+    InvalidPosition(usize),
+}
+
+/// Errors produced while reading or writing structure files
+#[derive(Debug, Clone, Error, PartialEq, Eq, Hash)]
+pub enum StructureError {
+    #[error(transparent)]
+    Parse(#[from] NbtParseError),
+    #[error(transparent)]
+    Convert(#[from] NbtConvertError),
+    #[error(transparent)]
+    Write(#[from] NbtWriteError),
+    #[error("A block refers to state {0}, which is not in every palette.")]
+    InvalidState(u32),
+}
+
+/// Errors produced while reading or writing NBT through an IO source
+#[cfg(feature = "std")]
+#[derive(Debug, Error)]
+pub enum NbtIoError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Parse(#[from] NbtParseError),
+    #[error(transparent)]
+    Convert(#[from] NbtConvertError),
+    #[error(transparent)]
+    Write(#[from] NbtWriteError),
+}
+
+/// Errors produced while reading or writing region files
+#[cfg(feature = "std")]
+#[derive(Debug, Error)]
+pub enum RegionError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Nbt(#[from] NbtIoError),
+    #[error("The region header is truncated.")]
+    InvalidHeader,
+    #[error("The chunk index {0} is not below 1024.")]
+    InvalidIndex(usize),
+    #[error("The chunk at index {0} has an invalid location or length.")]
+    InvalidChunk(usize),
+    #[error("Found unknown chunk compression type {0}.")]
+    UnknownCompression(u8),
+    #[error("No codec was registered for the custom compression {0:?}.")]
+    UnknownCustomCompression(String),
+    #[error("{0:?} compression can not be stored in a region file.")]
+    UnsupportedCompression(crate::compression::Compression),
+    #[error("The chunk at index {0} does not fit in 255 sectors.")]
+    ChunkTooLarge(usize),
+    #[error("The chunk at {0:?} is not part of this region.")]
+    NotInRegion(crate::region::ChunkPos),
+    #[error("The chunk at {0:?} is stored in an external file, but its directory is unknown.")]
+    ExternalChunk(crate::region::ChunkPos),
+}
+
+/// Errors produced by [Driver](crate::io::Driver)s, with the error type of their source
+#[derive(Debug, Error)]
+pub enum NbtRefillError<E> {
+    #[error("Failed to refill the input while reading NBT: {0:?}")]
+    Refill(E),
+    #[error(transparent)]
+    Parse(#[from] NbtParseError),
+    #[error(transparent)]
+    Convert(#[from] NbtConvertError),
+}
+
+/// Errors produced while reading NBT through an `embedded-io` source
+#[cfg(feature = "embedded-io")]
+#[derive(Debug, Error)]
+pub enum NbtEmbeddedError<E> {
+    #[error("IO error while reading NBT: {0:?}")]
+    Io(E),
+    #[error(transparent)]
+    Parse(#[from] NbtParseError),
+    #[error(transparent)]
+    Convert(#[from] NbtConvertError),
 }
@@ -0,0 +1,171 @@
+//! Tracing the parser over an input, to find out why and where it fails
+//!
+//! [explain] writes a line for every fragment, with the offset it was parsed at, the fragment and
+//! the state the parser is left in. Parsing errors and truncated input end the trace with a line
+//! describing them, and a histogram of the tags that were found is returned in either case.
+//!
+//! ```
+//! # use zeronbt::{NbtFsm, NbtTag, explain::explain};
+//! let mut out = String::new();
+//! let trace = explain(NbtFsm::new(), include_bytes!("../assets/bigtest.nbt"), &mut out).unwrap();
+//! assert!(trace.error.is_none());
+//! assert_eq!(trace.histogram.get(NbtTag::Compound), 6);
+//! assert!(out.starts_with("       0  CompoundTag"));
+//! ```
+use core::fmt::{self, Write};
+
+use crate::{Dialect, FsmResult, NbtFragment, NbtFsm, NbtTag, error::NbtParseError};
+
+/// How often each tag occurred in the input
+///
+/// Elements of numeric lists count towards their tag, the elements of arrays do not.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Histogram {
+    counts: [usize; 13],
+}
+
+impl Histogram {
+    pub fn get(&self, tag: NbtTag) -> usize {
+        self.counts[tag as usize]
+    }
+
+    /// The tags that occurred, along with their counts
+    pub fn iter(&self) -> impl Iterator<Item = (NbtTag, usize)> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|&(_, &count)| count > 0)
+            .filter_map(|(tag, &count)| Some((NbtTag::try_from(tag as u8).ok()?, count)))
+    }
+
+    fn add(&mut self, tag: NbtTag, count: usize) {
+        self.counts[tag as usize] += count;
+    }
+
+    pub(crate) fn count(&mut self, fragment: &NbtFragment<'_>) {
+        let tag = match *fragment {
+            NbtFragment::End => NbtTag::End,
+            NbtFragment::CompoundTag => NbtTag::Compound,
+            NbtFragment::Byte(_) => NbtTag::Byte,
+            NbtFragment::Short(_) => NbtTag::Short,
+            NbtFragment::Int(_) => NbtTag::Int,
+            NbtFragment::Long(_) => NbtTag::Long,
+            NbtFragment::Float(_) => NbtTag::Float,
+            NbtFragment::Double(_) => NbtTag::Double,
+            // Strings and byte arrays end with one empty frame each
+            NbtFragment::StringFrame([]) => NbtTag::String,
+            NbtFragment::ByteArrayFrame([]) => NbtTag::ByteArray,
+            NbtFragment::ListTag(tag, len) => {
+                if tag.is_numeric() {
+                    self.add(tag, len);
+                }
+                NbtTag::List
+            }
+            NbtFragment::IntArrayTag(_) => NbtTag::IntArray,
+            NbtFragment::LongArrayTag(_) => NbtTag::LongArray,
+            _ => return,
+        };
+        self.add(tag, 1);
+    }
+}
+
+impl fmt::Display for Histogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (tag, count) in self.iter() {
+            writeln!(f, "{:>10} {count}", alloc::format!("{tag:?}"))?;
+        }
+        Ok(())
+    }
+}
+
+/// The outcome of [explain]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace {
+    pub histogram: Histogram,
+    /// The error parsing stopped at, along with the offset of the data it was found in
+    pub error: Option<(usize, NbtParseError)>,
+}
+
+/// Parses all of `data` with `fsm`, tracing every fragment to `out`, see the [module docs](self)
+///
+/// Only failing to write the trace is an error, parsing errors are part of the [Trace].
+pub fn explain<D: Dialect>(
+    fsm: NbtFsm<'_, D>,
+    data: &[u8],
+    out: &mut impl Write,
+) -> Result<Trace, fmt::Error> {
+    let mut fsm = fsm.with_data(data);
+    let mut histogram = Histogram::default();
+    loop {
+        let offset = fsm.consumed();
+        let error = match fsm.next_fragment() {
+            Ok(FsmResult::Found(fragment)) => {
+                histogram.count(&fragment);
+                write!(out, "{offset:>8}  {fragment:?}  -> ")?;
+                fsm.write_state(out)?;
+                out.write_char('\n')?;
+                continue;
+            }
+            Ok(FsmResult::Needs(_)) if fsm.is_idle() && offset == data.len() => None,
+            Ok(FsmResult::Needs(needs)) => {
+                writeln!(
+                    out,
+                    "{offset:>8}  input ends, {needs} more bytes needed in state"
+                )?;
+                Some((offset, NbtParseError::UnexpectedEnd))
+            }
+            Err(err) => {
+                writeln!(out, "{offset:>8}  error: {err}")?;
+                Some((offset, err))
+            }
+        };
+        if error.is_some() {
+            write!(out, "{:>8}  state: ", "")?;
+            fsm.write_state(out)?;
+            out.write_char('\n')?;
+        }
+        return Ok(Trace { histogram, error });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::{String, ToString};
+
+    #[test]
+    fn truncated() {
+        let data = include_bytes!("../assets/bigtest.nbt");
+        let mut out = String::new();
+        let trace = explain(NbtFsm::new(), &data[..100], &mut out).unwrap();
+        assert_eq!(
+            trace.error.map(|(_, err)| err),
+            Some(NbtParseError::UnexpectedEnd)
+        );
+        assert!(out.lines().last().unwrap().contains("state: "));
+
+        let mut corrupt = data.to_vec();
+        corrupt[0] = 42;
+        let trace = explain(NbtFsm::new(), &corrupt, &mut String::new()).unwrap();
+        assert_eq!(trace.error, Some((0, NbtParseError::InvalidTag(42))));
+        assert_eq!(trace.histogram, Histogram::default());
+    }
+
+    #[test]
+    fn histogram() {
+        let data = include_bytes!("../assets/bigtest.nbt");
+        let trace = explain(NbtFsm::new(), data, &mut String::new()).unwrap();
+        assert_eq!(trace.histogram.get(NbtTag::Long), 3 + 5);
+        assert_eq!(trace.histogram.get(NbtTag::ByteArray), 1);
+        assert_eq!(
+            trace.histogram.get(NbtTag::End),
+            trace.histogram.get(NbtTag::Compound)
+        );
+        assert!(
+            trace
+                .histogram
+                .to_string()
+                .contains("    String 5\n      List 2\n")
+        );
+    }
+}
@@ -0,0 +1,679 @@
+//! Typed extraction of values straight from a complete document, without building a tree
+//!
+//! ```
+//! # use zeronbt::{error::NbtResult, extract::read_compound};
+//! # fn read(data: &[u8]) -> NbtResult<()> {
+//! let (x, z) = read_compound(data, |level| {
+//!     let x: i32 = level.field("xPos")?;
+//!     let z: i32 = level.field("zPos")?;
+//!     Ok((x, z))
+//! })?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Readers only hold a slice of the input, every lookup runs a fresh [NbtFsm] over it. Strings,
+//! byte arrays and numeric lists can be borrowed straight from the input.
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use core::marker::PhantomData;
+
+use crate::{
+    FsmResult, NbtFragment, NbtFsm, NbtName, NbtTag,
+    error::*,
+    mutf8,
+    view::{BeRepr, BeSlice},
+};
+
+/// Types that can be read from a single value of a complete document
+pub trait FromFragments<'d>: Sized {
+    fn from_fragments(value: ValueReader<'d>) -> NbtResult<Self>;
+}
+
+/// Reads the root value of a complete document
+pub fn read_value<'d, T: FromFragments<'d>>(data: &'d [u8]) -> NbtResult<T> {
+    let mut fsm = NbtFsm::new().with_data(data);
+    let first = match next(&mut fsm)? {
+        NbtFragment::CompoundTag => {
+            skip_name(&mut fsm)?;
+            NbtFragment::CompoundTag
+        }
+        NbtFragment::NameFrame(name) => {
+            skip_name_frames(&mut fsm, name)?;
+            next(&mut fsm)?
+        }
+        _ => return Err(NbtParseError::UnexpectedFragment),
+    };
+    T::from_fragments(ValueReader {
+        first,
+        data: &data[fsm.consumed()..],
+    })
+}
+
+/// Reads the root compound of a complete document
+pub fn read_compound<'d, T>(
+    data: &'d [u8],
+    read: impl FnOnce(&CompoundReader<'d>) -> NbtResult<T>,
+) -> NbtResult<T> {
+    read_value::<ValueReader<'d>>(data)?.compound(read)
+}
+
+#[inline]
+fn next<'d>(fsm: &mut NbtFsm<'d>) -> NbtResult<NbtFragment<'d>> {
+    match fsm.next_fragment()? {
+        FsmResult::Found(fragment) => Ok(fragment),
+        FsmResult::Needs(_) => Err(NbtParseError::UnexpectedEnd),
+    }
+}
+
+fn skip_name_frames<'d>(fsm: &mut NbtFsm<'d>, first: &[u8]) -> NbtResult<()> {
+    if first.is_empty() {
+        return Ok(());
+    }
+    loop {
+        match next(fsm)? {
+            NbtFragment::NameFrame([]) => return Ok(()),
+            NbtFragment::NameFrame(_) => {}
+            _ => return Err(NbtParseError::UnexpectedFragment),
+        }
+    }
+}
+
+/// Reads a complete name, which is a single frame as the input is never split
+fn read_name<'d>(fsm: &mut NbtFsm<'d>) -> NbtResult<&'d [u8]> {
+    let NbtFragment::NameFrame(name) = next(fsm)? else {
+        return Err(NbtParseError::UnexpectedFragment);
+    };
+    skip_name_frames(fsm, name)?;
+    Ok(name)
+}
+
+fn skip_name(fsm: &mut NbtFsm<'_>) -> NbtResult<()> {
+    read_name(fsm).map(|_| ())
+}
+
+/// Consumes the remaining fragments of a value, given the first one
+fn skip_value<'d>(fsm: &mut NbtFsm<'d>, first: NbtFragment<'d>) -> NbtResult<()> {
+    match first {
+        NbtFragment::CompoundTag => loop {
+            match next(fsm)? {
+                NbtFragment::End => return Ok(()),
+                NbtFragment::CompoundTag => {
+                    skip_name(fsm)?;
+                    skip_value(fsm, NbtFragment::CompoundTag)?;
+                }
+                NbtFragment::NameFrame(name) => {
+                    skip_name_frames(fsm, name)?;
+                    let first = next(fsm)?;
+                    skip_value(fsm, first)?;
+                }
+                _ => return Err(NbtParseError::UnexpectedFragment),
+            }
+        },
+        NbtFragment::StringFrame([]) | NbtFragment::ByteArrayFrame([]) => Ok(()),
+        NbtFragment::StringFrame(_) | NbtFragment::ByteArrayFrame(_) => loop {
+            if let NbtFragment::StringFrame([]) | NbtFragment::ByteArrayFrame([]) = next(fsm)? {
+                return Ok(());
+            }
+        },
+        NbtFragment::ListTag(tag, len) if tag.is_numeric() => skip_frames(fsm, len),
+        NbtFragment::IntArrayTag(len) | NbtFragment::LongArrayTag(len) => skip_frames(fsm, len),
+        NbtFragment::ListTag(NbtTag::End, _) => Ok(()),
+        NbtFragment::ListTag(_, len) => {
+            for _ in 0..len {
+                let first = next(fsm)?;
+                skip_value(fsm, first)?;
+            }
+            Ok(())
+        }
+        NbtFragment::End | NbtFragment::NameFrame(_) => Err(NbtParseError::UnexpectedFragment),
+        _ => Ok(()),
+    }
+}
+
+fn skip_frames(fsm: &mut NbtFsm<'_>, mut len: usize) -> NbtResult<()> {
+    while len != 0 {
+        let frame_len = match next(fsm)? {
+            NbtFragment::ByteListFrame(view) => view.len(),
+            NbtFragment::ShortListFrame(view) => view.len(),
+            NbtFragment::IntListFrame(view) => view.len(),
+            NbtFragment::LongListFrame(view) => view.len(),
+            NbtFragment::FloatListFrame(view) => view.len(),
+            NbtFragment::DoubleListFrame(view) => view.len(),
+            _ => return Err(NbtParseError::UnexpectedFragment),
+        };
+        len = len.saturating_sub(frame_len);
+    }
+    Ok(())
+}
+
+/// A single value of a complete document, ready to be converted with [FromFragments]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueReader<'d> {
+    /// The first fragment of the value, [NbtFragment::CompoundTag] for compounds
+    first: NbtFragment<'d>,
+    /// The input following the first fragment, and the name in the case of compounds
+    data: &'d [u8],
+}
+
+impl<'d> ValueReader<'d> {
+    pub fn tag(&self) -> NbtTag {
+        match self.first {
+            NbtFragment::Byte(_) => NbtTag::Byte,
+            NbtFragment::Short(_) => NbtTag::Short,
+            NbtFragment::Int(_) => NbtTag::Int,
+            NbtFragment::Long(_) => NbtTag::Long,
+            NbtFragment::Float(_) => NbtTag::Float,
+            NbtFragment::Double(_) => NbtTag::Double,
+            NbtFragment::ByteArrayFrame(_) => NbtTag::ByteArray,
+            NbtFragment::StringFrame(_) => NbtTag::String,
+            NbtFragment::ListTag(..) => NbtTag::List,
+            NbtFragment::CompoundTag => NbtTag::Compound,
+            NbtFragment::IntArrayTag(_) => NbtTag::IntArray,
+            NbtFragment::LongArrayTag(_) => NbtTag::LongArray,
+            _ => NbtTag::End,
+        }
+    }
+    /// The first fragment of the value
+    pub fn fragment(&self) -> &NbtFragment<'d> {
+        &self.first
+    }
+    pub fn compound<T>(
+        self,
+        read: impl FnOnce(&CompoundReader<'d>) -> NbtResult<T>,
+    ) -> NbtResult<T> {
+        read(&CompoundReader::from_fragments(self)?)
+    }
+    pub fn list(self) -> NbtResult<ListReader<'d>> {
+        ListReader::from_fragments(self)
+    }
+    /// The input following the first fragment, up to the end of the document
+    pub(crate) fn rest(&self) -> &'d [u8] {
+        self.data
+    }
+}
+
+impl<'d> FromFragments<'d> for ValueReader<'d> {
+    #[inline]
+    fn from_fragments(value: ValueReader<'d>) -> NbtResult<Self> {
+        Ok(value)
+    }
+}
+
+/// The entries of a compound, looked up by name
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CompoundReader<'d> {
+    data: &'d [u8],
+}
+
+impl<'d> CompoundReader<'d> {
+    /// Reads the entry called `name`, if it exists
+    pub fn get<T: FromFragments<'d>>(&self, name: &str) -> NbtResult<Option<T>> {
+        for entry in self.entries() {
+            let (entry_name, value) = entry?;
+            if entry_name.eq_str(name) {
+                return T::from_fragments(value).map(Some);
+            }
+        }
+        Ok(None)
+    }
+    /// Reads the entry called `name`, failing with [NbtParseError::MissingField] if it does not
+    /// exist
+    pub fn field<T: FromFragments<'d>>(&self, name: &str) -> NbtResult<T> {
+        self.get(name)?.ok_or(NbtParseError::MissingField)
+    }
+    /// Iterates over the names and values of all entries
+    pub fn entries(&self) -> CompoundEntries<'d> {
+        CompoundEntries {
+            data: self.data,
+            fsm: NbtFsm::new().with_data(self.data),
+            done: false,
+        }
+    }
+    /// A reader remembering the entries it skips past, for looking up many keys in any order
+    pub fn cached(&self) -> CachedCompound<'d> {
+        CachedCompound {
+            entries: self.entries(),
+            seen: BTreeMap::new(),
+        }
+    }
+}
+
+/// The entries of a compound, looked up by name and remembered once they were passed
+///
+/// A lookup only scans the compound as far as the entry it is looking for, and every entry it
+/// skips on the way is remembered, so looking up keys in a different order than they are stored
+/// in parses the compound only once.
+///
+/// ```
+/// # use zeronbt::extract::read_compound;
+/// let data = include_bytes!("../assets/bigtest.nbt");
+/// read_compound(data, |level| {
+///     let mut level = level.cached();
+///     // Stored last, every other entry is remembered on the way
+///     assert!(level.field::<f64>("doubleTest")? > 0.49);
+///     assert_eq!(level.field::<i64>("longTest")?, i64::MAX);
+///     assert_eq!(level.get::<i8>("missing")?, None);
+///     Ok(())
+/// })
+/// .unwrap();
+/// ```
+#[derive(Debug)]
+pub struct CachedCompound<'d> {
+    entries: CompoundEntries<'d>,
+    /// The entries that were passed, the first one of duplicated names
+    seen: BTreeMap<&'d [u8], ValueReader<'d>>,
+}
+
+impl<'d> CachedCompound<'d> {
+    /// Reads the entry called `name`, if it exists
+    pub fn get<T: FromFragments<'d>>(&mut self, name: &str) -> NbtResult<Option<T>> {
+        if let Some(value) = self.remembered(name) {
+            return T::from_fragments(value.clone()).map(Some);
+        }
+        for entry in self.entries.by_ref() {
+            let (entry_name, value) = entry?;
+            let found = entry_name.eq_str(name);
+            self.seen
+                .entry(entry_name.as_bytes())
+                .or_insert_with(|| value.clone());
+            if found {
+                return T::from_fragments(value).map(Some);
+            }
+        }
+        Ok(None)
+    }
+    /// Reads the entry called `name`, failing with [NbtParseError::MissingField] if it does not
+    /// exist
+    pub fn field<T: FromFragments<'d>>(&mut self, name: &str) -> NbtResult<T> {
+        self.get(name)?.ok_or(NbtParseError::MissingField)
+    }
+    /// Looks up an entry that was passed, with its name written as either Modified or standard
+    /// UTF-8
+    fn remembered(&self, name: &str) -> Option<&ValueReader<'d>> {
+        let modified = mutf8::encode(name);
+        self.seen
+            .get(&*modified)
+            .or_else(|| self.seen.get(name.as_bytes()))
+    }
+}
+
+impl<'d> FromFragments<'d> for CompoundReader<'d> {
+    fn from_fragments(value: ValueReader<'d>) -> NbtResult<Self> {
+        match value.first {
+            NbtFragment::CompoundTag => Ok(CompoundReader { data: value.data }),
+            _ => Err(NbtParseError::UnexpectedType),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CompoundEntries<'d> {
+    data: &'d [u8],
+    fsm: NbtFsm<'d>,
+    done: bool,
+}
+
+impl<'d> CompoundEntries<'d> {
+    fn next_entry(&mut self) -> NbtResult<Option<(NbtName<'d>, ValueReader<'d>)>> {
+        let (name, first) = match next(&mut self.fsm)? {
+            NbtFragment::End => return Ok(None),
+            NbtFragment::CompoundTag => (read_name(&mut self.fsm)?, NbtFragment::CompoundTag),
+            NbtFragment::NameFrame(name) => {
+                skip_name_frames(&mut self.fsm, name)?;
+                (name, next(&mut self.fsm)?)
+            }
+            _ => return Err(NbtParseError::UnexpectedFragment),
+        };
+        let value = ValueReader {
+            first: first.clone(),
+            data: &self.data[self.fsm.consumed()..],
+        };
+        skip_value(&mut self.fsm, first)?;
+        Ok(Some((NbtName::new(name), value)))
+    }
+}
+
+impl<'d> Iterator for CompoundEntries<'d> {
+    type Item = NbtResult<(NbtName<'d>, ValueReader<'d>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let entry = self.next_entry().transpose();
+        self.done = !matches!(entry, Some(Ok(_)));
+        entry
+    }
+}
+
+/// The elements of a list
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ListReader<'d> {
+    tag: NbtTag,
+    len: usize,
+    data: &'d [u8],
+}
+
+impl<'d> ListReader<'d> {
+    pub const fn tag(&self) -> NbtTag {
+        self.tag
+    }
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    pub fn iter<T: FromFragments<'d>>(&self) -> ListIter<'d, T> {
+        ListIter {
+            list: *self,
+            fsm: NbtFsm::in_list(self.tag, self.len).with_data(self.data),
+            idx: 0,
+            failed: false,
+            _t: PhantomData,
+        }
+    }
+    fn numeric_element(&self, idx: usize) -> Option<NbtFragment<'d>> {
+        fn get<T: BeRepr>(data: &[u8], idx: usize) -> Option<T> {
+            let bytes = data.get(idx * T::BYTES..(idx + 1) * T::BYTES)?;
+            BeSlice::<T>::new(bytes)?.get(0)
+        }
+        Some(match self.tag {
+            NbtTag::Byte => NbtFragment::Byte(get(self.data, idx)?),
+            NbtTag::Short => NbtFragment::Short(get(self.data, idx)?),
+            NbtTag::Int => NbtFragment::Int(get(self.data, idx)?),
+            NbtTag::Long => NbtFragment::Long(get(self.data, idx)?),
+            NbtTag::Float => NbtFragment::Float(get(self.data, idx)?),
+            NbtTag::Double => NbtFragment::Double(get(self.data, idx)?),
+            _ => return None,
+        })
+    }
+}
+
+impl<'d> FromFragments<'d> for ListReader<'d> {
+    fn from_fragments(value: ValueReader<'d>) -> NbtResult<Self> {
+        let (tag, len) = match value.first {
+            NbtFragment::ListTag(NbtTag::End, _) => (NbtTag::End, 0),
+            NbtFragment::ListTag(tag, len) => (tag, len),
+            // Arrays are read just like lists of their element type
+            NbtFragment::IntArrayTag(len) => (NbtTag::Int, len),
+            NbtFragment::LongArrayTag(len) => (NbtTag::Long, len),
+            _ => return Err(NbtParseError::UnexpectedType),
+        };
+        Ok(ListReader {
+            tag,
+            len,
+            data: value.data,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct ListIter<'d, T> {
+    list: ListReader<'d>,
+    fsm: NbtFsm<'d>,
+    idx: usize,
+    failed: bool,
+    _t: PhantomData<fn() -> T>,
+}
+
+impl<'d, T: FromFragments<'d>> ListIter<'d, T> {
+    fn next_value(&mut self) -> NbtResult<ValueReader<'d>> {
+        if self.list.tag.is_numeric() {
+            let first = self
+                .list
+                .numeric_element(self.idx)
+                .ok_or(NbtParseError::UnexpectedEnd)?;
+            return Ok(ValueReader { first, data: &[] });
+        }
+        let first = next(&mut self.fsm)?;
+        let value = ValueReader {
+            first: first.clone(),
+            data: &self.list.data[self.fsm.consumed()..],
+        };
+        skip_value(&mut self.fsm, first)?;
+        Ok(value)
+    }
+}
+
+impl<'d, T: FromFragments<'d>> Iterator for ListIter<'d, T> {
+    type Item = NbtResult<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.idx >= self.list.len {
+            return None;
+        }
+        let value = self.next_value().and_then(T::from_fragments);
+        self.idx += 1;
+        self.failed = value.is_err();
+        Some(value)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.list.len - self.idx))
+    }
+}
+
+macro_rules! impl_scalar {
+    ($($t:ty => $variant:ident),* $(,)?) => {
+        $(impl<'d> FromFragments<'d> for $t {
+            #[inline]
+            fn from_fragments(value: ValueReader<'d>) -> NbtResult<Self> {
+                match value.first {
+                    NbtFragment::$variant(val) => Ok(val),
+                    _ => Err(NbtParseError::UnexpectedType),
+                }
+            }
+        })*
+    };
+}
+impl_scalar!(
+    i8 => Byte,
+    i16 => Short,
+    i32 => Int,
+    i64 => Long,
+    f32 => Float,
+    f64 => Double,
+);
+
+impl<'d> FromFragments<'d> for bool {
+    fn from_fragments(value: ValueReader<'d>) -> NbtResult<Self> {
+        i8::from_fragments(value).map(|byte| byte != 0)
+    }
+}
+
+impl<'d> FromFragments<'d> for &'d [u8] {
+    /// Borrows the contents of a byte array
+    fn from_fragments(value: ValueReader<'d>) -> NbtResult<Self> {
+        match value.first {
+            NbtFragment::ByteArrayFrame(data) => Ok(data),
+            _ => Err(NbtParseError::UnexpectedType),
+        }
+    }
+}
+
+impl<'d> FromFragments<'d> for &'d str {
+    /// Borrows a string, which fails for strings that are not also valid UTF-8
+    fn from_fragments(value: ValueReader<'d>) -> NbtResult<Self> {
+        match value.first {
+            NbtFragment::StringFrame(data) => {
+                core::str::from_utf8(data).map_err(|_| NbtParseError::InvalidString)
+            }
+            _ => Err(NbtParseError::UnexpectedType),
+        }
+    }
+}
+
+impl<'d> FromFragments<'d> for String {
+    fn from_fragments(value: ValueReader<'d>) -> NbtResult<Self> {
+        match value.first {
+            NbtFragment::StringFrame(data) => mutf8::decode(data)
+                .map(|str| str.into_owned())
+                .ok_or(NbtParseError::InvalidString),
+            _ => Err(NbtParseError::UnexpectedType),
+        }
+    }
+}
+
+macro_rules! impl_view {
+    ($($t:ty => $tag:ident $(| $array:ident)?),* $(,)?) => {
+        $(impl<'d> FromFragments<'d> for BeSlice<'d, $t> {
+            /// Borrows the elements of a list, or the matching array type
+            fn from_fragments(value: ValueReader<'d>) -> NbtResult<Self> {
+                let len = match value.first {
+                    NbtFragment::ListTag(NbtTag::$tag, len) => len,
+                    $(NbtFragment::$array(len) => len,)?
+                    NbtFragment::ListTag(_, 0) => 0,
+                    _ => return Err(NbtParseError::UnexpectedType),
+                };
+                let data = value
+                    .data
+                    .get(..len * <$t>::BYTES)
+                    .ok_or(NbtParseError::UnexpectedEnd)?;
+                BeSlice::new(data).ok_or(NbtParseError::UnexpectedEnd)
+            }
+        })*
+    };
+}
+impl_view!(
+    i8 => Byte,
+    i16 => Short,
+    i32 => Int | IntArrayTag,
+    i64 => Long | LongArrayTag,
+    f32 => Float,
+    f64 => Double,
+);
+
+impl<'d, T: FromFragments<'d>> FromFragments<'d> for Vec<T> {
+    fn from_fragments(value: ValueReader<'d>) -> NbtResult<Self> {
+        if let NbtFragment::ByteArrayFrame(_) = value.first {
+            // Byte arrays can be read just like a list of bytes
+            let bytes = <&[u8]>::from_fragments(value)?;
+            return bytes
+                .iter()
+                .map(|&byte| {
+                    T::from_fragments(ValueReader {
+                        first: NbtFragment::Byte(byte as i8),
+                        data: &[],
+                    })
+                })
+                .collect();
+        }
+        value.list()?.iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    const BIGTEST: &[u8] = include_bytes!("../assets/bigtest.nbt");
+
+    #[derive(Debug, PartialEq)]
+    struct Food<'d> {
+        name: &'d str,
+        value: f32,
+    }
+
+    impl<'d> FromFragments<'d> for Food<'d> {
+        fn from_fragments(value: ValueReader<'d>) -> NbtResult<Self> {
+            value.compound(|food| {
+                Ok(Food {
+                    name: food.field("name")?,
+                    value: food.field("value")?,
+                })
+            })
+        }
+    }
+
+    #[test]
+    fn read_bigtest() {
+        read_compound(BIGTEST, |level| {
+            assert_eq!(level.field::<i64>("longTest")?, i64::MAX);
+            assert_eq!(level.field::<i8>("byteTest")?, i8::MAX);
+            assert_eq!(
+                level.field::<&str>("stringTest")?,
+                "HELLO WORLD THIS IS A TEST STRING ÅÄÖ!"
+            );
+            assert_eq!(level.get::<i32>("missing")?, None);
+            assert_eq!(
+                level.field::<i32>("longTest"),
+                Err(NbtParseError::UnexpectedType)
+            );
+            assert_eq!(
+                level.field::<i32>("missing"),
+                Err(NbtParseError::MissingField)
+            );
+
+            let nested: CompoundReader = level.field("nested compound test")?;
+            assert_eq!(
+                nested.field::<Food>("egg")?,
+                Food {
+                    name: "Eggbert",
+                    value: 0.5
+                }
+            );
+            assert_eq!(nested.field::<Food>("ham")?.name, "Hampus");
+
+            let longs: BeSlice<i64> = level.field("listTest (long)")?;
+            assert_eq!(longs.iter().collect::<Vec<_>>(), [11, 12, 13, 14, 15]);
+            assert_eq!(
+                level.field::<Vec<i64>>("listTest (long)")?,
+                [11, 12, 13, 14, 15]
+            );
+
+            let compounds: ListReader = level.field("listTest (compound)")?;
+            assert_eq!(compounds.tag(), NbtTag::Compound);
+            let created = compounds
+                .iter::<CompoundReader>()
+                .map(|compound| compound?.field::<i64>("created-on"))
+                .collect::<NbtResult<Vec<_>>>()?;
+            assert_eq!(created, [1264099775885; 2]);
+
+            let (name, bytes) = level
+                .entries()
+                .find(|entry| matches!(entry, Ok((name, _)) if name.as_bytes().starts_with(b"byteArrayTest")))
+                .unwrap()?;
+            assert!(name.eq_str("byteArrayTest (the first 1000 values of (n*n*255+n*7)%100, starting with n=0 (0, 62, 34, 16, 8, ...))"));
+            let bytes = <&[u8]>::from_fragments(bytes)?;
+            assert_eq!(bytes.len(), 1000);
+            assert_eq!(level.entries().count(), 11);
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn read_nested_lists() {
+        let mut input = vec![10, 0, 0];
+        input.extend_from_slice(&[9, 0, 1, b'a', 11, 0, 0, 0, 2]);
+        input.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 1]);
+        input.extend_from_slice(&[0, 0, 0, 2, 0, 0, 0, 2, 0, 0, 0, 3]);
+        input.extend_from_slice(&[8, 0, 1, b'b', 0, 2, b'h', b'i', 0]);
+        let value = read_compound(&input, |root| {
+            let arrays = root.field::<Vec<Vec<i32>>>("a")?;
+            let string = root.field::<String>("b")?;
+            Ok((arrays, string))
+        });
+        assert_eq!(value, Ok((vec![vec![1], vec![2, 3]], "hi".into())));
+    }
+
+    #[test]
+    fn cached_lookups() {
+        read_compound(BIGTEST, |level| {
+            let names: Vec<_> = level
+                .entries()
+                .map(|entry| entry.map(|(name, _)| name.to_str_lossy().into_owned()))
+                .collect::<NbtResult<_>>()?;
+            let mut cached = level.cached();
+            for name in names.iter().rev().chain(&names) {
+                let value = cached.field::<ValueReader<'_>>(name)?;
+                assert_eq!(Some(value), level.get(name)?);
+            }
+            assert_eq!(cached.get::<i32>("intTest")?, Some(i32::MAX));
+            assert_eq!(cached.get::<i32>("missing")?, None);
+            Ok(())
+        })
+        .unwrap();
+    }
+}
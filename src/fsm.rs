@@ -1,26 +1,70 @@
 use crate::view::{BeRepr, BeSlice};
 
 use super::{buf, error::*, tag::NbtTag};
-use alloc::vec::Vec;
+use alloc::{boxed::Box, vec::Vec};
+use core::marker::PhantomData;
 
-#[derive(Debug, Clone, PartialEq, Default)]
-pub struct NbtFsm<'d> {
+mod budget;
+pub use budget::{Budget, Budgeted};
+mod complete;
+pub use complete::CompleteFsm;
+mod dialect;
+pub use dialect::{Bedrock, BedrockNetwork, Dialect, Java, JavaNetwork};
+mod exact;
+pub use exact::ExactFragment;
+mod options;
+pub use options::{DEFAULT_MAX_DEPTH, NbtOptions};
+mod owned;
+pub use owned::NbtFsmOwned;
+mod raw;
+pub use raw::RawFragment;
+mod stats;
+pub use stats::NbtStats;
+#[cfg(feature = "serde")]
+mod serde;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NbtFsm<'d, D: Dialect = Java> {
     buffer: buf::Buffer<'d>,
     state: TagState,
     namestate: NameState,
     stack: Vec<Nested>,
+    /// The full lengths of the lists being read, only needed to tell the index of an element
+    list_lens: Vec<u32>,
+    /// Whether the last fragments were a [CompoundTag](NbtFragment::CompoundTag) and its name,
+    /// while the compound is already on the stack
+    compound_header: bool,
+    // The settings of NbtOptions are stored unpacked, which keeps moving the parser in with_data
+    // cheap
+    nameless_root: bool,
+    min_frame: usize,
+    lenient_end_lists: bool,
+    max_depth: usize,
+    /// Only allocated once enabled, which keeps the parser small to move
+    stats: Option<Box<NbtStats>>,
+    _dialect: PhantomData<D>,
+}
+
+impl<D: Dialect> Default for NbtFsm<'_, D> {
+    fn default() -> Self {
+        Self::with_dialect(D::default())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 enum Nested {
-    List { tag: NbtTag, len: usize },
+    /// List lengths are read from an i32, so they are kept in half the space on the stack
+    List {
+        tag: NbtTag,
+        len: u32,
+    },
     Compound,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 enum NameState {
     NoNameLen,
-    Name(usize),
+    Name(u16),
     #[default]
     NameComplete,
 }
@@ -42,6 +86,8 @@ enum TagState {
     ListNoTag,
     ListNoLength(NbtTag),
     List(NbtTag, usize),
+    IntArrayNoLength,
+    LongArrayNoLength,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -76,6 +122,20 @@ pub enum NbtFragment<'s> {
     Long(i64),
     Float(f32),
     Double(f64),
+    /// The start of a list holding `len` elements of the given tag.
+    ///
+    /// Numeric elements follow as list frames, every other element type is represented the same way
+    /// it would be as a compound entry, just without a name.
+    ListTag(NbtTag, usize),
+    /// The start of an int array holding `len` elements, which follow as [IntListFrame]s
+    ///
+    /// [IntListFrame]: NbtFragment::IntListFrame
+    IntArrayTag(usize),
+    /// The start of a long array holding `len` elements, which follow as [LongListFrame]s
+    ///
+    /// [LongListFrame]: NbtFragment::LongListFrame
+    LongArrayTag(usize),
+    ByteListFrame(BeSlice<'s, i8>),
     ShortListFrame(BeSlice<'s, i16>),
     IntListFrame(BeSlice<'s, i32>),
     LongListFrame(BeSlice<'s, i64>),
@@ -92,6 +152,212 @@ pub enum NbtFragment<'s> {
     StringFrame(&'s [u8]),
 }
 
+/// Where a value sits in the document, see [NbtFsm::context]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FragmentContext {
+    Root,
+    /// An entry of a compound, which has a name
+    Entry,
+    /// The element of a list or array at this index
+    Element(usize),
+}
+
+/// What the parser expects to read next, see [NbtFsm::phase]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phase {
+    /// Between root tags
+    Idle,
+    /// The tag of the next compound entry, or the end of the compound
+    ExpectingTag,
+    /// The length of the name of a tag
+    ExpectingNameLength,
+    ReadingName {
+        remaining: usize,
+    },
+    /// A number, or the header of a string, array or list of this tag
+    ExpectingValue(NbtTag),
+    /// The bytes of a string
+    ReadingString {
+        remaining: usize,
+    },
+    ReadingByteArray {
+        remaining: usize,
+    },
+    /// The elements of a list, or of an int or long array, which has `remaining` elements to go
+    InList {
+        tag: NbtTag,
+        remaining: usize,
+    },
+}
+
+/// The innermost compound or list the parser is in, see [NbtFsm::container]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Container {
+    Compound,
+    /// A list, or an int or long array, with the number of elements that have not been read
+    List {
+        tag: NbtTag,
+        remaining: usize,
+    },
+}
+
+/// An [NbtFragment] that owns its data, for keeping fragments around after the input buffer
+/// has been refilled
+#[derive(Debug, Clone, PartialEq)]
+pub enum OwnedNbtFragment {
+    End,
+    CompoundTag,
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ListTag(NbtTag, usize),
+    IntArrayTag(usize),
+    LongArrayTag(usize),
+    ByteListFrame(Vec<i8>),
+    ShortListFrame(Vec<i16>),
+    IntListFrame(Vec<i32>),
+    LongListFrame(Vec<i64>),
+    FloatListFrame(Vec<f32>),
+    DoubleListFrame(Vec<f64>),
+    NameFrame(Vec<u8>),
+    ByteArrayFrame(Vec<u8>),
+    StringFrame(Vec<u8>),
+}
+
+impl<'s> NbtFragment<'s> {
+    /// The data of a [ByteArrayFrame](NbtFragment::ByteArrayFrame) as signed bytes, like the
+    /// elements of a [ByteListFrame](NbtFragment::ByteListFrame)
+    ///
+    /// ```
+    /// # use zeronbt::NbtFragment;
+    /// let frame = NbtFragment::ByteArrayFrame(&[1, 0xFF]).byte_array_frame().unwrap();
+    /// assert!(frame.iter().eq([1, -1]));
+    /// assert!(NbtFragment::StringFrame(b"a").byte_array_frame().is_none());
+    /// ```
+    pub fn byte_array_frame(&self) -> Option<BeSlice<'s, i8>> {
+        match *self {
+            NbtFragment::ByteArrayFrame(data) => Some(BeSlice::from_bytes(data)),
+            _ => None,
+        }
+    }
+
+    /// The IEEE 754 bits of a [Float](NbtFragment::Float) fragment
+    ///
+    /// Floats are decoded by copying their bits, so this involves no float operations and can
+    /// be used on targets without an FPU, as can [BeSlice::to_bits] for float list frames.
+    ///
+    /// ```
+    /// # use zeronbt::NbtFragment;
+    /// assert_eq!(NbtFragment::Float(1.0).float_bits(), Some(0x3F80_0000));
+    /// assert_eq!(NbtFragment::Int(1).float_bits(), None);
+    /// ```
+    pub fn float_bits(&self) -> Option<u32> {
+        match *self {
+            NbtFragment::Float(value) => Some(value.to_bits()),
+            _ => None,
+        }
+    }
+
+    /// The IEEE 754 bits of a [Double](NbtFragment::Double) fragment, see
+    /// [float_bits](Self::float_bits)
+    pub fn double_bits(&self) -> Option<u64> {
+        match *self {
+            NbtFragment::Double(value) => Some(value.to_bits()),
+            _ => None,
+        }
+    }
+
+    /// How many bytes the fragment takes up in the Java layout, so the lengths of the fragments
+    /// of a document add up to the length of the document
+    ///
+    /// The tag and name length of a named value are counted by the empty [NameFrame] ending its
+    /// name, the lengths of strings and byte arrays by their empty last frame.
+    ///
+    /// ```
+    /// # use zeronbt::{FsmResult, NbtFsm};
+    /// let data = include_bytes!("../assets/bigtest.nbt");
+    /// let mut fsm = NbtFsm::new().with_data(data);
+    /// let mut len = 0;
+    /// while let FsmResult::Found(fragment) = fsm.next_fragment().unwrap() {
+    ///     len += fragment.encoded_len();
+    /// }
+    /// assert_eq!(len, data.len());
+    /// ```
+    ///
+    /// [NameFrame]: NbtFragment::NameFrame
+    pub const fn encoded_len(&self) -> usize {
+        match *self {
+            NbtFragment::End => 1,
+            NbtFragment::CompoundTag => 0,
+            NbtFragment::Byte(_) => 1,
+            NbtFragment::Short(_) => 2,
+            NbtFragment::Int(_) | NbtFragment::Float(_) => 4,
+            NbtFragment::Long(_) | NbtFragment::Double(_) => 8,
+            // The element tag and length
+            NbtFragment::ListTag(..) => 5,
+            NbtFragment::IntArrayTag(_) | NbtFragment::LongArrayTag(_) => 4,
+            NbtFragment::ByteListFrame(frame) => frame.raw_bytes().len(),
+            NbtFragment::ShortListFrame(frame) => frame.raw_bytes().len(),
+            NbtFragment::IntListFrame(frame) => frame.raw_bytes().len(),
+            NbtFragment::LongListFrame(frame) => frame.raw_bytes().len(),
+            NbtFragment::FloatListFrame(frame) => frame.raw_bytes().len(),
+            NbtFragment::DoubleListFrame(frame) => frame.raw_bytes().len(),
+            NbtFragment::NameFrame([]) => 3,
+            NbtFragment::ByteArrayFrame([]) => 4,
+            NbtFragment::StringFrame([]) => 2,
+            NbtFragment::NameFrame(data)
+            | NbtFragment::ByteArrayFrame(data)
+            | NbtFragment::StringFrame(data) => data.len(),
+        }
+    }
+
+    pub fn into_owned(self) -> OwnedNbtFragment {
+        match self {
+            NbtFragment::End => OwnedNbtFragment::End,
+            NbtFragment::CompoundTag => OwnedNbtFragment::CompoundTag,
+            NbtFragment::Byte(val) => OwnedNbtFragment::Byte(val),
+            NbtFragment::Short(val) => OwnedNbtFragment::Short(val),
+            NbtFragment::Int(val) => OwnedNbtFragment::Int(val),
+            NbtFragment::Long(val) => OwnedNbtFragment::Long(val),
+            NbtFragment::Float(val) => OwnedNbtFragment::Float(val),
+            NbtFragment::Double(val) => OwnedNbtFragment::Double(val),
+            NbtFragment::ListTag(tag, len) => OwnedNbtFragment::ListTag(tag, len),
+            NbtFragment::IntArrayTag(len) => OwnedNbtFragment::IntArrayTag(len),
+            NbtFragment::LongArrayTag(len) => OwnedNbtFragment::LongArrayTag(len),
+            NbtFragment::ByteListFrame(view) => {
+                OwnedNbtFragment::ByteListFrame(view.iter().collect())
+            }
+            NbtFragment::ShortListFrame(view) => {
+                OwnedNbtFragment::ShortListFrame(view.iter().collect())
+            }
+            NbtFragment::IntListFrame(view) => {
+                OwnedNbtFragment::IntListFrame(view.iter().collect())
+            }
+            NbtFragment::LongListFrame(view) => {
+                OwnedNbtFragment::LongListFrame(view.iter().collect())
+            }
+            NbtFragment::FloatListFrame(view) => {
+                OwnedNbtFragment::FloatListFrame(view.iter().collect())
+            }
+            NbtFragment::DoubleListFrame(view) => {
+                OwnedNbtFragment::DoubleListFrame(view.iter().collect())
+            }
+            NbtFragment::NameFrame(data) => OwnedNbtFragment::NameFrame(data.to_vec()),
+            NbtFragment::ByteArrayFrame(data) => OwnedNbtFragment::ByteArrayFrame(data.to_vec()),
+            NbtFragment::StringFrame(data) => OwnedNbtFragment::StringFrame(data.to_vec()),
+        }
+    }
+}
+
+impl From<NbtFragment<'_>> for OwnedNbtFragment {
+    fn from(fragment: NbtFragment<'_>) -> Self {
+        fragment.into_owned()
+    }
+}
+
 macro_rules! forward_needs {
     ($fsmresult:expr) => {
         match $fsmresult {
@@ -109,10 +375,14 @@ macro_rules! forward_needs {
 
 macro_rules! impl_list {
     ($t:ty, $frame:ident, $state:ident, $self:ident, $len:ident) => {{
-        let view = $self.read_array::<$t>($len);
-        if view.is_empty() {
-            return Ok(FsmResult::Needs(<$t>::BYTES));
+        if !D::LIST_FRAMES && <$t>::BYTES > 1 {
+            // Elements that are not big-endian are read one by one, like any other value
+            $self.state = TagState::List(NbtTag::$state, $len - 1);
+            $self.push_state();
+            $self.state = TagState::$state;
+            continue;
         }
+        let view = forward_needs!(wrap(Ok), $self.read_frame::<$t>($len));
         $self.state = TagState::List(NbtTag::$state, $len - view.len());
         return Ok(FsmResult::Found(NbtFragment::$frame(view)));
     }};
@@ -120,43 +390,245 @@ macro_rules! impl_list {
 
 impl<'d> NbtFsm<'d> {
     pub const fn new() -> Self {
+        Self::with_dialect(Java)
+    }
+    /// Creates a parser for the network format used since Minecraft 1.20.2, where the root tag
+    /// is not followed by a name
+    ///
+    /// An empty [NameFrame](NbtFragment::NameFrame) is still produced for the root, so consumers
+    /// see the same fragments as for a root with an empty name.
+    pub const fn new_nameless() -> Self {
+        Self::new().with_options(NbtOptions::new().nameless_root(true))
+    }
+    /// Starts parsing right before the first element of a list, whose header has already been read
+    pub(crate) fn in_list(tag: NbtTag, len: usize) -> Self {
+        let mut fsm = Self::new();
+        fsm.state = TagState::List(tag, len);
+        fsm.list_lens.push(len as u32);
+        fsm
+    }
+}
+
+impl<'d, D: Dialect> NbtFsm<'d, D> {
+    /// Creates a parser for another dialect than [Java], e.g. `NbtFsm::with_dialect(Bedrock)`
+    pub const fn with_dialect(_dialect: D) -> Self {
         Self {
             buffer: buf::Buffer::new(&[]),
             state: TagState::Empty,
             namestate: NameState::NameComplete,
             stack: Vec::new(),
+            list_lens: Vec::new(),
+            compound_header: false,
+            nameless_root: false,
+            min_frame: 0,
+            lenient_end_lists: false,
+            max_depth: options::DEFAULT_MAX_DEPTH,
+            stats: None,
+            _dialect: PhantomData,
         }
     }
-    pub fn with_data<'new>(self, data: &'new [u8]) -> NbtFsm<'new> {
+    pub const fn with_options(mut self, options: NbtOptions) -> Self {
+        self.nameless_root = options.nameless_root;
+        self.min_frame = options.min_frame;
+        self.lenient_end_lists = options.lenient_end_lists;
+        self.max_depth = options.max_depth;
+        self
+    }
+    pub const fn options(&self) -> NbtOptions {
+        NbtOptions::new()
+            .nameless_root(self.nameless_root)
+            .min_frame(self.min_frame)
+            .lenient_end_lists(self.lenient_end_lists)
+            .max_depth(self.max_depth)
+    }
+    /// Reserves room for `depth` nested compounds and lists, so parsing documents that nest up to
+    /// that deep does not reallocate
+    pub fn with_capacity(mut self, depth: usize) -> Self {
+        self.stack.reserve(depth);
+        self.list_lens.reserve(depth);
+        self
+    }
+    /// Collects [NbtStats] while parsing, which are read with [NbtFsm::stats]
+    pub fn with_stats(mut self) -> Self {
+        self.stats.get_or_insert_default();
+        self
+    }
+    /// The statistics collected since [NbtFsm::with_stats] was called, None if it was not
+    pub fn stats(&self) -> Option<NbtStats> {
+        let mut stats = NbtStats::clone(self.stats.as_ref()?);
+        stats.bytes += self.consumed();
+        Some(stats)
+    }
+    /// Shorthand for setting [NbtOptions::min_frame]
+    pub const fn with_min_frame(mut self, bytes: usize) -> Self {
+        self.min_frame = bytes;
+        self
+    }
+    #[inline]
+    pub fn with_data<'new>(self, data: &'new [u8]) -> NbtFsm<'new, D> {
+        let self_consumed = self.consumed();
         let Self {
             stack,
             state,
             namestate,
+            list_lens,
+            compound_header,
+            nameless_root,
+            min_frame,
+            lenient_end_lists,
+            max_depth,
+            mut stats,
             ..
         } = self;
+        if let Some(stats) = &mut stats {
+            stats.bytes += self_consumed;
+            stats.refills += 1;
+        }
         NbtFsm {
             buffer: buf::Buffer::new(data),
             state,
             stack,
             namestate,
+            list_lens,
+            compound_header,
+            nameless_root,
+            min_frame,
+            lenient_end_lists,
+            max_depth,
+            stats,
+            _dialect: PhantomData,
         }
     }
+    #[inline]
     pub fn consumed(&self) -> usize {
         self.buffer.consumed().len()
     }
-    #[inline]
+    /// Whether the parser is between root tags, and so would not lose anything if the input ended
+    pub fn is_idle(&self) -> bool {
+        self.state == TagState::Empty
+            && self.namestate == NameState::NameComplete
+            && self.stack.is_empty()
+    }
+    /// Fails once `depth` compounds and lists are open, counting the one being started
+    #[inline(always)]
+    fn check_depth(&self, depth: usize) -> NbtResult<()> {
+        match depth > self.max_depth {
+            true => Err(too_deep(self.max_depth)),
+            false => Ok(()),
+        }
+    }
+    /// Where the value that the last fragment belongs to sits in the document
+    ///
+    /// Names belong to the entry they name, [End](NbtFragment::End) to the compound it closes and
+    /// frames to the string, array or list they are part of. Elements of numeric lists are only
+    /// told apart when a [Dialect] produces them one by one.
+    pub fn context(&self) -> FragmentContext {
+        let mut parents = self.stack.iter().rev();
+        let mut lens = self.list_lens.iter().rev().map(|&len| len as usize);
+        match self.state {
+            TagState::List(tag, remaining) => {
+                let len = lens.next().unwrap_or(remaining);
+                let frames = match tag {
+                    NbtTag::Byte => true,
+                    NbtTag::Short | NbtTag::Int | NbtTag::Long | NbtTag::Float | NbtTag::Double => {
+                        D::LIST_FRAMES
+                    }
+                    _ => false,
+                };
+                // Otherwise the list has just started, or produced a frame
+                if remaining != len && !frames {
+                    return FragmentContext::Element(len - remaining - 1);
+                }
+            }
+            // Skip the compound itself
+            TagState::Empty if self.compound_header => _ = parents.next(),
+            _ => {}
+        }
+        match parents.next() {
+            None => FragmentContext::Root,
+            Some(Nested::Compound) => FragmentContext::Entry,
+            Some(&Nested::List { len: remaining, .. }) => {
+                let len = lens.next().unwrap_or(0);
+                FragmentContext::Element(len.saturating_sub(remaining as usize + 1))
+            }
+        }
+    }
+    /// The innermost compound or list the parser is in, None between root tags
+    ///
+    /// A compound or list counts as soon as the fragment that starts it has been read, and the
+    /// elements of a list that are started count as read.
+    pub fn container(&self) -> Option<Container> {
+        if let TagState::List(tag, remaining) = self.state {
+            return Some(Container::List { tag, remaining });
+        }
+        self.stack.last().map(|nested| match *nested {
+            Nested::Compound => Container::Compound,
+            Nested::List { tag, len } => Container::List {
+                tag,
+                remaining: len as usize,
+            },
+        })
+    }
+    /// How many elements of the innermost list are left, None if the parser is in a compound or
+    /// between root tags
+    pub fn remaining_in_container(&self) -> Option<usize> {
+        match self.container()? {
+            Container::List { remaining, .. } => Some(remaining),
+            Container::Compound => None,
+        }
+    }
+    /// What the parser expects to read next, for drivers that report progress or decide how long
+    /// to wait for more input
+    pub fn phase(&self) -> Phase {
+        match self.namestate {
+            NameState::NoNameLen => return Phase::ExpectingNameLength,
+            NameState::Name(remaining) => {
+                return Phase::ReadingName {
+                    remaining: remaining as usize,
+                };
+            }
+            NameState::NameComplete => {}
+        }
+        match self.state {
+            TagState::Empty if self.stack.is_empty() => Phase::Idle,
+            TagState::Empty => Phase::ExpectingTag,
+            TagState::Byte => Phase::ExpectingValue(NbtTag::Byte),
+            TagState::Short => Phase::ExpectingValue(NbtTag::Short),
+            TagState::Int => Phase::ExpectingValue(NbtTag::Int),
+            TagState::Long => Phase::ExpectingValue(NbtTag::Long),
+            TagState::Float => Phase::ExpectingValue(NbtTag::Float),
+            TagState::Double => Phase::ExpectingValue(NbtTag::Double),
+            TagState::ByteArrayNoLength => Phase::ExpectingValue(NbtTag::ByteArray),
+            TagState::ByteArray(remaining) => Phase::ReadingByteArray { remaining },
+            TagState::StringNoLength => Phase::ExpectingValue(NbtTag::String),
+            TagState::String(remaining) => Phase::ReadingString { remaining },
+            TagState::ListNoTag | TagState::ListNoLength(_) => Phase::ExpectingValue(NbtTag::List),
+            TagState::List(tag, remaining) => Phase::InList { tag, remaining },
+            TagState::IntArrayNoLength => Phase::ExpectingValue(NbtTag::IntArray),
+            TagState::LongArrayNoLength => Phase::ExpectingValue(NbtTag::LongArray),
+        }
+    }
+    /// Describes the internal state of the parser, for tracing it
+    pub(crate) fn write_state(&self, out: &mut impl core::fmt::Write) -> core::fmt::Result {
+        write!(
+            out,
+            "{:?} {:?} depth {}",
+            self.state,
+            self.namestate,
+            self.stack.len()
+        )
+    }
+    #[inline(always)]
     fn push_state(&mut self) {
-        let TagState::List(tag, len) = self.state else {
-            return;
-        };
-        self.stack.push(Nested::List { tag, len });
+        push_state(&self.state, &mut self.stack)
     }
-    fn pop_outer(&mut self) {
-        let Some(Nested::List { tag, len }) = self.stack.pop() else {
-            self.state = TagState::Empty;
-            return;
-        };
-        self.state = TagState::List(tag, len);
+    #[inline(always)]
+    fn finish_value(&mut self) {
+        finish_value(&mut self.state, &mut self.stack)
+    }
+    #[inline(always)]
+    fn end_compound(&mut self) {
+        end_compound(&mut self.state, &mut self.stack)
     }
     #[inline(always)]
     fn read_array<T: BeRepr>(&mut self, len: usize) -> BeSlice<'d, T> {
@@ -170,45 +642,55 @@ impl<'d> NbtFsm<'d> {
             BeSlice::new(data).unwrap_unchecked()
         }
     }
+    /// Reads the next frame of an array with `len > 0` elements left, respecting the minimum frame size
+    #[inline(always)]
+    fn read_frame<T: BeRepr>(&mut self, len: usize) -> FsmResult<BeSlice<'d, T>> {
+        let wanted = len
+            .saturating_mul(T::BYTES)
+            .min(self.min_frame.max(T::BYTES));
+        if self.buffer.available().len() < wanted {
+            return FsmResult::Needs(wanted);
+        }
+        FsmResult::Found(self.read_array(len))
+    }
     #[inline(always)]
     pub fn next_fragment(&mut self) -> NbtResult<FsmResult<NbtFragment<'d>>> {
+        let result = self.parse_fragment();
+        if let Some(stats) = &mut self.stats
+            && let Ok(FsmResult::Found(fragment)) = &result
+        {
+            stats.record(fragment, self.stack.len());
+        }
+        result
+    }
+    #[inline(always)]
+    fn parse_fragment(&mut self) -> NbtResult<FsmResult<NbtFragment<'d>>> {
         'name: loop {
-            match self.namestate {
-                NameState::NameComplete => (),
-                NameState::NoNameLen => {
-                    let len = forward_needs!(wrap(Ok), self.capture_short());
-                    let len = len as usize;
-                    self.namestate = NameState::Name(len);
-                    continue;
-                }
-                NameState::Name(len) => {
-                    if len == 0 {
-                        self.namestate = NameState::NameComplete;
-                        break Ok(FsmResult::Found(NbtFragment::NameFrame(&[])));
-                    }
-                    let frame = len.min(self.buffer.available().len());
-                    if frame == 0 {
-                        break Ok(FsmResult::Needs(1));
-                    }
-                    let bytes = self.buffer.consume(frame).unwrap();
-                    let len = len - frame;
-                    self.namestate = NameState::Name(len);
-                    break Ok(FsmResult::Found(NbtFragment::NameFrame(bytes)));
-                }
-            };
+            if !matches!(self.namestate, NameState::NameComplete) {
+                return self.name_frame();
+            }
             loop {
                 match self.state {
                     TagState::Empty => {
                         let tag = forward_needs!(wrap(Ok), self.capture_tag()?);
+                        self.compound_header = false;
+                        let nameless = self.nameless_root || !D::NAMED_ROOT;
+                        let namestate = if nameless && self.stack.is_empty() {
+                            NameState::Name(0)
+                        } else {
+                            NameState::NoNameLen
+                        };
                         let state = match tag {
                             NbtTag::End => {
-                                self.pop_outer();
+                                self.end_compound();
                                 return Ok(FsmResult::Found(NbtFragment::End));
                             }
                             NbtTag::Compound => {
                                 self.stack.push(Nested::Compound);
+                                self.check_depth(self.stack.len())?;
+                                self.compound_header = true;
                                 self.state = TagState::Empty;
-                                self.namestate = NameState::NoNameLen;
+                                self.namestate = namestate;
                                 return Ok(FsmResult::Found(NbtFragment::CompoundTag));
                             }
                             NbtTag::Byte => TagState::Byte,
@@ -220,11 +702,11 @@ impl<'d> NbtFsm<'d> {
                             NbtTag::ByteArray => TagState::ByteArrayNoLength,
                             NbtTag::String => TagState::StringNoLength,
                             NbtTag::List => TagState::ListNoTag,
-                            NbtTag::IntArray => TagState::ListNoLength(NbtTag::Int),
-                            NbtTag::LongArray => TagState::ListNoLength(NbtTag::Long),
+                            NbtTag::IntArray => TagState::IntArrayNoLength,
+                            NbtTag::LongArray => TagState::LongArrayNoLength,
                         };
                         self.state = state;
-                        self.namestate = NameState::NoNameLen;
+                        self.namestate = namestate;
                         continue 'name;
                     }
                     TagState::ListNoTag => {
@@ -232,28 +714,39 @@ impl<'d> NbtFsm<'d> {
                         self.state = TagState::ListNoLength(tag);
                         continue;
                     }
-                    TagState::ListNoLength(NbtTag::ByteArray) => {
-                        self.state = TagState::ByteArrayNoLength;
-                        continue;
-                    }
-                    TagState::ListNoLength(NbtTag::End) => {
-                        self.pop_outer();
-                        return Ok(FsmResult::Found(NbtFragment::End));
-                    }
                     TagState::ListNoLength(tag) => {
-                        let len = forward_needs!(wrap(Ok), self.capture_int());
-                        let Ok(len) = usize::try_from(len) else {
-                            return Err(NbtParseError::InvalidLen(len));
-                        };
+                        let mut len = forward_needs!(wrap(Ok), self.capture_len())?;
+                        // Elements of End tags can not be read, so they would be skipped without
+                        // being produced
+                        if tag == NbtTag::End && len > 0 {
+                            if !self.lenient_end_lists {
+                                return Err(NbtParseError::EndListLength(len));
+                            }
+                            len = 0;
+                        }
+                        // Every open compound and list is on the stack, except for this one
+                        self.check_depth(self.stack.len() + 1)?;
                         self.state = TagState::List(tag, len);
-                        continue;
+                        self.list_lens.push(len as u32);
+                        return Ok(FsmResult::Found(NbtFragment::ListTag(tag, len)));
                     }
-                    TagState::List(_, 0) => {
-                        self.pop_outer();
-                        continue;
+                    TagState::IntArrayNoLength => {
+                        let len = forward_needs!(wrap(Ok), self.capture_len())?;
+                        self.state = TagState::List(NbtTag::Int, len);
+                        self.list_lens.push(len as u32);
+                        return Ok(FsmResult::Found(NbtFragment::IntArrayTag(len)));
+                    }
+                    TagState::LongArrayNoLength => {
+                        let len = forward_needs!(wrap(Ok), self.capture_len())?;
+                        self.state = TagState::List(NbtTag::Long, len);
+                        self.list_lens.push(len as u32);
+                        return Ok(FsmResult::Found(NbtFragment::LongArrayTag(len)));
                     }
-                    TagState::List(NbtTag::End | NbtTag::Byte, _) => {
-                        unreachable!()
+                    // A list of End tags can not hold any elements
+                    TagState::List(_, 0) | TagState::List(NbtTag::End, _) => {
+                        self.list_lens.pop();
+                        self.finish_value();
+                        continue;
                     }
                     TagState::List(NbtTag::String, ref mut len) => {
                         *len -= 1;
@@ -272,14 +765,14 @@ impl<'d> NbtFsm<'d> {
                     TagState::List(NbtTag::IntArray, ref mut len) => {
                         *len -= 1;
                         self.push_state();
-                        self.state = TagState::ListNoLength(NbtTag::Int);
+                        self.state = TagState::IntArrayNoLength;
                         self.namestate = NameState::NameComplete;
                         continue;
                     }
                     TagState::List(NbtTag::LongArray, ref mut len) => {
                         *len -= 1;
                         self.push_state();
-                        self.state = TagState::ListNoLength(NbtTag::Long);
+                        self.state = TagState::LongArrayNoLength;
                         self.namestate = NameState::NameComplete;
                         continue;
                     }
@@ -295,8 +788,13 @@ impl<'d> NbtFsm<'d> {
                         self.push_state();
                         self.state = TagState::Empty;
                         self.stack.push(Nested::Compound);
+                        self.check_depth(self.stack.len())?;
+                        self.compound_header = true;
                         self.namestate = NameState::NameComplete;
-                        continue;
+                        return Ok(FsmResult::Found(NbtFragment::CompoundTag));
+                    }
+                    TagState::List(NbtTag::Byte, len) => {
+                        impl_list!(i8, ByteListFrame, Byte, self, len)
                     }
                     TagState::List(NbtTag::Short, len) => {
                         impl_list!(i16, ShortListFrame, Short, self, len)
@@ -314,83 +812,87 @@ impl<'d> NbtFsm<'d> {
                         impl_list!(f64, DoubleListFrame, Double, self, len)
                     }
                     TagState::StringNoLength => {
-                        let len = forward_needs!(wrap(Ok), self.capture_short());
+                        let len = forward_needs!(wrap(Ok), self.capture_ushort())?;
                         let len = len as usize;
                         self.state = TagState::String(len);
                         continue;
                     }
                     TagState::String(len) => {
                         if len == 0 {
-                            self.pop_outer();
+                            self.finish_value();
                             return Ok(FsmResult::Found(NbtFragment::StringFrame(&[])));
                         }
-                        let view = self.read_array::<u8>(len).raw_bytes();
-                        if view.is_empty() {
-                            return Ok(FsmResult::Needs(1));
-                        }
+                        let view = forward_needs!(wrap(Ok), self.read_frame::<u8>(len)).raw_bytes();
                         self.state = TagState::String(len - view.len());
                         return Ok(FsmResult::Found(NbtFragment::StringFrame(view)));
                     }
                     TagState::ByteArrayNoLength => {
-                        let len = forward_needs!(wrap(Ok), self.capture_int());
-                        let Ok(len) = usize::try_from(len) else {
-                            return Err(NbtParseError::InvalidLen(len));
-                        };
+                        let len = forward_needs!(wrap(Ok), self.capture_len())?;
                         self.state = TagState::ByteArray(len);
                         continue;
                     }
                     TagState::ByteArray(len) => {
                         if len == 0 {
-                            self.pop_outer();
+                            self.finish_value();
                             return Ok(FsmResult::Found(NbtFragment::ByteArrayFrame(&[])));
                         }
-                        let view = self.read_array::<u8>(len).raw_bytes();
-                        if view.is_empty() {
-                            return Ok(FsmResult::Needs(1));
-                        }
+                        let view = forward_needs!(wrap(Ok), self.read_frame::<u8>(len)).raw_bytes();
                         self.state = TagState::ByteArray(len - view.len());
                         return Ok(FsmResult::Found(NbtFragment::ByteArrayFrame(view)));
                     }
                     TagState::Byte => {
                         return Ok(self
                             .capture_byte()
-                            .on_found(|| self.pop_outer())
+                            .on_found(|| self.finish_value())
                             .map_found(NbtFragment::Byte));
                     }
                     TagState::Short => {
-                        return Ok(self
-                            .capture_short()
-                            .on_found(|| self.pop_outer())
-                            .map_found(NbtFragment::Short));
+                        let value = forward_needs!(wrap(Ok), self.capture_short())?;
+                        self.finish_value();
+                        return Ok(FsmResult::Found(NbtFragment::Short(value)));
                     }
                     TagState::Int => {
-                        return Ok(self
-                            .capture_int()
-                            .on_found(|| self.pop_outer())
-                            .map_found(NbtFragment::Int));
+                        let value = forward_needs!(wrap(Ok), self.capture_int())?;
+                        self.finish_value();
+                        return Ok(FsmResult::Found(NbtFragment::Int(value)));
                     }
                     TagState::Long => {
-                        return Ok(self
-                            .capture_long()
-                            .on_found(|| self.pop_outer())
-                            .map_found(NbtFragment::Long));
+                        let value = forward_needs!(wrap(Ok), self.capture_long())?;
+                        self.finish_value();
+                        return Ok(FsmResult::Found(NbtFragment::Long(value)));
                     }
                     TagState::Float => {
-                        return Ok(self
-                            .capture_float()
-                            .on_found(|| self.pop_outer())
-                            .map_found(NbtFragment::Float));
+                        let value = forward_needs!(wrap(Ok), self.capture_float())?;
+                        self.finish_value();
+                        return Ok(FsmResult::Found(NbtFragment::Float(value)));
                     }
                     TagState::Double => {
-                        return Ok(self
-                            .capture_double()
-                            .on_found(|| self.pop_outer())
-                            .map_found(NbtFragment::Double));
+                        let value = forward_needs!(wrap(Ok), self.capture_double())?;
+                        self.finish_value();
+                        return Ok(FsmResult::Found(NbtFragment::Double(value)));
                     }
                 };
             }
         }
     }
+    /// Produces the next frame of the name being read
+    #[inline(always)]
+    fn name_frame(&mut self) -> NbtResult<FsmResult<NbtFragment<'d>>> {
+        let len = match self.namestate {
+            NameState::NoNameLen => forward_needs!(wrap(Ok), self.capture_ushort())?,
+            NameState::Name(len) => len,
+            // Only called while a name is being read
+            NameState::NameComplete => 0,
+        };
+        if len == 0 {
+            self.namestate = NameState::NameComplete;
+            return Ok(FsmResult::Found(NbtFragment::NameFrame(&[])));
+        }
+        self.namestate = NameState::Name(len);
+        let frame = forward_needs!(wrap(Ok), self.read_frame::<u8>(len as usize)).raw_bytes();
+        self.namestate = NameState::Name(len - frame.len() as u16);
+        Ok(FsmResult::Found(NbtFragment::NameFrame(frame)))
+    }
     #[inline(always)]
     fn consume_arr<const LEN: usize>(&mut self) -> FsmResult<&'d [u8; LEN]> {
         match self.buffer.consume_arr() {
@@ -399,29 +901,29 @@ impl<'d> NbtFsm<'d> {
         }
     }
     #[inline(always)]
-    fn capture_double(&mut self) -> FsmResult<f64> {
-        let &be = forward_needs!(self.consume_arr());
-        FsmResult::Found(f64::from_be_bytes(be))
+    fn capture_double(&mut self) -> FsmResult<NbtResult<f64>> {
+        D::double(&mut self.buffer)
+    }
+    #[inline(always)]
+    fn capture_float(&mut self) -> FsmResult<NbtResult<f32>> {
+        D::float(&mut self.buffer)
     }
     #[inline(always)]
-    fn capture_float(&mut self) -> FsmResult<f32> {
-        let &be = forward_needs!(self.consume_arr());
-        FsmResult::Found(f32::from_be_bytes(be))
+    fn capture_long(&mut self) -> FsmResult<NbtResult<i64>> {
+        D::long(&mut self.buffer)
     }
     #[inline(always)]
-    fn capture_long(&mut self) -> FsmResult<i64> {
-        let &be = forward_needs!(self.consume_arr());
-        FsmResult::Found(i64::from_be_bytes(be))
+    fn capture_int(&mut self) -> FsmResult<NbtResult<i32>> {
+        D::int(&mut self.buffer)
     }
     #[inline(always)]
-    fn capture_int(&mut self) -> FsmResult<i32> {
-        let &be = forward_needs!(self.consume_arr());
-        FsmResult::Found(i32::from_be_bytes(be))
+    fn capture_short(&mut self) -> FsmResult<NbtResult<i16>> {
+        D::short(&mut self.buffer)
     }
+    /// Reads the unsigned length of a string or name
     #[inline(always)]
-    fn capture_short(&mut self) -> FsmResult<i16> {
-        let &be = forward_needs!(self.consume_arr());
-        FsmResult::Found(i16::from_be_bytes(be))
+    fn capture_ushort(&mut self) -> FsmResult<NbtResult<u16>> {
+        D::string_len(&mut self.buffer)
     }
     #[inline(always)]
     fn capture_byte(&mut self) -> FsmResult<i8> {
@@ -433,7 +935,58 @@ impl<'d> NbtFsm<'d> {
         let Some(&[tag]) = self.buffer.consume_arr() else {
             return Ok(FsmResult::Needs(1));
         };
-        let tag = NbtTag::try_from(tag)?;
-        Ok(FsmResult::Found(tag))
+        match NbtTag::try_from(tag) {
+            Ok(tag) => Ok(FsmResult::Found(tag)),
+            Err(_) => Err(invalid_tag(tag)),
+        }
+    }
+    /// Reads the length of a list or array
+    #[inline(always)]
+    fn capture_len(&mut self) -> FsmResult<NbtResult<usize>> {
+        D::len(&mut self.buffer)
     }
 }
+
+// The stack handling does not depend on the dialect, so it is kept out of the generic parser
+
+fn push_state(state: &TagState, stack: &mut Vec<Nested>) {
+    let &TagState::List(tag, len) = state else {
+        return;
+    };
+    stack.push(Nested::List {
+        tag,
+        len: len as u32,
+    });
+}
+
+/// Called once a value has been read completely, returns to the state of the enclosing container
+fn finish_value(state: &mut TagState, stack: &mut Vec<Nested>) {
+    let Some(&Nested::List { tag, len }) = stack.last() else {
+        *state = TagState::Empty;
+        return;
+    };
+    stack.pop();
+    *state = TagState::List(tag, len as usize);
+}
+
+fn end_compound(state: &mut TagState, stack: &mut Vec<Nested>) {
+    if let Some(Nested::Compound) = stack.last() {
+        stack.pop();
+    }
+    finish_value(state, stack);
+}
+
+#[cold]
+fn too_deep(max_depth: usize) -> NbtParseError {
+    NbtParseError::TooDeep(max_depth)
+}
+
+#[cold]
+fn invalid_tag(tag: u8) -> NbtParseError {
+    NbtParseError::InvalidTag(tag)
+}
+
+#[cold]
+pub(crate) fn invalid_len(len: i32) -> NbtParseError {
+    NbtParseError::InvalidLen(len)
+}
@@ -0,0 +1,92 @@
+use super::{Dialect, FsmResult, NbtFragment, NbtFsm};
+use crate::error::NbtResult;
+
+/// How much [NbtFsm::next_fragments_budgeted] may parse in a single call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Budget {
+    /// Stops once at least this many bytes were consumed
+    Bytes(usize),
+    /// Stops once this many fragments were produced
+    Fragments(usize),
+}
+
+/// Why [NbtFsm::next_fragments_budgeted] returned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Budgeted {
+    /// The budget was spent, more fragments may follow in the data
+    Spent,
+    /// The buffer must be filled with at least N bytes to continue parsing, see
+    /// [FsmResult::Needs]
+    Needs(usize),
+}
+
+impl<'d, D: Dialect> NbtFsm<'d, D> {
+    /// Parses fragments into `sink` until `budget` is spent or the data runs out
+    ///
+    /// This bounds the time a single call takes however much data is buffered, so that parsing
+    /// can be interleaved with other work, like the rest of a game tick or other async tasks.
+    ///
+    /// ```
+    /// # use zeronbt::{Budget, Budgeted, NbtFsm};
+    /// let data = include_bytes!("../../assets/bigtest.nbt");
+    /// let mut fsm = NbtFsm::new().with_data(data);
+    /// let (mut calls, mut fragments) = (0, 0);
+    /// while fsm.next_fragments_budgeted(Budget::Fragments(16), |_| fragments += 1).unwrap()
+    ///     == Budgeted::Spent
+    /// {
+    ///     calls += 1;
+    /// }
+    /// assert_eq!(calls, fragments / 16);
+    /// assert_eq!(fsm.consumed(), data.len());
+    /// ```
+    pub fn next_fragments_budgeted(
+        &mut self,
+        budget: Budget,
+        mut sink: impl FnMut(NbtFragment<'d>),
+    ) -> NbtResult<Budgeted> {
+        let start = self.consumed();
+        let mut fragments = 0;
+        loop {
+            let spent = match budget {
+                Budget::Bytes(max) => self.consumed() - start >= max,
+                Budget::Fragments(max) => fragments >= max,
+            };
+            if spent {
+                return Ok(Budgeted::Spent);
+            }
+            match self.next_fragment()? {
+                FsmResult::Found(fragment) => {
+                    fragments += 1;
+                    sink(fragment);
+                }
+                FsmResult::Needs(needs) => return Ok(Budgeted::Needs(needs)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CompleteFsm;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn byte_budget() {
+        let data = include_bytes!("../../assets/bigtest.nbt");
+        let mut fsm = NbtFsm::new().with_data(data);
+        let mut fragments = Vec::new();
+        loop {
+            let start = fsm.consumed();
+            let budgeted = fsm
+                .next_fragments_budgeted(Budget::Bytes(64), |fragment| fragments.push(fragment))
+                .unwrap();
+            if budgeted != Budgeted::Spent {
+                break;
+            }
+            assert!(fsm.consumed() - start >= 64);
+        }
+        let complete: Vec<_> = CompleteFsm::new(data).map(Result::unwrap).collect();
+        assert_eq!(fragments, complete);
+    }
+}
@@ -0,0 +1,391 @@
+use super::{
+    Dialect, FragmentContext, FsmResult, Java, NameState, NbtFragment, NbtFsm, Nested, TagState,
+    invalid_tag,
+};
+use crate::{
+    error::{NbtParseError, NbtResult},
+    tag::NbtTag,
+    view::{BeRepr, BeSlice},
+};
+
+/// Parses input that holds complete documents, without a refill loop around the parser
+///
+/// Running out of input in the middle of a document is reported as
+/// [NbtParseError::UnexpectedEnd] instead of asking for more data, so callers do not have to
+/// handle [FsmResult::Needs] or track how much was consumed. As nothing is split across refills,
+/// names, strings, byte arrays and numeric lists always arrive as a single frame, followed by an
+/// empty one where [NbtFsm] would produce one.
+///
+/// This is a separate driver over the state of [NbtFsm] rather than a wrapper around it: it
+/// takes every value from the input in one go, without checking whether it has to wait for more
+/// data or split a frame. The iterator ends after the first error.
+///
+/// ```
+/// # use zeronbt::{CompleteFsm, NbtFragment};
+/// let data = include_bytes!("../../assets/bigtest.nbt");
+/// let fragments = CompleteFsm::new(data).collect::<Result<Vec<_>, _>>().unwrap();
+/// assert_eq!(fragments.last(), Some(&NbtFragment::End));
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CompleteFsm<'d, D: Dialect = Java> {
+    fsm: NbtFsm<'d, D>,
+    failed: bool,
+}
+
+impl<'d> CompleteFsm<'d> {
+    pub fn new(data: &'d [u8]) -> Self {
+        Self::with_fsm(NbtFsm::new(), data)
+    }
+}
+
+impl<'d, D: Dialect> CompleteFsm<'d, D> {
+    /// Continues parsing with the state of `fsm`, e.g. one created with [NbtFsm::new_nameless]
+    pub fn with_fsm(fsm: NbtFsm<'_, D>, data: &'d [u8]) -> Self {
+        Self {
+            fsm: fsm.with_data(data),
+            failed: false,
+        }
+    }
+
+    /// Reads the next fragment, returning None once the input ends between two root tags
+    #[inline(always)]
+    pub fn next_fragment(&mut self) -> NbtResult<Option<NbtFragment<'d>>> {
+        self.next().transpose()
+    }
+
+    /// The number of bytes parsed so far
+    pub fn consumed(&self) -> usize {
+        self.fsm.consumed()
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.fsm.is_idle()
+    }
+
+    /// See [NbtFsm::context]
+    pub fn context(&self) -> FragmentContext {
+        self.fsm.context()
+    }
+
+    /// Returns the parser, e.g. to continue parsing in streaming mode
+    pub fn into_inner(self) -> NbtFsm<'d, D> {
+        self.fsm
+    }
+}
+
+impl<'d, D: Dialect> Iterator for CompleteFsm<'d, D> {
+    type Item = NbtResult<NbtFragment<'d>>;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let result = self.fsm.complete_fragment();
+        if let Some(stats) = &mut self.fsm.stats
+            && let Ok(Some(fragment)) = &result
+        {
+            stats.record(fragment, self.fsm.stack.len());
+        }
+        self.failed = result.is_err();
+        result.transpose()
+    }
+}
+
+impl<T> FsmResult<T> {
+    /// Running out of input is an error when all of it is there
+    #[inline(always)]
+    fn complete(self) -> NbtResult<T> {
+        match self {
+            FsmResult::Found(value) => Ok(value),
+            FsmResult::Needs(_) => Err(unexpected_end()),
+        }
+    }
+}
+
+#[cold]
+fn unexpected_end() -> NbtParseError {
+    NbtParseError::UnexpectedEnd
+}
+
+// The same states as the streaming parser, but every value is taken from the input whole
+impl<'d, D: Dialect> NbtFsm<'d, D> {
+    #[inline(always)]
+    fn complete_fragment(&mut self) -> NbtResult<Option<NbtFragment<'d>>> {
+        if !matches!(self.namestate, NameState::NameComplete) {
+            return self.complete_name().map(Some);
+        }
+        loop {
+            let fragment = match self.state {
+                TagState::Empty => {
+                    let Some(&[tag]) = self.buffer.consume_arr() else {
+                        // Input may only end between root tags
+                        return match self.stack.is_empty() {
+                            true => Ok(None),
+                            false => Err(unexpected_end()),
+                        };
+                    };
+                    let tag = NbtTag::try_from(tag).map_err(|_| invalid_tag(tag))?;
+                    self.compound_header = false;
+                    let nameless = self.nameless_root || !D::NAMED_ROOT;
+                    let namestate = if nameless && self.stack.is_empty() {
+                        NameState::Name(0)
+                    } else {
+                        NameState::NoNameLen
+                    };
+                    self.state = match tag {
+                        NbtTag::End => {
+                            self.end_compound();
+                            return Ok(Some(NbtFragment::End));
+                        }
+                        NbtTag::Compound => {
+                            self.stack.push(Nested::Compound);
+                            self.check_depth(self.stack.len())?;
+                            self.compound_header = true;
+                            self.namestate = namestate;
+                            return Ok(Some(NbtFragment::CompoundTag));
+                        }
+                        NbtTag::Byte => TagState::Byte,
+                        NbtTag::Short => TagState::Short,
+                        NbtTag::Int => TagState::Int,
+                        NbtTag::Long => TagState::Long,
+                        NbtTag::Float => TagState::Float,
+                        NbtTag::Double => TagState::Double,
+                        NbtTag::ByteArray => TagState::ByteArrayNoLength,
+                        NbtTag::String => TagState::StringNoLength,
+                        NbtTag::List => TagState::ListNoTag,
+                        NbtTag::IntArray => TagState::IntArrayNoLength,
+                        NbtTag::LongArray => TagState::LongArrayNoLength,
+                    };
+                    self.namestate = namestate;
+                    return self.complete_name().map(Some);
+                }
+                TagState::ListNoTag => {
+                    let tag = self.capture_tag()?.complete()?;
+                    self.state = TagState::ListNoLength(tag);
+                    continue;
+                }
+                TagState::ListNoLength(tag) => {
+                    let mut len = self.capture_len().complete()??;
+                    if tag == NbtTag::End && len > 0 {
+                        if !self.lenient_end_lists {
+                            return Err(NbtParseError::EndListLength(len));
+                        }
+                        len = 0;
+                    }
+                    self.check_depth(self.stack.len() + 1)?;
+                    self.state = TagState::List(tag, len);
+                    self.list_lens.push(len as u32);
+                    NbtFragment::ListTag(tag, len)
+                }
+                TagState::IntArrayNoLength => {
+                    let len = self.capture_len().complete()??;
+                    self.state = TagState::List(NbtTag::Int, len);
+                    self.list_lens.push(len as u32);
+                    NbtFragment::IntArrayTag(len)
+                }
+                TagState::LongArrayNoLength => {
+                    let len = self.capture_len().complete()??;
+                    self.state = TagState::List(NbtTag::Long, len);
+                    self.list_lens.push(len as u32);
+                    NbtFragment::LongArrayTag(len)
+                }
+                TagState::List(_, 0) | TagState::List(NbtTag::End, _) => {
+                    self.list_lens.pop();
+                    self.finish_value();
+                    continue;
+                }
+                TagState::List(NbtTag::Compound, ref mut len) => {
+                    *len -= 1;
+                    self.push_state();
+                    self.state = TagState::Empty;
+                    self.stack.push(Nested::Compound);
+                    self.check_depth(self.stack.len())?;
+                    self.compound_header = true;
+                    NbtFragment::CompoundTag
+                }
+                TagState::List(tag, ref mut len) => {
+                    let frames = D::LIST_FRAMES || tag == NbtTag::Byte;
+                    let element = match tag {
+                        NbtTag::String => TagState::StringNoLength,
+                        NbtTag::ByteArray => TagState::ByteArrayNoLength,
+                        NbtTag::IntArray => TagState::IntArrayNoLength,
+                        NbtTag::LongArray => TagState::LongArrayNoLength,
+                        NbtTag::List => TagState::ListNoTag,
+                        _ if frames => {
+                            let len = *len;
+                            self.state = TagState::List(tag, 0);
+                            return self.complete_list_frame(tag, len).map(Some);
+                        }
+                        NbtTag::Short => TagState::Short,
+                        NbtTag::Int => TagState::Int,
+                        NbtTag::Long => TagState::Long,
+                        NbtTag::Float => TagState::Float,
+                        _ => TagState::Double,
+                    };
+                    *len -= 1;
+                    self.push_state();
+                    self.state = element;
+                    continue;
+                }
+                TagState::StringNoLength => {
+                    let len = self.capture_ushort().complete()??;
+                    let string = self.buffer.consume(len.into()).ok_or_else(unexpected_end)?;
+                    self.state = TagState::String(0);
+                    if string.is_empty() {
+                        self.finish_value();
+                    }
+                    NbtFragment::StringFrame(string)
+                }
+                TagState::ByteArrayNoLength => {
+                    let len = self.capture_len().complete()??;
+                    let array = self.buffer.consume(len).ok_or_else(unexpected_end)?;
+                    self.state = TagState::ByteArray(0);
+                    if array.is_empty() {
+                        self.finish_value();
+                    }
+                    NbtFragment::ByteArrayFrame(array)
+                }
+                // Only left once the data of a string or byte array has been produced, or when
+                // continuing from a streaming parser
+                TagState::String(len) => {
+                    let string = self.buffer.consume(len).ok_or_else(unexpected_end)?;
+                    self.state = TagState::String(0);
+                    if len == 0 {
+                        self.finish_value();
+                    }
+                    NbtFragment::StringFrame(string)
+                }
+                TagState::ByteArray(len) => {
+                    let array = self.buffer.consume(len).ok_or_else(unexpected_end)?;
+                    self.state = TagState::ByteArray(0);
+                    if len == 0 {
+                        self.finish_value();
+                    }
+                    NbtFragment::ByteArrayFrame(array)
+                }
+                TagState::Byte => {
+                    let &[byte] = self.buffer.consume_arr().ok_or_else(unexpected_end)?;
+                    self.finish_value();
+                    NbtFragment::Byte(byte as i8)
+                }
+                TagState::Short => {
+                    let value = self.capture_short().complete()??;
+                    self.finish_value();
+                    NbtFragment::Short(value)
+                }
+                TagState::Int => {
+                    let value = self.capture_int().complete()??;
+                    self.finish_value();
+                    NbtFragment::Int(value)
+                }
+                TagState::Long => {
+                    let value = self.capture_long().complete()??;
+                    self.finish_value();
+                    NbtFragment::Long(value)
+                }
+                TagState::Float => {
+                    let value = self.capture_float().complete()??;
+                    self.finish_value();
+                    NbtFragment::Float(value)
+                }
+                TagState::Double => {
+                    let value = self.capture_double().complete()??;
+                    self.finish_value();
+                    NbtFragment::Double(value)
+                }
+            };
+            return Ok(Some(fragment));
+        }
+    }
+
+    /// Produces the whole name being read, or the empty frame following it
+    #[inline(always)]
+    fn complete_name(&mut self) -> NbtResult<NbtFragment<'d>> {
+        let len = match self.namestate {
+            NameState::NoNameLen => self.capture_ushort().complete()??,
+            NameState::Name(len) => len,
+            NameState::NameComplete => 0,
+        };
+        let name = self.buffer.consume(len.into()).ok_or_else(unexpected_end)?;
+        self.namestate = match len {
+            0 => NameState::NameComplete,
+            _ => NameState::Name(0),
+        };
+        Ok(NbtFragment::NameFrame(name))
+    }
+
+    /// Produces all `len` elements of a numeric list as one frame
+    #[inline(always)]
+    fn complete_list_frame(&mut self, tag: NbtTag, len: usize) -> NbtResult<NbtFragment<'d>> {
+        Ok(match tag {
+            NbtTag::Byte => NbtFragment::ByteListFrame(self.take_array(len)?),
+            NbtTag::Short => NbtFragment::ShortListFrame(self.take_array(len)?),
+            NbtTag::Int => NbtFragment::IntListFrame(self.take_array(len)?),
+            NbtTag::Long => NbtFragment::LongListFrame(self.take_array(len)?),
+            NbtTag::Float => NbtFragment::FloatListFrame(self.take_array(len)?),
+            _ => NbtFragment::DoubleListFrame(self.take_array(len)?),
+        })
+    }
+
+    #[inline(always)]
+    fn take_array<T: BeRepr>(&mut self, len: usize) -> NbtResult<BeSlice<'d, T>> {
+        let bytes = len.checked_mul(T::BYTES).ok_or_else(unexpected_end)?;
+        let data = self.buffer.consume(bytes).ok_or_else(unexpected_end)?;
+        // SAFETY: the length of data is a multiple of T::BYTES
+        Ok(unsafe { BeSlice::new(data).unwrap_unchecked() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workload::Workload;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn matches_streaming() {
+        let generated = Workload::new(3).to_bytes();
+        let inputs: [&[u8]; 3] = [
+            include_bytes!("../../assets/bigtest.nbt"),
+            include_bytes!("../../assets/chunk_0-0.nbt"),
+            &generated,
+        ];
+        for data in inputs {
+            let mut fsm = NbtFsm::new().with_data(data);
+            let mut streaming = Vec::new();
+            while let FsmResult::Found(fragment) = fsm.next_fragment().unwrap() {
+                streaming.push(fragment);
+            }
+            let complete: Vec<_> = CompleteFsm::new(data).map(Result::unwrap).collect();
+            assert_eq!(streaming, complete);
+
+            let mut truncated = CompleteFsm::new(&data[..data.len() - 1]);
+            assert_eq!(
+                truncated.find_map(Result::err),
+                Some(NbtParseError::UnexpectedEnd)
+            );
+        }
+    }
+
+    #[test]
+    fn continues_streaming() {
+        let data = include_bytes!("../../assets/bigtest.nbt");
+        for split in 0..data.len() {
+            let mut fsm = NbtFsm::new().with_data(&data[..split]);
+            while let FsmResult::Found(_) = fsm.next_fragment().unwrap() {}
+            let rest = &data[fsm.consumed()..];
+            let mut complete = CompleteFsm::with_fsm(fsm, rest);
+            assert!(complete.by_ref().all(|fragment| fragment.is_ok()));
+            assert_eq!(complete.consumed(), rest.len());
+            assert!(complete.is_idle());
+        }
+    }
+
+    #[test]
+    fn fused_after_error() {
+        let mut fsm = CompleteFsm::new(&[10, 0, 0, 0xFF, 0]);
+        assert_eq!(fsm.nth(2), Some(Err(NbtParseError::InvalidTag(0xFF))));
+        assert_eq!(fsm.next(), None);
+    }
+}
@@ -0,0 +1,370 @@
+use core::fmt::Debug;
+
+use alloc::vec::Vec;
+
+use super::{FsmResult, invalid_len};
+use crate::{
+    buf::Buffer,
+    error::{NbtParseError, NbtResult, NbtWriteError},
+};
+
+/// A binary flavour of NBT, chosen at compile time so that [NbtFsm](super::NbtFsm) is
+/// specialised for it and the Java format pays nothing for the others
+///
+/// Dialects whose numbers are not fixed-width big-endian can not hand out numeric lists and arrays
+/// as [BeSlice](crate::view::BeSlice)s: their elements are produced one by one as value
+/// fragments, the same way as elements of any other type. Byte lists and byte arrays are still
+/// produced as frames.
+pub trait Dialect: sealed::Sealed + Debug + Clone + Copy + PartialEq + Default {
+    /// Whether the root tag is followed by a name
+    const NAMED_ROOT: bool;
+}
+
+/// The format of Java Edition files, and of the network protocol before Minecraft 1.20.2
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Java;
+
+/// The network format of Java Edition since Minecraft 1.20.2, where the root tag has no name
+///
+/// [NbtFsm::new_nameless](super::NbtFsm::new_nameless) parses the same format, but decides at
+/// runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct JavaNetwork;
+
+/// The format of Bedrock Edition files, which is [Java] with little-endian numbers and standard
+/// UTF-8 strings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Bedrock;
+
+/// The network format of Bedrock Edition
+///
+/// Ints, longs and the lengths of lists and arrays are zigzag encoded VarInts, and string lengths
+/// are unsigned VarInts. Shorts, floats and doubles are little-endian, and strings are standard
+/// UTF-8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct BedrockNetwork;
+
+impl Dialect for Java {
+    const NAMED_ROOT: bool = true;
+}
+
+impl Dialect for JavaNetwork {
+    const NAMED_ROOT: bool = false;
+}
+
+impl Dialect for Bedrock {
+    const NAMED_ROOT: bool = true;
+}
+
+impl Dialect for BedrockNetwork {
+    const NAMED_ROOT: bool = true;
+}
+
+pub(crate) mod sealed {
+    use super::*;
+
+    /// The encodings of a [Dialect], kept out of the public interface
+    pub trait Sealed {
+        /// Whether numbers are fixed-width big-endian, so numeric lists can be produced as list
+        /// frames
+        const LIST_FRAMES: bool;
+        /// Whether names and strings are Modified UTF-8 rather than standard UTF-8
+        const MODIFIED_UTF8: bool;
+        fn short(buf: &mut Buffer<'_>) -> FsmResult<NbtResult<i16>>;
+        fn int(buf: &mut Buffer<'_>) -> FsmResult<NbtResult<i32>>;
+        fn long(buf: &mut Buffer<'_>) -> FsmResult<NbtResult<i64>>;
+        fn float(buf: &mut Buffer<'_>) -> FsmResult<NbtResult<f32>>;
+        fn double(buf: &mut Buffer<'_>) -> FsmResult<NbtResult<f64>>;
+        /// The length of a list or array
+        ///
+        /// Lengths are checked after asking for more data, which keeps the fixed-width dialects
+        /// as fast as before dialects existed.
+        fn len(buf: &mut Buffer<'_>) -> FsmResult<NbtResult<usize>>;
+        /// The length of a string or name
+        fn string_len(buf: &mut Buffer<'_>) -> FsmResult<NbtResult<u16>>;
+
+        fn put_short(value: i16, out: &mut Vec<u8>);
+        fn put_int(value: i32, out: &mut Vec<u8>);
+        fn put_long(value: i64, out: &mut Vec<u8>);
+        fn put_float(value: f32, out: &mut Vec<u8>);
+        fn put_double(value: f64, out: &mut Vec<u8>);
+        fn put_len(len: usize, out: &mut Vec<u8>) -> Result<(), NbtWriteError>;
+        fn put_string_len(len: usize, out: &mut Vec<u8>) -> Result<(), NbtWriteError>;
+    }
+}
+
+macro_rules! fixed {
+    ($from_bytes:ident; $($name:ident: $t:ty),*) => {
+        $(
+            #[inline(always)]
+            fn $name(buf: &mut Buffer<'_>) -> FsmResult<NbtResult<$t>> {
+                match buf.consume_arr() {
+                    Some(&bytes) => FsmResult::Found(Ok(<$t>::$from_bytes(bytes))),
+                    None => FsmResult::Needs(size_of::<$t>()),
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! put_fixed {
+    ($to_bytes:ident; $($name:ident: $t:ty),*) => {
+        $(
+            #[inline(always)]
+            fn $name(value: $t, out: &mut Vec<u8>) {
+                out.extend_from_slice(&value.$to_bytes());
+            }
+        )*
+    };
+}
+
+macro_rules! fixed_dialect {
+    ($dialect:ty, $from_bytes:ident, $to_bytes:ident, $list_frames:literal, $mutf8:literal) => {
+        impl sealed::Sealed for $dialect {
+            const LIST_FRAMES: bool = $list_frames;
+            const MODIFIED_UTF8: bool = $mutf8;
+            fixed!($from_bytes; short: i16, int: i32, long: i64, float: f32, double: f64);
+            put_fixed!(
+                $to_bytes;
+                put_short: i16, put_int: i32, put_long: i64, put_float: f32, put_double: f64
+            );
+            #[inline(always)]
+            fn len(buf: &mut Buffer<'_>) -> FsmResult<NbtResult<usize>> {
+                match buf.consume_arr() {
+                    Some(&bytes) => {
+                        let len = i32::$from_bytes(bytes);
+                        FsmResult::Found(usize::try_from(len).map_err(|_| invalid_len(len)))
+                    }
+                    None => FsmResult::Needs(4),
+                }
+            }
+            #[inline(always)]
+            fn string_len(buf: &mut Buffer<'_>) -> FsmResult<NbtResult<u16>> {
+                match buf.consume_arr() {
+                    Some(&bytes) => FsmResult::Found(Ok(u16::$from_bytes(bytes))),
+                    None => FsmResult::Needs(2),
+                }
+            }
+            #[inline]
+            fn put_len(len: usize, out: &mut Vec<u8>) -> Result<(), NbtWriteError> {
+                let len = i32::try_from(len).map_err(|_| NbtWriteError::TooManyElements(len))?;
+                out.extend_from_slice(&len.$to_bytes());
+                Ok(())
+            }
+            #[inline]
+            fn put_string_len(len: usize, out: &mut Vec<u8>) -> Result<(), NbtWriteError> {
+                let len = u16::try_from(len).map_err(|_| NbtWriteError::StringTooLong(len))?;
+                out.extend_from_slice(&len.$to_bytes());
+                Ok(())
+            }
+        }
+    };
+}
+
+fixed_dialect!(Java, from_be_bytes, to_be_bytes, true, true);
+fixed_dialect!(JavaNetwork, from_be_bytes, to_be_bytes, true, true);
+fixed_dialect!(Bedrock, from_le_bytes, to_le_bytes, false, false);
+
+impl sealed::Sealed for BedrockNetwork {
+    const LIST_FRAMES: bool = false;
+    const MODIFIED_UTF8: bool = false;
+    fixed!(from_le_bytes; short: i16, float: f32, double: f64);
+    #[inline]
+    fn int(buf: &mut Buffer<'_>) -> FsmResult<NbtResult<i32>> {
+        varint(buf, 32).map_found(|value| value.map(|value| zigzag(value) as i32))
+    }
+    #[inline]
+    fn long(buf: &mut Buffer<'_>) -> FsmResult<NbtResult<i64>> {
+        varint(buf, 64).map_found(|value| value.map(zigzag))
+    }
+    #[inline]
+    fn len(buf: &mut Buffer<'_>) -> FsmResult<NbtResult<usize>> {
+        Self::int(buf).map_found(|len| {
+            let len = len?;
+            usize::try_from(len).map_err(|_| invalid_len(len))
+        })
+    }
+    #[inline]
+    fn string_len(buf: &mut Buffer<'_>) -> FsmResult<NbtResult<u16>> {
+        varint(buf, 32).map_found(|len| {
+            let len = len?;
+            u16::try_from(len).map_err(|_| invalid_len(len as i32))
+        })
+    }
+    put_fixed!(to_le_bytes; put_short: i16, put_float: f32, put_double: f64);
+    #[inline]
+    fn put_int(value: i32, out: &mut Vec<u8>) {
+        put_varint(to_zigzag(value.into()), out);
+    }
+    #[inline]
+    fn put_long(value: i64, out: &mut Vec<u8>) {
+        put_varint(to_zigzag(value), out);
+    }
+    #[inline]
+    fn put_len(len: usize, out: &mut Vec<u8>) -> Result<(), NbtWriteError> {
+        let len = i32::try_from(len).map_err(|_| NbtWriteError::TooManyElements(len))?;
+        Self::put_int(len, out);
+        Ok(())
+    }
+    #[inline]
+    fn put_string_len(len: usize, out: &mut Vec<u8>) -> Result<(), NbtWriteError> {
+        let len = u16::try_from(len).map_err(|_| NbtWriteError::StringTooLong(len))?;
+        put_varint(len.into(), out);
+        Ok(())
+    }
+}
+
+/// Reads a VarInt of at most `bits` bits, asking for one more byte while it is incomplete
+///
+/// The last byte may only set the bits that are left, so the fifth byte of a 32 bit VarInt must
+/// be at most `0x0F`.
+fn varint(buf: &mut Buffer<'_>, bits: u32) -> FsmResult<NbtResult<u64>> {
+    let max_len = bits.div_ceil(7) as usize;
+    let available = buf.available();
+    let mut value = 0;
+    for (idx, &byte) in available.iter().take(max_len).enumerate() {
+        if idx + 1 == max_len && byte >> (bits as usize - 7 * idx) != 0 {
+            return FsmResult::Found(Err(NbtParseError::InvalidVarInt));
+        }
+        value |= u64::from(byte & 0x7F) << (7 * idx);
+        if byte & 0x80 == 0 {
+            buf.consume(idx + 1);
+            return FsmResult::Found(Ok(value));
+        }
+    }
+    if available.len() >= max_len {
+        return FsmResult::Found(Err(NbtParseError::InvalidVarInt));
+    }
+    FsmResult::Needs(available.len() + 1)
+}
+
+const fn zigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+/// Writes a VarInt, seven bits at a time starting with the lowest
+fn put_varint(mut value: u64, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+const fn to_zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        NbtFsm, NbtWriter,
+        value::{NbtCompound, NbtList, NbtValue, NbtValueBuilder},
+    };
+    use alloc::{string::String, vec::Vec};
+
+    fn read<D: Dialect>(dialect: D, data: &[u8]) -> NbtResult<(String, NbtValue)> {
+        let mut fsm = NbtFsm::with_dialect(dialect).with_data(data);
+        let mut builder = NbtValueBuilder::new();
+        loop {
+            match fsm.next_fragment()? {
+                FsmResult::Found(fragment) => {
+                    if let Some(root) = builder.push(fragment)? {
+                        return Ok(root);
+                    }
+                }
+                FsmResult::Needs(_) => return Err(NbtParseError::UnexpectedEnd),
+            }
+        }
+    }
+
+    fn write<D: Dialect>(dialect: D, value: &NbtValue) -> Vec<u8> {
+        let mut writer = NbtWriter::with_dialect(dialect);
+        assert_eq!(writer.push_value("", value), Ok(true));
+        writer.into_inner()
+    }
+
+    fn expected() -> NbtValue {
+        let mut compound = NbtCompound::new();
+        compound.insert("int", NbtValue::Int(-300));
+        compound.insert("long", NbtValue::Long(1 << 40));
+        compound.insert("str", NbtValue::String("hi".into()));
+        let shorts = NbtList::try_from(Vec::from([NbtValue::Short(1), NbtValue::Short(-2)]));
+        compound.insert("shorts", NbtValue::List(shorts.unwrap()));
+        compound.insert("ints", NbtValue::IntArray(Vec::from([7, -7])));
+        NbtValue::Compound(compound)
+    }
+
+    #[test]
+    fn bedrock() {
+        let mut data = Vec::from([10, 0, 0]);
+        data.extend([3, 3, 0, b'i', b'n', b't']);
+        data.extend((-300i32).to_le_bytes());
+        data.extend([4, 4, 0, b'l', b'o', b'n', b'g']);
+        data.extend((1i64 << 40).to_le_bytes());
+        data.extend([8, 3, 0, b's', b't', b'r', 2, 0, b'h', b'i']);
+        data.extend([9, 6, 0, b's', b'h', b'o', b'r', b't', b's', 2, 2, 0, 0, 0]);
+        data.extend([1, 0, 0xFE, 0xFF]);
+        data.extend([11, 4, 0, b'i', b'n', b't', b's', 2, 0, 0, 0]);
+        data.extend([7, 0, 0, 0, 0xF9, 0xFF, 0xFF, 0xFF, 0]);
+        assert_eq!(read(Bedrock, &data), Ok((String::new(), expected())));
+        assert_eq!(write(Bedrock, &expected()), data);
+    }
+
+    #[test]
+    fn bedrock_network() {
+        let mut data = Vec::from([10, 0]);
+        // Zigzag encoded, -300 becomes 599
+        data.extend([3, 3, b'i', b'n', b't', 0xD7, 0x04]);
+        data.extend([
+            4, 4, b'l', b'o', b'n', b'g', 0x80, 0x80, 0x80, 0x80, 0x80, 0x40,
+        ]);
+        data.extend([8, 3, b's', b't', b'r', 2, b'h', b'i']);
+        data.extend([
+            9, 6, b's', b'h', b'o', b'r', b't', b's', 2, 4, 1, 0, 0xFE, 0xFF,
+        ]);
+        data.extend([11, 4, b'i', b'n', b't', b's', 4, 14, 13, 0]);
+        assert_eq!(read(BedrockNetwork, &data), Ok((String::new(), expected())));
+        assert_eq!(write(BedrockNetwork, &expected()), data);
+
+        // Every prefix asks for more data instead of failing
+        for end in 0..data.len() {
+            let mut fsm = NbtFsm::with_dialect(BedrockNetwork).with_data(&data[..end]);
+            while let FsmResult::Found(_) = fsm.next_fragment().unwrap() {}
+        }
+        let overlong = [3, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01];
+        assert_eq!(
+            read(BedrockNetwork, &overlong),
+            Err(NbtParseError::InvalidVarInt)
+        );
+        // The fifth byte of an int only holds its top four bits
+        let overflowing = [3, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0x1F];
+        assert_eq!(
+            read(BedrockNetwork, &overflowing),
+            Err(NbtParseError::InvalidVarInt)
+        );
+        let widest = [3, 0, 0xFE, 0xFF, 0xFF, 0xFF, 0x0F];
+        let (_, value) = read(BedrockNetwork, &widest).unwrap();
+        assert_eq!(value, NbtValue::Int(i32::MAX));
+        let overflowing = [
+            4, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x02,
+        ];
+        assert_eq!(
+            read(BedrockNetwork, &overflowing),
+            Err(NbtParseError::InvalidVarInt)
+        );
+    }
+
+    #[test]
+    fn java_network() {
+        let data = [10, 1, 0, 1, b'b', 5, 0];
+        let (name, value) = read(JavaNetwork, &data).unwrap();
+        assert_eq!(name, "");
+        assert_eq!(
+            value.as_compound().unwrap().get("b"),
+            Some(&NbtValue::Byte(5))
+        );
+        assert_eq!(write(JavaNetwork, &value), data);
+    }
+}
@@ -0,0 +1,76 @@
+use core::{
+    hash::{Hash, Hasher},
+    mem::{self, Discriminant},
+};
+
+use super::NbtFragment;
+
+/// A fragment compared and hashed by the exact bits of its floats, so it can be used in sets,
+/// maps and snapshot comparisons
+///
+/// Unlike [NbtFragment]'s [PartialEq], NaNs with the same bits are equal and `0.0` and `-0.0`
+/// are not.
+///
+/// ```
+/// # use std::collections::HashSet;
+/// # use zeronbt::{ExactFragment, NbtFragment};
+/// let set: HashSet<_> = [NbtFragment::Float(f32::NAN), NbtFragment::Float(-0.0)]
+///     .into_iter()
+///     .map(ExactFragment)
+///     .collect();
+/// assert!(set.contains(&ExactFragment(NbtFragment::Float(f32::NAN))));
+/// assert!(!set.contains(&ExactFragment(NbtFragment::Float(0.0))));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ExactFragment<'s>(pub NbtFragment<'s>);
+
+impl<'s> ExactFragment<'s> {
+    /// The variant of the fragment along with everything it holds, with floats as their bits
+    fn parts(&self) -> (Discriminant<NbtFragment<'s>>, u64, u64, &'s [u8]) {
+        let numbers = |first: u64, second: u64| (first, second, &[][..]);
+        let (first, second, bytes) = match self.0 {
+            NbtFragment::End | NbtFragment::CompoundTag => numbers(0, 0),
+            NbtFragment::Byte(value) => numbers(value as u64, 0),
+            NbtFragment::Short(value) => numbers(value as u64, 0),
+            NbtFragment::Int(value) => numbers(value as u64, 0),
+            NbtFragment::Long(value) => numbers(value as u64, 0),
+            NbtFragment::Float(value) => numbers(value.to_bits() as u64, 0),
+            NbtFragment::Double(value) => numbers(value.to_bits(), 0),
+            NbtFragment::ListTag(tag, len) => numbers(tag as u64, len as u64),
+            NbtFragment::IntArrayTag(len) | NbtFragment::LongArrayTag(len) => {
+                numbers(len as u64, 0)
+            }
+            // Frames are compared by their bytes, which are the bits of their elements
+            NbtFragment::ByteListFrame(frame) => (0, 0, frame.raw_bytes()),
+            NbtFragment::ShortListFrame(frame) => (0, 0, frame.raw_bytes()),
+            NbtFragment::IntListFrame(frame) => (0, 0, frame.raw_bytes()),
+            NbtFragment::LongListFrame(frame) => (0, 0, frame.raw_bytes()),
+            NbtFragment::FloatListFrame(frame) => (0, 0, frame.raw_bytes()),
+            NbtFragment::DoubleListFrame(frame) => (0, 0, frame.raw_bytes()),
+            NbtFragment::NameFrame(data)
+            | NbtFragment::ByteArrayFrame(data)
+            | NbtFragment::StringFrame(data) => (0, 0, data),
+        };
+        (mem::discriminant(&self.0), first, second, bytes)
+    }
+}
+
+impl PartialEq for ExactFragment<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.parts() == other.parts()
+    }
+}
+
+impl Eq for ExactFragment<'_> {}
+
+impl Hash for ExactFragment<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.parts().hash(state);
+    }
+}
+
+impl<'s> From<NbtFragment<'s>> for ExactFragment<'s> {
+    fn from(fragment: NbtFragment<'s>) -> Self {
+        Self(fragment)
+    }
+}
@@ -0,0 +1,69 @@
+/// Settings of an [NbtFsm](super::NbtFsm) that are chosen at runtime, applied with
+/// [NbtFsm::with_options](super::NbtFsm::with_options)
+///
+/// ```
+/// # use zeronbt::{NbtFsm, NbtOptions};
+/// let options = NbtOptions::new().nameless_root(true).min_frame(4096);
+/// let fsm = NbtFsm::new().with_options(options);
+/// assert_eq!(fsm.options(), options);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NbtOptions {
+    pub(super) nameless_root: bool,
+    pub(super) min_frame: usize,
+    pub(super) lenient_end_lists: bool,
+    pub(super) max_depth: usize,
+}
+
+/// How deep compounds and lists may be nested by default, the same limit the game uses
+pub const DEFAULT_MAX_DEPTH: usize = 512;
+
+impl Default for NbtOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NbtOptions {
+    pub const fn new() -> Self {
+        Self {
+            nameless_root: false,
+            min_frame: 0,
+            lenient_end_lists: false,
+            max_depth: DEFAULT_MAX_DEPTH,
+        }
+    }
+
+    /// Expects root tags that are not followed by a name, see [NbtFsm::new_nameless](super::NbtFsm::new_nameless)
+    pub const fn nameless_root(mut self, nameless: bool) -> Self {
+        self.nameless_root = nameless;
+        self
+    }
+
+    /// Holds back frames of names, strings, byte arrays and numeric lists until at least `bytes`
+    /// of them, or the rest of the payload, are buffered
+    ///
+    /// Until then [FsmResult::Needs](super::FsmResult::Needs) asks for the missing data, so a
+    /// slowly arriving payload is produced in a few larger frames instead of one per read.
+    pub const fn min_frame(mut self, bytes: usize) -> Self {
+        self.min_frame = bytes;
+        self
+    }
+
+    /// Reads lists of End tags that declare a length above zero as empty lists, instead of failing
+    /// with [NbtParseError::EndListLength](crate::error::NbtParseError::EndListLength)
+    pub const fn lenient_end_lists(mut self, lenient: bool) -> Self {
+        self.lenient_end_lists = lenient;
+        self
+    }
+
+    /// Fails with [NbtParseError::TooDeep](crate::error::NbtParseError::TooDeep) once more than
+    /// `depth` compounds and lists are nested in each other, [DEFAULT_MAX_DEPTH] by default
+    ///
+    /// Values built from deeper documents are expensive to walk, and recursive consumers of them
+    /// may overflow the stack.
+    pub const fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+}
@@ -0,0 +1,108 @@
+use alloc::vec::Vec;
+use core::mem;
+
+use super::{FragmentContext, FsmResult, NbtFragment, NbtFsm, OwnedNbtFragment};
+use crate::error::NbtResult;
+
+/// An [NbtFsm] that owns its input, so it is `'static` and can be stored in long-lived tasks or
+/// held across await points
+///
+/// Fragments borrow from the parser itself, which keeps them valid until the next call, or can be
+/// taken as [OwnedNbtFragment]s. Any owned byte buffer can be used as input, e.g. `Vec<u8>`,
+/// `Box<[u8]>` or `bytes::Bytes`, while only `Vec<u8>` can be extended with more data.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct NbtFsmOwned<D = Vec<u8>> {
+    data: D,
+    pos: usize,
+    fsm: NbtFsm<'static>,
+}
+
+impl<D: AsRef<[u8]>> NbtFsmOwned<D> {
+    pub fn new(data: D) -> Self {
+        Self::with_fsm(NbtFsm::new(), data)
+    }
+
+    /// Continues parsing with the state of `fsm`, e.g. one created with [NbtFsm::new_nameless]
+    pub fn with_fsm(fsm: NbtFsm<'_>, data: D) -> Self {
+        Self {
+            data,
+            pos: 0,
+            fsm: fsm.with_data(&[]),
+        }
+    }
+
+    pub fn next_fragment(&mut self) -> NbtResult<FsmResult<NbtFragment<'_>>> {
+        let mut fsm = mem::take(&mut self.fsm).with_data(&self.data.as_ref()[self.pos..]);
+        let result = fsm.next_fragment();
+        self.pos += fsm.consumed();
+        self.fsm = fsm.with_data(&[]);
+        result
+    }
+
+    pub fn next_owned(&mut self) -> NbtResult<FsmResult<OwnedNbtFragment>> {
+        Ok(match self.next_fragment()? {
+            FsmResult::Found(fragment) => FsmResult::Found(fragment.into_owned()),
+            FsmResult::Needs(needs) => FsmResult::Needs(needs),
+        })
+    }
+
+    /// The input that has not been parsed yet
+    pub fn remaining(&self) -> &[u8] {
+        &self.data.as_ref()[self.pos..]
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.fsm.is_idle()
+    }
+
+    /// See [NbtFsm::context]
+    pub fn context(&self) -> FragmentContext {
+        self.fsm.context()
+    }
+
+    /// Returns the input, along with the parser state for continuing after the parsed part
+    pub fn into_parts(self) -> (D, usize, NbtFsm<'static>) {
+        (self.data, self.pos, self.fsm)
+    }
+}
+
+impl NbtFsmOwned<Vec<u8>> {
+    /// Appends more input, dropping the data that was already parsed
+    pub fn feed(&mut self, data: &[u8]) {
+        self.data.drain(..self.pos);
+        self.pos = 0;
+        self.data.extend_from_slice(data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::NbtValueBuilder;
+
+    #[test]
+    fn feed_in_pieces() {
+        let data = include_bytes!("../../assets/bigtest.nbt");
+        let mut fsm = NbtFsmOwned::new(data[..1].to_vec());
+        assert_eq!(
+            fsm.next_owned(),
+            Ok(FsmResult::Found(OwnedNbtFragment::CompoundTag))
+        );
+        let mut builder = NbtValueBuilder::new();
+        builder.push(NbtFragment::CompoundTag).unwrap();
+        let mut chunks = data[1..].chunks(3);
+        let root = loop {
+            match fsm.next_fragment().unwrap() {
+                FsmResult::Found(fragment) => {
+                    if let Some(root) = builder.push(fragment).unwrap() {
+                        break root;
+                    }
+                }
+                FsmResult::Needs(_) => fsm.feed(chunks.next().unwrap()),
+            }
+        };
+        assert_eq!(root, crate::value::NbtValue::read(data).unwrap());
+        assert!(fsm.is_idle());
+        assert!(fsm.remaining().is_empty());
+    }
+}
@@ -0,0 +1,115 @@
+use super::{Dialect, FsmResult, NbtFragment, NbtFsm};
+use crate::{NbtTag, error::NbtResult};
+
+/// The structure of a document along with the bytes of its values, see
+/// [NbtFsm::next_raw_fragment]
+///
+/// ```
+/// # use zeronbt::{FsmResult, NbtFsm, NbtTag, RawFragment};
+/// let data = include_bytes!("../../assets/bigtest.nbt");
+/// let mut fsm = NbtFsm::new().with_data(data);
+/// let mut numbers = 0;
+/// while let FsmResult::Found(fragment) = fsm.next_raw_fragment().unwrap() {
+///     if let RawFragment::Number(NbtTag::Int, bytes) = fragment {
+///         assert_eq!(bytes, i32::MAX.to_be_bytes());
+///         numbers += 1;
+///     }
+/// }
+/// assert_eq!(numbers, 1);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RawFragment<'d> {
+    End,
+    CompoundTag,
+    /// A number of the given tag, as it is encoded in the document
+    Number(NbtTag, &'d [u8]),
+    ListTag(NbtTag, usize),
+    IntArrayTag(usize),
+    LongArrayTag(usize),
+    NameFrame(&'d [u8]),
+    /// Part of a string, a byte array or a numeric list or array, as it is encoded in the document
+    ///
+    /// The tag is that of the string or byte array, or of the elements of the list or array.
+    /// Strings and byte arrays end with an empty frame, like
+    /// [StringFrame](NbtFragment::StringFrame)s and [ByteArrayFrame](NbtFragment::ByteArrayFrame)s.
+    Frame(NbtTag, &'d [u8]),
+}
+
+impl<'d, D: Dialect> NbtFsm<'d, D> {
+    /// Reads the next fragment, giving the bytes of numbers and frames instead of their values
+    ///
+    /// This is meant for proxies and indexers that pass values on or only record where they are.
+    /// The bytes of a number are the input consumed by it. Its decoded value is dropped right away,
+    /// which lets the compiler leave out decoding it where this is inlined, and frames are views of
+    /// the input either way.
+    #[inline(always)]
+    pub fn next_raw_fragment(&mut self) -> NbtResult<FsmResult<RawFragment<'d>>> {
+        let start = self.consumed();
+        let fragment = match self.next_fragment()? {
+            FsmResult::Needs(needs) => return Ok(FsmResult::Needs(needs)),
+            FsmResult::Found(fragment) => fragment,
+        };
+        let number = |tag| RawFragment::Number(tag, &self.buffer.consumed()[start..]);
+        Ok(FsmResult::Found(match fragment {
+            NbtFragment::End => RawFragment::End,
+            NbtFragment::CompoundTag => RawFragment::CompoundTag,
+            NbtFragment::Byte(_) => number(NbtTag::Byte),
+            NbtFragment::Short(_) => number(NbtTag::Short),
+            NbtFragment::Int(_) => number(NbtTag::Int),
+            NbtFragment::Long(_) => number(NbtTag::Long),
+            NbtFragment::Float(_) => number(NbtTag::Float),
+            NbtFragment::Double(_) => number(NbtTag::Double),
+            NbtFragment::ListTag(tag, len) => RawFragment::ListTag(tag, len),
+            NbtFragment::IntArrayTag(len) => RawFragment::IntArrayTag(len),
+            NbtFragment::LongArrayTag(len) => RawFragment::LongArrayTag(len),
+            NbtFragment::ByteListFrame(view) => RawFragment::Frame(NbtTag::Byte, view.raw_bytes()),
+            NbtFragment::ShortListFrame(view) => {
+                RawFragment::Frame(NbtTag::Short, view.raw_bytes())
+            }
+            NbtFragment::IntListFrame(view) => RawFragment::Frame(NbtTag::Int, view.raw_bytes()),
+            NbtFragment::LongListFrame(view) => RawFragment::Frame(NbtTag::Long, view.raw_bytes()),
+            NbtFragment::FloatListFrame(view) => {
+                RawFragment::Frame(NbtTag::Float, view.raw_bytes())
+            }
+            NbtFragment::DoubleListFrame(view) => {
+                RawFragment::Frame(NbtTag::Double, view.raw_bytes())
+            }
+            NbtFragment::NameFrame(frame) => RawFragment::NameFrame(frame),
+            NbtFragment::ByteArrayFrame(frame) => RawFragment::Frame(NbtTag::ByteArray, frame),
+            NbtFragment::StringFrame(frame) => RawFragment::Frame(NbtTag::String, frame),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Bedrock;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn raw_numbers() {
+        let data = include_bytes!("../../assets/bigtest.nbt");
+        let mut fsm = NbtFsm::new().with_data(data);
+        let mut numbers = Vec::new();
+        while let FsmResult::Found(fragment) = fsm.next_raw_fragment().unwrap() {
+            if let RawFragment::Number(tag, bytes) = fragment {
+                numbers.push((tag, bytes));
+            }
+        }
+        assert_eq!(numbers.len(), 10);
+        assert!(numbers.contains(&(NbtTag::Short, &32767i16.to_be_bytes()[..])));
+
+        // Bedrock numbers are little endian
+        let data = [2, 1, 0, b's', 0x34, 0x12];
+        let mut fsm = NbtFsm::with_dialect(Bedrock).with_data(&data);
+        let mut fragments = Vec::new();
+        while let FsmResult::Found(fragment) = fsm.next_raw_fragment().unwrap() {
+            fragments.push(fragment);
+        }
+        assert_eq!(
+            fragments.last(),
+            Some(&RawFragment::Number(NbtTag::Short, &[0x34, 0x12][..]))
+        );
+    }
+}
@@ -0,0 +1,157 @@
+//! Serialization of fragment streams, mainly for snapshotting the exact output of the parser
+//!
+//! Fragments are serialized like an externally tagged enum, with list frames as sequences of their
+//! decoded elements. Borrowed and owned fragments serialize identically.
+use ::serde::{Serialize, Serializer, ser::SerializeTupleVariant};
+
+use super::{NbtFragment, OwnedNbtFragment};
+use crate::NbtTag;
+
+const NAME: &str = "NbtFragment";
+
+struct Bytes<'b>(&'b [u8]);
+
+impl Serialize for Bytes<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+fn serialize_list_tag<S: Serializer>(
+    serializer: S,
+    tag: NbtTag,
+    len: usize,
+) -> Result<S::Ok, S::Error> {
+    let mut variant = serializer.serialize_tuple_variant(NAME, 8, "ListTag", 2)?;
+    variant.serialize_field(&tag)?;
+    variant.serialize_field(&len)?;
+    variant.end()
+}
+
+impl Serialize for NbtFragment<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            NbtFragment::End => serializer.serialize_unit_variant(NAME, 0, "End"),
+            NbtFragment::CompoundTag => serializer.serialize_unit_variant(NAME, 1, "CompoundTag"),
+            NbtFragment::Byte(val) => serializer.serialize_newtype_variant(NAME, 2, "Byte", val),
+            NbtFragment::Short(val) => serializer.serialize_newtype_variant(NAME, 3, "Short", val),
+            NbtFragment::Int(val) => serializer.serialize_newtype_variant(NAME, 4, "Int", val),
+            NbtFragment::Long(val) => serializer.serialize_newtype_variant(NAME, 5, "Long", val),
+            NbtFragment::Float(val) => serializer.serialize_newtype_variant(NAME, 6, "Float", val),
+            NbtFragment::Double(val) => {
+                serializer.serialize_newtype_variant(NAME, 7, "Double", val)
+            }
+            NbtFragment::ListTag(tag, len) => serialize_list_tag(serializer, *tag, *len),
+            NbtFragment::IntArrayTag(len) => {
+                serializer.serialize_newtype_variant(NAME, 9, "IntArrayTag", len)
+            }
+            NbtFragment::LongArrayTag(len) => {
+                serializer.serialize_newtype_variant(NAME, 10, "LongArrayTag", len)
+            }
+            NbtFragment::ByteListFrame(view) => {
+                serializer.serialize_newtype_variant(NAME, 11, "ByteListFrame", view)
+            }
+            NbtFragment::ShortListFrame(view) => {
+                serializer.serialize_newtype_variant(NAME, 12, "ShortListFrame", view)
+            }
+            NbtFragment::IntListFrame(view) => {
+                serializer.serialize_newtype_variant(NAME, 13, "IntListFrame", view)
+            }
+            NbtFragment::LongListFrame(view) => {
+                serializer.serialize_newtype_variant(NAME, 14, "LongListFrame", view)
+            }
+            NbtFragment::FloatListFrame(view) => {
+                serializer.serialize_newtype_variant(NAME, 15, "FloatListFrame", view)
+            }
+            NbtFragment::DoubleListFrame(view) => {
+                serializer.serialize_newtype_variant(NAME, 16, "DoubleListFrame", view)
+            }
+            NbtFragment::NameFrame(data) => {
+                serializer.serialize_newtype_variant(NAME, 17, "NameFrame", &Bytes(data))
+            }
+            NbtFragment::ByteArrayFrame(data) => {
+                serializer.serialize_newtype_variant(NAME, 18, "ByteArrayFrame", &Bytes(data))
+            }
+            NbtFragment::StringFrame(data) => {
+                serializer.serialize_newtype_variant(NAME, 19, "StringFrame", &Bytes(data))
+            }
+        }
+    }
+}
+
+impl Serialize for OwnedNbtFragment {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use OwnedNbtFragment as Owned;
+        match self {
+            Owned::End => serializer.serialize_unit_variant(NAME, 0, "End"),
+            Owned::CompoundTag => serializer.serialize_unit_variant(NAME, 1, "CompoundTag"),
+            Owned::Byte(val) => serializer.serialize_newtype_variant(NAME, 2, "Byte", val),
+            Owned::Short(val) => serializer.serialize_newtype_variant(NAME, 3, "Short", val),
+            Owned::Int(val) => serializer.serialize_newtype_variant(NAME, 4, "Int", val),
+            Owned::Long(val) => serializer.serialize_newtype_variant(NAME, 5, "Long", val),
+            Owned::Float(val) => serializer.serialize_newtype_variant(NAME, 6, "Float", val),
+            Owned::Double(val) => serializer.serialize_newtype_variant(NAME, 7, "Double", val),
+            Owned::ListTag(tag, len) => serialize_list_tag(serializer, *tag, *len),
+            Owned::IntArrayTag(len) => {
+                serializer.serialize_newtype_variant(NAME, 9, "IntArrayTag", len)
+            }
+            Owned::LongArrayTag(len) => {
+                serializer.serialize_newtype_variant(NAME, 10, "LongArrayTag", len)
+            }
+            Owned::ByteListFrame(vals) => {
+                serializer.serialize_newtype_variant(NAME, 11, "ByteListFrame", vals)
+            }
+            Owned::ShortListFrame(vals) => {
+                serializer.serialize_newtype_variant(NAME, 12, "ShortListFrame", vals)
+            }
+            Owned::IntListFrame(vals) => {
+                serializer.serialize_newtype_variant(NAME, 13, "IntListFrame", vals)
+            }
+            Owned::LongListFrame(vals) => {
+                serializer.serialize_newtype_variant(NAME, 14, "LongListFrame", vals)
+            }
+            Owned::FloatListFrame(vals) => {
+                serializer.serialize_newtype_variant(NAME, 15, "FloatListFrame", vals)
+            }
+            Owned::DoubleListFrame(vals) => {
+                serializer.serialize_newtype_variant(NAME, 16, "DoubleListFrame", vals)
+            }
+            Owned::NameFrame(data) => {
+                serializer.serialize_newtype_variant(NAME, 17, "NameFrame", &Bytes(data))
+            }
+            Owned::ByteArrayFrame(data) => {
+                serializer.serialize_newtype_variant(NAME, 18, "ByteArrayFrame", &Bytes(data))
+            }
+            Owned::StringFrame(data) => {
+                serializer.serialize_newtype_variant(NAME, 19, "StringFrame", &Bytes(data))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use alloc::vec::Vec;
+
+    use crate::{FsmResult, NbtFsm};
+
+    #[test]
+    fn snapshot() {
+        let mut input = alloc::vec![10, 0, 1, b'r'];
+        input.extend_from_slice(&[9, 0, 1, b'l', 2, 0, 0, 0, 2, 0, 1, 0xFF, 0xFE]);
+        input.extend_from_slice(&[8, 0, 1, b's', 0, 2, b'h', b'i', 0]);
+        let mut fsm = NbtFsm::new().with_data(&input);
+        let mut fragments = Vec::new();
+        while let FsmResult::Found(fragment) = fsm.next_fragment().unwrap() {
+            fragments.push(fragment);
+        }
+        let json = serde_json::to_string(&fragments).unwrap();
+        assert_eq!(
+            json,
+            r#"["CompoundTag",{"NameFrame":[114]},{"NameFrame":[]},{"NameFrame":[108]},{"NameFrame":[]},{"ListTag":["Short",2]},{"ShortListFrame":[1,-2]},{"NameFrame":[115]},{"NameFrame":[]},{"StringFrame":[104,105]},{"StringFrame":[]},"End"]"#
+        );
+        let owned: Vec<_> = fragments.into_iter().map(|f| f.into_owned()).collect();
+        assert_eq!(serde_json::to_string(&owned).unwrap(), json);
+    }
+}
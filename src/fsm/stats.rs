@@ -0,0 +1,37 @@
+use super::NbtFragment;
+use crate::explain::Histogram;
+
+/// Counters of a parser, collected once enabled with [NbtFsm::with_stats](super::NbtFsm::with_stats)
+///
+/// ```
+/// # use zeronbt::{FsmResult, NbtFsm, NbtTag};
+/// let data = include_bytes!("../../assets/bigtest.nbt");
+/// let mut fsm = NbtFsm::new().with_stats().with_data(data);
+/// while let FsmResult::Found(_) = fsm.next_fragment().unwrap() {}
+/// let stats = fsm.stats().unwrap();
+/// assert_eq!(stats.bytes, data.len());
+/// assert_eq!(stats.max_depth, 3);
+/// assert_eq!(stats.tags.get(NbtTag::Compound), 6);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct NbtStats {
+    /// The number of fragments produced
+    pub fragments: usize,
+    /// The number of bytes consumed, over all inputs
+    pub bytes: usize,
+    /// The deepest nesting of compounds and lists that was reached, counting the root
+    pub max_depth: usize,
+    /// The number of inputs given to the parser with [NbtFsm::with_data](super::NbtFsm::with_data)
+    pub refills: usize,
+    /// How often each tag occurred, counted like [explain](crate::explain::explain) does
+    pub tags: Histogram,
+}
+
+impl NbtStats {
+    #[inline]
+    pub(super) fn record(&mut self, fragment: &NbtFragment<'_>, depth: usize) {
+        self.fragments += 1;
+        self.max_depth = self.max_depth.max(depth);
+        self.tags.count(fragment);
+    }
+}
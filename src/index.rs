@@ -0,0 +1,303 @@
+//! An index of where every value of a complete document is, kept up to date across edits
+//!
+//! An [NbtIndex] records the tag and payload range of every compound entry and every element of a
+//! list of strings, lists or compounds. [NbtIndex::splice] replaces the payload of one value,
+//! parsing only the new payload and shifting the offsets of the values after it, so editors can
+//! change a value of a large document without indexing it again.
+//!
+//! ```
+//! # use zeronbt::{index::NbtIndex, value::NbtValue};
+//! let mut data = include_bytes!("../assets/bigtest.nbt").to_vec();
+//! let mut index = NbtIndex::new(&data).unwrap();
+//! let egg = index.find(&"\"nested compound test\".egg.name".parse().unwrap()).unwrap();
+//! index.splice(&mut data, egg, b"\0\x04Eggs").unwrap();
+//! assert_eq!(index, NbtIndex::new(&data).unwrap());
+//! let (_, value) = NbtValue::read(&data).unwrap();
+//! let name = value.get("nested compound test").and_then(|nested| nested.get("egg"));
+//! assert_eq!(name.and_then(|egg| egg.get("name")), Some(&NbtValue::from("Eggs")));
+//! ```
+use alloc::{string::String, vec::Vec};
+use core::ops::Range;
+
+use crate::{
+    FsmResult, NbtFragment, NbtFsm, NbtTag,
+    error::{NbtParseError, NbtResult},
+    levels::{Level, track},
+    mutf8,
+    path::{NbtPath, PathSegment},
+};
+
+/// The values of a document in the order they appear, see the [module docs](self)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NbtIndex {
+    entries: Vec<IndexEntry>,
+}
+
+/// A single value of an [NbtIndex]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// The number of values this one is nested in, 0 for the root
+    pub depth: usize,
+    /// The name of the root or key of a compound entry, None for list elements
+    pub key: Option<String>,
+    pub tag: NbtTag,
+    /// The bytes of the value, without its tag and name
+    pub payload: Range<usize>,
+}
+
+impl NbtIndex {
+    /// Indexes the complete document at the start of `data`
+    pub fn new(data: &[u8]) -> NbtResult<Self> {
+        let (entries, _) = parse(data)?;
+        Ok(Self { entries })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, entry: usize) -> Option<&IndexEntry> {
+        self.entries.get(entry)
+    }
+
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &IndexEntry> {
+        self.entries.iter()
+    }
+
+    /// The entries of the values directly inside `entry`
+    pub fn children(&self, entry: usize) -> impl Iterator<Item = usize> + '_ {
+        let depth = self.entries.get(entry).map_or(0, |entry| entry.depth + 1);
+        (entry + 1..self.subtree_end(entry))
+            .filter(move |&child| self.entries[child].depth == depth)
+    }
+
+    /// Finds the entry of the value `path` selects, if it selects a single one
+    ///
+    /// The elements of numeric lists and arrays are not indexed, so paths into them select
+    /// nothing.
+    pub fn find(&self, path: &NbtPath) -> Option<usize> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        let mut entry = 0;
+        for segment in path.segments() {
+            entry = match segment {
+                PathSegment::Key(key) => self
+                    .children(entry)
+                    .find(|&child| self.entries[child].key.as_deref() == Some(key.as_str()))?,
+                PathSegment::Index(index) => {
+                    let index = if *index < 0 {
+                        self.children(entry)
+                            .count()
+                            .checked_sub(index.unsigned_abs() as usize)?
+                    } else {
+                        *index as usize
+                    };
+                    self.children(entry)
+                        .filter(|&child| self.entries[child].key.is_none())
+                        .nth(index)?
+                }
+                PathSegment::All => return None,
+            };
+        }
+        Some(entry)
+    }
+
+    /// Replaces the payload of `entry` in `data` with `payload`, which has to be a payload of the
+    /// same tag
+    ///
+    /// Only the new payload is parsed, the values before `entry` keep their offsets and the values
+    /// after it are shifted by the change in length. `data` is left unchanged if the payload is
+    /// not valid.
+    ///
+    /// # Panics
+    ///
+    /// If `entry` is out of bounds.
+    pub fn splice(&mut self, data: &mut Vec<u8>, entry: usize, payload: &[u8]) -> NbtResult<()> {
+        let old = self.entries[entry].clone();
+        // The payload is indexed as the root of a document with an empty name
+        let mut document = Vec::with_capacity(payload.len() + 3);
+        document.extend_from_slice(&[old.tag as u8, 0, 0]);
+        document.extend_from_slice(payload);
+        let (mut entries, consumed) = parse(&document)?;
+        if consumed != document.len() {
+            return Err(NbtParseError::UnexpectedFragment);
+        }
+        for new in &mut entries {
+            new.depth += old.depth;
+            new.payload =
+                new.payload.start - 3 + old.payload.start..new.payload.end - 3 + old.payload.start;
+        }
+        entries[0].key = old.key;
+
+        data.splice(old.payload.clone(), payload.iter().copied());
+        let shift = |offset: usize| offset + payload.len() - old.payload.len();
+        for ancestor in &mut self.entries[..entry] {
+            if ancestor.payload.end >= old.payload.end {
+                ancestor.payload.end = shift(ancestor.payload.end);
+            }
+        }
+        let end = self.subtree_end(entry);
+        for after in &mut self.entries[end..] {
+            after.payload = shift(after.payload.start)..shift(after.payload.end);
+        }
+        self.entries.splice(entry..end, entries);
+        Ok(())
+    }
+
+    /// The entry after the last value inside `entry`
+    fn subtree_end(&self, entry: usize) -> usize {
+        let Some(IndexEntry { depth, .. }) = self.entries.get(entry) else {
+            return self.entries.len();
+        };
+        self.entries[entry + 1..]
+            .iter()
+            .position(|after| after.depth <= *depth)
+            .map_or(self.entries.len(), |position| entry + 1 + position)
+    }
+}
+
+/// Indexes the document at the start of `data`, returning its entries and length
+fn parse(data: &[u8]) -> NbtResult<(Vec<IndexEntry>, usize)> {
+    let mut fsm = NbtFsm::new().with_data(data);
+    let mut entries: Vec<IndexEntry> = Vec::new();
+    let mut levels = Vec::new();
+    // The entries of the values that are not complete yet
+    let mut open = Vec::new();
+    let mut name = Vec::new();
+    let mut named = false;
+    // Compounds are announced before their name, and start after it
+    let mut compound = false;
+    loop {
+        let start = fsm.consumed();
+        let FsmResult::Found(fragment) = fsm.next_fragment()? else {
+            return Err(NbtParseError::UnexpectedEnd);
+        };
+        let tag = match &fragment {
+            NbtFragment::CompoundTag if !matches!(levels.last(), Some(Level::List(_))) => {
+                compound = true;
+                None
+            }
+            &NbtFragment::NameFrame([]) if compound => {
+                compound = false;
+                named = true;
+                Some((NbtTag::Compound, fsm.consumed()))
+            }
+            &NbtFragment::NameFrame([]) => {
+                named = true;
+                None
+            }
+            NbtFragment::NameFrame(frame) => {
+                name.extend_from_slice(frame);
+                None
+            }
+            NbtFragment::End => None,
+            _ if matches!(levels.last(), Some(Level::Text | Level::Frames(_))) => None,
+            fragment => Some((fragment_tag(fragment), start)),
+        };
+        if let Some((tag, start)) = tag {
+            let key = if core::mem::take(&mut named) {
+                let key = mutf8::decode(&name).ok_or(NbtParseError::InvalidString)?;
+                let key = key.into_owned();
+                name.clear();
+                Some(key)
+            } else {
+                None
+            };
+            open.push(entries.len());
+            entries.push(IndexEntry {
+                depth: open.len() - 1,
+                key,
+                tag,
+                payload: start..start,
+            });
+        }
+        track(&mut levels, &fragment);
+        while open.len() > levels.len() {
+            if let Some(entry) = open.pop() {
+                entries[entry].payload.end = fsm.consumed();
+            }
+        }
+        if levels.is_empty() && open.is_empty() && !entries.is_empty() {
+            return Ok((entries, fsm.consumed()));
+        }
+    }
+}
+
+/// The tag of the value that `fragment` starts
+fn fragment_tag(fragment: &NbtFragment<'_>) -> NbtTag {
+    match fragment {
+        NbtFragment::End => NbtTag::End,
+        NbtFragment::CompoundTag => NbtTag::Compound,
+        NbtFragment::Byte(_) => NbtTag::Byte,
+        NbtFragment::Short(_) => NbtTag::Short,
+        NbtFragment::Int(_) => NbtTag::Int,
+        NbtFragment::Long(_) => NbtTag::Long,
+        NbtFragment::Float(_) => NbtTag::Float,
+        NbtFragment::Double(_) => NbtTag::Double,
+        NbtFragment::ListTag(..) => NbtTag::List,
+        NbtFragment::IntArrayTag(_) => NbtTag::IntArray,
+        NbtFragment::LongArrayTag(_) => NbtTag::LongArray,
+        NbtFragment::ByteArrayFrame(_) => NbtTag::ByteArray,
+        NbtFragment::StringFrame(_) => NbtTag::String,
+        NbtFragment::ByteListFrame(_)
+        | NbtFragment::ShortListFrame(_)
+        | NbtFragment::IntListFrame(_)
+        | NbtFragment::LongListFrame(_)
+        | NbtFragment::FloatListFrame(_)
+        | NbtFragment::DoubleListFrame(_)
+        | NbtFragment::NameFrame(_) => NbtTag::List,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::NbtValue;
+
+    #[test]
+    fn index_and_splice() {
+        let data = include_bytes!("../assets/bigtest.nbt");
+        let (_, expected) = NbtValue::read(data).unwrap();
+        let index = NbtIndex::new(data).unwrap();
+        assert_eq!(index.get(0).unwrap().key.as_deref(), Some("Level"));
+        assert_eq!(index.get(0).unwrap().payload.end, data.len());
+        for key in ["intTest", "listTest (compound)", "stringTest", "byteTest"] {
+            let mut path = NbtPath::root();
+            path.push(PathSegment::Key(key.into()));
+            let entry = index.get(index.find(&path).unwrap()).unwrap();
+            let mut document = alloc::vec![entry.tag as u8, 0, 0];
+            document.extend_from_slice(&data[entry.payload.clone()]);
+            assert_eq!(
+                NbtValue::read(&document).unwrap().1,
+                expected.get(key).unwrap().clone()
+            );
+        }
+        let element = index
+            .find(&"\"listTest (compound)\"[-1].name".parse().unwrap())
+            .unwrap();
+        assert_eq!(
+            &data[index.get(element).unwrap().payload.clone()],
+            b"\0\x0fCompound tag #1"
+        );
+
+        let mut data = data.to_vec();
+        let mut index = index;
+        let list = index
+            .find(&"\"listTest (compound)\"".parse().unwrap())
+            .unwrap();
+        // An empty list of compounds
+        index.splice(&mut data, list, &[10, 0, 0, 0, 0]).unwrap();
+        assert_eq!(index, NbtIndex::new(&data).unwrap());
+        let short = index.find(&"shortTest".parse().unwrap()).unwrap();
+        assert_eq!(
+            index.splice(&mut data, short, &[1]),
+            Err(NbtParseError::UnexpectedEnd)
+        );
+        assert_eq!(index, NbtIndex::new(&data).unwrap());
+    }
+}
@@ -0,0 +1,14 @@
+//! Conversions between [NbtValue](crate::value::NbtValue) and the value types of other NBT crates
+//!
+//! Each crate is supported behind a feature of the same name, with hematite-nbt imported as `nbt`.
+//! Converting from crates that allow lists mixing element types can fail, as NBT can not represent
+//! those.
+//!
+//! simdnbt needs a nightly compiler, so its conversions are in the separate `zeronbt-simdnbt`
+//! crate.
+#[cfg(feature = "fastnbt")]
+pub mod fastnbt;
+#[cfg(feature = "hematite-nbt")]
+pub mod hematite_nbt;
+#[cfg(feature = "valence_nbt")]
+pub mod valence_nbt;
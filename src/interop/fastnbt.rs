@@ -0,0 +1,135 @@
+use alloc::{string::String, vec::Vec};
+
+use fastnbt::{ByteArray, IntArray, LongArray, Value};
+
+use crate::{
+    NbtFragment,
+    error::{NbtConvertError, NbtResult},
+    value::{NbtList, NbtValue, NbtValueBuilder},
+};
+
+impl From<NbtValue> for Value {
+    fn from(value: NbtValue) -> Self {
+        match value {
+            NbtValue::Byte(value) => Value::Byte(value),
+            NbtValue::Short(value) => Value::Short(value),
+            NbtValue::Int(value) => Value::Int(value),
+            NbtValue::Long(value) => Value::Long(value),
+            NbtValue::Float(value) => Value::Float(value),
+            NbtValue::Double(value) => Value::Double(value),
+            NbtValue::ByteArray(values) => Value::ByteArray(ByteArray::new(values)),
+            NbtValue::String(value) => Value::String(value),
+            NbtValue::List(list) => {
+                Value::List(list.into_values().into_iter().map(Into::into).collect())
+            }
+            NbtValue::Compound(compound) => Value::Compound(
+                compound
+                    .into_iter()
+                    .map(|(key, value)| (key, value.into()))
+                    .collect(),
+            ),
+            NbtValue::IntArray(values) => Value::IntArray(IntArray::new(values)),
+            NbtValue::LongArray(values) => Value::LongArray(LongArray::new(values)),
+        }
+    }
+}
+
+impl From<&NbtValue> for Value {
+    fn from(value: &NbtValue) -> Self {
+        value.clone().into()
+    }
+}
+
+impl TryFrom<Value> for NbtValue {
+    type Error = NbtConvertError;
+
+    /// Fails if a list contains elements of different types
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        Ok(match value {
+            Value::Byte(value) => NbtValue::Byte(value),
+            Value::Short(value) => NbtValue::Short(value),
+            Value::Int(value) => NbtValue::Int(value),
+            Value::Long(value) => NbtValue::Long(value),
+            Value::Float(value) => NbtValue::Float(value),
+            Value::Double(value) => NbtValue::Double(value),
+            Value::String(value) => NbtValue::String(value),
+            Value::ByteArray(values) => NbtValue::ByteArray(values.into_inner()),
+            Value::IntArray(values) => NbtValue::IntArray(values.into_inner()),
+            Value::LongArray(values) => NbtValue::LongArray(values.into_inner()),
+            Value::List(values) => {
+                let mut list = NbtList::new();
+                for value in values {
+                    list.push(value.try_into()?)
+                        .map_err(|value| NbtConvertError::WrongTag {
+                            expected: list.tag(),
+                            found: value.tag(),
+                        })?;
+                }
+                NbtValue::List(list)
+            }
+            Value::Compound(entries) => NbtValue::Compound(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| Ok((key, NbtValue::try_from(value)?)))
+                    .collect::<Result<Vec<_>, NbtConvertError>>()?
+                    .into_iter()
+                    .collect(),
+            ),
+        })
+    }
+}
+
+impl TryFrom<&Value> for NbtValue {
+    type Error = NbtConvertError;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        value.clone().try_into()
+    }
+}
+
+/// Builds a [Value] from the fragments of a single root tag, returning None if they end early
+pub fn from_fragments<'a>(
+    fragments: impl IntoIterator<Item = NbtFragment<'a>>,
+) -> NbtResult<Option<(String, Value)>> {
+    let mut builder = NbtValueBuilder::new();
+    for fragment in fragments {
+        if let Some((name, value)) = builder.push(fragment)? {
+            return Ok(Some((name, value.into())));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FsmResult, NbtFsm};
+    use alloc::vec;
+
+    #[test]
+    fn round_trip() {
+        let data = include_bytes!("../../assets/bigtest.nbt");
+        let (_, value) = NbtValue::read(data).unwrap();
+        let converted = Value::from(&value);
+        assert_eq!(converted, fastnbt::from_bytes::<Value>(data).unwrap());
+        // fastnbt does not keep the order of compound entries
+        let back = NbtValue::try_from(converted).unwrap();
+        assert_eq!(Value::from(back), Value::from(&value));
+
+        let mixed = Value::List(vec![Value::Int(1), Value::Long(2)]);
+        assert!(NbtValue::try_from(mixed).is_err());
+    }
+
+    #[test]
+    fn fragments() {
+        let data = include_bytes!("../../assets/bigtest.nbt");
+        let mut fsm = NbtFsm::new().with_data(data);
+        let fragments = core::iter::from_fn(|| match fsm.next_fragment() {
+            Ok(FsmResult::Found(fragment)) => Some(fragment),
+            _ => None,
+        });
+        let (name, value) = from_fragments(fragments).unwrap().unwrap();
+        assert_eq!(name, "Level");
+        assert_eq!(value, fastnbt::from_bytes::<Value>(data).unwrap());
+    }
+}
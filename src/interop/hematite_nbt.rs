@@ -0,0 +1,168 @@
+use alloc::{string::String, vec::Vec};
+
+use nbt::{Blob, Value};
+
+use crate::{
+    NbtTag,
+    error::{NbtConvertError, NbtParseError, NbtResult},
+    value::{NbtCompound, NbtList, NbtValue},
+};
+
+impl From<NbtValue> for Value {
+    fn from(value: NbtValue) -> Self {
+        match value {
+            NbtValue::Byte(value) => Value::Byte(value),
+            NbtValue::Short(value) => Value::Short(value),
+            NbtValue::Int(value) => Value::Int(value),
+            NbtValue::Long(value) => Value::Long(value),
+            NbtValue::Float(value) => Value::Float(value),
+            NbtValue::Double(value) => Value::Double(value),
+            NbtValue::ByteArray(values) => Value::ByteArray(values),
+            NbtValue::String(value) => Value::String(value),
+            NbtValue::List(list) => {
+                Value::List(list.into_values().into_iter().map(Into::into).collect())
+            }
+            NbtValue::Compound(compound) => Value::Compound(
+                compound
+                    .into_iter()
+                    .map(|(key, value)| (key, value.into()))
+                    .collect(),
+            ),
+            NbtValue::IntArray(values) => Value::IntArray(values),
+            NbtValue::LongArray(values) => Value::LongArray(values),
+        }
+    }
+}
+
+impl From<&NbtValue> for Value {
+    fn from(value: &NbtValue) -> Self {
+        value.clone().into()
+    }
+}
+
+impl TryFrom<Value> for NbtValue {
+    type Error = NbtConvertError;
+
+    /// Fails if a list contains elements of different types
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        Ok(match value {
+            Value::Byte(value) => NbtValue::Byte(value),
+            Value::Short(value) => NbtValue::Short(value),
+            Value::Int(value) => NbtValue::Int(value),
+            Value::Long(value) => NbtValue::Long(value),
+            Value::Float(value) => NbtValue::Float(value),
+            Value::Double(value) => NbtValue::Double(value),
+            Value::ByteArray(values) => NbtValue::ByteArray(values),
+            Value::String(value) => NbtValue::String(value),
+            Value::List(values) => {
+                let mut list = NbtList::new();
+                for value in values {
+                    list.push(value.try_into()?)
+                        .map_err(|value| NbtConvertError::WrongTag {
+                            expected: list.tag(),
+                            found: value.tag(),
+                        })?;
+                }
+                NbtValue::List(list)
+            }
+            Value::Compound(entries) => NbtValue::Compound(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| Ok((key, NbtValue::try_from(value)?)))
+                    .collect::<Result<Vec<_>, NbtConvertError>>()?
+                    .into_iter()
+                    .collect(),
+            ),
+            Value::IntArray(values) => NbtValue::IntArray(values),
+            Value::LongArray(values) => NbtValue::LongArray(values),
+        })
+    }
+}
+
+impl TryFrom<&Value> for NbtValue {
+    type Error = NbtConvertError;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        value.clone().try_into()
+    }
+}
+
+impl From<NbtCompound> for Blob {
+    fn from(compound: NbtCompound) -> Self {
+        to_blob("", compound)
+    }
+}
+
+impl TryFrom<NbtValue> for Blob {
+    type Error = NbtConvertError;
+
+    /// Fails unless the value is a compound, the only root tag a [Blob] can hold
+    fn try_from(value: NbtValue) -> Result<Self, Self::Error> {
+        match value {
+            NbtValue::Compound(compound) => Ok(compound.into()),
+            value => Err(NbtConvertError::WrongTag {
+                expected: NbtTag::Compound,
+                found: value.tag(),
+            }),
+        }
+    }
+}
+
+impl TryFrom<&Blob> for NbtValue {
+    type Error = NbtParseError;
+
+    /// Discards the name of the blob, see [from_blob] to keep it
+    fn try_from(blob: &Blob) -> Result<Self, Self::Error> {
+        from_blob(blob).map(|(_, value)| value)
+    }
+}
+
+/// Wraps a compound in a [Blob] with the given name
+pub fn to_blob(name: &str, compound: NbtCompound) -> Blob {
+    let mut blob = Blob::named(name);
+    for (key, value) in compound {
+        blob.insert(key, Value::from(value))
+            .expect("the elements of an NbtList share a tag");
+    }
+    blob
+}
+
+/// Converts a [Blob] along with its name
+///
+/// [Blob] does not expose its entries, so it is encoded and parsed again. hematite-nbt writes
+/// strings as CESU-8, which only differs from MUTF-8 in how it encodes NUL characters, so this
+/// fails on strings containing those.
+pub fn from_blob(blob: &Blob) -> NbtResult<(String, NbtValue)> {
+    let mut data = Vec::with_capacity(blob.len_bytes());
+    blob.to_writer(&mut data)
+        .expect("writing to a Vec does not fail");
+    NbtValue::read(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn round_trip() {
+        let data = include_bytes!("../../assets/bigtest.nbt");
+        let (name, value) = NbtValue::read(data).unwrap();
+        let blob = Blob::from_reader(&mut &data[..]).unwrap();
+        // hematite-nbt does not keep the order of compound entries
+        let (read_name, read) = from_blob(&blob).unwrap();
+        assert_eq!(read_name, name);
+        assert_eq!(Value::from(read), Value::from(&value));
+
+        let NbtValue::Compound(compound) = value.clone() else {
+            panic!("the root is a compound");
+        };
+        assert_eq!(to_blob(&name, compound), blob);
+        let back = NbtValue::try_from(Value::from(&value)).unwrap();
+        assert_eq!(Value::from(back), Value::from(&value));
+        assert!(Blob::try_from(NbtValue::Int(1)).is_err());
+
+        let mixed = Value::List(vec![Value::Int(1), Value::Long(2)]);
+        assert!(NbtValue::try_from(mixed).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+use valence_nbt::{Compound, List, Value};
+
+use crate::value::{NbtCompound, NbtList, NbtValue};
+
+impl From<NbtValue> for Value {
+    fn from(value: NbtValue) -> Self {
+        match value {
+            NbtValue::Byte(value) => Value::Byte(value),
+            NbtValue::Short(value) => Value::Short(value),
+            NbtValue::Int(value) => Value::Int(value),
+            NbtValue::Long(value) => Value::Long(value),
+            NbtValue::Float(value) => Value::Float(value),
+            NbtValue::Double(value) => Value::Double(value),
+            NbtValue::ByteArray(values) => Value::ByteArray(values),
+            NbtValue::String(value) => Value::String(value),
+            NbtValue::List(list) => Value::List(list.into()),
+            NbtValue::Compound(compound) => Value::Compound(compound.into()),
+            NbtValue::IntArray(values) => Value::IntArray(values),
+            NbtValue::LongArray(values) => Value::LongArray(values),
+        }
+    }
+}
+
+impl From<NbtList> for List {
+    fn from(list: NbtList) -> Self {
+        let mut out = List::new();
+        for value in list.into_values() {
+            let pushed = out.try_push(Value::from(value));
+            debug_assert!(pushed, "the elements of an NbtList share a tag");
+        }
+        out
+    }
+}
+
+impl From<NbtCompound> for Compound {
+    fn from(compound: NbtCompound) -> Self {
+        compound
+            .into_iter()
+            .map(|(key, value)| (key, value.into()))
+            .collect()
+    }
+}
+
+impl From<Value> for NbtValue {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Byte(value) => NbtValue::Byte(value),
+            Value::Short(value) => NbtValue::Short(value),
+            Value::Int(value) => NbtValue::Int(value),
+            Value::Long(value) => NbtValue::Long(value),
+            Value::Float(value) => NbtValue::Float(value),
+            Value::Double(value) => NbtValue::Double(value),
+            Value::ByteArray(values) => NbtValue::ByteArray(values),
+            Value::String(value) => NbtValue::String(value),
+            Value::List(list) => NbtValue::List(list.into()),
+            Value::Compound(compound) => NbtValue::Compound(compound.into()),
+            Value::IntArray(values) => NbtValue::IntArray(values),
+            Value::LongArray(values) => NbtValue::LongArray(values),
+        }
+    }
+}
+
+impl From<List> for NbtList {
+    fn from(list: List) -> Self {
+        let mut out = NbtList::new();
+        for value in list {
+            out.push(value.into())
+                .expect("the elements of a valence_nbt list share a tag");
+        }
+        out
+    }
+}
+
+impl From<Compound> for NbtCompound {
+    fn from(compound: Compound) -> Self {
+        compound
+            .into_iter()
+            .map(|(key, value)| (key, NbtValue::from(value)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let data = include_bytes!("../../assets/chunk_0-0.nbt");
+        let (_, value) = NbtValue::read(data).unwrap();
+        let NbtValue::Compound(compound) = value.clone() else {
+            panic!("the root is a compound");
+        };
+        let converted = Compound::from(compound);
+        assert!(matches!(
+            converted.get("sections"),
+            Some(Value::List(List::Compound(_)))
+        ));
+        // Compounds are sorted by key without the preserve_order feature of valence_nbt
+        let back = NbtValue::Compound(converted.clone().into());
+        assert_eq!(Value::from(back), Value::Compound(converted));
+        assert_eq!(
+            Value::from(NbtValue::List(NbtList::new())),
+            Value::List(List::End)
+        );
+    }
+}
@@ -0,0 +1,125 @@
+//! Adapters for reading NBT from blocking and async IO sources, and from sources of any kind
+//! through [Refill]
+use alloc::{boxed::Box, vec};
+use core::mem;
+
+use crate::{FsmResult, NbtFragment, NbtFsm, error::NbtParseError};
+
+mod driver;
+pub use driver::{Driver, FnRefill, Refill, refill_fn};
+#[cfg(feature = "embedded-io")]
+mod embedded;
+#[cfg(feature = "embedded-io")]
+pub use embedded::EmbeddedNbtReader;
+#[cfg(feature = "embedded-io-async")]
+mod embedded_async;
+#[cfg(feature = "embedded-io-async")]
+pub use embedded_async::AsyncEmbeddedNbtReader;
+#[cfg(feature = "futures")]
+mod futures;
+#[cfg(feature = "futures")]
+pub use futures::AsyncNbtReader;
+#[cfg(feature = "std")]
+mod pipeline;
+#[cfg(feature = "std")]
+pub use pipeline::PipelineReader;
+mod progress;
+pub use progress::{Progress, ProgressHook};
+#[cfg(feature = "std")]
+mod read;
+#[cfg(feature = "std")]
+pub use read::NbtReader;
+#[cfg(feature = "std")]
+mod stream;
+#[cfg(feature = "std")]
+pub use stream::{CompoundReader, ListReader, NbtStreamReader, PayloadReader, ValueReader};
+
+const DEFAULT_CAPACITY: usize = 8 * 1024;
+const MIN_CAPACITY: usize = 16;
+
+/// The buffer and parser state shared by all the reader adapters
+#[derive(Debug)]
+struct Input {
+    buf: Box<[u8]>,
+    start: usize,
+    end: usize,
+    fsm: NbtFsm<'static>,
+    eof: bool,
+    progress: Option<ProgressHook>,
+}
+
+enum Step<'a> {
+    Found(NbtFragment<'a>),
+    Needs(usize),
+    End,
+}
+
+impl Input {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buf: vec![0; capacity.max(MIN_CAPACITY)].into_boxed_slice(),
+            start: 0,
+            end: 0,
+            fsm: NbtFsm::new(),
+            eof: false,
+            progress: None,
+        }
+    }
+
+    /// Parses the next fragment out of the buffered data
+    ///
+    /// # Safety
+    /// The returned fragment borrows the buffer, which must not be refilled while it is alive.
+    /// The lifetime is left unbounded so that the readers can return the fragment from inside
+    /// their refill loops, which the borrow checker would reject otherwise.
+    unsafe fn step<'a>(&mut self) -> Result<Step<'a>, NbtParseError> {
+        let data = &self.buf[self.start..self.end];
+        // SAFETY: upheld by the caller
+        let data: &'a [u8] = unsafe { core::slice::from_raw_parts(data.as_ptr(), data.len()) };
+        let mut fsm = mem::take(&mut self.fsm).with_data(data);
+        let result = fsm.next_fragment();
+        self.start += fsm.consumed();
+        if let Some(progress) = &mut self.progress {
+            let fragment = match &result {
+                Ok(FsmResult::Found(fragment)) => Some(fragment),
+                _ => None,
+            };
+            progress.advance(fsm.consumed(), &fsm, fragment);
+        }
+        self.fsm = fsm.with_data(&[]);
+        match result? {
+            FsmResult::Found(fragment) => Ok(Step::Found(fragment)),
+            FsmResult::Needs(needs) if !self.eof => Ok(Step::Needs(needs)),
+            FsmResult::Needs(_) if self.fsm.is_idle() && self.start == self.end => {
+                if let Some(progress) = &mut self.progress {
+                    progress.end();
+                }
+                Ok(Step::End)
+            }
+            FsmResult::Needs(_) => Err(NbtParseError::UnexpectedEnd),
+        }
+    }
+
+    /// Moves the unparsed data to the front of the buffer, growing it to fit `needs` bytes, and
+    /// returns the free space after it
+    fn spare(&mut self, needs: usize) -> &mut [u8] {
+        self.buf.copy_within(self.start..self.end, 0);
+        self.end -= self.start;
+        self.start = 0;
+        if needs > self.buf.len() {
+            let mut buf =
+                vec![0; needs.checked_next_power_of_two().unwrap_or(needs)].into_boxed_slice();
+            buf[..self.end].copy_from_slice(&self.buf[..self.end]);
+            self.buf = buf;
+        }
+        &mut self.buf[self.end..]
+    }
+
+    /// Records that `read` bytes were written to the [Input::spare] slice, returning whether
+    /// `needs` bytes are available now
+    fn filled(&mut self, read: usize, needs: usize) -> bool {
+        self.end += read;
+        self.eof = read == 0;
+        self.eof || self.end - self.start >= needs
+    }
+}
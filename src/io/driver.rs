@@ -0,0 +1,178 @@
+use alloc::string::String;
+
+use super::{DEFAULT_CAPACITY, Input, ProgressHook, Step};
+use crate::{
+    NbtFragment,
+    convert::FromNbt,
+    error::NbtRefillError,
+    value::{NbtValue, NbtValueBuilder},
+};
+
+/// A source of bytes for a [Driver], for IO APIs that the crate has no reader for
+///
+/// Blocking `std::io::Read` sources are covered by `NbtReader`, and `embedded_io::Read` ones by
+/// `EmbeddedNbtReader`.
+pub trait Refill {
+    type Error;
+
+    /// Writes the next bytes of the input to the start of `buf`, returning how many were written
+    ///
+    /// Returning 0 marks the end of the input.
+    fn refill(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error>;
+}
+
+/// Copies out the slice, advancing it past the copied bytes
+impl Refill for &[u8] {
+    type Error = core::convert::Infallible;
+
+    fn refill(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let len = buf.len().min(self.len());
+        let (data, rest) = self.split_at(len);
+        buf[..len].copy_from_slice(data);
+        *self = rest;
+        Ok(len)
+    }
+}
+
+/// A [Refill] source calling a closure, see [refill_fn]
+#[derive(Debug, Clone)]
+pub struct FnRefill<F>(F);
+
+/// Creates a [Refill] source from a closure with the signature of [Refill::refill]
+///
+/// ```
+/// # use zeronbt::io::{Driver, refill_fn};
+/// let mut data = &include_bytes!("../../assets/bigtest.nbt")[..];
+/// let source = refill_fn(|buf: &mut [u8]| {
+///     // Hands out one byte at a time
+///     let Some((&byte, rest)) = data.split_first() else {
+///         return Ok::<_, ()>(0);
+///     };
+///     buf[0] = byte;
+///     data = rest;
+///     Ok(1)
+/// });
+/// let (name, _) = Driver::new(source).read_value().unwrap().unwrap();
+/// assert_eq!(name, "Level");
+/// ```
+pub fn refill_fn<F, E>(f: F) -> FnRefill<F>
+where
+    F: FnMut(&mut [u8]) -> Result<usize, E>,
+{
+    FnRefill(f)
+}
+
+impl<F, E> Refill for FnRefill<F>
+where
+    F: FnMut(&mut [u8]) -> Result<usize, E>,
+{
+    type Error = E;
+
+    fn refill(&mut self, buf: &mut [u8]) -> Result<usize, E> {
+        (self.0)(buf)
+    }
+}
+
+/// Parses NBT from any [Refill] source, refilling an internal buffer whenever the parser needs
+/// more data
+///
+/// This is the refill loop every other reader of this module runs, for sources of any kind.
+#[derive(Debug)]
+pub struct Driver<F> {
+    source: F,
+    input: Input,
+}
+
+impl<F: Refill> Driver<F> {
+    pub fn new(source: F) -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY, source)
+    }
+
+    pub fn with_capacity(capacity: usize, source: F) -> Self {
+        Self {
+            source,
+            input: Input::with_capacity(capacity),
+        }
+    }
+
+    /// Reports how far the input has been parsed to `hook`
+    pub fn with_progress(mut self, hook: ProgressHook) -> Self {
+        self.input.progress = Some(hook);
+        self
+    }
+
+    pub fn get_ref(&self) -> &F {
+        &self.source
+    }
+
+    pub fn get_mut(&mut self) -> &mut F {
+        &mut self.source
+    }
+
+    /// Returns the source, discarding any data that was buffered but not parsed yet
+    pub fn into_inner(self) -> F {
+        self.source
+    }
+
+    /// Reads the next fragment, returning None once the source ends between two root tags
+    pub fn next_fragment(&mut self) -> Result<Option<NbtFragment<'_>>, NbtRefillError<F::Error>> {
+        loop {
+            // SAFETY: the buffer is only refilled on Needs, when no fragment is alive
+            match unsafe { self.input.step()? } {
+                Step::Found(fragment) => return Ok(Some(fragment)),
+                Step::End => return Ok(None),
+                Step::Needs(needs) => loop {
+                    let read = self
+                        .source
+                        .refill(self.input.spare(needs))
+                        .map_err(NbtRefillError::Refill)?;
+                    if self.input.filled(read, needs) {
+                        break;
+                    }
+                },
+            }
+        }
+    }
+
+    /// Reads the next complete root tag, returning None once the source ends
+    pub fn read_value(&mut self) -> Result<Option<(String, NbtValue)>, NbtRefillError<F::Error>> {
+        let mut builder = NbtValueBuilder::new();
+        while let Some(fragment) = self.next_fragment()? {
+            if let Some(root) = builder.push(fragment)? {
+                return Ok(Some(root));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Reads the next root tag and converts it to `T`, returning None once the source ends
+    pub fn read_as<T: FromNbt>(&mut self) -> Result<Option<T>, NbtRefillError<F::Error>> {
+        match self.read_value()? {
+            Some((_, value)) => Ok(Some(T::from_nbt(&value)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::NbtParseError;
+
+    #[test]
+    fn drive_slices() {
+        let data = include_bytes!("../../assets/bigtest.nbt");
+        let expected = NbtValue::read(data).unwrap();
+        // The smallest buffer, which splits names and strings into many frames
+        let mut driver = Driver::with_capacity(0, &data[..]);
+        assert_eq!(driver.read_value().unwrap(), Some(expected));
+        assert_eq!(driver.read_value().unwrap(), None);
+        assert!(driver.get_ref().is_empty());
+
+        let mut driver = Driver::new(&data[..100]);
+        assert!(matches!(
+            driver.read_value(),
+            Err(NbtRefillError::Parse(NbtParseError::UnexpectedEnd))
+        ));
+    }
+}
@@ -0,0 +1,114 @@
+use alloc::string::String;
+use embedded_io::Read;
+
+use super::{DEFAULT_CAPACITY, Input, Step};
+use crate::{
+    NbtFragment,
+    convert::FromNbt,
+    error::NbtEmbeddedError,
+    value::{NbtValue, NbtValueBuilder},
+};
+
+/// The [embedded_io::Read] counterpart of `NbtReader`, for `no_std` targets
+#[derive(Debug)]
+pub struct EmbeddedNbtReader<R> {
+    reader: R,
+    input: Input,
+}
+
+impl<R: Read> EmbeddedNbtReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY, reader)
+    }
+
+    pub fn with_capacity(capacity: usize, reader: R) -> Self {
+        Self {
+            reader,
+            input: Input::with_capacity(capacity),
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Returns the underlying reader, discarding any data that was buffered but not parsed yet
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads the next fragment, returning None once the source ends between two root tags
+    pub fn next_fragment(&mut self) -> Result<Option<NbtFragment<'_>>, NbtEmbeddedError<R::Error>> {
+        loop {
+            // SAFETY: the buffer is only refilled on Needs, when no fragment is alive
+            match unsafe { self.input.step()? } {
+                Step::Found(fragment) => return Ok(Some(fragment)),
+                Step::End => return Ok(None),
+                Step::Needs(needs) => loop {
+                    let read = self
+                        .reader
+                        .read(self.input.spare(needs))
+                        .map_err(NbtEmbeddedError::Io)?;
+                    if self.input.filled(read, needs) {
+                        break;
+                    }
+                },
+            }
+        }
+    }
+
+    /// Reads the next complete root tag, returning None once the source ends
+    pub fn read_value(&mut self) -> Result<Option<(String, NbtValue)>, NbtEmbeddedError<R::Error>> {
+        let mut builder = NbtValueBuilder::new();
+        while let Some(fragment) = self.next_fragment()? {
+            if let Some(root) = builder.push(fragment)? {
+                return Ok(Some(root));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Reads the next root tag and converts it to `T`, returning None once the source ends
+    pub fn read_as<T: FromNbt>(&mut self) -> Result<Option<T>, NbtEmbeddedError<R::Error>> {
+        match self.read_value()? {
+            Some((_, value)) => Ok(Some(T::from_nbt(&value)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use core::convert::Infallible;
+    use embedded_io::ErrorType;
+
+    /// Hands out at most a few bytes per call, to exercise the refill path
+    pub struct Trickle<'a>(pub &'a [u8]);
+
+    impl ErrorType for Trickle<'_> {
+        type Error = Infallible;
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, Infallible> {
+            let len = buf.len().min(self.0.len()).min(7);
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn read_bigtest() {
+        let data = include_bytes!("../../assets/bigtest.nbt");
+        let expected = NbtValue::read(data).unwrap();
+        let mut reader = EmbeddedNbtReader::with_capacity(0, Trickle(data));
+        assert_eq!(reader.read_value().unwrap(), Some(expected));
+        assert!(reader.read_value().unwrap().is_none());
+    }
+}
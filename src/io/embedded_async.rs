@@ -0,0 +1,112 @@
+use alloc::string::String;
+use embedded_io_async::Read;
+
+use super::{DEFAULT_CAPACITY, Input, Step};
+use crate::{
+    NbtFragment,
+    convert::FromNbt,
+    error::NbtEmbeddedError,
+    value::{NbtValue, NbtValueBuilder},
+};
+
+/// The [embedded_io_async::Read] counterpart of `NbtReader`, suspending whenever the parser needs
+/// more data
+#[derive(Debug)]
+pub struct AsyncEmbeddedNbtReader<R> {
+    reader: R,
+    input: Input,
+}
+
+impl<R: Read> AsyncEmbeddedNbtReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY, reader)
+    }
+
+    pub fn with_capacity(capacity: usize, reader: R) -> Self {
+        Self {
+            reader,
+            input: Input::with_capacity(capacity),
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Returns the underlying reader, discarding any data that was buffered but not parsed yet
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads the next fragment, returning None once the source ends between two root tags
+    pub async fn next_fragment(
+        &mut self,
+    ) -> Result<Option<NbtFragment<'_>>, NbtEmbeddedError<R::Error>> {
+        loop {
+            // SAFETY: the buffer is only refilled on Needs, when no fragment is alive
+            match unsafe { self.input.step()? } {
+                Step::Found(fragment) => return Ok(Some(fragment)),
+                Step::End => return Ok(None),
+                Step::Needs(needs) => loop {
+                    let read = self
+                        .reader
+                        .read(self.input.spare(needs))
+                        .await
+                        .map_err(NbtEmbeddedError::Io)?;
+                    if self.input.filled(read, needs) {
+                        break;
+                    }
+                },
+            }
+        }
+    }
+
+    /// Reads the next complete root tag, returning None once the source ends
+    pub async fn read_value(
+        &mut self,
+    ) -> Result<Option<(String, NbtValue)>, NbtEmbeddedError<R::Error>> {
+        let mut builder = NbtValueBuilder::new();
+        while let Some(fragment) = self.next_fragment().await? {
+            if let Some(root) = builder.push(fragment)? {
+                return Ok(Some(root));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Reads the next root tag and converts it to `T`, returning None once the source ends
+    pub async fn read_as<T: FromNbt>(&mut self) -> Result<Option<T>, NbtEmbeddedError<R::Error>> {
+        match self.read_value().await? {
+            Some((_, value)) => Ok(Some(T::from_nbt(&value)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::embedded::tests::Trickle;
+    use core::convert::Infallible;
+
+    impl Read for Trickle<'_> {
+        async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Infallible> {
+            embedded_io::Read::read(self, buf)
+        }
+    }
+
+    #[test]
+    fn read_bigtest() {
+        let data = include_bytes!("../../assets/bigtest.nbt");
+        let expected = NbtValue::read(data).unwrap();
+        let mut reader = AsyncEmbeddedNbtReader::with_capacity(0, Trickle(data));
+        futures_executor::block_on(async {
+            assert_eq!(reader.read_value().await.unwrap(), Some(expected));
+            assert!(reader.read_value().await.unwrap().is_none());
+        });
+    }
+}
@@ -0,0 +1,116 @@
+use alloc::string::String;
+use core::{future::poll_fn, pin::Pin};
+use futures_io::AsyncRead;
+use std::io::ErrorKind;
+
+use super::{DEFAULT_CAPACITY, Input, Step};
+use crate::{
+    NbtFragment,
+    convert::FromNbt,
+    error::NbtIoError,
+    value::{NbtValue, NbtValueBuilder},
+};
+
+/// The [AsyncRead] counterpart of [NbtReader](super::NbtReader), awaiting the source whenever the
+/// parser needs more data
+#[derive(Debug)]
+pub struct AsyncNbtReader<R> {
+    reader: R,
+    input: Input,
+}
+
+impl<R: AsyncRead + Unpin> AsyncNbtReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY, reader)
+    }
+
+    pub fn with_capacity(capacity: usize, reader: R) -> Self {
+        Self {
+            reader,
+            input: Input::with_capacity(capacity),
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Returns the underlying reader, discarding any data that was buffered but not parsed yet
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads the next fragment, returning None once the source ends between two root tags
+    pub async fn next_fragment(&mut self) -> Result<Option<NbtFragment<'_>>, NbtIoError> {
+        loop {
+            // SAFETY: the buffer is only refilled on Needs, when no fragment is alive
+            match unsafe { self.input.step()? } {
+                Step::Found(fragment) => return Ok(Some(fragment)),
+                Step::End => return Ok(None),
+                Step::Needs(needs) => loop {
+                    let spare = self.input.spare(needs);
+                    let read =
+                        match poll_fn(|cx| Pin::new(&mut self.reader).poll_read(cx, spare)).await {
+                            Ok(read) => read,
+                            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                            Err(err) => return Err(err.into()),
+                        };
+                    if self.input.filled(read, needs) {
+                        break;
+                    }
+                },
+            }
+        }
+    }
+
+    /// Reads the next complete root tag, returning None once the source ends
+    pub async fn read_value(&mut self) -> Result<Option<(String, NbtValue)>, NbtIoError> {
+        let mut builder = NbtValueBuilder::new();
+        while let Some(fragment) = self.next_fragment().await? {
+            if let Some(root) = builder.push(fragment)? {
+                return Ok(Some(root));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Reads the next root tag and converts it to `T`, returning None once the source ends
+    pub async fn read_as<T: FromNbt>(&mut self) -> Result<Option<T>, NbtIoError> {
+        match self.read_value().await? {
+            Some((_, value)) => Ok(Some(T::from_nbt(&value)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::read::tests::Trickle;
+    use core::task::{Context, Poll};
+
+    impl AsyncRead for Trickle<'_> {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<std::io::Result<usize>> {
+            Poll::Ready(std::io::Read::read(self.get_mut(), buf))
+        }
+    }
+
+    #[test]
+    fn read_bigtest() {
+        let data = include_bytes!("../../assets/bigtest.nbt");
+        let expected = NbtValue::read(data).unwrap();
+        let mut reader = AsyncNbtReader::with_capacity(0, Trickle(data));
+        futures_executor::block_on(async {
+            assert_eq!(reader.read_value().await.unwrap(), Some(expected));
+            assert!(reader.read_value().await.unwrap().is_none());
+        });
+    }
+}
@@ -0,0 +1,141 @@
+use alloc::vec::Vec;
+use std::{
+    io::{self, ErrorKind, Read},
+    sync::mpsc::{Receiver, SyncSender, TryRecvError, sync_channel},
+    thread,
+};
+
+use super::NbtReader;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+const DEPTH: usize = 4;
+
+/// A [Read] implementation fed by a background thread reading from another source
+///
+/// The source (usually a decompressor) is read on its own thread in chunks, which are passed
+/// through a bounded queue, so the source can never get more than `depth` chunks ahead of the
+/// parser. Emptied chunks are sent back to be reused, so no allocations happen once the pipeline
+/// is running.
+///
+/// Dropping the reader stops the background thread once its current read returns.
+#[derive(Debug)]
+pub struct PipelineReader {
+    filled: Receiver<io::Result<Vec<u8>>>,
+    empty: SyncSender<Vec<u8>>,
+    chunk: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl PipelineReader {
+    pub fn spawn<R: Read + Send + 'static>(reader: R) -> io::Result<Self> {
+        Self::with_config(reader, CHUNK_SIZE, DEPTH)
+    }
+
+    /// Spawns the reading thread, which reads chunks of up to `chunk_size` bytes and keeps at most
+    /// `depth` of them queued
+    pub fn with_config<R: Read + Send + 'static>(
+        mut reader: R,
+        chunk_size: usize,
+        depth: usize,
+    ) -> io::Result<Self> {
+        let depth = depth.max(1);
+        let (filled_tx, filled) = sync_channel(depth);
+        let (empty, empty_rx) = sync_channel::<Vec<u8>>(depth + 1);
+        thread::Builder::new()
+            .name("zeronbt-pipeline".into())
+            .spawn(move || {
+                loop {
+                    let mut chunk = match empty_rx.try_recv() {
+                        Ok(chunk) => chunk,
+                        Err(TryRecvError::Empty) => Vec::with_capacity(chunk_size),
+                        Err(TryRecvError::Disconnected) => return,
+                    };
+                    chunk.resize(chunk_size.max(1), 0);
+                    let read = loop {
+                        match reader.read(&mut chunk) {
+                            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                            read => break read,
+                        }
+                    };
+                    let last = !matches!(read, Ok(read) if read > 0);
+                    let message = read.map(|read| {
+                        chunk.truncate(read);
+                        chunk
+                    });
+                    if filled_tx.send(message).is_err() || last {
+                        return;
+                    }
+                }
+            })?;
+        Ok(Self {
+            filled,
+            empty,
+            chunk: Vec::new(),
+            pos: 0,
+            done: false,
+        })
+    }
+}
+
+impl Read for PipelineReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.chunk.len() {
+            if self.done {
+                return Ok(0);
+            }
+            let Ok(chunk) = self.filled.recv() else {
+                return Err(io::Error::other("the pipeline thread stopped unexpectedly"));
+            };
+            let chunk = chunk.inspect_err(|_| self.done = true)?;
+            if chunk.is_empty() {
+                self.done = true;
+            }
+            let used = core::mem::replace(&mut self.chunk, chunk);
+            self.pos = 0;
+            // The thread allocates a new chunk if this one can't be returned
+            let _ = self.empty.try_send(used);
+        }
+        let len = buf.len().min(self.chunk.len() - self.pos);
+        buf[..len].copy_from_slice(&self.chunk[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+impl NbtReader<PipelineReader> {
+    /// Reads `reader` on a background thread and parses the data on the current one
+    pub fn threaded<R: Read + Send + 'static>(reader: R) -> io::Result<Self> {
+        Ok(NbtReader::new(PipelineReader::spawn(reader)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::NbtValue;
+
+    #[test]
+    fn read_bigtest() {
+        let data = include_bytes!("../../assets/bigtest.nbt");
+        let expected = NbtValue::read(data).unwrap();
+        let mut reader = NbtReader::new(PipelineReader::with_config(&data[..], 100, 2).unwrap());
+        assert_eq!(reader.read_value().unwrap(), Some(expected));
+        assert!(reader.read_value().unwrap().is_none());
+    }
+
+    struct Failing;
+
+    impl Read for Failing {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("broken"))
+        }
+    }
+
+    #[test]
+    fn forwards_errors() {
+        let mut reader = PipelineReader::spawn(Failing).unwrap();
+        assert!(reader.read(&mut [0; 16]).is_err());
+        assert_eq!(reader.read(&mut [0; 16]).unwrap(), 0);
+    }
+}
@@ -0,0 +1,228 @@
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::fmt;
+
+use crate::{
+    FragmentContext, NbtFragment, NbtFsm,
+    levels::{Level, track},
+    mutf8::StringPolicy,
+    path::{NbtPath, PathSegment},
+};
+
+/// How far a reader got, passed to the callback of a [ProgressHook]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress {
+    /// The bytes parsed so far, which does not include data that was read but not parsed yet
+    pub consumed: u64,
+    /// The size of the input, if it was given to the hook
+    pub total: Option<u64>,
+    /// The value the parser is in, the root if it is between root tags
+    pub path: NbtPath,
+}
+
+impl Progress {
+    /// The share of the input that was parsed, between 0 and 1, if the total is known
+    pub fn fraction(&self) -> Option<f64> {
+        let total = self.total.filter(|&total| total > 0)?;
+        Some((self.consumed as f64 / total as f64).min(1.0))
+    }
+}
+
+type Callback = Box<dyn FnMut(&Progress) + Send>;
+
+/// Reports the progress of a reader to a callback every few bytes, see
+/// `NbtReader::with_progress` and [Driver::with_progress](super::Driver::with_progress)
+///
+/// The callback is called once the interval has been parsed since it was last called, and once
+/// more when the input ends.
+///
+/// ```
+/// # use std::sync::{Arc, Mutex};
+/// # use zeronbt::io::{Driver, Progress, ProgressHook};
+/// let data = include_bytes!("../../assets/bigtest.nbt");
+/// let reports = Arc::new(Mutex::new(Vec::new()));
+/// let hook = ProgressHook::new({
+///     let reports = reports.clone();
+///     move |progress: &Progress| reports.lock().unwrap().push(progress.clone())
+/// })
+/// .every(512)
+/// .total(data.len() as u64);
+/// let mut driver = Driver::new(&data[..]).with_progress(hook);
+/// while driver.next_fragment().unwrap().is_some() {}
+///
+/// let reports = reports.lock().unwrap();
+/// assert_eq!(reports.len(), 3);
+/// assert!(reports[1].path.to_string().starts_with("\"byteArrayTest"));
+/// assert_eq!(reports[2].fraction(), Some(1.0));
+/// ```
+pub struct ProgressHook {
+    callback: Callback,
+    interval: u64,
+    total: Option<u64>,
+    consumed: u64,
+    reported: u64,
+    paths: Paths,
+}
+
+impl ProgressHook {
+    /// Creates a hook calling `callback` every 1 MiB
+    pub fn new(callback: impl FnMut(&Progress) + Send + 'static) -> Self {
+        Self {
+            callback: Box::new(callback),
+            interval: 1024 * 1024,
+            total: None,
+            consumed: 0,
+            reported: 0,
+            paths: Paths::default(),
+        }
+    }
+
+    /// Calls the callback every `interval` bytes instead
+    pub fn every(mut self, interval: u64) -> Self {
+        self.interval = interval.max(1);
+        self
+    }
+
+    /// The size of the input, e.g. the length of the file being read
+    pub fn total(mut self, total: u64) -> Self {
+        self.total = Some(total);
+        self
+    }
+
+    /// Records that the parser consumed `consumed` more bytes and produced `fragment`
+    pub(super) fn advance(
+        &mut self,
+        consumed: usize,
+        fsm: &NbtFsm<'_>,
+        fragment: Option<&NbtFragment<'_>>,
+    ) {
+        self.consumed += consumed as u64;
+        if let Some(fragment) = fragment {
+            self.paths.track(fsm.context(), fragment);
+        }
+        if self.consumed - self.reported >= self.interval {
+            self.report();
+        }
+    }
+
+    /// Reports the end of the input, unless it was just reported
+    pub(super) fn end(&mut self) {
+        if self.reported != self.consumed {
+            self.report();
+        }
+    }
+
+    fn report(&mut self) {
+        self.reported = self.consumed;
+        let mut path = NbtPath::root();
+        // The root has no segment of its own
+        for segment in self.paths.segments.iter().skip(1) {
+            path.push(segment.clone());
+        }
+        (self.callback)(&Progress {
+            consumed: self.consumed,
+            total: self.total,
+            path,
+        });
+    }
+}
+
+impl fmt::Debug for ProgressHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProgressHook")
+            .field("interval", &self.interval)
+            .field("total", &self.total)
+            .field("consumed", &self.consumed)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Tracks the path of the innermost open value of a fragment stream
+#[derive(Debug, Default)]
+struct Paths {
+    levels: Vec<Level>,
+    /// The segment leading to each open value
+    segments: Vec<PathSegment>,
+    name: Vec<u8>,
+    name_done: bool,
+    /// Compounds are announced before their name, which becomes their segment once complete
+    compound_pending: bool,
+}
+
+impl Paths {
+    fn track(&mut self, context: FragmentContext, fragment: &NbtFragment<'_>) {
+        match *fragment {
+            NbtFragment::NameFrame([]) => {
+                self.name_done = true;
+                if core::mem::take(&mut self.compound_pending) {
+                    let key = self.key();
+                    if let Some(segment) = self.segments.last_mut() {
+                        *segment = PathSegment::Key(key);
+                    }
+                }
+                return;
+            }
+            NbtFragment::NameFrame(data) => {
+                if core::mem::take(&mut self.name_done) {
+                    self.name.clear();
+                }
+                self.name.extend_from_slice(data);
+                return;
+            }
+            _ => {}
+        }
+        let open = self.levels.len();
+        track(&mut self.levels, fragment);
+        if self.levels.len() > open {
+            let segment = match context {
+                FragmentContext::Element(index) => PathSegment::Index(index as i32),
+                _ if matches!(fragment, NbtFragment::CompoundTag) => {
+                    self.compound_pending = true;
+                    PathSegment::Key(Default::default())
+                }
+                _ => PathSegment::Key(self.key()),
+            };
+            self.segments.push(segment);
+        } else {
+            self.segments.truncate(self.levels.len());
+        }
+    }
+
+    fn key(&self) -> String {
+        let key = StringPolicy::Lossy.decode_name(&self.name);
+        key.unwrap_or_default().into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use super::*;
+    use crate::io::Driver;
+    use alloc::string::ToString;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn paths() {
+        let data = include_bytes!("../../assets/bigtest.nbt");
+        let paths = Arc::new(Mutex::new(Vec::new()));
+        let hook = ProgressHook::new({
+            let paths = paths.clone();
+            move |progress: &Progress| paths.lock().unwrap().push(progress.path.to_string())
+        })
+        .every(1);
+        let mut driver = Driver::new(&data[..]).with_progress(hook);
+        while driver.next_fragment().unwrap().is_some() {}
+
+        let paths = paths.lock().unwrap();
+        for path in [
+            "",
+            "stringTest",
+            "\"nested compound test\".ham.name",
+            "\"listTest (long)\"",
+            "\"listTest (compound)\"[1]",
+            "\"listTest (compound)\"[1].name",
+        ] {
+            assert!(paths.iter().any(|found| found == path), "{path}");
+        }
+    }
+}
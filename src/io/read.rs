@@ -0,0 +1,170 @@
+use alloc::string::String;
+use std::io::{ErrorKind, Read};
+
+use super::{DEFAULT_CAPACITY, Input, ProgressHook, Step};
+use crate::{
+    NbtFragment,
+    convert::FromNbt,
+    error::NbtIoError,
+    value::{NbtValue, NbtValueBuilder},
+};
+
+/// Parses NBT from a [Read] source, refilling an internal buffer whenever the parser needs more
+/// data
+///
+/// The reader is not wrapped in a [std::io::BufReader] internally, and any decompression (e.g.
+/// `flate2::read::GzDecoder`) has to be applied before passing it in.
+#[derive(Debug)]
+pub struct NbtReader<R> {
+    reader: R,
+    input: Input,
+}
+
+impl<R: Read> NbtReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY, reader)
+    }
+
+    pub fn with_capacity(capacity: usize, reader: R) -> Self {
+        Self {
+            reader,
+            input: Input::with_capacity(capacity),
+        }
+    }
+
+    /// Reports how far the input has been parsed to `hook`
+    pub fn with_progress(mut self, hook: ProgressHook) -> Self {
+        self.input.progress = Some(hook);
+        self
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Returns the underlying reader, discarding any data that was buffered but not parsed yet
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads the next fragment, returning None once the source ends between two root tags
+    pub fn next_fragment(&mut self) -> Result<Option<NbtFragment<'_>>, NbtIoError> {
+        loop {
+            // SAFETY: the buffer is only refilled on Needs, when no fragment is alive
+            match unsafe { self.input.step()? } {
+                Step::Found(fragment) => return Ok(Some(fragment)),
+                Step::End => return Ok(None),
+                Step::Needs(needs) => loop {
+                    let read = match self.reader.read(self.input.spare(needs)) {
+                        Ok(read) => read,
+                        Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                        Err(err) => return Err(err.into()),
+                    };
+                    if self.input.filled(read, needs) {
+                        break;
+                    }
+                },
+            }
+        }
+    }
+
+    /// Reads the next complete root tag, returning None once the source ends
+    pub fn read_value(&mut self) -> Result<Option<(String, NbtValue)>, NbtIoError> {
+        let mut builder = NbtValueBuilder::new();
+        while let Some(fragment) = self.next_fragment()? {
+            if let Some(root) = builder.push(fragment)? {
+                return Ok(Some(root));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Parses the next root tag without building it, returning false once the source ends
+    ///
+    /// This checks that the data is well-formed without allocating for its contents.
+    pub fn skip_value(&mut self) -> Result<bool, NbtIoError> {
+        while self.next_fragment()?.is_some() {
+            if self.input.fsm.is_idle() {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Reads the next root tag and converts it to `T`, returning None once the source ends
+    pub fn read_as<T: FromNbt>(&mut self) -> Result<Option<T>, NbtIoError> {
+        match self.read_value()? {
+            Some((_, value)) => Ok(Some(T::from_nbt(&value)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<R: Read> Iterator for NbtReader<R> {
+    type Item = Result<(String, NbtValue), NbtIoError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_value().transpose()
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use crate::error::NbtParseError;
+    use std::vec::Vec;
+
+    /// Hands out at most a few bytes per call, to exercise the refill path
+    pub struct Trickle<'a>(pub &'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = buf.len().min(self.0.len()).min(7);
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn read_bigtest() {
+        let data = include_bytes!("../../assets/bigtest.nbt");
+        let expected = NbtValue::read(data).unwrap();
+        let mut reader = NbtReader::with_capacity(0, Trickle(data));
+        assert_eq!(reader.read_value().unwrap(), Some(expected));
+        assert!(reader.read_value().unwrap().is_none());
+    }
+
+    #[test]
+    fn multiple_roots() {
+        let data = include_bytes!("../../assets/bigtest.nbt");
+        let data: Vec<u8> = data.iter().chain(data).copied().collect();
+        assert_eq!(NbtReader::new(data.as_slice()).count(), 2);
+    }
+
+    #[test]
+    fn skip_values() {
+        let data = include_bytes!("../../assets/bigtest.nbt");
+        let data: Vec<u8> = data.iter().chain(data).copied().collect();
+        let mut reader = NbtReader::new(Trickle(&data));
+        assert!(reader.skip_value().unwrap());
+        assert!(reader.read_value().unwrap().is_some());
+        assert!(!reader.skip_value().unwrap());
+        let mut reader = NbtReader::new(&data[..100]);
+        assert!(reader.skip_value().is_err());
+    }
+
+    #[test]
+    fn truncated() {
+        let data = include_bytes!("../../assets/bigtest.nbt");
+        let mut reader = NbtReader::new(&data[..data.len() - 1]);
+        assert!(matches!(
+            reader.read_value(),
+            Err(NbtIoError::Parse(NbtParseError::UnexpectedEnd))
+        ));
+    }
+}
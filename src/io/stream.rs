@@ -0,0 +1,624 @@
+use alloc::{string::String, vec::Vec};
+use std::io::{self, ErrorKind, Read};
+
+use super::NbtReader;
+use crate::{
+    NbtFragment, NbtTag,
+    convert::FromNbt,
+    error::{NbtIoError, NbtParseError},
+    levels::{Level, track},
+    mutf8,
+    value::{NbtValue, NbtValueBuilder},
+    view::BeSlice,
+};
+
+/// A cursor over the documents of a [Read] source, navigated like a tree
+///
+/// [next_root](NbtStreamReader::next_root) hands out a [ValueReader] for each root tag, which is
+/// read with typed getters or opened as a [CompoundReader] or [ListReader] to walk its children
+/// one at a time. Only the fragment being looked at is buffered, so arbitrarily large documents
+/// can be read. Values that are dropped without being read are skipped.
+///
+/// ```
+/// # use zeronbt::io::NbtStreamReader;
+/// let data = include_bytes!("../../assets/bigtest.nbt");
+/// let mut reader = NbtStreamReader::new(&data[..]);
+/// let (name, root) = reader.next_root()?.unwrap();
+/// assert_eq!(name, "Level");
+/// let mut level = root.compound()?;
+/// while let Some((name, value)) = level.next_entry()? {
+///     if name == "intTest" {
+///         assert_eq!(value.int()?, 2147483647);
+///     }
+/// }
+/// # Ok::<_, zeronbt::error::NbtIoError>(())
+/// ```
+#[derive(Debug)]
+pub struct NbtStreamReader<R> {
+    reader: NbtReader<R>,
+    /// The values that are open at the position of the reader
+    levels: Vec<Level>,
+    name: Vec<u8>,
+    /// The first frame of the string or byte array a [ValueReader] was handed out for
+    frame: Vec<u8>,
+}
+
+/// The part of a value that is read to find out its type
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Head {
+    Compound,
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    /// The first frame is kept in [NbtStreamReader::frame]
+    String,
+    ByteArray,
+    List(NbtTag, usize),
+    IntArray(usize),
+    LongArray(usize),
+}
+
+impl<R: Read> NbtStreamReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self::from_reader(NbtReader::new(reader))
+    }
+
+    /// Wraps a reader, e.g. one created with [NbtReader::with_capacity]
+    pub fn from_reader(reader: NbtReader<R>) -> Self {
+        Self {
+            reader,
+            levels: Vec::new(),
+            name: Vec::new(),
+            frame: Vec::new(),
+        }
+    }
+
+    pub fn get_ref(&self) -> &NbtReader<R> {
+        &self.reader
+    }
+
+    pub fn into_inner(self) -> NbtReader<R> {
+        self.reader
+    }
+
+    /// Reads up to the next root tag, returning its name and value, or None once the source ends
+    ///
+    /// The rest of the previous root is skipped if it was not read completely.
+    pub fn next_root(&mut self) -> Result<Option<(String, ValueReader<'_, R>)>, NbtIoError> {
+        self.skip_to(0)?;
+        let head = match self.reader.next_fragment()? {
+            None => return Ok(None),
+            Some(NbtFragment::CompoundTag) => {
+                self.levels.push(Level::Compound);
+                self.read_name(None)?;
+                Head::Compound
+            }
+            Some(NbtFragment::NameFrame(name)) => {
+                let name = name.to_vec();
+                self.read_name(Some(&name))?;
+                self.head()?
+            }
+            Some(_) => return Err(NbtParseError::UnexpectedFragment.into()),
+        };
+        let name = self.decode_name()?;
+        Ok(Some((name, ValueReader::new(self, head, 0))))
+    }
+
+    /// Reads the next fragment, keeping track of the values it opens and closes
+    fn next(&mut self) -> Result<NbtFragment<'_>, NbtIoError> {
+        let fragment = self
+            .reader
+            .next_fragment()?
+            .ok_or(NbtParseError::UnexpectedEnd)?;
+        track(&mut self.levels, &fragment);
+        Ok(fragment)
+    }
+
+    /// Skips fragments until only `depth` values are open
+    fn skip_to(&mut self, depth: usize) -> Result<(), NbtIoError> {
+        while self.levels.len() > depth {
+            self.next()?;
+        }
+        Ok(())
+    }
+
+    /// Reads name frames up to the empty one, starting with `first` if it was read already
+    fn read_name(&mut self, first: Option<&[u8]>) -> Result<(), NbtIoError> {
+        self.name.clear();
+        let mut more = match first {
+            Some(data) => {
+                self.name.extend_from_slice(data);
+                !data.is_empty()
+            }
+            None => true,
+        };
+        while more {
+            let fragment = self
+                .reader
+                .next_fragment()?
+                .ok_or(NbtParseError::UnexpectedEnd)?;
+            let NbtFragment::NameFrame(data) = fragment else {
+                return Err(NbtParseError::UnexpectedFragment.into());
+            };
+            more = !data.is_empty();
+            self.name.extend_from_slice(data);
+        }
+        Ok(())
+    }
+
+    fn decode_name(&self) -> Result<String, NbtIoError> {
+        let name = mutf8::decode(&self.name).ok_or(NbtParseError::InvalidString)?;
+        Ok(name.into_owned())
+    }
+
+    /// Reads the first fragment of a value that is not a compound entry
+    fn head(&mut self) -> Result<Head, NbtIoError> {
+        let mut frame = core::mem::take(&mut self.frame);
+        frame.clear();
+        let head = match self.next()? {
+            NbtFragment::CompoundTag => Head::Compound,
+            NbtFragment::Byte(value) => Head::Byte(value),
+            NbtFragment::Short(value) => Head::Short(value),
+            NbtFragment::Int(value) => Head::Int(value),
+            NbtFragment::Long(value) => Head::Long(value),
+            NbtFragment::Float(value) => Head::Float(value),
+            NbtFragment::Double(value) => Head::Double(value),
+            NbtFragment::StringFrame(data) => {
+                frame.extend_from_slice(data);
+                Head::String
+            }
+            NbtFragment::ByteArrayFrame(data) => {
+                frame.extend_from_slice(data);
+                Head::ByteArray
+            }
+            NbtFragment::ListTag(tag, len) => Head::List(tag, len),
+            NbtFragment::IntArrayTag(len) => Head::IntArray(len),
+            NbtFragment::LongArrayTag(len) => Head::LongArray(len),
+            _ => return Err(NbtParseError::UnexpectedFragment.into()),
+        };
+        self.frame = frame;
+        Ok(head)
+    }
+}
+
+/// A single value of a stream, which is read with one of the consuming methods
+///
+/// Dropping the reader skips the value.
+#[derive(Debug)]
+pub struct ValueReader<'r, R> {
+    stream: &'r mut NbtStreamReader<R>,
+    head: Head,
+    /// The number of values that are open around this one
+    depth: usize,
+}
+
+macro_rules! getters {
+    ($($(#[$doc:meta])* $name:ident, $variant:ident, $t:ty;)*) => {
+        $(
+            $(#[$doc])*
+            pub fn $name(self) -> Result<$t, NbtIoError> {
+                match self.head {
+                    Head::$variant(value) => Ok(value),
+                    _ => Err(NbtParseError::UnexpectedType.into()),
+                }
+            }
+        )*
+    };
+}
+
+impl<'r, R: Read> ValueReader<'r, R> {
+    fn new(stream: &'r mut NbtStreamReader<R>, head: Head, depth: usize) -> Self {
+        Self {
+            stream,
+            head,
+            depth,
+        }
+    }
+
+    pub fn tag(&self) -> NbtTag {
+        match self.head {
+            Head::Compound => NbtTag::Compound,
+            Head::Byte(_) => NbtTag::Byte,
+            Head::Short(_) => NbtTag::Short,
+            Head::Int(_) => NbtTag::Int,
+            Head::Long(_) => NbtTag::Long,
+            Head::Float(_) => NbtTag::Float,
+            Head::Double(_) => NbtTag::Double,
+            Head::String => NbtTag::String,
+            Head::ByteArray => NbtTag::ByteArray,
+            Head::List(..) => NbtTag::List,
+            Head::IntArray(_) => NbtTag::IntArray,
+            Head::LongArray(_) => NbtTag::LongArray,
+        }
+    }
+
+    getters!(
+        byte, Byte, i8;
+        short, Short, i16;
+        int, Int, i32;
+        long, Long, i64;
+        float, Float, f32;
+        double, Double, f64;
+    );
+
+    pub fn string(self) -> Result<String, NbtIoError> {
+        if self.head != Head::String {
+            return Err(NbtParseError::UnexpectedType.into());
+        }
+        let data = self.collect()?;
+        let string = mutf8::decode(&data).ok_or(NbtParseError::InvalidString)?;
+        Ok(string.into_owned())
+    }
+
+    pub fn byte_array(self) -> Result<Vec<i8>, NbtIoError> {
+        if self.head != Head::ByteArray {
+            return Err(NbtParseError::UnexpectedType.into());
+        }
+        let data = self.collect()?;
+        Ok(data.into_iter().map(|byte| byte as i8).collect())
+    }
+
+    /// Reads the rest of a value and builds it
+    pub fn value(self) -> Result<NbtValue, NbtIoError> {
+        let mut builder = NbtValueBuilder::new();
+        let frame = core::mem::take(&mut self.stream.frame);
+        let first = match self.head {
+            Head::Compound => {
+                builder.push(NbtFragment::CompoundTag)?;
+                NbtFragment::NameFrame(&[])
+            }
+            Head::Byte(value) => NbtFragment::Byte(value),
+            Head::Short(value) => NbtFragment::Short(value),
+            Head::Int(value) => NbtFragment::Int(value),
+            Head::Long(value) => NbtFragment::Long(value),
+            Head::Float(value) => NbtFragment::Float(value),
+            Head::Double(value) => NbtFragment::Double(value),
+            Head::String => NbtFragment::StringFrame(&frame),
+            Head::ByteArray => NbtFragment::ByteArrayFrame(&frame),
+            Head::List(tag, len) => NbtFragment::ListTag(tag, len),
+            Head::IntArray(len) => NbtFragment::IntArrayTag(len),
+            Head::LongArray(len) => NbtFragment::LongArrayTag(len),
+        };
+        if self.head != Head::Compound {
+            builder.push(NbtFragment::NameFrame(&[]))?;
+        }
+        let mut root = builder.push(first)?;
+        while root.is_none() && self.stream.levels.len() > self.depth {
+            let fragment = self.stream.next()?;
+            root = builder.push(fragment)?;
+        }
+        let (_, value) = root.ok_or(NbtParseError::UnexpectedFragment)?;
+        Ok(value)
+    }
+
+    /// Reads the rest of a value and converts it to `T`
+    pub fn read_as<T: FromNbt>(self) -> Result<T, NbtIoError> {
+        Ok(T::from_nbt(&self.value()?)?)
+    }
+
+    /// Opens a compound to read its entries one at a time
+    pub fn compound(self) -> Result<CompoundReader<'r, R>, NbtIoError> {
+        if self.head != Head::Compound {
+            return Err(NbtParseError::UnexpectedType.into());
+        }
+        Ok(CompoundReader {
+            depth: self.depth + 1,
+            stream: self.stream,
+        })
+    }
+
+    /// Opens a list to read its elements one at a time, int and long arrays are read as lists of
+    /// ints and longs
+    pub fn list(self) -> Result<ListReader<'r, R>, NbtIoError> {
+        let (tag, len) = match self.head {
+            Head::List(tag, len) => (tag, len),
+            Head::IntArray(len) => (NbtTag::Int, len),
+            Head::LongArray(len) => (NbtTag::Long, len),
+            _ => return Err(NbtParseError::UnexpectedType.into()),
+        };
+        Ok(ListReader {
+            depth: self.depth + 1,
+            stream: self.stream,
+            tag,
+            remaining: len,
+            frame: Vec::new(),
+            pos: 0,
+        })
+    }
+
+    /// Reads the data of a string, byte array, int array or long array as it arrives, without
+    /// collecting it
+    ///
+    /// Strings are read as Modified UTF-8 and int and long arrays as big-endian numbers.
+    pub fn payload(self) -> Result<PayloadReader<'r, R>, NbtIoError> {
+        if !matches!(
+            self.head,
+            Head::String | Head::ByteArray | Head::IntArray(_) | Head::LongArray(_)
+        ) {
+            return Err(NbtParseError::UnexpectedType.into());
+        }
+        Ok(PayloadReader {
+            data: core::mem::take(&mut self.stream.frame),
+            pos: 0,
+            stream: self.stream,
+            depth: self.depth,
+        })
+    }
+
+    /// Skips the value, which dropping the reader does as well once the next value is read
+    pub fn skip(self) -> Result<(), NbtIoError> {
+        self.stream.skip_to(self.depth)
+    }
+
+    /// The data of a string or byte array
+    fn collect(self) -> Result<Vec<u8>, NbtIoError> {
+        let mut data = core::mem::take(&mut self.stream.frame);
+        while self.stream.levels.len() > self.depth {
+            match self.stream.next()? {
+                NbtFragment::StringFrame(frame) | NbtFragment::ByteArrayFrame(frame) => {
+                    data.extend_from_slice(frame)
+                }
+                _ => return Err(NbtParseError::UnexpectedFragment.into()),
+            }
+        }
+        Ok(data)
+    }
+}
+
+/// The data of a string or array, pulled from the source one frame at a time
+#[derive(Debug)]
+pub struct PayloadReader<'r, R> {
+    stream: &'r mut NbtStreamReader<R>,
+    depth: usize,
+    /// The frame being handed out
+    data: Vec<u8>,
+    pos: usize,
+}
+
+impl<R: Read> PayloadReader<'_, R> {
+    fn fill(&mut self) -> Result<(), NbtIoError> {
+        self.data.clear();
+        self.pos = 0;
+        while self.data.is_empty() && self.stream.levels.len() > self.depth {
+            match self.stream.next()? {
+                NbtFragment::StringFrame(data) | NbtFragment::ByteArrayFrame(data) => {
+                    self.data.extend_from_slice(data)
+                }
+                NbtFragment::IntListFrame(values) => {
+                    self.data.extend_from_slice(values.raw_bytes())
+                }
+                NbtFragment::LongListFrame(values) => {
+                    self.data.extend_from_slice(values.raw_bytes())
+                }
+                _ => return Err(NbtParseError::UnexpectedFragment.into()),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for PayloadReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.data.len() {
+            self.fill().map_err(|err| match err {
+                NbtIoError::Io(err) => err,
+                err => io::Error::new(ErrorKind::InvalidData, err),
+            })?;
+        }
+        let len = buf.len().min(self.data.len() - self.pos);
+        buf[..len].copy_from_slice(&self.data[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+/// The entries of a compound, read in order
+#[derive(Debug)]
+pub struct CompoundReader<'r, R> {
+    stream: &'r mut NbtStreamReader<R>,
+    /// The number of values that are open inside the compound
+    depth: usize,
+}
+
+impl<R: Read> CompoundReader<'_, R> {
+    /// Reads up to the next entry, returning its name and value, or None after the last one
+    ///
+    /// The rest of the previous entry is skipped if it was not read completely.
+    pub fn next_entry(&mut self) -> Result<Option<(String, ValueReader<'_, R>)>, NbtIoError> {
+        if self.stream.levels.len() < self.depth {
+            return Ok(None);
+        }
+        self.stream.skip_to(self.depth)?;
+        let head = match self.stream.next()? {
+            NbtFragment::End => return Ok(None),
+            NbtFragment::CompoundTag => {
+                self.stream.read_name(None)?;
+                Head::Compound
+            }
+            NbtFragment::NameFrame(name) => {
+                let name = name.to_vec();
+                self.stream.read_name(Some(&name))?;
+                self.stream.head()?
+            }
+            _ => return Err(NbtParseError::UnexpectedFragment.into()),
+        };
+        let name = self.stream.decode_name()?;
+        Ok(Some((
+            name,
+            ValueReader::new(self.stream, head, self.depth),
+        )))
+    }
+
+    /// Skips the remaining entries
+    pub fn skip(self) -> Result<(), NbtIoError> {
+        self.stream.skip_to(self.depth - 1)
+    }
+}
+
+/// The elements of a list, read in order
+#[derive(Debug)]
+pub struct ListReader<'r, R> {
+    stream: &'r mut NbtStreamReader<R>,
+    /// The number of values that are open inside the list
+    depth: usize,
+    tag: NbtTag,
+    remaining: usize,
+    /// The rest of the frame the elements of numeric lists are taken from
+    frame: Vec<u8>,
+    pos: usize,
+}
+
+impl<R: Read> ListReader<'_, R> {
+    pub fn tag(&self) -> NbtTag {
+        self.tag
+    }
+
+    /// The number of elements that were not read yet
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// Reads up to the next element, returning None after the last one
+    ///
+    /// The rest of the previous element is skipped if it was not read completely.
+    pub fn next_element(&mut self) -> Result<Option<ValueReader<'_, R>>, NbtIoError> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        if !self.tag.is_numeric() {
+            self.stream.skip_to(self.depth)?;
+            let head = self.stream.head()?;
+            return Ok(Some(ValueReader::new(self.stream, head, self.depth)));
+        }
+        if self.pos == self.frame.len() {
+            self.frame.clear();
+            self.pos = 0;
+            let frame = match self.stream.next()? {
+                NbtFragment::ByteListFrame(values) => values.raw_bytes(),
+                NbtFragment::ShortListFrame(values) => values.raw_bytes(),
+                NbtFragment::IntListFrame(values) => values.raw_bytes(),
+                NbtFragment::LongListFrame(values) => values.raw_bytes(),
+                NbtFragment::FloatListFrame(values) => values.raw_bytes(),
+                NbtFragment::DoubleListFrame(values) => values.raw_bytes(),
+                _ => return Err(NbtParseError::UnexpectedFragment.into()),
+            };
+            self.frame.extend_from_slice(frame);
+        }
+        let head =
+            element(self.tag, &self.frame[self.pos..]).ok_or(NbtParseError::UnexpectedEnd)?;
+        self.pos += match self.tag {
+            NbtTag::Byte => 1,
+            NbtTag::Short => 2,
+            NbtTag::Int | NbtTag::Float => 4,
+            _ => 8,
+        };
+        // Numbers are read completely, so the depth never has to be reached
+        Ok(Some(ValueReader::new(self.stream, head, usize::MAX)))
+    }
+
+    /// Skips the remaining elements
+    pub fn skip(self) -> Result<(), NbtIoError> {
+        self.stream.skip_to(self.depth - 1)
+    }
+}
+
+/// Reads the first element of a numeric list frame
+fn element(tag: NbtTag, data: &[u8]) -> Option<Head> {
+    fn first<T: crate::view::BeRepr>(data: &[u8]) -> Option<T> {
+        BeSlice::<T>::new(data.get(..T::BYTES)?)?.get(0)
+    }
+    Some(match tag {
+        NbtTag::Byte => Head::Byte(first(data)?),
+        NbtTag::Short => Head::Short(first(data)?),
+        NbtTag::Int => Head::Int(first(data)?),
+        NbtTag::Long => Head::Long(first(data)?),
+        NbtTag::Float => Head::Float(first(data)?),
+        _ => Head::Double(first(data)?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::read::tests::Trickle;
+
+    #[test]
+    fn navigate() {
+        let data = include_bytes!("../../assets/bigtest.nbt");
+        let (_, expected) = NbtValue::read(data).unwrap();
+        let mut reader = NbtStreamReader::from_reader(NbtReader::with_capacity(0, Trickle(data)));
+        let (name, root) = reader.next_root().unwrap().unwrap();
+        assert_eq!(name, "Level");
+        let mut level = root.compound().unwrap();
+        let mut seen = 0;
+        while let Some((name, value)) = level.next_entry().unwrap() {
+            seen += 1;
+            match name.as_str() {
+                "longTest" => assert_eq!(value.long().unwrap(), i64::MAX),
+                "stringTest" => assert_eq!(
+                    value.string().unwrap(),
+                    expected.get("stringTest").unwrap().as_str().unwrap()
+                ),
+                "listTest (long)" => {
+                    let mut list = value.list().unwrap();
+                    assert_eq!(list.remaining(), 5);
+                    let first = list.next_element().unwrap().unwrap().long().unwrap();
+                    assert_eq!(first, 11);
+                    // The rest of the list is skipped
+                }
+                "listTest (compound)" => {
+                    let mut list = value.list().unwrap();
+                    let first = list.next_element().unwrap().unwrap();
+                    let mut first = first.compound().unwrap();
+                    let (key, _) = first.next_entry().unwrap().unwrap();
+                    assert!(key == "name" || key == "created-on");
+                    let second = list.next_element().unwrap().unwrap().value().unwrap();
+                    assert_eq!(
+                        Some(&second),
+                        expected
+                            .get("listTest (compound)")
+                            .and_then(|list| match list {
+                                NbtValue::List(list) => list.get(1),
+                                _ => None,
+                            })
+                    );
+                    assert!(list.next_element().unwrap().is_none());
+                }
+                "byteTest" => assert!(value.int().is_err()),
+                name => assert_eq!(&value.value().unwrap(), expected.get(name).unwrap()),
+            }
+        }
+        assert_eq!(seen, 11);
+        assert!(reader.next_root().unwrap().is_none());
+    }
+
+    #[test]
+    fn payload() {
+        let data = include_bytes!("../../assets/bigtest.nbt");
+        let (_, expected) = NbtValue::read(data).unwrap();
+        let mut reader = NbtStreamReader::from_reader(NbtReader::with_capacity(0, Trickle(data)));
+        let (_, root) = reader.next_root().unwrap().unwrap();
+        let mut level = root.compound().unwrap();
+        while let Some((name, value)) = level.next_entry().unwrap() {
+            let mut out = Vec::new();
+            match (expected.get(&name).unwrap(), value.tag()) {
+                (NbtValue::ByteArray(bytes), _) => {
+                    value.payload().unwrap().read_to_end(&mut out).unwrap();
+                    assert_eq!(out.len(), bytes.len());
+                    assert!(out.iter().zip(bytes).all(|(&a, &b)| a as i8 == b));
+                }
+                (NbtValue::String(string), _) => {
+                    value.payload().unwrap().read_to_end(&mut out).unwrap();
+                    assert_eq!(mutf8::decode(&out).unwrap(), string.as_str());
+                }
+                (_, NbtTag::Compound) => assert!(value.payload().is_err()),
+                _ => {}
+            }
+        }
+        assert!(reader.next_root().unwrap().is_none());
+    }
+}
@@ -0,0 +1,199 @@
+//! Converting between NBT and JSON, for tools that do not understand NBT
+//!
+//! Compounds become objects, and lists and arrays become JSON arrays. Type information is lost:
+//! numbers are written as plain JSON numbers, with NaN and infinities written as `null`. When
+//! reading JSON back with [JsonReader], [JsonHints] choose the types.
+use core::fmt::{self, Write};
+
+mod read;
+pub use read::{JsonHints, JsonReader};
+
+use crate::{
+    NbtFragment, NbtTag,
+    mutf8::StringPolicy,
+    text::{Emit, Layout, Number, Seq, Structure},
+};
+
+/// Writes the fragments of documents pushed to it as JSON
+///
+/// Root names are skipped. Invalid strings are written lossily
+/// unless another [StringPolicy] is set.
+#[derive(Debug, Clone)]
+pub struct JsonWriter<W> {
+    structure: Structure,
+    format: Format<W>,
+}
+
+#[derive(Debug, Clone)]
+struct Format<W> {
+    out: W,
+    layout: Layout,
+}
+
+impl<W: Write> JsonWriter<W> {
+    /// Creates a writer producing compact JSON
+    pub fn new(out: W) -> Self {
+        Self {
+            structure: Structure::default(),
+            format: Format {
+                out,
+                layout: Layout::new("  "),
+            },
+        }
+    }
+
+    /// Whether to spread objects and arrays of containers over multiple indented lines
+    pub fn pretty(mut self, pretty: bool) -> Self {
+        self.format.layout.pretty = pretty;
+        self
+    }
+
+    /// How strings that are not valid (Modified) UTF-8 are written, lossily by default
+    ///
+    /// Strict writers fail to push invalid strings, raw writers write them as byte arrays.
+    pub fn string_policy(mut self, policy: StringPolicy) -> Self {
+        self.structure.strings = policy;
+        self
+    }
+
+    /// Writes the next fragment, returning true once the root tag is complete
+    ///
+    /// Fragments of the next document may be pushed after that, they are written right after the
+    /// previous one.
+    pub fn push(&mut self, fragment: NbtFragment<'_>) -> Result<bool, fmt::Error> {
+        self.structure.push(fragment, &mut self.format)
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.format.out
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.format.out
+    }
+
+    pub fn into_inner(self) -> W {
+        self.format.out
+    }
+}
+
+impl<W: Write> Emit for Format<W> {
+    fn begin_compound(&mut self) -> fmt::Result {
+        self.layout.value(&mut self.out)?;
+        self.layout.open(&mut self.out, "{", true, true)
+    }
+
+    fn key(&mut self, key: &str) -> fmt::Result {
+        self.layout.key(&mut self.out)?;
+        write_string(&mut self.out, key)?;
+        self.out.write_char(':')?;
+        if self.layout.pretty {
+            self.out.write_char(' ')?;
+        }
+        Ok(())
+    }
+
+    fn end_compound(&mut self) -> fmt::Result {
+        self.layout.close(&mut self.out, "}")
+    }
+
+    fn begin_seq(&mut self, seq: Seq) -> fmt::Result {
+        self.layout.value(&mut self.out)?;
+        let multiline = matches!(seq, Seq::List(NbtTag::Compound | NbtTag::List));
+        self.layout.open(&mut self.out, "[", multiline, false)
+    }
+
+    fn end_seq(&mut self) -> fmt::Result {
+        self.layout.close(&mut self.out, "]")
+    }
+
+    fn number(&mut self, number: Number) -> fmt::Result {
+        self.layout.value(&mut self.out)?;
+        match number {
+            Number::Byte(value) => write!(self.out, "{value}"),
+            Number::Short(value) => write!(self.out, "{value}"),
+            Number::Int(value) => write!(self.out, "{value}"),
+            Number::Long(value) => write!(self.out, "{value}"),
+            Number::Float(value) if value.is_finite() => write!(self.out, "{value:?}"),
+            Number::Double(value) if value.is_finite() => write!(self.out, "{value:?}"),
+            Number::Float(_) | Number::Double(_) => self.out.write_str("null"),
+        }
+    }
+
+    fn string(&mut self, string: &str) -> fmt::Result {
+        self.layout.value(&mut self.out)?;
+        write_string(&mut self.out, string)
+    }
+}
+
+pub(crate) fn write_string(out: &mut impl Write, string: &str) -> fmt::Result {
+    out.write_char('"')?;
+    for char in string.chars() {
+        match char {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            char if (char as u32) < 0x20 => write!(out, "\\u{:04x}", char as u32)?,
+            char => out.write_char(char)?,
+        }
+    }
+    out.write_char('"')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FsmResult, NbtFsm};
+    use alloc::string::String;
+
+    #[test]
+    fn bigtest() {
+        let data = include_bytes!("../assets/bigtest.nbt");
+        for pretty in [false, true] {
+            let mut writer = JsonWriter::new(String::new()).pretty(pretty);
+            let mut fsm = NbtFsm::new().with_data(data);
+            while let FsmResult::Found(fragment) = fsm.next_fragment().unwrap() {
+                writer.push(fragment).unwrap();
+            }
+            let output = writer.into_inner();
+            let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+            assert_eq!(json["intTest"], 2147483647);
+            assert_eq!(json["nested compound test"]["egg"]["name"], "Eggbert");
+            assert_eq!(json["listTest (long)"].as_array().unwrap().len(), 5);
+        }
+    }
+
+    #[test]
+    fn non_finite() {
+        use crate::value::NbtValue;
+        let data = NbtValue::List(
+            [f64::NAN, f64::INFINITY, f64::NEG_INFINITY, 1.5]
+                .into_iter()
+                .map(NbtValue::Double)
+                .collect::<alloc::vec::Vec<_>>()
+                .try_into()
+                .unwrap(),
+        )
+        .to_bytes("")
+        .unwrap();
+        let mut writer = JsonWriter::new(String::new());
+        let mut fsm = NbtFsm::new().with_data(&data);
+        while let FsmResult::Found(fragment) = fsm.next_fragment().unwrap() {
+            writer.push(fragment).unwrap();
+        }
+        assert_eq!(writer.into_inner(), "[null,null,null,1.5]");
+    }
+
+    #[test]
+    fn root_end() {
+        let data = [0x0A, 0, 0, 0, 0];
+        let mut writer = JsonWriter::new(String::new());
+        let mut fsm = NbtFsm::new().with_data(&data);
+        while let FsmResult::Found(fragment) = fsm.next_fragment().unwrap() {
+            writer.push(fragment).unwrap();
+        }
+        assert_eq!(writer.into_inner(), "{}");
+    }
+}
@@ -0,0 +1,869 @@
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::ops::Range;
+
+use crate::{
+    NbtFragment, NbtTag,
+    error::JsonError,
+    mutf8,
+    path::{NbtPath, PathSegment},
+    text::{Number, list_frame},
+};
+
+/// The tags to read the values of a JSON document as, which has no type information of its own
+///
+/// A hint applies to the values its path selects. Indices only match the element at that index
+/// counted from the start, `[]` matches every element. The elements of a numeric list all have the
+/// same type, which only a hint ending in `[]` can choose. When several hints match, the one added
+/// first wins.
+///
+/// ```
+/// # use zeronbt::{NbtTag, json::JsonHints};
+/// let hints = JsonHints::new()
+///     .hint("DataVersion".parse().unwrap(), NbtTag::Int)
+///     .hint("Pos".parse().unwrap(), NbtTag::IntArray)
+///     .hint("Motion[]".parse().unwrap(), NbtTag::Float);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JsonHints {
+    hints: Vec<(NbtPath, NbtTag)>,
+}
+
+impl JsonHints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the values at `path` as `tag`
+    ///
+    /// Numbers can be given any numeric tag, arrays can be read as lists or as byte, int or long
+    /// arrays.
+    pub fn hint(mut self, path: NbtPath, tag: NbtTag) -> Self {
+        self.hints.push((path, tag));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hints.is_empty()
+    }
+
+    fn get(&self, path: &[PathSegment]) -> Option<NbtTag> {
+        self.hints
+            .iter()
+            .find(|(hint, _)| matches(hint.segments(), path))
+            .map(|&(_, tag)| tag)
+    }
+}
+
+fn matches(hint: &[PathSegment], path: &[PathSegment]) -> bool {
+    hint.len() == path.len()
+        && hint
+            .iter()
+            .zip(path)
+            .all(|(hint, segment)| match (hint, segment) {
+                (PathSegment::All, PathSegment::Index(_)) => true,
+                (hint, segment) => hint == segment,
+            })
+}
+
+/// Parses JSON into fragments, the inverse of [JsonWriter](super::JsonWriter)
+///
+/// Without [hints](JsonHints), `true` and `false` become the bytes 1 and 0, integers become ints,
+/// or longs if they do not fit, and all other numbers become doubles. Arrays become lists, whose
+/// numbers share the widest of these types. `null` has no equivalent and is an error.
+///
+/// Like [SnbtReader](crate::snbt::SnbtReader), the root is given an empty name, frames are never
+/// split and several root values may follow each other.
+///
+/// ```
+/// # use zeronbt::{NbtTag, json::{JsonHints, JsonReader}, value::{NbtValue, NbtValueBuilder}};
+/// let json = r#"{"Count": 64, "Pos": [1, 2, 3], "Tags": ["a", "b"]}"#;
+/// let hints = JsonHints::new()
+///     .hint("Count".parse().unwrap(), NbtTag::Byte)
+///     .hint("Pos".parse().unwrap(), NbtTag::IntArray);
+/// let mut reader = JsonReader::new(json).with_hints(hints);
+/// let mut builder = NbtValueBuilder::new();
+/// let (name, value) = loop {
+///     let fragment = reader.next_fragment().unwrap().unwrap();
+///     if let Some(root) = builder.push(fragment).unwrap() {
+///         break root;
+///     }
+/// };
+/// let data = value.to_bytes(&name).unwrap();
+/// assert_eq!(NbtValue::read(&data).unwrap().1, value);
+/// let value = value.as_compound().unwrap();
+/// assert_eq!(value.get("Count"), Some(&NbtValue::Byte(64)));
+/// assert_eq!(value.get("Pos"), Some(&NbtValue::IntArray(vec![1, 2, 3])));
+/// ```
+#[derive(Debug, Clone)]
+pub struct JsonReader<'s> {
+    lexer: Lexer<'s>,
+    hints: JsonHints,
+    stack: Vec<Container>,
+    /// The keys and indices leading to the current value, one for each open container
+    path: Vec<PathSegment>,
+    state: State,
+    /// The name, string or numbers that are produced as the next frame
+    buf: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Container {
+    Object,
+    Array(NbtTag),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    /// Between two root values
+    Root,
+    /// The name in the buffer is produced next, before the value or the entries of a compound
+    Name {
+        compound: bool,
+    },
+    NameEnd {
+        compound: bool,
+    },
+    Value,
+    /// Inside an object, expecting the next key or the closing brace
+    Entry {
+        first: bool,
+    },
+    /// Inside a list of containers or strings, expecting the next element or the closing bracket
+    Element {
+        tag: NbtTag,
+        first: bool,
+    },
+    StringEnd,
+    ByteArrayEnd,
+    /// The elements of a numeric list or array in the buffer are produced as one frame
+    Frame(NbtTag),
+}
+
+impl<'s> JsonReader<'s> {
+    pub fn new(json: &'s str) -> Self {
+        Self {
+            lexer: Lexer {
+                input: json,
+                pos: 0,
+            },
+            hints: JsonHints::new(),
+            stack: Vec::new(),
+            path: Vec::new(),
+            state: State::Root,
+            buf: Vec::new(),
+        }
+    }
+
+    pub fn with_hints(mut self, hints: JsonHints) -> Self {
+        self.hints = hints;
+        self
+    }
+
+    /// Whether the reader is between two root values
+    pub fn is_idle(&self) -> bool {
+        self.state == State::Root
+    }
+
+    /// Reads the next fragment, returning None once the text ends between two root values
+    pub fn next_fragment(&mut self) -> Result<Option<NbtFragment<'_>>, JsonError> {
+        loop {
+            match self.state {
+                State::Root => {
+                    if self.lexer.peek_token()?.is_none() {
+                        return Ok(None);
+                    }
+                    self.buf.clear();
+                    if self.peek_tag()? == NbtTag::Compound {
+                        self.lexer.next_token()?;
+                        return Ok(Some(self.open_object(true)));
+                    }
+                    self.state = State::Name { compound: false };
+                }
+                State::Name { compound } => {
+                    self.state = State::NameEnd { compound };
+                    if !self.buf.is_empty() {
+                        return Ok(Some(NbtFragment::NameFrame(&self.buf)));
+                    }
+                }
+                State::NameEnd { compound } => {
+                    self.state = match compound {
+                        true => State::Entry { first: true },
+                        false => State::Value,
+                    };
+                    return Ok(Some(NbtFragment::NameFrame(&[])));
+                }
+                State::Value => return self.value().map(Some),
+                State::Entry { first } => {
+                    let mut next = self.expect_token()?;
+                    if next.0 == Token::CloseObject {
+                        self.close();
+                        return Ok(Some(NbtFragment::End));
+                    }
+                    if !first {
+                        if next.0 != Token::Comma {
+                            return Err(unexpected(next));
+                        }
+                        next = self.expect_token()?;
+                    }
+                    let Token::String(key) = next.0 else {
+                        return Err(unexpected(next));
+                    };
+                    let key = unescape(key).ok_or(JsonError::InvalidString(next.1.start))?;
+                    let colon = self.expect_token()?;
+                    if colon.0 != Token::Colon {
+                        return Err(unexpected(colon));
+                    }
+                    self.buf.clear();
+                    self.buf.extend_from_slice(&mutf8::encode(&key));
+                    if let Some(PathSegment::Key(last)) = self.path.last_mut() {
+                        last.clear();
+                        last.push_str(&key);
+                    }
+                    if self.peek_tag()? == NbtTag::Compound {
+                        self.lexer.next_token()?;
+                        return Ok(Some(self.open_object(true)));
+                    }
+                    self.state = State::Name { compound: false };
+                }
+                State::Element { tag, first } => {
+                    let mut next = self.peek_token()?;
+                    if next.0 == Token::CloseArray {
+                        self.lexer.next_token()?;
+                        self.close();
+                        continue;
+                    }
+                    if !first {
+                        if next.0 != Token::Comma {
+                            return Err(unexpected(next));
+                        }
+                        self.lexer.next_token()?;
+                        next = self.peek_token()?;
+                        if let Some(PathSegment::Index(index)) = self.path.last_mut() {
+                            *index += 1;
+                        }
+                    }
+                    let found = self.peek_tag()?;
+                    if found != tag {
+                        return Err(JsonError::MixedList {
+                            pos: next.1.start,
+                            expected: tag,
+                            found,
+                        });
+                    }
+                    self.state = State::Value;
+                }
+                State::StringEnd => {
+                    self.finish_value();
+                    return Ok(Some(NbtFragment::StringFrame(&[])));
+                }
+                State::ByteArrayEnd => {
+                    self.finish_value();
+                    return Ok(Some(NbtFragment::ByteArrayFrame(&[])));
+                }
+                State::Frame(tag) => {
+                    self.finish_value();
+                    return Ok(Some(list_frame(tag, &self.buf)));
+                }
+            }
+        }
+    }
+
+    /// Reads a value that is not preceded by a name, or whose name was produced already
+    fn value(&mut self) -> Result<NbtFragment<'_>, JsonError> {
+        let next = self.expect_token()?;
+        match next.0 {
+            Token::OpenObject => Ok(self.open_object(false)),
+            Token::OpenArray => self.array(next.1.start),
+            Token::String(quoted) => {
+                let string = unescape(quoted).ok_or(JsonError::InvalidString(next.1.start))?;
+                Ok(self.string(&string))
+            }
+            Token::Literal(text) => {
+                let literal = Literal::parse(text).ok_or_else(|| unexpected(next.clone()))?;
+                let hint = self.hints.get(&self.path).filter(|&hint| hint.is_numeric());
+                let number = literal
+                    .convert(hint.unwrap_or(literal.tag()))
+                    .ok_or(JsonError::InvalidNumber(next.1.start))?;
+                self.finish_value();
+                Ok(number.into_fragment())
+            }
+            _ => Err(unexpected(next)),
+        }
+    }
+
+    fn open_object(&mut self, named: bool) -> NbtFragment<'static> {
+        self.stack.push(Container::Object);
+        self.path.push(PathSegment::Key(String::new()));
+        self.state = match named {
+            true => State::Name { compound: true },
+            false => State::Entry { first: true },
+        };
+        NbtFragment::CompoundTag
+    }
+
+    /// Leaves the innermost object or array once its closing bracket was read
+    fn close(&mut self) {
+        self.stack.pop();
+        self.path.pop();
+        self.finish_value();
+    }
+
+    fn string(&mut self, string: &str) -> NbtFragment<'_> {
+        if string.is_empty() {
+            self.finish_value();
+            return NbtFragment::StringFrame(&[]);
+        }
+        self.buf.clear();
+        self.buf.extend_from_slice(&mutf8::encode(string));
+        self.state = State::StringEnd;
+        NbtFragment::StringFrame(&self.buf)
+    }
+
+    /// Reads an array after its opening bracket
+    fn array(&mut self, pos: usize) -> Result<NbtFragment<'_>, JsonError> {
+        match self.hints.get(&self.path) {
+            None | Some(NbtTag::List) => {}
+            Some(tag @ (NbtTag::ByteArray | NbtTag::IntArray | NbtTag::LongArray)) => {
+                let len = self.numbers(tag)?;
+                return Ok(match tag {
+                    NbtTag::ByteArray if len == 0 => {
+                        self.finish_value();
+                        NbtFragment::ByteArrayFrame(&[])
+                    }
+                    NbtTag::ByteArray => {
+                        self.state = State::ByteArrayEnd;
+                        NbtFragment::ByteArrayFrame(&self.buf)
+                    }
+                    NbtTag::IntArray => {
+                        self.array_frame(NbtTag::Int, len);
+                        NbtFragment::IntArrayTag(len)
+                    }
+                    _ => {
+                        self.array_frame(NbtTag::Long, len);
+                        NbtFragment::LongArrayTag(len)
+                    }
+                });
+            }
+            Some(hint) => return Err(JsonError::InvalidHint { pos, hint }),
+        }
+        if self.peek_token()?.0 == Token::CloseArray {
+            self.lexer.next_token()?;
+            self.finish_value();
+            return Ok(NbtFragment::ListTag(NbtTag::End, 0));
+        }
+        // Numeric lists are typed by a hint for all of their elements
+        self.path.push(PathSegment::All);
+        let tag = self.peek_tag();
+        let hinted = self.hints.get(&self.path).is_some();
+        self.path.pop();
+        let tag = tag?;
+        if tag.is_numeric() {
+            let tag = match hinted {
+                true => tag,
+                false => widest(self.lexer.clone()),
+            };
+            let len = self.numbers(tag)?;
+            self.state = State::Frame(tag);
+            return Ok(NbtFragment::ListTag(tag, len));
+        }
+        let len = count_elements(self.lexer.clone());
+        self.stack.push(Container::Array(tag));
+        self.path.push(PathSegment::Index(0));
+        self.state = State::Element { tag, first: true };
+        Ok(NbtFragment::ListTag(tag, len))
+    }
+
+    fn array_frame(&mut self, tag: NbtTag, len: usize) {
+        match len {
+            0 => self.finish_value(),
+            _ => self.state = State::Frame(tag),
+        }
+    }
+
+    /// Reads the numbers of a numeric list or an array up to the closing bracket into the buffer,
+    /// returning how many there are
+    fn numbers(&mut self, tag: NbtTag) -> Result<usize, JsonError> {
+        let expected = match tag {
+            NbtTag::ByteArray => NbtTag::Byte,
+            NbtTag::IntArray => NbtTag::Int,
+            NbtTag::LongArray => NbtTag::Long,
+            tag => tag,
+        };
+        self.buf.clear();
+        if self.peek_token()?.0 == Token::CloseArray {
+            self.lexer.next_token()?;
+            return Ok(0);
+        }
+        let mut len = 0;
+        loop {
+            let next = self.expect_token()?;
+            let found = match next.0 {
+                Token::Literal(text) => {
+                    let literal = Literal::parse(text).ok_or_else(|| unexpected(next.clone()))?;
+                    let number = literal
+                        .convert(expected)
+                        .ok_or(JsonError::InvalidNumber(next.1.start))?;
+                    number.write_be(&mut self.buf);
+                    None
+                }
+                Token::String(_) => Some(NbtTag::String),
+                Token::OpenObject => Some(NbtTag::Compound),
+                Token::OpenArray => Some(NbtTag::List),
+                _ => return Err(unexpected(next)),
+            };
+            if let Some(found) = found {
+                return Err(JsonError::MixedList {
+                    pos: next.1.start,
+                    expected,
+                    found,
+                });
+            }
+            len += 1;
+            let next = self.expect_token()?;
+            match next.0 {
+                Token::Comma => {}
+                Token::CloseArray => return Ok(len),
+                _ => return Err(unexpected(next)),
+            }
+        }
+    }
+
+    /// Called once a value has been read completely, returns to the enclosing container
+    fn finish_value(&mut self) {
+        self.state = match self.stack.last() {
+            Some(Container::Object) => State::Entry { first: false },
+            Some(&Container::Array(tag)) => State::Element { tag, first: false },
+            None => State::Root,
+        };
+    }
+
+    fn expect_token(&mut self) -> Result<(Token<'s>, Range<usize>), JsonError> {
+        self.lexer.next_token()?.ok_or(JsonError::UnexpectedEnd)
+    }
+
+    fn peek_token(&self) -> Result<(Token<'s>, Range<usize>), JsonError> {
+        self.lexer.peek_token()?.ok_or(JsonError::UnexpectedEnd)
+    }
+
+    /// The tag of the value starting at the next token, checking that a hint for it fits
+    fn peek_tag(&self) -> Result<NbtTag, JsonError> {
+        let next = self.peek_token()?;
+        let hint = self.hints.get(&self.path);
+        let tag = match next.0 {
+            Token::OpenObject => NbtTag::Compound,
+            Token::OpenArray => match hint {
+                Some(tag @ (NbtTag::ByteArray | NbtTag::IntArray | NbtTag::LongArray)) => tag,
+                _ => NbtTag::List,
+            },
+            Token::String(_) => NbtTag::String,
+            Token::Literal(text) => {
+                let literal = Literal::parse(text).ok_or_else(|| unexpected(next.clone()))?;
+                hint.filter(|&hint| hint.is_numeric())
+                    .unwrap_or(literal.tag())
+            }
+            _ => return Err(unexpected(next)),
+        };
+        match hint {
+            Some(hint) if hint != tag => Err(JsonError::InvalidHint {
+                pos: next.1.start,
+                hint,
+            }),
+            _ => Ok(tag),
+        }
+    }
+}
+
+fn unexpected((token, span): (Token<'_>, Range<usize>)) -> JsonError {
+    JsonError::UnexpectedToken {
+        pos: span.start,
+        found: token.as_str().into(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token<'s> {
+    OpenObject,
+    CloseObject,
+    OpenArray,
+    CloseArray,
+    Comma,
+    Colon,
+    /// A string including its quotes, with escapes left in place
+    String(&'s str),
+    /// A number, `true`, `false` or `null`
+    Literal(&'s str),
+}
+
+impl<'s> Token<'s> {
+    fn as_str(&self) -> &'s str {
+        match self {
+            Token::OpenObject => "{",
+            Token::CloseObject => "}",
+            Token::OpenArray => "[",
+            Token::CloseArray => "]",
+            Token::Comma => ",",
+            Token::Colon => ":",
+            Token::String(text) | Token::Literal(text) => text,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Lexer<'s> {
+    input: &'s str,
+    pos: usize,
+}
+
+impl<'s> Lexer<'s> {
+    fn next_token(&mut self) -> Result<Option<(Token<'s>, Range<usize>)>, JsonError> {
+        let rest = &self.input[self.pos..];
+        self.pos += rest.len() - rest.trim_start_matches([' ', '\t', '\n', '\r']).len();
+        let start = self.pos;
+        let rest = &self.input[start..];
+        let Some(char) = rest.chars().next() else {
+            return Ok(None);
+        };
+        let (token, len) = match char {
+            '{' => (Token::OpenObject, 1),
+            '}' => (Token::CloseObject, 1),
+            '[' => (Token::OpenArray, 1),
+            ']' => (Token::CloseArray, 1),
+            ',' => (Token::Comma, 1),
+            ':' => (Token::Colon, 1),
+            '"' => {
+                let len = string_len(rest).ok_or(JsonError::UnexpectedEnd)?;
+                (Token::String(&rest[..len]), len)
+            }
+            char if is_literal(char) => {
+                let len = rest.find(|char| !is_literal(char)).unwrap_or(rest.len());
+                (Token::Literal(&rest[..len]), len)
+            }
+            found => return Err(JsonError::UnexpectedChar { pos: start, found }),
+        };
+        self.pos += len;
+        Ok(Some((token, start..self.pos)))
+    }
+
+    fn peek_token(&self) -> Result<Option<(Token<'s>, Range<usize>)>, JsonError> {
+        self.clone().next_token()
+    }
+}
+
+fn is_literal(char: char) -> bool {
+    char.is_ascii_alphanumeric() || matches!(char, '-' | '+' | '.')
+}
+
+/// The length of the string at the start of `rest`, including both quotes
+fn string_len(rest: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (idx, char) in rest.char_indices().skip(1) {
+        match char {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Some(idx + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Decodes a string token, returning None if it holds an invalid escape
+fn unescape(quoted: &str) -> Option<Cow<'_, str>> {
+    let inner = &quoted[1..quoted.len() - 1];
+    if !inner.contains('\\') {
+        return Some(Cow::Borrowed(inner));
+    }
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            out.push(char);
+            continue;
+        }
+        let escaped = match chars.next()? {
+            char @ ('"' | '\\' | '/') => char,
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'u' => match hex4(&mut chars)? {
+                // Characters outside the basic multilingual plane are escaped as surrogate pairs
+                high @ 0xD800..0xDC00 => {
+                    let (Some('\\'), Some('u')) = (chars.next(), chars.next()) else {
+                        return None;
+                    };
+                    let low = hex4(&mut chars)?
+                        .checked_sub(0xDC00)
+                        .filter(|&low| low < 0x400)?;
+                    char::from_u32(0x10000 + ((high - 0xD800) << 10) + low)?
+                }
+                code => char::from_u32(code)?,
+            },
+            _ => return None,
+        };
+        out.push(escaped);
+    }
+    Some(Cow::Owned(out))
+}
+
+fn hex4(chars: &mut core::str::Chars<'_>) -> Option<u32> {
+    let mut value = 0;
+    for _ in 0..4 {
+        value = value * 16 + chars.next()?.to_digit(16)?;
+    }
+    Some(value)
+}
+
+/// A number or boolean, before a tag is chosen for it
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Literal<'s> {
+    Bool(bool),
+    Integer(i64),
+    /// Kept as text, so floats are parsed directly instead of being rounded twice
+    Decimal(&'s str),
+}
+
+impl<'s> Literal<'s> {
+    /// Parses a literal token, returning None for `null` and anything that is not valid JSON
+    fn parse(text: &'s str) -> Option<Self> {
+        match text {
+            "true" => return Some(Literal::Bool(true)),
+            "false" => return Some(Literal::Bool(false)),
+            _ if !is_number(text) => return None,
+            _ => {}
+        }
+        match text.contains(['.', 'e', 'E']) {
+            false => Some(
+                text.parse()
+                    .map_or(Literal::Decimal(text), Literal::Integer),
+            ),
+            true => Some(Literal::Decimal(text)),
+        }
+    }
+
+    /// The tag the literal is read as without a hint
+    fn tag(self) -> NbtTag {
+        match self {
+            Literal::Bool(_) => NbtTag::Byte,
+            Literal::Integer(value) if i32::try_from(value).is_ok() => NbtTag::Int,
+            Literal::Integer(_) => NbtTag::Long,
+            Literal::Decimal(_) => NbtTag::Double,
+        }
+    }
+
+    fn convert(self, tag: NbtTag) -> Option<Number> {
+        let integer = match self {
+            Literal::Bool(value) => Some(i64::from(value)),
+            Literal::Integer(value) => Some(value),
+            Literal::Decimal(_) => None,
+        };
+        Some(match (tag, self) {
+            (NbtTag::Byte, _) => Number::Byte(integer?.try_into().ok()?),
+            (NbtTag::Short, _) => Number::Short(integer?.try_into().ok()?),
+            (NbtTag::Int, _) => Number::Int(integer?.try_into().ok()?),
+            (NbtTag::Long, _) => Number::Long(integer?),
+            (NbtTag::Float, Literal::Decimal(text)) => Number::Float(text.parse().ok()?),
+            (NbtTag::Float, _) => Number::Float(integer? as f32),
+            (NbtTag::Double, Literal::Decimal(text)) => Number::Double(text.parse().ok()?),
+            (NbtTag::Double, _) => Number::Double(integer? as f64),
+            _ => return None,
+        })
+    }
+}
+
+/// Whether the text has the form of a JSON number, like `-0`, `12.5` or `1e-3`
+fn is_number(text: &str) -> bool {
+    let digits = |part: &str| !part.is_empty() && part.bytes().all(|byte| byte.is_ascii_digit());
+    let text = text.strip_prefix('-').unwrap_or(text);
+    let (mantissa, exponent) = match text.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (text, None),
+    };
+    let (whole, fraction) = match mantissa.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (mantissa, None),
+    };
+    digits(whole)
+        && (whole == "0" || !whole.starts_with('0'))
+        && fraction.is_none_or(digits)
+        && exponent
+            .is_none_or(|exponent| digits(exponent.strip_prefix(['+', '-']).unwrap_or(exponent)))
+}
+
+/// The widest tag of the numbers in the list the lexer is in, so all of them can share it
+fn widest(mut lexer: Lexer<'_>) -> NbtTag {
+    let mut widest = NbtTag::Byte;
+    while let Ok(Some((token, _))) = lexer.next_token() {
+        match token {
+            Token::Literal(text) => {
+                if let Some(literal) = Literal::parse(text) {
+                    widest = widest.max(literal.tag());
+                }
+            }
+            Token::Comma => {}
+            _ => break,
+        }
+    }
+    widest
+}
+
+/// Counts the elements of the array the lexer is in by skipping over them, so the length can be
+/// produced before the elements are parsed
+fn count_elements(mut lexer: Lexer<'_>) -> usize {
+    let (mut depth, mut commas) = (0usize, 0);
+    while let Ok(Some((token, _))) = lexer.next_token() {
+        match token {
+            Token::OpenObject | Token::OpenArray => depth += 1,
+            Token::CloseObject | Token::CloseArray if depth == 0 => break,
+            Token::CloseObject | Token::CloseArray => depth -= 1,
+            Token::Comma if depth == 0 => commas += 1,
+            _ => {}
+        }
+    }
+    commas + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        FsmResult, NbtFsm,
+        json::JsonWriter,
+        value::{NbtValue, NbtValueBuilder},
+    };
+    use alloc::vec;
+
+    fn parse(json: &str, hints: JsonHints) -> Result<NbtValue, JsonError> {
+        let mut reader = JsonReader::new(json).with_hints(hints);
+        let mut builder = NbtValueBuilder::new();
+        loop {
+            let fragment = reader.next_fragment()?.ok_or(JsonError::UnexpectedEnd)?;
+            if let Some((_, value)) = builder.push(fragment).unwrap() {
+                assert!(reader.is_idle());
+                return Ok(value);
+            }
+        }
+    }
+
+    fn key(keys: &[&str]) -> NbtPath {
+        let mut path = NbtPath::root();
+        for &key in keys {
+            path.push(PathSegment::Key(key.into()));
+        }
+        path
+    }
+
+    #[test]
+    fn bigtest() {
+        let data = include_bytes!("../../assets/bigtest.nbt");
+        let mut writer = JsonWriter::new(String::new());
+        let mut fsm = NbtFsm::new().with_data(data);
+        while let FsmResult::Found(fragment) = fsm.next_fragment().unwrap() {
+            writer.push(fragment).unwrap();
+        }
+        let byte_array = "byteArrayTest (the first 1000 values of (n*n*255+n*7)%100, starting with n=0 (0, 62, 34, 16, 8, ...))";
+        let hints = JsonHints::new()
+            .hint(key(&["longTest"]), NbtTag::Long)
+            .hint(key(&["shortTest"]), NbtTag::Short)
+            .hint(key(&["byteTest"]), NbtTag::Byte)
+            .hint(key(&["floatTest"]), NbtTag::Float)
+            .hint(key(&[byte_array]), NbtTag::ByteArray)
+            .hint(r#""listTest (long)"[]"#.parse().unwrap(), NbtTag::Long)
+            .hint(
+                r#""listTest (compound)"[].created-on"#.parse().unwrap(),
+                NbtTag::Long,
+            )
+            .hint(
+                key(&["nested compound test", "egg", "value"]),
+                NbtTag::Float,
+            )
+            .hint(
+                key(&["nested compound test", "ham", "value"]),
+                NbtTag::Float,
+            );
+        let value = parse(&writer.into_inner(), hints).unwrap();
+        assert_eq!(value, NbtValue::read(data).unwrap().1);
+    }
+
+    #[test]
+    fn defaults() {
+        let value = parse(
+            r#"{"bool": true, "int": -5, "long": 3000000000, "double": 0.5, "mixed": [1, 3000000000],
+            "floats": [1, 2.5], "escaped": "\"é🦀\n", "nested": [[], [{}]]}"#,
+            JsonHints::new(),
+        )
+        .unwrap();
+        let value = value.as_compound().unwrap();
+        assert_eq!(value.get("bool"), Some(&NbtValue::Byte(1)));
+        assert_eq!(value.get("int"), Some(&NbtValue::Int(-5)));
+        assert_eq!(value.get("long"), Some(&NbtValue::Long(3000000000)));
+        assert_eq!(value.get("double"), Some(&NbtValue::Double(0.5)));
+        let list = |values: Vec<NbtValue>| NbtValue::List(values.try_into().unwrap());
+        assert_eq!(
+            value.get("mixed"),
+            Some(&list(vec![NbtValue::Long(1), NbtValue::Long(3000000000)]))
+        );
+        assert_eq!(
+            value.get("floats"),
+            Some(&list(vec![NbtValue::Double(1.0), NbtValue::Double(2.5)]))
+        );
+        assert_eq!(
+            value.get("escaped"),
+            Some(&NbtValue::String("\"é🦀\n".into()))
+        );
+        let nested = list(vec![
+            list(vec![]),
+            list(vec![NbtValue::Compound(Default::default())]),
+        ]);
+        assert_eq!(value.get("nested"), Some(&nested));
+    }
+
+    #[test]
+    fn errors() {
+        let none = JsonHints::new;
+        let byte = || JsonHints::new().hint(key(&["a"]), NbtTag::Byte);
+        assert_eq!(
+            parse(r#"{"a": null}"#, none()),
+            Err(JsonError::UnexpectedToken {
+                pos: 6,
+                found: "null".into()
+            })
+        );
+        assert_eq!(
+            parse(r#"{"a": 1,}"#, none()),
+            Err(JsonError::UnexpectedToken {
+                pos: 8,
+                found: "}".into()
+            })
+        );
+        assert_eq!(
+            parse(r#"[{}, "b"]"#, none()),
+            Err(JsonError::MixedList {
+                pos: 5,
+                expected: NbtTag::Compound,
+                found: NbtTag::String
+            })
+        );
+        assert_eq!(
+            parse(r#"{"a": 300}"#, byte()),
+            Err(JsonError::InvalidNumber(6))
+        );
+        assert_eq!(
+            parse(r#"{"a": "x"}"#, byte()),
+            Err(JsonError::InvalidHint {
+                pos: 6,
+                hint: NbtTag::Byte
+            })
+        );
+        assert_eq!(parse(r#"["\x"]"#, none()), Err(JsonError::InvalidString(1)));
+        assert_eq!(
+            parse("01", none()),
+            Err(JsonError::UnexpectedToken {
+                pos: 0,
+                found: "01".into()
+            })
+        );
+        assert_eq!(parse(r#"{"a": 1"#, none()), Err(JsonError::UnexpectedEnd));
+    }
+}
@@ -0,0 +1,220 @@
+//! Trees that leave large strings, arrays and lists in the document they were read from
+//!
+//! A [LazyValue] owns the small values of a document, but only records where strings, arrays and
+//! numeric lists above a size threshold are. These are parsed when they are
+//! [materialized](LazyValue::materialize), with the same document passed in again, so the trees of
+//! many chunks only hold their structure and small values while the large payloads stay in the
+//! buffers they were read from.
+//!
+//! ```
+//! # use zeronbt::{lazy::LazyValue, value::NbtValue};
+//! let data = include_bytes!("../assets/bigtest.nbt");
+//! let (_, value) = LazyValue::read(data, 256).unwrap();
+//! let entries = value.entries().unwrap();
+//! let (_, array) = entries.iter().find(|(key, _)| key.starts_with("byteArray")).unwrap();
+//! assert!(matches!(array, LazyValue::Spilled { .. }));
+//! assert_eq!(array.materialize(data).unwrap().as_byte_array().map(<[i8]>::len), Some(1000));
+//! assert_eq!(value.get("intTest"), Some(&LazyValue::Value(NbtValue::Int(2147483647))));
+//! ```
+use alloc::{string::String, vec::Vec};
+use core::ops::Range;
+
+use crate::{
+    NbtTag,
+    error::{NbtParseError, NbtResult},
+    index::NbtIndex,
+    value::{NbtCompound, NbtList, NbtValue},
+};
+
+/// A value whose large parts are left in its document, see the [module docs](self)
+///
+/// Compounds and lists that hold no spilled values are kept as a single [LazyValue::Value].
+#[derive(Debug, Clone, PartialEq)]
+pub enum LazyValue {
+    Value(NbtValue),
+    /// A compound holding spilled values, with its entries in order
+    Compound(Vec<(String, LazyValue)>),
+    /// A list of strings, lists or compounds holding spilled values
+    List(Vec<LazyValue>),
+    /// A string, array or numeric list that was not parsed, with the range of its payload
+    Spilled {
+        tag: NbtTag,
+        payload: Range<usize>,
+    },
+}
+
+impl LazyValue {
+    /// Reads the complete named tag at the start of `data`, spilling strings, arrays and numeric
+    /// lists whose payload is longer than `threshold` bytes
+    pub fn read(data: &[u8], threshold: usize) -> NbtResult<(String, LazyValue)> {
+        let index = NbtIndex::new(data)?;
+        let name = index
+            .get(0)
+            .and_then(|root| root.key.clone())
+            .unwrap_or_default();
+        Ok((name, Self::from_index(&index, 0, data, threshold)?))
+    }
+
+    /// Builds the value of `entry` of an index of `data`
+    pub fn from_index(
+        index: &NbtIndex,
+        entry: usize,
+        data: &[u8],
+        threshold: usize,
+    ) -> NbtResult<LazyValue> {
+        let value = index.get(entry).ok_or(NbtParseError::MissingField)?;
+        if value.payload.len() <= threshold {
+            return read_payload(value.tag, &data[value.payload.clone()]).map(LazyValue::Value);
+        }
+        match value.tag {
+            NbtTag::Compound => {
+                let entries = index
+                    .children(entry)
+                    .map(|child| {
+                        let key = index.get(child).and_then(|child| child.key.clone());
+                        Ok((
+                            key.unwrap_or_default(),
+                            Self::from_index(index, child, data, threshold)?,
+                        ))
+                    })
+                    .collect::<NbtResult<Vec<_>>>()?;
+                if entries
+                    .iter()
+                    .all(|(_, value)| matches!(value, LazyValue::Value(_)))
+                {
+                    let compound: NbtCompound = entries
+                        .into_iter()
+                        .filter_map(|(key, value)| Some((key, value.into_value()?)))
+                        .collect();
+                    return Ok(LazyValue::Value(NbtValue::Compound(compound)));
+                }
+                Ok(LazyValue::Compound(entries))
+            }
+            NbtTag::List if index.children(entry).next().is_some() => {
+                let elements = index
+                    .children(entry)
+                    .map(|child| Self::from_index(index, child, data, threshold))
+                    .collect::<NbtResult<Vec<_>>>()?;
+                if elements
+                    .iter()
+                    .all(|value| matches!(value, LazyValue::Value(_)))
+                {
+                    let values: Vec<_> = elements
+                        .into_iter()
+                        .filter_map(LazyValue::into_value)
+                        .collect();
+                    let list =
+                        NbtList::try_from(values).map_err(|_| NbtParseError::UnexpectedType)?;
+                    return Ok(LazyValue::Value(NbtValue::List(list)));
+                }
+                Ok(LazyValue::List(elements))
+            }
+            tag => Ok(LazyValue::Spilled {
+                tag,
+                payload: value.payload.clone(),
+            }),
+        }
+    }
+
+    pub fn tag(&self) -> NbtTag {
+        match self {
+            LazyValue::Value(value) => value.tag(),
+            LazyValue::Compound(_) => NbtTag::Compound,
+            LazyValue::List(_) => NbtTag::List,
+            LazyValue::Spilled { tag, .. } => *tag,
+        }
+    }
+
+    /// Looks up `key` if this value is a compound holding spilled values
+    ///
+    /// Compounds without spilled values are [LazyValue::Value]s, see [NbtValue::get] for those.
+    pub fn get(&self, key: &str) -> Option<&LazyValue> {
+        self.entries()?
+            .iter()
+            .find_map(|(entry, value)| (entry == key).then_some(value))
+    }
+
+    /// The entries of a compound holding spilled values
+    pub fn entries(&self) -> Option<&[(String, LazyValue)]> {
+        match self {
+            LazyValue::Compound(entries) => Some(entries),
+            _ => None,
+        }
+    }
+
+    /// Whether any part of this value was spilled
+    pub fn is_spilled(&self) -> bool {
+        !matches!(self, LazyValue::Value(_))
+    }
+
+    /// Parses the spilled parts of this value from `data`, which has to be the document it was read
+    /// from
+    pub fn materialize(&self, data: &[u8]) -> NbtResult<NbtValue> {
+        match self {
+            LazyValue::Value(value) => Ok(value.clone()),
+            LazyValue::Compound(entries) => entries
+                .iter()
+                .map(|(key, value)| Ok((key.as_str(), value.materialize(data)?)))
+                .collect::<NbtResult<NbtCompound>>()
+                .map(NbtValue::Compound),
+            LazyValue::List(elements) => {
+                let values = elements
+                    .iter()
+                    .map(|value| value.materialize(data))
+                    .collect::<NbtResult<Vec<_>>>()?;
+                let list = NbtList::try_from(values).map_err(|_| NbtParseError::UnexpectedType)?;
+                Ok(NbtValue::List(list))
+            }
+            LazyValue::Spilled { tag, payload } => {
+                let payload = data
+                    .get(payload.clone())
+                    .ok_or(NbtParseError::UnexpectedEnd)?;
+                read_payload(*tag, payload)
+            }
+        }
+    }
+
+    fn into_value(self) -> Option<NbtValue> {
+        match self {
+            LazyValue::Value(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a payload of `tag` as the root of a document with an empty name
+fn read_payload(tag: NbtTag, payload: &[u8]) -> NbtResult<NbtValue> {
+    let mut document = Vec::with_capacity(payload.len() + 3);
+    document.extend_from_slice(&[tag as u8, 0, 0]);
+    document.extend_from_slice(payload);
+    NbtValue::read(&document).map(|(_, value)| value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spill_and_materialize() {
+        let data = include_bytes!("../assets/bigtest.nbt");
+        let (_, expected) = NbtValue::read(data).unwrap();
+        let (name, value) = LazyValue::read(data, 16).unwrap();
+        assert_eq!(name, "Level");
+        assert!(value.is_spilled());
+        assert!(matches!(
+            value.get("stringTest"),
+            Some(LazyValue::Spilled {
+                tag: NbtTag::String,
+                ..
+            })
+        ));
+        assert!(matches!(
+            value.get("listTest (compound)"),
+            Some(LazyValue::List(_))
+        ));
+        assert_eq!(value.materialize(data).unwrap(), expected);
+
+        let (_, value) = LazyValue::read(data, data.len()).unwrap();
+        assert_eq!(value, LazyValue::Value(expected));
+    }
+}
@@ -0,0 +1,86 @@
+//! Tracking which values are open in a fragment stream, for adapters that need to know where a
+//! value ends without parsing it themselves
+use alloc::vec::Vec;
+
+use crate::NbtFragment;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Level {
+    Compound,
+    /// A list of strings, lists or compounds, with the number of elements still to come
+    List(usize),
+    /// A numeric list or array, with the number of elements still to come
+    Frames(usize),
+    /// A string or byte array, which ends with an empty frame
+    Text,
+}
+
+/// Updates the open values after `fragment` was read, returning whether it completed a value
+pub(crate) fn track(levels: &mut Vec<Level>, fragment: &NbtFragment<'_>) -> bool {
+    let complete = match *fragment {
+        NbtFragment::CompoundTag => {
+            levels.push(Level::Compound);
+            false
+        }
+        NbtFragment::End => {
+            levels.pop();
+            true
+        }
+        NbtFragment::NameFrame(_) => false,
+        NbtFragment::StringFrame([]) | NbtFragment::ByteArrayFrame([]) => {
+            if levels.last() == Some(&Level::Text) {
+                levels.pop();
+            }
+            true
+        }
+        NbtFragment::StringFrame(_) | NbtFragment::ByteArrayFrame(_) => {
+            if levels.last() != Some(&Level::Text) {
+                levels.push(Level::Text);
+            }
+            false
+        }
+        NbtFragment::ListTag(_, 0) | NbtFragment::IntArrayTag(0) | NbtFragment::LongArrayTag(0) => {
+            true
+        }
+        NbtFragment::ListTag(tag, len) if !tag.is_numeric() => {
+            levels.push(Level::List(len));
+            false
+        }
+        NbtFragment::ListTag(_, len)
+        | NbtFragment::IntArrayTag(len)
+        | NbtFragment::LongArrayTag(len) => {
+            levels.push(Level::Frames(len));
+            false
+        }
+        NbtFragment::ByteListFrame(values) => frames(levels, values.len()),
+        NbtFragment::ShortListFrame(values) => frames(levels, values.len()),
+        NbtFragment::IntListFrame(values) => frames(levels, values.len()),
+        NbtFragment::LongListFrame(values) => frames(levels, values.len()),
+        NbtFragment::FloatListFrame(values) => frames(levels, values.len()),
+        NbtFragment::DoubleListFrame(values) => frames(levels, values.len()),
+        _ => true,
+    };
+    if complete {
+        while let Some(Level::List(remaining)) = levels.last_mut() {
+            *remaining -= 1;
+            if *remaining > 0 {
+                break;
+            }
+            levels.pop();
+        }
+    }
+    complete
+}
+
+/// Counts the elements of a frame, returning whether the list or array is complete
+fn frames(levels: &mut Vec<Level>, len: usize) -> bool {
+    let Some(Level::Frames(remaining)) = levels.last_mut() else {
+        return false;
+    };
+    *remaining = remaining.saturating_sub(len);
+    if *remaining > 0 {
+        return false;
+    }
+    levels.pop();
+    true
+}
@@ -1,22 +1,80 @@
 #![no_std]
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+// Lets the derive macros refer to this crate as ::zeronbt from within its own tests
+#[cfg(all(test, feature = "derive"))]
+extern crate self as zeronbt;
 mod buf;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+pub mod checksum;
+pub mod chunk;
+#[cfg(feature = "tokio-util")]
+pub mod codec;
+#[cfg(feature = "std")]
+pub mod compression;
+pub mod convert;
+pub mod csv;
+pub mod diff;
+pub mod edit;
 pub mod error;
+pub mod explain;
+pub mod extract;
 mod fsm;
+pub mod index;
+#[cfg(any(feature = "fastnbt", feature = "hematite-nbt", feature = "valence_nbt"))]
+pub mod interop;
+pub mod io;
+pub mod json;
+pub mod lazy;
+mod levels;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
 pub use fsm::*;
+pub mod mutf8;
+mod name;
+pub use name::{NameHasher, NameMatch, NameMatcher, NbtName};
+pub mod path;
+#[cfg(feature = "std")]
+pub mod region;
+#[cfg(feature = "std")]
+pub mod save;
+pub mod schem;
+pub mod schema;
+pub mod segment;
+pub mod snbt;
+pub mod split;
+pub mod structure;
 mod tag;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod text;
+pub mod transcode;
+pub use tag::NbtTag;
+pub mod uuid;
+pub mod value;
 pub mod view;
+pub mod workload;
+#[cfg(feature = "std")]
+pub mod world;
+mod write;
+#[cfg(feature = "yaml")]
+pub mod yaml;
+pub use write::NbtWriter;
 
 #[cfg(test)]
 mod tests {
     extern crate std;
     use core::ops::Range;
     use std::fmt::Debug;
-    use std::prelude::rust_2024::*;
+    use std::string::ToString;
+    use std::vec::Vec;
     use std::{dbg, vec};
 
+    use crate::error::NbtParseError;
     use crate::view::BeSlice;
-    use crate::{FsmResult, NbtFragment, NbtFsm};
+    use crate::{FsmResult, NameMatch, NameMatcher, NbtFragment, NbtFsm, NbtTag};
 
     const INT_BYTES: [u8; 8] = *b"12345678";
 
@@ -67,20 +125,19 @@ mod tests {
         input.extend_from_slice(name);
     }
 
-    fn expect_name<'f>(mut fragments: impl Iterator<Item = NbtFragment<'f>>, name: &[u8]) {
-        let mut pos = 0;
-        while pos != name.len() {
-            let frame = fragments
-                .next()
-                .expect("Expected more NameFrame's with the name");
+    fn expect_name<'f>(fragments: impl Iterator<Item = NbtFragment<'f>>, name: &[u8]) {
+        let mut matcher = NameMatcher::new(name);
+        for frame in fragments {
             let NbtFragment::NameFrame(data) = frame else {
                 panic!("Found invalid NBT Fragment when parsing name: {frame:?}");
             };
-            let rem = &name[pos..];
-            assert!(rem.starts_with(data));
-            pos += data.len();
+            match matcher.push(data) {
+                NameMatch::Pending => {}
+                NameMatch::Matched => return,
+                NameMatch::Failed => panic!("Name does not match {name:?}"),
+            }
         }
-        assert_eq!(Some(NbtFragment::NameFrame(&[])), fragments.next())
+        panic!("Expected more NameFrame's with the name")
     }
 
     enum Expect<'d> {
@@ -277,7 +334,10 @@ mod tests {
         }
 
         let mut fragments = FragmentsWithSteamedInput::new(&complete_input);
-        let header = [Expect::Name(b"testIntList")];
+        let header = [
+            Expect::Name(b"testIntList"),
+            Expect::Fragment(NbtFragment::ListTag(NbtTag::Int, ints.len())),
+        ];
         for expect in header {
             expect.expect(&mut fragments);
         }
@@ -307,7 +367,10 @@ mod tests {
         }
 
         let mut fragments = FragmentsWithSteamedInput::new(&complete_input);
-        let header = [Expect::Name(b"testIntArray")];
+        let header = [
+            Expect::Name(b"testIntArray"),
+            Expect::Fragment(NbtFragment::IntArrayTag(ints.len())),
+        ];
         for expect in header {
             expect.expect(&mut fragments);
         }
@@ -369,4 +432,371 @@ mod tests {
         }
         assert!(fragments.next().is_none());
     }
+
+    /// Parses `data` given in two parts, split at `split`
+    fn parse_split(data: &[u8], split: usize) -> Result<Vec<NbtFragment<'_>>, NbtParseError> {
+        let mut fragments = Vec::new();
+        let mut fsm = NbtFsm::new().with_data(&data[..split]);
+        while let FsmResult::Found(fragment) = fsm.next_fragment()? {
+            fragments.push(fragment);
+        }
+        let rest = &data[fsm.consumed()..];
+        let mut fsm = fsm.with_data(rest);
+        while let FsmResult::Found(fragment) = fsm.next_fragment()? {
+            fragments.push(fragment);
+        }
+        Ok(fragments)
+    }
+
+    #[test]
+    fn split_names() {
+        let mut complete_input = vec![10];
+        push_name(&mut complete_input, b"root");
+        complete_input.push(1);
+        push_name(&mut complete_input, b"someByte");
+        complete_input.extend_from_slice(&[1, 0]);
+        for split in 0..complete_input.len() {
+            let fragments = parse_split(&complete_input, split).unwrap();
+            let mut names = vec![Vec::new()];
+            for fragment in &fragments {
+                if let NbtFragment::NameFrame(data) = fragment {
+                    match data.is_empty() {
+                        true => names.push(Vec::new()),
+                        false => names.last_mut().unwrap().extend_from_slice(data),
+                    }
+                }
+            }
+            assert_eq!(names, [&b"root"[..], b"someByte", b""], "split at {split}");
+            assert_eq!(fragments.last(), Some(&NbtFragment::End));
+        }
+    }
+
+    #[test]
+    fn invalid_headers() {
+        let header = |tag: u8| {
+            let mut input = vec![10, 0, 0, tag];
+            push_name(&mut input, b"a");
+            input
+        };
+        let mut inputs = vec![(header(0xFF), NbtParseError::InvalidTag(0xFF))];
+        let mut list = header(9);
+        list.push(0xFE);
+        inputs.push((list, NbtParseError::InvalidTag(0xFE)));
+        for tag in [7, 9, 11, 12] {
+            let mut input = header(tag);
+            if tag == 9 {
+                input.push(3);
+            }
+            input.extend_from_slice(&(-1i32).to_be_bytes());
+            inputs.push((input, NbtParseError::InvalidLen(-1)));
+        }
+        for (input, error) in inputs {
+            for split in 0..input.len() {
+                assert_eq!(parse_split(&input, split), Err(error.clone()));
+            }
+        }
+    }
+
+    #[test]
+    fn min_frame_size() {
+        let mut complete_input = vec![7];
+        push_name(&mut complete_input, b"testByteArray");
+        let bytearr: Vec<u8> = (0..1000).map(|n| n as u8).collect();
+        complete_input.extend_from_slice(&(bytearr.len() as i32).to_be_bytes());
+        complete_input.extend_from_slice(&bytearr);
+        let mut fragments = FragmentsWithSteamedInput::new(&complete_input);
+        fragments.fsm = NbtFsm::new().with_min_frame(64);
+
+        Expect::Name(b"testByteArray").expect(&mut fragments);
+        let mut frames = Vec::new();
+        for frame in &mut fragments {
+            let NbtFragment::ByteArrayFrame(data) = frame else {
+                panic!("Found invalid NBT Fragment when parsing byte array: {frame:?}");
+            };
+            frames.push(data);
+            if data.is_empty() {
+                break;
+            }
+        }
+        // 15 full frames, the 40 bytes left at the end of the payload and the empty frame
+        assert_eq!(frames.len(), 17);
+        assert!(frames[..15].iter().all(|frame| frame.len() == 64));
+        assert_eq!(frames.concat(), bytearr);
+        assert!(fragments.next().is_none());
+    }
+
+    #[test]
+    fn fragment_context() {
+        use crate::FragmentContext::{Element, Entry, Root};
+        use crate::value::{NbtCompound, NbtList, NbtValue};
+
+        let list = |values: Vec<NbtValue>| NbtValue::List(NbtList::try_from(values).unwrap());
+        let mut inner = NbtCompound::new();
+        inner.insert("x", "a");
+        let mut root = NbtCompound::new();
+        root.insert("b", 1i8);
+        root.insert(
+            "c",
+            list(vec![NbtValue::Compound(inner), NbtCompound::new().into()]),
+        );
+        root.insert("s", list(vec!["p".into(), "q".into()]));
+        root.insert("l", list(vec![list(vec![NbtValue::Int(1)])]));
+        let data = NbtValue::Compound(root).to_bytes("").unwrap();
+
+        let mut fsm = NbtFsm::new().with_data(&data);
+        let mut contexts = Vec::new();
+        while let FsmResult::Found(fragment) = fsm.next_fragment().unwrap() {
+            if !matches!(fragment, NbtFragment::NameFrame(_)) {
+                contexts.push((fragment, fsm.context()));
+            }
+        }
+        let string = |data| NbtFragment::StringFrame(data);
+        let ints = NbtFragment::IntListFrame(BeSlice::new(&[0, 0, 0, 1]).unwrap());
+        let expected = vec![
+            (NbtFragment::CompoundTag, Root),
+            (NbtFragment::Byte(1), Entry),
+            (NbtFragment::ListTag(NbtTag::Compound, 2), Entry),
+            (NbtFragment::CompoundTag, Element(0)),
+            (string(b"a"), Entry),
+            (string(b""), Entry),
+            (NbtFragment::End, Element(0)),
+            (NbtFragment::CompoundTag, Element(1)),
+            (NbtFragment::End, Element(1)),
+            (NbtFragment::ListTag(NbtTag::String, 2), Entry),
+            (string(b"p"), Element(0)),
+            (string(b""), Element(0)),
+            (string(b"q"), Element(1)),
+            (string(b""), Element(1)),
+            (NbtFragment::ListTag(NbtTag::List, 1), Entry),
+            (NbtFragment::ListTag(NbtTag::Int, 1), Element(0)),
+            (ints, Element(0)),
+            (NbtFragment::End, Root),
+        ];
+        assert_eq!(contexts, expected);
+    }
+
+    #[test]
+    fn read_long_string() {
+        // Lengths are unsigned, so these do not fit an i16
+        let name = "n".repeat(40000);
+        let string = "s".repeat(u16::MAX as usize);
+        let mut complete_input = vec![8];
+        push_name(&mut complete_input, name.as_bytes());
+        push_name(&mut complete_input, string.as_bytes());
+        assert_eq!(
+            crate::value::NbtValue::read(&complete_input),
+            Ok((name, crate::value::NbtValue::String(string)))
+        );
+    }
+
+    #[test]
+    fn float_bits() {
+        let bits = [0x3F80_0000u32, 0x7FC0_0001, 0x8000_0000];
+        let mut complete_input = vec![9];
+        push_name(&mut complete_input, b"floats");
+        complete_input.push(5);
+        complete_input.extend_from_slice(&3i32.to_be_bytes());
+        for bits in bits {
+            complete_input.extend_from_slice(&bits.to_be_bytes());
+        }
+        let mut fsm = NbtFsm::new().with_data(&complete_input);
+        let frame = loop {
+            match fsm.next_fragment() {
+                Ok(FsmResult::Found(NbtFragment::FloatListFrame(frame))) => break frame,
+                Ok(FsmResult::Found(_)) => {}
+                result => panic!("{result:?}"),
+            }
+        };
+        assert!(frame.to_bits().iter().eq(bits));
+    }
+
+    #[test]
+    fn tag_helpers() {
+        let tags = (0..=12).map(|tag| NbtTag::try_from(tag).unwrap());
+        let sizes: Vec<_> = tags.clone().map(NbtTag::fixed_payload_size).collect();
+        assert_eq!(
+            sizes,
+            [
+                Some(0),
+                Some(1),
+                Some(2),
+                Some(4),
+                Some(8),
+                Some(4),
+                Some(8)
+            ]
+            .into_iter()
+            .chain([None; 6])
+            .collect::<Vec<_>>()
+        );
+        assert!(
+            tags.clone()
+                .filter(|tag| tag.is_numeric())
+                .eq(tags.clone().skip(1).take(6))
+        );
+        assert!(
+            tags.filter(|tag| tag.is_container())
+                .eq([NbtTag::List, NbtTag::Compound])
+        );
+        assert_eq!(NbtTag::IntArray.to_string(), "TAG_Int_Array");
+    }
+
+    #[test]
+    fn phases() {
+        use crate::Phase;
+        let mut input = vec![10];
+        push_name(&mut input, b"root");
+        input.push(8);
+        push_name(&mut input, b"s");
+        push_name(&mut input, b"text");
+        input.push(0);
+
+        let fsm = NbtFsm::new();
+        assert_eq!(fsm.phase(), Phase::Idle);
+        let mut fsm = fsm.with_data(&input[..5]);
+        let mut phases = vec![];
+        while let FsmResult::Found(_) = fsm.next_fragment().unwrap() {
+            phases.push(fsm.phase());
+        }
+        phases.push(fsm.phase());
+        let mut fsm = fsm.with_data(&input[5..]);
+        while let FsmResult::Found(_) = fsm.next_fragment().unwrap() {
+            phases.push(fsm.phase());
+        }
+        assert_eq!(
+            phases,
+            [
+                Phase::ExpectingNameLength,
+                Phase::ReadingName { remaining: 2 },
+                // Waiting for more input
+                Phase::ReadingName { remaining: 2 },
+                Phase::ReadingName { remaining: 0 },
+                Phase::ExpectingTag,
+                Phase::ReadingName { remaining: 0 },
+                Phase::ExpectingValue(NbtTag::String),
+                Phase::ReadingString { remaining: 0 },
+                Phase::ExpectingTag,
+                Phase::Idle,
+            ]
+        );
+    }
+
+    #[test]
+    fn end_list_length() {
+        use crate::{NbtOptions, error::NbtParseError};
+        let mut input = vec![9];
+        push_name(&mut input, b"ends");
+        input.push(0);
+        input.extend_from_slice(&3i32.to_be_bytes());
+
+        let mut fsm = NbtFsm::new().with_data(&input);
+        let result = loop {
+            match fsm.next_fragment() {
+                Ok(FsmResult::Found(NbtFragment::NameFrame(_))) => {}
+                result => break result,
+            }
+        };
+        assert_eq!(result, Err(NbtParseError::EndListLength(3)));
+
+        let options = NbtOptions::new().lenient_end_lists(true);
+        let mut fsm = NbtFsm::new().with_options(options).with_data(&input);
+        let mut fragments = vec![];
+        while let FsmResult::Found(fragment) = fsm.next_fragment().unwrap() {
+            fragments.push(fragment);
+        }
+        assert_eq!(
+            fragments.last(),
+            Some(&NbtFragment::ListTag(NbtTag::End, 0))
+        );
+        assert!(fsm.is_idle());
+    }
+
+    #[test]
+    fn max_depth() {
+        use crate::{
+            CompleteFsm, DEFAULT_MAX_DEPTH, NbtOptions,
+            error::NbtParseError,
+            value::{NbtValue, NbtValueBuilder},
+        };
+        // A list of lists, each holding the next, 50001 deep
+        let mut input = vec![9, 0, 0];
+        for _ in 0..50_000 {
+            input.extend_from_slice(&[9, 0, 0, 0, 1]);
+        }
+        input.extend_from_slice(&[1, 0, 0, 0, 0]);
+        let too_deep = Err(NbtParseError::TooDeep(DEFAULT_MAX_DEPTH));
+        assert_eq!(NbtValue::read(&input).map(drop), too_deep);
+        assert_eq!(
+            CompleteFsm::new(&input).find_map(Result::err),
+            too_deep.err()
+        );
+
+        // Without a limit the value is built, and dropped without recursing
+        let options = NbtOptions::new().max_depth(usize::MAX);
+        let fsm = NbtFsm::new().with_options(options);
+        let mut builder = NbtValueBuilder::new();
+        let mut root = None;
+        for fragment in CompleteFsm::with_fsm(fsm, &input) {
+            root = builder.push(fragment.unwrap()).unwrap();
+        }
+        assert!(matches!(root, Some((_, NbtValue::List(_)))));
+        drop(root);
+
+        let options = NbtOptions::new().max_depth(2);
+        let read = |input: &[u8]| {
+            let mut fsm = NbtFsm::new().with_options(options).with_data(input);
+            while let FsmResult::Found(_) = fsm.next_fragment()? {}
+            Ok(())
+        };
+        // A compound holding a list of compounds
+        assert_eq!(read(&[10, 0, 0, 9, 0, 0, 10, 0, 0, 0, 0, 0]), Ok(()));
+        assert_eq!(
+            read(&[10, 0, 0, 9, 0, 0, 10, 0, 0, 0, 1, 0, 0]),
+            Err(NbtParseError::TooDeep(2))
+        );
+        assert_eq!(
+            read(&[10, 0, 0, 10, 0, 0, 10, 0, 0]),
+            Err(NbtParseError::TooDeep(2))
+        );
+    }
+
+    #[test]
+    fn containers() {
+        use crate::Container;
+        let data = include_bytes!("../assets/bigtest.nbt");
+        let mut fsm = NbtFsm::new().with_data(data);
+        let mut in_list = vec![];
+        while let FsmResult::Found(fragment) = fsm.next_fragment().unwrap() {
+            if let Some(Container::List {
+                tag: NbtTag::Compound,
+                remaining,
+            }) = fsm.container()
+            {
+                in_list.push((fragment, remaining));
+            }
+        }
+        assert_eq!(
+            in_list,
+            [
+                (NbtFragment::ListTag(NbtTag::Compound, 2), 2),
+                (NbtFragment::End, 1),
+                (NbtFragment::End, 0)
+            ]
+        );
+        assert_eq!(fsm.container(), None);
+    }
+
+    #[test]
+    fn stats_across_refills() {
+        let data = include_bytes!("../assets/bigtest.nbt");
+        let mut fragments = FragmentsWithSteamedInput::new(data);
+        fragments.fsm = core::mem::take(&mut fragments.fsm).with_stats();
+        let count = fragments.by_ref().count();
+        let stats = fragments.fsm.stats().unwrap();
+        assert_eq!(stats.fragments, count);
+        assert_eq!(stats.bytes, data.len());
+        // The input is given one more byte at a time
+        assert_eq!(stats.refills, data.len());
+        assert_eq!(NbtFsm::new().stats(), None);
+    }
 }
@@ -0,0 +1,253 @@
+//! Converting NBT to MessagePack, for pipelines that already consume it
+//!
+//! Compounds become maps with string keys and lists become arrays. Integers are always written
+//! with the width of their type, so bytes, shorts, ints and longs stay apart. Byte arrays become
+//! bin, while int and long arrays become extension types holding the big-endian elements, see
+//! [INT_ARRAY_EXT] and [LONG_ARRAY_EXT].
+//!
+//! ```
+//! # use zeronbt::{CompleteFsm, msgpack::MsgpackWriter};
+//! let data = b"\x0a\x00\x00\x08\x00\x04name\x00\x09Bananrama\x00";
+//! let mut writer = MsgpackWriter::new();
+//! for fragment in CompleteFsm::new(data) {
+//!     writer.push(fragment.unwrap());
+//! }
+//! // {"name": "Bananrama"}
+//! let mut expected = vec![0xDF, 0, 0, 0, 1, 0xA4];
+//! expected.extend_from_slice(b"name");
+//! expected.push(0xA9);
+//! expected.extend_from_slice(b"Bananrama");
+//! assert_eq!(writer.into_inner(), expected);
+//! ```
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{
+    NbtFragment,
+    text::{Emit, Number, Seq, Structure},
+};
+
+/// The extension type of int arrays, whose data is the big-endian elements
+pub const INT_ARRAY_EXT: i8 = 11;
+/// The extension type of long arrays, whose data is the big-endian elements
+pub const LONG_ARRAY_EXT: i8 = 12;
+
+const MAP32: u8 = 0xDF;
+const ARRAY32: u8 = 0xDD;
+const BIN32: u8 = 0xC6;
+const EXT32: u8 = 0xC9;
+
+/// Writes the fragments of documents pushed to it as MessagePack
+///
+/// Root names are skipped. Invalid strings are written lossily. As the number of entries of a
+/// compound is not known until it ends, maps and arrays are always written with 32-bit lengths,
+/// which are filled in once they end.
+#[derive(Debug, Clone, Default)]
+pub struct MsgpackWriter {
+    structure: Structure,
+    format: Format,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Format {
+    out: Vec<u8>,
+    /// The position of the length of each open map or array, along with the length so far
+    open: Vec<(usize, u32)>,
+    /// The extension type of the byte, int or long array being written, whose elements are
+    /// collected in `array`
+    in_array: Option<Option<i8>>,
+    array: Vec<u8>,
+}
+
+impl MsgpackWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes the next fragment, returning true once the root tag is complete
+    ///
+    /// Fragments of the next document may be pushed after that, they are written right after the
+    /// previous one.
+    pub fn push(&mut self, fragment: NbtFragment<'_>) -> bool {
+        // Writing to a Vec can not fail
+        matches!(self.structure.push(fragment, &mut self.format), Ok(true))
+    }
+
+    pub fn get_ref(&self) -> &Vec<u8> {
+        &self.format.out
+    }
+
+    /// The output written so far
+    ///
+    /// Maps and arrays that are still open have their lengths filled in once they end, so only
+    /// complete documents may be drained from it.
+    pub fn get_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.format.out
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.format.out
+    }
+}
+
+impl Format {
+    /// Counts a value towards the array it is an element of
+    fn element(&mut self) {
+        if let Some((pos, len)) = self.open.last_mut()
+            && self.out[*pos - 1] == ARRAY32
+        {
+            *len += 1;
+        }
+    }
+
+    /// Writes the marker of a map or array, with a length that is filled in by `close`
+    fn open(&mut self, marker: u8) {
+        self.element();
+        self.out.push(marker);
+        self.open.push((self.out.len(), 0));
+        self.out.extend_from_slice(&[0; 4]);
+    }
+
+    fn close(&mut self) {
+        let (pos, len) = self
+            .open
+            .pop()
+            .expect("only open maps and arrays are closed");
+        self.out[pos..pos + 4].copy_from_slice(&len.to_be_bytes());
+    }
+}
+
+impl Emit for Format {
+    fn begin_compound(&mut self) -> fmt::Result {
+        self.open(MAP32);
+        Ok(())
+    }
+
+    fn key(&mut self, key: &str) -> fmt::Result {
+        if let Some((_, len)) = self.open.last_mut() {
+            *len += 1;
+        }
+        write_str(&mut self.out, key);
+        Ok(())
+    }
+
+    fn end_compound(&mut self) -> fmt::Result {
+        self.close();
+        Ok(())
+    }
+
+    fn begin_seq(&mut self, seq: Seq) -> fmt::Result {
+        let ext = match seq {
+            Seq::List(_) => {
+                self.open(ARRAY32);
+                return Ok(());
+            }
+            Seq::ByteArray => None,
+            Seq::IntArray => Some(INT_ARRAY_EXT),
+            Seq::LongArray => Some(LONG_ARRAY_EXT),
+        };
+        self.element();
+        self.in_array = Some(ext);
+        self.array.clear();
+        Ok(())
+    }
+
+    fn end_seq(&mut self) -> fmt::Result {
+        // Arrays only hold numbers, so the sequence that ends is the array if one is open
+        let Some(ext) = self.in_array.take() else {
+            self.close();
+            return Ok(());
+        };
+        let len = self.array.len() as u32;
+        match ext {
+            None => self.out.push(BIN32),
+            Some(_) => self.out.push(EXT32),
+        }
+        self.out.extend_from_slice(&len.to_be_bytes());
+        if let Some(ext) = ext {
+            self.out.push(ext as u8);
+        }
+        self.out.extend_from_slice(&self.array);
+        Ok(())
+    }
+
+    fn number(&mut self, number: Number) -> fmt::Result {
+        if self.in_array.is_some() {
+            number.write_be(&mut self.array);
+            return Ok(());
+        }
+        self.element();
+        let marker = match number {
+            Number::Byte(_) => 0xD0,
+            Number::Short(_) => 0xD1,
+            Number::Int(_) => 0xD2,
+            Number::Long(_) => 0xD3,
+            Number::Float(_) => 0xCA,
+            Number::Double(_) => 0xCB,
+        };
+        self.out.push(marker);
+        number.write_be(&mut self.out);
+        Ok(())
+    }
+
+    fn string(&mut self, string: &str) -> fmt::Result {
+        self.element();
+        write_str(&mut self.out, string);
+        Ok(())
+    }
+}
+
+/// Writes a string with the shortest length prefix
+fn write_str(out: &mut Vec<u8>, string: &str) {
+    let len = string.len();
+    match len {
+        0..32 => out.push(0xA0 | len as u8),
+        32..=0xFF => out.extend_from_slice(&[0xD9, len as u8]),
+        0x100..=0xFFFF => {
+            out.push(0xDA);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        _ => {
+            out.push(0xDB);
+            out.extend_from_slice(&(len as u32).to_be_bytes());
+        }
+    }
+    out.extend_from_slice(string.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CompleteFsm, snbt::SnbtReader};
+    use alloc::vec;
+
+    #[test]
+    fn lengths() {
+        let mut reader = SnbtReader::new("{l: [{a: 1b}, {}], i: [I; 1, 2], b: [B; 3], f: [1.5f]}");
+        let mut writer = MsgpackWriter::new();
+        while let Some(fragment) = reader.next_fragment().unwrap() {
+            writer.push(fragment);
+        }
+        #[rustfmt::skip]
+        let expected = vec![
+            MAP32, 0, 0, 0, 4,
+            0xA1, b'l', ARRAY32, 0, 0, 0, 2,
+                MAP32, 0, 0, 0, 1, 0xA1, b'a', 0xD0, 1,
+                MAP32, 0, 0, 0, 0,
+            0xA1, b'i', EXT32, 0, 0, 0, 8, INT_ARRAY_EXT as u8, 0, 0, 0, 1, 0, 0, 0, 2,
+            0xA1, b'b', BIN32, 0, 0, 0, 1, 3,
+            0xA1, b'f', ARRAY32, 0, 0, 0, 1, 0xCA, 0x3F, 0xC0, 0, 0,
+        ];
+        assert_eq!(writer.into_inner(), expected);
+    }
+
+    #[test]
+    fn root_end() {
+        // The End tag of an empty document
+        let mut writer = MsgpackWriter::new();
+        for fragment in CompleteFsm::new(&[0]) {
+            assert!(!writer.push(fragment.unwrap()));
+        }
+        assert!(writer.into_inner().is_empty());
+    }
+}
@@ -0,0 +1,252 @@
+//! Conversions between Rust strings and the Modified UTF-8 encoding used by NBT
+//!
+//! Modified UTF-8 differs from standard UTF-8 in two ways: the null character is encoded as the
+//! two byte sequence `0xC0 0x80`, and characters outside the basic multilingual plane are encoded
+//! as a pair of three byte surrogates instead of one four byte sequence.
+//!
+//! Decoding also accepts standard UTF-8, as plenty of NBT in the wild was not written by Java.
+use alloc::{borrow::Cow, string::String, vec::Vec};
+
+/// Decodes Modified UTF-8 (or standard UTF-8) bytes, returning None if they are invalid in both
+pub fn decode(bytes: &[u8]) -> Option<Cow<'_, str>> {
+    if let Ok(str) = core::str::from_utf8(bytes) {
+        return Some(Cow::Borrowed(str));
+    }
+    let mut out = String::with_capacity(bytes.len());
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        let unit = next_unit(&mut rest)?;
+        let char = match unit {
+            0xD800..=0xDBFF => {
+                let low = next_unit(&mut rest)?;
+                if !(0xDC00..=0xDFFF).contains(&low) {
+                    return None;
+                }
+                0x10000 + ((unit - 0xD800) << 10) + (low - 0xDC00)
+            }
+            unit => unit,
+        };
+        out.push(char::from_u32(char)?);
+    }
+    Some(Cow::Owned(out))
+}
+
+/// What to do with names and strings that are neither Modified UTF-8 nor standard UTF-8
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum StringPolicy {
+    /// Fail on invalid strings
+    #[default]
+    Strict,
+    /// Replace invalid sequences with U+FFFD
+    Lossy,
+    /// Keep the bytes of invalid strings, as a byte array in place of the string
+    ///
+    /// Names have to be strings, so invalid names are replaced lossily.
+    Raw,
+}
+
+impl StringPolicy {
+    /// Decodes a name, returning None only if it is invalid and the policy is strict
+    pub fn decode_name(self, bytes: &[u8]) -> Option<Cow<'_, str>> {
+        match (decode(bytes), self) {
+            (Some(str), _) => Some(str),
+            (None, StringPolicy::Strict) => None,
+            (None, _) => Some(String::from_utf8_lossy(bytes)),
+        }
+    }
+}
+
+/// Reads a single 1-4 byte sequence, which may decode to a lone surrogate
+#[inline]
+fn next_unit(rest: &mut &[u8]) -> Option<u32> {
+    let (&first, tail) = rest.split_first()?;
+    let (len, init) = match first {
+        0x00..=0x7F => (1, first as u32),
+        0xC0..=0xDF => (2, (first & 0x1F) as u32),
+        0xE0..=0xEF => (3, (first & 0x0F) as u32),
+        0xF0..=0xF4 => (4, (first & 0x07) as u32),
+        _ => return None,
+    };
+    let continuation = tail.get(..len - 1)?;
+    let mut unit = init;
+    for &byte in continuation {
+        if byte & 0xC0 != 0x80 {
+            return None;
+        }
+        unit = (unit << 6) | (byte & 0x3F) as u32;
+    }
+    *rest = &tail[len - 1..];
+    Some(unit)
+}
+
+/// Encodes a string as Modified UTF-8, borrowing it if no conversion is necessary
+pub fn encode(str: &str) -> Cow<'_, [u8]> {
+    if !str.bytes().any(|byte| byte == 0 || byte >= 0xF0) {
+        return Cow::Borrowed(str.as_bytes());
+    }
+    let mut out = Vec::with_capacity(str.len() + 2);
+    for char in str.chars() {
+        out.extend_from_slice(encode_char(char, &mut [0; 6]));
+    }
+    Cow::Owned(out)
+}
+
+/// Encodes a single character, which takes up to six bytes for a surrogate pair
+pub(crate) fn encode_char(char: char, buf: &mut [u8; 6]) -> &[u8] {
+    match char {
+        '\0' => {
+            buf[..2].copy_from_slice(&[0xC0, 0x80]);
+            &buf[..2]
+        }
+        char if char.len_utf16() == 2 => {
+            for (unit, out) in char.encode_utf16(&mut [0; 2]).iter().zip(buf.chunks_mut(3)) {
+                out.copy_from_slice(&[
+                    0xE0 | (unit >> 12) as u8,
+                    0x80 | ((unit >> 6) & 0x3F) as u8,
+                    0x80 | (unit & 0x3F) as u8,
+                ]);
+            }
+            buf
+        }
+        char => char.encode_utf8(buf).as_bytes(),
+    }
+}
+
+/// Checks text split over several frames, accepting exactly what [decode] accepts
+///
+/// Sequences and surrogate pairs may be split between frames, so strings can be validated as
+/// they are parsed instead of being collected first.
+///
+/// ```
+/// # use zeronbt::mutf8::Validator;
+/// let mut validator = Validator::new();
+/// assert!(validator.push(b"null\xC0"));
+/// assert!(!validator.finish());
+/// assert!(validator.push(b"\x80byte"));
+/// assert!(validator.finish());
+/// assert!(!validator.push(b"\xFF"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Validator {
+    unit: u32,
+    /// The continuation bytes still missing from the current sequence
+    missing: u8,
+    /// A high surrogate waiting for the low half of its pair
+    high: Option<u32>,
+    invalid: bool,
+}
+
+impl Validator {
+    pub const fn new() -> Self {
+        Self {
+            unit: 0,
+            missing: 0,
+            high: None,
+            invalid: false,
+        }
+    }
+
+    /// Checks the next frame, returning false once the text is invalid
+    pub fn push(&mut self, data: &[u8]) -> bool {
+        for &byte in data {
+            if self.invalid {
+                break;
+            }
+            if self.missing > 0 {
+                if byte & 0xC0 != 0x80 {
+                    self.invalid = true;
+                    break;
+                }
+                self.unit = (self.unit << 6) | (byte & 0x3F) as u32;
+                self.missing -= 1;
+            } else {
+                (self.missing, self.unit) = match byte {
+                    0x00..=0x7F => (0, byte as u32),
+                    0xC0..=0xDF => (1, (byte & 0x1F) as u32),
+                    0xE0..=0xEF => (2, (byte & 0x0F) as u32),
+                    0xF0..=0xF4 => (3, (byte & 0x07) as u32),
+                    _ => {
+                        self.invalid = true;
+                        break;
+                    }
+                };
+            }
+            if self.missing == 0 {
+                self.complete_unit();
+            }
+        }
+        !self.invalid
+    }
+
+    /// Whether the frames pushed so far form complete, valid text
+    pub fn finish(&self) -> bool {
+        !self.invalid && self.missing == 0 && self.high.is_none()
+    }
+
+    /// Prepares the validator for the next string
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    fn complete_unit(&mut self) {
+        let char = match (self.high.take(), self.unit) {
+            (Some(high), low @ 0xDC00..=0xDFFF) => {
+                0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+            }
+            (Some(_), _) => {
+                self.invalid = true;
+                return;
+            }
+            (None, high @ 0xD800..=0xDBFF) => {
+                self.high = Some(high);
+                return;
+            }
+            (None, unit) => unit,
+        };
+        self.invalid = char::from_u32(char).is_none();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for str in ["", "Level", "ÅÄÖ!", "null\0byte", "emoji 🦀 crab"] {
+            let encoded = encode(str);
+            let needs_conversion = str.contains(['\0', '🦀']);
+            assert_eq!(core::str::from_utf8(&encoded).is_err(), needs_conversion);
+            assert_eq!(decode(&encoded).as_deref(), Some(str));
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(decode(&[0xFF]), None);
+        assert_eq!(decode(&[0xC0]), None);
+        // Lone high surrogate
+        assert_eq!(decode(&[0xED, 0xA0, 0xBD]), None);
+    }
+
+    #[test]
+    fn validate_split() {
+        let inputs: [&[u8]; 7] = [
+            &encode("null\0byte"),
+            &encode("emoji 🦀 crab"),
+            "emoji 🦀 crab".as_bytes(),
+            &[0xFF],
+            &[0xC0],
+            &[0xED, 0xA0, 0xBD],
+            &[0xED, 0xB0, 0x80, 0x41],
+        ];
+        for data in inputs {
+            for split in 0..=data.len() {
+                let mut validator = Validator::new();
+                validator.push(&data[..split]);
+                validator.push(&data[split..]);
+                assert_eq!(validator.finish(), decode(data).is_some(), "{data:?}");
+            }
+        }
+    }
+}
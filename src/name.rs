@@ -0,0 +1,273 @@
+use alloc::{borrow::Cow, string::String};
+use core::fmt;
+
+use crate::mutf8;
+
+/// The raw bytes of a complete name, as found in the input
+///
+/// Names are Modified UTF-8, which stores the null character and characters outside the basic
+/// multilingual plane differently than Rust strings do, so they should not be compared to
+/// `str::as_bytes` directly. [NbtName::eq_str] compares them without decoding or allocating.
+///
+/// ```
+/// # use zeronbt::NbtName;
+/// let name = NbtName::new(b"null\xC0\x80byte");
+/// assert!(name.eq_str("null\0byte"));
+/// assert!(!name.eq_str("Null\0byte"));
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct NbtName<'d>(&'d [u8]);
+
+impl<'d> NbtName<'d> {
+    pub const fn new(bytes: &'d [u8]) -> Self {
+        Self(bytes)
+    }
+
+    pub const fn as_bytes(&self) -> &'d [u8] {
+        self.0
+    }
+
+    /// Whether the name is `str`, comparing case-sensitively
+    ///
+    /// Names written as standard UTF-8 instead of Modified UTF-8 match as well, the same way
+    /// [mutf8::decode] accepts them.
+    pub fn eq_str(&self, str: &str) -> bool {
+        // Most names are ASCII, where both encodings are the same
+        if self.0 == str.as_bytes() {
+            return true;
+        }
+        let (mut modified, mut standard) = ([0; 6], [0; 4]);
+        let mut rest = self.0;
+        for char in str.chars() {
+            let modified = mutf8::encode_char(char, &mut modified);
+            let standard = char.encode_utf8(&mut standard);
+            match rest
+                .strip_prefix(modified)
+                .or_else(|| rest.strip_prefix(standard.as_bytes()))
+            {
+                Some(tail) => rest = tail,
+                None => return false,
+            }
+        }
+        rest.is_empty()
+    }
+
+    /// Decodes the name, returning None if it is not valid Modified UTF-8 or UTF-8
+    pub fn to_str(&self) -> Option<Cow<'d, str>> {
+        mutf8::decode(self.0)
+    }
+
+    /// Decodes the name, replacing invalid data
+    pub fn to_str_lossy(&self) -> Cow<'d, str> {
+        self.to_str()
+            .unwrap_or_else(|| String::from_utf8_lossy(self.0))
+    }
+}
+
+impl fmt::Debug for NbtName<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.to_str() {
+            Some(name) => fmt::Debug::fmt(&name, f),
+            None => f.debug_tuple("NbtName").field(&self.0).finish(),
+        }
+    }
+}
+
+impl<'d> From<&'d [u8]> for NbtName<'d> {
+    fn from(bytes: &'d [u8]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl PartialEq<str> for NbtName<'_> {
+    fn eq(&self, other: &str) -> bool {
+        self.eq_str(other)
+    }
+}
+
+impl PartialEq<&str> for NbtName<'_> {
+    fn eq(&self, other: &&str) -> bool {
+        self.eq_str(other)
+    }
+}
+
+/// The state of a [NameMatcher]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NameMatch {
+    /// The frames so far are a prefix of the expected name
+    Pending,
+    /// The name ended and is equal to the expected one
+    Matched,
+    /// The name differs from the expected one
+    Failed,
+}
+
+/// Compares a name split over [NameFrame](crate::NbtFragment::NameFrame)s to an expected one as
+/// the frames arrive
+///
+/// The comparison is bytewise, so the expected name must be Modified UTF-8, see [NbtName].
+///
+/// ```
+/// # use zeronbt::{NameMatch, NameMatcher};
+/// let mut matcher = NameMatcher::new(b"Level");
+/// assert_eq!(matcher.push(b"Lev"), NameMatch::Pending);
+/// assert_eq!(matcher.push(b"el"), NameMatch::Pending);
+/// assert_eq!(matcher.push(b""), NameMatch::Matched);
+/// matcher.reset();
+/// assert_eq!(matcher.push(b"Levels"), NameMatch::Failed);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NameMatcher<'n> {
+    name: &'n [u8],
+    pos: usize,
+    state: NameMatch,
+}
+
+impl<'n> NameMatcher<'n> {
+    pub const fn new(name: &'n [u8]) -> Self {
+        Self {
+            name,
+            pos: 0,
+            state: NameMatch::Pending,
+        }
+    }
+
+    /// Compares the next frame, the empty frame ending the name decides whether it matched
+    ///
+    /// Once the name failed to match it stays failed until the matcher is [reset](Self::reset).
+    pub fn push(&mut self, frame: &[u8]) -> NameMatch {
+        if self.state != NameMatch::Pending {
+            return self.state;
+        }
+        self.state = match &self.name[self.pos..] {
+            rest if frame.is_empty() && rest.is_empty() => NameMatch::Matched,
+            rest if !frame.is_empty() && rest.starts_with(frame) => {
+                self.pos += frame.len();
+                NameMatch::Pending
+            }
+            _ => NameMatch::Failed,
+        };
+        self.state
+    }
+
+    pub fn state(&self) -> NameMatch {
+        self.state
+    }
+
+    /// Prepares the matcher for the next name
+    pub fn reset(&mut self) {
+        self.pos = 0;
+        self.state = NameMatch::Pending;
+    }
+}
+
+const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+/// Hashes a name split over [NameFrame](crate::NbtFragment::NameFrame)s as the frames arrive
+///
+/// The hash is FxHash applied a byte at a time, so it does not depend on how the name was split.
+/// [NameHasher::hash] is a `const fn`, which lets hot keys be matched on their hashes:
+///
+/// ```
+/// # use zeronbt::NameHasher;
+/// const X_POS: u64 = NameHasher::hash(b"xPos");
+/// let mut hasher = NameHasher::new();
+/// hasher.push(b"xP");
+/// hasher.push(b"os");
+/// match hasher.push(b"") {
+///     Some(X_POS) => {}
+///     _ => unreachable!(),
+/// }
+/// ```
+///
+/// Different names can share a hash, so dispatch on it should be confirmed with a comparison
+/// where collisions matter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NameHasher {
+    hash: u64,
+}
+
+impl NameHasher {
+    pub const fn new() -> Self {
+        Self { hash: 0 }
+    }
+
+    /// The hash of a complete name
+    pub const fn hash(name: &[u8]) -> u64 {
+        let mut hasher = Self::new();
+        let mut i = 0;
+        while i < name.len() {
+            hasher.byte(name[i]);
+            i += 1;
+        }
+        hasher.hash
+    }
+
+    /// Hashes the next frame, returning the hash of the whole name once the empty frame ends it
+    ///
+    /// The hasher starts over with the next name after that.
+    pub fn push(&mut self, frame: &[u8]) -> Option<u64> {
+        if frame.is_empty() {
+            return Some(core::mem::take(&mut self.hash));
+        }
+        for &byte in frame {
+            self.byte(byte);
+        }
+        None
+    }
+
+    const fn byte(&mut self, byte: u8) {
+        self.hash = (self.hash.rotate_left(5) ^ byte as u64).wrapping_mul(SEED);
+    }
+}
+
+impl Default for NameHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eq_str() {
+        for str in ["", "Level", "ÅÄÖ!", "null\0byte", "emoji 🦀 crab"] {
+            assert!(NbtName::new(&mutf8::encode(str)).eq_str(str));
+            assert!(NbtName::new(str.as_bytes()).eq_str(str));
+        }
+        let level = NbtName::new(b"Level");
+        assert!(!level.eq_str("level"));
+        assert!(!level.eq_str("Leve"));
+        assert!(!level.eq_str("Levels"));
+        assert!(!NbtName::new(b"Leve\xC0").eq_str("Level"));
+    }
+
+    #[test]
+    fn matcher() {
+        let matches = |frames: &[&[u8]]| {
+            let mut matcher = NameMatcher::new(b"Level");
+            frames.iter().map(|frame| matcher.push(frame)).last()
+        };
+        assert_eq!(matches(&[b"L", b"evel", b""]), Some(NameMatch::Matched));
+        assert_eq!(matches(&[b"Level"]), Some(NameMatch::Pending));
+        assert_eq!(matches(&[b"Leve", b""]), Some(NameMatch::Failed));
+        assert_eq!(matches(&[b"Level", b"s", b""]), Some(NameMatch::Failed));
+        assert_eq!(matches(&[b"Lx", b"evel", b""]), Some(NameMatch::Failed));
+        assert_eq!(matches(&[b""]), Some(NameMatch::Failed));
+    }
+
+    #[test]
+    fn hasher() {
+        let name = b"listTest (compound)";
+        for split in 1..name.len() {
+            let mut hasher = NameHasher::new();
+            assert_eq!(hasher.push(&name[..split]), None);
+            assert_eq!(hasher.push(&name[split..]), None);
+            assert_eq!(hasher.push(&[]), Some(NameHasher::hash(name)));
+            assert_eq!(hasher.push(&[]), Some(NameHasher::hash(b"")));
+        }
+        assert_ne!(NameHasher::hash(b"xPos"), NameHasher::hash(b"zPos"));
+    }
+}
@@ -0,0 +1,271 @@
+//! Paths selecting values inside a document, in the syntax of the `/data` command
+//!
+//! A path is a sequence of compound keys separated by dots, each optionally followed by list or
+//! array indices: `Level.Sections[3].Palette`, `Inventory[-1]` or `Items[].id`. Keys that contain
+//! anything but letters, digits and `_-+` are quoted, like `"nested compound test".egg`. An empty
+//! path selects the root.
+//!
+//! The paths of [crate::diff] use the same syntax.
+//!
+//! ```
+//! # use zeronbt::{path::NbtPath, value::NbtValue};
+//! let (_, value) = NbtValue::read(include_bytes!("../assets/bigtest.nbt")).unwrap();
+//! let path: NbtPath = "\"nested compound test\".egg.name".parse().unwrap();
+//! let names = path.select(&value);
+//! assert_eq!(names[0].as_str(), Some("Eggbert"));
+//! ```
+use alloc::{borrow::Cow, format, string::String, vec, vec::Vec};
+use core::{fmt, str::FromStr};
+
+use crate::{error::PathError, value::NbtValue};
+
+/// A step of a path
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    /// The entry of a compound with the given key
+    Key(String),
+    /// An element of a list or array, counting from the end if negative
+    Index(i32),
+    /// Every element of a list or array, written as `[]`
+    All,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct NbtPath {
+    segments: Vec<PathSegment>,
+}
+
+impl NbtPath {
+    /// The empty path, selecting the root
+    pub const fn root() -> Self {
+        Self {
+            segments: Vec::new(),
+        }
+    }
+
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    pub fn push(&mut self, segment: PathSegment) {
+        self.segments.push(segment);
+    }
+
+    /// Collects every value the path leads to
+    ///
+    /// Elements of arrays are not stored as values and are returned owned, everything else is
+    /// borrowed from `root`.
+    pub fn select<'v>(&self, root: &'v NbtValue) -> Vec<Cow<'v, NbtValue>> {
+        let mut selected = vec![Cow::Borrowed(root)];
+        for segment in &self.segments {
+            let mut next = Vec::new();
+            for value in selected {
+                // Owned values are array elements, which have no children
+                if let Cow::Borrowed(value) = value {
+                    children(value, segment, &mut next);
+                }
+            }
+            selected = next;
+        }
+        selected
+    }
+}
+
+fn children<'v>(value: &'v NbtValue, segment: &PathSegment, out: &mut Vec<Cow<'v, NbtValue>>) {
+    macro_rules! elements {
+        ($values:expr, $variant:ident) => {
+            match *segment {
+                PathSegment::Index(index) => out.extend(
+                    resolve(index, $values.len())
+                        .map(|index| Cow::Owned(NbtValue::$variant($values[index]))),
+                ),
+                _ => out.extend(
+                    $values
+                        .iter()
+                        .map(|&value| Cow::Owned(NbtValue::$variant(value))),
+                ),
+            }
+        };
+    }
+    match (value, segment) {
+        (NbtValue::Compound(compound), PathSegment::Key(key)) => {
+            out.extend(compound.get(key).map(Cow::Borrowed))
+        }
+        (_, PathSegment::Key(_)) => {}
+        (NbtValue::List(list), PathSegment::Index(index)) => {
+            out.extend(resolve(*index, list.len()).map(|index| Cow::Borrowed(&list[index])))
+        }
+        (NbtValue::List(list), _) => out.extend(list.iter().map(Cow::Borrowed)),
+        (NbtValue::ByteArray(values), _) => elements!(values, Byte),
+        (NbtValue::IntArray(values), _) => elements!(values, Int),
+        (NbtValue::LongArray(values), _) => elements!(values, Long),
+        _ => {}
+    }
+}
+
+/// Turns a possibly negative index into one counted from the start, if it is in bounds
+pub(crate) fn resolve(index: i32, len: usize) -> Option<usize> {
+    let index = match index {
+        0.. => index as usize,
+        _ => len.checked_sub(index.unsigned_abs() as usize)?,
+    };
+    (index < len).then_some(index)
+}
+
+/// Appends `key` as a path segment, quoting it unless it is a plain identifier
+pub(crate) fn push_key(path: &mut String, key: &str) {
+    if !path.is_empty() {
+        path.push('.');
+    }
+    if is_plain(key) {
+        path.push_str(key);
+    } else {
+        path.push_str(&format!("{key:?}"));
+    }
+}
+
+fn is_plain(key: &str) -> bool {
+    !key.is_empty() && key.chars().all(is_plain_char)
+}
+
+fn is_plain_char(char: char) -> bool {
+    char.is_ascii_alphanumeric() || matches!(char, '_' | '-' | '+')
+}
+
+impl fmt::Display for NbtPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut path = String::new();
+        for segment in &self.segments {
+            match segment {
+                PathSegment::Key(key) => push_key(&mut path, key),
+                PathSegment::Index(index) => path.push_str(&format!("[{index}]")),
+                PathSegment::All => path.push_str("[]"),
+            }
+        }
+        f.write_str(&path)
+    }
+}
+
+impl FromStr for NbtPath {
+    type Err = PathError;
+
+    fn from_str(path: &str) -> Result<Self, PathError> {
+        let mut chars = path.char_indices().peekable();
+        let mut segments = Vec::new();
+        while let Some(&(pos, char)) = chars.peek() {
+            match char {
+                '.' if !segments.is_empty() => {
+                    chars.next();
+                    segments.push(PathSegment::Key(key(&mut chars)?));
+                }
+                '[' => {
+                    chars.next();
+                    let mut index = String::new();
+                    loop {
+                        match chars.next() {
+                            Some((_, ']')) => break,
+                            Some((_, char)) => index.push(char),
+                            None => return Err(PathError::UnexpectedEnd),
+                        }
+                    }
+                    segments.push(match index.trim() {
+                        "" => PathSegment::All,
+                        index => PathSegment::Index(
+                            index
+                                .parse()
+                                .map_err(|_| PathError::InvalidIndex(index.into()))?,
+                        ),
+                    });
+                }
+                _ if segments.is_empty() => segments.push(PathSegment::Key(key(&mut chars)?)),
+                found => return Err(PathError::UnexpectedChar { pos, found }),
+            }
+        }
+        Ok(Self { segments })
+    }
+}
+
+/// Reads a plain or quoted key
+fn key(chars: &mut core::iter::Peekable<core::str::CharIndices<'_>>) -> Result<String, PathError> {
+    let mut key = String::new();
+    match chars.peek() {
+        Some(&(_, quote @ ('"' | '\''))) => {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some((_, char)) if char == quote => return Ok(key),
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, 'n')) => key.push('\n'),
+                        Some((_, 'r')) => key.push('\r'),
+                        Some((_, 't')) => key.push('\t'),
+                        Some((_, char)) => key.push(char),
+                        None => return Err(PathError::UnexpectedEnd),
+                    },
+                    Some((_, char)) => key.push(char),
+                    None => return Err(PathError::UnexpectedEnd),
+                }
+            }
+        }
+        Some(&(pos, found)) if !is_plain_char(found) => {
+            Err(PathError::UnexpectedChar { pos, found })
+        }
+        Some(_) => {
+            while let Some(&(_, char)) = chars.peek()
+                && is_plain_char(char)
+            {
+                key.push(char);
+                chars.next();
+            }
+            Ok(key)
+        }
+        None => Err(PathError::UnexpectedEnd),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn parse() {
+        let path: NbtPath = r#"Level.Sections[-1]."a \"b\"".x[]"#.parse().unwrap();
+        assert_eq!(
+            path.segments(),
+            [
+                PathSegment::Key("Level".into()),
+                PathSegment::Key("Sections".into()),
+                PathSegment::Index(-1),
+                PathSegment::Key("a \"b\"".into()),
+                PathSegment::Key("x".into()),
+                PathSegment::All,
+            ]
+        );
+        assert_eq!(path.to_string().parse::<NbtPath>().unwrap(), path);
+        assert_eq!("".parse::<NbtPath>().unwrap(), NbtPath::root());
+        assert_eq!("[0]".parse::<NbtPath>().unwrap().to_string(), "[0]");
+        assert_eq!(
+            "a..b".parse::<NbtPath>(),
+            Err(PathError::UnexpectedChar { pos: 2, found: '.' })
+        );
+        assert_eq!(
+            "a[x]".parse::<NbtPath>(),
+            Err(PathError::InvalidIndex("x".into()))
+        );
+        assert_eq!("\"a".parse::<NbtPath>(), Err(PathError::UnexpectedEnd));
+    }
+
+    #[test]
+    fn select() {
+        let (_, value) = NbtValue::read(include_bytes!("../assets/bigtest.nbt")).unwrap();
+        let select = |path: &str| path.parse::<NbtPath>().unwrap().select(&value);
+        assert_eq!(select("intTest")[0].as_int(), Some(i32::MAX));
+        assert_eq!(select("\"listTest (long)\"[-1]")[0].as_long(), Some(15));
+        assert_eq!(select("\"listTest (long)\"[5]").len(), 0);
+        let names = select("\"listTest (compound)\"[].name");
+        assert_eq!(names.len(), 2);
+        assert_eq!(names[1].as_str(), Some("Compound tag #1"));
+        assert_eq!(select("\"byteArrayTest (the first 1000 values of (n*n*255+n*7)%100, starting with n=0 (0, 62, 34, 16, 8, ...))\"[1]")[0].as_byte(), Some(62));
+        assert_eq!(select("intTest.missing").len(), 0);
+    }
+}
@@ -0,0 +1,545 @@
+//! Reading and writing Anvil region files (`r.x.z.mca`)
+//!
+//! A region stores 32x32 chunks. Its first 8 KiB are a header holding the location and last
+//! modification time of every chunk, followed by the chunk payloads, each aligned to 4 KiB sectors.
+//! Every payload starts with its length and [Compression] type.
+//!
+//! Chunks are addressed by their index in the header, or by their [ChunkPos] in the world once the
+//! [RegionPos] of the region is known, which [Region::open_file] takes from the file name.
+//!
+//! Chunks that do not fit in 1 MiB are stored in separate `c.x.z.mcc` files next to the region,
+//! which requires the directory to be known, see [Region::with_external_dir].
+//!
+//! McRegion files (`r.x.z.mcr`), used before 1.2, share the layout and are read the same way. Their
+//! chunks hold the old block format, see [LegacySection](crate::chunk::LegacySection) and
+//! [McRegionBlocks](crate::chunk::McRegionBlocks).
+//!
+//! Since 1.17 the entities of each chunk are stored in regions of their own, in the `entities`
+//! directory of a world, see [Region::entity_chunks].
+use alloc::{string::String, sync::Arc, vec::Vec};
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    compression::{Compression, CustomCompression, Decompress},
+    error::{NbtIoError, NbtParseError, RegionError},
+    io::NbtReader,
+    mutf8,
+    value::NbtValue,
+};
+
+mod entities;
+pub use entities::{EntityChunk, RegionEntity};
+#[cfg(feature = "rayon")]
+mod par;
+mod pos;
+pub use pos::{ChunkPos, RegionPos};
+mod verify;
+pub use verify::{ChunkIssue, ChunkProblem};
+mod write;
+
+pub const SECTOR_SIZE: usize = 4096;
+pub const HEADER_SIZE: usize = 2 * SECTOR_SIZE;
+/// The number of chunks in a region
+pub const CHUNK_COUNT: usize = 1024;
+/// Set on the compression type of chunks stored in an external `.mcc` file
+const EXTERNAL_FLAG: u8 = 0x80;
+
+/// Where a chunk is stored, in sectors from the start of the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChunkLocation {
+    pub offset: u32,
+    pub sectors: u8,
+}
+
+impl ChunkLocation {
+    const fn from_entry(entry: u32) -> Option<Self> {
+        if entry == 0 {
+            return None;
+        }
+        Some(Self {
+            offset: entry >> 8,
+            sectors: entry as u8,
+        })
+    }
+
+    const fn to_entry(self) -> u32 {
+        (self.offset << 8) | self.sectors as u32
+    }
+
+    /// The byte range of the sectors in the file
+    pub const fn byte_range(self) -> core::ops::Range<u64> {
+        let start = self.offset as u64 * SECTOR_SIZE as u64;
+        start..start + self.sectors as u64 * SECTOR_SIZE as u64
+    }
+}
+
+/// The locations and timestamps of all chunks in a region
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionHeader {
+    locations: [u32; CHUNK_COUNT],
+    timestamps: [u32; CHUNK_COUNT],
+}
+
+impl Default for RegionHeader {
+    fn default() -> Self {
+        Self {
+            locations: [0; CHUNK_COUNT],
+            timestamps: [0; CHUNK_COUNT],
+        }
+    }
+}
+
+impl RegionHeader {
+    pub fn parse(data: &[u8; HEADER_SIZE]) -> Self {
+        let mut header = Self::default();
+        let (locations, timestamps) = data.split_at(SECTOR_SIZE);
+        for (idx, (location, timestamp)) in locations
+            .chunks_exact(4)
+            .zip(timestamps.chunks_exact(4))
+            .enumerate()
+        {
+            header.locations[idx] = u32::from_be_bytes(location.try_into().unwrap());
+            header.timestamps[idx] = u32::from_be_bytes(timestamp.try_into().unwrap());
+        }
+        header
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_SIZE);
+        for location in self.locations {
+            out.extend_from_slice(&location.to_be_bytes());
+        }
+        for timestamp in self.timestamps {
+            out.extend_from_slice(&timestamp.to_be_bytes());
+        }
+        out
+    }
+
+    /// The location of the chunk at `index`, or None if it was not generated
+    ///
+    /// # Panics
+    /// Panics if `index` is not below [CHUNK_COUNT]
+    pub fn location(&self, index: usize) -> Option<ChunkLocation> {
+        ChunkLocation::from_entry(self.locations[index])
+    }
+
+    pub fn set_location(&mut self, index: usize, location: Option<ChunkLocation>) {
+        self.locations[index] = location.map_or(0, ChunkLocation::to_entry);
+    }
+
+    /// The last modification time of the chunk at `index`, in seconds since the Unix epoch
+    pub fn timestamp(&self, index: usize) -> u32 {
+        self.timestamps[index]
+    }
+
+    pub fn set_timestamp(&mut self, index: usize, timestamp: u32) {
+        self.timestamps[index] = timestamp;
+    }
+}
+
+/// A chunk payload as stored in the region, before decompression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawChunk {
+    pub compression: Compression,
+    /// The name of the algorithm for [Compression::Custom]
+    pub custom_name: Option<String>,
+    pub data: Vec<u8>,
+}
+
+/// A chunk read while iterating over a region
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionChunk {
+    pub pos: ChunkPos,
+    pub timestamp: u32,
+    pub raw: RawChunk,
+}
+
+/// A region file, read through any seekable source
+#[derive(Debug)]
+pub struct Region<F> {
+    file: F,
+    header: RegionHeader,
+    pos: RegionPos,
+    external_dir: Option<PathBuf>,
+    codecs: Vec<Arc<dyn CustomCompression>>,
+    /// The payload returned by [Region::raw_chunk]
+    buf: Vec<u8>,
+}
+
+impl Region<File> {
+    /// Opens the region file at `path` for reading, taking its position from the file name if it
+    /// has the usual `r.x.z.mca` format
+    pub fn open_file(path: impl AsRef<Path>) -> Result<Self, RegionError> {
+        let path = path.as_ref();
+        Self::open_path(File::open(path)?, path)
+    }
+
+    /// Opens the region file at `path` for reading and writing, creating it if it does not exist
+    pub fn open_file_writable(path: impl AsRef<Path>) -> Result<Self, RegionError> {
+        let path = path.as_ref();
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        Self::open_path(file, path)
+    }
+
+    fn open_path(file: File, path: &Path) -> Result<Self, RegionError> {
+        let pos = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(RegionPos::from_file_name)
+            .unwrap_or_default();
+        let mut region = Self::open(file)?.with_pos(pos);
+        region.external_dir = path.parent().map(Path::to_path_buf);
+        Ok(region)
+    }
+}
+
+impl<F: Read + Seek> Region<F> {
+    /// Reads the header of the region, an empty file is treated as an empty region
+    pub fn open(mut file: F) -> Result<Self, RegionError> {
+        let mut data = [0; HEADER_SIZE];
+        file.seek(SeekFrom::Start(0))?;
+        let header = match read_full(&mut file, &mut data)? {
+            0 => RegionHeader::default(),
+            HEADER_SIZE => RegionHeader::parse(&data),
+            _ => return Err(RegionError::InvalidHeader),
+        };
+        Ok(Self {
+            file,
+            header,
+            pos: RegionPos::default(),
+            external_dir: None,
+            codecs: Vec::new(),
+            buf: Vec::new(),
+        })
+    }
+
+    /// Sets the position of the region in the world, which is used to compute chunk positions
+    pub fn with_pos(mut self, pos: RegionPos) -> Self {
+        self.pos = pos;
+        self
+    }
+
+    pub fn pos(&self) -> RegionPos {
+        self.pos
+    }
+
+    /// Sets the directory holding the `.mcc` files of oversized chunks, which is the directory of
+    /// the region file for regions opened with [Region::open_file]
+    pub fn with_external_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.external_dir = Some(dir.into());
+        self
+    }
+
+    /// The path of the file storing the chunk at `index` if it is oversized
+    fn external_path(&self, index: usize) -> Result<PathBuf, RegionError> {
+        let pos = self.pos.chunk(index);
+        let dir = self
+            .external_dir
+            .as_ref()
+            .ok_or(RegionError::ExternalChunk(pos))?;
+        Ok(dir.join(alloc::format!("c.{}.{}.mcc", pos.x, pos.z)))
+    }
+
+    /// Registers a codec for chunks stored with [Compression::Custom] under its name
+    pub fn register_codec(&mut self, codec: Arc<dyn CustomCompression>) {
+        self.codecs.push(codec);
+    }
+
+    pub fn header(&self) -> &RegionHeader {
+        &self.header
+    }
+
+    pub fn into_inner(self) -> F {
+        self.file
+    }
+
+    /// Reads the payload of the chunk at `index`, without decompressing it
+    pub fn read_raw(&mut self, index: usize) -> Result<Option<RawChunk>, RegionError> {
+        check_index(index)?;
+        let mut data = Vec::new();
+        let Some(id) = self.read_stored(index, &mut data)? else {
+            return Ok(None);
+        };
+        let compression =
+            Compression::from_region_id(id).ok_or(RegionError::UnknownCompression(id))?;
+        let mut custom_name = None;
+        if compression == Compression::Custom {
+            let Some((&[hi, lo], rest)) = data.split_first_chunk() else {
+                return Err(RegionError::InvalidChunk(index));
+            };
+            let name_len = u16::from_be_bytes([hi, lo]) as usize;
+            let name = rest
+                .get(..name_len)
+                .and_then(mutf8::decode)
+                .ok_or(RegionError::InvalidChunk(index))?;
+            custom_name = Some(name.into_owned());
+            data.drain(..2 + name_len);
+        }
+        Ok(Some(RawChunk {
+            compression,
+            custom_name,
+            data,
+        }))
+    }
+
+    /// Reads the payload of the chunk at `pos` as it is stored, along with its compression
+    ///
+    /// Unlike [Region::read_raw], the payload is returned as it is stored, which makes this the
+    /// cheapest way to copy chunks between regions. Payloads with [Compression::Custom] start
+    /// with the name of their algorithm. The payload is read into a buffer that is reused by the
+    /// next call.
+    pub fn raw_chunk(
+        &mut self,
+        pos: ChunkPos,
+    ) -> Result<Option<(&[u8], Compression)>, RegionError> {
+        if !self.pos.contains(pos) {
+            return Err(RegionError::NotInRegion(pos));
+        }
+        let mut buf = core::mem::take(&mut self.buf);
+        let id = self.read_stored(pos.index(), &mut buf);
+        self.buf = buf;
+        let Some(id) = id? else {
+            return Ok(None);
+        };
+        let compression =
+            Compression::from_region_id(id).ok_or(RegionError::UnknownCompression(id))?;
+        Ok(Some((self.buf.as_slice(), compression)))
+    }
+
+    /// Reads the data following the compression type of the chunk at `index` into `data`,
+    /// returning the compression type
+    fn read_stored(&mut self, index: usize, data: &mut Vec<u8>) -> Result<Option<u8>, RegionError> {
+        let Some(location) = self.header.location(index) else {
+            return Ok(None);
+        };
+        if (location.offset as usize) < HEADER_SIZE / SECTOR_SIZE || location.sectors == 0 {
+            return Err(RegionError::InvalidChunk(index));
+        }
+        self.file
+            .seek(SeekFrom::Start(location.byte_range().start))?;
+        let mut prefix = [0; 5];
+        self.file.read_exact(&mut prefix)?;
+        let [l0, l1, l2, l3, id] = prefix;
+        let len = u32::from_be_bytes([l0, l1, l2, l3]) as usize;
+        if len == 0 || len + 4 > location.sectors as usize * SECTOR_SIZE {
+            return Err(RegionError::InvalidChunk(index));
+        }
+        data.clear();
+        if id & EXTERNAL_FLAG != 0 {
+            let mut file = File::open(self.external_path(index)?)?;
+            file.read_to_end(data)?;
+        } else {
+            data.resize(len - 1, 0);
+            self.file.read_exact(data)?;
+        }
+        Ok(Some(id & !EXTERNAL_FLAG))
+    }
+
+    /// Reads and decompresses the chunk at `index`, returning its NBT
+    pub fn read_chunk(&mut self, index: usize) -> Result<Option<NbtValue>, RegionError> {
+        let Some(raw) = self.read_raw(index)? else {
+            return Ok(None);
+        };
+        self.parse(&raw).map(Some)
+    }
+
+    /// Reads the chunk at `pos` without decompressing it, returning an error if it is not part of
+    /// this region
+    pub fn chunk_at(&mut self, pos: ChunkPos) -> Result<Option<RegionChunk>, RegionError> {
+        if !self.pos.contains(pos) {
+            return Err(RegionError::NotInRegion(pos));
+        }
+        self.region_chunk(pos.index())
+    }
+
+    fn region_chunk(&mut self, index: usize) -> Result<Option<RegionChunk>, RegionError> {
+        Ok(self.read_raw(index)?.map(|raw| RegionChunk {
+            pos: self.pos.chunk(index),
+            timestamp: self.header.timestamp(index),
+            raw,
+        }))
+    }
+
+    /// Iterates over all chunks present in the region, in header order
+    pub fn chunks(&mut self) -> impl Iterator<Item = Result<RegionChunk, RegionError>> + '_ {
+        (0..CHUNK_COUNT).filter_map(|index| self.region_chunk(index).transpose())
+    }
+
+    /// Iterates over all chunks present in the region, decompressing and parsing each one
+    pub fn values(
+        &mut self,
+    ) -> impl Iterator<Item = Result<(ChunkPos, u32, NbtValue), RegionError>> + '_ {
+        (0..CHUNK_COUNT).filter_map(|index| {
+            let chunk = self.region_chunk(index).transpose()?;
+            Some(chunk.and_then(|chunk| {
+                let value = self.parse(&chunk.raw)?;
+                Ok((chunk.pos, chunk.timestamp, value))
+            }))
+        })
+    }
+
+    /// Decompresses and parses a raw chunk, using the codecs registered with this region
+    pub fn parse(&self, raw: &RawChunk) -> Result<NbtValue, RegionError> {
+        let (_, value) = NbtReader::new(decompress(&self.codecs, raw)?)
+            .read_value()?
+            .ok_or(NbtIoError::Parse(NbtParseError::UnexpectedEnd))?;
+        Ok(value)
+    }
+}
+
+/// Checks that `index` is the index of a chunk, for the methods taking one
+fn check_index(index: usize) -> Result<(), RegionError> {
+    if index >= CHUNK_COUNT {
+        return Err(RegionError::InvalidIndex(index));
+    }
+    Ok(())
+}
+
+/// Creates a decompressing reader for a raw chunk, looking up custom codecs in `codecs`
+fn decompress<'d>(
+    codecs: &[Arc<dyn CustomCompression>],
+    raw: &'d RawChunk,
+) -> Result<Decompress<&'d [u8]>, RegionError> {
+    let data = raw.data.as_slice();
+    Ok(match &raw.custom_name {
+        Some(name) => {
+            let codec = codecs
+                .iter()
+                .find(|codec| codec.name() == name)
+                .ok_or_else(|| RegionError::UnknownCustomCompression(name.clone()))?;
+            Decompress::custom(&**codec, data)?
+        }
+        None => Decompress::with_compression(raw.compression, data)?,
+    })
+}
+
+/// Reads until `buf` is full or the source ends, returning the number of bytes read
+fn read_full(file: &mut impl Read, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match file.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => (),
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use std::io::Cursor;
+
+    /// Builds a region by hand, with the given chunks stored in consecutive sectors
+    pub(super) fn build_region(chunks: &[(usize, u8, &[u8])]) -> Vec<u8> {
+        let mut header = RegionHeader::default();
+        let mut body = Vec::new();
+        for &(index, compression, payload) in chunks {
+            let offset = 2 + (body.len() / SECTOR_SIZE) as u32;
+            body.extend_from_slice(&(payload.len() as u32 + 1).to_be_bytes());
+            body.push(compression);
+            body.extend_from_slice(payload);
+            body.resize(body.len().next_multiple_of(SECTOR_SIZE), 0);
+            let sectors = (2 + (body.len() / SECTOR_SIZE) as u32 - offset) as u8;
+            header.set_location(index, Some(ChunkLocation { offset, sectors }));
+            header.set_timestamp(index, index as u32);
+        }
+        let mut region = header.to_bytes();
+        region.extend_from_slice(&body);
+        region
+    }
+
+    #[test]
+    fn read_chunks() {
+        let data = include_bytes!("../assets/chunk_0-0.nbt");
+        let (_, expected) = NbtValue::read(data).unwrap();
+        let uncompressed = build_region(&[(0, 3, data), (33, 3, data)]);
+        let mut region = Region::open(Cursor::new(uncompressed)).unwrap();
+        assert_eq!(region.header().timestamp(33), 33);
+        assert_eq!(region.header().location(1), None);
+        assert_eq!(region.read_chunk(1).unwrap(), None);
+        assert_eq!(region.read_chunk(0).unwrap().as_ref(), Some(&expected));
+        assert_eq!(region.read_chunk(33).unwrap(), Some(expected.clone()));
+
+        let mut region = region.with_pos(RegionPos::new(-1, 2));
+        let positions: Vec<_> = region
+            .values()
+            .map(|chunk| chunk.map(|(pos, timestamp, _)| (pos, timestamp)))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            positions,
+            [(ChunkPos::new(-32, 64), 0), (ChunkPos::new(-31, 65), 33)]
+        );
+        let chunk = region.chunk_at(ChunkPos::new(-31, 65)).unwrap().unwrap();
+        assert_eq!(region.parse(&chunk.raw).unwrap(), expected);
+        assert!(matches!(
+            region.chunk_at(ChunkPos::new(1, 65)),
+            Err(RegionError::NotInRegion(_))
+        ));
+    }
+
+    #[cfg(feature = "flate2")]
+    #[test]
+    fn read_compressed() {
+        let data = include_bytes!("../assets/chunk_0-0.nbt");
+        let (_, expected) = NbtValue::read(data).unwrap();
+        let zlib = crate::compression::compress(Compression::Zlib, data).unwrap();
+        let gzip = crate::compression::compress(Compression::Gzip, data).unwrap();
+        let mut region =
+            Region::open(Cursor::new(build_region(&[(5, 2, &zlib), (6, 1, &gzip)]))).unwrap();
+        assert_eq!(
+            region.read_raw(5).unwrap().unwrap().compression,
+            Compression::Zlib
+        );
+        assert_eq!(region.read_chunk(5).unwrap().as_ref(), Some(&expected));
+        assert_eq!(region.read_chunk(6).unwrap(), Some(expected));
+    }
+
+    #[test]
+    fn invalid() {
+        let mut region = Region::open(Cursor::new(build_region(&[(0, 42, b"data")]))).unwrap();
+        assert!(matches!(
+            region.read_chunk(0),
+            Err(RegionError::UnknownCompression(42))
+        ));
+        assert!(matches!(
+            Region::open(Cursor::new(vec![0; 100])),
+            Err(RegionError::InvalidHeader)
+        ));
+        assert!(matches!(
+            region.read_chunk(CHUNK_COUNT),
+            Err(RegionError::InvalidIndex(CHUNK_COUNT))
+        ));
+        let empty = Region::open(Cursor::new(Vec::new())).unwrap();
+        assert_eq!(empty.header(), &RegionHeader::default());
+    }
+
+    #[test]
+    fn raw_chunks() {
+        let data = build_region(&[(0, 42, b"unknown"), (33, 3, b"stored"), (34, 1, b"gzip")]);
+        let mut region = Region::open(Cursor::new(data)).unwrap();
+        assert!(matches!(
+            region.raw_chunk(ChunkPos::new(0, 0)),
+            Err(RegionError::UnknownCompression(42))
+        ));
+        let raw = region.raw_chunk(ChunkPos::new(1, 1)).unwrap();
+        assert_eq!(raw, Some((&b"stored"[..], Compression::None)));
+        let raw = region.raw_chunk(ChunkPos::new(2, 1)).unwrap();
+        assert_eq!(raw, Some((&b"gzip"[..], Compression::Gzip)));
+        assert_eq!(region.raw_chunk(ChunkPos::new(2, 0)).unwrap(), None);
+        assert!(region.raw_chunk(ChunkPos::new(32, 0)).is_err());
+    }
+}
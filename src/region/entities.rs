@@ -0,0 +1,117 @@
+use alloc::{string::String, vec::Vec};
+use std::io::{Read, Seek};
+
+use super::{ChunkPos, Region};
+use crate::{
+    convert::{FromNbt, field, field_or},
+    error::{NbtConvertError, NbtIoError, RegionError},
+    uuid::Uuid,
+    value::{NbtCompound, NbtValue},
+};
+
+/// An entity of an entities region, with `data` holding all of its fields
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegionEntity {
+    pub id: String,
+    pub uuid: Uuid,
+    pub pos: [f64; 3],
+    /// The entities riding this one
+    pub passengers: Vec<RegionEntity>,
+    pub data: NbtCompound,
+}
+
+/// The entities stored for a chunk in an entities region
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntityChunk {
+    pub pos: ChunkPos,
+    pub timestamp: u32,
+    pub data_version: i32,
+    pub entities: Vec<RegionEntity>,
+}
+
+impl FromNbt for RegionEntity {
+    fn from_nbt(value: &NbtValue) -> Result<Self, NbtConvertError> {
+        let data: NbtCompound = FromNbt::from_nbt(value)?;
+        Ok(Self {
+            id: field(&data, "id")?,
+            uuid: field(&data, "UUID")?,
+            pos: field(&data, "Pos")?,
+            passengers: field_or(&data, "Passengers", Vec::new())?,
+            data,
+        })
+    }
+}
+
+impl<F: Read + Seek> Region<F> {
+    /// Iterates over the chunks of an entities region, `entities/r.x.z.mca`, reading the
+    /// entities of each one
+    ///
+    /// Since 1.17 entities are stored in these regions instead of the chunks of the world.
+    pub fn entity_chunks(&mut self) -> impl Iterator<Item = Result<EntityChunk, RegionError>> + '_ {
+        self.values().map(|chunk| {
+            let (pos, timestamp, value) = chunk?;
+            let read = || {
+                let compound: NbtCompound = FromNbt::from_nbt(&value)?;
+                Ok(EntityChunk {
+                    pos,
+                    timestamp,
+                    data_version: field(&compound, "DataVersion")?,
+                    entities: field_or(&compound, "Entities", Vec::new())?,
+                })
+            };
+            read().map_err(|err: NbtConvertError| NbtIoError::Convert(err).into())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{convert::ToNbt, region::tests::build_region};
+    use alloc::vec;
+    use std::io::Cursor;
+
+    #[test]
+    fn entity_chunks() {
+        let uuid = Uuid::parse("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap();
+        let entity = |id: &str, passengers: Vec<NbtValue>| -> NbtValue {
+            let mut entity = NbtCompound::new();
+            entity.insert("id", id);
+            entity.insert("UUID", uuid.to_nbt());
+            entity.insert("Pos", [1.5, 64.0, -3.5].to_nbt());
+            if !passengers.is_empty() {
+                entity.insert("Passengers", passengers.to_nbt());
+            }
+            entity.into()
+        };
+        let mut chunk = NbtCompound::new();
+        chunk.insert("DataVersion", 3465);
+        chunk.insert("Position", NbtValue::IntArray(vec![31, 0]));
+        let zombie = entity("minecraft:zombie", Vec::new());
+        let entities = vec![
+            zombie,
+            entity(
+                "minecraft:pig",
+                vec![entity("minecraft:zombie", Vec::new())],
+            ),
+        ];
+        chunk.insert("Entities", entities.to_nbt());
+        let data = NbtValue::Compound(chunk).to_bytes("").unwrap();
+
+        let region = build_region(&[(31, 3, &data)]);
+        let mut region = Region::open(Cursor::new(region)).unwrap();
+        let chunks: Vec<_> = region.entity_chunks().collect::<Result<_, _>>().unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].pos, ChunkPos::new(31, 0));
+        assert_eq!(chunks[0].data_version, 3465);
+        let [zombie, pig] = &chunks[0].entities[..] else {
+            panic!("expected two entities");
+        };
+        assert_eq!(zombie.id, "minecraft:zombie");
+        assert_eq!(zombie.uuid, uuid);
+        assert_eq!(zombie.pos, [1.5, 64.0, -3.5]);
+        assert_eq!(pig.passengers.len(), 1);
+        assert_eq!(pig.passengers[0].id, "minecraft:zombie");
+        assert!(pig.data.get("Passengers").is_some());
+    }
+}
@@ -0,0 +1,106 @@
+use alloc::{string::String, sync::Arc, vec::Vec};
+use std::io::{Read, Seek};
+
+use rayon::prelude::*;
+
+use super::{ChunkPos, Region, RegionChunk, decompress};
+use crate::{
+    FsmResult, NbtFsm,
+    compression::CustomCompression,
+    error::{NbtIoError, NbtParseError, NbtResult, RegionError},
+    value::{NbtValue, NbtValueBuilder},
+};
+
+impl<F: Read + Seek + Send> Region<F> {
+    /// Decompresses and parses the chunks of the region on the rayon thread pool, in no particular
+    /// order
+    ///
+    /// Chunks are read from the file as the worker threads ask for them. Each worker keeps a
+    /// parser and a buffer to decompress into, which are reused for all chunks it processes.
+    pub fn par_chunks(
+        &mut self,
+    ) -> impl ParallelIterator<Item = Result<(ChunkPos, u32, NbtValue), RegionError>> + '_ {
+        let codecs = self.codecs.clone();
+        self.chunks()
+            .par_bridge()
+            .map_init(Worker::default, move |worker, chunk| {
+                worker.parse(&codecs, chunk?)
+            })
+    }
+}
+
+/// The state a worker thread reuses between chunks
+#[derive(Default)]
+struct Worker {
+    buf: Vec<u8>,
+    fsm: NbtFsm<'static>,
+}
+
+impl Worker {
+    fn parse(
+        &mut self,
+        codecs: &[Arc<dyn CustomCompression>],
+        chunk: RegionChunk,
+    ) -> Result<(ChunkPos, u32, NbtValue), RegionError> {
+        self.buf.clear();
+        decompress(codecs, &chunk.raw)?.read_to_end(&mut self.buf)?;
+        // A parser that failed is left in the middle of a document, so it is only kept once it read
+        // one completely
+        let mut fsm = core::mem::take(&mut self.fsm).with_data(&self.buf);
+        let (_, value) = read_root(&mut fsm).map_err(NbtIoError::Parse)?;
+        self.fsm = fsm.with_data(&[]);
+        Ok((chunk.pos, chunk.timestamp, value))
+    }
+}
+
+/// Reads a single root tag with `fsm`, like [NbtValue::read]
+fn read_root(fsm: &mut NbtFsm<'_>) -> NbtResult<(String, NbtValue)> {
+    let mut builder = NbtValueBuilder::new();
+    loop {
+        match fsm.next_fragment()? {
+            FsmResult::Needs(_) => return Err(NbtParseError::UnexpectedEnd),
+            FsmResult::Found(fragment) => {
+                if let Some(root) = builder.push(fragment)? {
+                    return Ok(root);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::region::tests::build_region;
+    use alloc::vec;
+    use std::io::Cursor;
+
+    #[test]
+    fn parse_in_parallel() {
+        let data = include_bytes!("../../assets/chunk_0-0.nbt");
+        let (_, expected) = NbtValue::read(data).unwrap();
+        let chunks: Vec<_> = (0..64).map(|index| (index * 3, 3, &data[..])).collect();
+        let mut region = Region::open(Cursor::new(build_region(&chunks))).unwrap();
+        let mut positions: Vec<_> = region
+            .par_chunks()
+            .map(|chunk| chunk.map(|(pos, _, value)| (pos, value == expected)))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        positions.sort_unstable_by_key(|(pos, _)| pos.index());
+        assert_eq!(positions.len(), 64);
+        for ((pos, matches), index) in positions.into_iter().zip((0..64).map(|index| index * 3)) {
+            assert_eq!(pos.index(), index);
+            assert!(matches);
+        }
+
+        // The parser of a worker that failed on a chunk still reads the next ones
+        let mut chunks = vec![(0, 3, &data[..10])];
+        chunks.extend((1..64).map(|index| (index, 3, &data[..])));
+        let mut region = Region::open(Cursor::new(build_region(&chunks))).unwrap();
+        let results: Vec<_> = region.par_chunks().collect();
+        assert_eq!(results.iter().filter(|chunk| chunk.is_err()).count(), 1);
+        for chunk in results.into_iter().flatten() {
+            assert_eq!(chunk.2, expected);
+        }
+    }
+}
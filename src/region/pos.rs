@@ -0,0 +1,115 @@
+use core::fmt;
+
+/// The position of a chunk in the world, in chunks (blocks / 16)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct ChunkPos {
+    pub x: i32,
+    pub z: i32,
+}
+
+/// The position of a region in the world, in regions (chunks / 32)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct RegionPos {
+    pub x: i32,
+    pub z: i32,
+}
+
+impl ChunkPos {
+    pub const fn new(x: i32, z: i32) -> Self {
+        Self { x, z }
+    }
+
+    /// The chunk containing the block at `x`, `z`
+    pub const fn from_block(x: i32, z: i32) -> Self {
+        Self::new(x >> 4, z >> 4)
+    }
+
+    pub const fn region(self) -> RegionPos {
+        RegionPos::new(self.x >> 5, self.z >> 5)
+    }
+
+    /// The position inside the region, both coordinates are in `0..32`
+    pub const fn local(self) -> (u8, u8) {
+        ((self.x & 31) as u8, (self.z & 31) as u8)
+    }
+
+    /// The index of the chunk in the region header
+    pub const fn index(self) -> usize {
+        let (x, z) = self.local();
+        x as usize + z as usize * 32
+    }
+}
+
+impl RegionPos {
+    pub const fn new(x: i32, z: i32) -> Self {
+        Self { x, z }
+    }
+
+    /// The chunk at `index` in the header of this region
+    ///
+    /// # Panics
+    /// Panics if `index` is not below [CHUNK_COUNT](super::CHUNK_COUNT)
+    pub const fn chunk(self, index: usize) -> ChunkPos {
+        assert!(index < super::CHUNK_COUNT);
+        ChunkPos::new(
+            self.x * 32 + (index % 32) as i32,
+            self.z * 32 + (index / 32) as i32,
+        )
+    }
+
+    pub const fn contains(self, chunk: ChunkPos) -> bool {
+        let region = chunk.region();
+        region.x == self.x && region.z == self.z
+    }
+
+    /// Parses the position out of a region file name like `r.-1.2.mca`, or `r.-1.2.mcr` for
+    /// McRegion files
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        let mut parts = name.split('.');
+        let (Some("r"), Some(x), Some(z), Some(_ext), None) = (
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+        ) else {
+            return None;
+        };
+        Some(Self::new(x.parse().ok()?, z.parse().ok()?))
+    }
+}
+
+impl fmt::Display for RegionPos {
+    /// Formats the position as a region file name with the `.mca` extension
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "r.{}.{}.mca", self.x, self.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn coordinates() {
+        let chunk = ChunkPos::new(-1, 33);
+        assert_eq!(chunk.region(), RegionPos::new(-1, 1));
+        assert_eq!(chunk.local(), (31, 1));
+        assert_eq!(chunk.index(), 63);
+        assert_eq!(chunk.region().chunk(chunk.index()), chunk);
+        assert!(chunk.region().contains(chunk));
+        assert!(!RegionPos::new(0, 1).contains(chunk));
+        assert_eq!(ChunkPos::from_block(-17, 15), ChunkPos::new(-2, 0));
+    }
+
+    #[test]
+    fn file_names() {
+        let pos = RegionPos::new(-3, 12);
+        assert_eq!(pos.to_string(), "r.-3.12.mca");
+        assert_eq!(RegionPos::from_file_name("r.-3.12.mca"), Some(pos));
+        assert_eq!(RegionPos::from_file_name("r.-3.12.mcr"), Some(pos));
+        assert_eq!(RegionPos::from_file_name("r.1.mca"), None);
+        assert_eq!(RegionPos::from_file_name("c.1.2.mcc"), None);
+    }
+}
@@ -0,0 +1,174 @@
+use alloc::vec::Vec;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use super::{CHUNK_COUNT, HEADER_SIZE, Region, SECTOR_SIZE, decompress};
+use crate::{
+    error::{NbtIoError, NbtParseError, RegionError},
+    io::NbtReader,
+};
+
+/// A problem found in a chunk by [Region::verify]
+#[derive(Debug)]
+pub enum ChunkIssue {
+    /// The sectors of the chunk overlap the header or extend past the end of the file
+    OutOfBounds,
+    /// The chunk shares sectors with the chunk at the given index
+    Overlap(usize),
+    /// The chunk can not be read, decompressed or parsed
+    Corrupt(RegionError),
+}
+
+#[derive(Debug)]
+pub struct ChunkProblem {
+    pub index: usize,
+    pub issue: ChunkIssue,
+}
+
+impl<F: Read + Seek> Region<F> {
+    /// Checks the header and every chunk in the region, returning all problems found
+    ///
+    /// Chunks are parsed without building their values, see [NbtReader::skip_value]. Errors are
+    /// only returned if the file itself can not be accessed.
+    pub fn verify(&mut self) -> Result<Vec<ChunkProblem>, RegionError> {
+        let sectors = self
+            .file
+            .seek(SeekFrom::End(0))?
+            .div_ceil(SECTOR_SIZE as u64);
+        let mut problems = Vec::new();
+        let mut ranges = Vec::new();
+        for index in 0..CHUNK_COUNT {
+            let Some(location) = self.header.location(index) else {
+                continue;
+            };
+            let start = location.offset as u64;
+            let end = start + location.sectors as u64;
+            if start < (HEADER_SIZE / SECTOR_SIZE) as u64 || location.sectors == 0 || end > sectors
+            {
+                problems.push(ChunkProblem {
+                    index,
+                    issue: ChunkIssue::OutOfBounds,
+                });
+                continue;
+            }
+            ranges.push((start, end, index));
+            if let Err(err) = self.check_chunk(index) {
+                problems.push(ChunkProblem {
+                    index,
+                    issue: ChunkIssue::Corrupt(err),
+                });
+            }
+        }
+        ranges.sort_unstable();
+        for (i, &(_, end, first)) in ranges.iter().enumerate() {
+            let overlapping = ranges[i + 1..]
+                .iter()
+                .take_while(|(start, ..)| *start < end);
+            for &(_, _, second) in overlapping {
+                problems.push(ChunkProblem {
+                    index: second,
+                    issue: ChunkIssue::Overlap(first),
+                });
+            }
+        }
+        problems.sort_by_key(|problem| problem.index);
+        Ok(problems)
+    }
+
+    fn check_chunk(&mut self, index: usize) -> Result<(), RegionError> {
+        let raw = self
+            .read_raw(index)?
+            .ok_or(RegionError::InvalidChunk(index))?;
+        let mut reader = NbtReader::new(decompress(&self.codecs, &raw)?);
+        if !reader.skip_value()? {
+            return Err(NbtIoError::Parse(NbtParseError::UnexpectedEnd).into());
+        }
+        Ok(())
+    }
+}
+
+impl<F: Read + Write + Seek> Region<F> {
+    /// Verifies the region, removing chunks that are out of bounds or corrupt, and moving intact
+    /// chunks that share sectors with others to sectors of their own
+    ///
+    /// Returns the problems that were found, which no longer apply once this returns.
+    pub fn repair(&mut self) -> Result<Vec<ChunkProblem>, RegionError> {
+        let problems = self.verify()?;
+        let broken = |index: usize| {
+            problems.iter().any(|problem| {
+                problem.index == index && !matches!(problem.issue, ChunkIssue::Overlap(_))
+            })
+        };
+        for problem in &problems {
+            if broken(problem.index) {
+                self.remove_chunk(problem.index)?;
+            }
+        }
+        for problem in &problems {
+            let ChunkIssue::Overlap(other) = problem.issue else {
+                continue;
+            };
+            // Dropping a corrupt chunk already frees the shared sectors
+            if broken(problem.index) || broken(other) {
+                continue;
+            }
+            let index = problem.index;
+            if let Some(raw) = self.read_raw(index)? {
+                let timestamp = self.header.timestamp(index);
+                self.header.set_location(index, None);
+                self.write_raw(index, &raw, timestamp)?;
+            }
+        }
+        Ok(problems)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::region::{ChunkLocation, tests::build_region};
+    use std::io::Cursor;
+
+    #[test]
+    fn verify_and_repair() {
+        let data = include_bytes!("../../assets/chunk_0-0.nbt");
+        let mut file = build_region(&[
+            (0, 3, data),
+            (1, 3, &data[..100]),
+            (2, 9, data),
+            (3, 3, data),
+        ]);
+        let location = |index: usize| {
+            let bytes: [u8; 4] = file[index * 4..index * 4 + 4].try_into().unwrap();
+            u32::from_be_bytes(bytes)
+        };
+        // Point chunk 4 at the sectors of chunk 3 and chunk 5 past the end of the file
+        let shared = location(3);
+        file[16..20].copy_from_slice(&shared.to_be_bytes());
+        file[20..24].copy_from_slice(&(1000u32 << 8 | 1).to_be_bytes());
+
+        let mut region = Region::open(Cursor::new(file)).unwrap();
+        let problems = region.verify().unwrap();
+        let summary: Vec<_> = problems
+            .iter()
+            .map(|problem| (problem.index, &problem.issue))
+            .collect();
+        assert!(matches!(
+            summary.as_slice(),
+            [
+                (1, ChunkIssue::Corrupt(_)),
+                (2, ChunkIssue::Corrupt(RegionError::UnknownCompression(9))),
+                (4, ChunkIssue::Overlap(3)),
+                (5, ChunkIssue::OutOfBounds),
+            ]
+        ));
+
+        assert_eq!(region.repair().unwrap().len(), 4);
+        assert!(region.verify().unwrap().is_empty());
+        for index in [1, 2, 5] {
+            assert_eq!(region.header().location(index), None);
+        }
+        let ChunkLocation { offset, .. } = region.header().location(4).unwrap();
+        assert_ne!(offset, shared >> 8);
+        assert_eq!(region.read_chunk(4).unwrap(), region.read_chunk(3).unwrap());
+    }
+}
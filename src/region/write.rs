@@ -0,0 +1,429 @@
+use alloc::{vec, vec::Vec};
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom, Write},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::{
+    CHUNK_COUNT, ChunkLocation, ChunkPos, EXTERNAL_FLAG, HEADER_SIZE, RawChunk, Region,
+    SECTOR_SIZE, check_index,
+};
+use crate::{
+    compression::{Compression, compress},
+    error::{NbtIoError, RegionError},
+    mutf8,
+    value::NbtValue,
+};
+
+/// The first sector after the header
+const FIRST_SECTOR: u32 = (HEADER_SIZE / SECTOR_SIZE) as u32;
+
+impl RawChunk {
+    /// Encodes the chunk as it is stored in the region, without the sector padding
+    pub fn encode(&self) -> Result<Vec<u8>, RegionError> {
+        Ok(encode(self.region_id()?, &self.payload()?))
+    }
+
+    fn region_id(&self) -> Result<u8, RegionError> {
+        self.compression
+            .region_id()
+            .ok_or(RegionError::UnsupportedCompression(self.compression))
+    }
+
+    /// The data following the compression type, which is also the content of `.mcc` files
+    fn payload(&self) -> Result<Vec<u8>, RegionError> {
+        let mut out = Vec::new();
+        if let Some(custom) = &self.custom_name {
+            let encoded = mutf8::encode(custom);
+            let len = u16::try_from(encoded.len())
+                .map_err(|_| RegionError::UnsupportedCompression(self.compression))?;
+            out.extend_from_slice(&len.to_be_bytes());
+            out.extend_from_slice(&encoded);
+        }
+        out.extend_from_slice(&self.data);
+        Ok(out)
+    }
+}
+
+/// Prefixes a payload with its length and compression type
+fn encode(id: u8, payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(5 + payload.len());
+    out.extend_from_slice(&(payload.len() as u32 + 1).to_be_bytes());
+    out.push(id);
+    out.extend_from_slice(payload);
+    out
+}
+
+fn remove_external(path: &std::path::Path) -> Result<(), RegionError> {
+    match std::fs::remove_file(path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+fn now() -> u32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs() as u32)
+}
+
+impl<F: Read + Write + Seek> Region<F> {
+    /// Stores an already compressed chunk at `index`, moving it if it no longer fits in its
+    /// sectors
+    ///
+    /// Chunks that do not fit in 255 sectors are stored in an external `.mcc` file if the
+    /// directory for those is known.
+    pub fn write_raw(
+        &mut self,
+        index: usize,
+        chunk: &RawChunk,
+        timestamp: u32,
+    ) -> Result<(), RegionError> {
+        check_index(index)?;
+        self.write_stored(index, chunk.compression, &chunk.payload()?, timestamp)
+    }
+
+    /// Stores a payload as returned by [Region::raw_chunk] at `index`
+    fn write_stored(
+        &mut self,
+        index: usize,
+        compression: Compression,
+        payload: &[u8],
+        timestamp: u32,
+    ) -> Result<(), RegionError> {
+        let id = compression
+            .region_id()
+            .ok_or(RegionError::UnsupportedCompression(compression))?;
+        let external = self.external_path(index);
+        let (mut encoded, sectors) = match u8::try_from((5 + payload.len()).div_ceil(SECTOR_SIZE)) {
+            Ok(sectors) => {
+                if let Ok(path) = &external {
+                    remove_external(path)?;
+                }
+                (encode(id, payload), sectors)
+            }
+            Err(_) => {
+                let path = external.map_err(|_| RegionError::ChunkTooLarge(index))?;
+                std::fs::write(path, payload)?;
+                ([0, 0, 0, 1, id | EXTERNAL_FLAG].to_vec(), 1)
+            }
+        };
+        encoded.resize(sectors as usize * SECTOR_SIZE, 0);
+
+        let offset = match self.header.location(index) {
+            Some(old) if old.sectors >= sectors && old.offset >= FIRST_SECTOR => old.offset,
+            _ => self.allocate(index, sectors),
+        };
+        self.file
+            .seek(SeekFrom::Start(offset as u64 * SECTOR_SIZE as u64))?;
+        self.file.write_all(&encoded)?;
+        self.header
+            .set_location(index, Some(ChunkLocation { offset, sectors }));
+        self.header.set_timestamp(index, timestamp);
+        self.write_header_entry(index)
+    }
+
+    /// Copies the chunk at `from` in `source` to `to` in this region, along with its timestamp,
+    /// returning false if there is no chunk at `from`
+    ///
+    /// The chunk is copied as it is stored, without decompressing it.
+    pub fn copy_chunk<G: Read + Seek>(
+        &mut self,
+        to: ChunkPos,
+        source: &mut Region<G>,
+        from: ChunkPos,
+    ) -> Result<bool, RegionError> {
+        if !self.pos.contains(to) {
+            return Err(RegionError::NotInRegion(to));
+        }
+        let timestamp = source.header.timestamp(from.index());
+        let Some((payload, compression)) = source.raw_chunk(from)? else {
+            return Ok(false);
+        };
+        self.write_stored(to.index(), compression, payload, timestamp)?;
+        Ok(true)
+    }
+
+    /// Moves the chunk at `from` in `source` to `to` in this region, returning false if there is
+    /// no chunk at `from`
+    ///
+    /// Chunks can be moved within one region with [Region::relocate_chunk].
+    pub fn move_chunk<G: Read + Write + Seek>(
+        &mut self,
+        to: ChunkPos,
+        source: &mut Region<G>,
+        from: ChunkPos,
+    ) -> Result<bool, RegionError> {
+        if !self.copy_chunk(to, source, from)? {
+            return Ok(false);
+        }
+        source.remove_chunk(from.index())?;
+        Ok(true)
+    }
+
+    /// Moves the chunk at index `from` to index `to` of this region, replacing any chunk there,
+    /// returning false if there is no chunk at `from`
+    ///
+    /// The sectors of the chunk stay where they are, only the header entries are updated, unless
+    /// the chunk is stored in an external file, which is renamed.
+    pub fn relocate_chunk(&mut self, from: usize, to: usize) -> Result<bool, RegionError> {
+        check_index(from)?;
+        check_index(to)?;
+        let Some(location) = self.header.location(from) else {
+            return Ok(false);
+        };
+        if from == to {
+            return Ok(true);
+        }
+        if let (Ok(old), Ok(new)) = (self.external_path(from), self.external_path(to)) {
+            match std::fs::rename(old, &new) {
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => remove_external(&new)?,
+                result => result?,
+            }
+        }
+        let timestamp = self.header.timestamp(from);
+        self.header.set_location(to, Some(location));
+        self.header.set_timestamp(to, timestamp);
+        self.write_header_entry(to)?;
+        self.header.set_location(from, None);
+        self.header.set_timestamp(from, 0);
+        self.write_header_entry(from)?;
+        Ok(true)
+    }
+
+    /// Encodes, compresses and stores a chunk at `index`, stamped with the current time
+    pub fn write_chunk(
+        &mut self,
+        index: usize,
+        value: &NbtValue,
+        compression: Compression,
+    ) -> Result<(), RegionError> {
+        let data = value.to_bytes("").map_err(NbtIoError::from)?;
+        let chunk = RawChunk {
+            compression,
+            custom_name: None,
+            data: compress(compression, &data)?,
+        };
+        self.write_raw(index, &chunk, now())
+    }
+
+    /// Removes the chunk at `index`, freeing its sectors for reuse
+    pub fn remove_chunk(&mut self, index: usize) -> Result<(), RegionError> {
+        check_index(index)?;
+        if let Ok(path) = self.external_path(index) {
+            remove_external(&path)?;
+        }
+        self.header.set_location(index, None);
+        self.header.set_timestamp(index, 0);
+        self.write_header_entry(index)
+    }
+
+    /// Rewrites all chunks back to back in index order, removing any unused sectors between them
+    ///
+    /// Returns the new length of the file in bytes. The source is not truncated, so any data after
+    /// that length has to be removed by the caller, which [Region::compact] does for files.
+    pub fn defragment(&mut self) -> Result<u64, RegionError> {
+        let mut chunks = Vec::new();
+        for index in 0..CHUNK_COUNT {
+            let Some(location) = self.header.location(index) else {
+                continue;
+            };
+            let mut data = vec![0; location.sectors as usize * SECTOR_SIZE];
+            self.file
+                .seek(SeekFrom::Start(location.byte_range().start))?;
+            self.file.read_exact(&mut data)?;
+            chunks.push((index, location.sectors, data));
+        }
+        let mut offset = FIRST_SECTOR;
+        self.file
+            .seek(SeekFrom::Start(FIRST_SECTOR as u64 * SECTOR_SIZE as u64))?;
+        for (index, sectors, data) in chunks {
+            self.file.write_all(&data)?;
+            self.header
+                .set_location(index, Some(ChunkLocation { offset, sectors }));
+            offset += sectors as u32;
+        }
+        self.write_header()?;
+        Ok(offset as u64 * SECTOR_SIZE as u64)
+    }
+
+    /// Finds the first run of `sectors` free sectors, ignoring the ones used by `index`
+    fn allocate(&self, index: usize, sectors: u8) -> u32 {
+        let mut used: Vec<(u32, u32)> = (0..CHUNK_COUNT)
+            .filter(|&other| other != index)
+            .filter_map(|other| self.header.location(other))
+            .map(|location| (location.offset, location.offset + location.sectors as u32))
+            .collect();
+        used.sort_unstable();
+        let mut start = FIRST_SECTOR;
+        for (used_start, used_end) in used {
+            if used_start >= start + sectors as u32 {
+                break;
+            }
+            start = start.max(used_end);
+        }
+        start
+    }
+
+    fn write_header_entry(&mut self, index: usize) -> Result<(), RegionError> {
+        if self.file.seek(SeekFrom::End(0))? < HEADER_SIZE as u64 {
+            return self.write_header();
+        }
+        let location = self.header.locations[index];
+        self.file.seek(SeekFrom::Start(index as u64 * 4))?;
+        self.file.write_all(&location.to_be_bytes())?;
+        let timestamp = self.header.timestamps[index];
+        self.file
+            .seek(SeekFrom::Start((SECTOR_SIZE + index * 4) as u64))?;
+        self.file.write_all(&timestamp.to_be_bytes())?;
+        Ok(())
+    }
+
+    fn write_header(&mut self) -> Result<(), RegionError> {
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(&self.header.to_bytes())?;
+        Ok(())
+    }
+}
+
+impl Region<File> {
+    /// Defragments the region and truncates the file to its new length
+    pub fn compact(&mut self) -> Result<(), RegionError> {
+        let len = self.defragment()?;
+        self.file.set_len(len)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn raw(len: usize, fill: u8) -> RawChunk {
+        RawChunk {
+            compression: Compression::None,
+            custom_name: None,
+            data: vec![fill; len],
+        }
+    }
+
+    #[test]
+    fn allocate_and_grow() {
+        let mut region = Region::open(Cursor::new(Vec::new())).unwrap();
+        region.write_raw(0, &raw(100, 1), 10).unwrap();
+        region.write_raw(1, &raw(5000, 2), 11).unwrap();
+        assert_eq!(
+            region.header().location(1),
+            Some(ChunkLocation {
+                offset: 3,
+                sectors: 2
+            })
+        );
+        // Growing chunk 0 moves it after chunk 1, freeing sector 2
+        region.write_raw(0, &raw(4096, 3), 12).unwrap();
+        assert_eq!(region.header().location(0).unwrap().offset, 5);
+        region.write_raw(2, &raw(10, 4), 13).unwrap();
+        assert_eq!(region.header().location(2).unwrap().offset, 2);
+
+        let data = region.into_inner().into_inner();
+        assert_eq!(data.len() % SECTOR_SIZE, 0);
+        let mut region = Region::open(Cursor::new(data)).unwrap();
+        assert_eq!(region.header().timestamp(0), 12);
+        assert_eq!(region.read_raw(0).unwrap(), Some(raw(4096, 3)));
+        assert_eq!(region.read_raw(1).unwrap(), Some(raw(5000, 2)));
+        assert_eq!(region.read_raw(2).unwrap(), Some(raw(10, 4)));
+    }
+
+    #[test]
+    fn defragment() {
+        let mut region = Region::open(Cursor::new(Vec::new())).unwrap();
+        for index in 0..4 {
+            region.write_raw(index, &raw(5000, index as u8), 0).unwrap();
+        }
+        region.remove_chunk(1).unwrap();
+        region.remove_chunk(2).unwrap();
+        assert!(matches!(
+            region.remove_chunk(CHUNK_COUNT),
+            Err(RegionError::InvalidIndex(CHUNK_COUNT))
+        ));
+        let len = region.defragment().unwrap();
+        assert_eq!(len, 6 * SECTOR_SIZE as u64);
+        assert_eq!(region.header().location(3).unwrap().offset, 4);
+
+        let mut data = region.into_inner().into_inner();
+        data.truncate(len as usize);
+        let mut region = Region::open(Cursor::new(data)).unwrap();
+        assert_eq!(region.read_raw(0).unwrap(), Some(raw(5000, 0)));
+        assert_eq!(region.read_raw(3).unwrap(), Some(raw(5000, 3)));
+        assert_eq!(region.read_raw(1).unwrap(), None);
+    }
+
+    #[test]
+    fn move_chunks() {
+        let mut source = Region::open(Cursor::new(Vec::new())).unwrap();
+        source.write_raw(0, &raw(100, 1), 10).unwrap();
+        source.write_raw(1, &raw(5000, 2), 11).unwrap();
+        let mut target = Region::open(Cursor::new(Vec::new()))
+            .unwrap()
+            .with_pos(crate::region::RegionPos::new(1, 0));
+        let (from, to) = (ChunkPos::new(1, 0), ChunkPos::new(34, 2));
+        assert!(target.move_chunk(to, &mut source, from).unwrap());
+        assert!(!target.move_chunk(to, &mut source, from).unwrap());
+        assert_eq!(source.read_raw(1).unwrap(), None);
+        assert_eq!(target.read_raw(to.index()).unwrap(), Some(raw(5000, 2)));
+        assert_eq!(target.header().timestamp(to.index()), 11);
+        assert!(matches!(
+            target.copy_chunk(from, &mut source, ChunkPos::new(0, 0)),
+            Err(RegionError::NotInRegion(_))
+        ));
+
+        assert!(source.relocate_chunk(0, 5).unwrap());
+        assert!(!source.relocate_chunk(0, 6).unwrap());
+        assert!(matches!(
+            source.relocate_chunk(5, CHUNK_COUNT),
+            Err(RegionError::InvalidIndex(CHUNK_COUNT))
+        ));
+        assert_eq!(source.read_raw(0).unwrap(), None);
+        assert_eq!(source.read_raw(5).unwrap(), Some(raw(100, 1)));
+        assert_eq!(source.header().timestamp(5), 10);
+    }
+
+    #[test]
+    fn external_chunks() {
+        let dir = std::env::temp_dir().join(alloc::format!("zeronbt-mcc-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let big = raw(300 * SECTOR_SIZE, 5);
+
+        let mut region = Region::open(Cursor::new(Vec::new())).unwrap();
+        assert!(matches!(
+            region.write_raw(0, &big, 0),
+            Err(RegionError::ChunkTooLarge(0))
+        ));
+
+        let mut region = region
+            .with_pos(crate::region::RegionPos::new(1, -1))
+            .with_external_dir(&dir);
+        region.write_raw(33, &big, 0).unwrap();
+        let path = dir.join("c.33.-31.mcc");
+        assert_eq!(std::fs::read(&path).unwrap(), big.data);
+        assert_eq!(region.header().location(33).unwrap().sectors, 1);
+        assert_eq!(region.read_raw(33).unwrap(), Some(big));
+
+        // Shrinking the chunk moves it back into the region
+        region.write_raw(33, &raw(10, 6), 0).unwrap();
+        assert!(!path.exists());
+        assert_eq!(region.read_raw(33).unwrap(), Some(raw(10, 6)));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn write_values() {
+        let (_, value) = NbtValue::read(include_bytes!("../../assets/chunk_0-0.nbt")).unwrap();
+        let mut region = Region::open(Cursor::new(Vec::new())).unwrap();
+        region.write_chunk(7, &value, Compression::None).unwrap();
+        assert_ne!(region.header().timestamp(7), 0);
+        assert_eq!(region.read_chunk(7).unwrap(), Some(value));
+    }
+}
@@ -0,0 +1,152 @@
+//! Crash-safe saving of world files
+//!
+//! Files are written to a temporary file next to the target, synced to disk and then renamed over
+//! the target, so the target always holds either the old or the new content. Like vanilla, the
+//! previous version can be kept as a backup with an `_old` suffix, e.g. `level.dat_old`.
+use alloc::format;
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    compression::{Compress, Compression},
+    error::NbtIoError,
+    value::NbtValue,
+};
+
+/// Options for saving a file atomically
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AtomicSave {
+    backup: bool,
+}
+
+impl AtomicSave {
+    pub const fn new() -> Self {
+        Self { backup: false }
+    }
+
+    /// Keeps the previous content of the file as `<name>_old`
+    pub const fn backup(mut self, backup: bool) -> Self {
+        self.backup = backup;
+        self
+    }
+
+    /// Writes the file through `write`, only replacing the target once everything was written
+    /// and synced
+    ///
+    /// If `write` or any of the file operations fail, the target is left untouched.
+    pub fn write<T>(
+        &self,
+        path: impl AsRef<Path>,
+        write: impl FnOnce(&mut BufWriter<File>) -> io::Result<T>,
+    ) -> io::Result<T> {
+        let path = path.as_ref();
+        let temp = suffixed(path, &format!(".tmp{}", std::process::id()));
+        let result = (|| {
+            let mut file = BufWriter::new(File::create(&temp)?);
+            let value = write(&mut file)?;
+            file.into_inner()?.sync_all()?;
+            if self.backup && path.exists() {
+                let old = backup_path(path);
+                let _ = fs::remove_file(&old);
+                // A hard link keeps the target in place until the rename replaces it
+                if fs::hard_link(path, &old).is_err() {
+                    fs::copy(path, &old)?;
+                }
+            }
+            fs::rename(&temp, path)?;
+            Ok(value)
+        })();
+        if result.is_err() {
+            let _ = fs::remove_file(&temp);
+            return result;
+        }
+        sync_dir(path);
+        result
+    }
+
+    pub fn write_bytes(&self, path: impl AsRef<Path>, data: &[u8]) -> io::Result<()> {
+        self.write(path, |file| file.write_all(data))
+    }
+
+    /// Encodes and compresses `value`, as used for `level.dat` and player data
+    pub fn write_value(
+        &self,
+        path: impl AsRef<Path>,
+        name: &str,
+        value: &NbtValue,
+        compression: Compression,
+    ) -> Result<(), NbtIoError> {
+        let data = value.to_bytes(name)?;
+        self.write(path, |file| {
+            let mut compress = Compress::new(compression, file)?;
+            compress.write_all(&data)?;
+            compress.finish()?;
+            Ok(())
+        })?;
+        Ok(())
+    }
+}
+
+/// The path the previous version of `path` is kept at
+pub fn backup_path(path: &Path) -> PathBuf {
+    suffixed(path, "_old")
+}
+
+fn suffixed(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().map_or_else(OsString::new, OsString::from);
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Makes the rename durable, which is only needed and possible on unix
+fn sync_dir(path: &Path) {
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        let dir = if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        };
+        let _ = File::open(dir).and_then(|dir| dir.sync_all());
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_with_backup() {
+        let dir = std::env::temp_dir().join(format!("zeronbt-save-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("level.dat");
+        let save = AtomicSave::new().backup(true);
+
+        save.write_bytes(&path, b"first").unwrap();
+        assert!(!backup_path(&path).exists());
+        save.write_bytes(&path, b"second").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        assert_eq!(fs::read(dir.join("level.dat_old")).unwrap(), b"first");
+
+        let failed = save.write(&path, |file| {
+            file.write_all(b"partial")?;
+            Err::<(), _>(io::Error::other("crash"))
+        });
+        assert!(failed.is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        let value = NbtValue::Compound([("Data", 1)].into_iter().collect());
+        AtomicSave::new()
+            .write_value(&path, "", &value, Compression::None)
+            .unwrap();
+        assert_eq!(NbtValue::read(&fs::read(&path).unwrap()).unwrap().1, value);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,511 @@
+//! Reading and writing Sponge schematics (`.schem`), versions 1 to 3
+//!
+//! Schematic files are usually gzip compressed, this module works with the decompressed NBT, see
+//! [compression](crate::compression) for decompressing them. Blocks and biomes are stored as a
+//! palette and indices into it, encoded as a stream of varints.
+use alloc::{string::String, vec, vec::Vec};
+
+use crate::{
+    NbtTag,
+    convert::{FromNbt, NbtArray, ToNbt, field, field_or},
+    error::{NbtConvertError, SchemError},
+    value::{NbtCompound, NbtList, NbtValue},
+};
+
+/// Palette entries along with the palette index of every position
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PaletteData {
+    pub palette: Vec<String>,
+    pub data: Vec<u32>,
+}
+
+/// A block entity, with `data` holding all fields except for the position and id
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SchemBlockEntity {
+    pub pos: [i32; 3],
+    pub id: String,
+    pub data: NbtCompound,
+}
+
+/// An entity, with `data` holding all fields except for the position and id
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SchemEntity {
+    pub pos: [f64; 3],
+    pub id: String,
+    pub data: NbtCompound,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schematic {
+    /// The format version, which also determines the layout used when writing
+    pub version: i32,
+    pub data_version: i32,
+    pub width: u16,
+    pub height: u16,
+    pub length: u16,
+    pub offset: [i32; 3],
+    pub metadata: Option<NbtCompound>,
+    /// The blocks, indexed by `x + z * width + y * width * length`
+    pub blocks: PaletteData,
+    pub block_entities: Vec<SchemBlockEntity>,
+    pub entities: Vec<SchemEntity>,
+    /// The biomes, which are two-dimensional before version 3
+    pub biomes: Option<PaletteData>,
+}
+
+impl Schematic {
+    /// Creates an empty schematic of the latest version, filled with the first palette entry
+    pub fn new(data_version: i32, width: u16, height: u16, length: u16) -> Self {
+        Self {
+            version: 3,
+            data_version,
+            width,
+            height,
+            length,
+            offset: [0; 3],
+            metadata: None,
+            blocks: PaletteData {
+                palette: vec!["minecraft:air".into()],
+                data: vec![0; width as usize * height as usize * length as usize],
+            },
+            block_entities: Vec::new(),
+            entities: Vec::new(),
+            biomes: None,
+        }
+    }
+
+    /// Parses a schematic from uncompressed NBT
+    pub fn read(data: &[u8]) -> Result<Self, SchemError> {
+        let (_, root) = NbtValue::read(data)?;
+        Self::from_value(&root)
+    }
+
+    /// Encodes the schematic as uncompressed NBT
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SchemError> {
+        Ok(match self.version {
+            3 => {
+                let root: NbtCompound = [("Schematic", self.to_value()?)].into_iter().collect();
+                NbtValue::Compound(root).to_bytes("")?
+            }
+            _ => self.to_value()?.to_bytes("Schematic")?,
+        })
+    }
+
+    /// Reads a schematic from its root compound, which contains a `Schematic` compound since
+    /// version 3
+    pub fn from_value(root: &NbtValue) -> Result<Self, SchemError> {
+        let schematic = match root.get("Schematic") {
+            Some(inner) => inner,
+            None => root,
+        };
+        let schematic = compound(schematic)?;
+        let version: i32 = field(schematic, "Version")?;
+        if !(1..=3).contains(&version) {
+            return Err(SchemError::UnsupportedVersion(version));
+        }
+        let width: i16 = field(schematic, "Width")?;
+        let height: i16 = field(schematic, "Height")?;
+        let length: i16 = field(schematic, "Length")?;
+        let (width, height, length) = (width as u16, height as u16, length as u16);
+        let volume = width as usize * height as usize * length as usize;
+
+        let (blocks, block_entities, biomes) = if version == 3 {
+            let blocks = compound(
+                schematic
+                    .get("Blocks")
+                    .ok_or_else(|| NbtConvertError::MissingKey("Blocks".into()))?,
+            )?;
+            let biomes = match schematic.get("Biomes") {
+                Some(biomes) => Some(read_palette_data(compound(biomes)?, "Palette", "Data")?),
+                None => None,
+            };
+            (
+                read_palette_data(blocks, "Palette", "Data")?,
+                read_block_entities(blocks.get("BlockEntities"), true)?,
+                biomes,
+            )
+        } else {
+            let biomes = match schematic.get("BiomePalette") {
+                Some(_) => Some(read_palette_data(schematic, "BiomePalette", "BiomeData")?),
+                None => None,
+            };
+            let block_entities = schematic
+                .get("BlockEntities")
+                .or_else(|| schematic.get("TileEntities"));
+            (
+                read_palette_data(schematic, "Palette", "BlockData")?,
+                read_block_entities(block_entities, false)?,
+                biomes,
+            )
+        };
+        if blocks.data.len() != volume {
+            return Err(SchemError::InvalidBlockData {
+                expected: volume,
+                found: blocks.data.len(),
+            });
+        }
+
+        let offset = match schematic.get("Offset") {
+            Some(offset) => vec3(&i32::from_nbt_array(offset)?)?,
+            None => [0; 3],
+        };
+        let entities = match schematic.get("Entities") {
+            Some(entities) => list(entities)?
+                .iter()
+                .map(|entity| read_entity(entity, version == 3))
+                .collect::<Result<_, _>>()?,
+            None => Vec::new(),
+        };
+        Ok(Self {
+            version,
+            data_version: field_or(schematic, "DataVersion", 0)?,
+            width,
+            height,
+            length,
+            offset,
+            metadata: schematic
+                .get("Metadata")
+                .map(compound)
+                .transpose()?
+                .cloned(),
+            blocks,
+            block_entities,
+            entities,
+            biomes,
+        })
+    }
+
+    /// Builds the `Schematic` compound, using the layout of [Schematic::version]
+    pub fn to_value(&self) -> Result<NbtValue, SchemError> {
+        let v3 = self.version == 3;
+        let mut out = NbtCompound::new();
+        out.insert("Version", self.version);
+        out.insert("DataVersion", self.data_version);
+        out.insert("Width", self.width as i16);
+        out.insert("Height", self.height as i16);
+        out.insert("Length", self.length as i16);
+        out.insert("Offset", self.offset.to_vec());
+        if let Some(metadata) = &self.metadata {
+            out.insert("Metadata", metadata.clone());
+        }
+        let block_entities = write_block_entities(&self.block_entities, v3);
+        if v3 {
+            let mut blocks = write_palette_data(&self.blocks, "Palette", "Data")?;
+            blocks.insert("BlockEntities", block_entities);
+            out.insert("Blocks", blocks);
+            if let Some(biomes) = &self.biomes {
+                out.insert("Biomes", write_palette_data(biomes, "Palette", "Data")?);
+            }
+        } else {
+            out.insert("PaletteMax", self.blocks.palette.len() as i32);
+            for (key, value) in write_palette_data(&self.blocks, "Palette", "BlockData")? {
+                out.insert(key, value);
+            }
+            out.insert("BlockEntities", block_entities);
+            if let Some(biomes) = &self.biomes {
+                out.insert("BiomePaletteMax", biomes.palette.len() as i32);
+                for (key, value) in write_palette_data(biomes, "BiomePalette", "BiomeData")? {
+                    out.insert(key, value);
+                }
+            }
+        }
+        if !self.entities.is_empty() {
+            let entities = self.entities.iter().map(|entity| {
+                position_and_id(entity.pos.as_slice().to_nbt(), &entity.id, &entity.data, v3)
+            });
+            out.insert("Entities", list_of(entities));
+        }
+        Ok(NbtValue::Compound(out))
+    }
+
+    /// The palette entry of the block at `x`, `y`, `z`
+    pub fn block(&self, x: u16, y: u16, z: u16) -> Option<&str> {
+        let index = self.index(x, y, z)?;
+        let entry = *self.blocks.data.get(index)?;
+        self.blocks.palette.get(entry as usize).map(String::as_str)
+    }
+
+    /// Sets the block at `x`, `y`, `z`, adding `state` to the palette if needed
+    ///
+    /// # Panics
+    /// Panics if the position is outside the schematic
+    pub fn set_block(&mut self, x: u16, y: u16, z: u16, state: &str) {
+        let index = self
+            .index(x, y, z)
+            .expect("position outside of the schematic");
+        let palette = &mut self.blocks.palette;
+        let entry = match palette.iter().position(|entry| entry == state) {
+            Some(entry) => entry,
+            None => {
+                palette.push(state.into());
+                palette.len() - 1
+            }
+        };
+        self.blocks.data[index] = entry as u32;
+    }
+
+    fn index(&self, x: u16, y: u16, z: u16) -> Option<usize> {
+        let (width, length) = (self.width as usize, self.length as usize);
+        (x < self.width && y < self.height && z < self.length)
+            .then(|| x as usize + z as usize * width + y as usize * width * length)
+    }
+}
+
+/// Decodes a stream of unsigned LEB128 varints, as used for block and biome data
+pub fn decode_varints(data: &[i8]) -> Result<Vec<u32>, SchemError> {
+    let mut values = Vec::with_capacity(data.len());
+    let mut value = 0u32;
+    let mut shift = 0;
+    for &byte in data {
+        let byte = byte as u8;
+        if shift > 28 || (shift == 28 && byte & 0x70 != 0) {
+            return Err(SchemError::InvalidVarint);
+        }
+        value |= ((byte & 0x7F) as u32) << shift;
+        if byte & 0x80 == 0 {
+            values.push(value);
+            value = 0;
+            shift = 0;
+        } else {
+            shift += 7;
+        }
+    }
+    if shift != 0 {
+        return Err(SchemError::InvalidVarint);
+    }
+    Ok(values)
+}
+
+pub fn encode_varints(values: &[u32]) -> Vec<i8> {
+    let mut out = Vec::with_capacity(values.len());
+    for &value in values {
+        let mut value = value;
+        while value >= 0x80 {
+            out.push((value as u8 | 0x80) as i8);
+            value >>= 7;
+        }
+        out.push(value as i8);
+    }
+    out
+}
+
+fn compound(value: &NbtValue) -> Result<&NbtCompound, NbtConvertError> {
+    value.as_compound().ok_or(NbtConvertError::WrongTag {
+        expected: NbtTag::Compound,
+        found: value.tag(),
+    })
+}
+
+fn list(value: &NbtValue) -> Result<&NbtList, NbtConvertError> {
+    value.as_list().ok_or(NbtConvertError::WrongTag {
+        expected: NbtTag::List,
+        found: value.tag(),
+    })
+}
+
+fn list_of(values: impl Iterator<Item = NbtCompound>) -> NbtList {
+    let mut list = NbtList::with_tag(NbtTag::Compound);
+    for value in values {
+        list.push(NbtValue::Compound(value))
+            .expect("all elements are compounds");
+    }
+    list
+}
+
+fn vec3<T: Copy>(values: &[T]) -> Result<[T; 3], SchemError> {
+    values
+        .try_into()
+        .map_err(|_| SchemError::InvalidPosition(values.len()))
+}
+
+fn read_palette_data(
+    compound: &NbtCompound,
+    palette_key: &str,
+    data_key: &str,
+) -> Result<PaletteData, SchemError> {
+    let mut entries: Vec<(String, i32)> = field(compound, palette_key)
+        .map(|palette: alloc::collections::BTreeMap<String, i32>| palette.into_iter().collect())?;
+    entries.sort_unstable_by_key(|(_, index)| *index);
+    let mut palette = Vec::with_capacity(entries.len());
+    for (expected, (entry, index)) in entries.into_iter().enumerate() {
+        if index as usize != expected {
+            return Err(SchemError::InvalidPalette);
+        }
+        palette.push(entry);
+    }
+    let data = compound
+        .get(data_key)
+        .ok_or_else(|| NbtConvertError::MissingKey(data_key.into()))?;
+    let data = decode_varints(
+        &i8::from_nbt_array(data).map_err(|err| NbtConvertError::in_field(data_key, err))?,
+    )?;
+    if data.iter().any(|&index| index as usize >= palette.len()) {
+        return Err(SchemError::InvalidPalette);
+    }
+    Ok(PaletteData { palette, data })
+}
+
+fn write_palette_data(
+    data: &PaletteData,
+    palette_key: &str,
+    data_key: &str,
+) -> Result<NbtCompound, SchemError> {
+    if data
+        .data
+        .iter()
+        .any(|&index| index as usize >= data.palette.len())
+    {
+        return Err(SchemError::InvalidPalette);
+    }
+    let palette: NbtCompound = data
+        .palette
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| (entry.as_str(), index as i32))
+        .collect();
+    let mut out = NbtCompound::new();
+    out.insert(palette_key, palette);
+    out.insert(data_key, encode_varints(&data.data));
+    Ok(out)
+}
+
+/// Splits an entity compound into its position, id and other data, which are stored in a `Data`
+/// compound since version 3
+fn split_entity(
+    value: &NbtValue,
+    nested: bool,
+) -> Result<(&NbtValue, String, NbtCompound), NbtConvertError> {
+    let entity = compound(value)?;
+    let pos = entity
+        .get("Pos")
+        .ok_or_else(|| NbtConvertError::MissingKey("Pos".into()))?;
+    let id = field(entity, "Id")?;
+    let data = if nested {
+        field_or(entity, "Data", NbtCompound::new())?
+    } else {
+        entity
+            .iter()
+            .filter(|(key, _)| !matches!(*key, "Pos" | "Id"))
+            .map(|(key, value)| (key, value.clone()))
+            .collect()
+    };
+    Ok((pos, id, data))
+}
+
+fn read_block_entities(
+    value: Option<&NbtValue>,
+    nested: bool,
+) -> Result<Vec<SchemBlockEntity>, SchemError> {
+    let Some(value) = value else {
+        return Ok(Vec::new());
+    };
+    list(value)?
+        .iter()
+        .map(|entity| {
+            let (pos, id, data) = split_entity(entity, nested)?;
+            let pos =
+                i32::from_nbt_array(pos).map_err(|err| NbtConvertError::in_field("Pos", err))?;
+            Ok(SchemBlockEntity {
+                pos: vec3(&pos)?,
+                id,
+                data,
+            })
+        })
+        .collect()
+}
+
+fn read_entity(value: &NbtValue, nested: bool) -> Result<SchemEntity, SchemError> {
+    let (pos, id, data) = split_entity(value, nested)?;
+    let pos: Vec<f64> = Vec::from_nbt(pos).map_err(|err| NbtConvertError::in_field("Pos", err))?;
+    Ok(SchemEntity {
+        pos: vec3(&pos)?,
+        id,
+        data,
+    })
+}
+
+fn position_and_id(pos: NbtValue, id: &str, data: &NbtCompound, nested: bool) -> NbtCompound {
+    let mut out = NbtCompound::new();
+    out.insert("Pos", pos);
+    out.insert("Id", id);
+    if nested {
+        out.insert("Data", data.clone());
+    } else {
+        for (key, value) in data.iter() {
+            out.insert(key, value.clone());
+        }
+    }
+    out
+}
+
+fn write_block_entities(entities: &[SchemBlockEntity], nested: bool) -> NbtList {
+    list_of(entities.iter().map(|entity| {
+        position_and_id(entity.pos.to_vec().into(), &entity.id, &entity.data, nested)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn varints() {
+        let values = [0, 1, 127, 128, 300, 16384, u32::MAX];
+        let encoded = encode_varints(&values);
+        assert_eq!(encoded[..5], [0, 1, 127, -128, 1]);
+        assert_eq!(decode_varints(&encoded).unwrap(), values);
+        assert_eq!(decode_varints(&[-128]), Err(SchemError::InvalidVarint));
+        assert_eq!(
+            decode_varints(&[-1, -1, -1, -1, 0x10]),
+            Err(SchemError::InvalidVarint)
+        );
+    }
+
+    fn sample(version: i32) -> Schematic {
+        let mut schematic = Schematic::new(3465, 3, 2, 200);
+        schematic.version = version;
+        schematic.offset = [1, -2, 3];
+        schematic.set_block(2, 1, 199, "minecraft:stone");
+        schematic.set_block(0, 0, 0, "minecraft:chest[facing=north]");
+        schematic.block_entities.push(SchemBlockEntity {
+            pos: [0, 0, 0],
+            id: "minecraft:chest".into(),
+            data: [("Lock", "key")].into_iter().collect(),
+        });
+        schematic.entities.push(SchemEntity {
+            pos: [0.5, 1.0, 2.5],
+            id: "minecraft:pig".into(),
+            data: [("Health", 10.0f32)].into_iter().collect(),
+        });
+        schematic
+    }
+
+    #[test]
+    fn round_trip() {
+        for version in [2, 3] {
+            let schematic = sample(version);
+            let bytes = schematic.to_bytes().unwrap();
+            let read = Schematic::read(&bytes).unwrap();
+            assert_eq!(read, schematic);
+            assert_eq!(read.block(2, 1, 199), Some("minecraft:stone"));
+            assert_eq!(read.block(1, 1, 199), Some("minecraft:air"));
+            assert_eq!(read.block(3, 0, 0), None);
+        }
+    }
+
+    #[test]
+    fn v2_layout() {
+        let value = sample(2).to_value().unwrap();
+        assert_eq!(value.get("PaletteMax"), Some(&NbtValue::Int(3)));
+        let chest = &value.get("BlockEntities").unwrap().as_list().unwrap()[0];
+        assert_eq!(chest.get("Lock"), Some(&NbtValue::String("key".into())));
+
+        let mut invalid = sample(2);
+        invalid.blocks.data.pop();
+        let bytes = invalid.to_bytes().unwrap();
+        assert!(matches!(
+            Schematic::read(&bytes),
+            Err(SchemError::InvalidBlockData { .. })
+        ));
+    }
+}
@@ -0,0 +1,442 @@
+//! Declarative validation of untrusted documents
+//!
+//! A [Schema] describes the expected structure of a value as data: its type, the range of numbers,
+//! the length of strings, lists and arrays, the schema of list and array elements and the entries
+//! of compounds. A [SchemaValidator] checks a fragment stream against it and collects every
+//! [Violation] along with its path, rather than stopping at the first one.
+//!
+//! ```
+//! # use zeronbt::{NbtTag, schema::{Schema, ViolationKind}};
+//! let item = Schema::compound()
+//!     .required("id", Schema::of(NbtTag::String).len(1, 64))
+//!     .required("Count", Schema::of(NbtTag::Byte).range(1.0, 64.0))
+//!     .optional("tag", Schema::compound());
+//! let violations = item.validate(include_bytes!("../assets/bigtest.nbt")).unwrap();
+//! assert_eq!(violations[0].kind, ViolationKind::MissingKey("id".into()));
+//! ```
+use alloc::{boxed::Box, string::String, vec, vec::Vec};
+use core::{fmt, ops::RangeInclusive};
+
+use crate::{
+    CompleteFsm, NbtFragment, NbtTag,
+    error::NbtResult,
+    path::{NbtPath, PathSegment},
+    text::{Emit, Number, Seq, Structure},
+};
+
+/// The expected structure of a value, which accepts anything unless constrained
+///
+/// Constraints that do not apply to the type of a value are ignored, e.g. the range of a schema
+/// that allows any type only applies to numbers.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Schema {
+    tag: Option<NbtTag>,
+    range: Option<RangeInclusive<f64>>,
+    len: Option<RangeInclusive<usize>>,
+    elements: Option<Box<Schema>>,
+    fields: Vec<Field>,
+    closed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Field {
+    key: String,
+    schema: Schema,
+    required: bool,
+}
+
+/// A place where a value does not match its schema
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    /// The value that does not match, or the compound missing a key
+    pub path: NbtPath,
+    pub kind: ViolationKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ViolationKind {
+    WrongTag {
+        expected: NbtTag,
+        found: NbtTag,
+    },
+    /// A number outside the allowed range
+    OutOfRange(f64),
+    /// A string, list or array with a length outside the allowed range, strings are measured in
+    /// characters
+    WrongLength(usize),
+    MissingKey(String),
+    /// A key that is not part of a closed compound schema
+    UnknownKey(String),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = match self.path.segments() {
+            [] => String::from("<root>"),
+            _ => alloc::format!("{}", self.path),
+        };
+        match &self.kind {
+            ViolationKind::WrongTag { expected, found } => {
+                write!(f, "{path}: expected a {expected:?} but found a {found:?}")
+            }
+            ViolationKind::OutOfRange(value) => write!(f, "{path}: {value} is out of range"),
+            ViolationKind::WrongLength(len) => write!(f, "{path}: length {len} is out of range"),
+            ViolationKind::MissingKey(key) => write!(f, "{path}: missing key {key:?}"),
+            ViolationKind::UnknownKey(key) => write!(f, "{path}: unknown key {key:?}"),
+        }
+    }
+}
+
+impl Schema {
+    /// Accepts any value
+    pub fn any() -> Self {
+        Self::default()
+    }
+
+    /// Accepts values of `tag`
+    pub fn of(tag: NbtTag) -> Self {
+        Self {
+            tag: Some(tag),
+            ..Self::default()
+        }
+    }
+
+    /// Accepts compounds, with entries added by [required](Schema::required) and
+    /// [optional](Schema::optional)
+    pub fn compound() -> Self {
+        Self::of(NbtTag::Compound)
+    }
+
+    /// Accepts lists whose elements match `elements`
+    pub fn list(elements: Schema) -> Self {
+        Self::of(NbtTag::List).elements(elements)
+    }
+
+    /// Limits numbers to `min..=max`
+    pub fn range(mut self, min: f64, max: f64) -> Self {
+        self.range = Some(min..=max);
+        self
+    }
+
+    /// Limits the length of strings, lists and arrays to `min..=max`
+    pub fn len(mut self, min: usize, max: usize) -> Self {
+        self.len = Some(min..=max);
+        self
+    }
+
+    /// Checks the elements of lists and arrays against `elements`
+    pub fn elements(mut self, elements: Schema) -> Self {
+        self.elements = Some(Box::new(elements));
+        self
+    }
+
+    /// Expects compounds to have an entry `key` matching `schema`
+    pub fn required(mut self, key: impl Into<String>, schema: Schema) -> Self {
+        self.fields.push(Field {
+            key: key.into(),
+            schema,
+            required: true,
+        });
+        self
+    }
+
+    /// Checks the entry `key` of compounds against `schema` if it is present
+    pub fn optional(mut self, key: impl Into<String>, schema: Schema) -> Self {
+        self.fields.push(Field {
+            key: key.into(),
+            schema,
+            required: false,
+        });
+        self
+    }
+
+    /// Rejects compound entries that were not added with [required](Schema::required) or
+    /// [optional](Schema::optional)
+    pub fn closed(mut self) -> Self {
+        self.closed = true;
+        self
+    }
+
+    /// Validates the root of a complete document
+    pub fn validate(&self, data: &[u8]) -> NbtResult<Vec<Violation>> {
+        let mut validator = SchemaValidator::new(self);
+        for fragment in CompleteFsm::new(data) {
+            if validator.push(fragment?) {
+                break;
+            }
+        }
+        Ok(validator.into_violations())
+    }
+}
+
+/// Checks fragment streams against a [Schema]
+///
+/// Violations are collected across every document pushed to it.
+#[derive(Debug, Clone)]
+pub struct SchemaValidator<'s> {
+    structure: Structure,
+    check: Check<'s>,
+}
+
+#[derive(Debug, Clone)]
+struct Check<'s> {
+    root: &'s Schema,
+    stack: Vec<Open<'s>>,
+    /// The path of the value being checked
+    path: Vec<PathSegment>,
+    /// The schema of the entry whose key was read, None if it is not checked
+    entry: Option<&'s Schema>,
+    violations: Vec<Violation>,
+}
+
+#[derive(Debug, Clone)]
+struct Open<'s> {
+    /// None for values whose contents are not checked
+    schema: Option<&'s Schema>,
+    /// The number of elements of a list or array so far
+    len: usize,
+    /// Which fields of a compound were found
+    found: Vec<bool>,
+    compound: bool,
+}
+
+impl<'s> SchemaValidator<'s> {
+    pub fn new(schema: &'s Schema) -> Self {
+        Self {
+            structure: Structure::default(),
+            check: Check {
+                root: schema,
+                stack: Vec::new(),
+                path: Vec::new(),
+                entry: None,
+                violations: Vec::new(),
+            },
+        }
+    }
+
+    pub fn schema(&self) -> &'s Schema {
+        self.check.root
+    }
+
+    /// Checks the next fragment, returning true once the root tag is complete
+    pub fn push(&mut self, fragment: NbtFragment<'_>) -> bool {
+        // Checking never fails
+        matches!(self.structure.push(fragment, &mut self.check), Ok(true))
+    }
+
+    pub fn violations(&self) -> &[Violation] {
+        &self.check.violations
+    }
+
+    pub fn into_violations(self) -> Vec<Violation> {
+        self.check.violations
+    }
+}
+
+impl<'s> Check<'s> {
+    fn violation(&mut self, kind: ViolationKind) {
+        let mut path = NbtPath::root();
+        for segment in &self.path {
+            path.push(segment.clone());
+        }
+        self.violations.push(Violation { path, kind });
+    }
+
+    /// Starts a value, returning its schema if its contents are checked
+    fn begin(&mut self, tag: NbtTag) -> Option<&'s Schema> {
+        let schema = match self.stack.last_mut() {
+            None => Some(self.root),
+            Some(parent) if parent.compound => self.entry.take(),
+            Some(parent) => {
+                self.path.push(PathSegment::Index(parent.len as i32));
+                parent.len += 1;
+                parent.schema.and_then(|schema| schema.elements.as_deref())
+            }
+        }?;
+        match schema.tag {
+            Some(expected) if expected != tag => {
+                self.violation(ViolationKind::WrongTag {
+                    expected,
+                    found: tag,
+                });
+                None
+            }
+            _ => Some(schema),
+        }
+    }
+
+    /// Ends a value, leaving its path
+    fn end(&mut self) {
+        if !self.stack.is_empty() {
+            self.path.pop();
+        }
+    }
+
+    fn check_len(&mut self, schema: &Schema, len: usize) {
+        if let Some(range) = &schema.len
+            && !range.contains(&len)
+        {
+            self.violation(ViolationKind::WrongLength(len));
+        }
+    }
+
+    fn open(&mut self, schema: Option<&'s Schema>, compound: bool) {
+        let found = match schema {
+            Some(schema) if compound => vec![false; schema.fields.len()],
+            _ => Vec::new(),
+        };
+        self.stack.push(Open {
+            schema,
+            len: 0,
+            found,
+            compound,
+        });
+    }
+}
+
+impl Emit for Check<'_> {
+    fn begin_compound(&mut self) -> fmt::Result {
+        let schema = self.begin(NbtTag::Compound);
+        self.open(schema, true);
+        Ok(())
+    }
+
+    fn key(&mut self, key: &str) -> fmt::Result {
+        let Some(open) = self.stack.last_mut() else {
+            return Ok(());
+        };
+        let field = open.schema.and_then(|schema| {
+            let index = schema.fields.iter().position(|field| field.key == key);
+            if let Some(index) = index {
+                open.found[index] = true;
+            }
+            index.map(|index| &schema.fields[index].schema)
+        });
+        let unknown = field.is_none() && open.schema.is_some_and(|schema| schema.closed);
+        self.entry = field;
+        if unknown {
+            self.violation(ViolationKind::UnknownKey(key.into()));
+        }
+        self.path.push(PathSegment::Key(key.into()));
+        Ok(())
+    }
+
+    fn end_compound(&mut self) -> fmt::Result {
+        let open = self.stack.pop().expect("compounds end after they begin");
+        if let Some(schema) = open.schema {
+            for (field, found) in schema.fields.iter().zip(open.found) {
+                if field.required && !found {
+                    self.violation(ViolationKind::MissingKey(field.key.clone()));
+                }
+            }
+        }
+        self.end();
+        Ok(())
+    }
+
+    fn begin_seq(&mut self, seq: Seq) -> fmt::Result {
+        let tag = match seq {
+            Seq::List(_) => NbtTag::List,
+            Seq::ByteArray => NbtTag::ByteArray,
+            Seq::IntArray => NbtTag::IntArray,
+            Seq::LongArray => NbtTag::LongArray,
+        };
+        let schema = self.begin(tag);
+        self.open(schema, false);
+        Ok(())
+    }
+
+    fn end_seq(&mut self) -> fmt::Result {
+        let open = self.stack.pop().expect("sequences end after they begin");
+        if let Some(schema) = open.schema {
+            self.check_len(schema, open.len);
+        }
+        self.end();
+        Ok(())
+    }
+
+    fn number(&mut self, number: Number) -> fmt::Result {
+        let (tag, value) = match number {
+            Number::Byte(value) => (NbtTag::Byte, value.into()),
+            Number::Short(value) => (NbtTag::Short, value.into()),
+            Number::Int(value) => (NbtTag::Int, value.into()),
+            Number::Long(value) => (NbtTag::Long, value as f64),
+            Number::Float(value) => (NbtTag::Float, value.into()),
+            Number::Double(value) => (NbtTag::Double, value),
+        };
+        if let Some(schema) = self.begin(tag)
+            && let Some(range) = &schema.range
+            && !range.contains(&value)
+        {
+            self.violation(ViolationKind::OutOfRange(value));
+        }
+        self.end();
+        Ok(())
+    }
+
+    fn string(&mut self, string: &str) -> fmt::Result {
+        if let Some(schema) = self.begin(NbtTag::String) {
+            self.check_len(schema, string.chars().count());
+        }
+        self.end();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snbt::SnbtReader;
+
+    fn validate(schema: &Schema, snbt: &str) -> Vec<String> {
+        let mut reader = SnbtReader::new(snbt);
+        let mut validator = SchemaValidator::new(schema);
+        while let Some(fragment) = reader.next_fragment().unwrap() {
+            validator.push(fragment);
+        }
+        let violations = validator.into_violations();
+        violations
+            .iter()
+            .map(alloc::string::ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn violations() {
+        let schema = Schema::compound()
+            .required("id", Schema::of(NbtTag::String).len(1, 8))
+            .required("Count", Schema::of(NbtTag::Byte).range(1.0, 64.0))
+            .optional(
+                "Pos",
+                Schema::list(Schema::of(NbtTag::Double).range(-10.0, 10.0)).len(3, 3),
+            )
+            .optional(
+                "Tags",
+                Schema::list(Schema::compound().required("Name", Schema::any()).closed()),
+            )
+            .closed();
+        assert_eq!(
+            validate(&schema, "{id: stone, Count: 1b, Pos: [0d, 0d, 0d]}"),
+            [] as [String; 0]
+        );
+        assert_eq!(
+            validate(
+                &schema,
+                "{id: minecraft_stone, Count: 100, Pos: [0d, 20d], Tags: [{Name: 1, x: 2}, {}], y: 3}"
+            ),
+            [
+                "id: length 15 is out of range",
+                "Count: expected a Byte but found a Int",
+                "Pos[1]: 20 is out of range",
+                "Pos: length 2 is out of range",
+                "Tags[0]: unknown key \"x\"",
+                "Tags[1]: missing key \"Name\"",
+                "<root>: unknown key \"y\"",
+            ]
+        );
+        assert_eq!(
+            validate(&schema, "[I; 1]"),
+            ["<root>: expected a Compound but found a IntArray"]
+        );
+    }
+}
@@ -0,0 +1,198 @@
+//! Splitting documents into segments of limited size, for protocols that cap the size of NBT
+//! payloads
+//!
+//! Every segment is a small NBT compound of its own, `{index: int, last: byte, data: [B; ...]}`,
+//! holding the next part of the encoded document. The document is reassembled by [join]ing the
+//! data of all segments in the order of their index, whatever order they arrive in.
+//!
+//! ```
+//! # use zeronbt::{segment::{SegmentWriter, join}, CompleteFsm};
+//! let data = include_bytes!("../assets/bigtest.nbt");
+//! let mut writer = SegmentWriter::new(256);
+//! for fragment in CompleteFsm::new(data) {
+//!     writer.push(fragment.unwrap()).unwrap();
+//! }
+//! let mut segments = writer.finish().unwrap();
+//! assert!(segments.iter().all(|segment| segment.len() <= 256));
+//! segments.reverse();
+//! assert_eq!(join(&segments).unwrap(), data);
+//! ```
+use alloc::vec::Vec;
+
+use crate::{
+    NbtFragment, NbtTag, NbtWriter,
+    error::{NbtWriteError, SegmentError},
+    extract::read_compound,
+    value::NbtValue,
+};
+
+/// The bytes every segment takes up besides its data
+pub const SEGMENT_OVERHEAD: usize = 3 + (3 + 5 + 4) + (3 + 4 + 1) + (3 + 4 + 4) + 1;
+
+/// Encodes fragments or values as segments of at most a given size, see the
+/// [module docs](self)
+#[derive(Debug, Clone)]
+pub struct SegmentWriter {
+    writer: NbtWriter,
+    /// The most data a segment holds
+    chunk: usize,
+    segments: Vec<Vec<u8>>,
+    /// The number of segments produced, including the ones that were taken
+    produced: usize,
+}
+
+impl SegmentWriter {
+    /// Creates a writer producing segments of at most `max_len` bytes
+    ///
+    /// # Panics
+    ///
+    /// If `max_len` leaves no room for data besides the [SEGMENT_OVERHEAD].
+    pub fn new(max_len: usize) -> Self {
+        assert!(
+            max_len > SEGMENT_OVERHEAD,
+            "segments of {max_len} bytes can not hold any data"
+        );
+        Self {
+            writer: NbtWriter::new(),
+            chunk: max_len - SEGMENT_OVERHEAD,
+            segments: Vec::new(),
+            produced: 0,
+        }
+    }
+
+    /// Writes the next fragment, returning true once the root tag is complete, see
+    /// [NbtWriter::push]
+    pub fn push(&mut self, fragment: NbtFragment<'_>) -> Result<bool, NbtWriteError> {
+        let done = self.writer.push(fragment)?;
+        self.flush()?;
+        Ok(done)
+    }
+
+    /// Writes a whole value, see [NbtWriter::push_value]
+    pub fn push_value(&mut self, name: &str, value: &NbtValue) -> Result<bool, NbtWriteError> {
+        let done = self.writer.push_value(name, value)?;
+        self.flush()?;
+        Ok(done)
+    }
+
+    /// Takes the segments that are full so far, e.g. to send them before the document is complete
+    pub fn take_segments(&mut self) -> Vec<Vec<u8>> {
+        core::mem::take(&mut self.segments)
+    }
+
+    /// Ends the last segment, returning every segment that was not taken yet
+    pub fn finish(mut self) -> Result<Vec<Vec<u8>>, NbtWriteError> {
+        let rest = core::mem::take(self.writer.get_mut());
+        let index = self.next_index()?;
+        self.segments.push(segment(index, true, &rest));
+        Ok(self.segments)
+    }
+
+    /// Moves full segments out of the output, always leaving some data for the last one
+    fn flush(&mut self) -> Result<(), NbtWriteError> {
+        let len = self.writer.get_ref().len();
+        if len <= self.chunk {
+            return Ok(());
+        }
+        // The writer only corrects list lengths if elements are removed, which never happens
+        // here, so its output can be drained at any time
+        let full = (len - 1) / self.chunk * self.chunk;
+        let out: Vec<u8> = self.writer.get_mut().drain(..full).collect();
+        for data in out.chunks(self.chunk) {
+            let index = self.next_index()?;
+            self.segments.push(segment(index, false, data));
+        }
+        Ok(())
+    }
+
+    fn next_index(&mut self) -> Result<i32, NbtWriteError> {
+        let index = i32::try_from(self.produced)
+            .map_err(|_| NbtWriteError::TooManyElements(self.produced))?;
+        self.produced += 1;
+        Ok(index)
+    }
+}
+
+/// Encodes a value as segments of at most `max_len` bytes
+pub fn split_value(
+    name: &str,
+    value: &NbtValue,
+    max_len: usize,
+) -> Result<Vec<Vec<u8>>, NbtWriteError> {
+    let mut writer = SegmentWriter::new(max_len);
+    writer.push_value(name, value)?;
+    writer.finish()
+}
+
+/// Reassembles a document from all of its segments, in any order
+pub fn join<S: AsRef<[u8]>>(
+    segments: impl IntoIterator<Item = S>,
+) -> Result<Vec<u8>, SegmentError> {
+    let mut parts = Vec::new();
+    for segment in segments {
+        let part = read_compound(segment.as_ref(), |segment| {
+            let index: i32 = segment.field("index")?;
+            let last: i8 = segment.field("last")?;
+            let data: &[u8] = segment.field("data")?;
+            Ok((index, last != 0, data.to_vec()))
+        })?;
+        parts.push(part);
+    }
+    parts.sort_unstable_by_key(|&(index, ..)| index);
+
+    let count = parts.len() as i32;
+    let mut out = Vec::new();
+    let mut ended = false;
+    for (expected, (index, last, data)) in (0..).zip(parts) {
+        match index {
+            _ if ended => return Err(SegmentError::AfterLast(index)),
+            _ if index < expected => return Err(SegmentError::Duplicate(index)),
+            _ if index > expected => return Err(SegmentError::Missing(expected)),
+            _ => {}
+        }
+        out.extend_from_slice(&data);
+        ended = last;
+    }
+    match ended {
+        true => Ok(out),
+        false => Err(SegmentError::Missing(count)),
+    }
+}
+
+fn segment(index: i32, last: bool, data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + SEGMENT_OVERHEAD);
+    out.extend_from_slice(&[NbtTag::Compound as u8, 0, 0]);
+    out.extend_from_slice(&[NbtTag::Int as u8, 0, 5]);
+    out.extend_from_slice(b"index");
+    out.extend_from_slice(&index.to_be_bytes());
+    out.extend_from_slice(&[NbtTag::Byte as u8, 0, 4]);
+    out.extend_from_slice(b"last");
+    out.push(last as u8);
+    out.extend_from_slice(&[NbtTag::ByteArray as u8, 0, 4]);
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&(data.len() as i32).to_be_bytes());
+    out.extend_from_slice(data);
+    out.push(0);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::NbtCompound;
+
+    #[test]
+    fn incomplete_segments() {
+        let mut compound = NbtCompound::new();
+        compound.insert("data", NbtValue::ByteArray(alloc::vec![7; 100]));
+        let segments = split_value("", &NbtValue::Compound(compound), 64).unwrap();
+        assert_eq!(segments.len(), 4);
+
+        assert_eq!(join(&segments[1..]), Err(SegmentError::Missing(0)));
+        assert_eq!(join(&segments[..3]), Err(SegmentError::Missing(3)));
+        let twice = [&segments[..], &segments[2..3]].concat();
+        assert_eq!(join(&twice), Err(SegmentError::Duplicate(2)));
+        let extra = [&segments[..], &[segment(4, false, &[])]].concat();
+        assert_eq!(join(&extra), Err(SegmentError::AfterLast(4)));
+    }
+}
@@ -0,0 +1,393 @@
+//! Reading and writing stringified NBT, the text format used by commands
+//!
+//! [SnbtReader] parses text into fragments, [SnbtWriter] writes fragments as text. The tokens
+//! the reader works with are available from [SnbtLexer], e.g. for syntax highlighting.
+//!
+//! Output follows the style of the game: numbers carry their type suffix, arrays are written as
+//! `[B; ...]`, `[I; ...]` and `[L; ...]`, and keys are only quoted when they have to be. The
+//! quoting rules are available on their own as well, see [write_key], [write_string] and
+//! [unquote].
+//!
+//! SNBT has no literal for NaN or the infinities, so these are written as the closest finite
+//! value instead: infinities become the largest float of their sign and NaN becomes zero.
+//!
+//! ```
+//! # use zeronbt::{FsmResult, NbtFsm, snbt::SnbtWriter, value::NbtValue};
+//! let data = NbtValue::Compound([("id", "minecraft:stone")].into_iter().collect())
+//!     .to_bytes("")
+//!     .unwrap();
+//! let mut writer = SnbtWriter::new(String::new());
+//! let mut fsm = NbtFsm::new().with_data(&data);
+//! while let Ok(FsmResult::Found(fragment)) = fsm.next_fragment() {
+//!     writer.push(fragment).unwrap();
+//! }
+//! assert_eq!(writer.into_inner(), r#"{id:"minecraft:stone"}"#);
+//! ```
+use alloc::{borrow::Cow, string::String};
+use core::fmt::{self, Write};
+
+mod lex;
+mod read;
+pub use lex::{SnbtLexer, SnbtToken};
+pub use read::SnbtReader;
+
+use crate::{
+    NbtFragment, NbtTag,
+    mutf8::StringPolicy,
+    text::{Emit, Layout, Number, Seq, Structure},
+};
+
+/// Writes the fragments of documents pushed to it as SNBT
+///
+/// Root names are not part of SNBT and are skipped. Invalid strings are written lossily
+/// unless another [StringPolicy] is set.
+#[derive(Debug, Clone)]
+pub struct SnbtWriter<W> {
+    structure: Structure,
+    format: Format<W>,
+}
+
+#[derive(Debug, Clone)]
+struct Format<W> {
+    out: W,
+    layout: Layout,
+}
+
+impl<W: Write> SnbtWriter<W> {
+    /// Creates a writer producing compact SNBT
+    pub fn new(out: W) -> Self {
+        Self {
+            structure: Structure::default(),
+            format: Format {
+                out,
+                layout: Layout::new("    "),
+            },
+        }
+    }
+
+    /// Whether to spread compounds and lists of containers over multiple indented lines
+    pub fn pretty(mut self, pretty: bool) -> Self {
+        self.format.layout.pretty = pretty;
+        self
+    }
+
+    /// How strings that are not valid (Modified) UTF-8 are written, lossily by default
+    ///
+    /// Strict writers fail to push invalid strings, raw writers write them as byte arrays.
+    pub fn string_policy(mut self, policy: StringPolicy) -> Self {
+        self.structure.strings = policy;
+        self
+    }
+
+    /// Writes the next fragment, returning true once the root tag is complete
+    ///
+    /// Fragments of the next document may be pushed after that, they are written right after the
+    /// previous one.
+    pub fn push(&mut self, fragment: NbtFragment<'_>) -> Result<bool, fmt::Error> {
+        self.structure.push(fragment, &mut self.format)
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.format.out
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.format.out
+    }
+
+    pub fn into_inner(self) -> W {
+        self.format.out
+    }
+}
+
+impl<W: Write> Emit for Format<W> {
+    fn begin_compound(&mut self) -> fmt::Result {
+        self.layout.value(&mut self.out)?;
+        self.layout.open(&mut self.out, "{", true, true)
+    }
+
+    fn key(&mut self, key: &str) -> fmt::Result {
+        self.layout.key(&mut self.out)?;
+        write_key(&mut self.out, key)?;
+        self.out.write_char(':')?;
+        if self.layout.pretty {
+            self.out.write_char(' ')?;
+        }
+        Ok(())
+    }
+
+    fn end_compound(&mut self) -> fmt::Result {
+        self.layout.close(&mut self.out, "}")
+    }
+
+    fn begin_seq(&mut self, seq: Seq) -> fmt::Result {
+        self.layout.value(&mut self.out)?;
+        let (bracket, multiline) = match seq {
+            Seq::List(tag) => ("[", matches!(tag, NbtTag::Compound | NbtTag::List)),
+            Seq::ByteArray => ("[B;", false),
+            Seq::IntArray => ("[I;", false),
+            Seq::LongArray => ("[L;", false),
+        };
+        self.layout.open(&mut self.out, bracket, multiline, false)
+    }
+
+    fn end_seq(&mut self) -> fmt::Result {
+        self.layout.close(&mut self.out, "]")
+    }
+
+    fn number(&mut self, number: Number) -> fmt::Result {
+        self.layout.value(&mut self.out)?;
+        match number {
+            Number::Byte(value) => write!(self.out, "{value}b"),
+            Number::Short(value) => write!(self.out, "{value}s"),
+            Number::Int(value) => write!(self.out, "{value}"),
+            Number::Long(value) => write!(self.out, "{value}L"),
+            Number::Float(value) => {
+                let value = match value.is_nan() {
+                    true => 0.0,
+                    false => value.clamp(f32::MIN, f32::MAX),
+                };
+                write!(self.out, "{value:?}f")
+            }
+            Number::Double(value) => {
+                let value = match value.is_nan() {
+                    true => 0.0,
+                    false => value.clamp(f64::MIN, f64::MAX),
+                };
+                write!(self.out, "{value:?}d")
+            }
+        }
+    }
+
+    fn string(&mut self, string: &str) -> fmt::Result {
+        self.layout.value(&mut self.out)?;
+        write_string(&mut self.out, string)
+    }
+}
+
+/// Whether a key can be written without quotes, as it only consists of characters allowed in
+/// unquoted strings
+pub fn is_plain_key(key: &str) -> bool {
+    !key.is_empty() && key.chars().all(lex::is_unquoted)
+}
+
+/// Writes a key, quoting it unless it is [plain](is_plain_key)
+pub fn write_key(out: &mut impl Write, key: &str) -> fmt::Result {
+    match is_plain_key(key) {
+        true => out.write_str(key),
+        false => write_string(out, key),
+    }
+}
+
+/// The quote a string is written with: single quotes if that avoids escaping double quotes
+pub fn quote_for(string: &str) -> char {
+    match string.contains('"') && !string.contains('\'') {
+        true => '\'',
+        false => '"',
+    }
+}
+
+/// Writes a quoted string, escaping backslashes, line breaks, tabs and the quote
+///
+/// ```
+/// # use zeronbt::snbt::{unquote, write_string};
+/// let mut out = String::new();
+/// write_string(&mut out, "it's \"quoted\"").unwrap();
+/// assert_eq!(out, r#""it's \"quoted\"""#);
+/// assert_eq!(unquote(&out).as_deref(), Some("it's \"quoted\""));
+/// ```
+pub fn write_string(out: &mut impl Write, string: &str) -> fmt::Result {
+    let quote = quote_for(string);
+    out.write_char(quote)?;
+    for char in string.chars() {
+        match char {
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            char if char == quote => {
+                out.write_char('\\')?;
+                out.write_char(char)?;
+            }
+            char => out.write_char(char)?,
+        }
+    }
+    out.write_char(quote)
+}
+
+/// Reads a quoted string, returning None if it is not enclosed in matching quotes or holds an
+/// unescaped quote or an invalid escape
+///
+/// Besides the escapes written by [write_string], the other escapes of the game are accepted:
+/// `\b`, `\f`, `\s` for a space and `\x`, `\u` and `\U` followed by 2, 4 or 8 hex digits.
+pub fn unquote(quoted: &str) -> Option<Cow<'_, str>> {
+    let quote = quoted
+        .chars()
+        .next()
+        .filter(|&char| matches!(char, '"' | '\''))?;
+    let inner = quoted[1..].strip_suffix(quote)?;
+    if !inner.contains(['\\', quote]) {
+        return Some(Cow::Borrowed(inner));
+    }
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(char) = chars.next() {
+        if char == quote {
+            return None;
+        }
+        if char != '\\' {
+            out.push(char);
+            continue;
+        }
+        let escaped = match chars.next()? {
+            char @ ('\\' | '\'' | '"') => char,
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            's' => ' ',
+            'x' => hex_char(&mut chars, 2)?,
+            'u' => hex_char(&mut chars, 4)?,
+            'U' => hex_char(&mut chars, 8)?,
+            _ => return None,
+        };
+        out.push(escaped);
+    }
+    Some(Cow::Owned(out))
+}
+
+fn hex_char(chars: &mut core::str::Chars<'_>, digits: usize) -> Option<char> {
+    let mut value = 0;
+    for _ in 0..digits {
+        value = value * 16 + chars.next()?.to_digit(16)?;
+    }
+    char::from_u32(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        FsmResult, NbtFsm,
+        value::{NbtCompound, NbtList, NbtValue},
+    };
+    use alloc::{string::String, vec};
+
+    fn snbt(data: &[u8], pretty: bool) -> String {
+        let mut writer = SnbtWriter::new(String::new()).pretty(pretty);
+        let mut fsm = NbtFsm::new().with_data(data);
+        let mut done = false;
+        while let FsmResult::Found(fragment) = fsm.next_fragment().unwrap() {
+            done = writer.push(fragment).unwrap();
+        }
+        assert!(done);
+        writer.into_inner()
+    }
+
+    #[test]
+    fn values() {
+        let list = NbtList::try_from(vec![NbtValue::Compound(NbtCompound::new())]).unwrap();
+        let compound: NbtCompound = [
+            ("byte", NbtValue::Byte(-1)),
+            ("float", NbtValue::Float(0.5)),
+            ("with space", NbtValue::String("it's \"quoted\"".into())),
+            ("bytes", NbtValue::ByteArray(vec![1, 2])),
+            ("longs", NbtValue::LongArray(vec![3])),
+            ("list", NbtValue::List(list)),
+            ("empty", NbtValue::List(NbtList::new())),
+        ]
+        .into_iter()
+        .collect();
+        let data = NbtValue::Compound(compound).to_bytes("root").unwrap();
+        assert_eq!(
+            snbt(&data, false),
+            r#"{byte:-1b,float:0.5f,"with space":"it's \"quoted\"",bytes:[B;1b,2b],longs:[L;3L],list:[{}],empty:[]}"#
+        );
+        assert_eq!(
+            snbt(&data, true),
+            r#"{
+    byte: -1b,
+    float: 0.5f,
+    "with space": "it's \"quoted\"",
+    bytes: [B; 1b, 2b],
+    longs: [L; 3L],
+    list: [
+        {}
+    ],
+    empty: []
+}"#
+        );
+    }
+
+    #[test]
+    fn non_finite() {
+        let compound: NbtCompound = [
+            ("nan", NbtValue::Double(f64::NAN)),
+            ("inf", NbtValue::Float(f32::INFINITY)),
+            ("neg_inf", NbtValue::Double(f64::NEG_INFINITY)),
+        ]
+        .into_iter()
+        .collect();
+        let data = NbtValue::Compound(compound).to_bytes("").unwrap();
+        let output = snbt(&data, false);
+        assert_eq!(
+            output,
+            "{nan:0.0d,inf:3.4028235e38f,neg_inf:-1.7976931348623157e308d}"
+        );
+        let mut reader = SnbtReader::new(&output);
+        while reader.next_fragment().unwrap().is_some() {}
+    }
+
+    #[test]
+    fn root_end() {
+        // An empty compound followed by the End tag of an empty document
+        let data = [0x0A, 0, 0, 0, 0];
+        let mut writer = SnbtWriter::new(String::new());
+        let mut fsm = NbtFsm::new().with_data(&data);
+        while let FsmResult::Found(fragment) = fsm.next_fragment().unwrap() {
+            writer.push(fragment).unwrap();
+        }
+        assert_eq!(writer.into_inner(), "{}");
+    }
+
+    #[test]
+    fn quoting() {
+        for string in [
+            "",
+            "plain",
+            "it's",
+            "\"quoted\"",
+            "both ' \"",
+            "back\\slash\n",
+        ] {
+            let mut quoted = String::new();
+            write_string(&mut quoted, string).unwrap();
+            assert_eq!(unquote(&quoted).as_deref(), Some(string));
+        }
+        assert_eq!(
+            unquote(r#"'\x41\u00e9\s\U0001F980'"#).as_deref(),
+            Some("Aé 🦀")
+        );
+        for invalid in [
+            "plain",
+            "\"",
+            "'mismatched\"",
+            r#""un"escaped""#,
+            r#""escaped\""#,
+            r#""\q""#,
+        ] {
+            assert_eq!(unquote(invalid), None, "{invalid}");
+        }
+        assert!(is_plain_key("Data.Version+1"));
+        assert!(!is_plain_key("with space") && !is_plain_key(""));
+    }
+
+    #[test]
+    fn bigtest() {
+        let output = snbt(include_bytes!("../assets/bigtest.nbt"), false);
+        assert!(output.starts_with("{longTest:9223372036854775807L,"));
+        assert!(output.contains(r#""listTest (long)":[11L,12L,13L,14L,15L]"#));
+        assert!(output.ends_with('}'));
+    }
+}
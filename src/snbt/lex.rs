@@ -0,0 +1,182 @@
+use core::ops::Range;
+
+use super::read::number;
+use crate::{NbtTag, error::SnbtError, text::Number};
+
+/// The smallest meaningful pieces of SNBT
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnbtToken<'s> {
+    OpenCompound,
+    CloseCompound,
+    OpenList,
+    CloseList,
+    Comma,
+    Colon,
+    Semicolon,
+    /// A quoted string including its quotes, with escapes left in place, see
+    /// [unquote](super::unquote)
+    Quoted(&'s str),
+    /// A run of characters allowed in unquoted strings, which is a key, a number, `true`/`false`
+    /// or a string
+    Word(&'s str),
+}
+
+impl<'s> SnbtToken<'s> {
+    /// The text of the token as it appears in the input
+    pub fn as_str(&self) -> &'s str {
+        match self {
+            SnbtToken::OpenCompound => "{",
+            SnbtToken::CloseCompound => "}",
+            SnbtToken::OpenList => "[",
+            SnbtToken::CloseList => "]",
+            SnbtToken::Comma => ",",
+            SnbtToken::Colon => ":",
+            SnbtToken::Semicolon => ";",
+            SnbtToken::Quoted(text) | SnbtToken::Word(text) => text,
+        }
+    }
+
+    /// The type of the number a [Word](SnbtToken::Word) reads as, or None if it is a string
+    ///
+    /// Words used as keys are always strings, and the `B`, `I` and `L` of array prefixes are
+    /// words as well.
+    pub fn number_tag(&self) -> Option<NbtTag> {
+        match self {
+            SnbtToken::Word(word) => number(word).map(Number::tag),
+            _ => None,
+        }
+    }
+}
+
+/// Splits SNBT into [SnbtToken]s along with their spans, the byte range they cover in the input
+///
+/// The lexer only knows about single tokens, so it accepts text that does not form valid SNBT.
+/// After an error it skips past the offending text, which lets editors highlight the rest of the
+/// input.
+///
+/// ```
+/// # use zeronbt::snbt::{SnbtLexer, SnbtToken};
+/// let tokens: Vec<_> = SnbtLexer::new("{Count: 64b}").map(Result::unwrap).collect();
+/// assert_eq!(tokens[2], (SnbtToken::Colon, 6..7));
+/// assert_eq!(tokens[3], (SnbtToken::Word("64b"), 8..11));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SnbtLexer<'s> {
+    input: &'s str,
+    pos: usize,
+}
+
+impl<'s> SnbtLexer<'s> {
+    pub fn new(input: &'s str) -> Self {
+        Self { input, pos: 0 }
+    }
+
+    /// The byte offset up to which the input has been read
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.input[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Reads the next token and its span, returning None at the end of the input
+    pub fn next_token(&mut self) -> Result<Option<(SnbtToken<'s>, Range<usize>)>, SnbtError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let rest = &self.input[start..];
+        let Some(char) = rest.chars().next() else {
+            return Ok(None);
+        };
+        let (token, len) = match char {
+            '{' => (SnbtToken::OpenCompound, 1),
+            '}' => (SnbtToken::CloseCompound, 1),
+            '[' => (SnbtToken::OpenList, 1),
+            ']' => (SnbtToken::CloseList, 1),
+            ',' => (SnbtToken::Comma, 1),
+            ':' => (SnbtToken::Colon, 1),
+            ';' => (SnbtToken::Semicolon, 1),
+            '"' | '\'' => {
+                let Some(len) = quoted_len(rest, char) else {
+                    self.pos = self.input.len();
+                    return Err(SnbtError::UnexpectedEnd);
+                };
+                (SnbtToken::Quoted(&rest[..len]), len)
+            }
+            char if is_unquoted(char) => {
+                let len = rest.find(|char| !is_unquoted(char)).unwrap_or(rest.len());
+                (SnbtToken::Word(&rest[..len]), len)
+            }
+            found => {
+                self.pos += found.len_utf8();
+                return Err(SnbtError::UnexpectedChar { pos: start, found });
+            }
+        };
+        self.pos += len;
+        Ok(Some((token, start..self.pos)))
+    }
+
+    /// Reads the next token without consuming it
+    pub fn peek_token(&self) -> Result<Option<(SnbtToken<'s>, Range<usize>)>, SnbtError> {
+        self.clone().next_token()
+    }
+}
+
+impl<'s> Iterator for SnbtLexer<'s> {
+    type Item = Result<(SnbtToken<'s>, Range<usize>), SnbtError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_token().transpose()
+    }
+}
+
+/// Whether a character may appear in unquoted strings, keys and numbers
+pub(crate) fn is_unquoted(char: char) -> bool {
+    char.is_ascii_alphanumeric() || matches!(char, '_' | '-' | '.' | '+')
+}
+
+/// The length of the quoted string at the start of `rest`, including both quotes
+fn quoted_len(rest: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (idx, char) in rest.char_indices().skip(1) {
+        match char {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            char if char == quote => return Some(idx + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn recovers() {
+        let tokens: Vec<_> = SnbtLexer::new("[1.5f, @ 'a\\'b', x:true] 'open").collect();
+        assert_eq!(
+            tokens,
+            [
+                Ok((SnbtToken::OpenList, 0..1)),
+                Ok((SnbtToken::Word("1.5f"), 1..5)),
+                Ok((SnbtToken::Comma, 5..6)),
+                Err(SnbtError::UnexpectedChar { pos: 7, found: '@' }),
+                Ok((SnbtToken::Quoted("'a\\'b'"), 9..15)),
+                Ok((SnbtToken::Comma, 15..16)),
+                Ok((SnbtToken::Word("x"), 17..18)),
+                Ok((SnbtToken::Colon, 18..19)),
+                Ok((SnbtToken::Word("true"), 19..23)),
+                Ok((SnbtToken::CloseList, 23..24)),
+                Err(SnbtError::UnexpectedEnd),
+            ]
+        );
+        let number_tag = |idx: usize| tokens[idx].clone().unwrap().0.number_tag();
+        assert_eq!(number_tag(1), Some(NbtTag::Float));
+        assert_eq!(number_tag(6), None);
+        assert_eq!(number_tag(8), Some(NbtTag::Byte));
+    }
+}
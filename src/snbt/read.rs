@@ -0,0 +1,706 @@
+use alloc::{collections::VecDeque, vec, vec::Vec};
+use core::ops::Range;
+
+use super::{
+    lex::{SnbtLexer, SnbtToken},
+    unquote,
+};
+use crate::{
+    NbtFragment, NbtTag,
+    error::SnbtError,
+    mutf8,
+    text::{Number, list_frame},
+};
+
+/// Parses SNBT into the same fragments [NbtFsm](crate::NbtFsm) produces for the equivalent
+/// binary document, so text can be fed to any consumer of fragments
+///
+/// SNBT has no root name, the root is given an empty one. As the whole text is available, names,
+/// strings, byte arrays and numeric lists arrive as a single frame, followed by an empty one where
+/// the parser would produce one. Several root values may follow each other, separated by
+/// whitespace.
+///
+/// Unquoted words are numbers if they have the form of one, with the type given by their suffix,
+/// and strings otherwise. `true` and `false` are the bytes 1 and 0. Elements of arrays may leave
+/// out their suffix, and all elements of a list must have the same type.
+///
+/// ```
+/// # use zeronbt::{snbt::SnbtReader, value::{NbtValue, NbtValueBuilder}};
+/// let mut reader = SnbtReader::new("{id: 'minecraft:stone', Count: 64b, pos: [I; 1, 2, 3]}");
+/// let mut builder = NbtValueBuilder::new();
+/// let (_, value) = loop {
+///     let fragment = reader.next_fragment().unwrap().unwrap();
+///     if let Some(root) = builder.push(fragment).unwrap() {
+///         break root;
+///     }
+/// };
+/// let value = value.as_compound().unwrap();
+/// assert_eq!(value.get("Count"), Some(&NbtValue::Byte(64)));
+/// assert_eq!(value.get("pos"), Some(&NbtValue::IntArray(vec![1, 2, 3])));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SnbtReader<'s> {
+    lexer: SnbtLexer<'s>,
+    stack: Vec<Container>,
+    state: State,
+    /// The name, string or numbers that are produced as the next frame
+    buf: Vec<u8>,
+    /// The lengths of lists that were counted along with an enclosing list, by where they start
+    nested: VecDeque<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Container {
+    Compound,
+    List(NbtTag),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    /// Between two root values
+    Root,
+    /// The name in the buffer is produced next, before the value or the entries of a compound
+    Name {
+        compound: bool,
+    },
+    NameEnd {
+        compound: bool,
+    },
+    Value,
+    /// Inside a compound, expecting the next key or the closing brace
+    Entry {
+        first: bool,
+    },
+    /// Inside a list of containers or strings, expecting the next element or the closing bracket
+    Element {
+        tag: NbtTag,
+        first: bool,
+    },
+    StringEnd,
+    ByteArrayEnd,
+    /// The elements of a numeric list or array in the buffer are produced as one frame
+    Frame(NbtTag),
+}
+
+impl<'s> SnbtReader<'s> {
+    pub fn new(snbt: &'s str) -> Self {
+        Self {
+            lexer: SnbtLexer::new(snbt),
+            stack: Vec::new(),
+            state: State::Root,
+            buf: Vec::new(),
+            nested: VecDeque::new(),
+        }
+    }
+
+    /// Whether the reader is between two root values
+    pub fn is_idle(&self) -> bool {
+        self.state == State::Root
+    }
+
+    /// Reads the next fragment, returning None once the text ends between two root values
+    pub fn next_fragment(&mut self) -> Result<Option<NbtFragment<'_>>, SnbtError> {
+        loop {
+            match self.state {
+                State::Root => {
+                    if self.lexer.peek_token()?.is_none() {
+                        return Ok(None);
+                    }
+                    self.buf.clear();
+                    if self.peek_tag()? == NbtTag::Compound {
+                        self.lexer.next_token()?;
+                        return Ok(Some(self.open_compound(true)));
+                    }
+                    self.state = State::Name { compound: false };
+                }
+                State::Name { compound } => {
+                    self.state = State::NameEnd { compound };
+                    if !self.buf.is_empty() {
+                        return Ok(Some(NbtFragment::NameFrame(&self.buf)));
+                    }
+                }
+                State::NameEnd { compound } => {
+                    self.state = match compound {
+                        true => State::Entry { first: true },
+                        false => State::Value,
+                    };
+                    return Ok(Some(NbtFragment::NameFrame(&[])));
+                }
+                State::Value => return self.value().map(Some),
+                State::Entry { first } => {
+                    let mut next = self.expect_token()?;
+                    if !first {
+                        match next.0 {
+                            SnbtToken::Comma => next = self.expect_token()?,
+                            SnbtToken::CloseCompound => {}
+                            _ => return Err(unexpected(next)),
+                        }
+                    }
+                    let key = match next.0 {
+                        SnbtToken::CloseCompound => {
+                            self.stack.pop();
+                            self.finish_value();
+                            return Ok(Some(NbtFragment::End));
+                        }
+                        SnbtToken::Word(key) => key.into(),
+                        SnbtToken::Quoted(key) => {
+                            unquote(key).ok_or(SnbtError::InvalidString(next.1.start))?
+                        }
+                        _ => return Err(unexpected(next)),
+                    };
+                    let colon = self.expect_token()?;
+                    if colon.0 != SnbtToken::Colon {
+                        return Err(unexpected(colon));
+                    }
+                    self.buf.clear();
+                    self.buf.extend_from_slice(&mutf8::encode(&key));
+                    if self.peek_tag()? == NbtTag::Compound {
+                        self.lexer.next_token()?;
+                        return Ok(Some(self.open_compound(true)));
+                    }
+                    self.state = State::Name { compound: false };
+                }
+                State::Element { tag, first } => {
+                    let mut next = self.peek_token()?;
+                    if !first {
+                        match next.0 {
+                            SnbtToken::Comma => {
+                                self.lexer.next_token()?;
+                                next = self.peek_token()?;
+                            }
+                            SnbtToken::CloseList => {}
+                            _ => return Err(unexpected(next)),
+                        }
+                    }
+                    if next.0 == SnbtToken::CloseList {
+                        self.lexer.next_token()?;
+                        self.stack.pop();
+                        self.finish_value();
+                        continue;
+                    }
+                    let found = self.peek_tag()?;
+                    if found != tag {
+                        return Err(SnbtError::MixedList {
+                            pos: next.1.start,
+                            expected: tag,
+                            found,
+                        });
+                    }
+                    self.state = State::Value;
+                }
+                State::StringEnd => {
+                    self.finish_value();
+                    return Ok(Some(NbtFragment::StringFrame(&[])));
+                }
+                State::ByteArrayEnd => {
+                    self.finish_value();
+                    return Ok(Some(NbtFragment::ByteArrayFrame(&[])));
+                }
+                State::Frame(tag) => {
+                    self.finish_value();
+                    return Ok(Some(list_frame(tag, &self.buf)));
+                }
+            }
+        }
+    }
+
+    /// Reads a value that is not preceded by a name, or whose name was produced already
+    fn value(&mut self) -> Result<NbtFragment<'_>, SnbtError> {
+        let next = self.expect_token()?;
+        match next.0 {
+            SnbtToken::OpenCompound => Ok(self.open_compound(false)),
+            SnbtToken::OpenList => self.list(),
+            SnbtToken::Quoted(quoted) => {
+                let string = unquote(quoted).ok_or(SnbtError::InvalidString(next.1.start))?;
+                Ok(self.string(&string))
+            }
+            SnbtToken::Word(word) => match number(word) {
+                Some(number) => {
+                    self.finish_value();
+                    Ok(number.into_fragment())
+                }
+                None => Ok(self.string(word)),
+            },
+            _ => Err(unexpected(next)),
+        }
+    }
+
+    fn open_compound(&mut self, named: bool) -> NbtFragment<'static> {
+        self.stack.push(Container::Compound);
+        self.state = match named {
+            true => State::Name { compound: true },
+            false => State::Entry { first: true },
+        };
+        NbtFragment::CompoundTag
+    }
+
+    fn string(&mut self, string: &str) -> NbtFragment<'_> {
+        if string.is_empty() {
+            self.finish_value();
+            return NbtFragment::StringFrame(&[]);
+        }
+        self.buf.clear();
+        self.buf.extend_from_slice(&mutf8::encode(string));
+        self.state = State::StringEnd;
+        NbtFragment::StringFrame(&self.buf)
+    }
+
+    /// Reads a list or array after its opening bracket
+    fn list(&mut self) -> Result<NbtFragment<'_>, SnbtError> {
+        if let Some(tag) = array_prefix(&mut self.lexer)? {
+            let len = self.numbers(tag)?;
+            return Ok(match tag {
+                NbtTag::ByteArray if len == 0 => {
+                    self.finish_value();
+                    NbtFragment::ByteArrayFrame(&[])
+                }
+                NbtTag::ByteArray => {
+                    self.state = State::ByteArrayEnd;
+                    NbtFragment::ByteArrayFrame(&self.buf)
+                }
+                NbtTag::IntArray => {
+                    self.array_frame(NbtTag::Int, len);
+                    NbtFragment::IntArrayTag(len)
+                }
+                _ => {
+                    self.array_frame(NbtTag::Long, len);
+                    NbtFragment::LongArrayTag(len)
+                }
+            });
+        }
+        if self.peek_token()?.0 == SnbtToken::CloseList {
+            self.lexer.next_token()?;
+            self.finish_value();
+            return Ok(NbtFragment::ListTag(NbtTag::End, 0));
+        }
+        let tag = self.peek_tag()?;
+        if tag.is_numeric() {
+            let len = self.numbers(tag)?;
+            self.state = State::Frame(tag);
+            return Ok(NbtFragment::ListTag(tag, len));
+        }
+        let len = match self.counted_len() {
+            Some(len) => len,
+            None => count_elements(self.lexer.clone(), &mut self.nested),
+        };
+        self.stack.push(Container::List(tag));
+        self.state = State::Element { tag, first: true };
+        Ok(NbtFragment::ListTag(tag, len))
+    }
+
+    /// The length of the list the lexer is in, if it was counted along with an enclosing list
+    fn counted_len(&mut self) -> Option<usize> {
+        let pos = self.lexer.pos();
+        while let Some(&(start, len)) = self.nested.front() {
+            if start > pos {
+                break;
+            }
+            self.nested.pop_front();
+            if start == pos {
+                return Some(len);
+            }
+        }
+        None
+    }
+
+    fn array_frame(&mut self, tag: NbtTag, len: usize) {
+        match len {
+            0 => self.finish_value(),
+            _ => self.state = State::Frame(tag),
+        }
+    }
+
+    /// Reads the numbers of a numeric list or an array up to the closing bracket into the buffer,
+    /// returning how many there are
+    fn numbers(&mut self, tag: NbtTag) -> Result<usize, SnbtError> {
+        let (expected, array) = match tag {
+            NbtTag::ByteArray => (NbtTag::Byte, true),
+            NbtTag::IntArray => (NbtTag::Int, true),
+            NbtTag::LongArray => (NbtTag::Long, true),
+            tag => (tag, false),
+        };
+        self.buf.clear();
+        let mut len = 0;
+        loop {
+            let next = self.expect_token()?;
+            let word = match next.0 {
+                SnbtToken::CloseList => return Ok(len),
+                SnbtToken::Word(word) => word,
+                SnbtToken::Quoted(_) => {
+                    return Err(SnbtError::MixedList {
+                        pos: next.1.start,
+                        expected,
+                        found: NbtTag::String,
+                    });
+                }
+                _ => return Err(unexpected(next)),
+            };
+            let number = number(word).ok_or(SnbtError::MixedList {
+                pos: next.1.start,
+                expected,
+                found: NbtTag::String,
+            })?;
+            let element = match array {
+                true => coerce(number, expected),
+                false => Some(number).filter(|number| number.tag() == expected),
+            };
+            let element = element.ok_or(SnbtError::MixedList {
+                pos: next.1.start,
+                expected,
+                found: number.tag(),
+            })?;
+            element.write_be(&mut self.buf);
+            len += 1;
+            let next = self.expect_token()?;
+            match next.0 {
+                SnbtToken::Comma => {}
+                SnbtToken::CloseList => return Ok(len),
+                _ => return Err(unexpected(next)),
+            }
+        }
+    }
+
+    /// Called once a value has been read completely, returns to the enclosing container
+    fn finish_value(&mut self) {
+        self.state = match self.stack.last() {
+            Some(Container::Compound) => State::Entry { first: false },
+            Some(&Container::List(tag)) => State::Element { tag, first: false },
+            None => State::Root,
+        };
+    }
+
+    fn expect_token(&mut self) -> Result<(SnbtToken<'s>, Range<usize>), SnbtError> {
+        self.lexer.next_token()?.ok_or(SnbtError::UnexpectedEnd)
+    }
+
+    fn peek_token(&self) -> Result<(SnbtToken<'s>, Range<usize>), SnbtError> {
+        self.lexer.peek_token()?.ok_or(SnbtError::UnexpectedEnd)
+    }
+
+    /// The type of the value starting at the next token
+    fn peek_tag(&self) -> Result<NbtTag, SnbtError> {
+        let mut lexer = self.lexer.clone();
+        let next = lexer.next_token()?.ok_or(SnbtError::UnexpectedEnd)?;
+        Ok(match next.0 {
+            SnbtToken::OpenCompound => NbtTag::Compound,
+            SnbtToken::OpenList => array_prefix(&mut lexer)?.unwrap_or(NbtTag::List),
+            SnbtToken::Quoted(_) => NbtTag::String,
+            SnbtToken::Word(word) => number(word).map_or(NbtTag::String, Number::tag),
+            _ => return Err(unexpected(next)),
+        })
+    }
+}
+
+fn unexpected((token, span): (SnbtToken<'_>, Range<usize>)) -> SnbtError {
+    SnbtError::UnexpectedToken {
+        pos: span.start,
+        found: token.as_str().into(),
+    }
+}
+
+/// Consumes the `B;`, `I;` or `L;` following the opening bracket of an array
+fn array_prefix(lexer: &mut SnbtLexer<'_>) -> Result<Option<NbtTag>, SnbtError> {
+    let mut ahead = lexer.clone();
+    let tag = match ahead.next_token()? {
+        Some((SnbtToken::Word("B"), _)) => NbtTag::ByteArray,
+        Some((SnbtToken::Word("I"), _)) => NbtTag::IntArray,
+        Some((SnbtToken::Word("L"), _)) => NbtTag::LongArray,
+        _ => return Ok(None),
+    };
+    match ahead.next_token()? {
+        Some((SnbtToken::Semicolon, _)) => {
+            *lexer = ahead;
+            Ok(Some(tag))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// A container skipped over by [count_elements]
+struct Skipped {
+    /// The position after the opening bracket of a list, None for compounds
+    list: Option<usize>,
+    commas: usize,
+    empty: bool,
+    trailing_comma: bool,
+}
+
+impl Skipped {
+    fn new(list: Option<usize>) -> Self {
+        Self {
+            list,
+            commas: 0,
+            empty: true,
+            trailing_comma: false,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self.empty {
+            true => 0,
+            false => self.commas + usize::from(!self.trailing_comma),
+        }
+    }
+}
+
+/// Counts the elements of the list the lexer is in by skipping over them, so the length can be
+/// produced before the elements are parsed
+///
+/// The lengths of the lists nested in it are stored in `nested`, ordered by where they start, so
+/// they are not skipped over again once they are parsed. Malformed elements are reported once
+/// they are parsed, not here.
+fn count_elements(mut lexer: SnbtLexer<'_>, nested: &mut VecDeque<(usize, usize)>) -> usize {
+    let mut found = Vec::new();
+    let mut stack = vec![Skipped::new(Some(lexer.pos()))];
+    let mut len = None;
+    while let Ok(Some((token, _))) = lexer.next_token() {
+        let open = stack.last_mut().expect("the counted list is open");
+        match token {
+            SnbtToken::CloseCompound | SnbtToken::CloseList => {
+                let closed = stack.pop().expect("the counted list is open");
+                let Some(parent) = stack.last_mut() else {
+                    len = Some(closed.len());
+                    break;
+                };
+                if let Some(start) = closed.list {
+                    found.push((start, closed.len()));
+                }
+                parent.trailing_comma = false;
+            }
+            _ => {
+                open.empty = false;
+                open.commas += usize::from(token == SnbtToken::Comma);
+                open.trailing_comma = token == SnbtToken::Comma;
+                if let SnbtToken::OpenCompound | SnbtToken::OpenList = token {
+                    let list = (token == SnbtToken::OpenList).then(|| lexer.pos());
+                    stack.push(Skipped::new(list));
+                }
+            }
+        }
+    }
+    found.sort_unstable();
+    *nested = found.into();
+    // If the input ends within the list, the elements up to there are counted
+    len.unwrap_or_else(|| stack[0].len())
+}
+
+/// Parses a word as a number, returning None if it is a string
+pub(super) fn number(word: &str) -> Option<Number> {
+    match word {
+        "true" => return Some(Number::Byte(1)),
+        "false" => return Some(Number::Byte(0)),
+        _ => {}
+    }
+    let (digits, suffix) = match word.as_bytes().last()?.to_ascii_lowercase() {
+        suffix @ (b'b' | b's' | b'l' | b'f' | b'd') => (&word[..word.len() - 1], Some(suffix)),
+        _ => (word, None),
+    };
+    if is_integer(digits) {
+        let number = match suffix {
+            Some(b'b') => Number::Byte(digits.parse().ok()?),
+            Some(b's') => Number::Short(digits.parse().ok()?),
+            Some(b'l') => Number::Long(digits.parse().ok()?),
+            Some(b'f') => Number::Float(digits.parse().ok()?),
+            Some(_) => Number::Double(digits.parse().ok()?),
+            None => Number::Int(digits.parse().ok()?),
+        };
+        return Some(number);
+    }
+    if !is_decimal(digits) {
+        return None;
+    }
+    match suffix {
+        Some(b'f') => Some(Number::Float(digits.parse().ok()?)),
+        Some(b'd') | None => Some(Number::Double(digits.parse().ok()?)),
+        Some(_) => None,
+    }
+}
+
+fn is_integer(digits: &str) -> bool {
+    let digits = digits.strip_prefix(['+', '-']).unwrap_or(digits);
+    !digits.is_empty() && digits.bytes().all(|byte| byte.is_ascii_digit())
+}
+
+/// Whether the word has the form `1.5`, `.5`, `1.` or `1e5`, optionally signed
+fn is_decimal(digits: &str) -> bool {
+    let digits = digits.strip_prefix(['+', '-']).unwrap_or(digits);
+    let (mantissa, exponent) = match digits.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (digits, None),
+    };
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let all_digits = |part: &str| part.bytes().all(|byte| byte.is_ascii_digit());
+    (!whole.is_empty() || !fraction.is_empty())
+        && all_digits(whole)
+        && all_digits(fraction)
+        && exponent.is_none_or(is_integer)
+}
+
+/// Converts an element of an array to the type of the array, accepting ints without a suffix
+fn coerce(number: Number, tag: NbtTag) -> Option<Number> {
+    match (number, tag) {
+        (number, tag) if number.tag() == tag => Some(number),
+        (Number::Int(value), NbtTag::Byte) => i8::try_from(value).ok().map(Number::Byte),
+        (Number::Int(value), NbtTag::Long) => Some(Number::Long(value.into())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        CompleteFsm,
+        snbt::SnbtWriter,
+        value::{NbtCompound, NbtList, NbtValue, NbtValueBuilder},
+    };
+    use alloc::{string::String, vec};
+
+    fn parse(snbt: &str) -> Result<NbtValue, SnbtError> {
+        let mut reader = SnbtReader::new(snbt);
+        let mut builder = NbtValueBuilder::new();
+        loop {
+            let fragment = reader.next_fragment()?.ok_or(SnbtError::UnexpectedEnd)?;
+            if let Some((name, value)) = builder.push(fragment).unwrap() {
+                assert_eq!(name, "");
+                assert!(reader.is_idle());
+                return Ok(value);
+            }
+        }
+    }
+
+    #[test]
+    fn matches_binary() {
+        let inputs: [&[u8]; 2] = [
+            include_bytes!("../../assets/bigtest.nbt"),
+            include_bytes!("../../assets/chunk_0-0.nbt"),
+        ];
+        for data in inputs {
+            let mut writer = SnbtWriter::new(String::new());
+            let mut binary: Vec<_> = CompleteFsm::new(data).map(Result::unwrap).collect();
+            for fragment in &binary {
+                writer.push(fragment.clone()).unwrap();
+            }
+            // The root name is lost in SNBT
+            if binary[1] != NbtFragment::NameFrame(&[]) {
+                binary.remove(1);
+            }
+
+            let snbt = writer.into_inner();
+            let mut reader = SnbtReader::new(&snbt);
+            let mut text = Vec::new();
+            while let Some(fragment) = reader.next_fragment().unwrap() {
+                text.push(fragment.into_owned());
+            }
+            let binary: Vec<_> = binary.into_iter().map(NbtFragment::into_owned).collect();
+            assert_eq!(binary, text);
+        }
+    }
+
+    #[test]
+    fn values() {
+        let value = parse(
+            r#"{
+                byte: 1b, short: -2S, int: +3, long: 4l, float: .5f, double: 1e3,
+                bool: true, word: minecraft.stone, "quoted key": 'it\'s', empty: "",
+                bytes: [B; 1b, 2], ints: [I;], longs: [L; 1, 2L,],
+                list: [[], [{}], [[I; 5]]], strings: ["a", b], doubles: [1.0, 2d],
+                number_like: 12abc, big: 2147483648,
+            }"#,
+        )
+        .unwrap();
+        let list = |values: Vec<NbtValue>| NbtValue::List(NbtList::try_from(values).unwrap());
+        let compound: NbtCompound = [
+            ("byte", NbtValue::Byte(1)),
+            ("short", NbtValue::Short(-2)),
+            ("int", NbtValue::Int(3)),
+            ("long", NbtValue::Long(4)),
+            ("float", NbtValue::Float(0.5)),
+            ("double", NbtValue::Double(1000.0)),
+            ("bool", NbtValue::Byte(1)),
+            ("word", NbtValue::String("minecraft.stone".into())),
+            ("quoted key", NbtValue::String("it's".into())),
+            ("empty", NbtValue::String("".into())),
+            ("bytes", NbtValue::ByteArray(vec![1, 2])),
+            ("ints", NbtValue::IntArray(vec![])),
+            ("longs", NbtValue::LongArray(vec![1, 2])),
+            (
+                "list",
+                list(vec![
+                    list(vec![]),
+                    list(vec![NbtValue::Compound(NbtCompound::new())]),
+                    list(vec![NbtValue::IntArray(vec![5])]),
+                ]),
+            ),
+            (
+                "strings",
+                list(vec![
+                    NbtValue::String("a".into()),
+                    NbtValue::String("b".into()),
+                ]),
+            ),
+            (
+                "doubles",
+                list(vec![NbtValue::Double(1.0), NbtValue::Double(2.0)]),
+            ),
+            ("number_like", NbtValue::String("12abc".into())),
+            ("big", NbtValue::String("2147483648".into())),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(value, NbtValue::Compound(compound));
+        assert_eq!(parse("'\\u00e9'"), Ok(NbtValue::String("é".into())));
+        assert_eq!(parse(" 5s "), Ok(NbtValue::Short(5)));
+    }
+
+    #[test]
+    fn nested_lists() {
+        let list_lens = |snbt: &str| {
+            let mut reader = SnbtReader::new(snbt);
+            let mut lens = Vec::new();
+            while let Some(fragment) = reader.next_fragment().unwrap() {
+                if let NbtFragment::ListTag(_, len) = fragment {
+                    lens.push(len);
+                }
+            }
+            lens
+        };
+        assert_eq!(
+            list_lens(r#"[[["a", "b",], [{l: [[c], [d, e]]}]], [[], ["e"]], [[{}, {}, {}]]]"#),
+            [3, 2, 2, 1, 2, 1, 2, 2, 0, 1, 1, 3]
+        );
+        // Each list is skipped over once, not once for every list it is nested in
+        let depth = 100_000;
+        let deep = "[".repeat(depth) + "a" + &"]".repeat(depth);
+        assert_eq!(list_lens(&deep), vec![1; depth]);
+    }
+
+    #[test]
+    fn errors() {
+        let mixed = |pos, expected, found| SnbtError::MixedList {
+            pos,
+            expected,
+            found,
+        };
+        assert_eq!(parse("[1, 2b]"), Err(mixed(4, NbtTag::Int, NbtTag::Byte)));
+        assert_eq!(
+            parse("[{}, []]"),
+            Err(mixed(5, NbtTag::Compound, NbtTag::List))
+        );
+        assert_eq!(parse("[B; 300]"), Err(mixed(4, NbtTag::Byte, NbtTag::Int)));
+        assert_eq!(parse("{a: 1"), Err(SnbtError::UnexpectedEnd));
+        assert_eq!(parse("'open"), Err(SnbtError::UnexpectedEnd));
+        assert_eq!(parse("'\\q'"), Err(SnbtError::InvalidString(0)));
+        assert_eq!(
+            parse("{a 1}"),
+            Err(SnbtError::UnexpectedToken {
+                pos: 3,
+                found: "1".into()
+            })
+        );
+        assert_eq!(
+            parse("{a: @}"),
+            Err(SnbtError::UnexpectedChar { pos: 4, found: '@' })
+        );
+    }
+}
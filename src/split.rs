@@ -0,0 +1,262 @@
+//! Splitting the entries of a root compound into independent streams
+//!
+//! An [NbtSplitter] sends the fragments of each top-level entry to the sink routed to its key, so
+//! sections like `sections` and `block_entities` of a chunk can be handed to different workers or
+//! files in a single pass. [entry_spans] finds the byte range of every entry instead, for when the
+//! whole document is in memory already.
+//!
+//! ```
+//! # use zeronbt::{CompleteFsm, NbtWriter, split::NbtSplitter, value::NbtValue};
+//! let data = include_bytes!("../assets/bigtest.nbt");
+//! let mut nested = NbtWriter::new();
+//! let mut others = 0;
+//! let mut splitter = NbtSplitter::new()
+//!     .route("nested compound test", |fragment| {
+//!         nested.push(fragment).unwrap();
+//!     })
+//!     .rest(|_| others += 1);
+//! for fragment in CompleteFsm::new(data) {
+//!     splitter.push(fragment.unwrap()).unwrap();
+//! }
+//! drop(splitter);
+//! let (name, value) = NbtValue::read(&nested.into_inner()).unwrap();
+//! assert_eq!(name, "nested compound test");
+//! assert!(value.get("egg").is_some());
+//! assert!(others > 0);
+//! ```
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::ops::Range;
+
+use crate::{
+    FsmResult, NbtFragment, NbtFsm,
+    error::{NbtParseError, NbtResult},
+    levels::{Level, track},
+    mutf8,
+};
+
+type Sink<'s> = Box<dyn FnMut(NbtFragment<'_>) + 's>;
+
+/// Sends the entries of a root compound to different sinks, see the [module docs](self)
+///
+/// Each sink is given the fragments of its entries as they would appear in a document of their own:
+/// a compound entry starts with its [CompoundTag](NbtFragment::CompoundTag) followed by its name,
+/// other entries with their name followed by their value. The fragments of the root compound
+/// itself are not passed on.
+pub struct NbtSplitter<'s> {
+    /// The MUTF-8 key and sink of each route
+    routes: Vec<(Vec<u8>, Sink<'s>)>,
+    rest: Option<Sink<'s>>,
+    entries: Entries,
+    /// The sink of the entry being passed on, None if it is dropped
+    sink: Option<usize>,
+}
+
+impl<'s> NbtSplitter<'s> {
+    /// A splitter that drops every entry
+    pub fn new() -> Self {
+        Self {
+            routes: Vec::new(),
+            rest: None,
+            entries: Entries::default(),
+            sink: None,
+        }
+    }
+
+    /// Sends the entries named `key` to `sink`
+    ///
+    /// The first route added for a key takes its entries.
+    pub fn route(mut self, key: &str, sink: impl FnMut(NbtFragment<'_>) + 's) -> Self {
+        self.routes
+            .push((mutf8::encode(key).into_owned(), Box::new(sink)));
+        self
+    }
+
+    /// Sends the entries that no route takes to `sink`, instead of dropping them
+    pub fn rest(mut self, sink: impl FnMut(NbtFragment<'_>) + 's) -> Self {
+        self.rest = Some(Box::new(sink));
+        self
+    }
+
+    /// Splits the next fragment, returning true once the root compound is complete
+    ///
+    /// Fragments of the next document may be pushed after that.
+    pub fn push(&mut self, fragment: NbtFragment<'_>) -> NbtResult<bool> {
+        match self.entries.push(&fragment)? {
+            Step::Root | Step::Head => {}
+            Step::Named { compound } => {
+                let name = &self.entries.name;
+                self.sink = self
+                    .routes
+                    .iter()
+                    .position(|(key, _)| key == name)
+                    .or(self.rest.as_ref().map(|_| self.routes.len()));
+                if compound {
+                    self.send(NbtFragment::CompoundTag);
+                }
+                let name = core::mem::take(&mut self.entries.name);
+                if !name.is_empty() {
+                    self.send(NbtFragment::NameFrame(&name));
+                }
+                self.send(NbtFragment::NameFrame(&[]));
+                self.entries.name = name;
+            }
+            Step::Value { last } => {
+                self.send(fragment);
+                if last {
+                    self.sink = None;
+                }
+            }
+            Step::Done => return Ok(true),
+        }
+        Ok(false)
+    }
+
+    fn send(&mut self, fragment: NbtFragment<'_>) {
+        let sink = match self.sink {
+            Some(index) if index < self.routes.len() => &mut self.routes[index].1,
+            Some(_) => match &mut self.rest {
+                Some(sink) => sink,
+                None => return,
+            },
+            None => return,
+        };
+        sink(fragment);
+    }
+}
+
+impl Default for NbtSplitter<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Finds the key and byte range of every entry of the root compound in `data`
+///
+/// Each range covers the tag byte, name and payload of the entry, which makes it a document of its
+/// own that can be parsed or copied as it is.
+pub fn entry_spans(data: &[u8]) -> NbtResult<Vec<(String, Range<usize>)>> {
+    let mut fsm = NbtFsm::new().with_data(data);
+    let mut entries = Entries::default();
+    let mut spans = Vec::new();
+    let mut start = 0;
+    let mut key = String::new();
+    loop {
+        let FsmResult::Found(fragment) = fsm.next_fragment()? else {
+            return Err(NbtParseError::UnexpectedEnd);
+        };
+        match entries.push(&fragment)? {
+            Step::Root => start = fsm.consumed(),
+            Step::Head => {}
+            Step::Named { .. } => {
+                key = mutf8::decode(&entries.name)
+                    .ok_or(NbtParseError::InvalidString)?
+                    .into_owned();
+            }
+            Step::Value { last: false } => {}
+            Step::Value { last: true } => {
+                spans.push((core::mem::take(&mut key), start..fsm.consumed()));
+                start = fsm.consumed();
+            }
+            Step::Done => return Ok(spans),
+        }
+    }
+}
+
+/// Where a fragment belongs in the root compound
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    /// The tag or name of the root
+    Root,
+    /// The tag or part of the name of an entry
+    Head,
+    /// The last frame of the name of an entry, which is found in [Entries::name]
+    Named { compound: bool },
+    /// A fragment of the value of an entry, which may be its last
+    Value { last: bool },
+    /// The end of the root compound
+    Done,
+}
+
+/// Tracks the entries of a root compound
+#[derive(Debug, Clone, Default)]
+struct Entries {
+    levels: Vec<Level>,
+    /// Whether the name of the root has been read
+    named: bool,
+    /// Whether the entry being read is a compound, announced before its name
+    compound: bool,
+    /// Whether the value of an entry is being read
+    open: bool,
+    name: Vec<u8>,
+}
+
+impl Entries {
+    fn push(&mut self, fragment: &NbtFragment<'_>) -> NbtResult<Step> {
+        if self.levels.is_empty() {
+            if *fragment != NbtFragment::CompoundTag {
+                return Err(NbtParseError::UnexpectedType);
+            }
+            self.levels.push(Level::Compound);
+            self.named = false;
+            return Ok(Step::Root);
+        }
+        if !self.named {
+            let NbtFragment::NameFrame(frame) = *fragment else {
+                return Err(NbtParseError::UnexpectedFragment);
+            };
+            self.named = frame.is_empty();
+            return Ok(Step::Root);
+        }
+        if self.open {
+            let last = track(&mut self.levels, fragment) && self.levels.len() == 1;
+            self.open = !last;
+            if last {
+                self.name.clear();
+            }
+            return Ok(Step::Value { last });
+        }
+        match *fragment {
+            NbtFragment::End => {
+                self.levels.clear();
+                Ok(Step::Done)
+            }
+            NbtFragment::CompoundTag if !self.compound => {
+                self.compound = true;
+                Ok(Step::Head)
+            }
+            NbtFragment::NameFrame([]) => {
+                let compound = core::mem::take(&mut self.compound);
+                if compound {
+                    self.levels.push(Level::Compound);
+                }
+                self.open = true;
+                Ok(Step::Named { compound })
+            }
+            NbtFragment::NameFrame(frame) => {
+                self.name.extend_from_slice(frame);
+                Ok(Step::Head)
+            }
+            _ => Err(NbtParseError::UnexpectedFragment),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::NbtValue;
+
+    #[test]
+    fn spans() {
+        let data = include_bytes!("../assets/bigtest.nbt");
+        let (_, expected) = NbtValue::read(data).unwrap();
+        let spans = entry_spans(data).unwrap();
+        assert_eq!(spans.len(), 11);
+        for (key, range) in spans {
+            let (name, value) = NbtValue::read(&data[range]).unwrap();
+            assert_eq!(name, key);
+            assert_eq!(expected.get(&key), Some(&value));
+        }
+        assert_eq!(entry_spans(&data[..100]), Err(NbtParseError::UnexpectedEnd));
+    }
+}
@@ -0,0 +1,322 @@
+//! Typed access to vanilla structure files, as saved by structure blocks
+//!
+//! Like schematics, structure files are usually gzip compressed, this module works with the
+//! decompressed NBT.
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+
+use crate::{
+    NbtTag,
+    convert::{FromNbt, ToNbt, field, field_or},
+    error::{NbtConvertError, StructureError},
+    value::{NbtCompound, NbtList, NbtValue},
+};
+
+/// A block state, as stored in structure palettes and chunk sections
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct BlockState {
+    pub name: String,
+    pub properties: BTreeMap<String, String>,
+}
+
+/// A block placed by the structure, referring to its state by palette index
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StructureBlock {
+    pub state: u32,
+    pub pos: [i32; 3],
+    /// The block entity data, without its position
+    pub nbt: Option<NbtCompound>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StructureEntity {
+    pub pos: [f64; 3],
+    pub block_pos: [i32; 3],
+    pub nbt: NbtCompound,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Structure {
+    pub data_version: i32,
+    pub size: [i32; 3],
+    /// The palettes of the structure, of which there is usually one, while some structures like
+    /// shipwrecks pick a random one of several
+    pub palettes: Vec<Vec<BlockState>>,
+    pub blocks: Vec<StructureBlock>,
+    pub entities: Vec<StructureEntity>,
+}
+
+impl BlockState {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            properties: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_property(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.properties.insert(key.into(), value.into());
+        self
+    }
+}
+
+impl Structure {
+    /// Parses a structure from uncompressed NBT, checking that all blocks refer to valid states
+    pub fn read(data: &[u8]) -> Result<Self, StructureError> {
+        let (_, root) = NbtValue::read(data)?;
+        let structure = Self::from_nbt(&root)?;
+        let states = structure.palettes.iter().map(Vec::len).min().unwrap_or(0);
+        if let Some(block) = structure
+            .blocks
+            .iter()
+            .find(|block| block.state as usize >= states)
+        {
+            return Err(StructureError::InvalidState(block.state));
+        }
+        Ok(structure)
+    }
+
+    /// Encodes the structure as uncompressed NBT
+    pub fn to_bytes(&self) -> Result<Vec<u8>, StructureError> {
+        Ok(self.to_nbt().to_bytes("")?)
+    }
+
+    /// The first palette, which is the only one for most structures
+    pub fn palette(&self) -> &[BlockState] {
+        self.palettes.first().map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the index of `state` in the first palette, adding it if it is missing
+    pub fn state_index(&mut self, state: BlockState) -> u32 {
+        if self.palettes.is_empty() {
+            self.palettes.push(Vec::new());
+        }
+        let palette = &mut self.palettes[0];
+        let index = match palette.iter().position(|other| *other == state) {
+            Some(index) => index,
+            None => {
+                palette.push(state);
+                palette.len() - 1
+            }
+        };
+        index as u32
+    }
+
+    /// The block at `pos` along with its state in the first palette
+    pub fn block_at(&self, pos: [i32; 3]) -> Option<(&StructureBlock, &BlockState)> {
+        let block = self.blocks.iter().find(|block| block.pos == pos)?;
+        Some((block, self.palette().get(block.state as usize)?))
+    }
+
+    /// Iterates over the blocks along with their states in the first palette
+    pub fn blocks_with_states(&self) -> impl Iterator<Item = (&StructureBlock, &BlockState)> {
+        self.blocks.iter().filter_map(|block| {
+            let state = self.palette().get(block.state as usize)?;
+            Some((block, state))
+        })
+    }
+}
+
+fn compound(value: &NbtValue) -> Result<&NbtCompound, NbtConvertError> {
+    value.as_compound().ok_or(NbtConvertError::WrongTag {
+        expected: NbtTag::Compound,
+        found: value.tag(),
+    })
+}
+
+fn list_of<T: ToNbt>(values: &[T]) -> NbtList {
+    let mut list = NbtList::with_tag(NbtTag::Compound);
+    for value in values {
+        list.push(value.to_nbt())
+            .expect("all elements are compounds");
+    }
+    list
+}
+
+impl FromNbt for BlockState {
+    fn from_nbt(value: &NbtValue) -> Result<Self, NbtConvertError> {
+        let compound = compound(value)?;
+        Ok(Self {
+            name: field(compound, "Name")?,
+            properties: field_or(compound, "Properties", BTreeMap::new())?,
+        })
+    }
+}
+
+impl ToNbt for BlockState {
+    fn to_nbt(&self) -> NbtValue {
+        let mut out = NbtCompound::new();
+        out.insert("Name", self.name.as_str());
+        if !self.properties.is_empty() {
+            out.insert("Properties", self.properties.to_nbt());
+        }
+        NbtValue::Compound(out)
+    }
+}
+
+impl FromNbt for StructureBlock {
+    fn from_nbt(value: &NbtValue) -> Result<Self, NbtConvertError> {
+        let compound = compound(value)?;
+        let state: i32 = field(compound, "state")?;
+        Ok(Self {
+            state: state as u32,
+            pos: field(compound, "pos")?,
+            nbt: compound
+                .get("nbt")
+                .map(|_| field(compound, "nbt"))
+                .transpose()?,
+        })
+    }
+}
+
+impl ToNbt for StructureBlock {
+    fn to_nbt(&self) -> NbtValue {
+        let mut out = NbtCompound::new();
+        out.insert("state", self.state as i32);
+        out.insert("pos", self.pos.to_nbt());
+        if let Some(nbt) = &self.nbt {
+            out.insert("nbt", nbt.clone());
+        }
+        NbtValue::Compound(out)
+    }
+}
+
+impl FromNbt for StructureEntity {
+    fn from_nbt(value: &NbtValue) -> Result<Self, NbtConvertError> {
+        let compound = compound(value)?;
+        Ok(Self {
+            pos: field(compound, "pos")?,
+            block_pos: field(compound, "blockPos")?,
+            nbt: field(compound, "nbt")?,
+        })
+    }
+}
+
+impl ToNbt for StructureEntity {
+    fn to_nbt(&self) -> NbtValue {
+        let mut out = NbtCompound::new();
+        out.insert("pos", self.pos.to_nbt());
+        out.insert("blockPos", self.block_pos.to_nbt());
+        out.insert("nbt", self.nbt.clone());
+        NbtValue::Compound(out)
+    }
+}
+
+impl FromNbt for Structure {
+    fn from_nbt(value: &NbtValue) -> Result<Self, NbtConvertError> {
+        let compound = compound(value)?;
+        let palettes = match compound.get("palette") {
+            Some(_) => Vec::from([field(compound, "palette")?]),
+            None => field_or(compound, "palettes", Vec::new())?,
+        };
+        Ok(Self {
+            data_version: field_or(compound, "DataVersion", 0)?,
+            size: field(compound, "size")?,
+            palettes,
+            blocks: field_or(compound, "blocks", Vec::new())?,
+            entities: field_or(compound, "entities", Vec::new())?,
+        })
+    }
+}
+
+impl ToNbt for Structure {
+    /// Stores a single palette under `palette`, and multiple ones under `palettes`
+    fn to_nbt(&self) -> NbtValue {
+        let mut out = NbtCompound::new();
+        out.insert("size", self.size.to_nbt());
+        out.insert("entities", list_of(&self.entities));
+        out.insert("blocks", list_of(&self.blocks));
+        match self.palettes.as_slice() {
+            [palette] => {
+                out.insert("palette", list_of(palette));
+            }
+            palettes => {
+                let mut list = NbtList::with_tag(NbtTag::List);
+                for palette in palettes {
+                    list.push(list_of(palette).into())
+                        .expect("all elements are lists");
+                }
+                out.insert("palettes", list);
+            }
+        }
+        out.insert("DataVersion", self.data_version);
+        NbtValue::Compound(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn sample() -> Structure {
+        let mut structure = Structure {
+            data_version: 3465,
+            size: [2, 1, 1],
+            ..Default::default()
+        };
+        let chest = structure
+            .state_index(BlockState::new("minecraft:chest").with_property("facing", "north"));
+        let air = structure.state_index(BlockState::new("minecraft:air"));
+        structure.blocks = vec![
+            StructureBlock {
+                state: chest,
+                pos: [0, 0, 0],
+                nbt: Some([("id", "minecraft:chest")].into_iter().collect()),
+            },
+            StructureBlock {
+                state: air,
+                pos: [1, 0, 0],
+                nbt: None,
+            },
+        ];
+        structure.entities.push(StructureEntity {
+            pos: [1.5, 0.0, 0.5],
+            block_pos: [1, 0, 0],
+            nbt: [("id", "minecraft:pig")].into_iter().collect(),
+        });
+        structure
+    }
+
+    #[test]
+    fn round_trip() {
+        let structure = sample();
+        let bytes = structure.to_bytes().unwrap();
+        let read = Structure::read(&bytes).unwrap();
+        assert_eq!(read, structure);
+        let (block, state) = read.block_at([0, 0, 0]).unwrap();
+        assert_eq!(state.properties["facing"], "north");
+        assert!(block.nbt.is_some());
+        assert_eq!(read.blocks_with_states().count(), 2);
+
+        let mut multiple = structure.clone();
+        multiple.palettes.push(multiple.palettes[0].clone());
+        let value = multiple.to_nbt();
+        assert!(value.get("palette").is_none());
+        assert_eq!(Structure::from_nbt(&value), Ok(multiple));
+    }
+
+    #[test]
+    fn invalid_state() {
+        let mut structure = sample();
+        structure.blocks[1].state = 2;
+        let bytes = structure.to_bytes().unwrap();
+        assert_eq!(
+            Structure::read(&bytes),
+            Err(StructureError::InvalidState(2))
+        );
+        let mut value = structure.to_nbt();
+        if let NbtValue::Compound(compound) = &mut value {
+            compound.insert("size", vec![NbtValue::Int(1)].to_nbt());
+        }
+        assert_eq!(
+            Structure::from_nbt(&value),
+            Err(NbtConvertError::in_field(
+                "size",
+                NbtConvertError::WrongLength {
+                    expected: 3,
+                    found: 1
+                }
+            ))
+        );
+    }
+}
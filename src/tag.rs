@@ -1,7 +1,9 @@
+use core::fmt;
+
 use crate::error::NbtParseError;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub(crate) enum NbtTag {
+pub enum NbtTag {
     End = 0,
     Byte = 1,
     Short = 2,
@@ -17,6 +19,81 @@ pub(crate) enum NbtTag {
     LongArray = 12,
 }
 
+impl NbtTag {
+    /// Whether values of this tag are single numbers, which lists store as plain frames
+    pub const fn is_numeric(self) -> bool {
+        matches!(
+            self,
+            NbtTag::Byte
+                | NbtTag::Short
+                | NbtTag::Int
+                | NbtTag::Long
+                | NbtTag::Float
+                | NbtTag::Double
+        )
+    }
+
+    /// Whether values of this tag hold other tagged values, which is lists and compounds
+    pub const fn is_container(self) -> bool {
+        matches!(self, NbtTag::List | NbtTag::Compound)
+    }
+
+    /// The length of the payload of every value of this tag, None if it depends on the value
+    pub const fn fixed_payload_size(self) -> Option<usize> {
+        match self {
+            NbtTag::End => Some(0),
+            NbtTag::Byte => Some(1),
+            NbtTag::Short => Some(2),
+            NbtTag::Int | NbtTag::Float => Some(4),
+            NbtTag::Long | NbtTag::Double => Some(8),
+            _ => None,
+        }
+    }
+}
+
+/// Writes the name the NBT specification uses for the tag, like `TAG_Int_Array`
+impl fmt::Display for NbtTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            NbtTag::End => "TAG_End",
+            NbtTag::Byte => "TAG_Byte",
+            NbtTag::Short => "TAG_Short",
+            NbtTag::Int => "TAG_Int",
+            NbtTag::Long => "TAG_Long",
+            NbtTag::Float => "TAG_Float",
+            NbtTag::Double => "TAG_Double",
+            NbtTag::ByteArray => "TAG_Byte_Array",
+            NbtTag::String => "TAG_String",
+            NbtTag::List => "TAG_List",
+            NbtTag::Compound => "TAG_Compound",
+            NbtTag::IntArray => "TAG_Int_Array",
+            NbtTag::LongArray => "TAG_Long_Array",
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for NbtTag {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let name = match self {
+            NbtTag::End => "End",
+            NbtTag::Byte => "Byte",
+            NbtTag::Short => "Short",
+            NbtTag::Int => "Int",
+            NbtTag::Long => "Long",
+            NbtTag::Float => "Float",
+            NbtTag::Double => "Double",
+            NbtTag::ByteArray => "ByteArray",
+            NbtTag::String => "String",
+            NbtTag::List => "List",
+            NbtTag::Compound => "Compound",
+            NbtTag::IntArray => "IntArray",
+            NbtTag::LongArray => "LongArray",
+        };
+        serializer.serialize_unit_variant("NbtTag", *self as u32, name)
+    }
+}
+
 impl TryFrom<u8> for NbtTag {
     type Error = NbtParseError;
 
@@ -0,0 +1,200 @@
+//! Helpers for testing code built on top of zeronbt
+//!
+//! Only available with the `testing` feature, the strategies also need the `proptest` feature.
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+use crate::{
+    OwnedNbtFragment,
+    diff::{DiffKind, Difference, diff},
+    value::{NbtCompound, NbtValue},
+};
+
+mod round_trip;
+#[cfg(feature = "proptest")]
+pub mod strategies;
+
+pub use round_trip::{Canonicalize, round_trip, round_trip_with};
+
+/// Anything [assert_nbt_eq](crate::assert_nbt_eq) can compare
+pub trait NbtOperand {
+    /// The root value along with its name, if it has one
+    fn to_root(&self) -> (Option<String>, NbtValue);
+
+    /// The encoded document, if this is one
+    fn bytes(&self) -> Option<&[u8]> {
+        None
+    }
+}
+
+impl NbtOperand for NbtValue {
+    fn to_root(&self) -> (Option<String>, NbtValue) {
+        (None, self.clone())
+    }
+}
+
+impl NbtOperand for NbtCompound {
+    fn to_root(&self) -> (Option<String>, NbtValue) {
+        (None, NbtValue::Compound(self.clone()))
+    }
+}
+
+/// Encoded documents, which panic if they can not be parsed
+impl NbtOperand for [u8] {
+    #[track_caller]
+    fn to_root(&self) -> (Option<String>, NbtValue) {
+        match NbtValue::read(self) {
+            Ok((name, value)) => (Some(name), value),
+            Err(err) => panic!("failed to parse NBT document: {err}"),
+        }
+    }
+
+    fn bytes(&self) -> Option<&[u8]> {
+        Some(self)
+    }
+}
+
+impl<const N: usize> NbtOperand for [u8; N] {
+    #[track_caller]
+    fn to_root(&self) -> (Option<String>, NbtValue) {
+        self.as_slice().to_root()
+    }
+
+    fn bytes(&self) -> Option<&[u8]> {
+        Some(self)
+    }
+}
+
+impl NbtOperand for Vec<u8> {
+    #[track_caller]
+    fn to_root(&self) -> (Option<String>, NbtValue) {
+        self.as_slice().to_root()
+    }
+
+    fn bytes(&self) -> Option<&[u8]> {
+        Some(self)
+    }
+}
+
+impl<T: NbtOperand + ?Sized> NbtOperand for &T {
+    #[track_caller]
+    fn to_root(&self) -> (Option<String>, NbtValue) {
+        (**self).to_root()
+    }
+
+    fn bytes(&self) -> Option<&[u8]> {
+        (**self).bytes()
+    }
+}
+
+/// Asserts that two values or encoded documents are structurally equal, see [crate::diff]
+///
+/// On failure, every difference is listed along with its path. The names of root tags are only
+/// compared when both sides are encoded documents.
+///
+/// ```
+/// # use zeronbt::{assert_nbt_eq, value::NbtValue};
+/// let data = NbtValue::Int(1).to_bytes("").unwrap();
+/// assert_nbt_eq!(data, NbtValue::Int(1));
+/// ```
+#[macro_export]
+macro_rules! assert_nbt_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::testing::assert_nbt_eq(&$left, &$right, ::core::option::Option::None)
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        $crate::testing::assert_nbt_eq(
+            &$left,
+            &$right,
+            ::core::option::Option::Some(::core::format_args!($($arg)+)),
+        )
+    };
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_nbt_eq(
+    left: &dyn NbtOperand,
+    right: &dyn NbtOperand,
+    message: Option<fmt::Arguments<'_>>,
+) {
+    let (left_name, left) = left.to_root();
+    let (right_name, right) = right.to_root();
+    let mut differences = diff(&left, &right);
+    if let (Some(left), Some(right)) = (left_name, right_name)
+        && left != right
+    {
+        let kind = DiffKind::Changed {
+            left: left.into(),
+            right: right.into(),
+        };
+        differences.insert(
+            0,
+            Difference {
+                path: "<name>".to_string(),
+                kind,
+            },
+        );
+    }
+    if differences.is_empty() {
+        return;
+    }
+    let mut report = String::new();
+    for difference in &differences {
+        report.push_str(&difference.to_string());
+        report.push('\n');
+    }
+    match message {
+        Some(message) => panic!("NBT values differ: {message}\n{report}"),
+        None => panic!("NBT values differ\n{report}"),
+    }
+}
+
+/// Joins consecutive frames holding parts of the same string, name, byte array or numeric list
+pub fn merge_frames(
+    fragments: impl IntoIterator<Item = OwnedNbtFragment>,
+) -> Vec<OwnedNbtFragment> {
+    use OwnedNbtFragment as F;
+    let mut out: Vec<OwnedNbtFragment> = Vec::new();
+    for fragment in fragments {
+        match (out.last_mut(), fragment) {
+            (Some(F::NameFrame(last)), F::NameFrame(next))
+            | (Some(F::StringFrame(last)), F::StringFrame(next))
+            | (Some(F::ByteArrayFrame(last)), F::ByteArrayFrame(next))
+                if !last.is_empty() && !next.is_empty() =>
+            {
+                last.extend(next)
+            }
+            (Some(F::ByteListFrame(last)), F::ByteListFrame(next)) => last.extend(next),
+            (Some(F::ShortListFrame(last)), F::ShortListFrame(next)) => last.extend(next),
+            (Some(F::IntListFrame(last)), F::IntListFrame(next)) => last.extend(next),
+            (Some(F::LongListFrame(last)), F::LongListFrame(next)) => last.extend(next),
+            (Some(F::FloatListFrame(last)), F::FloatListFrame(next)) => last.extend(next),
+            (Some(F::DoubleListFrame(last)), F::DoubleListFrame(next)) => last.extend(next),
+            (_, fragment) => out.push(fragment),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::value::NbtValue;
+
+    #[test]
+    fn equal_values() {
+        let data = include_bytes!("../assets/bigtest.nbt");
+        let (_, value) = NbtValue::read(data).unwrap();
+        assert_nbt_eq!(data, value);
+        assert_nbt_eq!(data.to_vec(), data, "same document");
+    }
+
+    #[test]
+    #[should_panic(expected = "~ <root>: Int(1) != Int(2)")]
+    fn different_values() {
+        assert_nbt_eq!(NbtValue::Int(1), NbtValue::Int(2));
+    }
+}
@@ -0,0 +1,293 @@
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
+use core::fmt::Write;
+
+use super::{NbtOperand, merge_frames};
+use crate::{FsmResult, NbtFsm, NbtTag, OwnedNbtFragment, mutf8, value::NbtValue};
+
+/// How fragment streams are normalized before [round_trip_with] compares them
+///
+/// Frames are always merged, see [merge_frames], and floats are compared by their bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Canonicalize {
+    /// Compares strings and names by their text instead of their bytes, so e.g. standard UTF-8
+    /// input equals the Modified UTF-8 it is written as
+    pub strings: bool,
+    /// Ignores the order of compound entries
+    pub entry_order: bool,
+}
+
+/// Parses `input`, writes it with [NbtValue::write] and checks that parsing the output produces
+/// the same fragments as parsing the input
+#[track_caller]
+pub fn round_trip(input: &dyn NbtOperand) {
+    round_trip_with(input, Canonicalize::default(), |name, value| {
+        value.to_bytes(name).expect("parsed values can be written")
+    })
+}
+
+/// Like [round_trip], with a custom writer and normalization
+///
+/// Values are encoded with [NbtValue::write] first, with an empty root name.
+#[track_caller]
+pub fn round_trip_with(
+    input: &dyn NbtOperand,
+    canonicalize: Canonicalize,
+    write: impl Fn(&str, &NbtValue) -> Vec<u8>,
+) {
+    let (name, value) = input.to_root();
+    let data = match input.bytes() {
+        Some(data) => data.to_owned(),
+        None => write(name.as_deref().unwrap_or(""), &value),
+    };
+    let (name, value) = match NbtValue::read(&data) {
+        Ok(root) => root,
+        Err(err) => panic!("failed to parse the input: {err}"),
+    };
+    let written = write(&name, &value);
+    let expected = canonical(parse(&data, "input"), canonicalize);
+    let found = canonical(parse(&written, "written output"), canonicalize);
+    let Some(index) = first_difference(&expected, &found) else {
+        return;
+    };
+    let mut report = String::new();
+    let start = index.saturating_sub(3);
+    for (label, fragments) in [("parsed", &expected), ("re-parsed", &found)] {
+        let _ = writeln!(report, "{label}:");
+        for (index, fragment) in fragments.iter().enumerate().skip(start).take(7) {
+            let _ = writeln!(report, "  {index}: {fragment:?}");
+        }
+    }
+    panic!("round trip changed fragment {index}\n{report}");
+}
+
+#[track_caller]
+fn parse(data: &[u8], what: &str) -> Vec<OwnedNbtFragment> {
+    let mut fsm = NbtFsm::new().with_data(data);
+    let mut fragments = Vec::new();
+    loop {
+        match fsm.next_fragment() {
+            Ok(FsmResult::Found(fragment)) => fragments.push(fragment.into_owned()),
+            Ok(FsmResult::Needs(_)) if fsm.is_idle() => return merge_frames(fragments),
+            Ok(FsmResult::Needs(_)) => panic!("the {what} ends early"),
+            Err(err) => panic!("failed to parse the {what}: {err}"),
+        }
+    }
+}
+
+fn first_difference(left: &[OwnedNbtFragment], right: &[OwnedNbtFragment]) -> Option<usize> {
+    let index = left
+        .iter()
+        .zip(right)
+        .position(|(left, right)| !same(left, right));
+    index.or((left.len() != right.len()).then(|| left.len().min(right.len())))
+}
+
+/// Equality with floats compared by their bits
+fn same(left: &OwnedNbtFragment, right: &OwnedNbtFragment) -> bool {
+    use OwnedNbtFragment as F;
+    match (left, right) {
+        (F::Float(left), F::Float(right)) => left.to_bits() == right.to_bits(),
+        (F::Double(left), F::Double(right)) => left.to_bits() == right.to_bits(),
+        (F::FloatListFrame(left), F::FloatListFrame(right)) => left
+            .iter()
+            .map(|value| value.to_bits())
+            .eq(right.iter().map(|value| value.to_bits())),
+        (F::DoubleListFrame(left), F::DoubleListFrame(right)) => left
+            .iter()
+            .map(|value| value.to_bits())
+            .eq(right.iter().map(|value| value.to_bits())),
+        (left, right) => left == right,
+    }
+}
+
+fn canonical(
+    fragments: Vec<OwnedNbtFragment>,
+    canonicalize: Canonicalize,
+) -> Vec<OwnedNbtFragment> {
+    let mut fragments: Vec<_> = fragments
+        .into_iter()
+        .map(|fragment| match fragment {
+            OwnedNbtFragment::NameFrame(data) if canonicalize.strings => {
+                OwnedNbtFragment::NameFrame(reencode(data))
+            }
+            OwnedNbtFragment::StringFrame(data) if canonicalize.strings => {
+                OwnedNbtFragment::StringFrame(reencode(data))
+            }
+            fragment => fragment,
+        })
+        .collect();
+    if canonicalize.entry_order {
+        let mut rest = fragments.as_slice();
+        let mut sorted = Vec::with_capacity(fragments.len());
+        sort_entry(&mut rest, &mut sorted);
+        sorted.extend_from_slice(rest);
+        fragments = sorted;
+    }
+    fragments
+}
+
+fn reencode(data: Vec<u8>) -> Vec<u8> {
+    match mutf8::decode(&data) {
+        Some(text) => mutf8::encode(&text).into_owned(),
+        None => data,
+    }
+}
+
+fn take<'f>(fragments: &mut &'f [OwnedNbtFragment]) -> Option<&'f OwnedNbtFragment> {
+    let (first, rest) = fragments.split_first()?;
+    *fragments = rest;
+    Some(first)
+}
+
+/// Copies the frames of a name, string or byte array up to and including the empty one ending it
+fn copy_data(fragments: &mut &[OwnedNbtFragment], out: &mut Vec<OwnedNbtFragment>) {
+    use OwnedNbtFragment as F;
+    while let Some(fragment) = take(fragments) {
+        out.push(fragment.clone());
+        if let F::NameFrame(data) | F::StringFrame(data) | F::ByteArrayFrame(data) = fragment
+            && data.is_empty()
+        {
+            return;
+        }
+    }
+}
+
+/// Copies a named entry, with the entries of compounds sorted by name
+fn sort_entry(fragments: &mut &[OwnedNbtFragment], out: &mut Vec<OwnedNbtFragment>) {
+    match fragments.first() {
+        // Compounds announce themselves before their name
+        Some(OwnedNbtFragment::CompoundTag) => {
+            out.push(OwnedNbtFragment::CompoundTag);
+            take(fragments);
+            copy_data(fragments, out);
+            sort_entries(fragments, out);
+        }
+        Some(_) => {
+            copy_data(fragments, out);
+            sort_value(fragments, out);
+        }
+        None => {}
+    }
+}
+
+fn sort_entries(fragments: &mut &[OwnedNbtFragment], out: &mut Vec<OwnedNbtFragment>) {
+    let mut entries = Vec::new();
+    loop {
+        match fragments.first() {
+            Some(OwnedNbtFragment::End) | None => break,
+            Some(_) => {
+                let mut entry = Vec::new();
+                sort_entry(fragments, &mut entry);
+                let name: Vec<u8> = entry
+                    .iter()
+                    .filter_map(|fragment| match fragment {
+                        OwnedNbtFragment::NameFrame(data) => Some(data.as_slice()),
+                        _ => None,
+                    })
+                    .take_while(|data| !data.is_empty())
+                    .flatten()
+                    .copied()
+                    .collect();
+                entries.push((name, entry));
+            }
+        }
+    }
+    entries.sort_by(|(left, _), (right, _)| left.cmp(right));
+    out.extend(entries.into_iter().flat_map(|(_, entry)| entry));
+    out.extend(take(fragments).cloned());
+}
+
+fn sort_value(fragments: &mut &[OwnedNbtFragment], out: &mut Vec<OwnedNbtFragment>) {
+    use OwnedNbtFragment as F;
+    let Some(fragment) = fragments.first() else {
+        return;
+    };
+    match fragment {
+        F::StringFrame(_) | F::ByteArrayFrame(_) => copy_data(fragments, out),
+        F::CompoundTag => {
+            out.extend(take(fragments).cloned());
+            sort_entries(fragments, out);
+        }
+        F::ListTag(tag, len) => {
+            let (tag, len) = (*tag, *len);
+            out.extend(take(fragments).cloned());
+            let numeric = matches!(
+                tag,
+                NbtTag::Byte
+                    | NbtTag::Short
+                    | NbtTag::Int
+                    | NbtTag::Long
+                    | NbtTag::Float
+                    | NbtTag::Double
+            );
+            if numeric {
+                // Merged into a single frame, if there are any elements
+                if len > 0 {
+                    out.extend(take(fragments).cloned());
+                }
+            } else {
+                for _ in 0..len {
+                    sort_value(fragments, out);
+                }
+            }
+        }
+        F::IntArrayTag(len) | F::LongArrayTag(len) => {
+            let len = *len;
+            out.extend(take(fragments).cloned());
+            if len > 0 {
+                out.extend(take(fragments).cloned());
+            }
+        }
+        _ => out.extend(take(fragments).cloned()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::NbtCompound;
+    use alloc::vec;
+
+    #[test]
+    fn assets() {
+        round_trip(include_bytes!("../../assets/bigtest.nbt"));
+        round_trip(include_bytes!("../../assets/chunk_0-0.nbt"));
+    }
+
+    #[test]
+    fn canonicalization() {
+        // Standard UTF-8 is read fine, but written as Modified UTF-8
+        let mut data = vec![8, 0, 0, 0, 4];
+        data.extend_from_slice("🦀".as_bytes());
+        let strings = Canonicalize {
+            strings: true,
+            ..Default::default()
+        };
+        round_trip_with(&data, strings, |name, value| value.to_bytes(name).unwrap());
+
+        let compound: NbtCompound = [("a", 1), ("b", 2)].into_iter().collect();
+        let reversed = |name: &str, value: &NbtValue| {
+            let compound: NbtCompound = value
+                .as_compound()
+                .unwrap()
+                .iter()
+                .rev()
+                .map(|(key, value)| (key, value.clone()))
+                .collect();
+            NbtValue::Compound(compound).to_bytes(name).unwrap()
+        };
+        let entry_order = Canonicalize {
+            entry_order: true,
+            ..Default::default()
+        };
+        round_trip_with(&compound, entry_order, reversed);
+    }
+
+    #[test]
+    #[should_panic(expected = "round trip changed fragment 1")]
+    fn strict_strings() {
+        let mut data = vec![8, 0, 0, 0, 4];
+        data.extend_from_slice("🦀".as_bytes());
+        round_trip(&data);
+    }
+}
@@ -0,0 +1,385 @@
+//! [proptest] strategies producing well-formed NBT documents, along with the fragments a parser
+//! is expected to produce for them
+//!
+//! ```
+//! use proptest::prelude::*;
+//! use zeronbt::{FsmResult, NbtFsm, testing::strategies::document};
+//!
+//! proptest!(|(doc in document())| {
+//!     let mut fsm = NbtFsm::new().with_data(&doc.bytes);
+//!     let mut fragments = Vec::new();
+//!     while let FsmResult::Found(fragment) = fsm.next_fragment().unwrap() {
+//!         fragments.push(fragment.into_owned());
+//!     }
+//!     prop_assert_eq!(fragments, doc.fragments);
+//! });
+//! ```
+//!
+//! Floats are never NaN, so generated values and fragments can be compared with `==`.
+use alloc::{string::String, vec::Vec};
+
+use proptest::{collection::vec, prelude::*};
+
+use crate::{
+    NbtTag, OwnedNbtFragment, mutf8,
+    value::{NbtCompound, NbtList, NbtValue},
+};
+
+/// A generated document, along with everything a parser should produce for it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    pub name: String,
+    pub value: NbtValue,
+    /// The encoded document
+    pub bytes: Vec<u8>,
+    /// The fragments [NbtFsm](crate::NbtFsm) produces when given all of `bytes` at once
+    pub fragments: Vec<OwnedNbtFragment>,
+}
+
+/// Short strings of arbitrary characters, including the NUL and supplementary characters that
+/// MUTF-8 encodes differently from UTF-8
+pub fn string() -> impl Strategy<Value = String> + Clone {
+    vec(any::<char>(), 0..16).prop_map(String::from_iter)
+}
+
+fn f32_value() -> impl Strategy<Value = f32> + Clone {
+    use proptest::num::f32::*;
+    POSITIVE | NEGATIVE | NORMAL | SUBNORMAL | ZERO | INFINITE
+}
+
+fn f64_value() -> impl Strategy<Value = f64> + Clone {
+    use proptest::num::f64::*;
+    POSITIVE | NEGATIVE | NORMAL | SUBNORMAL | ZERO | INFINITE
+}
+
+/// Values of the given tag that contain no other tags
+///
+/// # Panics
+/// If `tag` is [NbtTag::End], [NbtTag::List] or [NbtTag::Compound]
+pub fn scalar(tag: NbtTag) -> BoxedStrategy<NbtValue> {
+    match tag {
+        NbtTag::Byte => any::<i8>().prop_map(NbtValue::Byte).boxed(),
+        NbtTag::Short => any::<i16>().prop_map(NbtValue::Short).boxed(),
+        NbtTag::Int => any::<i32>().prop_map(NbtValue::Int).boxed(),
+        NbtTag::Long => any::<i64>().prop_map(NbtValue::Long).boxed(),
+        NbtTag::Float => f32_value().prop_map(NbtValue::Float).boxed(),
+        NbtTag::Double => f64_value().prop_map(NbtValue::Double).boxed(),
+        NbtTag::ByteArray => vec(any::<i8>(), 0..32)
+            .prop_map(NbtValue::ByteArray)
+            .boxed(),
+        NbtTag::String => string().prop_map(NbtValue::String).boxed(),
+        NbtTag::IntArray => vec(any::<i32>(), 0..16)
+            .prop_map(NbtValue::IntArray)
+            .boxed(),
+        NbtTag::LongArray => vec(any::<i64>(), 0..16)
+            .prop_map(NbtValue::LongArray)
+            .boxed(),
+        NbtTag::End | NbtTag::List | NbtTag::Compound => {
+            panic!("{tag:?} is not a scalar tag")
+        }
+    }
+}
+
+const SCALAR_TAGS: [NbtTag; 10] = [
+    NbtTag::Byte,
+    NbtTag::Short,
+    NbtTag::Int,
+    NbtTag::Long,
+    NbtTag::Float,
+    NbtTag::Double,
+    NbtTag::ByteArray,
+    NbtTag::String,
+    NbtTag::IntArray,
+    NbtTag::LongArray,
+];
+
+/// Scalars of any tag, and lists of them
+pub fn leaf() -> impl Strategy<Value = NbtValue> + Clone {
+    proptest::sample::select(&SCALAR_TAGS[..])
+        .prop_flat_map(|tag| {
+            prop_oneof![
+                3 => scalar(tag),
+                1 => vec(scalar(tag), 0..8).prop_map(move |values| {
+                    let mut list = NbtList::with_tag(tag);
+                    for value in values {
+                        list.push(value).expect("all values share a tag");
+                    }
+                    NbtValue::List(list)
+                }),
+            ]
+        })
+        .boxed()
+}
+
+/// Values of any tag, nested up to `depth` lists and compounds deep, with `branch` elements per
+/// container in expectation
+pub fn value_with(depth: u32, branch: u32) -> impl Strategy<Value = NbtValue> + Clone {
+    let size = branch.saturating_pow(depth).min(256);
+    leaf().prop_recursive(depth, size, branch, move |inner| {
+        let len = 0..branch as usize * 2;
+        prop_oneof![
+            vec(inner.clone(), len.clone()).prop_map(|values| {
+                let mut list = NbtList::new();
+                // Drop elements that do not match the first one, lists are homogeneous
+                for value in values {
+                    let _ = list.push(value);
+                }
+                NbtValue::List(list)
+            }),
+            vec((string(), inner), len)
+                .prop_map(|entries| NbtValue::Compound(entries.into_iter().collect())),
+        ]
+    })
+}
+
+pub fn value() -> impl Strategy<Value = NbtValue> + Clone {
+    value_with(4, 4)
+}
+
+pub fn compound() -> impl Strategy<Value = NbtCompound> + Clone {
+    vec((string(), value()), 0..8).prop_map(|entries| entries.into_iter().collect())
+}
+
+/// Documents with a compound root, as used by all files and most network packets
+pub fn document() -> impl Strategy<Value = Document> {
+    document_of(compound().prop_map(NbtValue::Compound))
+}
+
+/// Documents with roots produced by `root`, which may be of any tag
+pub fn document_of(root: impl Strategy<Value = NbtValue>) -> impl Strategy<Value = Document> {
+    (string(), root).prop_map(|(name, value)| {
+        let bytes = value
+            .to_bytes(&name)
+            .expect("generated values fit the limits of NBT");
+        let fragments = fragments(&name, &value);
+        Document {
+            name,
+            value,
+            bytes,
+            fragments,
+        }
+    })
+}
+
+/// The fragments [NbtFsm](crate::NbtFsm) produces for `value` as a root tag named `name`, when
+/// given the whole document at once
+///
+/// When the input arrives in pieces, strings, names, byte arrays and numeric lists may be split
+/// over several frames, see [merge_frames](super::merge_frames) for comparing those.
+pub fn fragments(name: &str, value: &NbtValue) -> Vec<OwnedNbtFragment> {
+    let mut out = Vec::new();
+    push_entry(name, value, &mut out);
+    out
+}
+
+fn push_data(data: &[u8], frame: fn(Vec<u8>) -> OwnedNbtFragment, out: &mut Vec<OwnedNbtFragment>) {
+    if !data.is_empty() {
+        out.push(frame(data.to_vec()));
+    }
+    out.push(frame(Vec::new()));
+}
+
+/// Compounds announce themselves before their name, every other tag follows it
+fn push_entry(name: &str, value: &NbtValue, out: &mut Vec<OwnedNbtFragment>) {
+    let name = mutf8::encode(name);
+    match value {
+        NbtValue::Compound(compound) => {
+            out.push(OwnedNbtFragment::CompoundTag);
+            push_data(&name, OwnedNbtFragment::NameFrame, out);
+            push_entries(compound, out);
+        }
+        value => {
+            push_data(&name, OwnedNbtFragment::NameFrame, out);
+            push_value(value, out);
+        }
+    }
+}
+
+fn push_entries(compound: &NbtCompound, out: &mut Vec<OwnedNbtFragment>) {
+    for (name, value) in compound.iter() {
+        push_entry(name, value, out);
+    }
+    out.push(OwnedNbtFragment::End);
+}
+
+fn push_value(value: &NbtValue, out: &mut Vec<OwnedNbtFragment>) {
+    use OwnedNbtFragment as F;
+    match value {
+        NbtValue::Byte(value) => out.push(F::Byte(*value)),
+        NbtValue::Short(value) => out.push(F::Short(*value)),
+        NbtValue::Int(value) => out.push(F::Int(*value)),
+        NbtValue::Long(value) => out.push(F::Long(*value)),
+        NbtValue::Float(value) => out.push(F::Float(*value)),
+        NbtValue::Double(value) => out.push(F::Double(*value)),
+        NbtValue::ByteArray(values) => {
+            let data: Vec<u8> = values.iter().map(|&value| value as u8).collect();
+            push_data(&data, F::ByteArrayFrame, out);
+        }
+        NbtValue::String(value) => push_data(&mutf8::encode(value), F::StringFrame, out),
+        NbtValue::List(list) => {
+            out.push(F::ListTag(list.tag(), list.len()));
+            if list.is_empty() {
+                return;
+            }
+            fn numbers<T>(list: &NbtList, map: fn(&NbtValue) -> Option<T>) -> Vec<T> {
+                list.iter().filter_map(map).collect()
+            }
+            match list.tag() {
+                NbtTag::Byte => out.push(F::ByteListFrame(numbers(list, NbtValue::as_byte))),
+                NbtTag::Short => out.push(F::ShortListFrame(numbers(list, NbtValue::as_short))),
+                NbtTag::Int => out.push(F::IntListFrame(numbers(list, NbtValue::as_int))),
+                NbtTag::Long => out.push(F::LongListFrame(numbers(list, NbtValue::as_long))),
+                NbtTag::Float => out.push(F::FloatListFrame(numbers(list, NbtValue::as_float))),
+                NbtTag::Double => out.push(F::DoubleListFrame(numbers(list, NbtValue::as_double))),
+                _ => list.iter().for_each(|value| push_value(value, out)),
+            }
+        }
+        NbtValue::Compound(compound) => {
+            out.push(F::CompoundTag);
+            push_entries(compound, out);
+        }
+        NbtValue::IntArray(values) => {
+            out.push(F::IntArrayTag(values.len()));
+            if !values.is_empty() {
+                out.push(F::IntListFrame(values.clone()));
+            }
+        }
+        NbtValue::LongArray(values) => {
+            out.push(F::LongArrayTag(values.len()));
+            if !values.is_empty() {
+                out.push(F::LongListFrame(values.clone()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CompleteFsm, FsmResult, NbtFsm, testing::merge_frames};
+
+    fn parse(data: &[u8], step: usize) -> Vec<OwnedNbtFragment> {
+        let mut fragments = Vec::new();
+        let mut fsm = NbtFsm::new();
+        let mut start = 0;
+        let mut end = 0;
+        loop {
+            let mut current = fsm.with_data(&data[start..end]);
+            while let FsmResult::Found(fragment) = current.next_fragment().unwrap() {
+                fragments.push(fragment.into_owned());
+            }
+            start += current.consumed();
+            fsm = current.with_data(&[]);
+            if end == data.len() {
+                return fragments;
+            }
+            end = (end + step).min(data.len());
+        }
+    }
+
+    /// Runs every parser over `data`, which only ever may return errors
+    fn parse_untrusted(data: &[u8], step: usize) {
+        for fsm in [
+            NbtFsm::new(),
+            NbtFsm::new_nameless(),
+            NbtFsm::new().with_min_frame(step),
+        ] {
+            let mut fsm = fsm;
+            let mut start = 0;
+            let mut end = 0;
+            loop {
+                let mut current = fsm.with_data(&data[start..end]);
+                while let Ok(FsmResult::Found(_)) = current.next_fragment() {}
+                start += current.consumed();
+                fsm = current.with_data(&[]);
+                if end == data.len() {
+                    break;
+                }
+                end = (end + step).min(data.len());
+            }
+        }
+        CompleteFsm::new(data)
+            .take_while(Result::is_ok)
+            .for_each(drop);
+        let _ = NbtValue::read(data);
+    }
+
+    /// A document of compounds and lists that each hold the next one, a compound for every
+    /// `true`, with a byte in the innermost one
+    fn nested(compounds: &[bool]) -> Vec<u8> {
+        let tag = |level: usize| match compounds.get(level) {
+            Some(true) => NbtTag::Compound as u8,
+            Some(false) => NbtTag::List as u8,
+            None => NbtTag::Byte as u8,
+        };
+        let mut data = alloc::vec![tag(0), 0, 0];
+        for (level, &compound) in compounds.iter().enumerate() {
+            match compound {
+                true => data.extend([tag(level + 1), 0, 0]),
+                false => data.extend([tag(level + 1), 0, 0, 0, 1]),
+            }
+        }
+        data.push(1);
+        for &compound in compounds.iter().rev() {
+            if compound {
+                data.push(0);
+            }
+        }
+        data
+    }
+
+    proptest! {
+        #[test]
+        fn nesting_is_limited(
+            compounds in vec(any::<bool>(), 0..1024),
+            max_depth in prop_oneof![0..8usize, 500..600usize],
+        ) {
+            use crate::{NbtOptions, error::NbtParseError};
+            let data = nested(&compounds);
+            let options = NbtOptions::new().max_depth(max_depth);
+            let expected = match compounds.len() > max_depth {
+                true => Err(NbtParseError::TooDeep(max_depth)),
+                false => Ok(()),
+            };
+            let mut fsm = NbtFsm::new().with_options(options).with_data(&data);
+            let streaming = loop {
+                match fsm.next_fragment() {
+                    Ok(FsmResult::Found(_)) => {}
+                    Ok(FsmResult::Needs(_)) => break Ok(()),
+                    Err(err) => break Err(err),
+                }
+            };
+            prop_assert_eq!(&streaming, &expected);
+            prop_assert!(expected.is_err() || fsm.is_idle());
+            let fsm = NbtFsm::new().with_options(options);
+            let complete = CompleteFsm::with_fsm(fsm, &data).find_map(Result::err);
+            prop_assert_eq!(complete, expected.err());
+        }
+
+        #[test]
+        fn untrusted_input_never_panics(data in vec(any::<u8>(), 0..512), step in 1..32usize) {
+            parse_untrusted(&data, step);
+        }
+
+        #[test]
+        fn corrupted_documents_never_panic(
+            doc in document_of(value()),
+            corruptions in vec((any::<prop::sample::Index>(), any::<u8>()), 1..8),
+            step in 1..32usize,
+        ) {
+            let mut data = doc.bytes;
+            for (index, byte) in corruptions {
+                let index = index.index(data.len());
+                data[index] = byte;
+            }
+            parse_untrusted(&data, step);
+        }
+
+        #[test]
+        fn fragments_match_parser(doc in document_of(value()), step in 1..64usize) {
+            prop_assert_eq!(&parse(&doc.bytes, doc.bytes.len()), &doc.fragments);
+            prop_assert_eq!(merge_frames(parse(&doc.bytes, step)), doc.fragments);
+            prop_assert_eq!(NbtValue::read(&doc.bytes).unwrap(), (doc.name, doc.value));
+        }
+    }
+}
@@ -0,0 +1,388 @@
+//! Turns fragment streams into the nested events text formats are written from
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
+use crate::{
+    NbtFragment, NbtTag,
+    mutf8::{self, StringPolicy},
+    view::BeSlice,
+};
+
+/// A number, either a tag of its own or an element of a list or array
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Number {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+}
+
+impl Number {
+    pub(crate) fn tag(self) -> NbtTag {
+        match self {
+            Number::Byte(_) => NbtTag::Byte,
+            Number::Short(_) => NbtTag::Short,
+            Number::Int(_) => NbtTag::Int,
+            Number::Long(_) => NbtTag::Long,
+            Number::Float(_) => NbtTag::Float,
+            Number::Double(_) => NbtTag::Double,
+        }
+    }
+
+    /// Appends the number in the layout of list frames
+    pub(crate) fn write_be(self, buf: &mut Vec<u8>) {
+        match self {
+            Number::Byte(value) => buf.push(value as u8),
+            Number::Short(value) => buf.extend_from_slice(&value.to_be_bytes()),
+            Number::Int(value) => buf.extend_from_slice(&value.to_be_bytes()),
+            Number::Long(value) => buf.extend_from_slice(&value.to_be_bytes()),
+            Number::Float(value) => buf.extend_from_slice(&value.to_be_bytes()),
+            Number::Double(value) => buf.extend_from_slice(&value.to_be_bytes()),
+        }
+    }
+
+    pub(crate) fn into_fragment(self) -> NbtFragment<'static> {
+        match self {
+            Number::Byte(value) => NbtFragment::Byte(value),
+            Number::Short(value) => NbtFragment::Short(value),
+            Number::Int(value) => NbtFragment::Int(value),
+            Number::Long(value) => NbtFragment::Long(value),
+            Number::Float(value) => NbtFragment::Float(value),
+            Number::Double(value) => NbtFragment::Double(value),
+        }
+    }
+}
+
+/// Produces numbers written with [Number::write_be] as the frame of a list of `tag`
+pub(crate) fn list_frame(tag: NbtTag, buf: &[u8]) -> NbtFragment<'_> {
+    let frame = match tag {
+        NbtTag::Byte => BeSlice::new(buf).map(NbtFragment::ByteListFrame),
+        NbtTag::Short => BeSlice::new(buf).map(NbtFragment::ShortListFrame),
+        NbtTag::Int => BeSlice::new(buf).map(NbtFragment::IntListFrame),
+        NbtTag::Long => BeSlice::new(buf).map(NbtFragment::LongListFrame),
+        NbtTag::Float => BeSlice::new(buf).map(NbtFragment::FloatListFrame),
+        _ => BeSlice::new(buf).map(NbtFragment::DoubleListFrame),
+    };
+    frame.expect("the buffer holds whole elements")
+}
+
+/// The kinds of sequences, lists are further described by the tag of their elements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Seq {
+    List(NbtTag),
+    ByteArray,
+    IntArray,
+    LongArray,
+}
+
+/// Receives the structure of a document, with names and strings decoded and lists delimited
+pub(crate) trait Emit {
+    fn begin_compound(&mut self) -> fmt::Result;
+    /// The name of the next compound entry
+    fn key(&mut self, key: &str) -> fmt::Result;
+    fn end_compound(&mut self) -> fmt::Result;
+    fn begin_seq(&mut self, seq: Seq) -> fmt::Result;
+    fn end_seq(&mut self) -> fmt::Result;
+    fn number(&mut self, number: Number) -> fmt::Result;
+    fn string(&mut self, string: &str) -> fmt::Result;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Open {
+    Compound,
+    /// A list or an int or long array, along with the number of elements still to come
+    Counted(usize),
+    /// Byte arrays are not announced by a fragment of their own and end with an empty frame
+    ByteArray,
+}
+
+/// Tracks where in the document the fragments pushed to it are
+#[derive(Debug, Clone)]
+pub(crate) struct Structure {
+    stack: Vec<Open>,
+    /// The frames of the name or string being read
+    text: Vec<u8>,
+    /// Compounds are announced before their name, which has to be emitted first
+    compound_pending: bool,
+    done: bool,
+    /// Invalid strings fail the push with [fmt::Error] when strict
+    pub(crate) strings: StringPolicy,
+}
+
+impl Default for Structure {
+    fn default() -> Self {
+        Self {
+            stack: Vec::new(),
+            text: Vec::new(),
+            compound_pending: false,
+            done: false,
+            strings: StringPolicy::Lossy,
+        }
+    }
+}
+
+impl Structure {
+    /// Pushes the next fragment of the document, returning true once the root tag is complete
+    pub(crate) fn push(
+        &mut self,
+        fragment: NbtFragment<'_>,
+        emit: &mut impl Emit,
+    ) -> Result<bool, fmt::Error> {
+        if self.done {
+            *self = Self {
+                strings: self.strings,
+                ..Self::default()
+            };
+        }
+        match fragment {
+            NbtFragment::NameFrame(data) if !data.is_empty() => self.text.extend_from_slice(data),
+            NbtFragment::NameFrame(_) => {
+                // The root name is not part of the structure
+                if !self.stack.is_empty() {
+                    emit.key(&self.strings.decode_name(&self.text).ok_or(fmt::Error)?)?;
+                }
+                self.text.clear();
+                if self.compound_pending {
+                    self.compound_pending = false;
+                    emit.begin_compound()?;
+                    self.stack.push(Open::Compound);
+                }
+            }
+            NbtFragment::CompoundTag => match self.stack.last() {
+                Some(Open::Counted(_)) => {
+                    emit.begin_compound()?;
+                    self.stack.push(Open::Compound);
+                }
+                _ => self.compound_pending = true,
+            },
+            // An End tag at the root is an empty document, which is not written
+            NbtFragment::End => {
+                if self.stack.pop().is_some() {
+                    emit.end_compound()?;
+                    self.complete(emit)?;
+                }
+            }
+            NbtFragment::Byte(value) => self.number(Number::Byte(value), emit)?,
+            NbtFragment::Short(value) => self.number(Number::Short(value), emit)?,
+            NbtFragment::Int(value) => self.number(Number::Int(value), emit)?,
+            NbtFragment::Long(value) => self.number(Number::Long(value), emit)?,
+            NbtFragment::Float(value) => self.number(Number::Float(value), emit)?,
+            NbtFragment::Double(value) => self.number(Number::Double(value), emit)?,
+            NbtFragment::StringFrame(data) if !data.is_empty() => self.text.extend_from_slice(data),
+            NbtFragment::StringFrame(_) => {
+                match mutf8::decode(&self.text) {
+                    Some(string) => emit.string(&string)?,
+                    None => self.invalid_string(emit)?,
+                }
+                self.text.clear();
+                self.complete(emit)?;
+            }
+            NbtFragment::ByteArrayFrame(data) => {
+                if self.stack.last() != Some(&Open::ByteArray) {
+                    emit.begin_seq(Seq::ByteArray)?;
+                    self.stack.push(Open::ByteArray);
+                }
+                if data.is_empty() {
+                    self.stack.pop();
+                    emit.end_seq()?;
+                    self.complete(emit)?;
+                }
+                for &byte in data {
+                    emit.number(Number::Byte(byte as i8))?;
+                }
+            }
+            NbtFragment::ListTag(tag, len) => self.begin_counted(Seq::List(tag), len, emit)?,
+            NbtFragment::IntArrayTag(len) => self.begin_counted(Seq::IntArray, len, emit)?,
+            NbtFragment::LongArrayTag(len) => self.begin_counted(Seq::LongArray, len, emit)?,
+            NbtFragment::ByteListFrame(values) => {
+                self.elements(values.iter().map(Number::Byte), emit)?
+            }
+            NbtFragment::ShortListFrame(values) => {
+                self.elements(values.iter().map(Number::Short), emit)?
+            }
+            NbtFragment::IntListFrame(values) => {
+                self.elements(values.iter().map(Number::Int), emit)?
+            }
+            NbtFragment::LongListFrame(values) => {
+                self.elements(values.iter().map(Number::Long), emit)?
+            }
+            NbtFragment::FloatListFrame(values) => {
+                self.elements(values.iter().map(Number::Float), emit)?
+            }
+            NbtFragment::DoubleListFrame(values) => {
+                self.elements(values.iter().map(Number::Double), emit)?
+            }
+        }
+        Ok(self.done)
+    }
+
+    fn number(&mut self, number: Number, emit: &mut impl Emit) -> fmt::Result {
+        emit.number(number)?;
+        self.complete(emit)
+    }
+
+    fn invalid_string(&self, emit: &mut impl Emit) -> fmt::Result {
+        match self.strings {
+            StringPolicy::Strict => Err(fmt::Error),
+            StringPolicy::Lossy => emit.string(&String::from_utf8_lossy(&self.text)),
+            StringPolicy::Raw => {
+                emit.begin_seq(Seq::ByteArray)?;
+                for &byte in &self.text {
+                    emit.number(Number::Byte(byte as i8))?;
+                }
+                emit.end_seq()
+            }
+        }
+    }
+
+    fn begin_counted(&mut self, seq: Seq, len: usize, emit: &mut impl Emit) -> fmt::Result {
+        emit.begin_seq(seq)?;
+        self.stack.push(Open::Counted(len));
+        if len == 0 {
+            self.stack.pop();
+            emit.end_seq()?;
+            self.complete(emit)?;
+        }
+        Ok(())
+    }
+
+    /// Numeric elements of a list or array, which do not complete a value each
+    fn elements(
+        &mut self,
+        values: impl ExactSizeIterator<Item = Number>,
+        emit: &mut impl Emit,
+    ) -> fmt::Result {
+        let len = values.len();
+        for value in values {
+            emit.number(value)?;
+        }
+        if let Some(Open::Counted(remaining)) = self.stack.last_mut() {
+            *remaining = remaining.saturating_sub(len);
+            if *remaining == 0 {
+                self.stack.pop();
+                emit.end_seq()?;
+                self.complete(emit)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Called after a value ended, closing the lists it completes
+    fn complete(&mut self, emit: &mut impl Emit) -> fmt::Result {
+        loop {
+            match self.stack.last_mut() {
+                Some(Open::Counted(remaining)) => {
+                    *remaining -= 1;
+                    if *remaining != 0 {
+                        return Ok(());
+                    }
+                    self.stack.pop();
+                    emit.end_seq()?;
+                }
+                Some(_) => return Ok(()),
+                None => {
+                    self.done = true;
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Level {
+    first: bool,
+    multiline: bool,
+    /// Entries of compounds are separated before their key rather than their value
+    keyed: bool,
+    /// Brackets with a prefix, like the `[B;` of SNBT arrays, are followed by a space
+    prefixed: bool,
+}
+
+/// Separators, line breaks and indentation of bracketed text formats
+#[derive(Debug, Clone)]
+pub(crate) struct Layout {
+    pub(crate) pretty: bool,
+    indent: &'static str,
+    levels: Vec<Level>,
+}
+
+impl Layout {
+    pub(crate) const fn new(indent: &'static str) -> Self {
+        Self {
+            pretty: false,
+            indent,
+            levels: Vec::new(),
+        }
+    }
+
+    /// Writes the opening bracket of a compound or sequence, which is only broken into lines
+    /// when pretty printing
+    pub(crate) fn open(
+        &mut self,
+        out: &mut impl fmt::Write,
+        bracket: &str,
+        multiline: bool,
+        keyed: bool,
+    ) -> fmt::Result {
+        out.write_str(bracket)?;
+        self.levels.push(Level {
+            first: true,
+            multiline: multiline && self.pretty,
+            keyed,
+            prefixed: bracket.len() > 1,
+        });
+        Ok(())
+    }
+
+    pub(crate) fn close(&mut self, out: &mut impl fmt::Write, bracket: &str) -> fmt::Result {
+        if let Some(level) = self.levels.pop()
+            && level.multiline
+            && !level.first
+        {
+            self.newline(out)?;
+        }
+        out.write_str(bracket)
+    }
+
+    /// Called before every key
+    pub(crate) fn key(&mut self, out: &mut impl fmt::Write) -> fmt::Result {
+        self.separate(out)
+    }
+
+    /// Called before every value, separating it from the previous one unless it follows a key
+    pub(crate) fn value(&mut self, out: &mut impl fmt::Write) -> fmt::Result {
+        match self.levels.last() {
+            Some(level) if !level.keyed => self.separate(out),
+            _ => Ok(()),
+        }
+    }
+
+    fn separate(&mut self, out: &mut impl fmt::Write) -> fmt::Result {
+        let pretty = self.pretty;
+        let Some(level) = self.levels.last_mut() else {
+            return Ok(());
+        };
+        let first = core::mem::replace(&mut level.first, false);
+        if !first {
+            out.write_char(',')?;
+        }
+        if level.multiline {
+            self.newline(out)
+        } else if (!first || level.prefixed) && pretty {
+            out.write_char(' ')
+        } else {
+            Ok(())
+        }
+    }
+
+    fn newline(&self, out: &mut impl fmt::Write) -> fmt::Result {
+        out.write_char('\n')?;
+        for _ in 0..self.levels.len() {
+            out.write_str(self.indent)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,179 @@
+//! Converting documents between [Dialect]s in a single pass, e.g. from Java to Bedrock Edition
+//!
+//! Numbers and lengths are re-encoded by the [NbtWriter] of the target dialect. Java encodes names
+//! and strings as Modified UTF-8 and Bedrock as standard UTF-8, so a [Transcoder] collects their
+//! frames and re-encodes them once complete, when the two dialects differ in that.
+//!
+//! ```
+//! # use zeronbt::{Bedrock, Java, transcode::transcode};
+//! let data = include_bytes!("../assets/bigtest.nbt");
+//! let bedrock = transcode(Java, Bedrock, data).unwrap();
+//! assert_eq!(&bedrock[3..8], b"Level");
+//! assert_eq!(transcode(Bedrock, Java, &bedrock).unwrap(), data);
+//! ```
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::marker::PhantomData;
+
+use crate::{
+    CompleteFsm, Dialect, NbtFragment, NbtFsm, NbtWriter,
+    error::{NbtParseError, TranscodeError},
+    mutf8::{self, StringPolicy},
+};
+
+/// Writes the fragments of documents in the dialect `F` as the dialect `T`, see the
+/// [module docs](self)
+#[derive(Debug, Clone)]
+pub struct Transcoder<F: Dialect, T: Dialect> {
+    writer: NbtWriter<T>,
+    /// The frames of the name or string being re-encoded
+    text: Vec<u8>,
+    strings: StringPolicy,
+    _from: PhantomData<F>,
+}
+
+impl<F: Dialect, T: Dialect> Transcoder<F, T> {
+    pub fn new(_from: F, to: T) -> Self {
+        Self {
+            writer: NbtWriter::with_dialect(to),
+            text: Vec::new(),
+            strings: StringPolicy::Strict,
+            _from: PhantomData,
+        }
+    }
+
+    /// How names and strings that are not valid in the source dialect are handled, failing by
+    /// default
+    ///
+    /// Raw transcoders copy the bytes of invalid strings as they are, and replace invalid names
+    /// lossily.
+    pub fn string_policy(mut self, policy: StringPolicy) -> Self {
+        self.strings = policy;
+        self
+    }
+
+    /// Writes the next fragment, returning true once the root tag is complete
+    pub fn push(&mut self, fragment: NbtFragment<'_>) -> Result<bool, TranscodeError> {
+        if F::MODIFIED_UTF8 == T::MODIFIED_UTF8 {
+            return Ok(self.writer.push(fragment)?);
+        }
+        match fragment {
+            NbtFragment::NameFrame(data) | NbtFragment::StringFrame(data) if !data.is_empty() => {
+                self.text.extend_from_slice(data);
+                Ok(false)
+            }
+            NbtFragment::NameFrame(_) => {
+                let name = self
+                    .strings
+                    .decode_name(&self.text)
+                    .ok_or(NbtParseError::InvalidString)?;
+                let name = encode::<T>(&name).into_owned();
+                self.text.clear();
+                self.frames(&name, |data| NbtFragment::NameFrame(data))
+            }
+            NbtFragment::StringFrame(_) => {
+                let decoded =
+                    mutf8::decode(&self.text).map(|string| encode::<T>(&string).into_owned());
+                let string = match (decoded, self.strings) {
+                    (Some(string), _) => string,
+                    (None, StringPolicy::Strict) => {
+                        return Err(NbtParseError::InvalidString.into());
+                    }
+                    (None, StringPolicy::Lossy) => {
+                        encode::<T>(&String::from_utf8_lossy(&self.text)).into_owned()
+                    }
+                    (None, StringPolicy::Raw) => core::mem::take(&mut self.text),
+                };
+                self.text.clear();
+                self.frames(&string, |data| NbtFragment::StringFrame(data))
+            }
+            fragment => Ok(self.writer.push(fragment)?),
+        }
+    }
+
+    /// Writes a complete name or string as a frame followed by the empty one ending it
+    fn frames(
+        &mut self,
+        data: &[u8],
+        frame: fn(&[u8]) -> NbtFragment<'_>,
+    ) -> Result<bool, TranscodeError> {
+        if !data.is_empty() {
+            self.writer.push(frame(data))?;
+        }
+        Ok(self.writer.push(frame(&[]))?)
+    }
+
+    pub fn get_ref(&self) -> &Vec<u8> {
+        self.writer.get_ref()
+    }
+
+    /// The output written so far, see [NbtWriter::get_mut]
+    pub fn get_mut(&mut self) -> &mut Vec<u8> {
+        self.writer.get_mut()
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.writer.into_inner()
+    }
+}
+
+/// Converts every document in `data` from the dialect `from` to the dialect `to`
+pub fn transcode<F: Dialect, T: Dialect>(
+    from: F,
+    to: T,
+    data: &[u8],
+) -> Result<Vec<u8>, TranscodeError> {
+    let mut transcoder = Transcoder::new(from, to);
+    for fragment in CompleteFsm::with_fsm(NbtFsm::with_dialect(from), data) {
+        transcoder.push(fragment?)?;
+    }
+    Ok(transcoder.into_inner())
+}
+
+/// Encodes a name or string the way the dialect `T` stores them
+fn encode<T: Dialect>(str: &str) -> Cow<'_, [u8]> {
+    match T::MODIFIED_UTF8 {
+        true => mutf8::encode(str),
+        false => Cow::Borrowed(str.as_bytes()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Bedrock, BedrockNetwork, Java, JavaNetwork, value::NbtValue};
+
+    #[test]
+    fn strings() {
+        let string = "nul \0 and emoji \u{1F600}";
+        let value = NbtValue::Compound([(string, string)].into_iter().collect());
+        let java = value.to_bytes("").unwrap();
+        let mutf8 = mutf8::encode(string);
+        assert!(java.windows(mutf8.len()).any(|window| *window == *mutf8));
+
+        let bedrock = transcode(Java, Bedrock, &java).unwrap();
+        assert!(!bedrock.windows(mutf8.len()).any(|window| *window == *mutf8));
+        let utf8 = string.as_bytes();
+        let found = bedrock.windows(utf8.len()).filter(|window| *window == utf8);
+        assert_eq!(found.count(), 2);
+        assert_eq!(transcode(Bedrock, Java, &bedrock).unwrap(), java);
+
+        let network = transcode(Java, BedrockNetwork, &java).unwrap();
+        let back = transcode(BedrockNetwork, JavaNetwork, &network).unwrap();
+        assert_eq!(back, transcode(Java, JavaNetwork, &java).unwrap());
+    }
+
+    #[test]
+    fn invalid_strings() {
+        // A compound holding the string "\xFF" under the name "s"
+        let data = b"\x0a\0\0\x08\0\x01s\0\x01\xff\0";
+        assert_eq!(
+            transcode(Java, Bedrock, data),
+            Err(TranscodeError::Parse(NbtParseError::InvalidString))
+        );
+        let mut transcoder = Transcoder::new(Java, Bedrock).string_policy(StringPolicy::Raw);
+        for fragment in CompleteFsm::new(data) {
+            transcoder.push(fragment.unwrap()).unwrap();
+        }
+        assert_eq!(transcoder.into_inner(), b"\x0a\0\0\x08\x01\0s\x01\0\xff\0");
+    }
+}
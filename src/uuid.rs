@@ -0,0 +1,107 @@
+//! UUIDs of entities and players, stored in NBT as an int array of 4 elements
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{
+    convert::{FromNbt, NbtArray, ToNbt},
+    error::NbtConvertError,
+    value::NbtValue,
+};
+
+/// A UUID, formatted as `069a79f4-44e9-4726-a5be-fca90e38aaf5`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Uuid(pub u128);
+
+impl Uuid {
+    /// Creates the UUID from its ints, most significant first
+    pub const fn from_ints(ints: [i32; 4]) -> Self {
+        let mut uuid = 0;
+        let mut i = 0;
+        while i < 4 {
+            uuid = uuid << 32 | ints[i] as u32 as u128;
+            i += 1;
+        }
+        Self(uuid)
+    }
+
+    pub const fn to_ints(self) -> [i32; 4] {
+        [
+            (self.0 >> 96) as i32,
+            (self.0 >> 64) as i32,
+            (self.0 >> 32) as i32,
+            self.0 as i32,
+        ]
+    }
+
+    /// Parses a UUID in the hyphenated form, which is also used for the names of player files
+    pub fn parse(str: &str) -> Option<Self> {
+        let mut uuid = 0;
+        let mut digits = 0;
+        for (index, char) in str.char_indices() {
+            if matches!(index, 8 | 13 | 18 | 23) {
+                if char != '-' {
+                    return None;
+                }
+                continue;
+            }
+            uuid = uuid << 4 | char.to_digit(16)? as u128;
+            digits += 1;
+        }
+        (digits == 32 && str.len() == 36).then_some(Self(uuid))
+    }
+}
+
+impl fmt::Display for Uuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hex = self.0;
+        write!(
+            f,
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            hex >> 96,
+            (hex >> 80) & 0xFFFF,
+            (hex >> 64) & 0xFFFF,
+            (hex >> 48) & 0xFFFF,
+            hex & 0xFFFF_FFFF_FFFF,
+        )
+    }
+}
+
+impl ToNbt for Uuid {
+    fn to_nbt(&self) -> NbtValue {
+        i32::to_nbt_array(&self.to_ints())
+    }
+}
+
+impl FromNbt for Uuid {
+    fn from_nbt(value: &NbtValue) -> Result<Self, NbtConvertError> {
+        let ints = i32::from_nbt_array(value)?;
+        let ints: [i32; 4] =
+            ints.try_into()
+                .map_err(|ints: Vec<i32>| NbtConvertError::WrongLength {
+                    expected: 4,
+                    found: ints.len(),
+                })?;
+        Ok(Self::from_ints(ints))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn formats() {
+        let uuid = Uuid::parse("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap();
+        assert_eq!(uuid.to_string(), "069a79f4-44e9-4726-a5be-fca90e38aaf5");
+        assert_eq!(uuid.to_ints()[0], 0x069a79f4);
+        assert_eq!(uuid.to_ints()[2], 0xa5befca9_u32 as i32);
+        assert_eq!(Uuid::from_ints(uuid.to_ints()), uuid);
+        assert_eq!(Uuid::from_nbt(&uuid.to_nbt()), Ok(uuid));
+
+        assert_eq!(Uuid::parse("069a79f444e94726a5befca90e38aaf5"), None);
+        assert_eq!(Uuid::parse("069a79f4-44e9-4726-a5be-fca90e38aaf"), None);
+        assert_eq!(Uuid::parse("069a79f4-44e9-4726-a5be-fca90e38aaf5a"), None);
+        assert_eq!(Uuid::parse("069a79f4-44e9-4726-a5be-fca90e38aag5"), None);
+    }
+}
@@ -0,0 +1,1505 @@
+//! An owned tree representation of NBT documents
+use alloc::{boxed::Box, collections::BTreeMap, string::String, vec::Vec};
+#[cfg(target_has_atomic = "ptr")]
+use alloc::{collections::BTreeSet, sync::Arc};
+use core::{
+    fmt, mem,
+    ops::{Deref, DerefMut},
+};
+
+use crate::{
+    FsmResult, NbtFragment, NbtFsm, NbtTag,
+    error::*,
+    mutf8::{self, StringPolicy},
+};
+
+mod canonical;
+pub use canonical::Normalization;
+#[cfg(feature = "rayon")]
+mod par;
+#[cfg(feature = "serde")]
+mod serde;
+mod write;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NbtValue {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    List(NbtList),
+    Compound(NbtCompound),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+macro_rules! impl_accessors {
+    ($($variant:ident, $method:ident, $t:ty);* $(;)?) => {
+        $(
+            #[inline]
+            pub fn $method(&self) -> Option<$t> {
+                match self {
+                    NbtValue::$variant(val) => Some(*val),
+                    _ => None,
+                }
+            }
+        )*
+    };
+    (ref $($variant:ident, $method:ident, $t:ty);* $(;)?) => {
+        $(
+            #[inline]
+            pub fn $method(&self) -> Option<&$t> {
+                match self {
+                    NbtValue::$variant(val) => Some(val),
+                    _ => None,
+                }
+            }
+        )*
+    };
+}
+
+impl NbtValue {
+    /// Reads a complete named tag from `data`, returning its name and value
+    pub fn read(data: &[u8]) -> NbtResult<(String, NbtValue)> {
+        let mut fsm = NbtFsm::new().with_data(data);
+        let mut builder = NbtValueBuilder::new();
+        loop {
+            match fsm.next_fragment()? {
+                FsmResult::Needs(_) => return Err(NbtParseError::UnexpectedEnd),
+                FsmResult::Found(fragment) => {
+                    if let Some(root) = builder.push(fragment)? {
+                        return Ok(root);
+                    }
+                }
+            }
+        }
+    }
+
+    pub const fn tag(&self) -> NbtTag {
+        match self {
+            NbtValue::Byte(_) => NbtTag::Byte,
+            NbtValue::Short(_) => NbtTag::Short,
+            NbtValue::Int(_) => NbtTag::Int,
+            NbtValue::Long(_) => NbtTag::Long,
+            NbtValue::Float(_) => NbtTag::Float,
+            NbtValue::Double(_) => NbtTag::Double,
+            NbtValue::ByteArray(_) => NbtTag::ByteArray,
+            NbtValue::String(_) => NbtTag::String,
+            NbtValue::List(_) => NbtTag::List,
+            NbtValue::Compound(_) => NbtTag::Compound,
+            NbtValue::IntArray(_) => NbtTag::IntArray,
+            NbtValue::LongArray(_) => NbtTag::LongArray,
+        }
+    }
+
+    /// Looks up `key` if this value is a compound
+    #[inline]
+    pub fn get(&self, key: &str) -> Option<&NbtValue> {
+        self.as_compound()?.get(key)
+    }
+
+    impl_accessors!(
+        Byte, as_byte, i8;
+        Short, as_short, i16;
+        Int, as_int, i32;
+        Long, as_long, i64;
+        Float, as_float, f32;
+        Double, as_double, f64;
+    );
+    /// A byte or short as an i16, the integer types narrower than it are widened
+    pub fn to_i16(&self) -> Option<i16> {
+        match *self {
+            NbtValue::Byte(value) => Some(value.into()),
+            NbtValue::Short(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// A byte, short or int as an i32, see [to_i64](NbtValue::to_i64)
+    pub fn to_i32(&self) -> Option<i32> {
+        match *self {
+            NbtValue::Int(value) => Some(value),
+            _ => self.to_i16().map(i32::from),
+        }
+    }
+
+    /// Any integer as an i64, as versions of the game disagree about the width of some keys
+    pub fn to_i64(&self) -> Option<i64> {
+        match *self {
+            NbtValue::Long(value) => Some(value),
+            _ => self.to_i32().map(i64::from),
+        }
+    }
+
+    /// A float or double as an f64
+    pub fn to_f64(&self) -> Option<f64> {
+        match *self {
+            NbtValue::Float(value) => Some(value.into()),
+            NbtValue::Double(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Looks up `key` if this value is a compound, see [NbtCompound::get_i16]
+    pub fn get_i16(&self, key: &str) -> Option<i16> {
+        self.as_compound()?.get_i16(key)
+    }
+
+    /// Looks up `key` if this value is a compound, see [NbtCompound::get_i32]
+    pub fn get_i32(&self, key: &str) -> Option<i32> {
+        self.as_compound()?.get_i32(key)
+    }
+
+    /// Looks up `key` if this value is a compound, see [NbtCompound::get_i64]
+    pub fn get_i64(&self, key: &str) -> Option<i64> {
+        self.as_compound()?.get_i64(key)
+    }
+
+    /// Looks up `key` if this value is a compound, see [NbtCompound::get_f64]
+    pub fn get_f64(&self, key: &str) -> Option<f64> {
+        self.as_compound()?.get_f64(key)
+    }
+
+    impl_accessors!(ref
+        ByteArray, as_byte_array, [i8];
+        String, as_str, str;
+        List, as_list, NbtList;
+        Compound, as_compound, NbtCompound;
+        IntArray, as_int_array, [i32];
+        LongArray, as_long_array, [i64];
+    );
+}
+
+macro_rules! impl_from {
+    ($($t:ty => $variant:ident),* $(,)?) => {
+        $(impl From<$t> for NbtValue {
+            #[inline]
+            fn from(value: $t) -> Self {
+                NbtValue::$variant(value.into())
+            }
+        })*
+    };
+}
+impl_from!(
+    i8 => Byte,
+    i16 => Short,
+    i32 => Int,
+    i64 => Long,
+    f32 => Float,
+    f64 => Double,
+    Vec<i8> => ByteArray,
+    String => String,
+    &str => String,
+    NbtList => List,
+    NbtCompound => Compound,
+    Vec<i32> => IntArray,
+    Vec<i64> => LongArray,
+);
+
+/// A list of values which all share the same tag
+#[derive(Debug, Clone, PartialEq)]
+pub struct NbtList {
+    tag: NbtTag,
+    values: Vec<NbtValue>,
+}
+
+impl Default for NbtList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NbtList {
+    pub const fn new() -> Self {
+        Self::with_tag(NbtTag::End)
+    }
+    pub const fn with_tag(tag: NbtTag) -> Self {
+        Self {
+            tag,
+            values: Vec::new(),
+        }
+    }
+    /// The tag of the elements of this list
+    pub const fn tag(&self) -> NbtTag {
+        self.tag
+    }
+    /// Appends a value to the list, returning it back if its tag does not match the list's.
+    ///
+    /// An empty list takes on the tag of the first value pushed to it.
+    pub fn push(&mut self, value: NbtValue) -> Result<(), NbtValue> {
+        if self.values.is_empty() {
+            self.tag = value.tag();
+        } else if self.tag != value.tag() {
+            return Err(value);
+        }
+        self.values.push(value);
+        Ok(())
+    }
+    pub fn into_values(mut self) -> Vec<NbtValue> {
+        mem::take(&mut self.values)
+    }
+}
+
+impl Drop for NbtList {
+    fn drop(&mut self) {
+        if self.values.iter().any(is_nested) {
+            drop_nested(mem::take(&mut self.values));
+        }
+    }
+}
+
+/// Whether dropping the value drops other lists or compounds
+fn is_nested(value: &NbtValue) -> bool {
+    match value {
+        NbtValue::List(list) => !list.values.is_empty(),
+        NbtValue::Compound(compound) => !compound.entries.is_empty(),
+        _ => false,
+    }
+}
+
+/// Drops nested lists and compounds one level at a time, as dropping them recursively overflows
+/// the stack on deeply nested values
+fn drop_nested(mut pending: Vec<NbtValue>) {
+    while let Some(mut value) = pending.pop() {
+        match &mut value {
+            NbtValue::List(list) => pending.append(&mut list.values),
+            NbtValue::Compound(compound) => {
+                pending.extend(compound.entries.drain(..).map(|(_, value)| value));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Deref for NbtList {
+    type Target = [NbtValue];
+
+    fn deref(&self) -> &Self::Target {
+        &self.values
+    }
+}
+
+impl TryFrom<Vec<NbtValue>> for NbtList {
+    type Error = NbtValue;
+
+    /// Fails with the first value whose tag does not match the first element's
+    fn try_from(values: Vec<NbtValue>) -> Result<Self, Self::Error> {
+        let mut list = NbtList::new();
+        list.values.reserve_exact(values.len());
+        for value in values {
+            list.push(value)?;
+        }
+        Ok(list)
+    }
+}
+
+impl IntoIterator for NbtList {
+    type Item = NbtValue;
+    type IntoIter = alloc::vec::IntoIter<NbtValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_values().into_iter()
+    }
+}
+
+impl<'l> IntoIterator for &'l NbtList {
+    type Item = &'l NbtValue;
+    type IntoIter = core::slice::Iter<'l, NbtValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.iter()
+    }
+}
+
+/// The entries of a compound, kept in the order they were inserted
+///
+/// Small compounds look keys up by scanning their entries, larger ones keep an index of the keys
+/// so that building a compound with many entries is not quadratic.
+#[derive(Clone, Default)]
+pub struct NbtCompound {
+    entries: Entries,
+}
+
+/// The number of entries past which a compound indexes its keys
+const INDEX_THRESHOLD: usize = 16;
+
+/// The entries of a compound, boxed together with the index of their keys once there are
+/// [INDEX_THRESHOLD] of them, which keeps small compounds as small as a Vec
+#[derive(Clone)]
+enum Entries {
+    Scanned(Vec<(Key, NbtValue)>),
+    Indexed(Box<Indexed>),
+}
+
+#[derive(Clone)]
+struct Indexed {
+    entries: Vec<(Key, NbtValue)>,
+    /// The position of each key in `entries`
+    index: BTreeMap<Key, usize>,
+}
+
+impl Default for Entries {
+    fn default() -> Self {
+        Entries::Scanned(Vec::new())
+    }
+}
+
+impl Deref for Entries {
+    type Target = Vec<(Key, NbtValue)>;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Entries::Scanned(entries) => entries,
+            Entries::Indexed(indexed) => &indexed.entries,
+        }
+    }
+}
+
+// Reordering the entries through this requires a reindex
+impl DerefMut for Entries {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            Entries::Scanned(entries) => entries,
+            Entries::Indexed(indexed) => &mut indexed.entries,
+        }
+    }
+}
+
+impl Drop for NbtCompound {
+    fn drop(&mut self) {
+        if self.values().any(is_nested) {
+            let entries = mem::take(&mut *self.entries);
+            drop_nested(entries.into_iter().map(|(_, value)| value).collect());
+        }
+    }
+}
+
+impl PartialEq for NbtCompound {
+    fn eq(&self, other: &Self) -> bool {
+        *self.entries == *other.entries
+    }
+}
+
+impl fmt::Debug for NbtCompound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NbtCompound")
+            .field("entries", &*self.entries)
+            .finish()
+    }
+}
+
+/// The longest key stored inline, which covers most keys found in the wild
+const INLINE_KEY: usize = 22;
+
+/// The key of a compound entry, which may share its allocation with other keys
+#[derive(Clone)]
+enum Key {
+    Inline {
+        len: u8,
+        bytes: [u8; INLINE_KEY],
+    },
+    Owned(String),
+    #[cfg(target_has_atomic = "ptr")]
+    Shared(Arc<str>),
+}
+
+impl Deref for Key {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        match self {
+            // SAFETY: inline keys are copied from a str
+            Key::Inline { len, bytes } => unsafe {
+                core::str::from_utf8_unchecked(bytes.get_unchecked(..*len as usize))
+            },
+            Key::Owned(key) => key,
+            #[cfg(target_has_atomic = "ptr")]
+            Key::Shared(key) => key,
+        }
+    }
+}
+
+impl Key {
+    /// Stores a short key without allocating
+    fn inline(key: &str) -> Option<Self> {
+        let mut bytes = [0; INLINE_KEY];
+        bytes.get_mut(..key.len())?.copy_from_slice(key.as_bytes());
+        Some(Key::Inline {
+            len: key.len() as u8,
+            bytes,
+        })
+    }
+}
+
+impl PartialEq for Key {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for Key {}
+
+impl PartialOrd for Key {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Key {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl core::borrow::Borrow<str> for Key {
+    fn borrow(&self) -> &str {
+        self
+    }
+}
+
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl From<Key> for String {
+    fn from(key: Key) -> Self {
+        match key {
+            Key::Inline { .. } => String::from(&*key),
+            Key::Owned(key) => key,
+            #[cfg(target_has_atomic = "ptr")]
+            Key::Shared(key) => String::from(&*key),
+        }
+    }
+}
+
+impl NbtCompound {
+    pub const fn new() -> Self {
+        Self {
+            entries: Entries::Scanned(Vec::new()),
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    #[inline]
+    fn position(&self, key: &str) -> Option<usize> {
+        match &self.entries {
+            Entries::Scanned(entries) => entries.iter().position(|(name, _)| **name == *key),
+            Entries::Indexed(indexed) => indexed.index.get(key).copied(),
+        }
+    }
+    #[inline]
+    pub fn get(&self, key: &str) -> Option<&NbtValue> {
+        let idx = self.position(key)?;
+        Some(&self.entries[idx].1)
+    }
+    /// The integer stored under `key` as an i16, widening bytes
+    pub fn get_i16(&self, key: &str) -> Option<i16> {
+        self.get(key)?.to_i16()
+    }
+    /// The integer stored under `key` as an i32, widening bytes and shorts
+    pub fn get_i32(&self, key: &str) -> Option<i32> {
+        self.get(key)?.to_i32()
+    }
+    /// The integer stored under `key` as an i64, widening bytes, shorts and ints
+    ///
+    /// ```
+    /// # use zeronbt::value::{NbtCompound, NbtValue};
+    /// let mut compound = NbtCompound::new();
+    /// compound.insert("xPos", NbtValue::Int(-3));
+    /// assert_eq!(compound.get_i64("xPos"), Some(-3));
+    /// assert_eq!(compound.get_i16("xPos"), None);
+    /// ```
+    pub fn get_i64(&self, key: &str) -> Option<i64> {
+        self.get(key)?.to_i64()
+    }
+    /// The float or double stored under `key` as an f64
+    pub fn get_f64(&self, key: &str) -> Option<f64> {
+        self.get(key)?.to_f64()
+    }
+    #[inline]
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut NbtValue> {
+        let idx = self.position(key)?;
+        Some(&mut self.entries[idx].1)
+    }
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+    /// Inserts an entry, replacing and returning the previous value stored under `key`
+    pub fn insert(
+        &mut self,
+        key: impl Into<String>,
+        value: impl Into<NbtValue>,
+    ) -> Option<NbtValue> {
+        self.insert_key(Key::Owned(key.into()), value.into())
+    }
+    fn insert_key(&mut self, key: Key, value: NbtValue) -> Option<NbtValue> {
+        if let Some(old) = self.get_mut(&key) {
+            return Some(mem::replace(old, value));
+        }
+        self.push(key, value);
+        None
+    }
+    /// Appends an entry whose key is not in the compound yet
+    fn push(&mut self, key: Key, value: NbtValue) {
+        match &mut self.entries {
+            Entries::Scanned(entries) => {
+                entries.push((key, value));
+                if entries.len() >= INDEX_THRESHOLD {
+                    self.reindex();
+                }
+            }
+            Entries::Indexed(indexed) => {
+                indexed.index.insert(key.clone(), indexed.entries.len());
+                indexed.entries.push((key, value));
+            }
+        }
+    }
+    /// Takes entries whose keys are all different
+    fn from_entries(entries: Vec<(Key, NbtValue)>) -> Self {
+        let mut compound = Self {
+            entries: Entries::Scanned(entries),
+        };
+        compound.reindex();
+        compound
+    }
+    /// Rebuilds the index after the entries were reordered
+    fn reindex(&mut self) {
+        let entries = mem::take(&mut *self.entries);
+        if entries.len() < INDEX_THRESHOLD {
+            self.entries = Entries::Scanned(entries);
+            return;
+        }
+        let index = entries
+            .iter()
+            .enumerate()
+            .map(|(idx, (key, _))| (key.clone(), idx))
+            .collect();
+        self.entries = Entries::Indexed(Box::new(Indexed { entries, index }));
+    }
+    pub fn remove(&mut self, key: &str) -> Option<NbtValue> {
+        let idx = self.position(key)?;
+        if let Entries::Indexed(indexed) = &mut self.entries {
+            indexed.index.remove(key);
+            for position in indexed.index.values_mut() {
+                if *position > idx {
+                    *position -= 1;
+                }
+            }
+        }
+        Some(self.entries.remove(idx).1)
+    }
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&str, &NbtValue)> + ExactSizeIterator {
+        self.entries.iter().map(|(name, value)| (&**name, value))
+    }
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &str> + ExactSizeIterator {
+        self.entries.iter().map(|(name, _)| &**name)
+    }
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &NbtValue> + ExactSizeIterator {
+        self.entries.iter().map(|(_, value)| value)
+    }
+}
+
+impl<K: Into<String>, V: Into<NbtValue>> FromIterator<(K, V)> for NbtCompound {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        let mut compound = NbtCompound::new();
+        for (key, value) in iter {
+            compound.insert(key, value);
+        }
+        compound
+    }
+}
+
+impl IntoIterator for NbtCompound {
+    type Item = (String, NbtValue);
+    type IntoIter = CompoundIntoIter;
+
+    fn into_iter(mut self) -> Self::IntoIter {
+        CompoundIntoIter(mem::take(&mut *self.entries).into_iter())
+    }
+}
+
+/// The entries of an [NbtCompound], taken by value
+#[derive(Debug, Clone)]
+pub struct CompoundIntoIter(alloc::vec::IntoIter<(Key, NbtValue)>);
+
+impl Iterator for CompoundIntoIter {
+    type Item = (String, NbtValue);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, value)| (key.into(), value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for CompoundIntoIter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(key, value)| (key.into(), value))
+    }
+}
+
+impl ExactSizeIterator for CompoundIntoIter {}
+
+/// Hands out shared copies of names, so that the keys of every document built with the same
+/// interner share one allocation per distinct name
+///
+/// Chunks repeat the same few dozen keys over and over, interning them saves most of the memory
+/// their keys would take otherwise. Keys are compared as strings, so an interned compound is
+/// equal to one built without interning.
+///
+/// ```
+/// # use zeronbt::value::{NameInterner, NbtValue, NbtValueBuilder};
+/// let data = include_bytes!("../assets/bigtest.nbt");
+/// let mut builder = NbtValueBuilder::new().with_interner(NameInterner::new());
+/// let mut fsm = zeronbt::CompleteFsm::new(data);
+/// let (_, root) = loop {
+///     if let Some(root) = builder.push(fsm.next().unwrap().unwrap()).unwrap() {
+///         break root;
+///     }
+/// };
+/// assert_eq!(root, NbtValue::read(data).unwrap().1);
+/// assert_eq!(builder.interner().unwrap().len(), 17);
+/// ```
+#[cfg(target_has_atomic = "ptr")]
+#[derive(Debug, Clone, Default)]
+pub struct NameInterner {
+    names: BTreeSet<Arc<str>>,
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl NameInterner {
+    pub const fn new() -> Self {
+        Self {
+            names: BTreeSet::new(),
+        }
+    }
+
+    /// Returns the shared copy of `name`, adding it if it has not been seen before
+    pub fn intern(&mut self, name: &str) -> Arc<str> {
+        if let Some(name) = self.names.get(name) {
+            return name.clone();
+        }
+        let name: Arc<str> = Arc::from(name);
+        self.names.insert(name.clone());
+        name
+    }
+
+    /// The number of distinct names
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Forgets every name, names already handed out stay valid
+    pub fn clear(&mut self) {
+        self.names.clear();
+    }
+}
+
+/// The most elements reserved up front for a list or array, as the declared length comes from the
+/// input and may be far larger than the data that follows
+const MAX_PREALLOCATED: usize = 1024;
+
+/// Assembles [NbtValue]s from the fragments produced by [NbtFsm]
+///
+/// The builder holds no reference to the input, so it can be fed fragments from any refill loop.
+#[derive(Debug, Clone, Default)]
+pub struct NbtValueBuilder {
+    stack: Vec<Partial>,
+    /// The entries of the small compounds being built, each compound taking those after its
+    /// `start`, so they are allocated once at their final size
+    entries: Vec<(Key, NbtValue)>,
+    name: Vec<u8>,
+    pending_name: Option<Key>,
+    strings: StringPolicy,
+    duplicates: DuplicateKeys,
+    #[cfg(target_has_atomic = "ptr")]
+    interner: Option<NameInterner>,
+}
+
+/// What [NbtValueBuilder] does with keys that occur more than once in a compound
+///
+/// The game keeps the last value, as do most tools, though some keep the first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum DuplicateKeys {
+    KeepFirst,
+    /// Replace the earlier value, keeping the position of the key
+    #[default]
+    KeepLast,
+    /// Turn the values into a list, in the order they occur, which fails if their tags differ
+    Collect,
+    /// Fail with [NbtParseError::DuplicateKey]
+    Error,
+}
+
+#[derive(Debug, Clone)]
+enum Partial {
+    Compound {
+        name: Option<Key>,
+        awaiting_name: bool,
+        /// Where the entries start in [NbtValueBuilder::entries], until there are too many to
+        /// look keys up by scanning them and they are moved to `compound`
+        start: Option<usize>,
+        compound: NbtCompound,
+        /// Keys whose values were collected into a list
+        collected: Vec<Key>,
+    },
+    List {
+        name: Option<Key>,
+        list: NbtList,
+        remaining: usize,
+    },
+    IntArray {
+        name: Option<Key>,
+        values: Vec<i32>,
+        remaining: usize,
+    },
+    LongArray {
+        name: Option<Key>,
+        values: Vec<i64>,
+        remaining: usize,
+    },
+    ByteArray {
+        name: Option<Key>,
+        data: Vec<i8>,
+    },
+    String {
+        name: Option<Key>,
+        data: Vec<u8>,
+    },
+}
+
+impl NbtValueBuilder {
+    pub const fn new() -> Self {
+        Self {
+            stack: Vec::new(),
+            entries: Vec::new(),
+            name: Vec::new(),
+            pending_name: None,
+            strings: StringPolicy::Strict,
+            duplicates: DuplicateKeys::KeepLast,
+            #[cfg(target_has_atomic = "ptr")]
+            interner: None,
+        }
+    }
+
+    /// What to do with keys that occur more than once in a compound, see [DuplicateKeys]
+    pub fn with_duplicate_keys(mut self, policy: DuplicateKeys) -> Self {
+        self.duplicates = policy;
+        self
+    }
+
+    /// How names and strings that are not valid (Modified) UTF-8 are handled, strictly by default
+    pub fn with_string_policy(mut self, policy: StringPolicy) -> Self {
+        self.strings = policy;
+        self
+    }
+
+    /// Shares the keys of compounds through `interner`, which is kept across documents
+    #[cfg(target_has_atomic = "ptr")]
+    pub fn with_interner(mut self, interner: NameInterner) -> Self {
+        self.interner = Some(interner);
+        self
+    }
+
+    #[cfg(target_has_atomic = "ptr")]
+    pub fn interner(&self) -> Option<&NameInterner> {
+        self.interner.as_ref()
+    }
+
+    /// Returns the interner, e.g. to keep using it with another builder
+    #[cfg(target_has_atomic = "ptr")]
+    pub fn take_interner(&mut self) -> Option<NameInterner> {
+        self.interner.take()
+    }
+
+    /// Feeds the next fragment to the builder, returning the name and value of the root tag once
+    /// it is complete
+    pub fn push(&mut self, fragment: NbtFragment<'_>) -> NbtResult<Option<(String, NbtValue)>> {
+        match fragment {
+            NbtFragment::End => match self.stack.pop() {
+                Some(Partial::Compound {
+                    name,
+                    start,
+                    mut compound,
+                    ..
+                }) => {
+                    if let Some(start) = start {
+                        compound = NbtCompound::from_entries(self.entries.drain(start..).collect());
+                    }
+                    self.complete(name, NbtValue::Compound(compound))
+                }
+                // An End tag at the root is an empty document
+                None => Ok(None),
+                Some(_) => Err(NbtParseError::UnexpectedFragment),
+            },
+            NbtFragment::CompoundTag => {
+                let awaiting_name = !matches!(self.stack.last(), Some(Partial::List { .. }));
+                self.stack.push(Partial::Compound {
+                    name: None,
+                    awaiting_name,
+                    start: Some(self.entries.len()),
+                    compound: NbtCompound::new(),
+                    collected: Vec::new(),
+                });
+                Ok(None)
+            }
+            NbtFragment::NameFrame(data) if !data.is_empty() => {
+                self.name.extend_from_slice(data);
+                Ok(None)
+            }
+            NbtFragment::NameFrame(_) => {
+                let name = self.take_name()?;
+                match self.stack.last_mut() {
+                    Some(Partial::Compound {
+                        name: own_name,
+                        awaiting_name: awaiting_name @ true,
+                        ..
+                    }) => {
+                        *awaiting_name = false;
+                        *own_name = Some(name);
+                    }
+                    _ => self.pending_name = Some(name),
+                }
+                Ok(None)
+            }
+            NbtFragment::Byte(val) => self.complete_value(NbtValue::Byte(val)),
+            NbtFragment::Short(val) => self.complete_value(NbtValue::Short(val)),
+            NbtFragment::Int(val) => self.complete_value(NbtValue::Int(val)),
+            NbtFragment::Long(val) => self.complete_value(NbtValue::Long(val)),
+            NbtFragment::Float(val) => self.complete_value(NbtValue::Float(val)),
+            NbtFragment::Double(val) => self.complete_value(NbtValue::Double(val)),
+            NbtFragment::ListTag(tag, len) => {
+                let name = self.pending_name.take();
+                if len == 0 || tag == NbtTag::End {
+                    return self.complete(name, NbtValue::List(NbtList::with_tag(tag)));
+                }
+                let mut list = NbtList::with_tag(tag);
+                list.values.reserve(len.min(MAX_PREALLOCATED));
+                self.stack.push(Partial::List {
+                    name,
+                    list,
+                    remaining: len,
+                });
+                Ok(None)
+            }
+            NbtFragment::IntArrayTag(len) => {
+                let name = self.pending_name.take();
+                if len == 0 {
+                    return self.complete(name, NbtValue::IntArray(Vec::new()));
+                }
+                self.stack.push(Partial::IntArray {
+                    name,
+                    values: Vec::with_capacity(len.min(MAX_PREALLOCATED)),
+                    remaining: len,
+                });
+                Ok(None)
+            }
+            NbtFragment::LongArrayTag(len) => {
+                let name = self.pending_name.take();
+                if len == 0 {
+                    return self.complete(name, NbtValue::LongArray(Vec::new()));
+                }
+                self.stack.push(Partial::LongArray {
+                    name,
+                    values: Vec::with_capacity(len.min(MAX_PREALLOCATED)),
+                    remaining: len,
+                });
+                Ok(None)
+            }
+            NbtFragment::ByteListFrame(view) => {
+                self.extend_list(view.len(), view.iter().map(NbtValue::Byte))
+            }
+            NbtFragment::ShortListFrame(view) => {
+                self.extend_list(view.len(), view.iter().map(NbtValue::Short))
+            }
+            NbtFragment::IntListFrame(view) => match self.stack.last_mut() {
+                Some(Partial::IntArray {
+                    values, remaining, ..
+                }) => {
+                    *remaining = remaining
+                        .checked_sub(view.len())
+                        .ok_or(NbtParseError::UnexpectedFragment)?;
+                    values.extend(view.iter());
+                    self.complete_array()
+                }
+                _ => self.extend_list(view.len(), view.iter().map(NbtValue::Int)),
+            },
+            NbtFragment::LongListFrame(view) => match self.stack.last_mut() {
+                Some(Partial::LongArray {
+                    values, remaining, ..
+                }) => {
+                    *remaining = remaining
+                        .checked_sub(view.len())
+                        .ok_or(NbtParseError::UnexpectedFragment)?;
+                    values.extend(view.iter());
+                    self.complete_array()
+                }
+                _ => self.extend_list(view.len(), view.iter().map(NbtValue::Long)),
+            },
+            NbtFragment::FloatListFrame(view) => {
+                self.extend_list(view.len(), view.iter().map(NbtValue::Float))
+            }
+            NbtFragment::DoubleListFrame(view) => {
+                self.extend_list(view.len(), view.iter().map(NbtValue::Double))
+            }
+            NbtFragment::ByteArrayFrame(data) => match self.stack.last_mut() {
+                Some(Partial::ByteArray { data: buf, .. }) if !data.is_empty() => {
+                    buf.extend(data.iter().map(|&byte| byte as i8));
+                    Ok(None)
+                }
+                Some(Partial::ByteArray { .. }) => {
+                    let Some(Partial::ByteArray { name, data }) = self.stack.pop() else {
+                        return Err(NbtParseError::UnexpectedFragment);
+                    };
+                    self.complete(name, NbtValue::ByteArray(data))
+                }
+                _ if data.is_empty() => self.complete_value(NbtValue::ByteArray(Vec::new())),
+                _ => {
+                    let name = self.pending_name.take();
+                    let data = data.iter().map(|&byte| byte as i8).collect();
+                    self.stack.push(Partial::ByteArray { name, data });
+                    Ok(None)
+                }
+            },
+            NbtFragment::StringFrame(data) => match self.stack.last_mut() {
+                Some(Partial::String { data: buf, .. }) if !data.is_empty() => {
+                    buf.extend_from_slice(data);
+                    Ok(None)
+                }
+                Some(Partial::String { .. }) => {
+                    let Some(Partial::String { name, data }) = self.stack.pop() else {
+                        return Err(NbtParseError::UnexpectedFragment);
+                    };
+                    let value = match (decode_string(data), self.strings) {
+                        (Ok(string), _) => NbtValue::String(string),
+                        (Err(_), StringPolicy::Strict) => {
+                            return Err(NbtParseError::InvalidString);
+                        }
+                        (Err(data), StringPolicy::Lossy) => {
+                            NbtValue::String(String::from_utf8_lossy(&data).into_owned())
+                        }
+                        (Err(data), StringPolicy::Raw) => {
+                            NbtValue::ByteArray(data.into_iter().map(|byte| byte as i8).collect())
+                        }
+                    };
+                    self.complete(name, value)
+                }
+                _ if data.is_empty() => self.complete_value(NbtValue::String(String::new())),
+                _ => {
+                    let name = self.pending_name.take();
+                    self.stack.push(Partial::String {
+                        name,
+                        data: data.to_vec(),
+                    });
+                    Ok(None)
+                }
+            },
+        }
+    }
+
+    /// Decodes the name that was read, reusing the buffer for the next one when interning
+    fn take_name(&mut self) -> NbtResult<Key> {
+        #[cfg(target_has_atomic = "ptr")]
+        if let Some(interner) = &mut self.interner {
+            let name = self
+                .strings
+                .decode_name(&self.name)
+                .ok_or(NbtParseError::InvalidString)?;
+            let name = interner.intern(&name);
+            self.name.clear();
+            return Ok(Key::Shared(name));
+        }
+        // Short names are copied out, so the buffer is kept for the next one
+        if let Some(key) = core::str::from_utf8(&self.name).ok().and_then(Key::inline) {
+            self.name.clear();
+            return Ok(key);
+        }
+        match decode_string(mem::take(&mut self.name)) {
+            Ok(name) => Ok(Key::Owned(name)),
+            Err(name) => self
+                .strings
+                .decode_name(&name)
+                .map(|name| Key::Owned(name.into_owned()))
+                .ok_or(NbtParseError::InvalidString),
+        }
+    }
+
+    #[inline]
+    fn complete_value(&mut self, value: NbtValue) -> NbtResult<Option<(String, NbtValue)>> {
+        let name = self.pending_name.take();
+        self.complete(name, value)
+    }
+
+    fn extend_list(
+        &mut self,
+        len: usize,
+        values: impl Iterator<Item = NbtValue>,
+    ) -> NbtResult<Option<(String, NbtValue)>> {
+        let Some(Partial::List {
+            list, remaining, ..
+        }) = self.stack.last_mut()
+        else {
+            return Err(NbtParseError::UnexpectedFragment);
+        };
+        *remaining = remaining
+            .checked_sub(len)
+            .ok_or(NbtParseError::UnexpectedFragment)?;
+        for value in values {
+            list.push(value)
+                .map_err(|_| NbtParseError::UnexpectedFragment)?;
+        }
+        if *remaining != 0 {
+            return Ok(None);
+        }
+        let Some(Partial::List { name, list, .. }) = self.stack.pop() else {
+            return Err(NbtParseError::UnexpectedFragment);
+        };
+        self.complete(name, NbtValue::List(list))
+    }
+
+    fn complete_array(&mut self) -> NbtResult<Option<(String, NbtValue)>> {
+        match self.stack.pop() {
+            Some(Partial::IntArray {
+                name,
+                values,
+                remaining: 0,
+            }) => self.complete(name, NbtValue::IntArray(values)),
+            Some(Partial::LongArray {
+                name,
+                values,
+                remaining: 0,
+            }) => self.complete(name, NbtValue::LongArray(values)),
+            Some(partial) => {
+                self.stack.push(partial);
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Stores a finished value in the enclosing container, or returns it if it is the root
+    fn complete(
+        &mut self,
+        mut name: Option<Key>,
+        mut value: NbtValue,
+    ) -> NbtResult<Option<(String, NbtValue)>> {
+        loop {
+            match self.stack.last_mut() {
+                None => return Ok(Some((name.map(String::from).unwrap_or_default(), value))),
+                Some(Partial::Compound {
+                    start,
+                    compound,
+                    collected,
+                    ..
+                }) => {
+                    let name = name.ok_or(NbtParseError::UnexpectedFragment)?;
+                    let old = match *start {
+                        Some(start) => self.entries[start..]
+                            .iter_mut()
+                            .find(|(key, _)| *key == name)
+                            .map(|(_, value)| value),
+                        None => compound.get_mut(&name),
+                    };
+                    let Some(old) = old else {
+                        match *start {
+                            Some(at) if self.entries.len() - at < INDEX_THRESHOLD => {
+                                self.entries.push((name, value));
+                            }
+                            Some(at) => {
+                                let mut entries = Vec::with_capacity(2 * INDEX_THRESHOLD);
+                                entries.extend(self.entries.drain(at..));
+                                *compound = NbtCompound::from_entries(entries);
+                                *start = None;
+                                compound.push(name, value);
+                            }
+                            None => compound.push(name, value),
+                        }
+                        return Ok(None);
+                    };
+                    match self.duplicates {
+                        DuplicateKeys::KeepFirst => {}
+                        DuplicateKeys::KeepLast => *old = value,
+                        DuplicateKeys::Collect => match old {
+                            NbtValue::List(list) if collected.iter().any(|key| **key == *name) => {
+                                list.push(value)
+                                    .map_err(|_| NbtParseError::UnexpectedType)?;
+                            }
+                            _ => {
+                                let first = mem::replace(old, NbtValue::Byte(0));
+                                let list = NbtList::try_from(alloc::vec![first, value])
+                                    .map_err(|_| NbtParseError::UnexpectedType)?;
+                                *old = NbtValue::List(list);
+                                collected.push(name);
+                            }
+                        },
+                        DuplicateKeys::Error => {
+                            return Err(NbtParseError::DuplicateKey(name.into()));
+                        }
+                    }
+                    return Ok(None);
+                }
+                Some(Partial::List {
+                    list, remaining, ..
+                }) => {
+                    if *remaining == 0 {
+                        return Err(NbtParseError::UnexpectedFragment);
+                    }
+                    list.push(value)
+                        .map_err(|_| NbtParseError::UnexpectedFragment)?;
+                    *remaining -= 1;
+                    if *remaining != 0 {
+                        return Ok(None);
+                    }
+                    let Some(Partial::List {
+                        name: list_name,
+                        list,
+                        ..
+                    }) = self.stack.pop()
+                    else {
+                        return Err(NbtParseError::UnexpectedFragment);
+                    };
+                    name = list_name;
+                    value = NbtValue::List(list);
+                }
+                // Dialects without big-endian list frames produce array elements one by one
+                Some(Partial::IntArray {
+                    values, remaining, ..
+                }) => {
+                    let (NbtValue::Int(element), 1..) = (value, *remaining) else {
+                        return Err(NbtParseError::UnexpectedFragment);
+                    };
+                    values.push(element);
+                    *remaining -= 1;
+                    return self.complete_array();
+                }
+                Some(Partial::LongArray {
+                    values, remaining, ..
+                }) => {
+                    let (NbtValue::Long(element), 1..) = (value, *remaining) else {
+                        return Err(NbtParseError::UnexpectedFragment);
+                    };
+                    values.push(element);
+                    *remaining -= 1;
+                    return self.complete_array();
+                }
+                Some(_) => return Err(NbtParseError::UnexpectedFragment),
+            }
+        }
+    }
+}
+
+/// Decodes a name or string, handing its bytes back if they are invalid
+fn decode_string(data: Vec<u8>) -> Result<String, Vec<u8>> {
+    match String::from_utf8(data) {
+        Ok(string) => Ok(string),
+        Err(err) => {
+            let string = mutf8::decode(err.as_bytes()).map(|str| str.into_owned());
+            string.ok_or_else(|| err.into_bytes())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inline_keys() {
+        let short = "a".repeat(INLINE_KEY);
+        let long = "b".repeat(INLINE_KEY + 1);
+        let mut compound = NbtCompound::new();
+        compound.insert(short.as_str(), NbtValue::Byte(1));
+        compound.insert(long.as_str(), NbtValue::Byte(2));
+        compound.insert("\u{e9}", NbtValue::Byte(3));
+        let data = NbtValue::Compound(compound.clone()).to_bytes("").unwrap();
+        let (_, value) = NbtValue::read(&data).unwrap();
+        let read = value.as_compound().unwrap();
+        assert!(matches!(read.entries[0].0, Key::Inline { .. }));
+        assert!(matches!(read.entries[1].0, Key::Owned(_)));
+        assert_eq!(*read, compound);
+        assert!(mem::size_of::<Key>() <= mem::size_of::<String>() + 8);
+    }
+
+    #[test]
+    fn indexed_keys() {
+        let mut compound: NbtCompound = (0..100)
+            .map(|i| (alloc::format!("key{i}"), NbtValue::Int(i)))
+            .collect();
+        assert!(matches!(compound.entries, Entries::Indexed(_)));
+        assert_eq!(mem::size_of::<NbtCompound>(), mem::size_of::<Vec<()>>());
+        assert_eq!(compound.insert("key7", 70), Some(NbtValue::Int(7)));
+        assert_eq!(compound.remove("key3"), Some(NbtValue::Int(3)));
+        assert_eq!(compound.get("key3"), None);
+        assert_eq!(compound.get("key4"), Some(&NbtValue::Int(4)));
+        assert_eq!(compound.get("key7"), Some(&NbtValue::Int(70)));
+        assert_eq!(compound.get("key99"), Some(&NbtValue::Int(99)));
+        assert_eq!(compound.len(), 99);
+
+        let mut value = NbtValue::Compound(compound);
+        value.sort_keys();
+        let compound = value.as_compound().unwrap();
+        assert_eq!(compound.keys().next(), Some("key0"));
+        for (key, value) in compound.iter() {
+            assert_eq!(compound.get(key), Some(value));
+        }
+    }
+
+    #[test]
+    fn interned_keys() {
+        let data = include_bytes!("../assets/chunk_0-0.nbt");
+        let mut builder = NbtValueBuilder::new().with_interner(NameInterner::new());
+        let mut read = || {
+            let mut fsm = NbtFsm::new().with_data(data);
+            loop {
+                let FsmResult::Found(fragment) = fsm.next_fragment().unwrap() else {
+                    panic!("Found the end of a complete document");
+                };
+                if let Some((_, root)) = builder.push(fragment).unwrap() {
+                    return root;
+                }
+            }
+        };
+        let (first, second) = (read(), read());
+        assert_eq!(first, NbtValue::read(data).unwrap().1);
+        let first = first.as_compound().unwrap().keys();
+        let second = second.as_compound().unwrap().keys();
+        for (first, second) in first.zip(second) {
+            assert_eq!(first.as_ptr(), second.as_ptr());
+        }
+    }
+
+    #[test]
+    fn string_policies() {
+        // A compound with the invalid name "k\xFF" holding the invalid string "\xFE"
+        let data = b"\x0a\0\0\x08\0\x02k\xff\0\x01\xfe\0";
+        let read = |policy| {
+            let mut builder = NbtValueBuilder::new().with_string_policy(policy);
+            let mut fsm = NbtFsm::new().with_data(data);
+            loop {
+                let FsmResult::Found(fragment) = fsm.next_fragment()? else {
+                    panic!("Found the end of a complete document");
+                };
+                if let Some((_, root)) = builder.push(fragment)? {
+                    return Ok(root);
+                }
+            }
+        };
+        assert_eq!(
+            read(StringPolicy::Strict),
+            Err(NbtParseError::InvalidString)
+        );
+        let lossy = read(StringPolicy::Lossy).unwrap();
+        assert_eq!(lossy.get("k\u{FFFD}"), Some(&NbtValue::from("\u{FFFD}")));
+        let raw = read(StringPolicy::Raw).unwrap();
+        assert_eq!(
+            raw.get("k\u{FFFD}"),
+            Some(&NbtValue::ByteArray(alloc::vec![-2]))
+        );
+
+        let write = |policy| {
+            let mut writer = crate::snbt::SnbtWriter::new(String::new()).string_policy(policy);
+            let mut fsm = NbtFsm::new().with_data(data);
+            while let FsmResult::Found(fragment) = fsm.next_fragment().unwrap() {
+                writer.push(fragment)?;
+            }
+            Ok(writer.into_inner())
+        };
+        assert_eq!(write(StringPolicy::Strict), Err(fmt::Error));
+        assert_eq!(write(StringPolicy::Raw).unwrap(), "{\"k\u{FFFD}\":[B;-2b]}");
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        // The byte "a" three times, 1, 2 and 3
+        let data = b"\x0a\0\0\x01\0\x01a\x01\x01\0\x01a\x02\x01\0\x01a\x03\0";
+        let read = |policy| {
+            let mut builder = NbtValueBuilder::new().with_duplicate_keys(policy);
+            let mut fsm = NbtFsm::new().with_data(data);
+            loop {
+                let FsmResult::Found(fragment) = fsm.next_fragment()? else {
+                    panic!("Found the end of a complete document");
+                };
+                if let Some((_, root)) = builder.push(fragment)? {
+                    return Ok(root.get("a").cloned());
+                }
+            }
+        };
+        assert_eq!(read(DuplicateKeys::KeepFirst), Ok(Some(NbtValue::Byte(1))));
+        assert_eq!(read(DuplicateKeys::KeepLast), Ok(Some(NbtValue::Byte(3))));
+        let bytes = NbtList::try_from(alloc::vec![1i8.into(), 2i8.into(), 3i8.into()]).unwrap();
+        assert_eq!(
+            read(DuplicateKeys::Collect),
+            Ok(Some(NbtValue::List(bytes)))
+        );
+        assert_eq!(
+            read(DuplicateKeys::Error),
+            Err(NbtParseError::DuplicateKey("a".into()))
+        );
+    }
+
+    #[test]
+    fn read_nested_compounds() {
+        let sized = |len: usize| -> NbtCompound {
+            (0..len)
+                .map(|i| (alloc::format!("key{i}"), NbtValue::Long(i as i64)))
+                .collect()
+        };
+        let mut root = NbtCompound::new();
+        for len in [
+            0,
+            1,
+            INDEX_THRESHOLD - 1,
+            INDEX_THRESHOLD,
+            3 * INDEX_THRESHOLD,
+        ] {
+            let mut outer = sized(len);
+            outer.insert("inner", sized(len));
+            outer.insert("after", 1);
+            root.insert(alloc::format!("len{len}"), outer);
+        }
+        let data = NbtValue::Compound(root.clone()).to_bytes("").unwrap();
+        let (_, value) = NbtValue::read(&data).unwrap();
+        assert_eq!(value, NbtValue::Compound(root));
+
+        // The byte "a" at both ends of a compound that is too large to scan
+        let mut data = alloc::vec![0x0a, 0, 0, 1, 0, 1, b'a', 1];
+        for i in 0..2 * INDEX_THRESHOLD as u8 {
+            data.extend([1, 0, 1, b'A' + i, 0]);
+        }
+        data.extend([1, 0, 1, b'a', 2, 0]);
+        let mut builder = NbtValueBuilder::new().with_duplicate_keys(DuplicateKeys::KeepLast);
+        let mut fsm = NbtFsm::new().with_data(&data);
+        let root = loop {
+            let FsmResult::Found(fragment) = fsm.next_fragment().unwrap() else {
+                panic!("Found the end of a complete document");
+            };
+            if let Some((_, root)) = builder.push(fragment).unwrap() {
+                break root;
+            }
+        };
+        let root = root.as_compound().unwrap();
+        assert_eq!(root.len(), 2 * INDEX_THRESHOLD + 1);
+        assert_eq!(root.get("a"), Some(&NbtValue::Byte(2)));
+        assert_eq!(root.keys().next(), Some("a"));
+    }
+
+    #[test]
+    fn read_bigtest() {
+        let data = include_bytes!("../assets/bigtest.nbt");
+        let (name, root) = NbtValue::read(data).unwrap();
+        assert_eq!(name, "Level");
+        let root = root.as_compound().unwrap();
+        assert_eq!(root.len(), 11);
+        assert_eq!(root.get("longTest"), Some(&NbtValue::Long(i64::MAX)));
+        assert_eq!(root.get("shortTest"), Some(&NbtValue::Short(i16::MAX)));
+        assert_eq!(root.get("intTest"), Some(&NbtValue::Int(i32::MAX)));
+        assert_eq!(root.get("byteTest"), Some(&NbtValue::Byte(i8::MAX)));
+        assert_eq!(
+            root.get("stringTest").and_then(NbtValue::as_str),
+            Some("HELLO WORLD THIS IS A TEST STRING ÅÄÖ!")
+        );
+        let nested = root.get("nested compound test").unwrap();
+        assert_eq!(
+            nested.get("egg").and_then(|egg| egg.get("name")),
+            Some(&NbtValue::from("Eggbert"))
+        );
+        assert_eq!(
+            nested.get("ham").and_then(|ham| ham.get("value")),
+            Some(&NbtValue::Float(0.75))
+        );
+
+        let longs = root
+            .get("listTest (long)")
+            .and_then(NbtValue::as_list)
+            .unwrap();
+        assert_eq!(longs.tag(), NbtTag::Long);
+        assert_eq!(
+            longs
+                .iter()
+                .filter_map(NbtValue::as_long)
+                .collect::<Vec<_>>(),
+            [11, 12, 13, 14, 15]
+        );
+
+        let compounds = root
+            .get("listTest (compound)")
+            .and_then(NbtValue::as_list)
+            .unwrap();
+        assert_eq!(compounds.tag(), NbtTag::Compound);
+        assert_eq!(compounds.len(), 2);
+        for (idx, compound) in compounds.iter().enumerate() {
+            let name = alloc::format!("Compound tag #{idx}");
+            assert_eq!(
+                compound.get("name").and_then(NbtValue::as_str),
+                Some(&*name)
+            );
+            assert_eq!(
+                compound.get("created-on"),
+                Some(&NbtValue::Long(1264099775885))
+            );
+        }
+
+        let bytes = root
+            .get("byteArrayTest (the first 1000 values of (n*n*255+n*7)%100, starting with n=0 (0, 62, 34, 16, 8, ...))")
+            .and_then(NbtValue::as_byte_array)
+            .unwrap();
+        assert_eq!(bytes.len(), 1000);
+        for (n, &byte) in bytes.iter().enumerate() {
+            assert_eq!(byte as usize, (n * n * 255 + n * 7) % 100);
+        }
+    }
+
+    #[test]
+    fn read_nested_lists() {
+        let mut input = alloc::vec![10, 0, 0];
+        // A list of two int arrays
+        input.extend_from_slice(&[9, 0, 1, b'a', 11, 0, 0, 0, 2]);
+        input.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 1]);
+        input.extend_from_slice(&[0, 0, 0, 2, 0, 0, 0, 2, 0, 0, 0, 3]);
+        // A list of lists of compounds
+        input.extend_from_slice(&[9, 0, 1, b'b', 9, 0, 0, 0, 2]);
+        input.extend_from_slice(&[
+            10, 0, 0, 0, 1, 1, 0, 1, b'c', 4, 3, 0, 1, b'd', 0, 0, 0, 5, 0,
+        ]);
+        input.extend_from_slice(&[0, 0, 0, 0, 0]);
+        // An empty list of bytes
+        input.extend_from_slice(&[9, 0, 1, b'e', 1, 0, 0, 0, 0]);
+        input.push(0);
+
+        let (_, root) = NbtValue::read(&input).unwrap();
+        let arrays = root.get("a").and_then(NbtValue::as_list).unwrap();
+        assert_eq!(
+            **arrays,
+            [
+                NbtValue::IntArray(alloc::vec![1]),
+                NbtValue::IntArray(alloc::vec![2, 3])
+            ]
+        );
+        let lists = root.get("b").and_then(NbtValue::as_list).unwrap();
+        assert_eq!(lists.len(), 2);
+        let inner = lists[0].as_list().unwrap();
+        assert_eq!(inner.tag(), NbtTag::Compound);
+        assert_eq!(inner[0].get("c"), Some(&NbtValue::Byte(4)));
+        assert_eq!(inner[0].get("d"), Some(&NbtValue::Int(5)));
+        assert_eq!(lists[1].as_list().map(|list| list.tag()), Some(NbtTag::End));
+        let empty = root.get("e").and_then(NbtValue::as_list).unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.tag(), NbtTag::Byte);
+    }
+}
@@ -0,0 +1,139 @@
+use super::NbtValue;
+use crate::NbtTag;
+
+/// What [NbtValue::normalize] rewrites, nothing by default
+///
+/// Each option erases a difference that writers of the same data disagree on, so that trees
+/// read from them compare equal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Normalization {
+    empty_lists: bool,
+    widen_integers: bool,
+    widen_arrays: bool,
+}
+
+impl Normalization {
+    pub const fn new() -> Self {
+        Self {
+            empty_lists: false,
+            widen_integers: false,
+            widen_arrays: false,
+        }
+    }
+
+    /// Gives empty lists the End element tag
+    pub const fn empty_lists(mut self, empty_lists: bool) -> Self {
+        self.empty_lists = empty_lists;
+        self
+    }
+
+    /// Widens bytes, shorts and ints to longs, including the elements of lists
+    pub const fn widen_integers(mut self, widen_integers: bool) -> Self {
+        self.widen_integers = widen_integers;
+        self
+    }
+
+    /// Widens byte and int arrays to long arrays
+    pub const fn widen_arrays(mut self, widen_arrays: bool) -> Self {
+        self.widen_arrays = widen_arrays;
+        self
+    }
+}
+
+impl NbtValue {
+    /// Sorts the entries of every compound in this value by key
+    pub fn sort_keys(&mut self) {
+        match self {
+            NbtValue::Compound(compound) => {
+                compound
+                    .entries
+                    .sort_unstable_by(|(left, _), (right, _)| str::cmp(left, right));
+                compound.reindex();
+                for (_, value) in compound.entries.iter_mut() {
+                    value.sort_keys();
+                }
+            }
+            NbtValue::List(list) => list.values.iter_mut().for_each(NbtValue::sort_keys),
+            _ => {}
+        }
+    }
+
+    /// Rewrites this value and every value inside it as chosen by `normalization`
+    ///
+    /// Together with [sort_keys](NbtValue::sort_keys) this produces a canonical tree, which
+    /// compares equal to the canonical trees of the same data written differently.
+    ///
+    /// ```
+    /// # use zeronbt::value::{NbtList, NbtValue, Normalization};
+    /// let mut value = NbtValue::List(NbtList::try_from(vec![NbtValue::Short(5)]).unwrap());
+    /// value.normalize(Normalization::new().widen_integers(true));
+    /// assert_eq!(value.as_list().map(|list| list.tag()), Some(zeronbt::NbtTag::Long));
+    /// ```
+    pub fn normalize(&mut self, normalization: Normalization) {
+        match self {
+            NbtValue::Byte(value) if normalization.widen_integers => {
+                *self = NbtValue::Long((*value).into())
+            }
+            NbtValue::Short(value) if normalization.widen_integers => {
+                *self = NbtValue::Long((*value).into())
+            }
+            NbtValue::Int(value) if normalization.widen_integers => {
+                *self = NbtValue::Long((*value).into())
+            }
+            NbtValue::ByteArray(values) if normalization.widen_arrays => {
+                *self = NbtValue::LongArray(values.iter().map(|&value| value.into()).collect())
+            }
+            NbtValue::IntArray(values) if normalization.widen_arrays => {
+                *self = NbtValue::LongArray(values.iter().map(|&value| value.into()).collect())
+            }
+            NbtValue::List(list) => {
+                for value in &mut list.values {
+                    value.normalize(normalization);
+                }
+                match list.values.first() {
+                    Some(first) => list.tag = first.tag(),
+                    None if normalization.empty_lists => list.tag = NbtTag::End,
+                    None => {}
+                }
+            }
+            NbtValue::Compound(compound) => {
+                for (_, value) in compound.entries.iter_mut() {
+                    value.normalize(normalization);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::{NbtCompound, NbtList};
+
+    #[test]
+    fn canonical() {
+        let mut first = NbtCompound::new();
+        first.insert("b", NbtValue::Byte(1));
+        first.insert("a", NbtValue::List(NbtList::with_tag(NbtTag::Int)));
+        first.insert("c", NbtValue::IntArray(alloc::vec![-1]));
+        let mut second = NbtCompound::new();
+        second.insert("c", NbtValue::LongArray(alloc::vec![-1]));
+        second.insert("a", NbtValue::List(NbtList::new()));
+        second.insert("b", NbtValue::Int(1));
+        let (mut first, mut second) = (NbtValue::Compound(first), NbtValue::Compound(second));
+        assert_ne!(first, second);
+
+        let normalization = Normalization::new()
+            .empty_lists(true)
+            .widen_integers(true)
+            .widen_arrays(true);
+        for value in [&mut first, &mut second] {
+            value.sort_keys();
+            value.normalize(normalization);
+        }
+        assert_eq!(first, second);
+        let keys: alloc::vec::Vec<_> = first.as_compound().unwrap().keys().collect();
+        assert_eq!(keys, ["a", "b", "c"]);
+    }
+}
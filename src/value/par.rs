@@ -0,0 +1,87 @@
+use alloc::{string::String, vec::Vec};
+
+use rayon::prelude::*;
+
+use super::{NbtCompound, NbtValue};
+use crate::{
+    FsmResult, NbtFragment, NbtFsm, NbtTag,
+    error::{NbtParseError, NbtResult},
+    mutf8,
+    split::entry_spans,
+};
+
+/// Compounds smaller than this are parsed on a single thread
+const SPLIT_SIZE: usize = 1 << 16;
+
+impl NbtValue {
+    /// Reads a complete named tag like [NbtValue::read], building large compounds on the rayon
+    /// thread pool
+    ///
+    /// The entries of a compound are found first, then parsed in parallel, splitting the entries
+    /// that are large compounds themselves the same way. Lists are parsed on a single thread.
+    pub fn par_read(data: &[u8]) -> NbtResult<(String, NbtValue)> {
+        if data.len() < SPLIT_SIZE || data.first() != Some(&(NbtTag::Compound as u8)) {
+            return NbtValue::read(data);
+        }
+        let name = root_name(data)?;
+        Ok((name, NbtValue::Compound(par_compound(data)?)))
+    }
+}
+
+fn par_compound(data: &[u8]) -> NbtResult<NbtCompound> {
+    let entries: Vec<_> = entry_spans(data)?
+        .into_par_iter()
+        .map(|(key, range)| {
+            let entry = &data[range];
+            let value = if entry.len() >= SPLIT_SIZE && entry[0] == NbtTag::Compound as u8 {
+                NbtValue::Compound(par_compound(entry)?)
+            } else {
+                NbtValue::read(entry)?.1
+            };
+            Ok((key, value))
+        })
+        .collect::<NbtResult<_>>()?;
+    Ok(entries.into_iter().collect())
+}
+
+fn root_name(data: &[u8]) -> NbtResult<String> {
+    let mut fsm = NbtFsm::new().with_data(data);
+    let mut name = Vec::new();
+    loop {
+        match fsm.next_fragment()? {
+            FsmResult::Needs(_) => return Err(NbtParseError::UnexpectedEnd),
+            FsmResult::Found(NbtFragment::NameFrame([])) => break,
+            FsmResult::Found(NbtFragment::NameFrame(frame)) => name.extend_from_slice(frame),
+            FsmResult::Found(_) => {}
+        }
+    }
+    Ok(mutf8::decode(&name)
+        .ok_or(NbtParseError::InvalidString)?
+        .into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{format, vec};
+
+    #[test]
+    fn parse_in_parallel() {
+        let data = include_bytes!("../../assets/bigtest.nbt");
+        let (_, bigtest) = NbtValue::read(data).unwrap();
+        let nested: NbtCompound = (0..200)
+            .map(|index| (format!("copy {index}"), bigtest.clone()))
+            .collect();
+        let mut root = NbtCompound::new();
+        root.insert("nested", nested);
+        root.insert("bytes", vec![1i8; 100_000]);
+        root.insert("last", 5);
+        let data = NbtValue::Compound(root).to_bytes("root").unwrap();
+        assert!(data.len() > 2 * SPLIT_SIZE);
+        assert_eq!(NbtValue::par_read(&data), NbtValue::read(&data));
+        assert_eq!(
+            NbtValue::par_read(&data[..data.len() - 1]),
+            Err(NbtParseError::UnexpectedEnd)
+        );
+    }
+}
@@ -0,0 +1,242 @@
+//! Self-describing serde support for the value tree, in the spirit of `serde_json::Value`
+//!
+//! Arrays serialize as plain sequences, so formats without a notion of NBT arrays will read them
+//! back as lists. NBT has no null, so deserializing a null or unit value fails, fields that may be
+//! null can be read as an `Option<NbtValue>`.
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
+use ::serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{self, MapAccess, SeqAccess, Visitor},
+};
+
+use super::{NbtCompound, NbtList, NbtValue};
+
+impl Serialize for NbtValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            NbtValue::Byte(val) => serializer.serialize_i8(*val),
+            NbtValue::Short(val) => serializer.serialize_i16(*val),
+            NbtValue::Int(val) => serializer.serialize_i32(*val),
+            NbtValue::Long(val) => serializer.serialize_i64(*val),
+            NbtValue::Float(val) => serializer.serialize_f32(*val),
+            NbtValue::Double(val) => serializer.serialize_f64(*val),
+            NbtValue::ByteArray(vals) => serializer.collect_seq(vals),
+            NbtValue::String(val) => serializer.serialize_str(val),
+            NbtValue::List(list) => list.serialize(serializer),
+            NbtValue::Compound(compound) => compound.serialize(serializer),
+            NbtValue::IntArray(vals) => serializer.collect_seq(vals),
+            NbtValue::LongArray(vals) => serializer.collect_seq(vals),
+        }
+    }
+}
+
+impl Serialize for NbtList {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl Serialize for NbtCompound {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = NbtValue;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any NBT value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(NbtValue::Byte(v as i8))
+    }
+    fn visit_i8<E>(self, v: i8) -> Result<Self::Value, E> {
+        Ok(NbtValue::Byte(v))
+    }
+    fn visit_i16<E>(self, v: i16) -> Result<Self::Value, E> {
+        Ok(NbtValue::Short(v))
+    }
+    fn visit_i32<E>(self, v: i32) -> Result<Self::Value, E> {
+        Ok(NbtValue::Int(v))
+    }
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(NbtValue::Long(v))
+    }
+    // NBT has no unsigned types, so unsigned integers are widened to the next signed type
+    fn visit_u8<E>(self, v: u8) -> Result<Self::Value, E> {
+        Ok(NbtValue::Short(v.into()))
+    }
+    fn visit_u16<E>(self, v: u16) -> Result<Self::Value, E> {
+        Ok(NbtValue::Int(v.into()))
+    }
+    fn visit_u32<E>(self, v: u32) -> Result<Self::Value, E> {
+        Ok(NbtValue::Long(v.into()))
+    }
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        match i64::try_from(v) {
+            Ok(v) => Ok(NbtValue::Long(v)),
+            Err(_) => Err(E::invalid_value(de::Unexpected::Unsigned(v), &self)),
+        }
+    }
+    fn visit_f32<E>(self, v: f32) -> Result<Self::Value, E> {
+        Ok(NbtValue::Float(v))
+    }
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(NbtValue::Double(v))
+    }
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(NbtValue::String(v.into()))
+    }
+    fn visit_string<E>(self, v: String) -> Result<Self::Value, E> {
+        Ok(NbtValue::String(v))
+    }
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(NbtValue::ByteArray(
+            v.iter().map(|&byte| byte as i8).collect(),
+        ))
+    }
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Err(E::custom(
+            "NBT has no null value, use an Option<NbtValue> for values that may be null",
+        ))
+    }
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        self.visit_unit()
+    }
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        NbtValue::deserialize(deserializer)
+    }
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        NbtValue::deserialize(deserializer)
+    }
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+        ListVisitor.visit_seq(seq).map(NbtValue::List)
+    }
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+        CompoundVisitor.visit_map(map).map(NbtValue::Compound)
+    }
+}
+
+struct ListVisitor;
+
+impl<'de> Visitor<'de> for ListVisitor {
+    type Value = NbtList;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a sequence of NBT values sharing one tag")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(value) = seq.next_element::<NbtValue>()? {
+            values.push(value);
+        }
+        NbtList::try_from(values).map_err(|value| {
+            de::Error::custom(format_args!(
+                "found a {:?} in a list of a different type",
+                value.tag()
+            ))
+        })
+    }
+}
+
+struct CompoundVisitor;
+
+impl<'de> Visitor<'de> for CompoundVisitor {
+    type Value = NbtCompound;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map of NBT values")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut compound = NbtCompound::new();
+        while let Some((key, value)) = map.next_entry::<String, NbtValue>()? {
+            compound.insert(key, value);
+        }
+        Ok(compound)
+    }
+}
+
+impl<'de> Deserialize<'de> for NbtValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for NbtList {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(ListVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for NbtCompound {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(CompoundVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use super::*;
+
+    #[test]
+    fn json_round_trip() {
+        let (_, root) = NbtValue::read(include_bytes!("../../assets/bigtest.nbt")).unwrap();
+        let json = serde_json::to_string(&root).unwrap();
+        let generic: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(generic["longTest"], i64::MAX);
+        assert_eq!(generic["nested compound test"]["egg"]["name"], "Eggbert");
+        assert_eq!(generic["listTest (long)"][4], 15);
+
+        let value: NbtValue = serde_json::from_str(&json).unwrap();
+        assert!(
+            value
+                .as_compound()
+                .unwrap()
+                .keys()
+                .eq(root.as_compound().unwrap().keys())
+        );
+        assert_eq!(value.get("longTest"), Some(&NbtValue::Long(i64::MAX)));
+        assert_eq!(value.get("intTest"), Some(&NbtValue::Long(i32::MAX.into())));
+        assert_eq!(value.get("stringTest"), root.get("stringTest"));
+    }
+
+    #[test]
+    fn nested_field() {
+        #[derive(Deserialize)]
+        struct Config {
+            name: String,
+            data: NbtValue,
+        }
+        let config: Config =
+            serde_json::from_str(r#"{"name": "test", "data": {"list": [1, 2], "f": 0.5}}"#)
+                .unwrap();
+        assert_eq!(config.name, "test");
+        let list = config.data.get("list").and_then(NbtValue::as_list).unwrap();
+        assert_eq!(**list, [NbtValue::Long(1), NbtValue::Long(2)]);
+        assert_eq!(config.data.get("f"), Some(&NbtValue::Double(0.5)));
+
+        assert!(serde_json::from_str::<NbtValue>(r#"[1, "two"]"#).is_err());
+    }
+
+    #[test]
+    fn null() {
+        use alloc::string::ToString;
+        let err = serde_json::from_str::<NbtValue>(r#"{"a": null}"#).unwrap_err();
+        assert!(err.to_string().starts_with("NBT has no null value"));
+        assert!(serde_json::from_str::<NbtValue>("null").is_err());
+        let value: Option<NbtValue> = serde_json::from_str("null").unwrap();
+        assert_eq!(value, None);
+    }
+}
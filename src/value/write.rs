@@ -0,0 +1,112 @@
+use alloc::vec::Vec;
+
+use super::{NbtCompound, NbtList, NbtValue};
+use crate::{error::NbtWriteError, mutf8};
+
+impl NbtValue {
+    /// Encodes the value as a root tag named `name`, appending it to `out`
+    pub fn write(&self, name: &str, out: &mut Vec<u8>) -> Result<(), NbtWriteError> {
+        out.push(self.tag() as u8);
+        write_string(name, out)?;
+        self.write_payload(out)
+    }
+
+    /// Encodes the value as a root tag without a name, as used by the network format since
+    /// Minecraft 1.20.2
+    pub fn write_nameless(&self, out: &mut Vec<u8>) -> Result<(), NbtWriteError> {
+        out.push(self.tag() as u8);
+        self.write_payload(out)
+    }
+
+    /// Encodes the value as a root tag named `name`
+    pub fn to_bytes(&self, name: &str) -> Result<Vec<u8>, NbtWriteError> {
+        let mut out = Vec::new();
+        self.write(name, &mut out)?;
+        Ok(out)
+    }
+
+    /// Appends the value without its tag byte or name
+    pub fn write_payload(&self, out: &mut Vec<u8>) -> Result<(), NbtWriteError> {
+        match self {
+            NbtValue::Byte(val) => out.push(*val as u8),
+            NbtValue::Short(val) => out.extend_from_slice(&val.to_be_bytes()),
+            NbtValue::Int(val) => out.extend_from_slice(&val.to_be_bytes()),
+            NbtValue::Long(val) => out.extend_from_slice(&val.to_be_bytes()),
+            NbtValue::Float(val) => out.extend_from_slice(&val.to_be_bytes()),
+            NbtValue::Double(val) => out.extend_from_slice(&val.to_be_bytes()),
+            NbtValue::ByteArray(values) => {
+                write_len(values.len(), out)?;
+                out.extend(values.iter().map(|&val| val as u8));
+            }
+            NbtValue::String(string) => write_string(string, out)?,
+            NbtValue::List(list) => write_list(list, out)?,
+            NbtValue::Compound(compound) => write_compound(compound, out)?,
+            NbtValue::IntArray(values) => {
+                write_len(values.len(), out)?;
+                for val in values {
+                    out.extend_from_slice(&val.to_be_bytes());
+                }
+            }
+            NbtValue::LongArray(values) => {
+                write_len(values.len(), out)?;
+                for val in values {
+                    out.extend_from_slice(&val.to_be_bytes());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_len(len: usize, out: &mut Vec<u8>) -> Result<(), NbtWriteError> {
+    let len = i32::try_from(len).map_err(|_| NbtWriteError::TooManyElements(len))?;
+    out.extend_from_slice(&len.to_be_bytes());
+    Ok(())
+}
+
+fn write_string(string: &str, out: &mut Vec<u8>) -> Result<(), NbtWriteError> {
+    let bytes = mutf8::encode(string);
+    let len = u16::try_from(bytes.len()).map_err(|_| NbtWriteError::StringTooLong(bytes.len()))?;
+    out.extend_from_slice(&len.to_be_bytes());
+    out.extend_from_slice(&bytes);
+    Ok(())
+}
+
+fn write_list(list: &NbtList, out: &mut Vec<u8>) -> Result<(), NbtWriteError> {
+    out.push(list.tag() as u8);
+    write_len(list.len(), out)?;
+    for value in list.iter() {
+        value.write_payload(out)?;
+    }
+    Ok(())
+}
+
+fn write_compound(compound: &NbtCompound, out: &mut Vec<u8>) -> Result<(), NbtWriteError> {
+    for (name, value) in compound.iter() {
+        value.write(name, out)?;
+    }
+    out.push(0);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_bigtest() {
+        let data = include_bytes!("../../assets/bigtest.nbt");
+        let (name, root) = NbtValue::read(data).unwrap();
+        let written = root.to_bytes(&name).unwrap();
+        assert_eq!(NbtValue::read(&written).unwrap(), (name, root));
+    }
+
+    #[test]
+    fn string_too_long() {
+        let value = NbtValue::from("a".repeat(u16::MAX as usize + 1));
+        assert_eq!(
+            value.to_bytes(""),
+            Err(NbtWriteError::StringTooLong(u16::MAX as usize + 1))
+        );
+    }
+}
@@ -53,7 +53,7 @@ basic_be_impl!(f32, f64);
 impl<'s, T: BeRepr> BeSlice<'s, T> {
     #[inline(always)]
     pub fn new(data: &'s [u8]) -> Option<Self> {
-        if data.len() % T::BYTES != 0 {
+        if !data.len().is_multiple_of(T::BYTES) {
             return None;
         }
         Some(BeSlice {
@@ -97,6 +97,45 @@ impl<'s, T: BeRepr> BeSlice<'s, T> {
     }
 }
 
+impl<'s> BeSlice<'s, i8> {
+    /// Views bytes as signed bytes, which every slice is a valid view of
+    pub const fn from_bytes(data: &'s [u8]) -> Self {
+        BeSlice {
+            data,
+            _repr: PhantomData,
+        }
+    }
+}
+
+// Reading floats is a bitwise copy, so the raw views let integer-only code, e.g. on targets
+// without an FPU, handle them without pulling in float formatting or arithmetic
+impl<'s> BeSlice<'s, f32> {
+    /// Views the elements as their IEEE 754 bits
+    pub const fn to_bits(self) -> BeSlice<'s, u32> {
+        BeSlice {
+            data: self.data,
+            _repr: PhantomData,
+        }
+    }
+}
+
+impl<'s> BeSlice<'s, f64> {
+    /// Views the elements as their IEEE 754 bits
+    pub const fn to_bits(self) -> BeSlice<'s, u64> {
+        BeSlice {
+            data: self.data,
+            _repr: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'s, T: BeRepr + serde::Serialize> serde::Serialize for BeSlice<'s, T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
 pub struct BeIterator<'s, T: BeRepr>(BeSlice<'s, T>);
 
 impl<'s, T: BeRepr> Iterator for BeIterator<'s, T> {
@@ -0,0 +1,276 @@
+//! Seeded generation of large, realistic documents for benchmarks
+//!
+//! Generated documents resemble world data: compounds with well-known keys, namespaced ids,
+//! packed long arrays and lists of compounds. The same seed and settings always produce the same
+//! document, on every platform.
+//!
+//! ```
+//! # use zeronbt::{value::NbtValue, workload::Workload};
+//! let data = Workload::new(7).depth(3).width(8).to_bytes();
+//! assert!(NbtValue::read(&data).is_ok());
+//! ```
+use alloc::{format, string::String, vec::Vec};
+
+use crate::{
+    NbtTag,
+    value::{NbtCompound, NbtList, NbtValue},
+};
+
+const KEYS: &[&str] = &[
+    "id",
+    "Name",
+    "Pos",
+    "Motion",
+    "Rotation",
+    "Health",
+    "Items",
+    "Count",
+    "Slot",
+    "tag",
+    "Properties",
+    "Palette",
+    "BlockStates",
+    "data",
+    "palette",
+    "block_states",
+    "biomes",
+    "Heightmaps",
+    "sections",
+    "block_entities",
+    "Status",
+    "LastUpdate",
+    "InhabitedTime",
+    "xPos",
+    "yPos",
+    "zPos",
+    "DataVersion",
+    "CustomName",
+    "Attributes",
+    "Base",
+];
+
+const WORDS: &[&str] = &[
+    "stone",
+    "dirt",
+    "grass_block",
+    "oak_log",
+    "water",
+    "air",
+    "deepslate",
+    "iron_ore",
+    "chest",
+    "zombie",
+    "plains",
+    "forest",
+    "north",
+    "south",
+    "lower",
+    "upper",
+    "true",
+    "false",
+];
+
+/// Settings for a generated document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Workload {
+    seed: u64,
+    depth: u32,
+    width: usize,
+    string_len: usize,
+    array_len: usize,
+    list_len: usize,
+}
+
+impl Workload {
+    /// Default settings, producing documents of anywhere from a few hundred bytes to about half a
+    /// MiB depending on the seed
+    pub const fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            depth: 3,
+            width: 12,
+            string_len: 24,
+            array_len: 256,
+            list_len: 12,
+        }
+    }
+
+    /// The number of compounds and lists nested inside each other below the root
+    pub const fn depth(mut self, depth: u32) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// The most entries a compound has, each one has at least half as many
+    pub const fn width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// The longest string generated, in bytes
+    pub const fn string_len(mut self, len: usize) -> Self {
+        self.string_len = len;
+        self
+    }
+
+    /// The longest byte, int or long array generated
+    pub const fn array_len(mut self, len: usize) -> Self {
+        self.array_len = len;
+        self
+    }
+
+    /// The longest list generated
+    pub const fn list_len(mut self, len: usize) -> Self {
+        self.list_len = len;
+        self
+    }
+
+    /// Generates the root compound
+    pub fn generate(&self) -> NbtCompound {
+        let mut generator = Generator {
+            settings: self,
+            rng: SplitMix64(self.seed),
+        };
+        generator.compound(self.depth)
+    }
+
+    /// Generates the document and encodes it with an empty root name
+    pub fn to_bytes(&self) -> Vec<u8> {
+        NbtValue::Compound(self.generate())
+            .to_bytes("")
+            .expect("generated values fit the limits of NBT")
+    }
+}
+
+struct Generator<'s> {
+    settings: &'s Workload,
+    rng: SplitMix64,
+}
+
+impl Generator<'_> {
+    fn compound(&mut self, depth: u32) -> NbtCompound {
+        let min = self.settings.width / 2;
+        let width = min + self.rng.below(self.settings.width - min + 1);
+        let mut out = NbtCompound::new();
+        for index in 0..width {
+            let key = KEYS[self.rng.below(KEYS.len())];
+            // Keep keys unique without making them all look made up
+            let key = match out.contains_key(key) {
+                true => format!("{key}{index}"),
+                false => key.into(),
+            };
+            let value = self.value(depth);
+            out.insert(key, value);
+        }
+        out
+    }
+
+    fn value(&mut self, depth: u32) -> NbtValue {
+        // Containers get rarer the deeper they are nested
+        let kinds = if depth == 0 { 10 } else { 14 };
+        match self.rng.below(kinds) {
+            0 => NbtValue::Byte(self.rng.next() as i8 & 1),
+            1 => NbtValue::Short(self.rng.next() as i16),
+            2 | 3 => NbtValue::Int(self.rng.next() as i32),
+            4 => NbtValue::Long(self.rng.next() as i64),
+            5 => NbtValue::Float(self.rng.float() as f32),
+            6 => NbtValue::Double(self.rng.float() * 1000.0),
+            7 | 8 => NbtValue::String(self.string()),
+            9 => self.array(),
+            10 | 11 => NbtValue::Compound(self.compound(depth - 1)),
+            _ => NbtValue::List(self.list(depth - 1)),
+        }
+    }
+
+    fn array(&mut self) -> NbtValue {
+        let len = self.rng.below(self.settings.array_len + 1);
+        match self.rng.below(3) {
+            0 => NbtValue::ByteArray((0..len).map(|_| self.rng.next() as i8).collect()),
+            1 => NbtValue::IntArray((0..len).map(|_| self.rng.next() as i32).collect()),
+            _ => NbtValue::LongArray((0..len).map(|_| self.rng.next() as i64).collect()),
+        }
+    }
+
+    fn list(&mut self, depth: u32) -> NbtList {
+        let len = self.rng.below(self.settings.list_len + 1);
+        let (tag, element): (_, fn(&mut Self, u32) -> NbtValue) = match self.rng.below(4) {
+            0 => (NbtTag::Double, |generator, _| {
+                NbtValue::Double(generator.rng.float() * 256.0)
+            }),
+            1 => (NbtTag::String, |generator, _| {
+                NbtValue::String(generator.string())
+            }),
+            _ => (NbtTag::Compound, |generator, depth| {
+                NbtValue::Compound(generator.compound(depth))
+            }),
+        };
+        let mut out = NbtList::with_tag(tag);
+        for _ in 0..len {
+            let value = element(self, depth);
+            out.push(value).expect("all elements share a tag");
+        }
+        out
+    }
+
+    /// Namespaced ids and block state strings, cut to the configured length
+    fn string(&mut self) -> String {
+        let word = WORDS[self.rng.below(WORDS.len())];
+        let mut out = match self.rng.below(3) {
+            0 => String::from(word),
+            _ => format!("minecraft:{word}"),
+        };
+        while out.len() < self.settings.string_len && self.rng.below(4) == 0 {
+            out.push('_');
+            out.push_str(WORDS[self.rng.below(WORDS.len())]);
+        }
+        out.truncate(self.settings.string_len);
+        out
+    }
+}
+
+/// A small, fast generator whose output does not depend on the platform
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..bound`, or 0 if `bound` is 0
+    fn below(&mut self, bound: usize) -> usize {
+        match bound {
+            0 => 0,
+            bound => (self.next() % bound as u64) as usize,
+        }
+    }
+
+    /// A number in `0.0..1.0`
+    fn float(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deterministic() {
+        let workload = Workload::new(42);
+        let data = workload.to_bytes();
+        assert_eq!(data, workload.to_bytes());
+        assert_ne!(data, Workload::new(43).to_bytes());
+        let (_, value) = NbtValue::read(&data).unwrap();
+        assert_eq!(value, NbtValue::Compound(workload.generate()));
+
+        let flat = Workload::new(42).depth(0).string_len(4).generate();
+        assert!(flat.values().all(|value| match value {
+            NbtValue::String(string) => string.len() <= 4,
+            value => !matches!(value.tag(), NbtTag::List | NbtTag::Compound),
+        }));
+    }
+}
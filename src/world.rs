@@ -0,0 +1,127 @@
+//! Reading the files of a world directory
+//!
+//! Every player who joined a world has a gzip compressed file in its `playerdata` directory,
+//! named after their UUID, holding their inventory, position and other state. [playerdata]
+//! scans all of them, e.g. to audit inventories.
+//!
+//! ```no_run
+//! # use zeronbt::world::playerdata;
+//! for (uuid, player) in playerdata("world").unwrap() {
+//!     match player {
+//!         Ok(player) => println!("{uuid}: {:?}", player.get("Pos")),
+//!         Err(err) => eprintln!("{uuid}: {err}"),
+//!     }
+//! }
+//! ```
+use alloc::{format, vec::Vec};
+use std::{
+    fs::{self, File},
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    error::{NbtIoError, NbtParseError},
+    io::NbtReader,
+    uuid::Uuid,
+    value::NbtValue,
+};
+
+/// The player files of a world, read one at a time, see [playerdata]
+#[derive(Debug)]
+pub struct PlayerData {
+    files: alloc::vec::IntoIter<(Uuid, PathBuf)>,
+}
+
+impl Iterator for PlayerData {
+    /// The UUID of the player along with their data, or the error that occurred reading it
+    type Item = (Uuid, Result<NbtValue, NbtIoError>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (uuid, path) = self.files.next()?;
+        Some((uuid, read_player(&path)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.files.size_hint()
+    }
+}
+
+impl ExactSizeIterator for PlayerData {}
+
+/// Lists the player files in the `playerdata` directory of `world`, ordered by UUID
+///
+/// Other files in the directory, like the `<uuid>.dat_old` backups, are skipped. A file that can
+/// not be read does not end the iteration, its error is returned along with its UUID instead.
+pub fn playerdata(world: impl AsRef<Path>) -> io::Result<PlayerData> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(world.as_ref().join("playerdata"))? {
+        let path = entry?.path();
+        let uuid = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".dat"))
+            .and_then(Uuid::parse);
+        if let Some(uuid) = uuid {
+            files.push((uuid, path));
+        }
+    }
+    files.sort_unstable();
+    Ok(PlayerData {
+        files: files.into_iter(),
+    })
+}
+
+/// Reads the file of a single player of `world`
+pub fn player(world: impl AsRef<Path>, uuid: Uuid) -> Result<NbtValue, NbtIoError> {
+    let path = world
+        .as_ref()
+        .join("playerdata")
+        .join(format!("{uuid}.dat"));
+    read_player(&path)
+}
+
+fn read_player(path: &Path) -> Result<NbtValue, NbtIoError> {
+    let file = BufReader::new(File::open(path)?);
+    let (_, value) = NbtReader::decompress(file)?
+        .read_value()?
+        .ok_or(NbtParseError::UnexpectedEnd)?;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compression::Compression, save::AtomicSave, value::NbtCompound};
+
+    #[test]
+    fn scan_players() {
+        let world = std::env::temp_dir().join(format!("zeronbt-world-{}", std::process::id()));
+        let dir = world.join("playerdata");
+        fs::create_dir_all(&dir).unwrap();
+        let first = Uuid::parse("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap();
+        let second = Uuid::parse("853c80ef-3c37-49fd-aa49-938b674adae6").unwrap();
+        let mut data = NbtCompound::new();
+        data.insert("XpLevel", 30);
+        let data = NbtValue::Compound(data);
+        let save = AtomicSave::new().backup(true);
+        for uuid in [second, first, first] {
+            let path = dir.join(format!("{uuid}.dat"));
+            save.write_value(path, "", &data, Compression::None)
+                .unwrap();
+        }
+        // A truncated file
+        fs::write(dir.join(format!("{second}.dat")), b"\x0a\0").unwrap();
+        fs::write(dir.join("notes.txt"), b"").unwrap();
+
+        let players: Vec<_> = playerdata(&world).unwrap().collect();
+        let single = player(&world, first);
+        fs::remove_dir_all(&world).unwrap();
+        assert_eq!(players.len(), 2);
+        assert_eq!(players[0].0, first);
+        assert_eq!(players[0].1.as_ref().unwrap(), &data);
+        assert_eq!(players[1].0, second);
+        assert!(players[1].1.is_err());
+        assert_eq!(single.unwrap(), data);
+    }
+}
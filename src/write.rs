@@ -0,0 +1,510 @@
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use crate::{
+    CompleteFsm, Dialect, Java, NbtFragment, NbtTag,
+    error::NbtWriteError,
+    mutf8::{self, StringPolicy},
+    path::{NbtPath, PathSegment},
+    value::NbtValue,
+    view::{BeRepr, BeSlice},
+};
+
+/// Encodes fragment streams as binary NBT, the inverse of [NbtFsm](crate::NbtFsm)
+///
+/// Names, strings and byte arrays are collected until their last frame, as their length is
+/// written first. Everything else is written as soon as it is pushed.
+///
+/// ```
+/// # use zeronbt::{CompleteFsm, NbtWriter};
+/// let data = include_bytes!("../assets/bigtest.nbt");
+/// let mut writer = NbtWriter::new();
+/// for fragment in CompleteFsm::new(data) {
+///     writer.push(fragment.unwrap()).unwrap();
+/// }
+/// assert_eq!(writer.into_inner(), data);
+/// ```
+///
+/// Other dialects are written with [NbtWriter::with_dialect], so fragments read from one can be
+/// written as another:
+///
+/// ```
+/// # use zeronbt::{BedrockNetwork, CompleteFsm, NbtFsm, NbtWriter};
+/// let data = include_bytes!("../assets/bigtest.nbt");
+/// let mut writer = NbtWriter::with_dialect(BedrockNetwork);
+/// for fragment in CompleteFsm::new(data) {
+///     writer.push(fragment.unwrap()).unwrap();
+/// }
+/// let packet = writer.into_inner();
+///
+/// let mut writer = NbtWriter::new();
+/// for fragment in CompleteFsm::with_fsm(NbtFsm::with_dialect(BedrockNetwork), &packet) {
+///     writer.push(fragment.unwrap()).unwrap();
+/// }
+/// assert_eq!(writer.into_inner(), data);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct NbtWriter<D: Dialect = Java> {
+    out: Vec<u8>,
+    stack: Vec<Open>,
+    /// The frames of the name of the next entry
+    name: Vec<u8>,
+    /// The name of the entry whose header was written last
+    key: Vec<u8>,
+    /// The frames of the string or byte array being written
+    text: Vec<u8>,
+    /// Compounds are announced before their name, so their header is written once it is complete
+    compound_pending: bool,
+    done: bool,
+    budget: Option<usize>,
+    /// Where the current document starts in the output
+    start: usize,
+    /// The names of the open values, for reporting where the budget was exceeded
+    keys: Vec<Vec<u8>>,
+    _dialect: PhantomData<D>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Open {
+    Compound,
+    List {
+        tag: NbtTag,
+        len: usize,
+        remaining: usize,
+        /// Where the length of the list is written, to be corrected if elements are removed
+        len_pos: usize,
+        len_size: usize,
+        removed: usize,
+    },
+    /// An int or long array, along with the number of elements still to come
+    Array(usize),
+}
+
+impl NbtWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<D: Dialect> NbtWriter<D> {
+    /// Creates a writer for another dialect than [Java], e.g. `NbtWriter::with_dialect(Bedrock)`
+    pub fn with_dialect(_dialect: D) -> Self {
+        Self {
+            out: Vec::new(),
+            stack: Vec::new(),
+            name: Vec::new(),
+            key: Vec::new(),
+            text: Vec::new(),
+            compound_pending: false,
+            done: false,
+            budget: None,
+            start: 0,
+            keys: Vec::new(),
+            _dialect: PhantomData,
+        }
+    }
+
+    /// Limits every document to `budget` bytes, failing with [NbtWriteError::OverBudget] as soon
+    /// as a value does not fit anymore
+    ///
+    /// The error holds the path of that value. The document is left incomplete, so the writer
+    /// should not be used afterwards.
+    ///
+    /// ```
+    /// # use zeronbt::{NbtWriter, error::NbtWriteError, value::NbtValue};
+    /// let (_, value) = NbtValue::read(include_bytes!("../assets/bigtest.nbt")).unwrap();
+    /// let mut writer = NbtWriter::new().budget(256);
+    /// let Err(NbtWriteError::OverBudget { path, .. }) = writer.push_value("", &value) else {
+    ///     panic!("bigtest is larger than 256 bytes");
+    /// };
+    /// assert_eq!(path.to_string(), "");
+    /// ```
+    pub fn budget(mut self, budget: usize) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Writes the next fragment, returning true once the root tag is complete
+    ///
+    /// Fragments of the next document may be pushed after that, they are written right after the
+    /// previous one.
+    pub fn push(&mut self, fragment: NbtFragment<'_>) -> Result<bool, NbtWriteError> {
+        self.reset_if_done();
+        match fragment {
+            NbtFragment::NameFrame(data) if !data.is_empty() => self.name.extend_from_slice(data),
+            NbtFragment::NameFrame(_) => {
+                if self.compound_pending {
+                    self.compound_pending = false;
+                    self.header(NbtTag::Compound)?;
+                    self.check_budget(0, None)?;
+                    self.open(Open::Compound);
+                }
+            }
+            NbtFragment::CompoundTag => match self.stack.last() {
+                Some(Open::List { .. }) => self.open(Open::Compound),
+                _ => self.compound_pending = true,
+            },
+            NbtFragment::End => {
+                let (open, key) = self.close();
+                if open != Some(Open::Compound) {
+                    return Err(NbtWriteError::UnexpectedFragment);
+                }
+                self.out.push(0);
+                self.check_budget(0, Some(&key))?;
+                self.complete();
+            }
+            NbtFragment::Byte(value) => self.number(NbtTag::Byte, |out| out.push(value as u8))?,
+            NbtFragment::Short(value) => {
+                self.number(NbtTag::Short, |out| D::put_short(value, out))?
+            }
+            NbtFragment::Int(value) => self.number(NbtTag::Int, |out| D::put_int(value, out))?,
+            NbtFragment::Long(value) => self.number(NbtTag::Long, |out| D::put_long(value, out))?,
+            NbtFragment::Float(value) => {
+                self.number(NbtTag::Float, |out| D::put_float(value, out))?
+            }
+            NbtFragment::Double(value) => {
+                self.number(NbtTag::Double, |out| D::put_double(value, out))?
+            }
+            NbtFragment::StringFrame(data) | NbtFragment::ByteArrayFrame(data)
+                if !data.is_empty() =>
+            {
+                self.text.extend_from_slice(data);
+                // Strings and byte arrays are only written once complete, but already count
+                let name = core::mem::take(&mut self.name);
+                let checked = self.check_budget(self.text.len(), Some(&name));
+                self.name = name;
+                checked?;
+            }
+            NbtFragment::StringFrame(_) => {
+                self.header(NbtTag::String)?;
+                write_string::<D>(&self.text, &mut self.out)?;
+                self.text.clear();
+                self.check_budget(0, None)?;
+                self.complete();
+            }
+            NbtFragment::ByteArrayFrame(_) => {
+                self.header(NbtTag::ByteArray)?;
+                D::put_len(self.text.len(), &mut self.out)?;
+                self.out.extend_from_slice(&self.text);
+                self.text.clear();
+                self.check_budget(0, None)?;
+                self.complete();
+            }
+            NbtFragment::ListTag(tag, len) => {
+                self.header(NbtTag::List)?;
+                self.out.push(tag as u8);
+                let len_pos = self.out.len();
+                D::put_len(len, &mut self.out)?;
+                self.check_budget(0, None)?;
+                match len {
+                    0 => self.complete(),
+                    _ => self.open(Open::List {
+                        tag,
+                        len,
+                        remaining: len,
+                        len_pos,
+                        len_size: self.out.len() - len_pos,
+                        removed: 0,
+                    }),
+                }
+            }
+            NbtFragment::IntArrayTag(len) => self.array(NbtTag::IntArray, len)?,
+            NbtFragment::LongArrayTag(len) => self.array(NbtTag::LongArray, len)?,
+            NbtFragment::ByteListFrame(values) => {
+                self.frame(values, |value, out| out.push(value as u8))?
+            }
+            NbtFragment::ShortListFrame(values) => self.frame(values, D::put_short)?,
+            NbtFragment::IntListFrame(values) => self.frame(values, D::put_int)?,
+            NbtFragment::LongListFrame(values) => self.frame(values, D::put_long)?,
+            NbtFragment::FloatListFrame(values) => self.frame(values, D::put_float)?,
+            NbtFragment::DoubleListFrame(values) => self.frame(values, D::put_double)?,
+        }
+        Ok(self.done)
+    }
+
+    /// Writes a whole value where the next one would start, returning true if it completes the
+    /// root tag
+    ///
+    /// The name is used for compound entries and the root, and ignored for list elements, which
+    /// must match the type of the list.
+    pub fn push_value(&mut self, name: &str, value: &NbtValue) -> Result<bool, NbtWriteError> {
+        self.reset_if_done();
+        match self.stack.last() {
+            Some(&Open::List { tag, .. }) if tag != value.tag() => {
+                return Err(NbtWriteError::WrongElementTag {
+                    expected: tag,
+                    found: value.tag(),
+                });
+            }
+            Some(Open::Array(_)) => return Err(NbtWriteError::UnexpectedFragment),
+            _ if !D::LIST_FRAMES => return self.replay(name, value),
+            Some(Open::List { .. }) => value.write_payload(&mut self.out)?,
+            None if !D::NAMED_ROOT => value.write_nameless(&mut self.out)?,
+            _ => value.write(name, &mut self.out)?,
+        }
+        self.check_budget(0, Some(&*mutf8::encode(name)))?;
+        self.complete();
+        Ok(self.done)
+    }
+
+    /// Writes a value through its fragments, for dialects whose numbers are not encoded like
+    /// those of [NbtValue::write]
+    fn replay(&mut self, name: &str, value: &NbtValue) -> Result<bool, NbtWriteError> {
+        let data = value.to_bytes(name)?;
+        // List elements have no name
+        let mut skip_name = matches!(self.stack.last(), Some(Open::List { .. }));
+        for fragment in CompleteFsm::new(&data) {
+            let fragment = fragment.expect("values are encoded as valid NBT");
+            match fragment {
+                NbtFragment::NameFrame(name) if skip_name => skip_name = !name.is_empty(),
+                fragment => {
+                    self.push(fragment)?;
+                }
+            }
+        }
+        Ok(self.done)
+    }
+
+    /// Leaves out the next element of the list being written, whose length is corrected once
+    /// the list is complete
+    pub(crate) fn remove_element(&mut self) -> bool {
+        if let Some(Open::List { removed, .. }) = self.stack.last_mut() {
+            *removed += 1;
+            self.complete();
+        }
+        self.done
+    }
+
+    pub fn get_ref(&self) -> &Vec<u8> {
+        &self.out
+    }
+
+    /// The output written so far
+    ///
+    /// Lists that elements are removed from have their length corrected once they are complete,
+    /// so only complete documents may be drained from it in that case.
+    pub fn get_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.out
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.out
+    }
+
+    fn reset_if_done(&mut self) {
+        if self.done {
+            self.done = false;
+            self.name.clear();
+            self.start = self.out.len();
+        }
+    }
+
+    /// Writes the tag and name of a compound entry or the root, which list elements have none of
+    fn header(&mut self, tag: NbtTag) -> Result<(), NbtWriteError> {
+        if let Some(Open::List { .. } | Open::Array(_)) = self.stack.last() {
+            return Ok(());
+        }
+        self.out.push(tag as u8);
+        if D::NAMED_ROOT || !self.stack.is_empty() {
+            write_string::<D>(&self.name, &mut self.out)?;
+        }
+        core::mem::swap(&mut self.name, &mut self.key);
+        self.name.clear();
+        Ok(())
+    }
+
+    fn open(&mut self, open: Open) {
+        if self.budget.is_some() {
+            self.keys.push(self.key.clone());
+        }
+        self.stack.push(open);
+    }
+
+    /// Closes the innermost value, returning it along with its name if the budget is tracked
+    fn close(&mut self) -> (Option<Open>, Vec<u8>) {
+        let key = match self.budget {
+            Some(_) => self.keys.pop().unwrap_or_default(),
+            None => Vec::new(),
+        };
+        (self.stack.pop(), key)
+    }
+
+    /// Fails if the current document and `pending` bytes exceed the budget
+    ///
+    /// The value exceeding it is the element of the innermost list, or the entry of the innermost
+    /// compound named `key`, which is the entry written last by default.
+    fn check_budget(&self, pending: usize, key: Option<&[u8]>) -> Result<(), NbtWriteError> {
+        let Some(budget) = self.budget else {
+            return Ok(());
+        };
+        if self.out.len().saturating_sub(self.start) + pending <= budget {
+            return Ok(());
+        }
+        let mut path = NbtPath::root();
+        let current = key.unwrap_or(&self.key);
+        for (depth, open) in self.stack.iter().enumerate() {
+            // The root has no name in the path
+            match *open {
+                Open::Compound => {
+                    let key = self.keys.get(depth + 1).map_or(current, Vec::as_slice);
+                    let key = StringPolicy::Lossy.decode_name(key).unwrap_or_default();
+                    path.push(PathSegment::Key(key.into_owned()));
+                }
+                // Numeric elements are not values of their own
+                Open::List { tag, .. } if tag.is_numeric() => {}
+                Open::List { len, remaining, .. } => {
+                    path.push(PathSegment::Index((len - remaining) as i32))
+                }
+                Open::Array(_) => {}
+            }
+        }
+        Err(NbtWriteError::OverBudget { budget, path })
+    }
+
+    fn number(
+        &mut self,
+        tag: NbtTag,
+        write: impl FnOnce(&mut Vec<u8>),
+    ) -> Result<(), NbtWriteError> {
+        self.header(tag)?;
+        write(&mut self.out);
+        self.check_budget(0, None)?;
+        self.complete();
+        Ok(())
+    }
+
+    fn array(&mut self, tag: NbtTag, len: usize) -> Result<(), NbtWriteError> {
+        self.header(tag)?;
+        D::put_len(len, &mut self.out)?;
+        self.check_budget(0, None)?;
+        match len {
+            0 => self.complete(),
+            _ => self.open(Open::Array(len)),
+        }
+        Ok(())
+    }
+
+    /// Numeric elements of a list or array, which do not complete a value each
+    ///
+    /// Frames are copied as they are in big-endian dialects, and written element by element
+    /// otherwise.
+    fn frame<T: BeRepr>(
+        &mut self,
+        values: BeSlice<'_, T>,
+        put: impl Fn(T, &mut Vec<u8>),
+    ) -> Result<(), NbtWriteError> {
+        let len = values.len();
+        let remaining = match self.stack.last_mut() {
+            Some(Open::List { tag, remaining, .. }) if tag.is_numeric() => remaining,
+            Some(Open::Array(remaining)) => remaining,
+            _ => return Err(NbtWriteError::UnexpectedFragment),
+        };
+        *remaining = remaining.saturating_sub(len);
+        let complete = *remaining == 0;
+        if D::LIST_FRAMES {
+            self.out.extend_from_slice(values.raw_bytes());
+        } else {
+            for value in values.iter() {
+                put(value, &mut self.out);
+            }
+        }
+        self.check_budget(0, None)?;
+        if complete {
+            self.close();
+            self.complete();
+        }
+        Ok(())
+    }
+
+    /// Called once a value has been written completely, counting it towards the enclosing list
+    fn complete(&mut self) {
+        while let Some(Open::List {
+            len,
+            remaining,
+            len_pos,
+            len_size,
+            removed,
+            ..
+        }) = self.stack.last_mut()
+        {
+            *remaining -= 1;
+            if *remaining > 0 {
+                return;
+            }
+            if *removed > 0 {
+                // VarInt lengths may get shorter
+                let mut encoded = Vec::new();
+                D::put_len(*len - *removed, &mut encoded).expect("the length only got shorter");
+                self.out.splice(*len_pos..*len_pos + *len_size, encoded);
+            }
+            self.close();
+        }
+        self.done = self.stack.is_empty();
+    }
+}
+
+/// Writes a name or string that is already encoded as Modified UTF-8
+fn write_string<D: Dialect>(data: &[u8], out: &mut Vec<u8>) -> Result<(), NbtWriteError> {
+    D::put_string_len(data.len(), out)?;
+    out.extend_from_slice(data);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CompleteFsm;
+    use alloc::string::ToString;
+
+    #[test]
+    fn round_trip() {
+        let inputs: [&[u8]; 2] = [
+            include_bytes!("../assets/bigtest.nbt"),
+            include_bytes!("../assets/chunk_0-0.nbt"),
+        ];
+        for data in inputs {
+            let mut writer = NbtWriter::new();
+            let mut done = false;
+            for fragment in CompleteFsm::new(data) {
+                done = writer.push(fragment.unwrap()).unwrap();
+            }
+            assert!(done);
+            assert_eq!(writer.into_inner(), data);
+        }
+    }
+
+    #[test]
+    fn budget() {
+        let data = include_bytes!("../assets/bigtest.nbt");
+        let over = |budget: usize| {
+            let mut writer = NbtWriter::new().budget(budget);
+            for fragment in CompleteFsm::new(data) {
+                if let Err(NbtWriteError::OverBudget { path, .. }) = writer.push(fragment.unwrap())
+                {
+                    return Some(path.to_string());
+                }
+            }
+            assert_eq!(writer.into_inner(), data);
+            None
+        };
+        assert_eq!(over(data.len()), None);
+        assert_eq!(over(data.len() - 1).as_deref(), Some(""));
+        assert_eq!(over(0).as_deref(), Some(""));
+        assert_eq!(over(20).as_deref(), Some("longTest"));
+        assert_eq!(
+            over(200).as_deref(),
+            Some("\"nested compound test\".egg.name")
+        );
+        assert_eq!(over(300).as_deref(), Some("\"listTest (compound)\""));
+        assert_eq!(
+            over(320).as_deref(),
+            Some("\"listTest (compound)\"[0].name")
+        );
+        assert_eq!(
+            over(600).as_deref(),
+            Some(
+                "\"byteArrayTest (the first 1000 values of (n*n*255+n*7)%100, starting with n=0 (0, 62, 34, 16, 8, ...))\""
+            )
+        );
+    }
+}
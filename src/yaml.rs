@@ -0,0 +1,375 @@
+//! Writing NBT as YAML, for reviewing and diffing documents as text
+//!
+//! Compounds and lists of compounds or lists are written in block style, every other list and
+//! array on a single line in flow style. Strings are always quoted, and the type of numbers,
+//! lists and arrays is kept in a comment after them, so little is lost compared to SNBT.
+//!
+//! ```
+//! # use zeronbt::{FsmResult, NbtFsm, value::NbtValue, yaml::YamlWriter};
+//! let value = NbtValue::Compound([("id", "minecraft:stone")].into_iter().collect());
+//! let data = value.to_bytes("").unwrap();
+//! let mut writer = YamlWriter::new(String::new());
+//! let mut fsm = NbtFsm::new().with_data(&data);
+//! while let Ok(FsmResult::Found(fragment)) = fsm.next_fragment() {
+//!     writer.push(fragment).unwrap();
+//! }
+//! assert_eq!(writer.into_inner(), "id: \"minecraft:stone\"\n");
+//! ```
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+
+use crate::{
+    NbtFragment, NbtTag,
+    json::write_string,
+    mutf8::StringPolicy,
+    text::{Emit, Number, Seq, Structure},
+};
+
+/// Writes the fragments of documents pushed to it as YAML
+///
+/// Root names are skipped, and documents after the first are separated by `---`. Invalid
+/// strings are written lossily unless another [StringPolicy] is set.
+#[derive(Debug, Clone)]
+pub struct YamlWriter<W> {
+    structure: Structure,
+    format: Format<W>,
+}
+
+#[derive(Debug, Clone)]
+struct Format<W> {
+    out: W,
+    levels: Vec<Level>,
+    /// Nothing of the current document has been written yet
+    fresh: bool,
+    /// A document was completed, the next one is preceded by a separator
+    ended: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Level {
+    /// A compound, whose keys start at `indent`, right after the `-` of a list element if inline
+    Map {
+        indent: usize,
+        first: bool,
+        inline: bool,
+    },
+    /// A list of compounds or lists, with the `-` of its elements at `indent`
+    Block {
+        indent: usize,
+        seq: Seq,
+        empty: bool,
+    },
+    /// Any other list or array, on a single line
+    Flow { seq: Seq, first: bool },
+}
+
+impl<W: Write> YamlWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            structure: Structure::default(),
+            format: Format {
+                out,
+                levels: Vec::new(),
+                fresh: true,
+                ended: false,
+            },
+        }
+    }
+
+    /// How strings that are not valid (Modified) UTF-8 are written, lossily by default
+    ///
+    /// Strict writers fail to push invalid strings, raw writers write them as byte arrays.
+    pub fn string_policy(mut self, policy: StringPolicy) -> Self {
+        self.structure.strings = policy;
+        self
+    }
+
+    /// Writes the next fragment, returning true once the root tag is complete
+    ///
+    /// Every document ends with a line break.
+    pub fn push(&mut self, fragment: NbtFragment<'_>) -> Result<bool, fmt::Error> {
+        if core::mem::take(&mut self.format.ended) {
+            self.format.out.write_str("---\n")?;
+        }
+        let done = self.structure.push(fragment, &mut self.format)?;
+        if done {
+            self.format.out.write_char('\n')?;
+            self.format.fresh = true;
+            self.format.ended = true;
+        }
+        Ok(done)
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.format.out
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.format.out
+    }
+
+    pub fn into_inner(self) -> W {
+        self.format.out
+    }
+}
+
+impl<W: Write> Format<W> {
+    fn write_str(&mut self, str: &str) -> fmt::Result {
+        self.fresh = false;
+        self.out.write_str(str)
+    }
+
+    /// Separates a value from the key or `-` before it, flow elements are separated by
+    /// [Format::element]
+    fn space(&mut self) -> fmt::Result {
+        match self.fresh || matches!(self.levels.last(), Some(Level::Flow { .. })) {
+            true => Ok(()),
+            false => self.out.write_char(' '),
+        }
+    }
+
+    fn line(&mut self, indent: usize) -> fmt::Result {
+        if !self.fresh {
+            self.out.write_char('\n')?;
+        }
+        self.fresh = false;
+        for _ in 0..indent {
+            self.out.write_char(' ')?;
+        }
+        Ok(())
+    }
+
+    /// Starts the next element of the enclosing list, returning the indentation of the entries of
+    /// a compound starting there
+    fn element(&mut self) -> Result<Option<usize>, fmt::Error> {
+        match self.levels.last_mut() {
+            Some(Level::Block { indent, seq, empty }) => {
+                let (indent, seq) = (*indent, *seq);
+                if core::mem::take(empty) {
+                    self.comment(seq)?;
+                }
+                self.line(indent)?;
+                self.write_str("-")?;
+                Ok(Some(indent + 2))
+            }
+            Some(Level::Flow { first, .. }) => {
+                if !core::mem::take(first) {
+                    self.write_str(", ")?;
+                }
+                Ok(None)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// The indentation of the entries of a compound that is the value of the next key
+    fn nested_indent(&self) -> usize {
+        match self.levels.last() {
+            Some(Level::Map { indent, .. }) => indent + 2,
+            _ => 0,
+        }
+    }
+
+    fn comment(&mut self, seq: Seq) -> fmt::Result {
+        match seq {
+            Seq::List(tag) => write!(self.out, "  # list of {}", tag_name(tag)),
+            Seq::ByteArray => self.out.write_str("  # byte array"),
+            Seq::IntArray => self.out.write_str("  # int array"),
+            Seq::LongArray => self.out.write_str("  # long array"),
+        }
+    }
+}
+
+impl<W: Write> Emit for Format<W> {
+    fn begin_compound(&mut self) -> fmt::Result {
+        let level = match self.element()? {
+            Some(indent) => Level::Map {
+                indent,
+                first: true,
+                inline: true,
+            },
+            None => Level::Map {
+                indent: self.nested_indent(),
+                first: true,
+                inline: false,
+            },
+        };
+        self.levels.push(level);
+        Ok(())
+    }
+
+    fn key(&mut self, key: &str) -> fmt::Result {
+        let Some(Level::Map {
+            indent,
+            first,
+            inline,
+        }) = self.levels.last_mut()
+        else {
+            return Err(fmt::Error);
+        };
+        let (indent, inline) = (*indent, core::mem::take(first) && *inline);
+        match inline {
+            true => self.space()?,
+            false => self.line(indent)?,
+        }
+        write_key(&mut self.out, key)?;
+        self.write_str(":")
+    }
+
+    fn end_compound(&mut self) -> fmt::Result {
+        if let Some(Level::Map { first: true, .. }) = self.levels.pop() {
+            self.space()?;
+            self.write_str("{}")?;
+        }
+        Ok(())
+    }
+
+    fn begin_seq(&mut self, seq: Seq) -> fmt::Result {
+        let indent = match self.element()? {
+            Some(indent) => indent,
+            None => self.nested_indent(),
+        };
+        if let Seq::List(NbtTag::Compound | NbtTag::List) = seq {
+            self.levels.push(Level::Block {
+                indent,
+                seq,
+                empty: true,
+            });
+            return Ok(());
+        }
+        self.space()?;
+        self.write_str("[")?;
+        self.levels.push(Level::Flow { seq, first: true });
+        Ok(())
+    }
+
+    fn end_seq(&mut self) -> fmt::Result {
+        match self.levels.pop() {
+            Some(Level::Block {
+                seq, empty: true, ..
+            }) => {
+                self.space()?;
+                self.write_str("[]")?;
+                self.comment(seq)
+            }
+            Some(Level::Flow { seq, .. }) => {
+                self.write_str("]")?;
+                self.comment(seq)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn number(&mut self, number: Number) -> fmt::Result {
+        self.element()?;
+        self.space()?;
+        self.fresh = false;
+        match number {
+            Number::Byte(value) => write!(self.out, "{value}")?,
+            Number::Short(value) => write!(self.out, "{value}")?,
+            Number::Int(value) => write!(self.out, "{value}")?,
+            Number::Long(value) => write!(self.out, "{value}")?,
+            Number::Float(value) => write_float(&mut self.out, value.into(), value.is_nan())?,
+            Number::Double(value) => write_float(&mut self.out, value, value.is_nan())?,
+        }
+        if let Some(Level::Flow { .. }) = self.levels.last() {
+            return Ok(());
+        }
+        write!(self.out, "  # {}", tag_name(number.tag()))
+    }
+
+    fn string(&mut self, string: &str) -> fmt::Result {
+        self.element()?;
+        self.space()?;
+        self.fresh = false;
+        write_string(&mut self.out, string)
+    }
+}
+
+/// Writes a float, with YAML's names for infinities and NaN
+fn write_float(out: &mut impl Write, value: f64, nan: bool) -> fmt::Result {
+    match value {
+        _ if nan => out.write_str(".nan"),
+        f64::INFINITY => out.write_str(".inf"),
+        f64::NEG_INFINITY => out.write_str("-.inf"),
+        value => write!(out, "{value:?}"),
+    }
+}
+
+/// Writes a key, quoting it unless it is a plain word that YAML does not read as anything else
+fn write_key(out: &mut impl Write, key: &str) -> fmt::Result {
+    let plain = key.starts_with(|char: char| char.is_ascii_alphabetic() || char == '_')
+        && key
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || char == '_')
+        && !matches!(
+            key.to_ascii_lowercase().as_str(),
+            "true" | "false" | "null" | "yes" | "no" | "on" | "off" | "y" | "n"
+        );
+    match plain {
+        true => out.write_str(key),
+        false => write_string(out, key),
+    }
+}
+
+fn tag_name(tag: NbtTag) -> &'static str {
+    match tag {
+        NbtTag::End => "end",
+        NbtTag::Byte => "byte",
+        NbtTag::Short => "short",
+        NbtTag::Int => "int",
+        NbtTag::Long => "long",
+        NbtTag::Float => "float",
+        NbtTag::Double => "double",
+        NbtTag::ByteArray => "byte array",
+        NbtTag::String => "string",
+        NbtTag::List => "list",
+        NbtTag::Compound => "compound",
+        NbtTag::IntArray => "int array",
+        NbtTag::LongArray => "long array",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FsmResult, NbtFsm};
+    use alloc::string::String;
+
+    #[test]
+    fn bigtest() {
+        let data = include_bytes!("../assets/bigtest.nbt");
+        let mut writer = YamlWriter::new(String::new());
+        let mut fsm = NbtFsm::new().with_data(data);
+        while let FsmResult::Found(fragment) = fsm.next_fragment().unwrap() {
+            writer.push(fragment).unwrap();
+        }
+        let output = writer.into_inner();
+        assert!(output.starts_with("longTest: 9223372036854775807  # long\n"));
+        assert!(output.contains("\nintTest: 2147483647  # int\n"));
+        assert!(output.contains("\n\"listTest (long)\": [11, 12, 13, 14, 15]  # list of long\n"));
+        assert!(output.contains(concat!(
+            "\n\"listTest (compound)\":  # list of compound\n",
+            "  - name: \"Compound tag #0\"\n",
+            "    \"created-on\": 1264099775885  # long\n",
+            "  - name: \"Compound tag #1\"\n",
+        )));
+        assert!(output.contains("\n  egg:\n    name: \"Eggbert\"\n    value: 0.5  # float\n"));
+        assert!(output.ends_with("]  # byte array\ndoubleTest: 0.4931287132182315  # double\n"));
+    }
+
+    #[test]
+    fn empty_containers() {
+        // An empty compound and an empty list of lists, twice
+        let data = b"\x0a\0\0\x0a\0\x01c\0\x09\0\x01l\x09\0\0\0\0\0";
+        let mut writer = YamlWriter::new(String::new());
+        for data in [data, data] {
+            let mut fsm = NbtFsm::new().with_data(data);
+            while let FsmResult::Found(fragment) = fsm.next_fragment().unwrap() {
+                writer.push(fragment).unwrap();
+            }
+        }
+        let document = "c: {}\nl: []  # list of list\n";
+        assert_eq!(writer.into_inner(), [document, document].join("---\n"));
+    }
+}
@@ -0,0 +1,210 @@
+//! Derive macros for `zeronbt::convert::{FromNbt, ToNbt}`
+//!
+//! Structs with named fields are mapped to compounds, with one entry per field. Fields accept the
+//! following attributes:
+//! - `#[nbt(rename = "xPos")]` stores the field under a different key
+//! - `#[nbt(default)]` or `#[nbt(default = "path::to::fn")]` fills in a missing key instead of
+//!   failing
+//! - `#[nbt(array)]` stores a `Vec<i8>`, `Vec<i32>` or `Vec<i64>` as a byte/int/long array instead
+//!   of a list
+//!
+//! Fields of type `Option<T>` are left out when `None` and read as `None` when missing.
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    Data, DeriveInput, Error, Expr, Fields, GenericArgument, LitStr, Path, PathArguments, Result,
+    Type, parse_macro_input,
+};
+
+#[proc_macro_derive(ToNbt, attributes(nbt))]
+pub fn derive_to_nbt(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_to_nbt(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(FromNbt, attributes(nbt))]
+pub fn derive_from_nbt(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_from_nbt(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+enum FieldDefault {
+    Required,
+    Trait,
+    Function(Path),
+}
+
+struct Field {
+    ident: syn::Ident,
+    key: String,
+    default: FieldDefault,
+    array: bool,
+    /// The inner type if the field is an `Option<T>`
+    option: Option<Type>,
+}
+
+fn parse_fields(input: &DeriveInput) -> Result<Vec<Field>> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "NBT derives only support structs",
+        ));
+    };
+    let Fields::Named(named) = &data.fields else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "NBT derives only support structs with named fields",
+        ));
+    };
+    named
+        .named
+        .iter()
+        .map(|field| {
+            let ident = field.ident.clone().expect("named fields have identifiers");
+            let mut parsed = Field {
+                key: ident.to_string(),
+                ident,
+                default: FieldDefault::Required,
+                array: false,
+                option: option_inner(&field.ty),
+            };
+            for attr in field
+                .attrs
+                .iter()
+                .filter(|attr| attr.path().is_ident("nbt"))
+            {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("rename") {
+                        parsed.key = meta.value()?.parse::<LitStr>()?.value();
+                    } else if meta.path.is_ident("default") {
+                        parsed.default = match meta.value() {
+                            Ok(value) => FieldDefault::Function(value.parse::<LitStr>()?.parse()?),
+                            Err(_) => FieldDefault::Trait,
+                        };
+                    } else if meta.path.is_ident("array") {
+                        parsed.array = true;
+                    } else {
+                        return Err(meta.error("unknown nbt attribute"));
+                    }
+                    Ok(())
+                })?;
+            }
+            Ok(parsed)
+        })
+        .collect()
+}
+
+fn option_inner(ty: &Type) -> Option<Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        GenericArgument::Type(inner) => Some(inner.clone()),
+        _ => None,
+    }
+}
+
+fn expand_to_nbt(input: DeriveInput) -> Result<TokenStream2> {
+    let fields = parse_fields(&input)?;
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let inserts = fields.iter().map(|field| {
+        let Field { ident, key, .. } = field;
+        let convert = |value: TokenStream2| {
+            if field.array {
+                quote!(::zeronbt::convert::NbtArray::to_nbt_array(#value))
+            } else {
+                quote!(::zeronbt::convert::ToNbt::to_nbt(#value))
+            }
+        };
+        if field.option.is_some() {
+            let convert = convert(quote!(value));
+            quote! {
+                if let ::core::option::Option::Some(value) = &self.#ident {
+                    compound.insert(#key, #convert);
+                }
+            }
+        } else {
+            let convert = convert(quote!(&self.#ident));
+            quote!(compound.insert(#key, #convert);)
+        }
+    });
+    Ok(quote! {
+        impl #impl_generics ::zeronbt::convert::ToNbt for #name #ty_generics #where_clause {
+            fn to_nbt(&self) -> ::zeronbt::value::NbtValue {
+                let mut compound = ::zeronbt::value::NbtCompound::new();
+                #(#inserts)*
+                ::zeronbt::value::NbtValue::Compound(compound)
+            }
+        }
+    })
+}
+
+fn expand_from_nbt(input: DeriveInput) -> Result<TokenStream2> {
+    let fields = parse_fields(&input)?;
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let reads = fields.iter().map(|field| {
+        let Field { ident, key, .. } = field;
+        let convert = if field.array {
+            quote!(::zeronbt::convert::NbtArray::from_nbt_array(value))
+        } else {
+            quote!(::zeronbt::convert::FromNbt::from_nbt(value))
+        };
+        let convert = quote! {
+            #convert.map_err(|err| ::zeronbt::error::NbtConvertError::in_field(#key, err))?
+        };
+        let missing: Expr = match (&field.default, &field.option) {
+            (FieldDefault::Trait, _) => syn::parse_quote!(::core::default::Default::default()),
+            (FieldDefault::Function(path), _) => syn::parse_quote!(#path()),
+            (FieldDefault::Required, Some(_)) => syn::parse_quote!(::core::option::Option::None),
+            (FieldDefault::Required, None) => syn::parse_quote! {
+                return ::core::result::Result::Err(
+                    ::zeronbt::error::NbtConvertError::MissingKey(#key.into())
+                )
+            },
+        };
+        let found = if field.option.is_some() {
+            quote!(::core::option::Option::Some(#convert))
+        } else {
+            convert
+        };
+        quote! {
+            #ident: match compound.get(#key) {
+                ::core::option::Option::Some(value) => #found,
+                ::core::option::Option::None => #missing,
+            },
+        }
+    });
+    Ok(quote! {
+        impl #impl_generics ::zeronbt::convert::FromNbt for #name #ty_generics #where_clause {
+            fn from_nbt(
+                value: &::zeronbt::value::NbtValue,
+            ) -> ::core::result::Result<Self, ::zeronbt::error::NbtConvertError> {
+                let ::zeronbt::value::NbtValue::Compound(compound) = value else {
+                    return ::core::result::Result::Err(
+                        ::zeronbt::error::NbtConvertError::WrongTag {
+                            expected: ::zeronbt::NbtTag::Compound,
+                            found: value.tag(),
+                        },
+                    );
+                };
+                ::core::result::Result::Ok(Self {
+                    #(#reads)*
+                })
+            }
+        }
+    })
+}
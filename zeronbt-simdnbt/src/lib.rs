@@ -0,0 +1,230 @@
+//! Conversions between [NbtValue] and the value types of [simdnbt]
+//!
+//! simdnbt relies on `portable_simd`, so this crate only builds on a nightly toolchain. Neither
+//! value type belongs to this crate, so the conversions are functions rather than `From` impls.
+use simdnbt::{
+    Mutf8Str, Mutf8String, borrow,
+    owned::{self, NbtTag as Tag},
+};
+
+use zeronbt::{
+    NbtFragment, NbtTag,
+    error::NbtResult,
+    value::{NbtCompound, NbtList, NbtValue, NbtValueBuilder},
+};
+
+/// simdnbt keeps strings as MUTF-8 without validating them, invalid ones are converted lossily
+fn string(value: &Mutf8Str) -> String {
+    value.to_string_lossy().into_owned()
+}
+
+/// Converts a value to a simdnbt tag
+pub fn to_tag(value: NbtValue) -> Tag {
+    match value {
+        NbtValue::Byte(value) => Tag::Byte(value),
+        NbtValue::Short(value) => Tag::Short(value),
+        NbtValue::Int(value) => Tag::Int(value),
+        NbtValue::Long(value) => Tag::Long(value),
+        NbtValue::Float(value) => Tag::Float(value),
+        NbtValue::Double(value) => Tag::Double(value),
+        NbtValue::ByteArray(values) => Tag::ByteArray(bytes_from_i8(values)),
+        NbtValue::String(value) => Tag::String(value.into()),
+        NbtValue::List(list) => Tag::List(to_list(list)),
+        NbtValue::Compound(compound) => Tag::Compound(to_compound(compound)),
+        NbtValue::IntArray(values) => Tag::IntArray(values),
+        NbtValue::LongArray(values) => Tag::LongArray(values),
+    }
+}
+
+/// Converts a list to a simdnbt list
+pub fn to_list(list: NbtList) -> owned::NbtList {
+    let tag = list.tag();
+    let mut values = list.into_values().into_iter().map(to_tag);
+    macro_rules! collect {
+        ($variant:ident) => {
+            owned::NbtList::$variant(
+                values
+                    .by_ref()
+                    .map(|value| match value {
+                        Tag::$variant(value) => value,
+                        _ => unreachable!("the elements of an NbtList share a tag"),
+                    })
+                    .collect(),
+            )
+        };
+    }
+    match tag {
+        NbtTag::End => owned::NbtList::Empty,
+        NbtTag::Byte => collect!(Byte),
+        NbtTag::Short => collect!(Short),
+        NbtTag::Int => collect!(Int),
+        NbtTag::Long => collect!(Long),
+        NbtTag::Float => collect!(Float),
+        NbtTag::Double => collect!(Double),
+        NbtTag::ByteArray => collect!(ByteArray),
+        NbtTag::String => collect!(String),
+        NbtTag::List => collect!(List),
+        NbtTag::Compound => collect!(Compound),
+        NbtTag::IntArray => collect!(IntArray),
+        NbtTag::LongArray => collect!(LongArray),
+    }
+}
+
+/// Converts a compound to a simdnbt compound
+pub fn to_compound(compound: NbtCompound) -> owned::NbtCompound {
+    owned::NbtCompound::from_values(
+        compound
+            .into_iter()
+            .map(|(key, value)| (key.into(), to_tag(value)))
+            .collect(),
+    )
+}
+
+/// Converts a simdnbt tag to a value
+pub fn from_tag(value: Tag) -> NbtValue {
+    match value {
+        Tag::Byte(value) => NbtValue::Byte(value),
+        Tag::Short(value) => NbtValue::Short(value),
+        Tag::Int(value) => NbtValue::Int(value),
+        Tag::Long(value) => NbtValue::Long(value),
+        Tag::Float(value) => NbtValue::Float(value),
+        Tag::Double(value) => NbtValue::Double(value),
+        Tag::ByteArray(values) => NbtValue::ByteArray(bytes_to_i8(values)),
+        Tag::String(value) => NbtValue::String(string(&value)),
+        Tag::List(list) => NbtValue::List(from_list(list)),
+        Tag::Compound(compound) => NbtValue::Compound(from_compound(compound)),
+        Tag::IntArray(values) => NbtValue::IntArray(values),
+        Tag::LongArray(values) => NbtValue::LongArray(values),
+    }
+}
+
+/// Converts a simdnbt list to a list
+pub fn from_list(list: owned::NbtList) -> NbtList {
+    fn build<T>(tag: NbtTag, values: Vec<T>, map: impl Fn(T) -> NbtValue) -> NbtList {
+        let mut out = NbtList::with_tag(tag);
+        for value in values {
+            out.push(map(value))
+                .expect("the elements of a simdnbt list share a tag");
+        }
+        out
+    }
+    match list {
+        owned::NbtList::Empty => NbtList::new(),
+        owned::NbtList::Byte(values) => build(NbtTag::Byte, values, NbtValue::Byte),
+        owned::NbtList::Short(values) => build(NbtTag::Short, values, NbtValue::Short),
+        owned::NbtList::Int(values) => build(NbtTag::Int, values, NbtValue::Int),
+        owned::NbtList::Long(values) => build(NbtTag::Long, values, NbtValue::Long),
+        owned::NbtList::Float(values) => build(NbtTag::Float, values, NbtValue::Float),
+        owned::NbtList::Double(values) => build(NbtTag::Double, values, NbtValue::Double),
+        owned::NbtList::ByteArray(values) => build(NbtTag::ByteArray, values, |values| {
+            NbtValue::ByteArray(bytes_to_i8(values))
+        }),
+        owned::NbtList::String(values) => build(NbtTag::String, values, |value| {
+            NbtValue::String(string(&value))
+        }),
+        owned::NbtList::List(values) => {
+            build(NbtTag::List, values, |list| NbtValue::List(from_list(list)))
+        }
+        owned::NbtList::Compound(values) => build(NbtTag::Compound, values, |compound| {
+            NbtValue::Compound(from_compound(compound))
+        }),
+        owned::NbtList::IntArray(values) => build(NbtTag::IntArray, values, NbtValue::IntArray),
+        owned::NbtList::LongArray(values) => build(NbtTag::LongArray, values, NbtValue::LongArray),
+    }
+}
+
+/// Converts a simdnbt compound to a compound
+pub fn from_compound(compound: owned::NbtCompound) -> NbtCompound {
+    compound
+        .into_iter()
+        .map(|(key, value)| (string(&key), from_tag(value)))
+        .collect()
+}
+
+/// Converts a borrowed simdnbt tag to a value
+pub fn from_borrowed_tag(value: borrow::NbtTag<'_, '_>) -> NbtValue {
+    from_tag(value.to_owned())
+}
+
+/// Converts a borrowed simdnbt compound to a compound
+pub fn from_borrowed_compound(compound: borrow::NbtCompound<'_, '_>) -> NbtCompound {
+    from_compound(compound.to_owned())
+}
+
+/// Converts a root tag parsed by simdnbt, as returned by [simdnbt::borrow::read]
+pub fn from_base_nbt(nbt: &borrow::BaseNbt<'_>) -> (String, NbtValue) {
+    let owned = nbt.to_owned();
+    let name = string(owned.name());
+    (name, NbtValue::Compound(from_compound(owned.as_compound())))
+}
+
+/// Builds a simdnbt tag from the fragments of a single root tag, returning None if they end early
+pub fn from_fragments<'a>(
+    fragments: impl IntoIterator<Item = NbtFragment<'a>>,
+) -> NbtResult<Option<(String, Tag)>> {
+    let mut builder = NbtValueBuilder::new();
+    for fragment in fragments {
+        if let Some((name, value)) = builder.push(fragment)? {
+            return Ok(Some((name, to_tag(value))));
+        }
+    }
+    Ok(None)
+}
+
+/// Wraps a compound as a root tag that simdnbt can write
+pub fn to_base_nbt(name: &str, compound: NbtCompound) -> owned::BaseNbt {
+    owned::BaseNbt::new(Mutf8String::from(name.to_string()), to_compound(compound))
+}
+
+fn bytes_from_i8(values: Vec<i8>) -> Vec<u8> {
+    values.into_iter().map(|value| value as u8).collect()
+}
+
+fn bytes_to_i8(values: Vec<u8>) -> Vec<i8> {
+    values.into_iter().map(|value| value as i8).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use zeronbt::{FsmResult, NbtFsm};
+
+    #[test]
+    fn round_trip() {
+        let data = include_bytes!("../../assets/bigtest.nbt");
+        let (name, value) = NbtValue::read(data).unwrap();
+        let nbt = simdnbt::borrow::read(&mut Cursor::new(data))
+            .unwrap()
+            .unwrap();
+        assert_eq!(from_base_nbt(&nbt), (name.clone(), value.clone()));
+
+        let NbtValue::Compound(compound) = value.clone() else {
+            panic!("root is a compound");
+        };
+        let mut written = Vec::new();
+        to_base_nbt(&name, compound).write(&mut written);
+        assert_eq!(NbtValue::read(&written).unwrap(), (name, value));
+
+        let empty = NbtValue::List(NbtList::with_tag(NbtTag::Int));
+        assert_eq!(from_tag(to_tag(empty.clone())), empty);
+        let bytes = NbtValue::ByteArray(vec![-1, 0, 1]);
+        assert_eq!(from_tag(to_tag(bytes.clone())), bytes);
+    }
+
+    #[test]
+    fn fragments() {
+        let data = include_bytes!("../../assets/bigtest.nbt");
+        let mut fsm = NbtFsm::new().with_data(data);
+        let fragments = core::iter::from_fn(|| match fsm.next_fragment() {
+            Ok(FsmResult::Found(fragment)) => Some(fragment),
+            _ => None,
+        });
+        let (name, tag) = from_fragments(fragments).unwrap().unwrap();
+        assert_eq!(name, "Level");
+        let expected = simdnbt::owned::read(&mut Cursor::new(data))
+            .unwrap()
+            .unwrap();
+        assert_eq!(tag, Tag::Compound(expected.as_compound()));
+    }
+}